@@ -14,6 +14,8 @@ Notes:
 - Commands assume you run this from repo root on Windows with cargo/g++ in PATH.
 """
 
+import hashlib
+import json
 import os
 import sys
 import subprocess
@@ -1929,6 +1931,10 @@ class Editor:
         
         compiled_count = 0
         failed_count = 0
+        unchanged_count = 0
+        # Content hashes from the last build: a shader is recompiled only when its source (or the
+        # .spv it produced) actually changed, not when a copy or touch bumped its timestamp
+        shader_hashes = self._load_shader_hashes(project_dir)
         
         for shader_entry in shaders_to_compile:
             # Unpack tuple (source_path, output_path) - output_path may be None
@@ -1972,23 +1978,62 @@ class Editor:
                 # Different drive on Windows
                 rel_path = glsl_path
             
+            # The stage flag is part of the source key: the same file compiled differently is a new artifact
+            source_key = f"{self._file_hash(glsl_path)}:{stage_flag or ''}"
+            recorded = shader_hashes.get(spv_path, {})
+            spv_hash = self._file_hash(spv_path)
+            if spv_hash is not None and recorded.get("source") == source_key and recorded.get("spv") == spv_hash:
+                unchanged_count += 1
+                continue
+            
             result = self._run_step(f"Shader({os.path.basename(glsl_path)})", compile_cmd)
             if result:
                 compiled_count += 1
+                shader_hashes[spv_path] = {"source": source_key, "spv": self._file_hash(spv_path)}
                 self.log_lines.append(f"[OK] Compiled: {rel_path} -> {os.path.basename(spv_path)}")
             else:
                 failed_count += 1
+                shader_hashes.pop(spv_path, None)
                 self.log_lines.append(f"[FAIL] Failed: {rel_path}")
         
+        self._save_shader_hashes(project_dir, shader_hashes)
         shader_compile_time = time.time() - shader_compile_start
         
         if compiled_count > 0:
-            self.log_lines.append(f"Shader compilation: {compiled_count} succeeded, {failed_count} failed")
+            self.log_lines.append(f"Shader compilation: {compiled_count} succeeded, {failed_count} failed, {unchanged_count} unchanged")
         elif failed_count > 0:
             self.log_lines.append(f"Shader compilation failed: {failed_count} shader(s) failed")
+        elif unchanged_count > 0:
+            self.log_lines.append(f"Shaders up to date ({unchanged_count} unchanged)")
         
         return failed_count == 0, shader_compile_time
     
+    @staticmethod
+    def _file_hash(path):
+        """SHA-256 of a file's contents, or None if it can't be read."""
+        try:
+            with open(path, "rb") as f:
+                return hashlib.sha256(f.read()).hexdigest()
+        except OSError:
+            return None
+    
+    @staticmethod
+    def _load_shader_hashes(project_dir):
+        """Per-artifact hashes from the last shader build: {spv_path: {"source": ..., "spv": ...}}"""
+        try:
+            with open(os.path.join(project_dir, ".heidic_shader_hashes.json"), "r") as f:
+                return json.load(f)
+        except (OSError, ValueError):
+            return {}
+    
+    @staticmethod
+    def _save_shader_hashes(project_dir, shader_hashes):
+        try:
+            with open(os.path.join(project_dir, ".heidic_shader_hashes.json"), "w") as f:
+                json.dump(shader_hashes, f, indent=2, sort_keys=True)
+        except OSError:
+            pass  # Only costs a recompile next build
+    
     def _compile_neuroshell_shaders(self, project_root):
         """Compile NEUROSHELL UI shaders if they exist.
         Returns compilation time in seconds (0 if not compiled or failed)."""
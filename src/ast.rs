@@ -1,6 +1,8 @@
 use crate::error::SourceLocation;
+use crate::input_map;
+use serde::Serialize;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Type {
     I32,
     I64,
@@ -14,6 +16,10 @@ pub enum Type {
     #[allow(dead_code)] // Component system not yet fully implemented
     Component(String),
     Query(Vec<Type>), // query<Component1, Component2, ...>
+    QueryChunk(Vec<Type>),  // The iterator of `for chunk in q.chunks(n)`: contiguous slices of the query's components
+    QueryFilter(QueryFilter, Box<Type>),  // with<T> / without<T> / changed<T> among a query's components
+    Res(Box<Type>),  // res<Time>: a singleton resource parameter, supplied by codegen instead of the caller
+    Function(Vec<Type>, Box<Type>), // fn(i32, f32): bool - callback / function pointer
     Void,
     // Vulkan types
     VkInstance,
@@ -45,12 +51,12 @@ pub enum Type {
     Error,  // Represents a type error - propagates through operations
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Program {
     pub items: Vec<Item>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Item {
     Struct(StructDef),
     Component(ComponentDef),
@@ -60,38 +66,108 @@ pub enum Item {
     ExternFunction(ExternFunctionDef),
     Resource(ResourceDef),
     Pipeline(PipelineDef),
+    Gpu(GpuConfig),  // gpu { prefer: discrete, required_features: [...] }
+    Interface(InterfaceDef),  // interface Damageable { fn apply_damage(self, amount: f32); }
+    Impl(ImplDef),  // impl Damageable for Health { ... }
+    GpuQuery(GpuQueryDef),  // gpu_query Occlusion; gpu_query PipelineStatistics(vertex_shader_invocations) VsCount[16];
+    Test(TestDef),  // test "title screen renders" { ... } (built by `heidic_v2 test`)
+    Const(ConstDef),  // const MAX_LIGHTS: i32 = 16;
+    StaticAssert(StaticAssertDef),  // static_assert(MAX_LIGHTS <= 16, "the light UBO holds 16");
+    Event(EventDef),  // event Collision { a: i64, b: i64 }
+    SaveSchema(SaveSchemaDef),  // save_schema SaveGame v2 { level: i32, from v1(old, new) { ... } }
+    Scene(SceneDef),  // scene "level1.hscene"; - entities spawned at startup
+    Migrate(MigrateDef),  // migrate Health(old: HealthV1): Health { ... }
+    Uniform(UniformDef),  // uniform CameraUBO { view: Mat4, proj: Mat4 }
+    Input(InputDef),  // input { jump: Key.Space | Gamepad.A; move_x: axis(Key.A, Key.D); }
+    Window(WindowConfig),  // window { title: "Game", width: 1280, height: 720, vsync: true, resizable: true }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct StructDef {
     pub name: String,
     pub fields: Vec<Field>,
+    pub is_export: bool,  // true if marked with @[export] (written by --emit=layouts)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ComponentDef {
     pub name: String,
     pub fields: Vec<Field>,
     pub is_soa: bool,  // true if component_soa, false if regular component
     pub is_hot: bool,  // true if marked with @hot
     pub is_cuda: bool,  // true if marked with @[cuda]
+    pub is_export: bool,  // true if marked with @[export] (written by --emit=layouts)
+    pub is_replicated: bool,  // true if marked with @[replicated] (sent to clients by net_update)
+    pub pool_capacity: Option<u32>,  // @[pool(capacity = N)]: storage for N allocated up front, never grown
+    pub doc: Option<String>,  // `///` comment lines above the component
 }
 
-#[derive(Debug, Clone)]
+impl ComponentDef {
+    /// A component_soa with @cold fields: those share one side array (`heidic_cold`) of a Cold struct
+    pub fn has_cold_fields(&self) -> bool {
+        self.is_soa && self.fields.iter().any(|f| f.cold)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct SystemDef {
     pub name: String,
     pub functions: Vec<FunctionDef>,
+    pub handlers: Vec<EventHandler>,  // on Collision(ev) { ... }
     pub is_hot: bool,  // true if marked with @hot
+    pub schedule: Option<SystemSchedule>,  // Some if run by run_systems() (@[stage], @[before], @[after])
+    pub strict_fp: bool,  // @strict_fp system: every function and handler of it is strict_fp
+    pub location: SourceLocation,
+}
+
+// @[stage(update)] @[before(Physics)] @[after(Input)]
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemSchedule {
+    pub stage: Option<String>,  // Runs in the update stage when omitted
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceDef {
+    pub name: String,
+    pub methods: Vec<InterfaceMethod>,
+    pub location: SourceLocation,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceMethod {
+    pub name: String,
+    pub params: Vec<Param>,  // First param is always `self` (typed as the interface)
+    pub return_type: Type,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImplDef {
+    pub interface: String,
+    pub target: String,  // Implementing component or struct
+    pub methods: Vec<(FunctionDef, SourceLocation)>,  // First param is `self` (typed as the target)
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ShaderDef {
     pub stage: ShaderStage,
     pub path: String,  // Path to shader source file
     pub is_hot: bool,  // true if marked with @hot
+    pub inline: Option<InlineShader>,  // `shader vertex Name { glsl { ... } }`: source written to `path`
+}
+
+/// GLSL embedded in the .hd file
+#[derive(Debug, Clone, Serialize)]
+pub struct InlineShader {
+    pub name: String,
+    pub glsl: String,
+    pub location: SourceLocation,  // `glsl {`, which is line 1 of the GLSL
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum ShaderStage {
     Vertex,
     Fragment,
@@ -101,28 +177,46 @@ pub enum ShaderStage {
     TessellationEvaluation,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Field {
     pub name: String,
     pub ty: Type,
+    pub cold: bool,  // @cold: a component_soa keeps it in a side array, out of the hot field arrays
+    pub doc: Option<String>,  // `///` comment lines above a component field
+    pub range: Option<(f64, f64)>,  // @[range(min, max)]: the values an inspector lets you pick
+    pub unit: Option<String>,  // @[unit("m/s")]: shown next to the value by inspectors
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Param {
     pub name: String,
     pub ty: Type,
+    pub default: Option<Expression>,  // Default value: fn shade(intensity: f32 = 1.0)
+    pub mutable: bool,  // fn step(mut velocity: Vec3): parameters are immutable otherwise
+    pub location: SourceLocation,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FunctionDef {
     pub name: String,
     pub params: Vec<Param>,
     pub return_type: Type,
     pub body: Vec<Statement>,
-    pub cuda_kernel: Option<String>,  // Some(kernel_name) if marked with @[launch(kernel = name)]
+    pub cuda_kernel: Option<CudaLaunch>,  // Some(..) if marked with @[launch(kernel = name)]
+    pub no_reload_check: bool,  // @[no_reload_check]: its loops get no hot-reload checks
+    pub is_hot: bool,  // @hot fn: compiled into the hot functions DLL and reloaded while the game runs
+    pub strict_fp: bool,  // @strict_fp: IEEE float math (no fast-math, no FMA contraction) for lockstep/replay
+}
+
+/// @[launch(kernel = name, async = true)]: the function runs on the GPU as `<name>_kernel`,
+/// started from the host by the generated `<fn>_launch`
+#[derive(Debug, Clone, Serialize)]
+pub struct CudaLaunch {
+    pub kernel: String,
+    pub is_async: bool,  // async = true: the launch returns once queued; <fn>_wait() finishes it
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ExternFunctionDef {
     pub name: String,
     pub params: Vec<Param>,
@@ -130,64 +224,454 @@ pub struct ExternFunctionDef {
     pub library: Option<String>, // Library name to link against
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ResourceDef {
     pub name: String,
     pub resource_type: String, // "Texture", "Mesh", etc.
     pub path: String,          // File path (string literal)
     pub is_hot: bool,          // true if marked with @hot
+    pub texture_options: Option<TextureOptions>,  // `{ filter: ..., wrap: ..., mips: ... }` after a texture's path
+    pub is_spatial: bool,      // true if marked with @[spatial] (a Sound played at an entity's Position)
 }
 
-#[derive(Debug, Clone)]
+/// How a texture resource is sampled, and whether a PNG gets a mip chain
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TextureOptions {
+    pub filter: TextureFilter,
+    pub wrap: TextureWrap,
+    pub mips: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        TextureOptions { filter: TextureFilter::Linear, wrap: TextureWrap::Repeat, mips: false }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum TextureFilter {
+    Linear,
+    Nearest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum TextureWrap {
+    Repeat,
+    Clamp,   // Clamp to the edge texel
+    Mirror,  // Mirrored repeat
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct PipelineDef {
     pub name: String,
     pub shaders: Vec<PipelineShader>,  // Shader stage and path
     pub layout: Option<PipelineLayout>, // Optional descriptor set layout
+    pub vertex_input: Option<VertexInput>,  // vertex_input VertexData: vertex buffer layout from a struct
+    pub state: PipelineState,  // blend: / cull: / topology: / depth_test: / polygon: keys
+}
+
+impl PipelineDef {
+    pub fn push_constants(&self) -> Option<&PushConstants> {
+        self.layout.as_ref()?.push_constants.as_ref()
+    }
+
+    /// The struct HEIDIC code fills and passes to push_constants_<pipeline>()
+    pub fn push_constants_struct(&self) -> String {
+        format!("{}PushConstants", self.name)
+    }
+
+    pub fn push_constants_function(&self) -> String {
+        format!("push_constants_{}", self.name.to_lowercase())
+    }
+
+    pub fn bindings(&self) -> &[LayoutBinding] {
+        self.layout.as_ref().map(|layout| layout.bindings.as_slice()).unwrap_or_default()
+    }
+
+    /// Allocates the pipeline's descriptor set (pipelines with bindings)
+    pub fn descriptor_sets_function(&self) -> String {
+        format!("create_descriptor_sets_{}", self.name.to_lowercase())
+    }
+
+    /// Points a uniform or storage binding of the descriptor set at a VkBuffer
+    pub fn update_binding_function(&self) -> String {
+        format!("update_binding_{}", self.name.to_lowercase())
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Fixed-function state of a pipeline; each key defaults to what pipelines always used
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PipelineState {
+    pub blend: BlendMode,
+    pub cull: CullMode,
+    pub topology: Topology,
+    pub depth_test: bool,  // depth_test: on|off
+    pub polygon: PolygonMode,
+}
+
+impl Default for PipelineState {
+    fn default() -> Self {
+        PipelineState {
+            blend: BlendMode::Opaque,
+            cull: CullMode::Back,
+            topology: Topology::Triangles,
+            depth_test: true,
+            polygon: PolygonMode::Fill,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    Alpha,     // src * a + dst * (1 - a)
+    Additive,  // src * a + dst
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum CullMode {
+    #[default]
+    Back,
+    Front,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum Topology {
+    #[default]
+    Triangles,
+    TriangleStrip,
+    Lines,
+    LineStrip,
+    Points,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    Wireframe,
+    Points,
+}
+
+/// `vertex_input Name` in a pipeline: one vertex binding with an attribute per field of struct `Name`
+#[derive(Debug, Clone, Serialize)]
+pub struct VertexInput {
+    pub struct_name: String,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuConfig {
+    pub prefer: GpuPreference,
+    pub device_index: Option<u32>,  // device: N - force a specific physical device
+    pub transfer_queue: TransferQueueMode,  // transfer_queue: dedicated|shared - where staging uploads run
+    pub required_features: Vec<(String, SourceLocation)>,  // Device is rejected without these
+    pub optional_features: Vec<(String, SourceLocation)>,  // Enabled only when supported
+    pub location: SourceLocation,
+}
+
+/// `window { ... }`: the window open_window() creates and how its swapchain presents
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub vsync: bool,  // Present in step with the display (FIFO) instead of as fast as possible
+    pub resizable: bool,  // The swapchain is rebuilt when the window is resized
+    pub location: SourceLocation,
+}
+
+impl WindowConfig {
+    /// The functions a window block adds, with the C++ functions behind them: open_window()
+    /// creates the window and its renderer (null on failure), close_window(window) tears both down
+    pub const FUNCTIONS: [(&'static str, &'static str); 2] = [
+        ("open_window", "heidic_open_window"),
+        ("close_window", "heidic_close_window"),
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum GpuPreference {
+    Any,
+    Discrete,
+    Integrated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum TransferQueueMode {
+    Dedicated,  // Use a transfer-only queue family when the device has one (default)
+    Shared,     // Always upload on the graphics queue
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuQueryDef {
+    pub name: String,  // Pool name used by begin_query/end_query/query_result (defaults to the kind)
+    pub kind: GpuQueryKind,
+    pub count: u32,  // Number of query slots
+    pub location: SourceLocation,
+}
+
+// A message type: `emit Collision { ... };` queues one, and systems handle them with `on Collision(ev)`
+#[derive(Debug, Clone, Serialize)]
+pub struct EventDef {
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub location: SourceLocation,
+}
+
+// A uniform buffer's contents: a C++ struct at the shaders' std140 offsets, one host-visible
+// buffer, and update_<name>(value) to copy a value into it
+#[derive(Debug, Clone, Serialize)]
+pub struct UniformDef {
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub location: SourceLocation,
+}
+
+impl UniformDef {
+    /// update_camera_ubo(value) for CameraUBO
+    pub fn update_function(&self) -> String {
+        format!("update_{}", snake_case(&self.name))
+    }
+    
+    /// The HeidicUniformBuffer holding the uniform's VkBuffer
+    pub fn buffer_global(&self) -> String {
+        format!("g_uniform_{}", snake_case(&self.name))
+    }
+}
+
+// The program's input actions, each bound to alternative keys, mouse buttons and gamepad controls
+#[derive(Debug, Clone, Serialize)]
+pub struct InputDef {
+    pub actions: Vec<InputAction>,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InputAction {
+    pub name: String,
+    pub bindings: Vec<InputBinding>,  // Alternatives separated by `|`
+    pub location: SourceLocation,
+}
+
+impl InputAction {
+    /// Axis actions read as a number in [-1, 1]: those with an axis(...) binding, or bound only to
+    /// gamepad sticks and triggers. The others are buttons.
+    pub fn is_axis(&self) -> bool {
+        self.bindings.iter().any(|b| matches!(b, InputBinding::Axis(..)))
+            || self.bindings.iter().all(|b| matches!(b, InputBinding::Source(source) if source.is_gamepad_axis()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum InputBinding {
+    Source(InputSource),
+    Axis(InputSource, InputSource),  // axis(negative, positive): -1, 0 or 1 from two buttons
+}
+
+// Key.Space, Mouse.Left, Gamepad.A, Gamepad.LeftX
+#[derive(Debug, Clone, Serialize)]
+pub struct InputSource {
+    pub device: String,
+    pub name: String,
+    pub location: SourceLocation,
+}
+
+impl InputSource {
+    pub fn is_gamepad_axis(&self) -> bool {
+        self.device == "Gamepad" && input_map::GAMEPAD_AXES.contains(&self.name.as_str())
+    }
+}
+
+/// CameraUBO -> camera_ubo, LightData -> light_data
+pub fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let after_lower = chars[i - 1].is_lowercase() || chars[i - 1].is_ascii_digit();
+            let acronym_end = chars[i - 1].is_uppercase() && chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if after_lower || acronym_end {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+// `on Collision(ev) { ... }` in a system: runs once per event emitted since the system last ran
+#[derive(Debug, Clone, Serialize)]
+pub struct EventHandler {
+    pub event: String,
+    pub param: String,
+    pub body: Vec<Statement>,
+    pub location: SourceLocation,
+}
+
+// One version of a save file format. The newest version of a schema is the struct programs use;
+// files written by older versions are read and migrated forward one version at a time.
+#[derive(Debug, Clone, Serialize)]
+pub struct SaveSchemaDef {
+    pub name: String,  // "SaveData" when the declaration doesn't name the schema
+    pub version: u32,
+    pub fields: Vec<Field>,
+    pub migration: Option<SchemaMigration>,
+    pub location: SourceLocation,
+}
+
+// from v1(old, new) { new.score = old.score as i64; } - runs after fields that kept their name
+// and a compatible type have been copied
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaMigration {
+    pub from: u32,
+    pub old: String,
+    pub new: String,
+    pub body: Vec<Statement>,
+    pub location: SourceLocation,
+}
+
+// A scene file's entities, spawned when the program starts. The parser only records the path;
+// scene::load reads the file into `entities` before type checking.
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneDef {
+    pub path: String,  // Relative to the source file
+    pub entities: Vec<SceneEntity>,
+    pub location: SourceLocation,
+}
+
+// A component's migration from an older layout, which is declared as a struct (the parameter's
+// type). Used instead of the field-by-field copy when the @hot component migration finds the
+// previous layout was the old one, and by load_world for components saved with it.
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrateDef {
+    pub function: FunctionDef,  // Named after the component: one parameter, returning the component
+    pub location: SourceLocation,
+}
+
+impl MigrateDef {
+    /// The struct declaring the old layout
+    pub fn old_struct(&self) -> Option<&str> {
+        match self.function.params.first().map(|param| &param.ty) {
+            Some(Type::Struct(name)) | Some(Type::Component(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The generated C++ function
+    pub fn cpp_name(&self) -> String {
+        format!("heidic_migrate_{}_from_{}", self.function.name, self.old_struct().unwrap_or("old"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneEntity {
+    pub name: Option<String>,  // Only labels the entity in generated code and errors
+    pub components: serde_json::Map<String, serde_json::Value>,  // Component name -> its field values
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestDef {
+    pub name: String,
+    pub body: Vec<Statement>,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstDef {
+    pub name: String,
+    pub ty: Option<Type>,  // Inferred from the value when omitted
+    pub value: Expression,  // Evaluated at compile time
+    pub is_tweak: bool,  // @tweak: a variable starting at `value`, adjustable while the program runs (stdlib/tweak.h)
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StaticAssertDef {
+    pub condition: Expression,
+    pub message: Option<String>,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum GpuQueryKind {
+    Occlusion,  // Samples passing depth/stencil tests
+    Timestamp,  // Elapsed GPU time in nanoseconds
+    PipelineStatistics(String, SourceLocation),  // One counter, named as in PIPELINE_STATISTICS
+}
+
+/// Pipeline statistics selectable in `gpu_query PipelineStatistics(...)`, with their Vulkan flag.
+pub const PIPELINE_STATISTICS: &[(&str, &str)] = &[
+    ("input_assembly_vertices", "VK_QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_VERTICES_BIT"),
+    ("input_assembly_primitives", "VK_QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_PRIMITIVES_BIT"),
+    ("vertex_shader_invocations", "VK_QUERY_PIPELINE_STATISTIC_VERTEX_SHADER_INVOCATIONS_BIT"),
+    ("clipping_invocations", "VK_QUERY_PIPELINE_STATISTIC_CLIPPING_INVOCATIONS_BIT"),
+    ("clipping_primitives", "VK_QUERY_PIPELINE_STATISTIC_CLIPPING_PRIMITIVES_BIT"),
+    ("fragment_shader_invocations", "VK_QUERY_PIPELINE_STATISTIC_FRAGMENT_SHADER_INVOCATIONS_BIT"),
+    ("compute_shader_invocations", "VK_QUERY_PIPELINE_STATISTIC_COMPUTE_SHADER_INVOCATIONS_BIT"),
+];
+
+#[derive(Debug, Clone, Serialize)]
 pub struct PipelineShader {
     pub stage: ShaderStage,
     pub path: String,  // Path to shader file
+    pub inline: Option<String>,  // Name of an inline shader, whose .spv `path` is
+    pub location: SourceLocation,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PipelineLayout {
     pub bindings: Vec<LayoutBinding>,
+    pub push_constants: Option<PushConstants>,  // push_constants { mvp: Mat4, time: f32 }
 }
 
-#[derive(Debug, Clone)]
+/// A pipeline's push constant block: one range for all of its shader stages
+#[derive(Debug, Clone, Serialize)]
+pub struct PushConstants {
+    pub fields: Vec<Field>,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct LayoutBinding {
     pub binding: u32,  // Binding index
     pub binding_type: BindingType,
-    pub name: String,  // Resource name (for reference)
+    pub name: String,  // Resource name (for reference; named storage bindings get a GPU buffer)
+    pub location: SourceLocation,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum BindingType {
     Uniform(String),      // uniform TypeName
     Storage(String),      // storage TypeName[]
     Sampler2D,           // sampler2D
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Statement {
-    Let { name: String, ty: Option<Type>, value: Expression, location: SourceLocation },
+    Let { name: String, mutable: bool, ty: Option<Type>, value: Expression, location: SourceLocation },
+    Destructure { pattern: Pattern, mutable: bool, value: Expression, location: SourceLocation },  // let Position { x, y, .. } = p;
     Assign { target: Expression, value: Expression, location: SourceLocation },
     If { condition: Expression, then_block: Vec<Statement>, else_block: Option<Vec<Statement>>, location: SourceLocation },
     While { condition: Expression, body: Vec<Statement>, location: SourceLocation },
-    For { iterator: String, collection: Expression, body: Vec<Statement>, location: SourceLocation },
+    For { iterator: String, collection: Expression, chunk_size: Option<Expression>, body: Vec<Statement>, location: SourceLocation },  // chunk_size: `for c in q.chunks(n)`
     Loop { body: Vec<Statement>, location: SourceLocation },
+    // spawn_batch(count) { init(index) { ... } }: creates `count` entities; component literals
+    // written as statements in the init block are attached to each one
+    SpawnBatch { count: Expression, index: String, body: Vec<Statement>, location: SourceLocation },
     Return(Option<Expression>, SourceLocation),
-    Break(SourceLocation),
+    Break(Option<Expression>, SourceLocation),  // `break value;` leaves a loop used as a value
     Continue(SourceLocation),
     Defer(Box<Expression>, SourceLocation),  // defer expr; - executes at scope exit
+    Emit(Expression, SourceLocation),  // emit Collision { a: x, b: y }; - queues an event
     Expression(Expression, SourceLocation),
     #[allow(dead_code)] // Block statements not yet fully implemented
     Block(Vec<Statement>, SourceLocation),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Expression {
     Literal(Literal, SourceLocation),
     Variable(String, SourceLocation),
@@ -199,40 +683,174 @@ pub enum Expression {
     ArrayLiteral { elements: Vec<Expression>, location: SourceLocation },
     StringInterpolation { parts: Vec<StringInterpolationPart>, location: SourceLocation },
     Match { expr: Box<Expression>, arms: Vec<MatchArm>, location: SourceLocation },
+    Cast { expr: Box<Expression>, ty: Type, location: SourceLocation },  // x as f32
+    If { condition: Box<Expression>, then_branch: ValueBlock, else_branch: ValueBlock, location: SourceLocation },  // if c { a } else { b }
+    Block { body: ValueBlock, location: SourceLocation },  // { let a = compute(); a * 2.0 }
+    Loop { body: Vec<Statement>, location: SourceLocation },  // let found = loop { ... break id; };
+    // size_of<T>(), align_of<T>(), offset_of<T>(field): i32 constants from the layout model
+    LayoutOf { query: LayoutQuery, ty: Type, field: Option<String>, location: SourceLocation },
+    // frame.alloc_array<Vec3>(100): zeroed scratch memory that lasts until the end of the frame
+    FrameAlloc { arena: Box<Expression>, element: Type, count: Box<Expression>, location: SourceLocation },
+    // sync_to_gpu<Position>() / sync_to_cpu<Position>(): copy a @[cuda] component to its device mirror or back
+    GpuSync { direction: SyncDirection, component: Type, location: SourceLocation },
     #[allow(dead_code)] // Struct literals not yet fully implemented
     StructLiteral { name: String, fields: Vec<(String, Expression)>, location: SourceLocation },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum LayoutQuery {
+    Size,
+    Align,
+    Offset,
+}
+
+impl LayoutQuery {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "size_of" => Some(LayoutQuery::Size),
+            "align_of" => Some(LayoutQuery::Align),
+            "offset_of" => Some(LayoutQuery::Offset),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutQuery::Size => "size_of",
+            LayoutQuery::Align => "align_of",
+            LayoutQuery::Offset => "offset_of",
+        }
+    }
+}
+
+/// Which way sync_to_gpu<T>() / sync_to_cpu<T>() copy a @[cuda] component
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SyncDirection {
+    ToGpu,
+    ToCpu,
+}
+
+impl SyncDirection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sync_to_gpu" => Some(SyncDirection::ToGpu),
+            "sync_to_cpu" => Some(SyncDirection::ToCpu),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SyncDirection::ToGpu => "sync_to_gpu",
+            SyncDirection::ToCpu => "sync_to_cpu",
+        }
+    }
+}
+
+/// Filters in `query<...>`: they select entities without giving access to the component
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum QueryFilter {
+    With,     // has the component
+    Without,  // doesn't have the component
+    Changed,  // has the component, and it was added or assigned since the function last ran
+}
+
+impl QueryFilter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "with" => Some(QueryFilter::With),
+            "without" => Some(QueryFilter::Without),
+            "changed" => Some(QueryFilter::Changed),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QueryFilter::With => "with",
+            QueryFilter::Without => "without",
+            QueryFilter::Changed => "changed",
+        }
+    }
+}
+
+// `{ statements; value }`: a block whose last expression, written without ';', is its value
+#[derive(Debug, Clone, Serialize)]
+pub struct ValueBlock {
+    pub statements: Vec<Statement>,
+    pub value: Box<Expression>,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct MatchArm {
     pub pattern: Pattern,
+    pub guard: Option<Box<Expression>>,  // `pattern if condition =>`: the arm is only chosen when the condition holds
     pub body: Vec<Statement>,
+    pub value: Option<Box<Expression>>,  // Last expression without ';': the arm's value when the match is used as one
     pub location: SourceLocation,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Pattern {
     Literal(Literal, SourceLocation),
     Variable(String, SourceLocation),
     Wildcard(SourceLocation),  // _ pattern
     Ident(String, SourceLocation),  // For enum variants or constants (e.g., VK_SUCCESS)
+    Or(Vec<Pattern>, SourceLocation),  // 1 | 2 | 3: matches when any alternative does
+    Range { start: Literal, end: Literal, inclusive: bool, end_location: SourceLocation, location: SourceLocation },  // 0..10 or 0..=9
+    Struct { name: String, fields: Vec<FieldPattern>, rest: bool, location: SourceLocation },  // Hit { entity, point: p, .. }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldPattern {
+    pub field: String,
+    pub pattern: Pattern,  // Just `entity` binds the field to a variable of the same name
+    pub location: SourceLocation,
 }
 
-#[derive(Debug, Clone)]
+impl Pattern {
+    /// Whether the pattern matches every value of its type (`_`, a binding, or a struct pattern
+    /// whose fields all do)
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::Wildcard(_) | Pattern::Variable(..) => true,
+            Pattern::Or(alternatives, _) => alternatives.iter().any(Pattern::is_irrefutable),
+            Pattern::Struct { fields, .. } => fields.iter().all(|field| field.pattern.is_irrefutable()),
+            Pattern::Literal(..) | Pattern::Ident(..) | Pattern::Range { .. } => false,
+        }
+    }
+
+    /// The variables the pattern binds, each with the fields leading to its value
+    pub fn bindings(&self) -> Vec<(Vec<&str>, &str, SourceLocation)> {
+        match self {
+            Pattern::Variable(name, location) => vec![(Vec::new(), name.as_str(), *location)],
+            Pattern::Struct { fields, .. } => fields.iter()
+                .flat_map(|field| field.pattern.bindings().into_iter().map(move |(mut path, name, location)| {
+                    path.insert(0, field.field.as_str());
+                    (path, name, location)
+                }))
+                .collect(),
+            Pattern::Or(alternatives, _) => alternatives.iter().flat_map(Pattern::bindings).collect(),
+            Pattern::Literal(..) | Pattern::Wildcard(_) | Pattern::Ident(..) | Pattern::Range { .. } => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum StringInterpolationPart {
     Literal(String),
     Variable(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Literal {
-    Int(i64),
-    Float(f64),
+    Int(i64, Option<Type>),    // Type suffix, if written (10i64); unsuffixed integers are i32
+    Float(f64, Option<Type>),  // Type suffix, if written (0.1f64); unsuffixed floats are f32
     Bool(bool),
     String(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum BinaryOp {
     Add,
     Sub,
@@ -249,7 +867,7 @@ pub enum BinaryOp {
     Or,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum UnaryOp {
     Neg,
     Not,
@@ -260,15 +878,18 @@ impl Statement {
     pub fn location(&self) -> SourceLocation {
         match self {
             Statement::Let { location, .. } => *location,
+            Statement::Destructure { location, .. } => *location,
             Statement::Assign { location, .. } => *location,
             Statement::If { location, .. } => *location,
             Statement::While { location, .. } => *location,
             Statement::For { location, .. } => *location,
             Statement::Loop { location, .. } => *location,
+            Statement::SpawnBatch { location, .. } => *location,
             Statement::Return(_, location) => *location,
-            Statement::Break(location) => *location,
+            Statement::Break(_, location) => *location,
             Statement::Continue(location) => *location,
             Statement::Defer(_, location) => *location,
+            Statement::Emit(_, location) => *location,
             Statement::Expression(_, location) => *location,
             Statement::Block(_, location) => *location,
         }
@@ -288,8 +909,28 @@ impl Expression {
             Expression::ArrayLiteral { location, .. } => *location,
             Expression::StringInterpolation { location, .. } => *location,
             Expression::Match { location, .. } => *location,
+            Expression::Cast { location, .. } => *location,
+            Expression::If { location, .. } => *location,
+            Expression::Block { location, .. } => *location,
+            Expression::Loop { location, .. } => *location,
+            Expression::LayoutOf { location, .. } => *location,
+            Expression::FrameAlloc { location, .. } => *location,
+            Expression::GpuSync { location, .. } => *location,
             Expression::StructLiteral { location, .. } => *location,
         }
     }
 }
 
+
+/// Query array name for a component: Position -> positions, Velocity -> velocities
+pub fn component_array_name(component_name: &str) -> String {
+    let component_lower = component_name.to_lowercase();
+    if component_lower.ends_with('y') {
+        // Velocity -> velocities (y -> ies)
+        format!("{}ies", &component_lower[..component_lower.len()-1])
+    } else if component_lower.ends_with('s') || component_lower.ends_with('x') || component_lower.ends_with('z') || component_lower.ends_with('h') {
+        format!("{}es", component_lower)
+    } else {
+        format!("{}s", component_lower)
+    }
+}
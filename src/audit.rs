@@ -0,0 +1,380 @@
+// Safety audit (`--audit`): the places the generated C++ hands raw pointers around outside the
+// HEIDIC type system -- arrays marshaled to extern fns, resource accessors whose Resource<T>* is an
+// i32 handle in HEIDIC, the device pointers of CUDA launch wrappers and the host arrays Vulkan
+// compute launches copy into storage buffers -- each with the HEIDIC
+// location it comes from. Written to <file>.audit.json and printed, so the unsafe surface of a
+// program can be reviewed like any other diff.
+
+use serde_json::{json, Value};
+
+use crate::ast::*;
+use crate::codegen::{cpp_resource_type, GpuBackend};
+use crate::cuda;
+use crate::vulkan_compute;
+use crate::error::SourceLocation;
+
+/// What kind of raw pointer flow a finding is
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    ExternArray,        // Array argument of an extern fn, passed as a pointer to its first element
+    ResourcePointer,    // get_resource_<name>(): Resource<T>* seen by HEIDIC as an i32
+    CudaDevicePointer,  // cudaMalloc'd buffers and device pointers of a @[launch] wrapper
+    ComputeBuffer,      // Host arrays a --gpu-backend=vulkan launch copies into and out of storage buffers
+}
+
+impl Kind {
+    const ALL: [Kind; 4] = [Kind::ExternArray, Kind::ResourcePointer, Kind::CudaDevicePointer, Kind::ComputeBuffer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::ExternArray => "extern_array",
+            Kind::ResourcePointer => "resource_pointer",
+            Kind::CudaDevicePointer => "cuda_device_pointer",
+            Kind::ComputeBuffer => "compute_buffer",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Kind::ExternArray => "Arrays passed to extern functions as raw pointers",
+            Kind::ResourcePointer => "Resource pointers used as i32 handles",
+            Kind::CudaDevicePointer => "CUDA device pointers",
+            Kind::ComputeBuffer => "Vulkan compute buffer copies",
+        }
+    }
+}
+
+pub struct Finding {
+    pub kind: Kind,
+    pub location: SourceLocation,
+    pub item: String,       // "fn update", "resource Brick", ...
+    pub generated: String,  // The C++ that does it
+    pub detail: String,
+}
+
+/// Every raw pointer site of `program`, built for `backend`; `item_locations` parallels `program.items`
+pub fn audit(program: &Program, item_locations: &[SourceLocation], backend: GpuBackend) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let externs: Vec<&ExternFunctionDef> = program.items.iter()
+        .filter_map(|item| match item { Item::ExternFunction(ext) => Some(ext), _ => None })
+        .collect();
+    let resources: Vec<&ResourceDef> = program.items.iter()
+        .filter_map(|item| match item { Item::Resource(res) => Some(res), _ => None })
+        .collect();
+    let cuda_components: Vec<&ComponentDef> = program.items.iter()
+        .filter_map(|item| match item { Item::Component(c) if c.is_cuda => Some(c), _ => None })
+        .collect();
+    // Once a program syncs them, @[cuda] components live in device mirrors
+    let resident = bodies(program).iter().any(|(_, body)| {
+        let mut calls = Vec::new();
+        statement_calls(body, &mut calls);
+        calls.iter().any(|(name, ..)| [SyncDirection::ToGpu.name(), SyncDirection::ToCpu.name()].contains(name))
+    });
+
+    for (index, item) in program.items.iter().enumerate() {
+        let location = item_locations.get(index).copied().unwrap_or_else(SourceLocation::unknown);
+        match item {
+            Item::Resource(res) => findings.push(Finding {
+                kind: Kind::ResourcePointer,
+                location,
+                item: format!("resource {}", res.name),
+                generated: format!("extern \"C\" Resource<{}>* get_resource_{}()", cpp_resource_type(&res.resource_type), res.name.to_lowercase()),
+                detail: "returns a pointer to the resource global; HEIDIC code sees it as an i32".to_string(),
+            }),
+            Item::Component(c) if c.is_cuda && resident => mirror_findings(c, location, &mut findings),
+            Item::Function(f) if f.cuda_kernel.is_some() && backend == GpuBackend::Vulkan => {
+                compute_findings(f, location, &cuda_components, &mut findings);
+            }
+            Item::Function(f) if f.cuda_kernel.is_some() => {
+                cuda_findings(f, location, &cuda_components, resident, &mut findings);
+            }
+            _ => {}
+        }
+    }
+
+    // Call sites, in source order of the items
+    for (item, body) in bodies(program) {
+        let mut calls = Vec::new();
+        statement_calls(body, &mut calls);
+        for (name, args, location) in calls {
+            if let Some(ext) = externs.iter().find(|ext| ext.name == name) {
+                for (param, _) in ext.params.iter().zip(args).filter(|(param, _)| matches!(param.ty, Type::Array(_))) {
+                    findings.push(Finding {
+                        kind: Kind::ExternArray,
+                        location,
+                        item: item.clone(),
+                        generated: format!("{}(... heidic_data({}) ...)", name, param.name),
+                        detail: format!("array passed to extern fn {} as a pointer to its first element; its length isn't passed or checked", name),
+                    });
+                }
+            }
+            let accessor = name.strip_prefix("get_resource_");
+            if let Some(res) = accessor.and_then(|lower| resources.iter().find(|res| res.name.to_lowercase() == lower)) {
+                findings.push(Finding {
+                    kind: Kind::ResourcePointer,
+                    location,
+                    item: item.clone(),
+                    generated: format!("{}()", name),
+                    detail: format!("the Resource<{}>* of resource {} is used as an i32 handle", cpp_resource_type(&res.resource_type), res.name),
+                });
+            }
+        }
+    }
+    findings
+}
+
+// A GPU-resident component's mirror in the .cu, filled from and copied back into raw host arrays
+// as long as the residency's row count
+fn mirror_findings(component: &ComponentDef, location: SourceLocation, findings: &mut Vec<Finding>) {
+    for array in cuda::resident_arrays(component) {
+        let mirrored = match &array.field {
+            Some(field) => format!("{}.{}", array.component, field),
+            None => format!("every {}", array.component),
+        };
+        findings.push(Finding {
+            kind: Kind::CudaDevicePointer,
+            location,
+            item: format!("component {}", component.name),
+            generated: format!("{}.upload({}, heidic_count, 0)", array.buffer, array.host),
+            detail: format!("device mirror of {}, copied from the entities by {}<{c}>() and back by {}<{c}>()",
+                mirrored, SyncDirection::ToGpu.name(), SyncDirection::ToCpu.name(), c = component.name),
+        });
+    }
+}
+
+// The launch bridge in the .cu copies the arrays of the kernel's query to the device and back, as
+// many elements as the query's size() reports, and the kernel receives them as device pointers.
+// Over resident components the kernel indexes the mirrors through the query's entity ids instead.
+fn cuda_findings(f: &FunctionDef, location: SourceLocation, components: &[&ComponentDef], resident: bool, findings: &mut Vec<Finding>) {
+    let (Some(launch), Some((query, component_types))) = (&f.cuda_kernel, cuda::kernel_query(f)) else { return };
+    let item = format!("fn {}", f.name);
+    let queried = components.iter().filter(|component| component_types.iter()
+        .any(|ty| matches!(ty, Type::Struct(name) | Type::Component(name) if *name == component.name)));
+    for component in queried {
+        if resident {
+            findings.push(Finding {
+                kind: Kind::CudaDevicePointer,
+                location,
+                item: item.clone(),
+                generated: format!("HeidicCudaResident{{..., {}.data(), d_{}_entities.data()}}", cuda::resident_rows(component), query.name),
+                detail: format!("the kernel reaches {} at its mirror row of each entity id; the launch checks sync_to_gpu<{}>() gave every entity of '{}' a row",
+                    component.name, component.name, query.name),
+            });
+            continue;
+        }
+        for array in cuda::device_arrays(&query.name, component) {
+            let copied = match &array.field {
+                Some(field) => format!("{}.{}", array.component, field),
+                None => format!("the {} array", array.component),
+            };
+            findings.push(Finding {
+                kind: Kind::CudaDevicePointer,
+                location,
+                item: item.clone(),
+                generated: format!("{}.upload({}, heidic_count, heidic_stream)", array.buffer, array.host),
+                detail: format!("device copy of {}, copied from and back to the host array; it must hold {}.size() elements", copied, query.name),
+            });
+        }
+    }
+    findings.push(Finding {
+        kind: Kind::CudaDevicePointer,
+        location,
+        item,
+        generated: format!("{}_kernel<<<...>>>(d_{})", launch.kernel, query.name),
+        detail: format!("query '{}' is passed to the {} kernel as device pointers, bounded by its size()", query.name, launch.kernel),
+    });
+}
+
+// A Vulkan compute launch copies q.size() elements of each query array into its storage buffer
+// through a raw host pointer and back out (at the wait, for an async launch, which keeps the pointer)
+fn compute_findings(f: &FunctionDef, location: SourceLocation, components: &[&ComponentDef], findings: &mut Vec<Finding>) {
+    let (Some(launch), Some((query, component_types))) = (&f.cuda_kernel, cuda::kernel_query(f)) else { return };
+    let queried = components.iter().filter(|component| component_types.iter()
+        .any(|ty| matches!(ty, Type::Struct(name) | Type::Component(name) if *name == component.name)));
+    for component in queried {
+        for array in cuda::device_arrays(&query.name, component) {
+            let copied = match &array.field {
+                Some(field) => format!("{}.{}", array.component, field),
+                None => format!("the {} array", array.component),
+            };
+            let read_back = if launch.is_async { format!(", read back by {}()", cuda::wait_function(f)) } else { String::new() };
+            findings.push(Finding {
+                kind: Kind::ComputeBuffer,
+                location,
+                item: format!("fn {}", f.name),
+                generated: format!("heidic_gpu_buffer_write(&{}, heidic_compute_host({}.{}), ...)", vulkan_compute::buffer_global(launch, &array), query.name, array.member),
+                detail: format!("storage buffer copy of {}{}; the host array must hold {}.size() elements until then", copied, read_back, query.name),
+            });
+        }
+    }
+}
+
+/// The report as <file>.audit.json content
+pub fn report_json(findings: &[Finding], source: &str) -> Value {
+    let summary: serde_json::Map<String, Value> = Kind::ALL.iter()
+        .map(|&kind| (kind.name().to_string(), json!(findings.iter().filter(|f| f.kind == kind).count())))
+        .collect();
+    json!({
+        "source": source,
+        "summary": summary,
+        "findings": findings.iter().map(|finding| json!({
+            "kind": finding.kind.name(),
+            "item": finding.item,
+            "source": (!finding.location.is_unknown())
+                .then(|| json!({ "file": source, "line": finding.location.line, "column": finding.location.column })),
+            "generated": finding.generated,
+            "detail": finding.detail,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// The report for the terminal, grouped by kind
+pub fn report_text(findings: &[Finding], source: &str) -> String {
+    let mut out = format!("Safety audit of {}: {} raw pointer site(s)\n", source, findings.len());
+    for kind in Kind::ALL {
+        let group: Vec<&Finding> = findings.iter().filter(|f| f.kind == kind).collect();
+        if group.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n{} ({}):\n", kind.title(), group.len()));
+        for finding in group {
+            let at = if finding.location.is_unknown() {
+                source.to_string()
+            } else {
+                format!("{}:{}:{}", source, finding.location.line, finding.location.column)
+            };
+            out.push_str(&format!("  {}  {}: {}\n", at, finding.item, finding.detail));
+            out.push_str(&format!("      {}\n", finding.generated));
+        }
+    }
+    out
+}
+
+/// Every call in the program's bodies, with its arguments and location
+pub fn calls(program: &Program) -> Vec<(&str, &[Expression], SourceLocation)> {
+    let mut calls = Vec::new();
+    for (_, body) in bodies(program) {
+        statement_calls(body, &mut calls);
+    }
+    calls
+}
+
+// Every statement body with the item it belongs to
+fn bodies(program: &Program) -> Vec<(String, &[Statement])> {
+    let mut bodies = Vec::new();
+    for item in &program.items {
+        match item {
+            Item::Function(f) => bodies.push((format!("fn {}", f.name), f.body.as_slice())),
+            Item::System(s) => {
+                for f in &s.functions {
+                    bodies.push((format!("fn {} in system {}", f.name, s.name), f.body.as_slice()));
+                }
+                for handler in &s.handlers {
+                    bodies.push((format!("on {} in system {}", handler.event, s.name), handler.body.as_slice()));
+                }
+            }
+            Item::Impl(i) => {
+                for (f, _) in &i.methods {
+                    bodies.push((format!("fn {} in impl {} for {}", f.name, i.interface, i.target), f.body.as_slice()));
+                }
+            }
+            Item::Test(t) => bodies.push((format!("test \"{}\"", t.name), t.body.as_slice())),
+            Item::Migrate(m) => bodies.push((format!("migrate {}", m.function.name), m.function.body.as_slice())),
+            Item::SaveSchema(schema) => {
+                if let Some(migration) = &schema.migration {
+                    bodies.push((format!("save_schema {}", schema.name), migration.body.as_slice()));
+                }
+            }
+            _ => {}
+        }
+    }
+    bodies
+}
+
+fn statement_calls<'a>(statements: &'a [Statement], calls: &mut Vec<(&'a str, &'a [Expression], SourceLocation)>) {
+    for statement in statements {
+        match statement {
+            Statement::Let { value, .. } | Statement::Expression(value, _) | Statement::Return(Some(value), _)
+            | Statement::Break(Some(value), _) | Statement::Destructure { value, .. } | Statement::Emit(value, _) => expression_calls(value, calls),
+            Statement::Defer(value, _) => expression_calls(value, calls),
+            Statement::Assign { target, value, .. } => {
+                expression_calls(target, calls);
+                expression_calls(value, calls);
+            }
+            Statement::If { condition, then_block, else_block, .. } => {
+                expression_calls(condition, calls);
+                statement_calls(then_block, calls);
+                if let Some(else_block) = else_block {
+                    statement_calls(else_block, calls);
+                }
+            }
+            Statement::While { condition, body, .. } => {
+                expression_calls(condition, calls);
+                statement_calls(body, calls);
+            }
+            Statement::For { collection, chunk_size, body, .. } => {
+                expression_calls(collection, calls);
+                if let Some(size) = chunk_size {
+                    expression_calls(size, calls);
+                }
+                statement_calls(body, calls);
+            }
+            Statement::SpawnBatch { count, body, .. } => {
+                expression_calls(count, calls);
+                statement_calls(body, calls);
+            }
+            Statement::Loop { body, .. } | Statement::Block(body, _) => statement_calls(body, calls),
+            Statement::Return(None, _) | Statement::Break(None, _) | Statement::Continue(_) => {}
+        }
+    }
+}
+
+fn expression_calls<'a>(expr: &'a Expression, calls: &mut Vec<(&'a str, &'a [Expression], SourceLocation)>) {
+    match expr {
+        Expression::Call { name, args, location } => {
+            calls.push((name, args, *location));
+            args.iter().for_each(|arg| expression_calls(arg, calls));
+        }
+        Expression::BinaryOp { left, right, .. } => {
+            expression_calls(left, calls);
+            expression_calls(right, calls);
+        }
+        Expression::UnaryOp { expr, .. } | Expression::Cast { expr, .. } | Expression::MemberAccess { object: expr, .. } => expression_calls(expr, calls),
+        Expression::Index { array, index, .. } => {
+            expression_calls(array, calls);
+            expression_calls(index, calls);
+        }
+        Expression::ArrayLiteral { elements, .. } => elements.iter().for_each(|e| expression_calls(e, calls)),
+        Expression::Match { expr, arms, .. } => {
+            expression_calls(expr, calls);
+            for arm in arms {
+                if let Some(guard) = &arm.guard {
+                    expression_calls(guard, calls);
+                }
+                statement_calls(&arm.body, calls);
+                if let Some(value) = &arm.value {
+                    expression_calls(value, calls);
+                }
+            }
+        }
+        Expression::If { condition, then_branch, else_branch, .. } => {
+            expression_calls(condition, calls);
+            for block in [then_branch, else_branch] {
+                statement_calls(&block.statements, calls);
+                expression_calls(&block.value, calls);
+            }
+        }
+        Expression::Block { body, .. } => {
+            statement_calls(&body.statements, calls);
+            expression_calls(&body.value, calls);
+        }
+        Expression::Loop { body, .. } => statement_calls(body, calls),
+        Expression::FrameAlloc { arena, count, .. } => {
+            expression_calls(arena, calls);
+            expression_calls(count, calls);
+        }
+        Expression::StructLiteral { fields, .. } => fields.iter().for_each(|(_, value)| expression_calls(value, calls)),
+        Expression::GpuSync { direction, location, .. } => calls.push((direction.name(), &[], *location)),
+        Expression::Literal(..) | Expression::Variable(..) | Expression::StringInterpolation { .. } | Expression::LayoutOf { .. } => {}
+    }
+}
@@ -0,0 +1,181 @@
+// heidic_v2 build: compile the generated C++ into the program. A plain program is one call to the
+// C++ compiler. A program with @[launch] kernels has a second translation unit, <file>.cu: nvcc
+// compiles it to an object of its own, the C++ compiler compiles the .cpp, and nvcc links the two
+// objects with the CUDA runtime. A --backend=c program is one call to the C compiler, and a
+// --target=wasm program one call to em++, which writes <file>.js and <file>.wasm. Hot systems'
+// DLLs are built first, so the program finds them when it starts. `compile` prints the same
+// commands for building by hand.
+
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+use crate::doctor;
+
+/// The generated files of one program and how they must be compiled
+#[derive(Default)]
+pub struct Build {
+    pub source: PathBuf,        // <file>.cpp, or <file>.c with --backend=c
+    pub cuda: Option<PathBuf>,  // <file>.cu, when the program has CUDA kernels
+    pub exe: String,            // <file>.js with --target=wasm
+    pub wasm: bool,             // --target=wasm: built by Emscripten
+    pub strict_fp: bool,        // floats must stay IEEE: no contraction on the host, no FMA in kernels
+    pub dlls: Vec<PathBuf>,     // <system>_hot.dll.cpp sources, each built into <system>.dll next to it
+    pub dll_compiler: Option<String>,  // heidic.toml [hot_reload] compiler (the C++ compiler when not set)
+    pub dll_flags: Vec<String>,        // [hot_reload] flags
+    pub include_dirs: Vec<PathBuf>,    // Where stdlib/ is, when it isn't next to the generated code
+    pub flags: Vec<String>,            // heidic.toml [build] cxx_flags
+}
+
+/// The compilers a build runs
+pub struct Toolchain {
+    pub cxx: String,
+    pub cc: String,  // C compiler, for --backend=c
+    pub nvcc: String,
+    pub emxx: String,  // Emscripten's C++ compiler, for --target=wasm
+    pub ccbin: Option<String>,  // Host compiler nvcc is told to use, when CXX chose one
+}
+
+impl Default for Toolchain {
+    /// The names the printed commands use
+    fn default() -> Self {
+        Self { cxx: "g++".to_string(), cc: "cc".to_string(), nvcc: "nvcc".to_string(), emxx: "em++".to_string(), ccbin: None }
+    }
+}
+
+impl Toolchain {
+    /// $CXX (or g++), $CC (or cc), nvcc from CUDA_PATH/bin or PATH, and em++ from PATH (emsdk_env sets it up)
+    pub fn find() -> Self {
+        let configured = env::var("CXX").ok().filter(|cxx| !cxx.is_empty());
+        Self {
+            cxx: configured.clone().unwrap_or_else(|| "g++".to_string()),
+            cc: env::var("CC").ok().filter(|cc| !cc.is_empty()).unwrap_or_else(|| "cc".to_string()),
+            nvcc: doctor::find_nvcc().map(|nvcc| nvcc.display().to_string()).unwrap_or_else(|| "nvcc".to_string()),
+            emxx: "em++".to_string(),
+            ccbin: configured,
+        }
+    }
+}
+
+/// One compiler invocation
+pub struct Step {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.program, self.args.join(" "))
+    }
+}
+
+impl Build {
+    /// The compiler calls that produce the executable, in order
+    pub fn steps(&self, tools: &Toolchain) -> Vec<Step> {
+        let mut steps = self.dll_steps(tools);
+        steps.extend(self.program_steps(tools));
+        steps
+    }
+
+    // -I for each include directory, then the configured flags
+    fn extra_flags(&self) -> Vec<String> {
+        let includes = self.include_dirs.iter().map(|dir| format!("-I{}", dir.display()));
+        includes.chain(self.flags.iter().cloned()).collect()
+    }
+
+    // One shared library per hot system, compiled like --hot-rebuild compiles it
+    fn dll_steps(&self, tools: &Toolchain) -> Vec<Step> {
+        let compiler = self.dll_compiler.as_deref().unwrap_or(&tools.cxx);
+        self.dlls.iter().map(|source| {
+            let mut args = vec!["-std=c++17".to_string(), "-fwrapv".to_string()];
+            if self.strict_fp {
+                args.push("-ffp-contract=off".to_string());
+            }
+            args.extend(self.extra_flags());
+            args.extend(["-shared".to_string(), "-o".to_string(), dll_path(source).display().to_string(), source.display().to_string()]);
+            args.extend(self.dll_flags.iter().cloned());
+            Step { program: compiler.to_string(), args }
+        }).collect()
+    }
+
+    fn program_steps(&self, tools: &Toolchain) -> Vec<Step> {
+        let step = |program: &str, args: Vec<String>| Step { program: program.to_string(), args };
+        let path = |path: &Path| path.display().to_string();
+        if self.source.extension().is_some_and(|ext| ext == "c") {
+            // -lm: fmod/fmodf for float %
+            let mut args = vec!["-std=c99".to_string(), "-O3".to_string(), "-fwrapv".to_string()];
+            if self.strict_fp {
+                args.push("-ffp-contract=off".to_string());
+            }
+            args.extend(self.extra_flags());
+            args.extend([path(&self.source), "-o".to_string(), self.exe.clone(), "-lm".to_string()]);
+            return vec![step(&tools.cc, args)];
+        }
+        let mut host = vec!["-std=c++17".to_string(), "-O3".to_string(), "-fwrapv".to_string()];
+        if self.strict_fp {
+            host.push("-ffp-contract=off".to_string());
+        }
+        host.extend(self.extra_flags());
+        if self.wasm {
+            // ASYNCIFY: glfwPollEvents() and heidic_sleep_ms() yield to the browser mid-loop (stdlib/canvas.h).
+            // The page harness writes files with FS before main() runs and after they change.
+            host.extend([
+                "-fexceptions".to_string(),
+                "-sASYNCIFY".to_string(),
+                "-sALLOW_MEMORY_GROWTH=1".to_string(),
+                "-sFORCE_FILESYSTEM=1".to_string(),
+                "-sEXPORTED_RUNTIME_METHODS=FS,addRunDependency,removeRunDependency".to_string(),
+            ]);
+            return vec![step(&tools.emxx, [host, vec![path(&self.source), "-o".to_string(), self.exe.clone()]].concat())];
+        }
+        let Some(cuda) = &self.cuda else {
+            return vec![step(&tools.cxx, [host, vec![path(&self.source), "-o".to_string(), self.exe.clone()]].concat())];
+        };
+
+        // nvcc hands host compiler flags over with -Xcompiler; --fmad=false keeps kernels' floats strict too
+        let mut device = vec!["-std=c++17".to_string(), "-O3".to_string(), "-Xcompiler".to_string(), "-fwrapv".to_string()];
+        if self.strict_fp {
+            device.extend(["-Xcompiler".to_string(), "-ffp-contract=off".to_string(), "--fmad=false".to_string()]);
+        }
+        device.extend(self.include_dirs.iter().map(|dir| format!("-I{}", dir.display())));
+        let ccbin: Vec<String> = tools.ccbin.iter().flat_map(|cxx| ["-ccbin".to_string(), cxx.clone()]).collect();
+        let cuda_object = path(&object_path(cuda));
+        let cpp_object = path(&object_path(&self.source));
+        vec![
+            step(&tools.nvcc, [device, ccbin.clone(), vec!["-c".to_string(), path(cuda), "-o".to_string(), cuda_object.clone()]].concat()),
+            step(&tools.cxx, [host, vec!["-c".to_string(), path(&self.source), "-o".to_string(), cpp_object.clone()]].concat()),
+            step(&tools.nvcc, [ccbin, vec![cpp_object, cuda_object, "-o".to_string(), self.exe.clone()]].concat()),
+        ]
+    }
+
+    /// Run the steps, stopping at the first that fails
+    pub fn run(&self, tools: &Toolchain) -> Result<()> {
+        for step in self.steps(tools) {
+            println!("{}", step);
+            let status = Command::new(&step.program).args(&step.args).status()
+                .with_context(|| format!("Couldn't run {} (heidic_v2 doctor checks the toolchain)", step.program))?;
+            if !status.success() {
+                anyhow::bail!("{} failed ({})", step.program, status);
+            }
+        }
+        println!("Built {}", self.exe);
+        Ok(())
+    }
+}
+
+/// physics_hot.dll.cpp -> physics.dll (the name the program loads)
+fn dll_path(source: &Path) -> PathBuf {
+    let name = source.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    source.with_file_name(format!("{}.dll", name.strip_suffix("_hot.dll.cpp").unwrap_or(&name)))
+}
+
+/// cuda_test.cpp -> cuda_test.o, cuda_test.cu -> cuda_test.cu.o (the two mustn't collide)
+fn object_path(source: &Path) -> PathBuf {
+    match source.extension().and_then(|ext| ext.to_str()) {
+        Some("cu") => source.with_extension("cu.o"),
+        _ => source.with_extension("o"),
+    }
+}
@@ -0,0 +1,1300 @@
+// Plain C99 output (--backend=c) for toolchains without C++17, such as some embedded and console
+// SDKs. CGenerator is the second implementation of codegen::CodeGenerator, next to the C++ one,
+// and covers the language's core: structs, functions, consts, extern fns, callbacks (function
+// pointers) and interfaces, which become manual vtables. An interface value is a fat pointer
+// `{ self, vtable }`, built where a struct is passed for an interface parameter; each impl's
+// methods are static functions taking the receiver as `void*`, gathered in one `static const`
+// vtable per (interface, struct) pair. Calls on a known struct skip the vtable.
+//
+// C has no lambdas or statement expressions, so if/match/loop/block values are spelled out as
+// statements assigning a variable, which works where the value is bound by let, assigned or
+// returned. Everything built on the C++ runtime (ECS components and queries, systems, resources,
+// the renderer, events, hot reload, arrays, optionals, joining strings) has no C equivalent;
+// conflicts() reports it at compile time, the way --runtime minimal reports what it leaves out.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+
+use crate::ast::*;
+use crate::codegen::CodeGenerator;
+use crate::const_eval::{self, ConstValue};
+use crate::error::SourceLocation;
+use crate::formatter::type_str;
+use crate::generated_names;
+use crate::layout::{LayoutEngine, TargetAbi};
+
+/// C keywords and the C library names the generated file includes; HEIDIC names spelled like
+/// one get a heidic_ prefix (extern fns keep theirs: they name C functions)
+const C_RESERVED: &[&str] = &[
+    "auto", "case", "char", "const", "default", "do", "double", "enum", "extern", "float", "goto",
+    "inline", "int", "long", "register", "restrict", "short", "signed", "sizeof", "static", "struct",
+    "switch", "typedef", "union", "unsigned", "volatile", "int32_t", "int64_t", "size_t", "printf",
+    "strcmp", "fmod", "fmodf", "offsetof", "NULL",
+];
+
+fn c_name(name: &str) -> String {
+    if C_RESERVED.contains(&name) {
+        format!("heidic_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// The program's main becomes heidic_main (mangled to hd_<program>_main); C's main calls it
+fn function_name(name: &str) -> String {
+    if name == "main" { "heidic_main".to_string() } else { c_name(name) }
+}
+
+/// The vtable of `interface` for `target`: heidic_Damageable_Health
+fn vtable_name(interface: &str, target: &str) -> String {
+    format!("heidic_{}_{}", interface, target)
+}
+
+/// An impl method: heidic_Damageable_Health_apply_damage
+fn method_name(interface: &str, target: &str, method: &str) -> String {
+    format!("heidic_{}_{}_{}", interface, target, method)
+}
+
+// A string literal as written: the lexer keeps its escapes (\n, \t, \") as they are in the source
+fn c_string(text: &str) -> String {
+    format!("\"{}\"", text)
+}
+
+// Text inside a printf format: the literal's escapes, and % doubled
+fn format_text(text: &str) -> String {
+    text.replace('%', "%%")
+}
+
+/// (message, suggestion) at a location of the .hd file
+pub type Conflict = (SourceLocation, String, String);
+
+/// What --backend=c can't generate: items and constructs built on the C++ runtime
+pub fn conflicts(program: &Program, item_locations: &[SourceLocation]) -> Vec<Conflict> {
+    translate(program, item_locations, TargetAbi::host()).conflicts
+}
+
+/// The C generator: `generate` fails on programs conflicts() reports, so callers check first
+pub struct CGenerator {
+    target_abi: TargetAbi,  // --target-abi: the layout model size_of/align_of/offset_of use
+    strict_fp: bool,  // --strict-fp
+    has_strict_fp: bool,  // Some function of the program is @strict_fp (set by generate)
+    symbol_prefix: String,  // hd_<program>_ (generated_names::mangle)
+}
+
+impl CGenerator {
+    pub fn new() -> Self {
+        Self {
+            target_abi: TargetAbi::host(),
+            strict_fp: false,
+            has_strict_fp: false,
+            symbol_prefix: generated_names::symbol_prefix(""),
+        }
+    }
+
+    pub fn set_target_abi(&mut self, abi: TargetAbi) {
+        self.target_abi = abi;
+    }
+
+    /// C has no per-function float pragmas the C++ runtime's HEIDIC_STRICT_FP_BEGIN could map to,
+    /// so any @strict_fp function makes the whole file strict (see uses_strict_fp)
+    pub fn set_strict_fp(&mut self) {
+        self.strict_fp = true;
+    }
+}
+
+impl CodeGenerator for CGenerator {
+    fn generate(&mut self, program: &Program) -> Result<String> {
+        let translation = translate(program, &[], self.target_abi);
+        if let Some((_, message, _)) = translation.conflicts.first() {
+            anyhow::bail!("{} (run c_codegen::conflicts before generating C)", message);
+        }
+        self.has_strict_fp = translation.strict_fp;
+        Ok(generated_names::mangle(&translation.code, &self.symbol_prefix, &HashSet::new()))
+    }
+
+    fn extension(&self) -> &'static str {
+        "c"
+    }
+
+    fn set_program_name(&mut self, name: &str) {
+        self.symbol_prefix = generated_names::symbol_prefix(name);
+    }
+
+    fn symbol_prefix(&self) -> &str {
+        &self.symbol_prefix
+    }
+
+    fn uses_strict_fp(&self) -> bool {
+        self.strict_fp || self.has_strict_fp
+    }
+}
+
+struct Translation {
+    code: String,
+    conflicts: Vec<Conflict>,
+    strict_fp: bool,
+}
+
+// What an item is, when C has no equivalent for it
+fn item_conflict(item: &Item) -> Option<(String, &'static str)> {
+    let ecs = "The ECS runtime is C++; keep the data in structs and pass them to functions";
+    let renderer = "The renderer is built on the C++ runtime; draw through extern fns, or build with --backend=cpp";
+    let files = "Save files and scenes go through the C++ runtime; serialize through extern fns";
+    let what = match item {
+        Item::Component(c) => format!("component '{}'", c.name),
+        Item::System(s) => format!("system '{}'", s.name),
+        Item::Shader(shader) => format!("shader '{}'", shader.path),
+        Item::Resource(r) => format!("resource '{}'", r.name),
+        Item::Pipeline(p) => format!("pipeline '{}'", p.name),
+        Item::Gpu(_) => "gpu { ... }".to_string(),
+        Item::Window(_) => "window { ... }".to_string(),
+        Item::GpuQuery(q) => format!("gpu_query '{}'", q.name),
+        Item::Uniform(u) => format!("uniform '{}'", u.name),
+        Item::Input(_) => "input { ... }".to_string(),
+        Item::Event(e) => format!("event '{}'", e.name),
+        Item::SaveSchema(schema) => format!("save_schema '{}'", schema.name),
+        Item::Scene(scene) => format!("scene '{}'", scene.path),
+        Item::Migrate(m) => format!("migrate fn for '{}'", m.function.name),
+        Item::Test(test) => format!("test \"{}\"", test.name),
+        Item::Const(c) if c.is_tweak => format!("@tweak const '{}'", c.name),
+        Item::Function(f) if f.cuda_kernel.is_some() => format!("@[launch] fn '{}'", f.name),
+        Item::Function(f) if f.is_hot => format!("@hot fn '{}'", f.name),
+        _ => return None,
+    };
+    let suggestion = match item {
+        Item::Component(_) | Item::System(_) => ecs,
+        Item::Resource(_) => "Resources load through the engine's C++ asset loaders; load assets with extern fns",
+        Item::Input(_) => "Input actions read devices through the C++ runtime; poll them with extern fns",
+        Item::Event(_) => "Event queues are C++ containers; call the handling functions directly",
+        Item::SaveSchema(_) | Item::Scene(_) | Item::Migrate(_) => files,
+        Item::Test(_) => "The test runner is C++; run `heidic_v2 test` without --backend=c",
+        Item::Const(_) => "The tweak editor is C++; make it a plain const",
+        Item::Function(f) if f.cuda_kernel.is_some() => "GPU kernels are launched from the C++ runtime; build with --backend=cpp",
+        Item::Function(_) => "Hot reload loads C++ DLLs; drop @hot for this build",
+        _ => renderer,
+    };
+    Some((format!("{} isn't available with --backend=c", what), suggestion))
+}
+
+fn translate(program: &Program, item_locations: &[SourceLocation], abi: TargetAbi) -> Translation {
+    let location_of = |index: usize| item_locations.get(index).copied().unwrap_or_else(SourceLocation::unknown);
+    let mut translator = Translator::new(program, abi);
+    for (index, item) in program.items.iter().enumerate() {
+        if let Some((message, suggestion)) = item_conflict(item) {
+            translator.conflict(location_of(index), message, suggestion);
+        }
+    }
+    let functions: Vec<(usize, &FunctionDef)> = program.items.iter().enumerate()
+        .filter_map(|(index, item)| match item {
+            Item::Function(f) if item_conflict(item).is_none() => Some((index, f)),
+            _ => None,
+        })
+        .collect();
+    let impls: Vec<(usize, &ImplDef)> = program.items.iter().enumerate()
+        .filter_map(|(index, item)| match item {
+            Item::Impl(imp) if translator.structs.contains_key(&imp.target) && translator.interfaces.contains_key(&imp.interface) => Some((index, imp)),
+            _ => None,
+        })
+        .collect();
+    let mut output = String::new();
+
+    let structs = translator.struct_order();
+    if !structs.is_empty() {
+        output.push_str("// Structs\n");
+        for s in &structs {
+            output.push_str(&format!("typedef struct {name} {name};\n", name = c_name(&s.name)));
+        }
+        output.push('\n');
+        for s in &structs {
+            translator.location = location_of(translator.item_index(&s.name));
+            output.push_str(&format!("struct {} {{\n", c_name(&s.name)));
+            for field in &s.fields {
+                output.push_str(&format!("    {};\n", translator.declaration(&field.ty, &c_name(&field.name), translator.location)));
+            }
+            // C doesn't allow empty structs
+            if s.fields.is_empty() {
+                output.push_str("    char heidic_unused;\n");
+            }
+            output.push_str("};\n\n");
+        }
+    }
+
+    for (index, item) in program.items.iter().enumerate() {
+        let Item::Interface(iface) = item else { continue };
+        translator.location = location_of(index);
+        output.push_str(&format!("// interface {}: an implementing struct and its vtable\n", iface.name));
+        output.push_str(&format!("typedef struct heidic_{}_vtable {{\n", iface.name));
+        for method in &iface.methods {
+            let params = translator.parameters(&method.params, true);
+            output.push_str(&format!("    {};\n", translator.declaration(&method.return_type, &format!("(*{})({})", c_name(&method.name), params), method.location)));
+        }
+        output.push_str(&format!("}} heidic_{}_vtable;\n\n", iface.name));
+        output.push_str(&format!("typedef struct {name} {{\n    void* self;\n    const heidic_{iface}_vtable* vtable;\n}} {name};\n\n",
+            name = c_name(&iface.name), iface = iface.name));
+    }
+
+    let mut consts = String::new();
+    for (index, item) in program.items.iter().enumerate() {
+        if let Item::Const(c) = item {
+            if !c.is_tweak {
+                translator.location = location_of(index);
+                consts.push_str(&translator.constant(c));
+            }
+        }
+    }
+    if !consts.is_empty() {
+        output.push_str("// Constants\n");
+        output.push_str(&consts);
+        output.push('\n');
+    }
+
+    let externs: Vec<&ExternFunctionDef> = program.items.iter().filter_map(|item| match item {
+        Item::ExternFunction(f) => Some(f),
+        _ => None,
+    }).collect();
+    if !externs.is_empty() {
+        output.push_str("// Extern functions\n");
+        for f in externs {
+            let params = translator.parameters(&f.params, false);
+            output.push_str(&format!("{};\n", translator.declaration(&f.return_type, &format!("{}({})", f.name, params), translator.location)));
+        }
+        output.push('\n');
+    }
+
+    let mut prototypes = String::new();
+    for (index, f) in &functions {
+        translator.location = location_of(*index);
+        prototypes.push_str(&format!("{};\n", translator.signature(f, &function_name(&f.name), None)));
+    }
+    for (index, imp) in &impls {
+        translator.location = location_of(*index);
+        for (method, _) in &imp.methods {
+            let name = method_name(&imp.interface, &imp.target, &method.name);
+            prototypes.push_str(&format!("static {};\n", translator.signature(method, &name, Some(&imp.target))));
+        }
+    }
+    if !prototypes.is_empty() {
+        output.push_str("// Functions\n");
+        output.push_str(&prototypes);
+        output.push('\n');
+    }
+
+    for (_, imp) in &impls {
+        let Some(iface) = translator.interfaces.get(&imp.interface).copied() else { continue };
+        let methods: Vec<String> = iface.methods.iter()
+            .map(|method| method_name(&imp.interface, &imp.target, &method.name))
+            .collect();
+        output.push_str(&format!("static const heidic_{}_vtable {} = {{ {} }};\n",
+            imp.interface, vtable_name(&imp.interface, &imp.target), methods.join(", ")));
+    }
+    if !impls.is_empty() {
+        output.push('\n');
+    }
+
+    for (index, f) in &functions {
+        translator.location = location_of(*index);
+        output.push_str(&translator.function(f, &function_name(&f.name), None));
+    }
+    for (index, imp) in &impls {
+        for (method, location) in &imp.methods {
+            translator.location = if location.line == 0 { location_of(*index) } else { *location };
+            let name = format!("static {}", method_name(&imp.interface, &imp.target, &method.name));
+            output.push_str(&translator.function(method, &name, Some(&imp.target)));
+        }
+    }
+
+    if !translator.layout_checks.is_empty() {
+        output.push_str("// Layout checks (size_of/offset_of): a negative array size if C disagrees with the layout model\n");
+        for check in &translator.layout_checks {
+            output.push_str(check);
+        }
+        output.push('\n');
+    }
+
+    if functions.iter().any(|(_, f)| f.name == "main") {
+        output.push_str("int main(void) {\n");
+        output.push_str("    heidic_main();\n");
+        output.push_str("    return 0;\n");
+        output.push_str("}\n");
+    }
+
+    let mut code = String::from("// C99 (heidic_v2 --backend=c)\n");
+    for header in ["inttypes.h", "stdbool.h", "stddef.h", "stdint.h", "stdio.h", "string.h"] {
+        code.push_str(&format!("#include <{}>\n", header));
+    }
+    if translator.uses_math {
+        code.push_str("#include <math.h>\n");
+    }
+    code.push('\n');
+    code.push_str(&output);
+    let strict_fp = functions.iter().any(|(_, f)| f.strict_fp);
+    Translation { code, conflicts: translator.conflicts, strict_fp }
+}
+
+// A name in scope: its C spelling (`(*self)` for an impl method's receiver, a field of a match
+// temporary for a pattern binding) and its type
+struct Local {
+    c: String,
+    ty: Type,
+}
+
+#[derive(Default)]
+struct Scope {
+    locals: HashMap<String, Local>,
+    defers: Vec<String>,  // Statements of `defer`, run in reverse when the scope is left
+    is_loop: bool,  // A loop body: break and continue leave it
+}
+
+// A loop being translated: the variable `break value;` sets, for a loop used as a value, and
+// the type of the first such value
+struct LoopTarget {
+    variable: Option<String>,
+    value_type: Option<Type>,
+}
+
+// HEIDIC items, statements and expressions as C, with the types C declarations need
+struct Translator<'a> {
+    items: &'a [Item],
+    structs: HashMap<String, &'a StructDef>,
+    interfaces: HashMap<String, &'a InterfaceDef>,
+    impls: Vec<&'a ImplDef>,
+    functions: HashMap<String, &'a FunctionDef>,
+    externs: HashMap<String, &'a ExternFunctionDef>,
+    consts: HashMap<String, (Type, ConstValue)>,
+    layout_structs: HashMap<String, StructDef>,
+    layout_components: HashMap<String, ComponentDef>,  // Always empty: components aren't generated
+    abi: TargetAbi,
+    scopes: Vec<Scope>,
+    loops: Vec<LoopTarget>,
+    return_type: Type,
+    main_returns_int: bool,  // Translating `fn main(): void`, which returns 0 to C's main
+    temporaries: usize,
+    uses_math: bool,  // fmod/fmodf: the file includes <math.h>
+    layout_checks: Vec<String>,
+    conflicts: Vec<Conflict>,
+    location: SourceLocation,  // The item being translated, for what has no location of its own
+}
+
+impl<'a> Translator<'a> {
+    fn new(program: &'a Program, abi: TargetAbi) -> Self {
+        let mut translator = Self {
+            items: &program.items,
+            structs: HashMap::new(),
+            interfaces: HashMap::new(),
+            impls: Vec::new(),
+            functions: HashMap::new(),
+            externs: HashMap::new(),
+            consts: HashMap::new(),
+            layout_structs: HashMap::new(),
+            layout_components: HashMap::new(),
+            abi,
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            return_type: Type::Void,
+            main_returns_int: false,
+            temporaries: 0,
+            uses_math: false,
+            layout_checks: Vec::new(),
+            conflicts: Vec::new(),
+            location: SourceLocation::unknown(),
+        };
+        for item in &program.items {
+            match item {
+                Item::Struct(s) => {
+                    translator.structs.insert(s.name.clone(), s);
+                    translator.layout_structs.insert(s.name.clone(), s.clone());
+                }
+                Item::Interface(iface) => {
+                    translator.interfaces.insert(iface.name.clone(), iface);
+                }
+                Item::Impl(imp) => translator.impls.push(imp),
+                Item::Function(f) => {
+                    translator.functions.insert(f.name.clone(), f);
+                }
+                Item::ExternFunction(f) => {
+                    translator.externs.insert(f.name.clone(), f);
+                }
+                _ => {}
+            }
+        }
+        translator
+    }
+
+    fn conflict(&mut self, location: SourceLocation, message: String, suggestion: &str) {
+        let location = if location.line == 0 { self.location } else { location };
+        self.conflicts.push((location, message, suggestion.to_string()));
+    }
+
+    fn unsupported(&mut self, location: SourceLocation, what: &str, suggestion: &str) {
+        self.conflict(location, format!("{} isn't available with --backend=c", what), suggestion);
+    }
+
+    fn item_index(&self, name: &str) -> usize {
+        self.items.iter().position(|item| matches!(item, Item::Struct(s) if s.name == name)).unwrap_or(0)
+    }
+
+    fn layouts(&self) -> LayoutEngine<'_> {
+        LayoutEngine::new(&self.layout_structs, &self.layout_components, self.abi)
+    }
+
+    fn temporary(&mut self, purpose: &str) -> String {
+        self.temporaries += 1;
+        format!("heidic_{}_{}", purpose, self.temporaries)
+    }
+
+    // Structs in an order C accepts: each after the structs it holds by value
+    fn struct_order(&self) -> Vec<&'a StructDef> {
+        fn visit<'a>(s: &'a StructDef, structs: &HashMap<String, &'a StructDef>, seen: &mut HashSet<String>, order: &mut Vec<&'a StructDef>) {
+            if !seen.insert(s.name.clone()) {
+                return;
+            }
+            for field in &s.fields {
+                if let Type::Struct(name) = &field.ty {
+                    if let Some(inner) = structs.get(name) {
+                        visit(inner, structs, seen, order);
+                    }
+                }
+            }
+            order.push(s);
+        }
+        let mut seen = HashSet::new();
+        let mut order = Vec::new();
+        for item in self.items {
+            if let Item::Struct(s) = item {
+                visit(s, &self.structs, &mut seen, &mut order);
+            }
+        }
+        order
+    }
+
+    fn declare(&mut self, name: &str, c: String, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.locals.insert(name.to_string(), Local { c, ty });
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Local> {
+        self.scopes.iter().rev().find_map(|scope| scope.locals.get(name))
+    }
+
+    fn c_type(&mut self, ty: &Type, location: SourceLocation) -> String {
+        match ty {
+            Type::I32 => "int32_t".to_string(),
+            Type::I64 => "int64_t".to_string(),
+            Type::F32 => "float".to_string(),
+            Type::F64 => "double".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::String => "const char*".to_string(),
+            Type::Void => "void".to_string(),
+            Type::Struct(name) if self.structs.contains_key(name) || self.interfaces.contains_key(name) => c_name(name),
+            Type::Function(..) => self.declaration(ty, "", location),
+            Type::Error => "void".to_string(),  // Already reported
+            Type::Array(_) => {
+                self.unsupported(location, &format!("Array type '{}'", type_str(ty)),
+                    "Arrays are std::vectors in the C++ runtime; pass a pointer and a length through extern fns");
+                "void".to_string()
+            }
+            Type::Optional(_) => {
+                self.unsupported(location, &format!("Optional type '{}'", type_str(ty)), "Keep a bool flag next to the value");
+                "void".to_string()
+            }
+            _ => {
+                self.unsupported(location, &format!("Type '{}'", type_str(ty)),
+                    "It's part of the C++ runtime; use numbers, bools, strings, structs and fn types, or build with --backend=cpp");
+                "void".to_string()
+            }
+        }
+    }
+
+    // `declarator` declared with type `ty`: function pointer types wrap around the name
+    // (`bool (*on_hit)(int32_t)`); an empty declarator gives the type alone
+    fn declaration(&mut self, ty: &Type, declarator: &str, location: SourceLocation) -> String {
+        if let Type::Function(params, ret) = ty {
+            let params: Vec<String> = params.iter().map(|param| self.declaration(param, "", location)).collect();
+            let params = if params.is_empty() { "void".to_string() } else { params.join(", ") };
+            return self.declaration(ret, &format!("(*{})({})", declarator, params), location);
+        }
+        let base = self.c_type(ty, location);
+        if declarator.is_empty() { base } else { format!("{} {}", base, declarator) }
+    }
+
+    // A parameter list; an interface method's receiver is `void* self`
+    fn parameters(&mut self, params: &[Param], receiver: bool) -> String {
+        let mut list = Vec::new();
+        for (index, param) in params.iter().enumerate() {
+            if receiver && index == 0 {
+                list.push("void* self".to_string());
+            } else {
+                list.push(self.declaration(&param.ty, &c_name(&param.name), param.location));
+            }
+        }
+        if list.is_empty() { "void".to_string() } else { list.join(", ") }
+    }
+
+    // `int32_t add(int32_t a, int32_t b)`; impl methods (`receiver` is the target struct) take `void* heidic_self`
+    fn signature(&mut self, f: &FunctionDef, name: &str, receiver: Option<&str>) -> String {
+        let params = match receiver {
+            Some(_) => {
+                let rest = self.parameters(&f.params[1.min(f.params.len())..], false);
+                if rest == "void" { "void* heidic_self".to_string() } else { format!("void* heidic_self, {}", rest) }
+            }
+            None => self.parameters(&f.params, false),
+        };
+        if f.name == "main" && receiver.is_none() && matches!(f.return_type, Type::Void) {
+            return format!("int {}({})", name, params);
+        }
+        self.declaration(&f.return_type, &format!("{}({})", name, params), self.location)
+    }
+
+    fn function(&mut self, f: &FunctionDef, name: &str, receiver: Option<&str>) -> String {
+        let signature = self.signature(f, name.trim_start_matches("static "), receiver);
+        let storage = if name.starts_with("static ") { "static " } else { "" };
+        let mut output = format!("{}{} {{\n", storage, signature);
+        self.scopes = vec![Scope::default()];
+        self.loops.clear();
+        self.return_type = f.return_type.clone();
+        self.main_returns_int = f.name == "main" && receiver.is_none() && matches!(f.return_type, Type::Void);
+        for (index, param) in f.params.iter().enumerate() {
+            match receiver {
+                Some(target) if index == 0 => {
+                    output.push_str(&format!("    {target}* self = ({target}*)heidic_self;\n", target = c_name(target)));
+                    self.declare(&param.name, "(*self)".to_string(), Type::Struct(target.to_string()));
+                }
+                _ => self.declare(&param.name, c_name(&param.name), param.ty.clone()),
+            }
+        }
+        for stmt in &f.body {
+            self.statement(stmt, 1, &mut output);
+        }
+        if !ends_scope(&f.body) {
+            self.leave_scope(1, &mut output);
+            if self.main_returns_int {
+                output.push_str("    return 0;\n");
+            }
+        }
+        self.scopes.clear();
+        output.push_str("}\n\n");
+        output
+    }
+
+    // `static const int32_t MAX_LIGHTS = 16;`: the value is folded, since C doesn't treat one
+    // const as a constant expression in another's initializer
+    fn constant(&mut self, c: &ConstDef) -> String {
+        let value_type = self.constant_type(&c.value);
+        let ty = c.ty.clone().unwrap_or(value_type);
+        let consts = &self.consts;
+        let constant = |name: &str| consts.get(name).map(|(_, value)| *value);
+        let value = const_eval::evaluate(&c.value, &constant, &self.layouts()).ok()
+            .and_then(|value| value.convert(&ty).ok());
+        let literal = match (&ty, value) {
+            (Type::I32, Some(ConstValue::Int(n))) => n.to_string(),
+            (Type::I64, Some(ConstValue::Int(n))) => format!("INT64_C({})", n),
+            (Type::F32, Some(ConstValue::Float(n))) => format!("{:?}f", n),
+            (Type::F64, Some(ConstValue::Float(n))) => format!("{:?}", n),
+            (Type::Bool, Some(ConstValue::Bool(b))) => b.to_string(),
+            _ => {
+                self.unsupported(c.location, &format!("const '{}' of type '{}'", c.name, type_str(&ty)),
+                    "C consts are numbers and bools here; use a fn returning the value");
+                return String::new();
+            }
+        };
+        let declaration = self.declaration(&ty, &c_name(&c.name), c.location);
+        self.consts.insert(c.name.clone(), (ty, value.unwrap_or(ConstValue::Error)));
+        format!("static const {} = {};\n", declaration, literal)
+    }
+
+    // The type of a const's value, without reporting anything (its value is checked by constant())
+    fn constant_type(&mut self, value: &Expression) -> Type {
+        let conflicts = self.conflicts.len();
+        let (_, ty) = self.expression(value);
+        self.conflicts.truncate(conflicts);
+        ty
+    }
+
+    // The defers run when leaving the innermost scope, or every scope up to the enclosing loop
+    // body or the function, innermost first
+    fn pending_defers(&self, up_to_loop: bool, all: bool) -> Vec<String> {
+        let mut defers = Vec::new();
+        for scope in self.scopes.iter().rev() {
+            defers.extend(scope.defers.iter().rev().cloned());
+            if !all || (up_to_loop && scope.is_loop) {
+                break;
+            }
+        }
+        defers
+    }
+
+    fn emit_defers(&self, defers: Vec<String>, indent: usize, output: &mut String) {
+        let pad = "    ".repeat(indent);
+        for defer in defers {
+            output.push_str(&format!("{}{}\n", pad, defer));
+        }
+    }
+
+    // Falling off the end of the innermost scope
+    fn leave_scope(&self, indent: usize, output: &mut String) {
+        self.emit_defers(self.pending_defers(false, false), indent, output);
+    }
+
+    fn block(&mut self, statements: &[Statement], indent: usize, is_loop: bool, output: &mut String) {
+        self.scopes.push(Scope { is_loop, ..Scope::default() });
+        for stmt in statements {
+            self.statement(stmt, indent, output);
+        }
+        if !ends_scope(statements) {
+            self.leave_scope(indent, output);
+        }
+        self.scopes.pop();
+    }
+
+    fn loop_body(&mut self, body: &[Statement], variable: Option<String>, indent: usize, output: &mut String) -> Option<Type> {
+        self.loops.push(LoopTarget { variable, value_type: None });
+        self.block(body, indent, true, output);
+        self.loops.pop().and_then(|target| target.value_type)
+    }
+
+    fn statement(&mut self, stmt: &Statement, indent: usize, output: &mut String) {
+        let pad = "    ".repeat(indent);
+        match stmt {
+            Statement::Let { name, ty, value, location, .. } => {
+                let variable = c_name(name);
+                if is_statement_value(value) {
+                    let mut body = String::new();
+                    let value_type = self.assign(&variable, value, indent, &mut body);
+                    let ty = ty.clone().unwrap_or(value_type);
+                    output.push_str(&format!("{}{};\n", pad, self.declaration(&ty, &variable, *location)));
+                    output.push_str(&body);
+                    self.declare(name, variable, ty);
+                } else {
+                    let (value, value_type) = self.expression(value);
+                    let ty = ty.clone().unwrap_or(value_type);
+                    output.push_str(&format!("{}{} = {};\n", pad, self.declaration(&ty, &variable, *location), value));
+                    self.declare(name, variable, ty);
+                }
+            }
+            Statement::Destructure { pattern, value, location, .. } => {
+                let (value, ty) = self.expression(value);
+                let temporary = self.temporary("destructure");
+                output.push_str(&format!("{}{} = {};\n", pad, self.declaration(&ty, &temporary, *location), value));
+                for (path, name, binding_location) in pattern.bindings() {
+                    let field_type = self.field_path_type(&ty, &path, binding_location);
+                    let variable = c_name(name);
+                    let access = std::iter::once(temporary.clone()).chain(path.iter().map(|field| c_name(field))).collect::<Vec<_>>().join(".");
+                    output.push_str(&format!("{}{} = {};\n", pad, self.declaration(&field_type, &variable, binding_location), access));
+                    self.declare(name, variable, field_type);
+                }
+            }
+            Statement::Assign { target, value, .. } => {
+                let (target, _) = self.expression(target);
+                if is_statement_value(value) {
+                    self.assign(&target, value, indent, output);
+                } else {
+                    let (value, _) = self.expression(value);
+                    output.push_str(&format!("{}{} = {};\n", pad, target, value));
+                }
+            }
+            Statement::If { condition, then_block, else_block, .. } => {
+                let (condition, _) = self.expression(condition);
+                output.push_str(&format!("{}if ({}) {{\n", pad, condition));
+                self.block(then_block, indent + 1, false, output);
+                if let Some(else_block) = else_block {
+                    output.push_str(&format!("{}}} else {{\n", pad));
+                    self.block(else_block, indent + 1, false, output);
+                }
+                output.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::While { condition, body, .. } => {
+                let (condition, _) = self.expression(condition);
+                output.push_str(&format!("{}while ({}) {{\n", pad, condition));
+                self.loop_body(body, None, indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::Loop { body, .. } => {
+                output.push_str(&format!("{}while (true) {{\n", pad));
+                self.loop_body(body, None, indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::Return(None, _) => {
+                self.emit_defers(self.pending_defers(false, true), indent, output);
+                output.push_str(&format!("{}{};\n", pad, if self.main_returns_int { "return 0" } else { "return" }));
+            }
+            Statement::Return(Some(value), location) => {
+                let defers = self.pending_defers(false, true);
+                if defers.is_empty() && !is_statement_value(value) {
+                    let (value, _) = self.expression(value);
+                    output.push_str(&format!("{}return {};\n", pad, value));
+                } else {
+                    // The value is computed before the defers run
+                    let result = self.temporary("result");
+                    let return_type = self.return_type.clone();
+                    output.push_str(&format!("{}{};\n", pad, self.declaration(&return_type, &result, *location)));
+                    self.assign(&result, value, indent, output);
+                    self.emit_defers(defers, indent, output);
+                    output.push_str(&format!("{}return {};\n", pad, result));
+                }
+            }
+            Statement::Break(value, location) => {
+                if let Some(value) = value {
+                    match self.loops.last().and_then(|target| target.variable.clone()) {
+                        Some(variable) => {
+                            let ty = self.assign(&variable, value, indent, output);
+                            if let Some(target) = self.loops.last_mut() {
+                                target.value_type.get_or_insert(ty);
+                            }
+                        }
+                        None => self.unsupported(*location, "Breaking with a value out of a loop that isn't bound by let",
+                            "Bind the loop's value: let found = loop { ... break value; };"),
+                    }
+                }
+                self.emit_defers(self.pending_defers(true, true), indent, output);
+                output.push_str(&format!("{}break;\n", pad));
+            }
+            Statement::Continue(_) => {
+                self.emit_defers(self.pending_defers(true, true), indent, output);
+                output.push_str(&format!("{}continue;\n", pad));
+            }
+            Statement::Defer(expr, _) => {
+                let (expr, _) = self.expression(expr);
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.defers.push(format!("{};", expr));
+                }
+            }
+            Statement::Expression(Expression::Match { expr, arms, .. }, _) => {
+                self.match_chain(expr, arms, None, indent, output);
+            }
+            Statement::Expression(expr, _) => {
+                let (expr, _) = self.expression(expr);
+                output.push_str(&format!("{}{};\n", pad, expr));
+            }
+            Statement::Block(statements, _) => {
+                output.push_str(&format!("{}{{\n", pad));
+                self.block(statements, indent + 1, false, output);
+                output.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::For { location, .. } => self.unsupported(*location, "A for loop over a collection",
+                "Arrays and queries are C++ containers; count with a while loop"),
+            Statement::SpawnBatch { location, .. } => self.unsupported(*location, "spawn_batch",
+                "The ECS runtime is C++; keep the data in structs and pass them to functions"),
+            Statement::Emit(_, location) => self.unsupported(*location, "Emitting an event",
+                "Event queues are C++ containers; call the handling functions directly"),
+        }
+    }
+
+    // `target = value;`, with if/match/loop/block values spelled out as statements; returns the
+    // value's type
+    fn assign(&mut self, target: &str, value: &Expression, indent: usize, output: &mut String) -> Type {
+        let pad = "    ".repeat(indent);
+        match value {
+            Expression::If { condition, then_branch, else_branch, .. } if is_statement_value(value) => {
+                let (condition, _) = self.expression(condition);
+                output.push_str(&format!("{}if ({}) {{\n", pad, condition));
+                let ty = self.value_block(target, then_branch, indent + 1, output);
+                output.push_str(&format!("{}}} else {{\n", pad));
+                self.value_block(target, else_branch, indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+                ty
+            }
+            Expression::Block { body, .. } => {
+                output.push_str(&format!("{}{{\n", pad));
+                let ty = self.value_block(target, body, indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+                ty
+            }
+            Expression::Loop { body, location } => {
+                output.push_str(&format!("{}while (true) {{\n", pad));
+                let ty = self.loop_body(body, Some(target.to_string()), indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+                ty.unwrap_or_else(|| {
+                    self.unsupported(*location, "A loop value without a `break value;`", "Give the let a type, or break with a value");
+                    Type::Error
+                })
+            }
+            Expression::Match { expr, arms, .. } => self.match_chain(expr, arms, Some(target), indent, output),
+            _ => {
+                let (value, ty) = self.expression(value);
+                output.push_str(&format!("{}{} = {};\n", pad, target, value));
+                ty
+            }
+        }
+    }
+
+    fn value_block(&mut self, target: &str, block: &ValueBlock, indent: usize, output: &mut String) -> Type {
+        self.scopes.push(Scope::default());
+        for stmt in &block.statements {
+            self.statement(stmt, indent, output);
+        }
+        let ty = self.assign(target, &block.value, indent, output);
+        self.leave_scope(indent, output);
+        self.scopes.pop();
+        ty
+    }
+
+    // A match as an if/else chain over a copy of the scrutinee; pattern bindings name its fields.
+    // Arms with values assign `target`. Returns the type of the first arm's value.
+    fn match_chain(&mut self, scrutinee: &Expression, arms: &[MatchArm], target: Option<&str>, indent: usize, output: &mut String) -> Type {
+        let pad = "    ".repeat(indent);
+        let (value, ty) = self.expression(scrutinee);
+        let temporary = self.temporary("match");
+        output.push_str(&format!("{}{{\n", pad));
+        output.push_str(&format!("{}    {} = {};\n", pad, self.declaration(&ty, &temporary, scrutinee.location()), value));
+        let mut value_type = None;
+        let mut opened = false;
+        for arm in arms {
+            self.scopes.push(Scope::default());
+            let mut conditions = Vec::new();
+            self.pattern(&arm.pattern, &temporary, &ty, &mut conditions);
+            if let Some(guard) = &arm.guard {
+                conditions.push(self.expression(guard).0);
+            }
+            let keyword = match (opened, conditions.is_empty()) {
+                (false, true) => "{".to_string(),
+                (true, true) => "} else {".to_string(),
+                (false, false) => format!("if ({}) {{", conditions.join(" && ")),
+                (true, false) => format!("}} else if ({}) {{", conditions.join(" && ")),
+            };
+            output.push_str(&format!("{}    {}\n", pad, keyword));
+            for stmt in &arm.body {
+                self.statement(stmt, indent + 2, output);
+            }
+            if let (Some(value), Some(target)) = (&arm.value, target) {
+                let ty = self.assign(target, value, indent + 2, output);
+                value_type.get_or_insert(ty);
+            }
+            if !ends_scope(&arm.body) {
+                self.leave_scope(indent + 2, output);
+            }
+            self.scopes.pop();
+            opened = true;
+            // Arms after one that matches everything can't run
+            if conditions.is_empty() {
+                break;
+            }
+        }
+        if opened {
+            output.push_str(&format!("{}    }}\n", pad));
+        }
+        output.push_str(&format!("{}}}\n", pad));
+        value_type.unwrap_or(Type::Void)
+    }
+
+    // The conditions under which `pattern` matches `value` (none: always), declaring its bindings
+    fn pattern(&mut self, pattern: &Pattern, value: &str, ty: &Type, conditions: &mut Vec<String>) {
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Variable(name, _) => self.declare(name, value.to_string(), ty.clone()),
+            Pattern::Literal(Literal::String(s), _) => conditions.push(format!("strcmp({}, {}) == 0", value, c_string(s))),
+            Pattern::Literal(literal, _) => conditions.push(format!("{} == {}", value, c_literal(literal))),
+            Pattern::Ident(name, location) => match self.consts.contains_key(name) {
+                true => conditions.push(format!("{} == {}", value, c_name(name))),
+                false => self.unsupported(*location, &format!("Matching on '{}'", name), "Match on a const or a literal"),
+            },
+            Pattern::Range { start, end, inclusive, .. } => {
+                let end_op = if *inclusive { "<=" } else { "<" };
+                conditions.push(format!("({} <= {} && {} {} {})", c_literal(start), value, value, end_op, c_literal(end)));
+            }
+            Pattern::Or(alternatives, location) => {
+                if !pattern.bindings().is_empty() {
+                    self.unsupported(*location, "An | pattern with bindings", "Bind the value in each arm of its own");
+                    return;
+                }
+                let mut any = Vec::new();
+                for alternative in alternatives {
+                    let mut all = Vec::new();
+                    self.pattern(alternative, value, ty, &mut all);
+                    if all.is_empty() {
+                        return;  // An alternative that always matches
+                    }
+                    any.push(all.join(" && "));
+                }
+                conditions.push(format!("({})", any.join(" || ")));
+            }
+            Pattern::Struct { fields, location, .. } => {
+                for field in fields {
+                    let field_type = self.field_path_type(ty, &[field.field.as_str()], *location);
+                    self.pattern(&field.pattern, &format!("{}.{}", value, c_name(&field.field)), &field_type, conditions);
+                }
+            }
+        }
+    }
+
+    fn field_type(&self, ty: &Type, field: &str) -> Option<Type> {
+        let Type::Struct(name) = ty else { return None };
+        self.structs.get(name)?.fields.iter().find(|f| f.name == field).map(|f| f.ty.clone())
+    }
+
+    fn field_path_type(&mut self, ty: &Type, path: &[&str], location: SourceLocation) -> Type {
+        let mut ty = ty.clone();
+        for field in path {
+            match self.field_type(&ty, field) {
+                Some(field_type) => ty = field_type,
+                None => {
+                    self.unsupported(location, &format!("Field '{}' of '{}'", field, type_str(&ty)), "Only struct fields can be read in C");
+                    return Type::Error;
+                }
+            }
+        }
+        ty
+    }
+
+    fn expression(&mut self, expr: &Expression) -> (String, Type) {
+        match expr {
+            Expression::Literal(literal, _) => {
+                let ty = match literal {
+                    Literal::Int(_, Some(Type::I64)) => Type::I64,
+                    Literal::Int(..) => Type::I32,
+                    Literal::Float(_, Some(Type::F64)) => Type::F64,
+                    Literal::Float(..) => Type::F32,
+                    Literal::Bool(_) => Type::Bool,
+                    Literal::String(_) => Type::String,
+                };
+                (c_literal(literal), ty)
+            }
+            Expression::Variable(name, location) => {
+                if let Some(local) = self.lookup(name) {
+                    return (local.c.clone(), local.ty.clone());
+                }
+                if let Some((ty, _)) = self.consts.get(name) {
+                    return (c_name(name), ty.clone());
+                }
+                if let Some(f) = self.functions.get(name).copied() {
+                    let params = f.params.iter().map(|param| param.ty.clone()).collect();
+                    return (function_name(name), Type::Function(params, Box::new(f.return_type.clone())));
+                }
+                if let Some(f) = self.externs.get(name).copied() {
+                    let params = f.params.iter().map(|param| param.ty.clone()).collect();
+                    return (name.clone(), Type::Function(params, Box::new(f.return_type.clone())));
+                }
+                self.unsupported(*location, &format!("'{}'", name), "It's part of the C++ runtime; pass the value in through an extern fn");
+                (String::new(), Type::Error)
+            }
+            Expression::BinaryOp { op, left, right, location } => {
+                let (l, left_type) = self.expression(left);
+                let (r, right_type) = self.expression(right);
+                let comparison = matches!(op, BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge);
+                let symbol = binary_symbol(op);
+                if matches!(left_type, Type::String) || matches!(right_type, Type::String) {
+                    if comparison {
+                        return (format!("(strcmp({}, {}) {} 0)", l, r, symbol), Type::Bool);
+                    }
+                    self.unsupported(*location, "Joining strings",
+                        "C strings aren't growable; format text with print, or build it through an extern fn");
+                    return (String::new(), Type::String);
+                }
+                if comparison || matches!(op, BinaryOp::And | BinaryOp::Or) {
+                    return (format!("({} {} {})", l, symbol, r), Type::Bool);
+                }
+                let ty = arithmetic_type(&left_type, &right_type);
+                if matches!(op, BinaryOp::Mod) && matches!(ty, Type::F32 | Type::F64) {
+                    self.uses_math = true;
+                    let function = if matches!(ty, Type::F64) { "fmod" } else { "fmodf" };
+                    return (format!("{}({}, {})", function, l, r), ty);
+                }
+                (format!("({} {} {})", l, symbol, r), ty)
+            }
+            Expression::UnaryOp { op, expr, .. } => {
+                let (operand, ty) = self.expression(expr);
+                match op {
+                    UnaryOp::Neg => (format!("-({})", operand), ty),
+                    UnaryOp::Not => (format!("!({})", operand), Type::Bool),
+                }
+            }
+            Expression::Call { name, args, location } => self.call(name, args, *location),
+            Expression::MemberAccess { object, member, location } => {
+                let (object, ty) = self.expression(object);
+                match self.field_type(&ty, member) {
+                    Some(field_type) => (format!("{}.{}", object, c_name(member)), field_type),
+                    None => {
+                        self.unsupported(*location, &format!("Member '{}' of '{}'", member, type_str(&ty)), "Only struct fields can be read in C");
+                        (String::new(), Type::Error)
+                    }
+                }
+            }
+            Expression::Cast { expr, ty, location } => {
+                let (value, _) = self.expression(expr);
+                let c_type = self.declaration(ty, "", *location);
+                (format!("(({}){})", c_type, value), ty.clone())
+            }
+            Expression::If { condition, then_branch, else_branch, location } => {
+                if is_statement_value(expr) {
+                    self.unsupported(*location, "An if expression with statements inside another expression",
+                        "Bind it with let first: let value = if ... { ... } else { ... };");
+                    return (String::new(), Type::Error);
+                }
+                let (condition, _) = self.expression(condition);
+                let (then_value, ty) = self.expression(&then_branch.value);
+                let (else_value, _) = self.expression(&else_branch.value);
+                (format!("({} ? {} : {})", condition, then_value, else_value), ty)
+            }
+            Expression::Block { location, .. } | Expression::Loop { location, .. } | Expression::Match { location, .. } => {
+                self.unsupported(*location, "A block, loop or match value inside another expression",
+                    "Bind it with let first, then use the variable");
+                (String::new(), Type::Error)
+            }
+            Expression::StructLiteral { name, fields, location } => {
+                if !self.structs.contains_key(name) {
+                    self.unsupported(*location, &format!("A '{}' literal", name), "Only structs can be built in C");
+                    return (String::new(), Type::Error);
+                }
+                let values: Vec<String> = fields.iter()
+                    .map(|(field, value)| format!(".{} = {}", c_name(field), self.expression(value).0))
+                    .collect();
+                (format!("({}){{ {} }}", c_name(name), values.join(", ")), Type::Struct(name.clone()))
+            }
+            Expression::LayoutOf { query, ty, field, location } => (self.layout_of(*query, ty, field.as_deref(), *location), Type::I32),
+            Expression::Index { location, .. } | Expression::ArrayLiteral { location, .. } => {
+                let what = if matches!(expr, Expression::Index { .. }) { "Indexing an array" } else { "An array literal" };
+                self.unsupported(*location, what, "Arrays are std::vectors in the C++ runtime; pass a pointer and a length through extern fns");
+                (String::new(), Type::Error)
+            }
+            Expression::StringInterpolation { location, .. } => {
+                self.unsupported(*location, "String interpolation outside print",
+                    "C strings aren't growable; print the values, or format them through an extern fn");
+                (String::new(), Type::String)
+            }
+            Expression::FrameAlloc { location, .. } => {
+                self.unsupported(*location, "The frame arena", "Allocate scratch memory through an extern fn");
+                (String::new(), Type::Error)
+            }
+            Expression::GpuSync { location, .. } => {
+                self.unsupported(*location, "GPU-resident components", "GPU kernels are launched from the C++ runtime; build with --backend=cpp");
+                (String::new(), Type::Error)
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expression], location: SourceLocation) -> (String, Type) {
+        if name == "print" {
+            return (self.print(args), Type::Void);
+        }
+        // A callback parameter or local
+        if let Some(Local { c, ty: Type::Function(_, ret) }) = self.lookup(name) {
+            let (callee, ret) = (c.clone(), (**ret).clone());
+            let args: Vec<String> = args.iter().map(|arg| self.expression(arg).0).collect();
+            return (format!("{}({})", callee, args.join(", ")), ret);
+        }
+        let method = self.interfaces.values()
+            .find_map(|iface| iface.methods.iter().find(|method| method.name == name).map(|method| (*iface, method)));
+        if let Some((iface, method)) = method {
+            return self.method_call(iface, method, args, location);
+        }
+        if let Some(f) = self.functions.get(name).copied() {
+            let mut values = Vec::new();
+            for (index, param) in f.params.iter().enumerate() {
+                match args.get(index).or(param.default.as_ref()) {
+                    Some(arg) => values.push(self.argument(arg, &param.ty)),
+                    None => break,
+                }
+            }
+            return (format!("{}({})", function_name(name), values.join(", ")), f.return_type.clone());
+        }
+        if let Some(f) = self.externs.get(name).copied() {
+            let args: Vec<String> = args.iter().map(|arg| self.expression(arg).0).collect();
+            return (format!("{}({})", name, args.join(", ")), f.return_type.clone());
+        }
+        self.unsupported(location, &format!("'{}'", name),
+            "C code calls the program's own fns, extern fns and print; declare the C function you need with extern fn");
+        (String::new(), Type::Error)
+    }
+
+    // An argument for a parameter of type `param`: a struct passed for an interface parameter
+    // becomes the interface's fat pointer to it
+    fn argument(&mut self, arg: &Expression, param: &Type) -> String {
+        let (value, ty) = self.expression(arg);
+        let Type::Struct(iface) = param else { return value };
+        if !self.interfaces.contains_key(iface) {
+            return value;
+        }
+        match ty {
+            Type::Struct(target) if target != *iface => {
+                if !is_place(arg) {
+                    self.unsupported(arg.location(), &format!("Passing a temporary '{}' as '{}'", target, iface),
+                        "An interface refers to its struct; bind the value to a variable first");
+                }
+                format!("({}){{ &{}, &{} }}", c_name(iface), value, vtable_name(iface, &target))
+            }
+            _ => value,
+        }
+    }
+
+    // apply_damage(d, 5.0): through the vtable for an interface value, straight to the impl for a struct
+    fn method_call(&mut self, iface: &InterfaceDef, method: &InterfaceMethod, args: &[Expression], location: SourceLocation) -> (String, Type) {
+        let Some(receiver) = args.first() else {
+            self.unsupported(location, &format!("Calling '{}' without a receiver", method.name), "Pass the struct as the first argument");
+            return (String::new(), Type::Error);
+        };
+        let (object, ty) = self.expression(receiver);
+        let mut values = Vec::new();
+        for (arg, param) in args.iter().skip(1).zip(method.params.iter().skip(1)) {
+            values.push(self.argument(arg, &param.ty));
+        }
+        let call = match &ty {
+            Type::Struct(name) if *name == iface.name => {
+                values.insert(0, format!("{}.self", object));
+                format!("{}.vtable->{}({})", object, c_name(&method.name), values.join(", "))
+            }
+            Type::Struct(target) => {
+                if !is_place(receiver) {
+                    self.unsupported(receiver.location(), &format!("Calling '{}' on a temporary '{}'", method.name, target),
+                        "Methods take their receiver by reference; bind the value to a variable first");
+                }
+                values.insert(0, format!("&{}", object));
+                format!("{}({})", method_name(&iface.name, target, &method.name), values.join(", "))
+            }
+            _ => {
+                self.unsupported(receiver.location(), &format!("Calling '{}' on '{}'", method.name, type_str(&ty)), "Pass a struct that implements the interface");
+                String::new()
+            }
+        };
+        (call, method.return_type.clone())
+    }
+
+    // print(a, b, ...) writes its arguments back to back and a newline, like the C++ runtime's
+    // `std::cout << a << b << std::endl`; interpolated values are formatted like std::to_string
+    fn print(&mut self, args: &[Expression]) -> String {
+        let mut format = String::new();
+        let mut values = Vec::new();
+        for arg in args {
+            match arg {
+                Expression::Literal(Literal::String(text), _) => format.push_str(&format_text(text)),
+                Expression::StringInterpolation { parts, location } => {
+                    for part in parts {
+                        match part {
+                            StringInterpolationPart::Literal(text) => format.push_str(&format_text(text)),
+                            StringInterpolationPart::Variable(name) => {
+                                let (value, ty) = self.expression(&Expression::Variable(name.clone(), *location));
+                                let spec = match ty {
+                                    Type::F32 | Type::F64 => "%f",
+                                    _ => self.format_spec(&ty, *location),
+                                };
+                                format.push_str(spec);
+                                values.push(value);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    let (value, ty) = self.expression(arg);
+                    format.push_str(self.format_spec(&ty, arg.location()));
+                    values.push(value);
+                }
+            }
+        }
+        let mut call = format!("printf(\"{}\\n\"", format);
+        for value in values {
+            call.push_str(", ");
+            call.push_str(&value);
+        }
+        call.push(')');
+        call
+    }
+
+    fn format_spec(&mut self, ty: &Type, location: SourceLocation) -> &'static str {
+        match ty {
+            Type::I32 => "%\" PRId32 \"",
+            Type::I64 => "%\" PRId64 \"",
+            Type::F32 | Type::F64 => "%g",
+            Type::Bool => "%d",
+            Type::String => "%s",
+            Type::Error => "",
+            _ => {
+                self.unsupported(location, &format!("Printing a '{}'", type_str(ty)), "Print its fields one by one");
+                ""
+            }
+        }
+    }
+
+    // size_of<T>() and friends are the layout model's values, as in C++; C compiles a check that
+    // it agrees (C99 has no alignof to check align_of with)
+    fn layout_of(&mut self, query: LayoutQuery, ty: &Type, field: Option<&str>, location: SourceLocation) -> String {
+        if !self.layouts().is_standard_layout(ty) {
+            self.unsupported(location, &format!("{} of '{}'", query.name(), type_str(ty)),
+                "Its strings are pointers in C, so the layout model's C++ size doesn't apply; use a type of numbers and structs");
+            return "0".to_string();
+        }
+        let layouts = self.layouts();
+        let value = match (query, field) {
+            (LayoutQuery::Offset, Some(field)) => layouts.offset_of(ty, field),
+            (LayoutQuery::Align, _) => layouts.layout(ty).map(|layout| layout.align),
+            _ => layouts.layout(ty).map(|layout| layout.size),
+        };
+        let Ok(value) = value else {
+            return format!("/* {}: no layout */ 0", query.name());
+        };
+        let c_type = self.declaration(ty, "", location);
+        let c_query = match (query, field) {
+            (LayoutQuery::Offset, Some(field)) => format!("offsetof({}, {})", c_type, c_name(field)),
+            (LayoutQuery::Align, _) => return value.to_string(),
+            _ => format!("sizeof({})", c_type),
+        };
+        let check = format!("typedef char heidic_layout_check_{}[({} == {}) ? 1 : -1];\n", self.layout_checks.len() + 1, c_query, value);
+        if !self.layout_checks.iter().any(|existing| existing.ends_with(&check[check.find('[').unwrap_or(0)..])) {
+            self.layout_checks.push(check);
+        }
+        value.to_string()
+    }
+}
+
+fn c_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Int(n, Some(Type::I64)) => format!("INT64_C({})", n),
+        Literal::Int(n, _) => n.to_string(),
+        Literal::Float(n, Some(Type::F64)) => format!("{:?}", n),
+        Literal::Float(n, _) => format!("{:?}f", n),
+        Literal::Bool(b) => b.to_string(),
+        Literal::String(s) => c_string(s),
+    }
+}
+
+fn binary_symbol(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Eq => "==",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Le => "<=",
+        BinaryOp::Gt => ">",
+        BinaryOp::Ge => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+    }
+}
+
+// C's usual arithmetic conversions, for the number types HEIDIC has
+fn arithmetic_type(left: &Type, right: &Type) -> Type {
+    for ty in [Type::F64, Type::F32, Type::I64] {
+        if std::mem::discriminant(left) == std::mem::discriminant(&ty) || std::mem::discriminant(right) == std::mem::discriminant(&ty) {
+            return ty;
+        }
+    }
+    left.clone()
+}
+
+// A variable or one of its fields: something whose address can be taken
+fn is_place(expr: &Expression) -> bool {
+    match expr {
+        Expression::Variable(..) => true,
+        Expression::MemberAccess { object, .. } => is_place(object),
+        _ => false,
+    }
+}
+
+// A value C can only compute with statements: assigned by assign(), not written by expression()
+fn is_statement_value(expr: &Expression) -> bool {
+    match expr {
+        Expression::Block { .. } | Expression::Loop { .. } | Expression::Match { .. } => true,
+        Expression::If { then_branch, else_branch, .. } => {
+            [then_branch, else_branch].iter().any(|branch| !branch.statements.is_empty() || is_statement_value(&branch.value))
+        }
+        _ => false,
+    }
+}
+
+// Statements after which the end of the scope isn't reached
+fn ends_scope(statements: &[Statement]) -> bool {
+    matches!(statements.last(), Some(Statement::Return(..) | Statement::Break(..) | Statement::Continue(_)))
+}
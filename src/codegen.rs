@@ -1,10 +1,656 @@
 use crate::ast::*;
+use crate::cpp_style::EmitStyle;
+use crate::error::SourceLocation;
+use crate::cuda;
+use crate::vulkan_compute;
+use crate::wasm;
+use crate::frame_arena;
+use crate::generated_names;
+use crate::hierarchy;
+use crate::input_map;
+use crate::spatial_audio;
+use crate::layout::{LayoutEngine, TargetAbi};
+use crate::replication;
+use crate::resources;
+use crate::save_schema;
+use crate::shaders::{self, CompilerKind};
+use crate::scene;
+use crate::schedule::{self, EntryArgument};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-pub struct CodeGenerator {
+// Hot system DLL ABI version (major.minor). Bump MAJOR when the calling convention or
+// descriptor format changes; bump MINOR for additive changes older DLLs remain compatible with.
+const HOT_ABI_VERSION_MAJOR: u32 = 1;
+const HOT_ABI_VERSION_MINOR: u32 = 0;
+
+// The hot system holding the program's top-level @hot fns (built as hot_functions.dll)
+pub const HOT_FUNCTIONS_MODULE: &str = "hot_functions";
+
+// stdlib/ headers version the generated code is written against (stdlib/version.h). Keep
+// MAJOR in step with the headers; raise MINOR when codegen starts using something new in them.
+pub const STDLIB_VERSION_MAJOR: u32 = 1;
+pub const STDLIB_VERSION_MINOR: u32 = 34;
+
+// FNV-1a 64-bit hash (stable across compiler runs, unlike std's DefaultHasher)
+pub fn fnv1a_64(data: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// The component an assignment inside `for entity in q` writes: entity.Velocity.x, entity.Velocity
+fn written_component<'a>(target: &'a Expression, entity_name: &str) -> Option<&'a str> {
+    match target {
+        Expression::MemberAccess { object, member, .. } => match object.as_ref() {
+            Expression::Variable(name, _) if name == entity_name => Some(member),
+            object => written_component(object, entity_name),
+        },
+        Expression::Index { array, .. } => written_component(array, entity_name),
+        _ => None,
+    }
+}
+
+// Compile-time check that the C++ compiler lays a type out the way the layout model says
+fn layout_check(cpp_query: &str, value: usize) -> String {
+    format!("static_assert({} == {}, \"HEIDIC layout model disagrees with the C++ compiler (check --target-abi)\");\n", cpp_query, value)
+}
+
+// The C++ members of a component: its fields, except that a component_soa's @cold fields are
+// one `heidic_cold` array of its Cold struct after the hot field arrays
+fn component_members(c: &ComponentDef) -> Vec<Field> {
+    if !c.has_cold_fields() {
+        return c.fields.clone();
+    }
+    let mut members: Vec<Field> = c.fields.iter().filter(|f| !f.cold).cloned().collect();
+    let cold = Type::Array(Box::new(Type::Struct(format!("{}::Cold", c.name))));
+    members.push(Field { name: "heidic_cold".to_string(), ty: cold, cold: false, doc: None, range: None, unit: None });
+    members
+}
+
+// Element `index` of a component_soa field: x[i], or heidic_cold[i].x for a @cold field
+// The components a query's arrays hold: its types other than filters
+fn query_components(component_types: &[Type]) -> Vec<&str> {
+    component_types.iter().filter_map(|ty| match ty {
+        Type::Component(name) | Type::Struct(name) => Some(name.as_str()),
+        _ => None,
+    }).collect()
+}
+
+// The function that gathers a query type's entities from g_storage
+fn query_gather_function(query: &str) -> String {
+    format!("heidic_gather_{}", query)
+}
+
+fn soa_element(c: &ComponentDef, field: &str, index: &str) -> String {
+    if c.fields.iter().any(|f| f.name == field && f.cold) {
+        format!("heidic_cold[{}].{}", index, field)
+    } else {
+        format!("{}[{}]", field, index)
+    }
+}
+
+// Literals carry their HEIDIC type into C++: unsuffixed floats are f32 (1.0f, never the
+// int `1` or a double), f64 literals stay doubles, and i64 literals are widened explicitly
+// A C++ string literal holding `text`
+pub(crate) fn cpp_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+// `heidic_match.hit.point`: a field path from a match or destructure pattern
+fn field_path(base: &str, path: &[&str]) -> String {
+    std::iter::once(base).chain(path.iter().copied()).collect::<Vec<_>>().join(".")
+}
+
+fn literal_to_cpp(lit: &Literal) -> String {
+    match lit {
+        Literal::Int(n, Some(Type::I64)) => format!("static_cast<int64_t>({})", n),
+        Literal::Int(n, _) => n.to_string(),
+        Literal::Float(n, Some(Type::F64)) => format!("{:?}", n),
+        Literal::Float(n, _) => format!("{:?}f", n),
+        Literal::Bool(b) => b.to_string(),
+        Literal::String(s) => format!("\"{}\"", s),
+    }
+}
+
+
+// The queue an event type's `emit`s go to
+fn event_queue(event: &str) -> String {
+    format!("heidic_events_{}", event)
+}
+
+// C++ keywords a migration block can't use as a variable name; `from v1(old, new)` is the usual spelling
+const CPP_KEYWORDS: &[&str] = &["new", "delete", "this", "class", "template", "operator", "namespace", "typename"];
+
+// `on Collision(ev) { ... }` in system Physics becomes heidic_Physics_on_Collision(Collision ev)
+fn event_handler_function(system: &SystemDef, handler: &EventHandler) -> FunctionDef {
+    FunctionDef {
+        name: format!("heidic_{}_on_{}", system.name, handler.event),
+        params: vec![Param {
+            name: handler.param.clone(),
+            ty: Type::Struct(handler.event.clone()),
+            default: None,
+            mutable: false,
+            location: handler.location,
+        }],
+        return_type: Type::Void,
+        body: handler.body.clone(),
+        cuda_kernel: None,
+        no_reload_check: false,
+        is_hot: false,
+        strict_fp: system.strict_fp,
+    }
+}
+
+/// C++ class of a `resource` type; unknown types are used as-is (might be custom)
+pub fn cpp_resource_type(resource_type: &str) -> &str {
+    match resource_type {
+        "Texture" => "TextureResource",
+        "Mesh" => "MeshResource",
+        "Sound" | "Music" => "AudioResource",
+        "Video" => "VideoResource",
+        _ => resource_type,
+    }
+}
+
+// The HeidicAudioMixer gain an audio resource plays at: Music on the music channel, Sound on the sound one
+fn audio_channel_gain(res: &ResourceDef) -> &'static str {
+    if res.resource_type == "Music" { "musicGain" } else { "soundGain" }
+}
+
+// Every function body in the program: free fns, system fns, impl methods and migrate functions
+fn program_functions(program: &Program) -> impl Iterator<Item = &FunctionDef> {
+    program.items.iter().flat_map(|item| -> Box<dyn Iterator<Item = &FunctionDef> + '_> {
+        match item {
+            Item::Function(f) => Box::new(std::iter::once(f)),
+            Item::Migrate(m) => Box::new(std::iter::once(&m.function)),
+            Item::System(s) => Box::new(s.functions.iter()),
+            Item::Impl(i) => Box::new(i.methods.iter().map(|(f, _)| f)),
+            _ => Box::new(std::iter::empty()),
+        }
+    })
+}
+
+// The query<...> types of the program's fn parameters
+fn program_queries(program: &Program) -> impl Iterator<Item = &Vec<Type>> {
+    program_functions(program).flat_map(|f| &f.params).filter_map(|param| match &param.ty {
+        Type::Query(component_types) => Some(component_types),
+        _ => None,
+    })
+}
+
+// Whether any function, method or test in the program calls one of `names`
+fn program_calls(program: &Program, names: &[&str]) -> bool {
+    program.items.iter().any(|item| item_calls(item, names))
+}
+
+/// Whether a function, system, impl, test or migrate item calls one of `names`
+pub fn item_calls(item: &Item, names: &[&str]) -> bool {
+    match item {
+        Item::Function(f) => statements_call(&f.body, names),
+        Item::System(s) => s.functions.iter().any(|f| statements_call(&f.body, names))
+            || s.handlers.iter().any(|h| statements_call(&h.body, names)),
+        Item::Impl(i) => i.methods.iter().any(|(f, _)| statements_call(&f.body, names)),
+        Item::Test(t) => statements_call(&t.body, names),
+        Item::Migrate(m) => statements_call(&m.function.body, names),
+        _ => false,
+    }
+}
+
+fn statements_call(statements: &[Statement], names: &[&str]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Let { value, .. } | Statement::Expression(value, _) | Statement::Return(Some(value), _)
+        | Statement::Break(Some(value), _) | Statement::Destructure { value, .. } => expression_calls(value, names),
+        Statement::Defer(value, _) => expression_calls(value, names),
+        Statement::Emit(event, _) => expression_calls(event, names),
+        Statement::Assign { target, value, .. } => expression_calls(target, names) || expression_calls(value, names),
+        Statement::If { condition, then_block, else_block, .. } => {
+            expression_calls(condition, names) || statements_call(then_block, names)
+                || else_block.as_ref().is_some_and(|block| statements_call(block, names))
+        }
+        Statement::While { condition: value, body, .. } => expression_calls(value, names) || statements_call(body, names),
+        // Chunked iteration counts as a call of "chunks", as in q.chunks(1024)
+        Statement::For { collection, chunk_size, body, .. } => {
+            (chunk_size.is_some() && names.contains(&"chunks")) || expression_calls(collection, names)
+                || chunk_size.as_ref().is_some_and(|size| expression_calls(size, names)) || statements_call(body, names)
+        }
+        Statement::Loop { body, .. } | Statement::Block(body, _) => statements_call(body, names),
+        // Counts as a call of "spawn_batch", since it needs the same runtime support as one
+        Statement::SpawnBatch { count, body, .. } => {
+            names.contains(&"spawn_batch") || expression_calls(count, names) || statements_call(body, names)
+        }
+        Statement::Return(None, _) | Statement::Break(None, _) | Statement::Continue(_) => false,
+    })
+}
+
+// Functions that run inside some loop: called from a loop body, called by a function that runs
+// inside a loop, or run by the frame itself (scheduled systems, event handlers). Hot-reload checks
+// go in the outermost loops of the other functions only.
+fn inner_loop_functions(program: &Program) -> HashSet<String> {
+    let mut callers: Vec<(&str, Vec<(&str, bool)>)> = Vec::new();
+    let mut inner: HashSet<String> = HashSet::new();
+    for item in &program.items {
+        match item {
+            Item::System(s) => {
+                if s.schedule.is_some() {
+                    inner.extend(s.functions.iter().map(|f| f.name.clone()));
+                }
+                inner.extend(s.handlers.iter().map(|h| event_handler_function(s, h).name));
+                for handler in &s.handlers {
+                    callers.push(("", statement_calls(&handler.body, false)));
+                }
+            }
+            Item::Test(t) => callers.push(("", statement_calls(&t.body, false))),
+            _ => {}
+        }
+    }
+    callers.extend(program_functions(program).map(|f| (f.name.as_str(), statement_calls(&f.body, false))));
+    for (_, calls) in &callers {
+        inner.extend(calls.iter().filter(|(_, in_loop)| *in_loop).map(|(name, _)| name.to_string()));
+    }
+    // Everything an inner function calls runs inside the same loop
+    let mut pending: Vec<String> = inner.iter().cloned().collect();
+    while let Some(name) = pending.pop() {
+        for (_, calls) in callers.iter().filter(|(caller, _)| *caller == name) {
+            for (callee, _) in calls {
+                if inner.insert(callee.to_string()) {
+                    pending.push(callee.to_string());
+                }
+            }
+        }
+    }
+    inner
+}
+
+// Functions called by `statements`, and whether each call is inside a loop
+fn statement_calls(statements: &[Statement], in_loop: bool) -> Vec<(&str, bool)> {
+    let mut calls = Vec::new();
+    for statement in statements {
+        match statement {
+            Statement::Let { value, .. } | Statement::Expression(value, _) | Statement::Return(Some(value), _)
+            | Statement::Break(Some(value), _) | Statement::Emit(value, _)
+            | Statement::Destructure { value, .. } => expression_call_names(value, in_loop, &mut calls),
+            Statement::Defer(value, _) => expression_call_names(value, in_loop, &mut calls),
+            Statement::Assign { target, value, .. } => {
+                expression_call_names(target, in_loop, &mut calls);
+                expression_call_names(value, in_loop, &mut calls);
+            }
+            Statement::If { condition, then_block, else_block, .. } => {
+                expression_call_names(condition, in_loop, &mut calls);
+                calls.extend(statement_calls(then_block, in_loop));
+                calls.extend(else_block.iter().flat_map(|block| statement_calls(block, in_loop)));
+            }
+            Statement::While { condition, body, .. } => {
+                expression_call_names(condition, true, &mut calls);
+                calls.extend(statement_calls(body, true));
+            }
+            Statement::For { collection, chunk_size, body, .. } => {
+                expression_call_names(collection, in_loop, &mut calls);
+                if let Some(size) = chunk_size {
+                    expression_call_names(size, in_loop, &mut calls);
+                }
+                calls.extend(statement_calls(body, true));
+            }
+            Statement::Loop { body, .. } => calls.extend(statement_calls(body, true)),
+            Statement::SpawnBatch { count, body, .. } => {
+                expression_call_names(count, in_loop, &mut calls);
+                calls.extend(statement_calls(body, true));
+            }
+            Statement::Block(body, _) => calls.extend(statement_calls(body, in_loop)),
+            Statement::Return(None, _) | Statement::Break(None, _) | Statement::Continue(_) => {}
+        }
+    }
+    calls
+}
+
+fn expression_call_names<'a>(expr: &'a Expression, in_loop: bool, calls: &mut Vec<(&'a str, bool)>) {
+    match expr {
+        Expression::Call { name, args, .. } => {
+            calls.push((name, in_loop));
+            args.iter().for_each(|arg| expression_call_names(arg, in_loop, calls));
+        }
+        Expression::BinaryOp { left, right, .. } => {
+            expression_call_names(left, in_loop, calls);
+            expression_call_names(right, in_loop, calls);
+        }
+        Expression::UnaryOp { expr, .. } | Expression::Cast { expr, .. } | Expression::MemberAccess { object: expr, .. } => {
+            expression_call_names(expr, in_loop, calls)
+        }
+        Expression::Index { array, index, .. } => {
+            expression_call_names(array, in_loop, calls);
+            expression_call_names(index, in_loop, calls);
+        }
+        Expression::ArrayLiteral { elements, .. } => elements.iter().for_each(|e| expression_call_names(e, in_loop, calls)),
+        Expression::Match { expr, arms, .. } => {
+            expression_call_names(expr, in_loop, calls);
+            for arm in arms {
+                if let Some(guard) = &arm.guard {
+                    expression_call_names(guard, in_loop, calls);
+                }
+                calls.extend(statement_calls(&arm.body, in_loop));
+                if let Some(value) = &arm.value {
+                    expression_call_names(value, in_loop, calls);
+                }
+            }
+        }
+        Expression::If { condition, then_branch, else_branch, .. } => {
+            expression_call_names(condition, in_loop, calls);
+            for block in [then_branch, else_branch] {
+                calls.extend(statement_calls(&block.statements, in_loop));
+                expression_call_names(&block.value, in_loop, calls);
+            }
+        }
+        Expression::Block { body, .. } => {
+            calls.extend(statement_calls(&body.statements, in_loop));
+            expression_call_names(&body.value, in_loop, calls);
+        }
+        Expression::Loop { body, .. } => calls.extend(statement_calls(body, true)),
+        Expression::StructLiteral { fields, .. } => fields.iter().for_each(|(_, value)| expression_call_names(value, in_loop, calls)),
+        Expression::FrameAlloc { arena, count, .. } => {
+            expression_call_names(arena, in_loop, calls);
+            expression_call_names(count, in_loop, calls);
+        }
+        Expression::LayoutOf { .. } | Expression::GpuSync { .. } | Expression::StringInterpolation { .. } | Expression::Literal(..) | Expression::Variable(..) => {}
+    }
+}
+
+// A break that leaves the loop around `statements` (not one inside a loop of its own)
+fn breaks_enclosing_loop(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Break(..) => true,
+        Statement::If { then_block, else_block, .. } => {
+            breaks_enclosing_loop(then_block) || else_block.as_ref().is_some_and(|block| breaks_enclosing_loop(block))
+        }
+        Statement::Block(body, _) => breaks_enclosing_loop(body),
+        Statement::Expression(Expression::Match { arms, .. }, _) => arms.iter().any(|arm| breaks_enclosing_loop(&arm.body)),
+        _ => false,
+    })
+}
+
+fn expression_calls(expr: &Expression, names: &[&str]) -> bool {
+    match expr {
+        Expression::Call { name, args, .. } => {
+            names.contains(&name.as_str()) || args.iter().any(|arg| expression_calls(arg, names))
+        }
+        Expression::BinaryOp { left, right, .. } => expression_calls(left, names) || expression_calls(right, names),
+        Expression::UnaryOp { expr, .. } | Expression::Cast { expr, .. } => expression_calls(expr, names),
+        Expression::MemberAccess { object, .. } => expression_calls(object, names),
+        Expression::Index { array, index, .. } => expression_calls(array, names) || expression_calls(index, names),
+        Expression::ArrayLiteral { elements, .. } => elements.iter().any(|e| expression_calls(e, names)),
+        Expression::Match { expr, arms, .. } => {
+            expression_calls(expr, names) || arms.iter().any(|arm| {
+                arm.guard.as_ref().is_some_and(|guard| expression_calls(guard, names))
+                    || statements_call(&arm.body, names) || arm.value.as_ref().is_some_and(|value| expression_calls(value, names))
+            })
+        }
+        Expression::If { condition, then_branch, else_branch, .. } => {
+            expression_calls(condition, names) || [then_branch, else_branch].iter().any(|block| {
+                statements_call(&block.statements, names) || expression_calls(&block.value, names)
+            })
+        }
+        Expression::Block { body, .. } => statements_call(&body.statements, names) || expression_calls(&body.value, names),
+        Expression::Loop { body, .. } => statements_call(body, names),
+        Expression::StructLiteral { fields, .. } => fields.iter().any(|(_, value)| expression_calls(value, names)),
+        Expression::LayoutOf { query, .. } => names.contains(&query.name()),
+        Expression::GpuSync { direction, .. } => names.contains(&direction.name()),
+        // Counts as a call of "alloc_array", as in frame.alloc_array<Vec3>(100)
+        Expression::FrameAlloc { arena, count, .. } => {
+            names.contains(&frame_arena::METHOD) || expression_calls(arena, names) || expression_calls(count, names)
+        }
+        Expression::StringInterpolation { .. } | Expression::Literal(..) | Expression::Variable(..) => false,
+    }
+}
+
+// A component layout load_world reads: the component's own fields, or an old struct a migrate fn
+// turns into the component
+struct WorldLoadBlock<'a> {
+    component: &'a str,
+    descriptor: String,       // Saved field names and types it matches, as a std::vector<std::string>
+    value_type: String,       // Type each entry is read into
+    members: Vec<String>,     // Members of value_type, in saved order
+    migrate: Option<String>,  // Migrate function from value_type to the component
+}
+
+/// `heidic_v2 test`: generate a test runner in place of the program's main
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TestOptions {
+    pub render: bool,         // --render: run tests that render frames (offscreen)
+    pub update_golden: bool,  // --update-golden: expect_frame writes its reference image
+}
+
+/// --hot-rebuild: the running program compiles its hot DLLs itself when their sources change
+#[derive(Debug, Clone)]
+pub struct HotRebuild {
+    pub compiler: String,    // C++ compiler command (heidic.toml [hot_reload] compiler)
+    pub flags: Vec<String>,  // Extra compiler flags ([hot_reload] flags)
+    pub heidic: String,      // heidic compiler that regenerates the DLL sources from the .hd source
+    pub source: String,      // The .hd source, relative to the directory the program runs in
+}
+
+/// --ecs-storage: the EntityStorage backend g_storage uses (stdlib/entity_storage.h)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EcsStorage {
+    #[default]
+    SparseSet,  // One sparse set per component type: cheap adds and removes
+    Archetype,  // Entities grouped by component set into contiguous columns: linear iteration
+}
+
+impl EcsStorage {
+    pub const NAMES: &'static [&'static str] = &["sparse", "archetype"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sparse" => Some(EcsStorage::SparseSet),
+            "archetype" => Some(EcsStorage::Archetype),
+            _ => None,
+        }
+    }
+}
+
+/// --gpu-backend: what @[launch] functions compile to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GpuBackend {
+    #[default]
+    Cuda,    // CUDA kernels in <file>.cu, built by nvcc
+    Vulkan,  // GLSL compute shaders dispatched on the engine's Vulkan device (vulkan_compute.rs)
+}
+
+impl GpuBackend {
+    pub const NAMES: &'static [&'static str] = &["cuda", "vulkan"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cuda" => Some(GpuBackend::Cuda),
+            "vulkan" => Some(GpuBackend::Vulkan),
+            _ => None,
+        }
+    }
+}
+
+/// --runtime: what generated code may assume of the platform it runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuntimeProfile {
+    #[default]
+    Standard,  // iostream, std::unordered_map, exceptions, Vulkan/GLFW/ImGui headers
+    Minimal,   // None of those (consoles, WASM, embedded): stdlib/minimal_runtime.h
+}
+
+impl RuntimeProfile {
+    pub const NAMES: &'static [&'static str] = &["standard", "minimal"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(RuntimeProfile::Standard),
+            "minimal" => Some(RuntimeProfile::Minimal),
+            _ => None,
+        }
+    }
+}
+
+/// --target: the platform the generated C++ is built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Target {
+    #[default]
+    Native,  // The host's C++ compiler, with Vulkan, GLFW and DLL hot reload
+    Wasm,    // Emscripten: WebGPU and the page's canvas (wasm.rs), reloads driven by the page harness
+}
+
+impl Target {
+    pub const NAMES: &'static [&'static str] = &["native", "wasm"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "native" => Some(Target::Native),
+            "wasm" => Some(Target::Wasm),
+            _ => None,
+        }
+    }
+}
+
+// Calls whose generated code or headers need the standard runtime (files, sockets, the GPU)
+const STANDARD_RUNTIME_CALLS: &[(&str, &str)] = &[
+    ("save_world", "save files are written with <fstream>"),
+    ("load_world", "save files are read with <fstream>"),
+    ("screenshot", "frame capture reads back a Vulkan image"),
+    ("readback", "GPU buffers need Vulkan"),
+    ("upload", "GPU buffers need Vulkan"),
+    ("tweak_panel", "the tweak editor draws with ImGui"),
+    ("tweak_file", "tweak files are written with <fstream>"),
+];
+
+fn is_platform_type(ty: &Type) -> bool {
+    match ty {
+        Type::VkInstance | Type::VkDevice | Type::VkResult | Type::VkPhysicalDevice | Type::VkQueue
+        | Type::VkCommandPool | Type::VkCommandBuffer | Type::VkSwapchainKHR | Type::VkSurfaceKHR
+        | Type::VkRenderPass | Type::VkPipeline | Type::VkFramebuffer | Type::VkBuffer | Type::VkImage
+        | Type::VkImageView | Type::VkSemaphore | Type::VkFence | Type::GLFWwindow | Type::GLFWbool => true,
+        Type::Array(inner) | Type::Optional(inner) => is_platform_type(inner),
+        Type::Function(params, ret) => params.iter().any(is_platform_type) || is_platform_type(ret),
+        _ => false,
+    }
+}
+
+/// What `--runtime minimal` can't generate, as (item index, message, suggestion): items and calls
+/// built on the engine's loaders, hot reload, Vulkan, GLFW, ImGui, files or sockets
+pub fn minimal_runtime_conflicts(program: &Program) -> Vec<(usize, String, String)> {
+    let mut conflicts = Vec::new();
+    let defined: HashSet<&str> = program_functions(program).map(|f| f.name.as_str()).collect();
+    let vulkan = "--runtime minimal leaves out Vulkan; build the renderer against the standard runtime".to_string();
+    let hot = "Hot reload loads DLLs and reports through iostream; drop @hot for this build".to_string();
+    for (index, item) in program.items.iter().enumerate() {
+        let mut conflict = |message: String, suggestion: &str| conflicts.push((index, message, suggestion.to_string()));
+        let bodies: Vec<&[Statement]> = match item {
+            Item::Function(f) => vec![&f.body],
+            Item::System(s) => s.functions.iter().map(|f| f.body.as_slice()).chain(s.handlers.iter().map(|h| h.body.as_slice())).collect(),
+            Item::Impl(i) => i.methods.iter().map(|(f, _)| f.body.as_slice()).collect(),
+            Item::Migrate(m) => vec![&m.function.body],
+            _ => Vec::new(),
+        };
+        match item {
+            Item::Resource(r) => conflict(format!("resource '{}' isn't available with --runtime minimal", r.name),
+                "Resources load through the engine's asset loaders, which use exceptions and iostream; load assets with extern fns"),
+            Item::Pipeline(p) => conflict(format!("pipeline '{}' isn't available with --runtime minimal", p.name), &vulkan),
+            Item::Shader(shader) => conflict(format!("shader '{}' isn't available with --runtime minimal", shader.path), &vulkan),
+            Item::Gpu(_) => conflict("gpu { ... } isn't available with --runtime minimal".to_string(), &vulkan),
+            Item::Window(_) => conflict("window { ... } isn't available with --runtime minimal".to_string(), &vulkan),
+            Item::GpuQuery(q) => conflict(format!("gpu_query '{}' isn't available with --runtime minimal", q.name), &vulkan),
+            Item::Uniform(u) => conflict(format!("uniform '{}' isn't available with --runtime minimal", u.name), &vulkan),
+            Item::Input(_) => conflict("input { ... } isn't available with --runtime minimal".to_string(),
+                "Input actions read the keyboard, mouse and gamepads through GLFW; poll them with extern fns"),
+            Item::SaveSchema(schema) => conflict(format!("save_schema '{}' isn't available with --runtime minimal", schema.name),
+                "Save files are read and written with <fstream>; serialize through extern fns instead"),
+            Item::Const(c) if c.is_tweak => conflict(format!("@tweak const '{}' isn't available with --runtime minimal", c.name),
+                "The tweak editor uses ImGui and <fstream>; make it a plain const"),
+            Item::Component(c) if c.is_hot => conflict(format!("@hot component '{}' isn't available with --runtime minimal", c.name), &hot),
+            Item::Component(c) if c.is_replicated => conflict(format!("@[replicated] component '{}' isn't available with --runtime minimal", c.name),
+                "Replication runs over the standard runtime's sockets"),
+            Item::System(s) if s.is_hot => conflict(format!("@hot system '{}' isn't available with --runtime minimal", s.name), &hot),
+            Item::Function(f) if f.is_hot => conflict(format!("@hot fn '{}' isn't available with --runtime minimal", f.name), &hot),
+            _ => {}
+        }
+        let signatures: Vec<(&str, Vec<&Type>)> = match item {
+            Item::Function(f) => vec![(f.name.as_str(), f.params.iter().map(|p| &p.ty).chain([&f.return_type]).collect())],
+            Item::ExternFunction(f) => vec![(f.name.as_str(), f.params.iter().map(|p| &p.ty).chain([&f.return_type]).collect())],
+            Item::Struct(st) => vec![(st.name.as_str(), st.fields.iter().map(|field| &field.ty).collect())],
+            Item::Component(c) => vec![(c.name.as_str(), c.fields.iter().map(|field| &field.ty).collect())],
+            _ => Vec::new(),
+        };
+        for (name, types) in signatures {
+            if types.into_iter().any(is_platform_type) {
+                conflict(format!("'{}' uses Vulkan or GLFW types, which --runtime minimal leaves out", name),
+                    "Keep platform handles in the platform layer and pass plain values or ids across extern fns");
+            }
+        }
+        let mut reported = HashSet::new();
+        for (call, _) in bodies.iter().flat_map(|body| statement_calls(body, false)) {
+            if defined.contains(call) || !reported.insert(call) {
+                continue;
+            }
+            let reason = STANDARD_RUNTIME_CALLS.iter().find(|(name, _)| *name == call).map(|(_, reason)| *reason)
+                .or_else(|| replication::FUNCTIONS.contains(&call).then_some("replication runs over sockets"))
+                .or_else(|| call.starts_with("glfw").then_some("GLFW is left out"))
+                .or_else(|| call.starts_with("ImGui").then_some("ImGui is left out"));
+            if let Some(reason) = reason {
+                conflict(format!("'{}' isn't available with --runtime minimal: {}", call, reason),
+                    "Call it from a build with the standard runtime, or through an extern fn the platform layer provides");
+            }
+        }
+    }
+    conflicts
+}
+
+/// A backend: turns a checked program into one source file for the platform's compiler
+pub trait CodeGenerator {
+    fn generate(&mut self, program: &Program) -> Result<String>;
+
+    /// Extension of the generated file: "cpp" or "c"
+    fn extension(&self) -> &'static str;
+
+    /// Names the hd_<program>_ prefix of the program's symbols (generated_names::mangle)
+    fn set_program_name(&mut self, name: &str);
+
+    fn symbol_prefix(&self) -> &str;
+
+    /// Whether the generated code needs -ffp-contract=off (set by generate)
+    fn uses_strict_fp(&self) -> bool;
+}
+
+/// --backend: the language the program is generated in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    #[default]
+    Cpp,  // C++17 (CppGenerator), with the full runtime
+    C,    // C99 (c_codegen::CGenerator), for toolchains without C++: the core language only
+}
+
+impl Backend {
+    pub const NAMES: &'static [&'static str] = &["cpp", "c"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cpp" => Some(Backend::Cpp),
+            "c" => Some(Backend::C),
+            _ => None,
+        }
+    }
+}
+
+pub struct CppGenerator {
     components: HashMap<String, ComponentDef>,  // Store component metadata for SOA detection
-    hot_systems: Vec<SystemDef>,  // Store hot-reloadable systems
+    structs: HashMap<String, StructDef>,  // Struct definitions for the layout model
+    migrations: Vec<MigrateDef>,  // migrate functions, used by the @hot component migration and load_world
+    layout_checks: Vec<String>,  // static_asserts that the C++ compiler agrees with the layout model
+    target_abi: TargetAbi,  // --target-abi: standard library the layout model assumes
+    ecs_storage: EcsStorage,  // --ecs-storage: backend of g_storage
+    gpu_backend: GpuBackend,  // --gpu-backend: CUDA kernels or Vulkan compute shaders for @[launch] functions
+    runtime: RuntimeProfile,  // --runtime: standard or minimal runtime headers
+    target: Target,  // --target: native or browser (WebGPU/canvas) headers and reload hooks
+    hot_systems: Vec<SystemDef>,  // Store hot-reloadable systems (top-level @hot fns as one more, see HOT_FUNCTIONS_MODULE)
+    hot_dll: Option<String>,  // The hot system whose DLL is being generated
+    dll_dir: String,  // Where hot DLLs are loaded from, relative to the program's directory ("" beside it)
     hot_shaders: Vec<ShaderDef>,  // Store hot-reloadable shaders
     hot_components: Vec<ComponentDef>,  // Store hot-reloadable components
     has_resources: bool,  // Track if program has resource declarations
@@ -12,14 +658,58 @@ pub struct CodeGenerator {
     image_resources: Vec<ResourceDef>,  // Store Image resources for bindless integration
     cuda_functions: Vec<FunctionDef>,  // Store functions with @[launch] attribute
     cuda_components: Vec<ComponentDef>,  // Store components with @[cuda] attribute
+    extern_functions: Vec<ExternFunctionDef>,  // extern fn declarations (array arguments are passed as pointers)
+    consts: Vec<ConstDef>,  // Top-level consts (also emitted into @hot system DLLs)
+    scheduled_systems: Vec<SystemDef>,  // Systems run by run_systems(), in run order
+    resource_types: Vec<String>,  // Types taken as res<T>, each with one generated instance
+    builtin_resources: Vec<StructDef>,  // Built-in resource types (Time, Input) the program uses
+    events: Vec<EventDef>,  // Event types, each with a queue drained by its `on` handlers
+    scenes: Vec<SceneDef>,  // Scene files with entities to spawn at startup
+    chunk_iterators: Vec<String>,  // Iterators of the enclosing `for chunk in q.chunks(n)` loops
+    query_params: HashMap<String, Vec<Type>>,  // Query parameters of the function being generated
+    changed_components: HashSet<String>,  // Components some query filters with changed<T>; writes to them are tracked
+    uses_hierarchy: bool,  // Entities have parents (stdlib/hierarchy.h); query loops name their entities
+    spatial_sounds: Vec<ResourceDef>,  // @[spatial] sounds, placed by entity Positions; query loops name their entities
+    reload_checks: bool,  // The next while loop gets hot-reload checks and a frame arena scope (outermost loops only)
+    uses_frame_arena: bool,  // frame.alloc_array allocates from g_frame_arena (stdlib/frame_arena.h)
+    uses_gpu_sync: bool,  // sync_to_gpu/sync_to_cpu are called: @[cuda] components live in device mirrors
+    cuda_query: Option<String>,  // Query parameter of the CUDA kernel being generated: its loop is spread over threads
+    inner_loop_functions: HashSet<String>,  // Functions that run inside a loop: their loops get no reload checks
     defer_counter: usize,  // Counter for generating unique defer variable names
+    function_params: HashMap<String, Vec<Param>>,  // Parameter lists (with defaults) for call-site expansion
+    gpu_config: Option<GpuConfig>,  // gpu { ... } device selection block
+    window_config: Option<WindowConfig>,  // window { ... } block opened by open_window()
+    interfaces: HashMap<String, InterfaceDef>,  // Interfaces (parameters of these types are monomorphized)
+    impls: Vec<ImplDef>,  // impl Interface for Component blocks
+    gpu_queries: Vec<GpuQueryDef>,  // gpu_query pools
+    gpu_buffers: Vec<(String, String)>,  // Named storage bindings (name, element type), one buffer per name
+    uniforms: Vec<UniformDef>,  // uniform blocks, each a std140 struct and a host-visible buffer
+    input: Option<InputDef>,  // input { ... } actions, updated at the start of run_systems()
+    emit_style: EmitStyle,  // --emit-readable / --emit-compact
+    test_options: Option<TestOptions>,  // Some when building the test runner
+    hot_rebuild: Option<HotRebuild>,  // Some when the program rebuilds its hot DLLs (--hot-rebuild)
+    symbol_prefix: String,  // hd_<program>_: prefix of the program's globals and functions (generated_names::mangle)
+    shader_compiler: CompilerKind,  // Rebuilds hot shaders' .spv when their GLSL source changes
+    checked_arithmetic: Option<String>,  // --checked-arithmetic: the source file named when integer math overflows
+    strict_fp: bool,  // --strict-fp: every function is @strict_fp
+    has_strict_fp: bool,  // Some function or system of the program is @strict_fp
 }
 
-impl CodeGenerator {
+impl CppGenerator {
     pub fn new() -> Self {
         Self {
             components: HashMap::new(),
+            structs: HashMap::new(),
+            migrations: Vec::new(),
+            layout_checks: Vec::new(),
+            target_abi: TargetAbi::host(),
+            ecs_storage: EcsStorage::default(),
+            gpu_backend: GpuBackend::default(),
+            runtime: RuntimeProfile::default(),
+            target: Target::default(),
+            dll_dir: String::new(),
             hot_systems: Vec::new(),
+            hot_dll: None,
             hot_shaders: Vec::new(),
             hot_components: Vec::new(),
             has_resources: false,
@@ -27,15 +717,167 @@ impl CodeGenerator {
             image_resources: Vec::new(),
             cuda_functions: Vec::new(),
             cuda_components: Vec::new(),
+            extern_functions: Vec::new(),
+            consts: Vec::new(),
+            scheduled_systems: Vec::new(),
+            resource_types: Vec::new(),
+            builtin_resources: Vec::new(),
+            events: Vec::new(),
+            scenes: Vec::new(),
+            chunk_iterators: Vec::new(),
+            query_params: HashMap::new(),
+            changed_components: HashSet::new(),
+            uses_hierarchy: false,
+            spatial_sounds: Vec::new(),
+            reload_checks: false,
+            uses_frame_arena: false,
+            uses_gpu_sync: false,
+            cuda_query: None,
+            inner_loop_functions: HashSet::new(),
             defer_counter: 0,
+            function_params: HashMap::new(),
+            gpu_config: None,
+            window_config: None,
+            interfaces: HashMap::new(),
+            impls: Vec::new(),
+            gpu_queries: Vec::new(),
+            gpu_buffers: Vec::new(),
+            uniforms: Vec::new(),
+            input: None,
+            emit_style: EmitStyle::Default,
+            test_options: None,
+            hot_rebuild: None,
+            symbol_prefix: generated_names::symbol_prefix(""),
+            shader_compiler: CompilerKind::default(),
+            checked_arithmetic: None,
+            strict_fp: false,
+            has_strict_fp: false,
         }
     }
     
-    pub fn generate(&mut self, program: &Program) -> Result<String> {
+    pub fn set_emit_style(&mut self, style: EmitStyle) {
+        self.emit_style = style;
+    }
+    
+    pub fn set_target_abi(&mut self, abi: TargetAbi) {
+        self.target_abi = abi;
+    }
+    
+    pub fn set_ecs_storage(&mut self, storage: EcsStorage) {
+        self.ecs_storage = storage;
+    }
+    
+    /// Callers check vulkan_compute::conflicts() first for GpuBackend::Vulkan
+    pub fn set_gpu_backend(&mut self, backend: GpuBackend) {
+        self.gpu_backend = backend;
+    }
+    
+    /// Callers check minimal_runtime_conflicts() first: codegen assumes the program fits the profile
+    pub fn set_runtime(&mut self, runtime: RuntimeProfile) {
+        self.runtime = runtime;
+    }
+    
+    /// Callers check wasm::conflicts() first for Target::Wasm
+    pub fn set_target(&mut self, target: Target) {
+        self.target = target;
+    }
+    
+    /// heidic.toml [build] output: hot DLLs are built there rather than next to the source
+    pub fn set_dll_dir(&mut self, dir: &str) {
+        self.dll_dir = if dir.is_empty() { String::new() } else { format!("{}/", dir.trim_end_matches('/')) };
+    }
+    
+    // The path a hot system's DLL is loaded from
+    fn dll_file(&self, system: &SystemDef) -> String {
+        format!("{}{}.dll", self.dll_dir, system.name.to_lowercase())
+    }
+    
+    pub fn set_test_options(&mut self, options: TestOptions) {
+        self.test_options = Some(options);
+    }
+    
+    /// Integer +, -, *, /, % and negation stop the program on overflow, naming `source`:line:column
+    pub fn set_checked_arithmetic(&mut self, source: &str) {
+        self.checked_arithmetic = Some(source.to_string());
+    }
+    
+    /// Every function gets @strict_fp's IEEE float semantics
+    pub fn set_strict_fp(&mut self) {
+        self.strict_fp = true;
+    }
+    
+    /// Whether the generated code has CUDA kernels or GPU-resident components and is compiled with nvcc (set by generate)
+    pub fn uses_cuda(&self) -> bool {
+        self.gpu_backend == GpuBackend::Cuda && (!self.cuda_functions.is_empty() || self.uses_gpu_sync)
+    }
+    
+    /// Whether @[launch] functions dispatch Vulkan compute shaders (written by vulkan_compute::compute_shaders)
+    pub fn uses_vulkan_compute(&self) -> bool {
+        self.gpu_backend == GpuBackend::Vulkan && !self.cuda_functions.is_empty()
+    }
+    
+    /// Extra compiler flags for the generated files: " -ffp-contract=off" when floats must be strict
+    pub fn strict_fp_flags(&self) -> &'static str {
+        if self.uses_strict_fp() { " -ffp-contract=off" } else { "" }
+    }
+    
+    pub fn set_hot_rebuild(&mut self, rebuild: HotRebuild) {
+        self.hot_rebuild = Some(rebuild);
+    }
+    
+    pub fn set_shader_compiler(&mut self, compiler: CompilerKind) {
+        self.shader_compiler = compiler;
+    }
+    
+}
+
+impl CodeGenerator for CppGenerator {
+    fn extension(&self) -> &'static str {
+        "cpp"
+    }
+    
+    fn set_program_name(&mut self, name: &str) {
+        self.symbol_prefix = generated_names::symbol_prefix(name);
+    }
+    
+    fn symbol_prefix(&self) -> &str {
+        &self.symbol_prefix
+    }
+    
+    fn uses_strict_fp(&self) -> bool {
+        self.strict_fp || self.has_strict_fp
+    }
+    
+    fn generate(&mut self, program: &Program) -> Result<String> {
         let mut output = String::new();
         
+        // Browsers load no DLLs: @hot items compile as ordinary ones, and tweak files reload through heidic_reload()
+        let without_hot_reload;
+        let program = if self.target == Target::Wasm {
+            without_hot_reload = wasm::without_hot_reload(program);
+            &without_hot_reload
+        } else {
+            program
+        };
+        
+        // Spatial sounds are heard from the built-in AudioListener component
+        self.spatial_sounds = spatial_audio::spatial_sounds(program).into_iter().cloned().collect();
+        if !self.spatial_sounds.is_empty() {
+            self.components.insert(spatial_audio::LISTENER.to_string(), spatial_audio::listener());
+        }
+        
         // First pass: collect component metadata (for SOA detection), hot systems, hot shaders, hot components, pipelines, and CUDA items
         for item in &program.items {
+            if let Item::Struct(s) = item {
+                self.structs.insert(s.name.clone(), s.clone());
+            }
+            if let Item::Event(e) = item {
+                self.structs.insert(e.name.clone(), StructDef { name: e.name.clone(), fields: e.fields.clone(), is_export: false });
+                self.events.push(e.clone());
+            }
+            if let Item::Migrate(migrate) = item {
+                self.migrations.push(migrate.clone());
+            }
             if let Item::Component(c) = item {
                 self.components.insert(c.name.clone(), c.clone());
                 if c.is_hot {
@@ -49,6 +891,9 @@ impl CodeGenerator {
                 if s.is_hot {
                     self.hot_systems.push(s.clone());
                 }
+                for f in &s.functions {
+                    self.function_params.insert(f.name.clone(), f.params.clone());
+                }
             }
             if let Item::Shader(sh) = item {
                 if sh.is_hot {
@@ -56,44 +901,271 @@ impl CodeGenerator {
                 }
             }
             if let Item::Pipeline(p) = item {
+                for binding in p.layout.iter().flat_map(|layout| layout.bindings.iter()) {
+                    if let BindingType::Storage(element) = &binding.binding_type {
+                        if !binding.name.is_empty() && self.gpu_buffer_element(&binding.name).is_none() {
+                            self.gpu_buffers.push((binding.name.clone(), element.clone()));
+                        }
+                    }
+                }
                 self.pipelines.push(p.clone());
             }
+            if let Item::Gpu(gpu) = item {
+                self.gpu_config = Some(gpu.clone());
+            }
+            if let Item::Window(window) = item {
+                self.window_config.get_or_insert_with(|| window.clone());
+            }
+            if let Item::Uniform(u) = item {
+                self.uniforms.push(u.clone());
+            }
+            if let Item::Input(input) = item {
+                self.input.get_or_insert_with(|| input.clone());
+            }
             if let Item::Function(f) = item {
                 if f.cuda_kernel.is_some() {
                     self.cuda_functions.push(f.clone());
                 }
+                self.function_params.insert(f.name.clone(), f.params.clone());
+            }
+            if let Item::ExternFunction(ext) = item {
+                self.function_params.insert(ext.name.clone(), ext.params.clone());
+                self.extern_functions.push(ext.clone());
+            }
+            if let Item::Const(c) = item {
+                self.consts.push(c.clone());
+            }
+            if let Item::Interface(iface) = item {
+                for method in &iface.methods {
+                    self.function_params.insert(method.name.clone(), method.params.clone());
+                }
+                self.interfaces.insert(iface.name.clone(), iface.clone());
+            }
+            if let Item::Impl(imp) = item {
+                self.impls.push(imp.clone());
+            }
+            if let Item::GpuQuery(query) = item {
+                self.gpu_queries.push(query.clone());
+            }
+        }
+        
+        self.has_strict_fp = program.items.iter().any(|item| match item {
+            Item::Function(f) => f.strict_fp,
+            Item::System(s) => s.strict_fp,
+            _ => false,
+        });
+        
+        // Top-level @hot fns share one DLL, loaded and reloaded like a @hot system's
+        let hot_functions: Vec<FunctionDef> = program.items.iter()
+            .filter_map(|item| match item {
+                Item::Function(f) if f.is_hot => Some(f.clone()),
+                _ => None,
+            })
+            .collect();
+        if !hot_functions.is_empty() {
+            self.hot_systems.push(SystemDef {
+                name: HOT_FUNCTIONS_MODULE.to_string(),
+                functions: hot_functions,
+                handlers: Vec::new(),
+                is_hot: true,
+                schedule: None,
+                strict_fp: false,  // Each @hot fn keeps its own
+                location: crate::error::SourceLocation::unknown(),
+            });
+        }
+        
+        // Resources taken as res<T>; built-in ones the program doesn't declare itself are generated
+        self.resource_types = resources::resource_types(program);
+        self.builtin_resources = self.resource_types.iter()
+            .filter(|name| !resources::is_declared(program, name))
+            .filter_map(|name| resources::builtin(name))
+            .collect();
+        for s in &self.builtin_resources {
+            self.structs.insert(s.name.clone(), s.clone());
+        }
+        
+        // Every save_schema version is a struct; the newest has the schema's own name
+        let save_schemas = save_schema::schemas(program);
+        for versions in save_schemas.values() {
+            let newest = versions.last().map_or(0, |schema| schema.version);
+            for schema in versions {
+                let name = save_schema::struct_name(schema, newest);
+                self.structs.insert(name.clone(), StructDef { name, fields: schema.fields.clone(), is_export: false });
             }
         }
         
+        // Query filters check components in g_storage, and changed<T> needs writes to T recorded there
+        let query_filters: Vec<(QueryFilter, String)> = program_queries(program)
+            .flatten()
+            .filter_map(|ty| match ty {
+                Type::QueryFilter(filter, component) => Some((*filter, self.type_to_cpp(component))),
+                _ => None,
+            })
+            .collect();
+        self.inner_loop_functions = inner_loop_functions(program);
+        
+        let uses_net = replication::FUNCTIONS.iter()
+            .any(|name| program_calls(program, &[*name]) && !program_functions(program).any(|f| f.name == *name));
+
+        // @hot components are tracked too: their migration only rewrites entities written since it
+        // last ran. So are replicated ones, which snapshots only carry when they changed.
+        let replicated: Vec<String> = self.components.values()
+            .filter(|component| uses_net && component.is_replicated)
+            .map(|component| component.name.clone())
+            .collect();
+        self.changed_components = query_filters.iter()
+            .filter(|(filter, _)| *filter == QueryFilter::Changed)
+            .map(|(_, component)| component.clone())
+            .chain(self.hot_components.iter().map(|component| component.name.clone()))
+            .chain(replicated)
+            .collect();
+        
+        // Parent/Children in filters are the built-in components unless the program declares its own
+        self.uses_hierarchy = program_calls(program, hierarchy::METHODS)
+            || query_filters.iter().any(|(_, component)| {
+                hierarchy::BUILTINS.contains(&component.as_str()) && !resources::is_declared(program, component)
+            });
+        
+        // Chunk loops hand out slices, which extern fns receive as pointers like any array
+        let uses_slices = program_calls(program, &["chunks"])
+            || self.extern_functions.iter().any(|ext| ext.params.iter().any(|p| matches!(p.ty, Type::Array(_))));
+
+        // The type checker has already rejected schedules that can't be satisfied
+        let systems: Vec<&SystemDef> = program.items.iter().filter_map(|item| match item {
+            Item::System(s) => Some(s),
+            _ => None,
+        }).collect();
+        self.scheduled_systems = schedule::schedule(&systems).map(|order| order.into_iter().cloned().collect()).unwrap_or_default();
+        let uses_scheduler = !self.scheduled_systems.is_empty() || program_calls(program, &["run_systems"]);
+        self.uses_frame_arena = program_calls(program, &[frame_arena::METHOD]) || frame_arena::is_passed(program);
+        self.uses_gpu_sync = program_calls(program, &[SyncDirection::ToGpu.name(), SyncDirection::ToCpu.name()]);
+
+        self.scenes = program.items.iter().filter_map(|item| match item {
+            Item::Scene(scene) if !scene.entities.is_empty() => Some(scene.clone()),
+            _ => None,
+        }).collect();
+
+        let uses_world = ["save_world", "load_world"].iter()
+            .any(|name| program_calls(program, &[*name]) && !program_functions(program).any(|f| f.name == *name));
+        let uses_memory_report = program_calls(program, &["memory_report"]) && !program_functions(program).any(|f| f.name == "memory_report");
+
+        // Queries are gathered from g_storage, and so are hot components, bulk spawning, scenes,
+        // saved worlds, replication and GPU mirrors
+        let mut query_names = HashSet::new();
+        let queries: Vec<&Vec<Type>> = program_queries(program)
+            .filter(|query| query_names.insert(self.type_to_cpp(&Type::Query(query.to_vec()))))
+            .collect();
+        let uses_entity_storage = !self.hot_components.is_empty() || !queries.is_empty() || self.uses_hierarchy || !self.spatial_sounds.is_empty()
+            || !self.scenes.is_empty() || uses_world || uses_net || uses_memory_report || self.uses_gpu_sync
+            || program_calls(program, &["reserve_entities", "spawn_batch"]);
+
         // Generate includes and standard library (AFTER collecting hot items so we know what to include)
-        output.push_str("#include <iostream>\n");
+        let minimal = self.runtime == RuntimeProfile::Minimal;
+        if !minimal {
+            output.push_str("#include <iostream>\n");
+        }
         output.push_str("#include <vector>\n");
         output.push_str("#include <string>\n");
-        output.push_str("#include <unordered_map>\n");
+        if !minimal {
+            output.push_str("#include <unordered_map>\n");
+        }
         output.push_str("#include <memory>\n");
         output.push_str("#include <cmath>\n");
         output.push_str("#include <cstdint>\n");
+        let uses_vertex_input = program.items.iter().any(|item| matches!(item, Item::Pipeline(p) if p.vertex_input.is_some()));
+        let uses_push_constants = program.items.iter().any(|item| matches!(item, Item::Pipeline(p) if p.push_constants().is_some()));
+        let uses_uniforms = program.items.iter().any(|item| matches!(item, Item::Uniform(_)));
+        if program_calls(program, &["offset_of"]) || uses_vertex_input || uses_push_constants || uses_uniforms {
+            output.push_str("#include <cstddef>\n");  // offsetof in the layout checks, vertex attributes, push constants and uniforms
+        }
         output.push_str("#include <optional>\n");  // For optional types
-        // Include chrono if we have hot components (for ECS timing) or hot systems/shaders
-        if !self.hot_components.is_empty() || !self.hot_systems.is_empty() || !self.hot_shaders.is_empty() {
+        output.push_str("#include <functional>\n");  // For function types (std::function)
+        // Include chrono for hot-reload polling and the scheduler's frame time
+        if self.has_hot_reload() || uses_scheduler {
             output.push_str("#include <chrono>\n");
         }
         output.push_str("\n");
         
         // Include EDEN standard library (Vulkan, GLFW, GLM math, ImGui)
-        output.push_str("// EDEN ENGINE Standard Library\n");
-        output.push_str("#include \"stdlib/vulkan.h\"\n");
-        output.push_str("#include \"stdlib/glfw.h\"\n");
-        output.push_str("#include \"stdlib/math.h\"\n");
-        output.push_str("#include \"stdlib/imgui.h\"\n");
-        // Include entity storage if we have hot components
-        if !self.hot_components.is_empty() {
+        output.push_str(&self.section("EDEN ENGINE Standard Library"));
+        output.push_str(&self.generate_stdlib_version_check());
+        if minimal {
+            // Before the other headers, which size their component type maps with it (stdlib/type_map.h).
+            // Parent and Children are stored like the program's components.
+            output.push_str("#define HEIDIC_MINIMAL_RUNTIME\n");
+            output.push_str(&format!("#define HEIDIC_MAX_COMPONENT_TYPES {}\n", self.components.len() + hierarchy::BUILTINS.len()));
+            output.push_str("#include \"stdlib/minimal_runtime.h\"\n");
+            output.push_str("#include \"stdlib/math.h\"\n");
+        } else if self.target == Target::Wasm {
+            // Before the other headers, so those including "glfw.h" get the canvas instead
+            output.push_str("#define HEIDIC_WASM\n");
+            output.push_str("#include \"stdlib/canvas.h\"\n");
+            output.push_str("#include \"stdlib/webgpu.h\"\n");
+            output.push_str("#include \"stdlib/math.h\"\n");
+        } else {
+            output.push_str("#include \"stdlib/vulkan.h\"\n");
+            output.push_str("#include \"stdlib/glfw.h\"\n");
+            output.push_str("#include \"stdlib/math.h\"\n");
+            output.push_str("#include \"stdlib/imgui.h\"\n");
+            output.push_str("#include \"stdlib/transfer_queue.h\"\n");
+        }
+        // Include entity storage if we have hot components or spawn entities in bulk
+        if uses_entity_storage {
+            if self.ecs_storage == EcsStorage::Archetype {
+                output.push_str("#define HEIDIC_ARCHETYPE_STORAGE\n");
+            }
             output.push_str("#include \"stdlib/entity_storage.h\"\n");
         }
+        if self.uses_hierarchy {
+            output.push_str("#include \"stdlib/hierarchy.h\"\n");
+        }
+        if uses_slices {
+            output.push_str("#include \"stdlib/slice.h\"\n");
+        }
+        if self.uses_frame_arena {
+            output.push_str("#include \"stdlib/frame_arena.h\"\n");
+        }
+        if self.uses_cuda() {
+            output.push_str("#include \"stdlib/cuda_bridge.h\"\n");
+        }
+        if self.uses_vulkan_compute() {
+            output.push_str("#include \"stdlib/vulkan_compute.h\"\n");
+        }
+        if self.checked_arithmetic.is_some() {
+            output.push_str("#include \"stdlib/checked_arithmetic.h\"\n");
+        }
+        if self.uses_strict_fp() {
+            output.push_str("#include \"stdlib/strict_fp.h\"\n");
+        }
+        if !self.events.is_empty() {
+            output.push_str("#include \"stdlib/events.h\"\n");
+        }
+        if self.has_tweaks() {
+            output.push_str("#include \"stdlib/tweak.h\"\n");
+        }
+        if !save_schemas.is_empty() || uses_world || uses_net {
+            output.push_str("#include \"stdlib/save_data.h\"\n");
+        }
+        if uses_net {
+            output.push_str("#include \"stdlib/net.h\"\n");
+        }
+        if uses_push_constants || uses_uniforms {
+            output.push_str("#include \"stdlib/push_constants.h\"\n");
+        }
+        if uses_uniforms {
+            output.push_str("#include \"stdlib/uniform_buffer.h\"\n");
+        }
+        if self.input.is_some() {
+            output.push_str("#include \"stdlib/input_map.h\"\n");
+        }
+        // Frame capture for screenshot() and the test runner's expect_frame()
+        if self.test_options.is_some() || program_calls(program, &["screenshot"]) {
+            output.push_str("#include \"stdlib/frame_capture.h\"\n");
+        }
         output.push_str("\n");
         
         // Defer statement support (RAII helper)
-        output.push_str("// Defer statement support\n");
+        output.push_str(&self.section("Defer statement support"));
         output.push_str("// Note: Defer expressions should not throw exceptions.\n");
         output.push_str("// If a defer expression throws during stack unwinding, std::terminate is called.\n");
         output.push_str("template<typename F>\n");
@@ -111,6 +1183,11 @@ impl CodeGenerator {
         output.push_str("}\n");
         output.push_str("\n");
         
+        output.push_str(&self.generate_consts());
+        if self.has_tweaks() {
+            output.push_str(&self.generate_tweaks());
+        }
+        
         // Generate structs and components
         for item in &program.items {
             match item {
@@ -123,6 +1200,30 @@ impl CodeGenerator {
                 _ => {}
             }
         }
+        if !self.spatial_sounds.is_empty() {
+            output.push_str(&self.generate_component(&spatial_audio::listener(), 0));
+        }
+        for query in &queries {
+            output.push_str(&self.generate_query_struct(query));
+        }
+        
+        if !self.resource_types.is_empty() {
+            output.push_str(&self.generate_resources());
+        }
+        if !self.events.is_empty() {
+            output.push_str(&self.generate_events());
+        }
+        for versions in save_schemas.values() {
+            let newest = versions.last().map_or(0, |schema| schema.version);
+            for schema in versions {
+                output.push_str(&self.generate_struct(&self.structs[&save_schema::struct_name(schema, newest)], 0));
+            }
+        }
+        
+        // Generate interfaces and their impl specializations (bodies come with the functions)
+        if !self.interfaces.is_empty() {
+            output.push_str(&self.generate_interfaces(program));
+        }
         
         // Generate ComponentRegistry if we have any components
         if !self.components.is_empty() {
@@ -165,15 +1266,54 @@ impl CodeGenerator {
             }
         }
         
-        // Generate bindless infrastructure if we have Image resources
+        // Generate GPU device selection config (explicit gpu block, or implied by bindless)
+        if self.needs_gpu_config() {
+            output.push_str(&self.generate_gpu_config());
+        }
+        
+        // Generate the window open_window() creates
+        if let Some(window) = &self.window_config {
+            output.push_str(&self.generate_window_config(window));
+        }
+        
+        // Generate the arena frame.alloc_array allocates from
+        if self.uses_frame_arena {
+            output.push_str(&self.generate_frame_arena());
+        }
+        
+        // Generate GPU query pool descriptors (Vulkan pools are created on first use)
+        if !self.gpu_queries.is_empty() {
+            output.push_str(&self.generate_gpu_queries());
+        }
+        
+        // Generate storage buffers for named storage bindings (allocated on first upload)
+        if !self.gpu_buffers.is_empty() {
+            output.push_str(&self.generate_gpu_buffers());
+        }
+        
+        // Generate bindless infrastructure if we have Image resources (storage buffers join them)
         if !self.image_resources.is_empty() {
-            output.push_str("\n// Bindless texture infrastructure\n");
+            output.push_str(&format!("\n{}", self.section("Bindless texture infrastructure")));
             output.push_str(&self.generate_bindless_infrastructure());
         }
         
+        // Generate uniform block structs and their buffers (created on first use)
+        if !self.uniforms.is_empty() {
+            output.push_str(&format!("\n{}", self.section("Uniform blocks")));
+            for uniform in &self.uniforms {
+                output.push_str(&self.generate_uniform(uniform));
+            }
+        }
+        
+        // Input actions: their state, and the update that reads the devices into it
+        if let Some(input) = &self.input {
+            output.push_str(&format!("\n{}", self.section("Input actions")));
+            output.push_str(&self.generate_input(input));
+        }
+        
         // Generate pipeline declarations and creation functions
         if !self.pipelines.is_empty() {
-            output.push_str("\n// Pipeline declarations and creation functions\n");
+            output.push_str(&format!("\n{}", self.section("Pipeline declarations and creation functions")));
             for pipeline in &self.pipelines {
                 output.push_str(&self.generate_pipeline(pipeline));
             }
@@ -182,7 +1322,7 @@ impl CodeGenerator {
         // Generate resource accessor functions (so resources can be accessed in HEIDIC)
         // Generate accessors for ALL resources, not just hot ones
         if has_any_resources {
-            output.push_str("\n// Resource accessor functions (for HEIDIC access)\n");
+            output.push_str(&format!("\n{}", self.section("Resource accessor functions (for HEIDIC access)")));
             for item in &program.items {
                 if let Item::Resource(res) = item {
                     output.push_str(&self.generate_resource_accessor(res));
@@ -190,48 +1330,31 @@ impl CodeGenerator {
             }
             output.push_str("\n");
             
-            // Generate helper functions for audio resources (play, stop)
+            // Generate helper functions for audio resources (play, looping play, stop, pause, resume,
+            // volume) and the mixer their volumes go through
             output.push_str("// Audio resource helper functions (for HEIDIC access)\n");
-            for item in &program.items {
-                if let Item::Resource(res) = item {
-                    let resource_type = res.resource_type.as_str();
-                    if resource_type == "Sound" || resource_type == "Music" {
-                        let accessor_name = format!("get_resource_{}", res.name.to_lowercase());
-                        let play_func_name = format!("play_resource_{}", res.name.to_lowercase());
-                        let stop_func_name = format!("stop_resource_{}", res.name.to_lowercase());
-                        
-                        // Play function
-                        output.push_str(&format!(
-                            "extern \"C\" int32_t {}() {{\n",
-                            play_func_name
-                        ));
-                        output.push_str(&format!(
-                            "    auto* res = {}();\n",
-                            accessor_name
-                        ));
-                        output.push_str("    if (!res) { std::cerr << \"[Audio] Resource pointer is null\" << std::endl; return 0; }\n");
-                        output.push_str("    auto* audio = res->get();\n");
-                        output.push_str("    if (!audio) { std::cerr << \"[Audio] AudioResource is null - resource failed to load. Check if file exists and format is supported (WAV works, OGG requires SDL3_mixer)\" << std::endl; return 0; }\n");
-                        output.push_str("    bool result = audio->play(false);\n");
-                        output.push_str("    if (!result) { std::cerr << \"[Audio] play() returned false\" << std::endl; }\n");
-                        output.push_str("    return result ? 1 : 0;\n");
-                        output.push_str("}\n\n");
-                        
-                        // Stop function
-                        output.push_str(&format!(
-                            "extern \"C\" void {}() {{\n",
-                            stop_func_name
-                        ));
-                        output.push_str(&format!(
-                            "    auto* res = {}();\n",
-                            accessor_name
-                        ));
-                        output.push_str("    if (!res) return;\n");
-                        output.push_str("    auto* audio = res->get();\n");
-                        output.push_str("    if (audio) audio->stop();\n");
-                        output.push_str("}\n\n");
-                    }
+            let audio_resources: Vec<&ResourceDef> = program.items.iter()
+                .filter_map(|item| match item {
+                    Item::Resource(res) if res.resource_type == "Sound" || res.resource_type == "Music" => Some(res),
+                    _ => None,
+                })
+                .collect();
+            if !audio_resources.is_empty() {
+                output.push_str("// Audio mixer: each clip plays at its own volume times the master and its channel's volume\n");
+                output.push_str("static HeidicAudioMixer g_audio_mixer;\n\n");
+            }
+            if !self.spatial_sounds.is_empty() {
+                output.push_str("// Entities the spatial sounds play at (heidic_update_spatial_audio follows their Positions)\n");
+                for res in &self.spatial_sounds {
+                    output.push_str(&format!("static EntityId {} = INVALID_ENTITY;\n", spatial_audio::emitter_global(res)));
                 }
+                output.push_str("static void heidic_update_spatial_audio();\n\n");
+            }
+            for res in &audio_resources {
+                output.push_str(&self.generate_audio_helpers(res));
+            }
+            if !audio_resources.is_empty() {
+                output.push_str(&self.generate_audio_mixer(&audio_resources));
             }
             
             // Generate helper functions for video resources (play, pause, stop, seek, update, etc.)
@@ -403,7 +1526,7 @@ impl CodeGenerator {
         
         // Generate extern function declarations (C linkage)
         // Note: Resource accessor functions are already implemented above, so we don't need to declare them here
-        let mut extern_libraries = std::collections::HashSet::new();
+        let mut extern_libraries = std::collections::BTreeSet::new();
         
         for item in &program.items {
             if let Item::ExternFunction(ext) = item {
@@ -450,6 +1573,18 @@ impl CodeGenerator {
         let mut has_main = false;
         for item in &program.items {
             match item {
+                Item::Function(f) if f.is_hot => {
+                    functions.push(f.clone());
+                    output.push_str(&self.hot_function_pointer_decl(f));
+                }
+                Item::Function(f) if f.cuda_kernel.is_some() => {
+                    functions.push(f.clone());
+                    // The host reaches the kernel through its launch wrapper
+                    output.push_str(&format!("{};\n", self.cuda_launch_signature(f)));
+                    if f.cuda_kernel.as_ref().is_some_and(|launch| launch.is_async) {
+                        output.push_str(&format!("void {}();\n", cuda::wait_function(f)));
+                    }
+                }
                 Item::Function(f) => {
                     if f.name == "main" {
                         has_main = true;
@@ -466,52 +1601,45 @@ impl CodeGenerator {
                     } else {
                         self.type_to_cpp(&f.return_type)
                     };
-                    output.push_str(&format!("{} {}(", return_type, func_name));
+                    output.push_str(&format!("{}{} {}(", self.interface_template_header(&f.params), return_type, func_name));
                     for (i, param) in f.params.iter().enumerate() {
                         if i > 0 {
                             output.push_str(", ");
                         }
-                        output.push_str(&format!("{} {}", 
-                            self.type_to_cpp(&param.ty), 
-                            param.name));
+                        output.push_str(&self.param_to_cpp(param));
                     }
                     output.push_str(");\n");
                 }
+                Item::Migrate(migrate) => {
+                    let f = FunctionDef { name: migrate.cpp_name(), ..migrate.function.clone() };
+                    let params: Vec<String> = f.params.iter().map(|param| self.param_to_cpp(param)).collect();
+                    output.push_str(&format!("{} {}({});\n", self.type_to_cpp(&f.return_type), f.name, params.join(", ")));
+                    functions.push(f);
+                }
                 Item::System(s) => {
                     // Only generate forward declarations for non-hot systems
                     // Hot systems are in separate DLLs
                     if !s.is_hot {
-                        for func in &s.functions {
+                        let handlers = s.handlers.iter().map(|handler| event_handler_function(s, handler));
+                        for func in s.functions.iter().cloned().chain(handlers).collect::<Vec<_>>().iter() {
                             functions.push(func.clone());
                             // Generate forward declaration
-                            output.push_str(&format!("{} {}(", 
+                            output.push_str(&format!("{}{} {}(", 
+                                self.interface_template_header(&func.params),
                                 self.type_to_cpp(&func.return_type), 
                                 func.name));
                             for (i, param) in func.params.iter().enumerate() {
                                 if i > 0 {
                                     output.push_str(", ");
                                 }
-                                output.push_str(&format!("{} {}", 
-                                    self.type_to_cpp(&param.ty), 
-                                    param.name));
+                                output.push_str(&self.param_to_cpp(param));
                             }
                             output.push_str(");\n");
                         }
                     } else {
                         // Generate function pointer declarations for hot systems
                         for func in &s.functions {
-                            let return_type = self.type_to_cpp(&func.return_type);
-                            // Generate function pointer type
-                            output.push_str(&format!("// Hot-reloadable function: {}\n", func.name));
-                            output.push_str(&format!("typedef {} (*{}_ptr)(", return_type, func.name));
-                            for (i, param) in func.params.iter().enumerate() {
-                                if i > 0 {
-                                    output.push_str(", ");
-                                }
-                                output.push_str(&format!("{}", self.type_to_cpp(&param.ty)));
-                            }
-                            output.push_str(");\n");
-                            output.push_str(&format!("extern {}_ptr g_{};\n\n", func.name, func.name));
+                            output.push_str(&self.hot_function_pointer_decl(func));
                         }
                     }
                 }
@@ -520,12 +1648,17 @@ impl CodeGenerator {
         }
         output.push_str("\n");
         
+        if !save_schemas.is_empty() {
+            output.push_str(&self.generate_save_schemas(&save_schemas));
+        }
+        
         // Generate forward declarations for hot-reload functions if we have hot systems
         if !self.hot_systems.is_empty() {
             output.push_str("// Hot-reload function forward declarations\n");
             output.push_str("void check_and_reload_hot_system();\n");
             output.push_str("void load_hot_system(const char* dll_path);\n");
             output.push_str("void unload_hot_system();\n");
+            output.push_str("bool rollback_last_reload();\n");
             output.push_str("\n");
         }
         
@@ -533,7 +1666,7 @@ impl CodeGenerator {
         if !self.hot_shaders.is_empty() {
             output.push_str("// Shader hot-reload function forward declarations\n");
             output.push_str("void check_and_reload_hot_shaders();\n");
-            output.push_str("extern \"C\" void heidic_reload_shader(const char* shader_path);\n");
+            output.push_str("extern \"C\" int32_t heidic_reload_shader(const char* shader_path);\n");
             output.push_str("\n");
         }
         
@@ -550,14 +1683,56 @@ impl CodeGenerator {
             output.push_str("void check_and_migrate_hot_components();\n");
             output.push_str("void init_component_versions();\n");
             output.push_str("\n");
-            
-            // Generate ECS storage globals
-            output.push_str("// ECS storage for hot components\n");
+        }
+        if self.has_hot_reload() {
+            output.push_str(&self.reload_check_function());
+        }
+        if uses_entity_storage {
+            output.push_str(&self.section("ECS storage"));
             output.push_str("static EntityStorage g_storage;\n");
-            output.push_str("static std::vector<EntityId> g_entities;\n");
-            output.push_str("static constexpr float BOUNDS = 3.0f;\n");
-            output.push_str("static auto g_last_update_time = std::chrono::high_resolution_clock::now();\n");
-            output.push_str("\n");
+            if !self.hot_components.is_empty() {
+                output.push_str("static std::vector<EntityId> g_entities;\n");
+                output.push_str("static constexpr float BOUNDS = 3.0f;\n");
+                output.push_str("static auto g_last_update_time = std::chrono::high_resolution_clock::now();\n");
+            }
+            output.push('\n');
+            for query in &queries {
+                output.push_str(&self.generate_query_gather(query));
+            }
+        }
+        if !self.spatial_sounds.is_empty() {
+            output.push_str(&self.generate_spatial_audio_update());
+        }
+        // After the storage: run_systems() gathers queries from it and updates spatial sounds
+        if uses_scheduler {
+            output.push_str(&self.generate_scheduler());
+        }
+        if !self.scenes.is_empty() {
+            output.push_str(&self.generate_scenes());
+        }
+        if uses_world {
+            output.push_str(&self.generate_world());
+        }
+        if uses_memory_report {
+            output.push_str(&self.generate_memory_report());
+        }
+        if uses_net {
+            output.push_str(&self.generate_replication());
+        }
+        if self.uses_gpu_sync {
+            output.push_str(&self.section("GPU-Resident Components (mirrors in the .cu)"));
+            for component in &self.cuda_components {
+                output.push_str(&self.generate_gpu_sync_functions(component));
+            }
+        }
+        
+        // Generate interface method implementations: Interface<Target>::method
+        for imp in self.impls.clone() {
+            for (method, _) in &imp.methods {
+                let mut def = method.clone();
+                def.name = format!("{}<{}>::{}", imp.interface, imp.target, method.name);
+                output.push_str(&self.generate_function(&def, 0));
+            }
         }
         
         // Generate function implementations (excluding hot systems and CUDA kernels)
@@ -573,22 +1748,33 @@ impl CodeGenerator {
             }
         }
         
-        // Generate CUDA kernel code and launch wrappers
-        if !self.cuda_functions.is_empty() {
-            output.push_str("\n// CUDA Kernel Code\n");
-            let cuda_funcs = self.cuda_functions.clone();
-            for f in &cuda_funcs {
-                output.push_str(&self.generate_cuda_kernel(f));
+        // Test blocks become functions called by the test runner (test builds only)
+        if self.test_options.is_some() {
+            output.push_str(&self.generate_test_functions(program));
+        }
+        
+        // Launch wrappers of CUDA kernels; the kernels themselves are in the .cu (generate_cuda_source)
+        if self.uses_vulkan_compute() {
+            output.push_str(&format!("\n{}", self.section("Vulkan Compute Launches (shaders in compute_shaders/)")));
+            for f in &self.cuda_functions {
+                output.push_str(&self.generate_compute_launch(f));
             }
-            output.push_str("\n// CUDA Launch Wrappers\n");
+        } else if !self.cuda_functions.is_empty() {
+            output.push_str(&format!("\n{}", self.section("CUDA Launches (kernels in the .cu)")));
             for f in &self.cuda_functions {
                 output.push_str(&self.generate_cuda_launch_wrapper(f));
             }
         }
         
+        // Every kind of hot reload reports to the same session log
+        if !self.hot_systems.is_empty() || !self.hot_shaders.is_empty() || !self.hot_components.is_empty() {
+            output.push_str(&format!("\n{}", self.section("Hot-Reload Session Log")));
+            output.push_str(&self.generate_reload_log());
+        }
+        
         // Generate hot-reload runtime integration
         if !self.hot_systems.is_empty() {
-            output.push_str("\n// Hot-Reload Runtime Integration\n");
+            output.push_str(&format!("\n{}", self.section("Hot-Reload Runtime Integration")));
             output.push_str("#include <windows.h>\n");
             output.push_str("#include <string>\n");
             output.push_str("#include <thread>\n");
@@ -606,32 +1792,90 @@ impl CodeGenerator {
             output.push_str("// Hot-reload helper functions\n");
             output.push_str("HMODULE g_hot_dll = nullptr;\n");
             output.push_str("\n");
-            output.push_str("void load_hot_system(const char* dll_path) {\n");
-            output.push_str("    // Unload old DLL if loaded\n");
-            output.push_str("    if (g_hot_dll) {\n");
-            output.push_str("        FreeLibrary(g_hot_dll);\n");
-            output.push_str("        g_hot_dll = nullptr;\n");
-            output.push_str("    }\n");
-            output.push_str("    \n");
-            output.push_str("    // Load new DLL\n");
-            output.push_str("    g_hot_dll = LoadLibraryA(dll_path);\n");
+            self.generate_hot_abi_verifier(&mut output);
+            output.push_str("// Resolve a loaded DLL's entry points; false (with nothing loaded) if it can't be used\n");
+            output.push_str("static bool load_hot_library(const char* path) {\n");
+            output.push_str("    g_hot_dll = LoadLibraryA(path);\n");
             output.push_str("    if (!g_hot_dll) {\n");
-            output.push_str("        std::cerr << \"Failed to load hot-reload DLL: \" << dll_path << std::endl;\n");
-            output.push_str("        return;\n");
+            output.push_str("        std::cerr << \"Failed to load hot-reload DLL: \" << path << std::endl;\n");
+            output.push_str("        return false;\n");
             output.push_str("    }\n");
             output.push_str("    \n");
+            output.push_str("    // Refuse DLLs built against a different ABI (signatures or component layouts)\n");
+            output.push_str("    bool ok = verify_hot_abi(g_hot_dll, path);\n");
+            output.push_str("    \n");
             output.push_str("    // Load function pointers\n");
             for system in &self.hot_systems {
                 for func in &system.functions {
-                    output.push_str(&format!("    g_{} = ({}_ptr)GetProcAddress(g_hot_dll, \"{}\");\n", 
+                    output.push_str(&format!("    g_{} = ok ? ({}_ptr)GetProcAddress(g_hot_dll, \"{}\") : nullptr;\n", 
                         func.name, func.name, func.name));
-                    output.push_str(&format!("    if (!g_{}) {{\n", func.name));
+                    output.push_str(&format!("    if (ok && !g_{}) {{\n", func.name));
                     output.push_str(&format!("        std::cerr << \"Failed to load function: {}\" << std::endl;\n", func.name));
+                    output.push_str("        ok = false;\n");
                     output.push_str("    }\n");
                 }
             }
+            output.push_str("    if (!ok) {\n");
+            output.push_str("        unload_hot_system();\n");
+            output.push_str("    }\n");
+            output.push_str("    return ok;\n");
             output.push_str("}\n");
-            output.push_str("\n");
+            output.push('\n');
+            output.push_str("// DLLs are loaded from numbered shadow copies, so the build can overwrite the original while\n");
+            output.push_str("// it's in use and the previous copy that loaded cleanly stays around for rollback_last_reload()\n");
+            output.push_str("static std::string g_hot_dll_current;     // Shadow copy currently loaded\n");
+            output.push_str("static std::string g_hot_dll_known_good;  // The one before it\n");
+            output.push_str("static int g_hot_dll_generation = 0;\n");
+            output.push('\n');
+            output.push_str("void load_hot_system(const char* dll_path) {\n");
+            output.push_str("    std::string shadow = std::string(dll_path) + \".shadow\" + std::to_string(++g_hot_dll_generation);\n");
+            output.push_str("    if (!CopyFileA(dll_path, shadow.c_str(), FALSE)) {\n");
+            output.push_str("        log_reload_event(\"system\", dll_path, false, \"could not copy the DLL (missing, or still being written?)\");\n");
+            output.push_str("        return;\n");
+            output.push_str("    }\n");
+            output.push_str("    std::string previous = g_hot_dll_current;\n");
+            output.push_str("    unload_hot_system();\n");
+            output.push_str("    if (load_hot_library(shadow.c_str())) {\n");
+            output.push_str("        if (!previous.empty()) {\n");
+            output.push_str("            if (!g_hot_dll_known_good.empty()) {\n");
+            output.push_str("                DeleteFileA(g_hot_dll_known_good.c_str());\n");
+            output.push_str("            }\n");
+            output.push_str("            g_hot_dll_known_good = previous;\n");
+            output.push_str("        }\n");
+            output.push_str("        g_hot_dll_current = shadow;\n");
+            output.push_str("        log_reload_event(\"system\", dll_path, true, \"loaded \" + shadow);\n");
+            output.push_str("        return;\n");
+            output.push_str("    }\n");
+            output.push_str("    DeleteFileA(shadow.c_str());\n");
+            output.push_str("    // Keep the game running on the code that worked\n");
+            output.push_str("    if (!previous.empty() && load_hot_library(previous.c_str())) {\n");
+            output.push_str("        log_reload_event(\"system\", dll_path, false, \"rejected; still running \" + previous);\n");
+            output.push_str("    } else {\n");
+            output.push_str("        g_hot_dll_current.clear();\n");
+            output.push_str("        log_reload_event(\"system\", dll_path, false, \"rejected; no hot code loaded\");\n");
+            output.push_str("    }\n");
+            output.push_str("}\n");
+            output.push('\n');
+            output.push_str("// Console command: go back to the DLL that was loaded before the last successful reload\n");
+            output.push_str("bool rollback_last_reload() {\n");
+            output.push_str("    if (g_hot_dll_known_good.empty()) {\n");
+            output.push_str("        log_reload_event(\"rollback\", g_hot_dll_current.c_str(), false, \"no earlier DLL to roll back to\");\n");
+            output.push_str("        return false;\n");
+            output.push_str("    }\n");
+            output.push_str("    std::string bad = g_hot_dll_current;\n");
+            output.push_str("    unload_hot_system();\n");
+            output.push_str("    if (!load_hot_library(g_hot_dll_known_good.c_str())) {\n");
+            output.push_str("        log_reload_event(\"rollback\", g_hot_dll_known_good.c_str(), false, \"known-good DLL failed to load\");\n");
+            output.push_str("        load_hot_library(bad.c_str());\n");
+            output.push_str("        return false;\n");
+            output.push_str("    }\n");
+            output.push_str("    DeleteFileA(bad.c_str());\n");
+            output.push_str("    g_hot_dll_current = g_hot_dll_known_good;\n");
+            output.push_str("    g_hot_dll_known_good.clear();\n");
+            output.push_str("    log_reload_event(\"rollback\", g_hot_dll_current.c_str(), true, \"replaced \" + bad);\n");
+            output.push_str("    return true;\n");
+            output.push_str("}\n");
+            output.push('\n');
             output.push_str("void unload_hot_system() {\n");
             output.push_str("    if (g_hot_dll) {\n");
             output.push_str("        FreeLibrary(g_hot_dll);\n");
@@ -644,112 +1888,100 @@ impl CodeGenerator {
             output.push_str("    }\n");
             output.push_str("}\n");
             output.push_str("\n");
-            output.push_str("// File watching and auto-reload\n");
-            output.push_str("#include <sys/stat.h>\n");
-            output.push_str("#include <io.h>\n");
-            output.push_str("#include <chrono>\n");
-            output.push_str("\n");
-            output.push_str("static time_t g_last_dll_time = 0;\n");
-            output.push_str("static std::chrono::steady_clock::time_point g_startup_time = std::chrono::steady_clock::now();\n");
-            output.push_str("static const int STARTUP_GRACE_PERIOD_SECONDS = 3; // Ignore DLL changes for first 3 seconds after startup\n");
+            output.push_str("// File watching and auto-reload: a DLL reloads when its contents change\n");
+            output.push_str("#include \"stdlib/content_hash.h\"\n");
             output.push_str("\n");
+            for system in &self.hot_systems {
+                output.push_str(&format!("static HeidicFileWatch g_dll_watch_{} {{{}}};\n", system.name.to_lowercase(), cpp_string(&self.dll_file(system))));
+            }
+            output.push('\n');
+            if let Some(rebuild) = &self.hot_rebuild {
+                output.push_str(&self.generate_hot_rebuild(rebuild));
+            }
             output.push_str("void check_and_reload_hot_system() {\n");
-            output.push_str("    // Ignore DLL changes during startup grace period (to avoid reloading immediately after build)\n");
-            output.push_str("    auto now = std::chrono::steady_clock::now();\n");
-            output.push_str("    auto elapsed = std::chrono::duration_cast<std::chrono::seconds>(now - g_startup_time).count();\n");
-            output.push_str("    if (elapsed < STARTUP_GRACE_PERIOD_SECONDS) {\n");
-            output.push_str("        return; // Still in startup grace period\n");
-            output.push_str("    }\n");
+            if self.hot_rebuild.is_some() {
+                output.push_str("    rebuild_changed_hot_sources();\n");
+            }
             for system in &self.hot_systems {
-                let dll_name = format!("{}.dll", system.name.to_lowercase());
-                output.push_str(&format!("    // Check {} DLL file modification time\n", system.name));
-                output.push_str(&format!("    struct stat dll_stat;\n"));
-                output.push_str(&format!("    if (stat(\"{}\", &dll_stat) == 0) {{\n", dll_name));
-                output.push_str(&format!("        if (dll_stat.st_mtime > g_last_dll_time) {{\n"));
-                output.push_str(&format!("            g_last_dll_time = dll_stat.st_mtime;\n"));
-                output.push_str(&format!("            std::cout << \"[Hot-Reload] Detected change in {}, reloading...\" << std::endl;\n", dll_name));
-                output.push_str(&format!("            // Unload old DLL first\n"));
-                output.push_str(&format!("            unload_hot_system();\n"));
-                output.push_str(&format!("            // Small delay to ensure DLL is fully unloaded on Windows\n"));
-                output.push_str(&format!("            std::this_thread::sleep_for(std::chrono::milliseconds(100));\n"));
-                output.push_str(&format!("            load_hot_system(\"{}\");\n", dll_name));
-                output.push_str(&format!("            std::cout << \"[Hot-Reload] {} reloaded successfully!\" << std::endl;\n", system.name));
-                output.push_str(&format!("        }}\n"));
-                output.push_str(&format!("    }}\n"));
+                let dll_name = self.dll_file(system);
+                output.push_str(&format!("    if (heidic_watch_changed(g_dll_watch_{})) {{\n", system.name.to_lowercase()));
+                output.push_str(&format!("        std::cout << \"[Hot-Reload] Detected change in {}, reloading...\" << std::endl;\n", dll_name));
+                output.push_str(&format!("        load_hot_system(\"{}\");\n", dll_name));
+                output.push_str("    }\n");
             }
             output.push_str("}\n");
-            output.push_str("\n");
+            output.push('\n');
         }
         
         // Generate shader hot-reload runtime integration
         if !self.hot_shaders.is_empty() {
-            output.push_str("\n// Shader Hot-Reload Runtime Integration\n");
-            output.push_str("#include <sys/stat.h>\n");
-            output.push_str("#include <io.h>\n");
-            output.push_str("#include <map>\n");
-            output.push_str("#include <string>\n");
-            output.push_str("\n");
-            output.push_str("// Store last modification times for hot shaders\n");
-            output.push_str("static std::map<std::string, time_t> g_shader_mtimes;\n");
-            output.push_str("\n");
-            output.push_str("void check_and_reload_hot_shaders() {\n");
-            for (idx, shader) in self.hot_shaders.iter().enumerate() {
-                // Get the shader file path (could be .glsl or .spv)
-                let shader_path = &shader.path;
-                // Determine the .spv path - keep extension to avoid conflicts (e.g., my_shader.vert.spv)
-                let spv_path = if shader_path.ends_with(".glsl") {
-                    shader_path.replace(".glsl", ".spv")
-                } else if shader_path.ends_with(".vert") || shader_path.ends_with(".frag") || shader_path.ends_with(".comp") {
-                    format!("{}.spv", shader_path)  // my_shader.vert.spv, my_shader.frag.spv
+            output.push_str(&format!("\n{}", self.section("Shader Hot-Reload Runtime Integration")));
+            output.push_str("#include \"stdlib/content_hash.h\"\n");
+            output.push_str("#include <cstdlib>\n");
+            output.push('\n');
+            output.push_str("// The compiled .spv is watched; the source path tells the runtime which stage it is.\n");
+            output.push_str("// A GLSL source is watched too and rebuilt into the .spv when it's saved.\n");
+            output.push_str("struct HotShaderWatch {\n");
+            output.push_str("    HeidicFileWatch spv;\n");
+            output.push_str("    const char* source;\n");
+            output.push_str("    HeidicFileWatch glsl;\n");
+            output.push_str("    const char* build;  // nullptr for a declared .spv\n");
+            // The declared pipelines that load this shader; a shader none of them loads is one of the engine's
+            let max_pipelines = self.hot_shaders.iter().map(|shader| self.hot_shader_pipelines(shader).len()).max().unwrap_or(0).max(1);
+            output.push_str(&format!("    bool (*pipelines[{}])();\n", max_pipelines));
+            output.push_str("};\n");
+            output.push_str("static HotShaderWatch g_hot_shader_watches[] = {\n");
+            for shader in &self.hot_shaders {
+                let spv = shaders::loaded_path(&shader.path);
+                let build = if shaders::is_glsl(&shader.path) {
+                    let args = self.shader_compiler.args(&shader.stage, &format!("\"{}\"", shader.path), &format!("\"{}\"", spv));
+                    cpp_string(&format!("{} {}", self.shader_compiler.program(), args.join(" ")))
                 } else {
-                    format!("{}.spv", shader_path)
+                    "nullptr".to_string()
                 };
-                
-                // Use unique variable name for each shader
-                let stat_var_name = format!("shader_stat_{}", idx);
-                
-                output.push_str(&format!("    // Check {} shader file modification time\n", shader_path));
-                output.push_str(&format!("    struct stat {};\n", stat_var_name));
-                output.push_str(&format!("    if (stat(\"{}\", &{}) == 0) {{\n", spv_path, stat_var_name));
-                output.push_str(&format!("        auto it = g_shader_mtimes.find(\"{}\");\n", spv_path));
-                output.push_str(&format!("        time_t last_mtime = (it != g_shader_mtimes.end()) ? it->second : 0;\n"));
-                output.push_str(&format!("        if ({}.st_mtime > last_mtime) {{\n", stat_var_name));
-                output.push_str(&format!("            g_shader_mtimes[\"{}\"] = {}.st_mtime;\n", spv_path, stat_var_name));
-                output.push_str(&format!("            std::cout << \"[Shader Hot-Reload] Detected change in {}, reloading...\" << std::endl;\n", spv_path));
-                // Pass the original source path so we can determine shader stage (vertex/fragment)
-                output.push_str(&format!("            heidic_reload_shader(\"{}\");\n", shader_path));
-                output.push_str(&format!("            std::cout << \"[Shader Hot-Reload] {} reloaded successfully!\" << std::endl;\n", spv_path));
-                output.push_str(&format!("        }}\n"));
-                output.push_str(&format!("    }}\n"));
+                let rebuilds: Vec<String> = self.hot_shader_pipelines(shader).iter()
+                    .map(|pipeline| format!("rebuild_pipeline_{}", pipeline.name.to_lowercase()))
+                    .collect();
+                output.push_str(&format!("    {{ {{\"{}\"}}, \"{}\", {{\"{}\"}}, {}, {{{}}} }},\n", spv, shader.path, shader.path, build, rebuilds.join(", ")));
             }
+            output.push_str("};\n");
+            output.push('\n');
+            output.push_str("void check_and_reload_hot_shaders() {\n");
+            output.push_str("    for (HotShaderWatch& shader : g_hot_shader_watches) {\n");
+            output.push_str("        if (shader.build && heidic_watch_changed(shader.glsl)) {\n");
+            output.push_str("            std::cout << \"[Shader Hot-Reload] Compiling \" << shader.source << \": \" << shader.build << std::endl;\n");
+            output.push_str("            bool built = std::system(shader.build) == 0;\n");
+            output.push_str("            log_reload_event(\"shader build\", shader.source, built, built ? \"compiled\" : \"GLSL errors above; keeping the previous shader\");\n");
+            output.push_str("        }\n");
+            output.push_str("        if (!heidic_watch_changed(shader.spv)) {\n");
+            output.push_str("            continue;\n");
+            output.push_str("        }\n");
+            output.push_str("        std::cout << \"[Shader Hot-Reload] Detected change in \" << shader.spv.path << \", reloading...\" << std::endl;\n");
+            output.push_str("        bool ok = true;\n");
+            output.push_str("        if (shader.pipelines[0]) {\n");
+            output.push_str("            for (bool (*rebuild)() : shader.pipelines) {\n");
+            output.push_str("                if (rebuild) ok = rebuild() && ok;\n");
+            output.push_str("            }\n");
+            output.push_str("        } else {\n");
+            output.push_str("            ok = heidic_reload_shader(shader.source) != 0;\n");
+            output.push_str("        }\n");
+            output.push_str("        log_reload_event(\"shader\", shader.spv.path.c_str(), ok, ok ? \"pipeline rebuilt\" : \"see the error above\");\n");
+            output.push_str("    }\n");
             output.push_str("}\n");
-            output.push_str("\n");
-            // Initialize shader modification times at startup
-            output.push_str("static void init_shader_mtimes() {\n");
-            for (idx, shader) in self.hot_shaders.iter().enumerate() {
-                let shader_path = &shader.path;
-                // Use same naming as check_and_reload_hot_shaders: keep extension for .vert/.frag/.comp
-                let spv_path = if shader_path.ends_with(".glsl") {
-                    shader_path.replace(".glsl", ".spv")
-                } else if shader_path.ends_with(".vert") || shader_path.ends_with(".frag") || shader_path.ends_with(".comp") {
-                    format!("{}.spv", shader_path)  // my_shader.vert.spv, my_shader.frag.spv
-                } else {
-                    format!("{}.spv", shader_path)
-                };
-                // Use unique variable name for each shader
-                let stat_var_name = format!("shader_stat_init_{}", idx);
-                output.push_str(&format!("    struct stat {};\n", stat_var_name));
-                output.push_str(&format!("    if (stat(\"{}\", &{}) == 0) {{\n", spv_path, stat_var_name));
-                output.push_str(&format!("        g_shader_mtimes[\"{}\"] = {}.st_mtime;\n", spv_path, stat_var_name));
-                output.push_str(&format!("    }}\n"));
-            }
+            output.push('\n');
+            // The shaders loaded at startup are the baseline
+            output.push_str("static void init_shader_watches() {\n");
+            output.push_str("    for (HotShaderWatch& shader : g_hot_shader_watches) {\n");
+            output.push_str("        heidic_watch_reset(shader.spv);\n");
+            output.push_str("        heidic_watch_reset(shader.glsl);\n");
+            output.push_str("    }\n");
             output.push_str("}\n");
-            output.push_str("\n");
+            output.push('\n');
         }
         
         // Generate resource hot-reload runtime integration
         if self.has_resources {
-            output.push_str("\n// Resource Hot-Reload Runtime Integration (CONTINUUM)\n");
+            output.push_str(&format!("\n{}", self.section("Resource Hot-Reload Runtime Integration (CONTINUUM)")));
             output.push_str("void check_and_reload_resources() {\n");
             for item in &program.items {
                 if let Item::Resource(res) = item {
@@ -766,7 +1998,7 @@ impl CodeGenerator {
         
         // Generate component hot-reload runtime integration
         if !self.hot_components.is_empty() {
-            output.push_str("\n// Component Hot-Reload Runtime Integration\n");
+            output.push_str(&format!("\n{}", self.section("Component Hot-Reload Runtime Integration")));
             output.push_str("#include <sys/stat.h>\n");
             output.push_str("#include <io.h>\n");
             output.push_str("#include <map>\n");
@@ -807,7 +2039,9 @@ impl CodeGenerator {
                 output.push_str(&format!("static ComponentMetadata g_metadata_{} = {{\n", component.name.to_lowercase()));
                 output.push_str(&format!("    \"{}\",\n", component.name));
                 output.push_str(&format!("    1,  // Version (increments when layout changes)\n"));
-                output.push_str(&format!("    sizeof({}),\n", component.name));
+                let size = self.layouts().field_offsets(&component.fields, 0)
+                    .map_or_else(|_| format!("sizeof({})", component.name), |(_, layout)| layout.size.to_string());
+                output.push_str(&format!("    {},  // Size\n", size));
                 output.push_str(&format!("    \"{}\"  // Field signature\n", field_sig));
                 output.push_str("};\n");
                 output.push_str("\n");
@@ -827,7 +2061,6 @@ impl CodeGenerator {
             // Generate version tracking map
             output.push_str("// Track component versions at runtime\n");
             output.push_str("static std::map<std::string, uint32_t> g_component_versions;\n");
-            output.push_str("static std::map<std::string, time_t> g_component_file_mtimes;\n");
             output.push_str("\n");
             
             // Generate migration functions for each component
@@ -900,6 +2133,7 @@ impl CodeGenerator {
                     component.name));
                 output.push_str(&format!("        migrate_{}(g_prev_metadata_{}.version, g_metadata_{}.version);\n", 
                     comp_name_lower, comp_name_lower, comp_name_lower));
+                output.push_str(&format!("        log_reload_event(\"component\", \"{}\", true, \"entities migrated to the new layout\");\n", component.name));
                 output.push_str(&format!("        // Update previous metadata to current\n"));
                 output.push_str(&format!("        g_prev_metadata_{} = g_metadata_{};\n", comp_name_lower, comp_name_lower));
                 output.push_str(&format!("        g_component_versions[\"{}\"] = g_metadata_{}.version;\n", 
@@ -922,56 +2156,220 @@ impl CodeGenerator {
             output.push_str("\n");
         }
         
-        // Add C++ main wrapper if HEIDIC main exists
-        if has_main {
+        if !self.layout_checks.is_empty() {
+            output.push_str(&self.section("Layout checks (size_of/align_of/offset_of)"));
+            for check in &self.layout_checks {
+                output.push_str(check);
+            }
+            output.push('\n');
+        }
+        
+        // Add C++ main wrapper if HEIDIC main exists (test builds get the test runner instead)
+        if self.test_options.is_some() {
+            output.push_str(&self.generate_test_runner(program));
+        } else if has_main {
             output.push_str("int main(int argc, char* argv[]) {\n");
-            // Load hot-reloadable systems at startup
+            output.push_str(&self.generate_main_startup());
+            output.push_str("    heidic_main();\n");
+            // Only unload hot system if we have hot systems
             if !self.hot_systems.is_empty() {
-                for system in &self.hot_systems {
-                    let dll_name = format!("{}.dll", system.name.to_lowercase());
-                    let dll_cpp_name = format!("{}_hot.dll.cpp", system.name.to_lowercase());
-                    output.push_str(&format!("    // Initialize file watching\n"));
-                    output.push_str(&format!("    struct stat dll_stat;\n"));
-                    output.push_str(&format!("    if (stat(\"{}\", &dll_stat) == 0) {{\n", dll_cpp_name));
-                    output.push_str(&format!("        g_last_dll_time = dll_stat.st_mtime;\n"));
-                    output.push_str(&format!("    }}\n"));
-                    output.push_str(&format!("    load_hot_system(\"{}\");\n", dll_name));
-                }
-            }
-            // Initialize shader modification times at startup
-            if !self.hot_shaders.is_empty() {
-                output.push_str("    init_shader_mtimes();\n");
+                output.push_str("    unload_hot_system();\n");
             }
-            // Initialize component versions at startup
-            if !self.hot_components.is_empty() {
-                output.push_str("    init_component_versions();\n");
+            output.push_str("    return 0;\n");
+            output.push_str("}\n");
+        }
+        
+        // Prefix the program's own symbols so two generated programs can share a process
+        let hot_pointers: HashSet<String> = self.hot_systems.iter()
+            .flat_map(|system| system.functions.iter().map(|f| format!("g_{}", f.name)))
+            .collect();
+        Ok(generated_names::mangle(&output, &self.symbol_prefix, &hot_pointers))
+    }
+}
+
+impl CppGenerator {
+    // Runtime setup shared by the program's main and the test runner, before any HEIDIC code runs
+    fn generate_main_startup(&self) -> String {
+        let mut output = String::new();
+        // Device selection must be configured before the renderer picks a GPU
+        if self.needs_gpu_config() {
+            output.push_str("    heidic_set_gpu_config(&g_gpu_config);\n");
+        }
+        if self.window_config.is_some() {
+            output.push_str("    heidic_set_window_config(&g_window_config);\n");
+        }
+        // Load hot-reloadable systems at startup
+        if !self.hot_systems.is_empty() {
+            for system in &self.hot_systems {
+                let dll_name = self.dll_file(system);
+                output.push_str(&format!("    load_hot_system(\"{}\");\n", dll_name));
+                output.push_str(&format!("    heidic_watch_reset(g_dll_watch_{});\n", system.name.to_lowercase()));
+                if self.hot_rebuild.is_some() {
+                    output.push_str(&format!("    heidic_watch_reset(g_hot_rebuild_{});\n", system.name.to_lowercase()));
+                }
             }
-            // Register all components in ComponentRegistry
-            if !self.components.is_empty() {
-                output.push_str("    register_all_components();\n");
+            if self.hot_rebuild.is_some() {
+                output.push_str("    heidic_watch_reset(g_hot_rebuild_source);\n");
             }
-            // Initialize bindless system if we have Image resources
-            if !self.image_resources.is_empty() {
-                output.push_str("    init_bindless_system();\n");
+        }
+        // The shaders on disk at startup are the ones the pipelines were built from
+        if !self.hot_shaders.is_empty() {
+            output.push_str("    init_shader_watches();\n");
+        }
+        // Initialize component versions at startup
+        if !self.hot_components.is_empty() {
+            output.push_str("    init_component_versions();\n");
+        }
+        // Register all components in ComponentRegistry
+        if !self.components.is_empty() {
+            output.push_str("    register_all_components();\n");
+        }
+        for i in 0..self.scenes.len() {
+            output.push_str(&format!("    heidic_spawn_scene_{}();\n", i));
+        }
+        // Initialize bindless system if we have Image resources
+        if !self.image_resources.is_empty() {
+            output.push_str("    init_bindless_system();\n");
+        }
+        // Initialize pipelines (must be called after Vulkan initialization)
+        if !self.pipelines.is_empty() {
+            output.push_str("    // Initialize pipelines\n");
+            for pipeline in &self.pipelines {
+                let pipeline_name_lower = pipeline.name.to_lowercase();
+                output.push_str(&format!("    create_pipeline_{}();\n", pipeline_name_lower));
             }
-            // Initialize pipelines (must be called after Vulkan initialization)
-            if !self.pipelines.is_empty() {
-                output.push_str("    // Initialize pipelines\n");
-                for pipeline in &self.pipelines {
-                    let pipeline_name_lower = pipeline.name.to_lowercase();
-                    output.push_str(&format!("    create_pipeline_{}();\n", pipeline_name_lower));
+        }
+        output
+    }
+    
+    fn generate_test_functions(&mut self, program: &Program) -> String {
+        let mut output = self.section("Tests");
+        output.push_str("static bool g_heidic_test_failed = false;\n");
+        output.push('\n');
+        output.push_str("// expect() / expect_frame(): a failed check fails the running test but doesn't stop it\n");
+        output.push_str("static bool heidic_expect(bool ok, const char* what) {\n");
+        output.push_str("    if (!ok) {\n");
+        output.push_str("        std::cerr << \"    \" << what << std::endl;\n");
+        output.push_str("        g_heidic_test_failed = true;\n");
+        output.push_str("    }\n");
+        output.push_str("    return ok;\n");
+        output.push_str("}\n");
+        output.push('\n');
+        let tests: Vec<&TestDef> = program.items.iter().filter_map(|item| match item {
+            Item::Test(test) => Some(test),
+            _ => None,
+        }).collect();
+        for (i, test) in tests.iter().enumerate() {
+            let function = FunctionDef {
+                name: format!("heidic_test_{}", i),
+                params: Vec::new(),
+                return_type: Type::Void,
+                body: test.body.clone(),
+                cuda_kernel: None,
+                no_reload_check: false,
+                is_hot: false,
+                strict_fp: false,
+            };
+            output.push_str(&format!("// test \"{}\"\n", test.name));
+            output.push_str(&self.generate_function(&function, 0));
+        }
+        output
+    }
+    
+    // Runs every test block and reports like `cargo test`; exits non-zero when a test fails.
+    // Tests that render (screenshot/expect_frame) only run with --render.
+    fn generate_test_runner(&self, program: &Program) -> String {
+        let Some(options) = self.test_options else { return String::new() };
+        let mut output = self.section("Test runner");
+        output.push_str("struct HeidicTest {\n");
+        output.push_str("    const char* name;\n");
+        output.push_str("    void (*run)();\n");
+        output.push_str("    bool skip;  // Renders, and the runner wasn't built with --render\n");
+        output.push_str("};\n");
+        output.push('\n');
+        output.push_str("int main(int argc, char* argv[]) {\n");
+        output.push_str(&self.generate_main_startup());
+        if options.render {
+            output.push_str("    heidic_set_render_mode(HEIDIC_RENDER_OFFSCREEN);\n");
+            output.push_str("    heidic_set_frame_capture(1);\n");
+        }
+        if options.update_golden {
+            output.push_str("    heidic_set_update_golden(1);\n");
+        }
+        output.push_str("    static const HeidicTest tests[] = {\n");
+        let tests = program.items.iter().filter_map(|item| match item {
+            Item::Test(test) => Some(test),
+            _ => None,
+        });
+        for (i, test) in tests.enumerate() {
+            let renders = statements_call(&test.body, &["screenshot", "expect_frame"]);
+            output.push_str(&format!("        {{ \"{}\", heidic_test_{}, {} }},\n", test.name, i, renders && !options.render));
+        }
+        output.push_str("    };\n");
+        output.push_str("    int passed = 0, failed = 0, skipped = 0;\n");
+        output.push_str("    for (const HeidicTest& test : tests) {\n");
+        output.push_str("        if (test.skip) {\n");
+        output.push_str("            std::cout << \"test \" << test.name << \" ... skipped (renders; build with heidic_v2 test --render)\" << std::endl;\n");
+        output.push_str("            skipped++;\n");
+        output.push_str("            continue;\n");
+        output.push_str("        }\n");
+        output.push_str("        g_heidic_test_failed = false;\n");
+        output.push_str("        test.run();\n");
+        output.push_str("        std::cout << \"test \" << test.name << \" ... \" << (g_heidic_test_failed ? \"FAILED\" : \"ok\") << std::endl;\n");
+        output.push_str("        (g_heidic_test_failed ? failed : passed)++;\n");
+        output.push_str("    }\n");
+        if !self.hot_systems.is_empty() {
+            output.push_str("    unload_hot_system();\n");
+        }
+        output.push_str("    std::cout << \"\\ntest result: \" << (failed ? \"FAILED\" : \"ok\") << \". \" << passed << \" passed; \"\n");
+        output.push_str("              << failed << \" failed; \" << skipped << \" skipped\" << std::endl;\n");
+        output.push_str("    return failed ? 1 : 0;\n");
+        output.push_str("}\n");
+        output
+    }
+    
+    /// <file>.cu: the kernels of @[launch] functions, the device mirrors of GPU-resident components
+    /// and the extern "C" functions the .cpp calls, built with nvcc (call after generate)
+    pub fn generate_cuda_source(&mut self) -> String {
+        let mut output = String::new();
+        output.push_str("// CUDA kernels of @[launch] functions\n");
+        output.push_str("// Built with nvcc and linked with the program's .cpp, which calls the extern \"C\" functions below\n");
+        output.push_str("#include <cstddef>\n");
+        output.push_str("#include <cstdint>\n");
+        output.push('\n');
+        output.push_str(&self.generate_stdlib_version_check());
+        output.push_str("#include \"stdlib/cuda_launch.h\"\n");
+        output.push('\n');
+        
+        // Arrays of AoS components are copied as they are, so the kernel needs their layout; the
+        // type checker only lets @[cuda] components hold numbers and bools, laid out the same here
+        let aos: Vec<ComponentDef> = self.cuda_components.iter().filter(|c| !c.is_soa).cloned().collect();
+        if !aos.is_empty() {
+            output.push_str(&self.section("CUDA Components"));
+            for component in &aos {
+                output.push_str(&format!("struct {} {{\n", component.name));
+                for field in &component.fields {
+                    output.push_str(&format!("    {} {};\n", self.type_to_cpp(&field.ty), field.name));
                 }
+                output.push_str("};\n\n");
             }
-            output.push_str("    heidic_main();\n");
-            // Only unload hot system if we have hot systems
-            if !self.hot_systems.is_empty() {
-                output.push_str("    unload_hot_system();\n");
-            }
-            output.push_str("    return 0;\n");
-            output.push_str("}\n");
         }
         
-        Ok(output)
+        if self.uses_gpu_sync {
+            output.push_str(&self.section("GPU-Resident Components"));
+            for component in &self.cuda_components {
+                output.push_str(&self.generate_gpu_mirror(component));
+            }
+        }
+        output.push_str(&self.section("CUDA Kernel Code"));
+        for f in self.cuda_functions.clone() {
+            output.push_str(&self.generate_cuda_kernel(&f));
+        }
+        output.push_str(&self.section("CUDA Launch Bridges"));
+        for f in &self.cuda_functions {
+            output.push_str(&self.generate_cuda_bridge(f));
+        }
+        generated_names::mangle(&output, &self.symbol_prefix, &HashSet::new())
     }
     
     // Generate DLL source file for a hot system
@@ -982,10 +2380,43 @@ impl CodeGenerator {
         output.push_str("// Auto-generated from @hot system\n");
         output.push_str("#include <cmath>\n");
         output.push_str("#include <cstdint>\n");
-        output.push_str("\n");
+        if self.checked_arithmetic.is_some() {
+            output.push_str("#include \"stdlib/checked_arithmetic.h\"\n");
+        }
+        if self.uses_strict_fp() {
+            output.push_str("#include \"stdlib/strict_fp.h\"\n");
+        }
+        // Hot code allocates from the FrameArena it is passed, never a copy of the program's arena
+        if self.uses_frame_arena {
+            output.push_str("#include \"stdlib/frame_arena.h\"\n");
+        }
+        output.push('\n');
+        
+        output.push_str(&self.generate_consts());
+        
+        // ABI descriptor: checked by load_hot_system before any function pointer is bound
+        let descriptor = self.hot_abi_descriptor(system);
+        output.push_str("// Hot-reload ABI descriptor\n");
+        output.push_str("extern \"C\" {\n");
+        output.push_str(&format!("    uint32_t heidic_hot_abi_version() {{ return ({}u << 16) | {}u; }}\n",
+            HOT_ABI_VERSION_MAJOR, HOT_ABI_VERSION_MINOR));
+        output.push_str(&format!("    const char* heidic_hot_abi_system() {{ return \"{}\"; }}\n", system.name));
+        output.push_str(&format!("    uint64_t heidic_hot_abi_signature_hash() {{ return 0x{:016x}ULL; }}\n",
+            fnv1a_64(&self.hot_abi_signatures(system))));
+        output.push_str(&format!("    uint64_t heidic_hot_abi_layout_hash() {{ return 0x{:016x}ULL; }}\n",
+            fnv1a_64(&self.hot_abi_layouts())));
+        output.push_str(&format!("    const char* heidic_hot_abi_descriptor() {{ return \"{}\"; }}\n",
+            descriptor.replace('\n', "\\n")));
+        output.push_str("}\n");
+        output.push('\n');
         
         // Generate function implementations with extern "C"
+        self.hot_dll = Some(system.name.clone());
         for func in &system.functions {
+            let strict_fp = func.strict_fp || self.strict_fp;
+            if strict_fp {
+                output.push_str("HEIDIC_STRICT_FP_BEGIN\n");
+            }
             output.push_str("extern \"C\" {\n");
             let return_type = self.type_to_cpp(&func.return_type);
             output.push_str(&format!("    {} {}(", return_type, func.name));
@@ -1023,12 +2454,194 @@ impl CodeGenerator {
             
             output.push_str("    }\n");
             output.push_str("}\n");
+            if strict_fp {
+                output.push_str("HEIDIC_STRICT_FP_END\n");
+            }
             output.push_str("\n");
         }
+        self.hot_dll = None;
         
         output
     }
     
+    // Function signatures exported by a hot system, one per line
+    fn hot_abi_signatures(&self, system: &SystemDef) -> String {
+        let mut out = String::new();
+        for func in &system.functions {
+            let params: Vec<String> = func.params.iter().map(|p| self.type_to_cpp(&p.ty)).collect();
+            out.push_str(&format!("fn {}({}) -> {}\n", func.name, params.join(", "), self.type_to_cpp(&func.return_type)));
+        }
+        out
+    }
+    
+    // Component layouts shared between the host and hot DLLs (sorted so the hash is deterministic)
+    fn hot_abi_layouts(&self) -> String {
+        let mut names: Vec<&String> = self.components.keys().collect();
+        names.sort();
+        let mut out = String::new();
+        for name in names {
+            let component = &self.components[name];
+            let fields: Vec<String> = component.fields.iter()
+                .map(|f| format!("{} {}", self.type_to_cpp(&f.ty), f.name))
+                .collect();
+            let kind = if component.is_soa { "component_soa" } else { "component" };
+            out.push_str(&format!("{} {} {{ {} }}\n", kind, name, fields.join("; ")));
+        }
+        out
+    }
+    
+    // Human-readable ABI descriptor, printed when a DLL is refused
+    fn hot_abi_descriptor(&self, system: &SystemDef) -> String {
+        format!("heidic-hot-abi {}.{} system {}\n{}{}",
+            HOT_ABI_VERSION_MAJOR, HOT_ABI_VERSION_MINOR, system.name,
+            self.hot_abi_signatures(system), self.hot_abi_layouts())
+    }
+    
+    // Generate log_reload_event(): one line per reload attempt, appended to heidic_reload.log and echoed
+    fn generate_reload_log(&self) -> String {
+        let mut output = String::new();
+        output.push_str("#include <ctime>\n");
+        output.push_str("#include <cstdio>\n");
+        output.push('\n');
+        output.push_str("static void log_reload_event(const char* kind, const char* target, bool ok, const std::string& detail) {\n");
+        output.push_str("    static FILE* log_file = nullptr;\n");
+        output.push_str("    char stamp[32];\n");
+        output.push_str("    time_t now = time(nullptr);\n");
+        output.push_str("    strftime(stamp, sizeof(stamp), \"%Y-%m-%d %H:%M:%S\", localtime(&now));\n");
+        output.push_str("    if (!log_file) {\n");
+        output.push_str("        log_file = fopen(\"heidic_reload.log\", \"a\");\n");
+        output.push_str("        if (log_file) {\n");
+        output.push_str("            fprintf(log_file, \"=== session started %s ===\\n\", stamp);\n");
+        output.push_str("        }\n");
+        output.push_str("    }\n");
+        output.push_str("    const char* status = ok ? \"ok\" : \"FAILED\";\n");
+        output.push_str("    std::cout << \"[Hot-Reload] \" << kind << \" \" << target << \": \" << status << \" (\" << detail << \")\" << std::endl;\n");
+        output.push_str("    if (log_file) {\n");
+        output.push_str("        fprintf(log_file, \"%s  %-9s %-32s %-6s %s\\n\", stamp, kind, target, status, detail.c_str());\n");
+        output.push_str("        fflush(log_file);\n");
+        output.push_str("    }\n");
+        output.push_str("}\n");
+        output.push('\n');
+        output
+    }
+    
+    // Generate verify_hot_abi(): compares a loaded DLL's descriptor against the host's expectations
+    fn generate_hot_abi_verifier(&self, output: &mut String) {
+        output.push_str("// Hot-reload ABI verification\n");
+        output.push_str("#include <cstring>\n");
+        output.push_str("typedef uint32_t (*heidic_hot_abi_version_ptr)();\n");
+        output.push_str("typedef const char* (*heidic_hot_abi_str_ptr)();\n");
+        output.push_str("typedef uint64_t (*heidic_hot_abi_hash_ptr)();\n");
+        output.push_str(&format!("static const uint32_t HEIDIC_HOT_ABI_MAJOR = {};\n", HOT_ABI_VERSION_MAJOR));
+        output.push_str(&format!("static const uint32_t HEIDIC_HOT_ABI_MINOR = {};\n", HOT_ABI_VERSION_MINOR));
+        output.push_str("struct HotAbiExpectation { const char* system; uint64_t signature_hash; uint64_t layout_hash; const char* descriptor; };\n");
+        output.push_str("static const HotAbiExpectation g_hot_abi_expected[] = {\n");
+        let layout_hash = fnv1a_64(&self.hot_abi_layouts());
+        for system in &self.hot_systems {
+            output.push_str(&format!("    {{ \"{}\", 0x{:016x}ULL, 0x{:016x}ULL, \"{}\" }},\n",
+                system.name,
+                fnv1a_64(&self.hot_abi_signatures(system)),
+                layout_hash,
+                self.hot_abi_descriptor(system).replace('\n', "\\n")));
+        }
+        output.push_str("};\n");
+        output.push('\n');
+        output.push_str("static bool verify_hot_abi(HMODULE dll, const char* dll_path) {\n");
+        output.push_str("    auto get_version = (heidic_hot_abi_version_ptr)GetProcAddress(dll, \"heidic_hot_abi_version\");\n");
+        output.push_str("    auto get_system = (heidic_hot_abi_str_ptr)GetProcAddress(dll, \"heidic_hot_abi_system\");\n");
+        output.push_str("    auto get_signature_hash = (heidic_hot_abi_hash_ptr)GetProcAddress(dll, \"heidic_hot_abi_signature_hash\");\n");
+        output.push_str("    auto get_layout_hash = (heidic_hot_abi_hash_ptr)GetProcAddress(dll, \"heidic_hot_abi_layout_hash\");\n");
+        output.push_str("    auto get_descriptor = (heidic_hot_abi_str_ptr)GetProcAddress(dll, \"heidic_hot_abi_descriptor\");\n");
+        output.push_str("    if (!get_version || !get_system || !get_signature_hash || !get_layout_hash) {\n");
+        output.push_str("        std::cerr << \"[Hot-Reload] Refusing to load \" << dll_path << \": no ABI descriptor (rebuild it with the current compiler)\" << std::endl;\n");
+        output.push_str("        return false;\n");
+        output.push_str("    }\n");
+        output.push_str("    uint32_t version = get_version();\n");
+        output.push_str("    if ((version >> 16) != HEIDIC_HOT_ABI_MAJOR || (version & 0xFFFF) > HEIDIC_HOT_ABI_MINOR) {\n");
+        output.push_str("        std::cerr << \"[Hot-Reload] Refusing to load \" << dll_path << \": ABI version \" << (version >> 16) << \".\" << (version & 0xFFFF)\n");
+        output.push_str("                  << \" is incompatible with host ABI \" << HEIDIC_HOT_ABI_MAJOR << \".\" << HEIDIC_HOT_ABI_MINOR << std::endl;\n");
+        output.push_str("        return false;\n");
+        output.push_str("    }\n");
+        output.push_str("    const char* system = get_system();\n");
+        output.push_str("    for (const auto& expected : g_hot_abi_expected) {\n");
+        output.push_str("        if (std::strcmp(expected.system, system) != 0) continue;\n");
+        output.push_str("        const char* mismatch = nullptr;\n");
+        output.push_str("        if (get_signature_hash() != expected.signature_hash) mismatch = \"function signatures\";\n");
+        output.push_str("        else if (get_layout_hash() != expected.layout_hash) mismatch = \"component layouts\";\n");
+        output.push_str("        if (mismatch) {\n");
+        output.push_str("            std::cerr << \"[Hot-Reload] Refusing to load \" << dll_path << \": \" << mismatch << \" differ from the host\" << std::endl;\n");
+        output.push_str("            std::cerr << \"  host expects:\\n\" << expected.descriptor << std::endl;\n");
+        output.push_str("            if (get_descriptor) std::cerr << \"  DLL provides:\\n\" << get_descriptor() << std::endl;\n");
+        output.push_str("            return false;\n");
+        output.push_str("        }\n");
+        output.push_str("        return true;\n");
+        output.push_str("    }\n");
+        output.push_str("    std::cerr << \"[Hot-Reload] Refusing to load \" << dll_path << \": unknown hot system '\" << system << \"'\" << std::endl;\n");
+        output.push_str("    return false;\n");
+        output.push_str("}\n");
+        output.push('\n');
+    }
+    
+    // --hot-rebuild: an edited .hd source is compiled again, which rewrites the DLL sources, and a
+    // changed DLL source is compiled into its DLL, which the DLL watch then loads. The frame waits
+    // while a build runs; a failed build leaves the previous code running.
+    fn generate_hot_rebuild(&self, rebuild: &HotRebuild) -> String {
+        let quote = |arg: &str| format!("\"{}\"", arg);
+        let mut output = String::from("// Automatic rebuilds (--hot-rebuild)\n");
+        output.push_str("#include <cstdlib>\n");
+        output.push('\n');
+        output.push_str(&format!("static HeidicFileWatch g_hot_rebuild_source {{{}}};\n", cpp_string(&rebuild.source)));
+        for system in &self.hot_systems {
+            let name = system.name.to_lowercase();
+            output.push_str(&format!("static HeidicFileWatch g_hot_rebuild_{} {{\"{}_hot.dll.cpp\"}};\n", name, name));
+        }
+        output.push('\n');
+        output.push_str("static bool run_hot_build(const char* target, const char* command) {\n");
+        output.push_str("    std::cout << \"[Hot-Reload] Building \" << target << \": \" << command << std::endl;\n");
+        output.push_str("    bool ok = std::system(command) == 0;\n");
+        output.push_str("    log_reload_event(\"build\", target, ok, ok ? \"built\" : \"build failed; still running the previous code\");\n");
+        output.push_str("    return ok;\n");
+        output.push_str("}\n");
+        output.push('\n');
+        output.push_str("static void rebuild_changed_hot_sources() {\n");
+        let command = format!("{} compile --hot-rebuild {}", quote(&rebuild.heidic), quote(&rebuild.source));
+        output.push_str("    if (heidic_watch_changed(g_hot_rebuild_source)) {\n");
+        output.push_str(&format!("        run_hot_build({}, {});\n", cpp_string(&rebuild.source), cpp_string(&command)));
+        output.push_str("    }\n");
+        for system in &self.hot_systems {
+            let name = system.name.to_lowercase();
+            let mut command = format!("{} -std=c++17 -fwrapv{} -shared -o {} {}", rebuild.compiler, self.strict_fp_flags(),
+                quote(&format!("{}.dll", name)), quote(&format!("{}_hot.dll.cpp", name)));
+            for flag in &rebuild.flags {
+                command.push(' ');
+                command.push_str(flag);
+            }
+            output.push_str(&format!("    if (heidic_watch_changed(g_hot_rebuild_{})) {{\n", name));
+            output.push_str(&format!("        run_hot_build(\"{}.dll\", {});\n", name, cpp_string(&command)));
+            output.push_str("    }\n");
+        }
+        output.push_str("}\n");
+        output.push('\n');
+        output
+    }
+    
+    // The host calls a hot-reloadable function through a pointer into the loaded DLL
+    fn hot_function_pointer_decl(&self, func: &FunctionDef) -> String {
+        let params: Vec<String> = func.params.iter().map(|param| self.type_to_cpp(&param.ty)).collect();
+        let mut output = format!("// Hot-reloadable function: {}\n", func.name);
+        output.push_str(&format!("typedef {} (*{}_ptr)({});\n", self.type_to_cpp(&func.return_type), func.name, params.join(", ")));
+        output.push_str(&format!("extern {}_ptr g_{};\n\n", func.name, func.name));
+        output
+    }
+    
+    // Whether calls to `name` go through a hot DLL's function pointer. Inside a DLL, calls to its
+    // own functions are direct.
+    fn calls_through_hot_pointer(&self, name: &str) -> bool {
+        self.hot_systems.iter()
+            .filter(|system| self.hot_dll.as_deref() != Some(system.name.as_str()))
+            .any(|system| system.functions.iter().any(|f| f.name == name))
+    }
+    
     // Get list of hot systems (for generating DLL files)
     pub fn get_hot_systems(&self) -> &Vec<SystemDef> {
         &self.hot_systems
@@ -1065,33 +2678,87 @@ impl CodeGenerator {
         }
         output.push_str("\n");
         
+        // Entities whose component wasn't added or written since the previous migration already
+        // hold migrated values; the component's version skips the scan when none was
+        output.push_str("    // Change tick of the previous migration: older components are already migrated\n");
+        output.push_str("    static uint32_t migrated_tick = 0;\n");
+        output.push_str(&format!("    if (g_storage.component_version<{}>() <= migrated_tick) {{\n", component.name));
+        output.push_str(&format!("        std::cout << \"[Component Migration] No {} entities changed since the last migration\" << std::endl;\n", component.name));
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push('\n');
+        
         // Collect all entities with this component first (to avoid iterator invalidation)
-        output.push_str(&format!("    // Collect all entities with {} component (to avoid iterator invalidation during migration)\n", component.name));
+        output.push_str(&format!("    // Collect the {} entities touched since then (to avoid iterator invalidation during migration)\n", component.name));
         output.push_str("    std::vector<EntityId> entities_to_migrate;\n");
-        output.push_str(&format!("    g_storage.for_each<{}>([&](EntityId e, {}&) {{\n", component.name, component.name));
-        output.push_str("        entities_to_migrate.push_back(e);\n");
+        output.push_str("    int skipped_count = 0;\n");
+        // A component_soa migrates one entity's values (its Row) at a time
+        let value_type = if component.is_soa { format!("{}::Row", component.name) } else { component.name.clone() };
+        output.push_str(&format!("    g_storage.for_each<{}>([&](EntityId e, const {}&) {{\n", component.name, value_type));
+        output.push_str(&format!("        if (g_storage.changed_since<{}>(e, migrated_tick)) {{\n", component.name));
+        output.push_str("            entities_to_migrate.push_back(e);\n");
+        output.push_str("        } else {\n");
+        output.push_str("            skipped_count++;\n");
+        output.push_str("        }\n");
         output.push_str(&format!("    }});\n"));
         output.push_str("\n");
         output.push_str("    // Migrate each entity\n");
         output.push_str("    int migrated_count = 0;\n");
         output.push_str("    for (EntityId e : entities_to_migrate) {\n");
-        output.push_str(&format!("        {}* old_comp_ptr = g_storage.get_component<{}>(e);\n", component.name, component.name));
-        output.push_str("        if (!old_comp_ptr) continue;  // Entity no longer has component\n");
-        output.push_str(&format!("        {} old_comp = *old_comp_ptr;  // Copy old data before removing\n", component.name));
+        if component.is_soa {
+            output.push_str(&format!("        std::optional<{}> old_row = g_storage.get_row<{}>(e);\n", value_type, component.name));
+            output.push_str("        if (!old_row) continue;  // Entity no longer has component\n");
+            output.push_str(&format!("        {} old_comp = *old_row;\n", value_type));
+        } else {
+            output.push_str(&format!("        {}* old_comp_ptr = g_storage.get_component<{}>(e);\n", component.name, component.name));
+            output.push_str("        if (!old_comp_ptr) continue;  // Entity no longer has component\n");
+            output.push_str(&format!("        {} old_comp = *old_comp_ptr;  // Copy old data before removing\n", component.name));
+        }
         output.push_str("\n");
         output.push_str(&format!("        // Create new component instance, zero-initialized\n"));
-        output.push_str(&format!("        {} new_comp{{}};\n", component.name));
+        output.push_str(&format!("        {} new_comp{{}};\n", value_type));
         output.push_str("\n");
         
+        // A migrate function whose old layout was the previous one builds the new value. The
+        // storage only holds the current layout, so the old value gets the fields both share.
+        let migrations: Vec<(&MigrateDef, &StructDef)> = self.migrations.iter()
+            .filter(|migrate| migrate.function.name == component.name)
+            .filter_map(|migrate| Some((migrate, self.structs.get(migrate.old_struct()?)?)))
+            .collect();
+        let mut pad = "        ";
+        for (i, (migrate, old)) in migrations.iter().enumerate() {
+            let signature: String = old.fields.iter().map(|field| format!("{}:{};", field.name, self.type_to_cpp(&field.ty))).collect();
+            let keyword = if i == 0 { "if" } else { "} else if" };
+            output.push_str(&format!("        {} (old_sig == \"{}\") {{  // migrate {}(old: {})\n", keyword, signature, component.name, old.name));
+            output.push_str(&format!("            {} old{{}};\n", old.name));
+            for field in &old.fields {
+                let shared = component.fields.iter().any(|f| f.name == field.name && self.type_to_cpp(&f.ty) == self.type_to_cpp(&field.ty));
+                if shared {
+                    output.push_str(&format!("            old.{} = old_comp.{};\n", field.name, field.name));
+                }
+            }
+            output.push_str(&format!("            new_comp = {}(old);\n", migrate.cpp_name()));
+            pad = "            ";
+        }
+        if !migrations.is_empty() {
+            output.push_str("        } else {\n");
+        }
+
         // Copy fields that existed in old version, use defaults for new fields
-        output.push_str("        // Copy fields that existed in old version\n");
+        output.push_str(&format!("{}// Copy fields that existed in old version\n", pad));
         for field in &component.fields {
-            let default_val = self.get_default_value_for_type(&field.ty);
-            output.push_str(&format!("        if (has_{}_in_old) {{\n", field.name));
-            output.push_str(&format!("            new_comp.{} = old_comp.{};  // Copy existing field\n", field.name, field.name));
-            output.push_str(&format!("        }} else {{\n"));
-            output.push_str(&format!("            new_comp.{} = {};  // New field, use default\n", field.name, default_val));
-            output.push_str(&format!("        }}\n"));
+            let default_val = match &field.ty {
+                Type::Array(element) if component.is_soa => self.get_default_value_for_type(element),
+                ty => self.get_default_value_for_type(ty),
+            };
+            output.push_str(&format!("{}if (has_{}_in_old) {{\n", pad, field.name));
+            output.push_str(&format!("{}    new_comp.{} = old_comp.{};  // Copy existing field\n", pad, field.name, field.name));
+            output.push_str(&format!("{}}} else {{\n", pad));
+            output.push_str(&format!("{}    new_comp.{} = {};  // New field, use default\n", pad, field.name, default_val));
+            output.push_str(&format!("{}}}\n", pad));
+        }
+        if !migrations.is_empty() {
+            output.push_str("        }\n");
         }
         
         output.push_str("\n");
@@ -1100,9 +2767,10 @@ impl CodeGenerator {
         output.push_str(&format!("        g_storage.add_component<{}>(e, new_comp);\n", component.name));
         output.push_str("        migrated_count++;\n");
         output.push_str("    }\n");
+        output.push_str("    migrated_tick = g_storage.change_tick();\n");
         
         output.push_str("\n");
-        output.push_str(&format!("    std::cout << \"[Component Migration] Migrated \" << migrated_count << \" {} entities\" << std::endl;\n", 
+        output.push_str(&format!("    std::cout << \"[Component Migration] Migrated \" << migrated_count << \" {} entities (\" << skipped_count << \" unchanged since the last migration)\" << std::endl;\n", 
             component.name));
         output.push_str("}\n");
         output.push_str("\n");
@@ -1124,6 +2792,70 @@ impl CodeGenerator {
         }.to_string()
     }
     
+    // Interfaces are resolved at compile time: each interface is a class template specialized
+    // per implementing type, and each method gets a forwarding function template so calls like
+    // apply_damage(health, 5.0) dispatch on the receiver's static type (monomorphization)
+    fn generate_interfaces(&self, program: &Program) -> String {
+        let mut output = String::new();
+        for item in &program.items {
+            let Item::Interface(iface) = item else { continue };
+            output.push_str(&format!("// interface {}\n", iface.name));
+            output.push_str(&format!("template<typename Self> struct {};  // Specialized by each impl block\n", iface.name));
+            for method in &iface.methods {
+                let params: Vec<String> = method.params.iter().skip(1)
+                    .map(|p| format!("{} {}", self.type_to_cpp(&p.ty), p.name))
+                    .collect();
+                let args: Vec<&str> = method.params.iter().map(|p| p.name.as_str()).collect();
+                let mut signature = vec!["Self& self".to_string()];
+                signature.extend(params);
+                output.push_str(&format!("template<typename Self> inline {} {}({}) {{ return {}<Self>::{}({}); }}\n",
+                    self.type_to_cpp(&method.return_type), method.name, signature.join(", "),
+                    iface.name, method.name, args.join(", ")));
+            }
+            output.push('\n');
+        }
+        for imp in &self.impls {
+            output.push_str(&format!("template<> struct {}<{}> {{\n", imp.interface, imp.target));
+            for (method, _) in &imp.methods {
+                let params: Vec<String> = method.params.iter().map(|p| self.param_to_cpp(p)).collect();
+                output.push_str(&format!("    static {} {}({});\n",
+                    self.type_to_cpp(&method.return_type), method.name, params.join(", ")));
+            }
+            output.push_str("};\n\n");
+        }
+        output
+    }
+    
+    // Functions taking interface-typed parameters become templates over the concrete type
+    fn interface_template_header(&self, params: &[Param]) -> String {
+        let type_params: Vec<String> = params.iter()
+            .filter(|p| matches!(&p.ty, Type::Struct(name) if self.interfaces.contains_key(name)))
+            .map(|p| format!("typename T_{}", p.name))
+            .collect();
+        if type_params.is_empty() {
+            String::new()
+        } else {
+            format!("template<{}> ", type_params.join(", "))
+        }
+    }
+    
+    // Immutable locals become `const` so the C++ optimizer can rely on them. The type checker has
+    // rejected assigning to a let without `mut` and passing one to a parameter that can modify it.
+    fn is_const_local(&self, mutable: bool) -> bool {
+        !mutable
+    }
+    
+    fn param_to_cpp(&self, param: &Param) -> String {
+        match &param.ty {
+            Type::Struct(name) if self.interfaces.contains_key(name) => format!("T_{}& {}", param.name, param.name),
+            // Queries are passed by reference so the caller can write back what the function changed
+            Type::Query(_) => format!("{}& {}", self.type_to_cpp(&param.ty), param.name),
+            // Interface method receivers are passed by reference so methods can mutate the component
+            ty if param.name == "self" => format!("{}& self", self.type_to_cpp(ty)),
+            ty => format!("{} {}", self.type_to_cpp(ty), param.name),
+        }
+    }
+    
     fn generate_struct(&self, s: &StructDef, indent: usize) -> String {
         let mut output = format!("struct {} {{\n", s.name);
         for field in &s.fields {
@@ -1136,42 +2868,179 @@ impl CodeGenerator {
         output
     }
     
+    // query<A, B, ...>: the entities that have every component, and parallel arrays of their
+    // components (a component_soa's are its own field arrays). Filters are checked by the loops
+    // over the query, so they add no arrays.
+    fn generate_query_struct(&self, component_types: &[Type]) -> String {
+        let mut output = format!("struct {} {{\n", self.type_to_cpp(&Type::Query(component_types.to_vec())));
+        output.push_str("    std::vector<EntityId> entities;\n");
+        for component in query_components(component_types) {
+            if self.is_component_soa(component) {
+                output.push_str(&format!("    {} {};\n", component, component_array_name(component)));
+            } else {
+                output.push_str(&format!("    std::vector<{}> {};\n", component, component_array_name(component)));
+            }
+        }
+        output.push_str("    size_t size() const { return entities.size(); }\n");
+        output.push_str("};\n\n");
+        output
+    }
+
+    // heidic_gather_Query_A_B() copies the query's components out of g_storage and
+    // heidic_commit() writes them back, after the function taking the query has run. Writes
+    // that changed<T> filters should see are recorded by the loops themselves (mark_changed).
+    fn generate_query_gather(&self, component_types: &[Type]) -> String {
+        let query = self.type_to_cpp(&Type::Query(component_types.to_vec()));
+        let components = query_components(component_types);
+        let Some((first, rest)) = components.split_first() else {
+            return String::new();
+        };
+        let push = |component: &str, value: &str| if self.is_component_soa(component) {
+            format!("query.{}.heidic_push({});", component_array_name(component), value)
+        } else {
+            format!("query.{}.push_back({});", component_array_name(component), value)
+        };
+        let mut output = format!("static inline {} {}() {{\n", query, query_gather_function(&query));
+        output.push_str(&format!("    {} query;\n", query));
+        output.push_str(&format!("    g_storage.for_each<{}>([&](EntityId entity, const auto& value) {{\n", first));
+        for component in rest {
+            output.push_str(&format!("        if (!g_storage.has_component<{}>(entity)) return;\n", component));
+        }
+        output.push_str("        query.entities.push_back(entity);\n");
+        output.push_str(&format!("        {}\n", push(first, "value")));
+        for component in rest {
+            let value = if self.is_component_soa(component) {
+                format!("*g_storage.get_row<{}>(entity)", component)
+            } else {
+                format!("*g_storage.get_component<{}>(entity)", component)
+            };
+            output.push_str(&format!("        {}\n", push(component, &value)));
+        }
+        output.push_str("    });\n");
+        output.push_str("    return query;\n");
+        output.push_str("}\n\n");
+
+        output.push_str(&format!("static inline void heidic_commit(const {}& query) {{\n", query));
+        output.push_str("    for (size_t i = 0; i < query.size(); ++i) {\n");
+        for component in &components {
+            let array = component_array_name(component);
+            if self.is_component_soa(component) {
+                output.push_str(&format!("        g_storage.set_row<{}>(query.entities[i], query.{}.heidic_row(i));\n", component, array));
+            } else {
+                output.push_str(&format!("        if ({}* value = g_storage.get_component<{}>(query.entities[i])) *value = query.{}[i];\n",
+                    component, component, array));
+            }
+        }
+        output.push_str("    }\n");
+        output.push_str("}\n\n");
+        output
+    }
+
     fn generate_component(&self, c: &ComponentDef, indent: usize) -> String {
         let mut output = format!("struct {} {{\n", c.name);
-        for field in &c.fields {
+        if c.is_soa {
+            output.push_str(&self.generate_soa_row(c, indent));
+        }
+        for field in component_members(c) {
             output.push_str(&format!("{}    {} {};\n", 
                 self.indent(indent + 1), 
                 self.type_to_cpp(&field.ty), 
                 field.name));
         }
+        if c.is_soa {
+            output.push_str(&self.generate_soa_methods(c, indent));
+        }
+        // @[pool]: EntityStorage allocates the storage once and never grows it (stdlib/entity_storage.h)
+        if let Some(capacity) = c.pool_capacity {
+            output.push_str(&format!("{}    static constexpr size_t heidic_pool_capacity = {};\n", self.indent(indent + 1), capacity));
+            output.push_str(&format!("{}    static constexpr const char* heidic_pool_name = \"{}\";\n", self.indent(indent + 1), c.name));
+        }
         output.push_str("};\n\n");
         output
     }
     
+    // A component_soa is its own structure of arrays; Row is one entity's values, which
+    // EntityStorage spreads over the field arrays (stdlib/entity_storage.h). Its @cold fields
+    // share one array of Cold structs, so loops over the hot arrays don't pull them into cache.
+    fn generate_soa_row(&self, c: &ComponentDef, indent: usize) -> String {
+        let pad = self.indent(indent + 1);
+        let element = |field: &Field| match &field.ty {
+            Type::Array(element) => self.type_to_cpp(element),
+            ty => self.type_to_cpp(ty),
+        };
+        let mut output = format!("{}    struct Row {{\n", pad);
+        for field in &c.fields {
+            output.push_str(&format!("{}        {} {};\n", pad, element(field), field.name));
+        }
+        output.push_str(&format!("{}    }};\n", pad));
+        if c.has_cold_fields() {
+            output.push_str(&format!("{}    struct Cold {{\n", pad));
+            for field in c.fields.iter().filter(|f| f.cold) {
+                output.push_str(&format!("{}        {} {};\n", pad, element(field), field.name));
+            }
+            output.push_str(&format!("{}    }};\n", pad));
+        }
+        output
+    }
+    
+    // Element-wise operations on every field array, used by EntityStorage's component_soa storage
+    fn generate_soa_methods(&self, c: &ComponentDef, indent: usize) -> String {
+        let pad = self.indent(indent + 1);
+        let arrays: Vec<String> = component_members(c).into_iter().map(|f| f.name).collect();
+        let each = |op: &dyn Fn(&str) -> String| arrays.iter().map(|f| op(f)).collect::<Vec<_>>().join(" ");
+        // Fields are reached through this-> so a field named like a parameter still works
+        let rows: Vec<String> = c.fields.iter().map(|f| format!("this->{}", soa_element(c, &f.name, "i"))).collect();
+        let cold: Vec<String> = c.fields.iter().filter(|f| f.cold).map(|f| format!("row.{}", f.name)).collect();
+        let store = |f: &str| if f == "heidic_cold" { format!("Cold{{{}}}", cold.join(", ")) } else { format!("row.{}", f) };
+        let methods = [
+            format!("Row heidic_row(size_t i) const {{ return Row{{{}}}; }}", rows.join(", ")),
+            format!("void heidic_push(const Row& row) {{ {} }}", each(&|f| format!("this->{}.push_back({});", f, store(f)))),
+            format!("void heidic_set(size_t i, const Row& row) {{ {} }}", each(&|f| format!("this->{}[i] = {};", f, store(f)))),
+            format!("void heidic_move(size_t from, size_t to) {{ {} }}", each(&|f| format!("this->{f}[to] = std::move(this->{f}[from]);", f = f))),
+            format!("void heidic_pop() {{ {} }}", each(&|f| format!("this->{}.pop_back();", f))),
+            format!("void heidic_reserve(size_t n) {{ {} }}", each(&|f| format!("this->{}.reserve(n);", f))),
+            format!("size_t heidic_bytes() const {{ return 0{}; }}",
+                arrays.iter().map(|f| format!(" + this->{f}.capacity() * sizeof(this->{f}[0])", f = f)).collect::<String>()),
+        ];
+        let mut output = String::new();
+        for method in methods {
+            output.push_str(&format!("{}    {}\n", pad, method));
+        }
+        output
+    }
+    
     fn generate_component_registry(&self) -> String {
         let mut output = String::new();
         
         // Include ComponentRegistry header
-        output.push_str("// Component Registry and Reflection\n");
+        output.push_str(&self.section("Component Registry and Reflection"));
         output.push_str("#include \"stdlib/component_registry.h\"\n");
         output.push_str("\n");
         
         // Generate component metadata and reflection data for each component
-        for (_comp_name, component) in &self.components {
+        for component in self.sorted_components() {
             output.push_str(&self.generate_component_metadata(component));
         }
         
         // Generate registration function
-        output.push_str("// Component Registry Initialization\n");
+        output.push_str(&self.section("Component Registry Initialization"));
         output.push_str("void register_all_components() {\n");
-        for (comp_name, _) in &self.components {
-            output.push_str(&format!("    ComponentRegistry::register_component<{}>();\n", comp_name));
+        for component in self.sorted_components() {
+            output.push_str(&format!("    ComponentRegistry::register_component<{}>();\n", component.name));
         }
         output.push_str("}\n\n");
         
         output
     }
     
+    // Components in name order: HashMap order changes between runs, and registration order
+    // decides runtime component IDs
+    fn sorted_components(&self) -> Vec<&ComponentDef> {
+        let mut components: Vec<&ComponentDef> = self.components.values().collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        components
+    }
+    
     fn generate_component_metadata(&self, component: &ComponentDef) -> String {
         let mut output = String::new();
         let comp_name = &component.name;
@@ -1182,94 +3051,294 @@ impl CodeGenerator {
         output.push_str(&format!("template<>\n"));
         output.push_str(&format!("struct ComponentMetadata<{}> {{\n", comp_name));
         output.push_str(&format!("    static constexpr const char* name() {{ return \"{}\"; }}\n", comp_name));
-        output.push_str(&format!("    static constexpr uint32_t id() {{ return component_id<{}>(); }}\n", comp_name));
-        output.push_str(&format!("    static constexpr size_t size() {{ return sizeof({}); }}\n", comp_name));
-        output.push_str(&format!("    static constexpr size_t alignment() {{ return alignof({}); }}\n", comp_name));
+        output.push_str(&format!("    static uint32_t id() {{ return component_id<{}>(); }}\n", comp_name));
+        // Sizes and offsets come from the layout model, so tools reading this data (and the
+        // compiler itself) agree with it; the static_asserts below catch a wrong --target-abi.
+        // Fields the model can't lay out fall back to asking the C++ compiler.
+        let members = component_members(component);
+        let layout = self.layouts().field_offsets(&members, 0).ok();
+        let (size, alignment) = match &layout {
+            Some((_, layout)) => (layout.size.to_string(), layout.align.to_string()),
+            None => (format!("sizeof({})", comp_name), format!("alignof({})", comp_name)),
+        };
+        output.push_str(&format!("    static constexpr size_t size() {{ return {}; }}\n", size));
+        output.push_str(&format!("    static constexpr size_t alignment() {{ return {}; }}\n", alignment));
         output.push_str(&format!("    static constexpr bool is_soa() {{ return {}; }}\n", if component.is_soa { "true" } else { "false" }));
+        output.push_str(&format!("    static constexpr const char* doc() {{ return {}; }}\n", cpp_string(component.doc.as_deref().unwrap_or(""))));
         output.push_str("};\n\n");
         
         // Generate field reflection data
         output.push_str(&format!("// Field Reflection Data: {}\n", comp_name));
         output.push_str(&format!("template<>\n"));
         output.push_str(&format!("struct ComponentFields<{}> {{\n", comp_name));
-        output.push_str(&format!("    static constexpr size_t field_count = {};\n", component.fields.len()));
+        output.push_str(&format!("    static constexpr size_t field_count = {};\n", members.len()));
         output.push_str("    struct FieldInfo {\n");
         output.push_str("        const char* name;\n");
         output.push_str("        const char* type_name;\n");
         output.push_str("        size_t offset;\n");
         output.push_str("        size_t size;\n");
+        output.push_str("        const char* doc;  // `///` comment above the field, or \"\"\n");
+        output.push_str("        const char* unit;  // @[unit(\"...\")], or \"\"\n");
+        output.push_str("        bool has_range;  // @[range(min, max)]\n");
+        output.push_str("        double range_min;\n");
+        output.push_str("        double range_max;\n");
         output.push_str("    };\n");
-        output.push_str("    static FieldInfo get_fields() {\n");
+        output.push_str("    static const FieldInfo* get_fields() {\n");
         output.push_str("        static FieldInfo fields[] = {\n");
         
-        // Generate field info using offsetof() for accurate offsets
-        for field in &component.fields {
-            let field_type_size = self.estimate_type_size(&field.ty);
+        for (i, field) in members.iter().enumerate() {
             let field_type_name = self.type_to_cpp(&field.ty);
+            let (offset, field_type_size) = match &layout {
+                Some((offsets, _)) => (offsets[i].to_string(), self.layouts().layout(&field.ty).map(|l| l.size).unwrap_or_default().to_string()),
+                None => (format!("offsetof({}, {})", comp_name, field.name), format!("sizeof({})", field_type_name)),
+            };
             
-            output.push_str(&format!("            {{ \"{}\", \"{}\", offsetof({}, {}), {} }},\n",
-                field.name, field_type_name, comp_name, field.name, field_type_size));
+            let (min, max) = field.range.unwrap_or_default();
+            output.push_str(&format!("            {{ \"{}\", \"{}\", {}, {}, {}, \"{}\", {}, {:?}, {:?} }},\n",
+                field.name, field_type_name, offset, field_type_size, cpp_string(field.doc.as_deref().unwrap_or("")),
+                field.unit.as_deref().unwrap_or(""), field.range.is_some(), min, max));
         }
         
         output.push_str("        };\n");
         output.push_str("        return fields;\n");
         output.push_str("    }\n");
-        output.push_str("};\n\n");
+        output.push_str("};\n");
+        if let Some((offsets, layout)) = &layout {
+            output.push_str(&layout_check(&format!("sizeof({})", comp_name), layout.size));
+            output.push_str(&layout_check(&format!("alignof({})", comp_name), layout.align));
+            let standard_layout = self.layouts().is_standard_layout(&Type::Component(comp_name.clone()));
+            for (field, offset) in members.iter().zip(offsets).filter(|_| standard_layout) {
+                output.push_str(&layout_check(&format!("offsetof({}, {})", comp_name, field.name), *offset));
+            }
+        }
+        output.push('\n');
         
         output
     }
     
-    fn estimate_type_size(&self, ty: &Type) -> usize {
-        match ty {
-            Type::I32 => 4,
-            Type::I64 => 8,
-            Type::F32 => 4,
-            Type::F64 => 8,
-            Type::Bool => 1,
-            Type::String => 32, // std::string size (approximate)
-            Type::Array(_) => 24, // std::vector size (approximate)
-            Type::Vec2 => 8,
-            Type::Vec3 => 12,
-            Type::Vec4 => 16,
-            Type::Mat4 => 64,
-            Type::Struct(_name) => 16, // Default struct size (would need actual struct lookup)
-            Type::Component(_name) => 16, // Default component size
-            _ => 8, // Default pointer size
+    fn needs_gpu_config(&self) -> bool {
+        self.gpu_config.is_some() || !self.image_resources.is_empty() || self.uses_pipeline_statistics() || self.uses_non_solid_fill()
+    }
+    
+    fn uses_non_solid_fill(&self) -> bool {
+        self.pipelines.iter().any(|p| p.state.polygon != PolygonMode::Fill)
+    }
+    
+    fn uses_pipeline_statistics(&self) -> bool {
+        self.gpu_queries.iter().any(|q| matches!(q.kind, GpuQueryKind::PipelineStatistics(..)))
+    }
+    
+    // Generate the HeidicGpuConfig consumed by heidic_init_renderer's device selection
+    fn generate_gpu_config(&self) -> String {
+        let mut output = String::new();
+        let (prefer, device_index, transfer_queue) = match &self.gpu_config {
+            Some(gpu) => (gpu.prefer, gpu.device_index.map(|i| i as i64).unwrap_or(-1), gpu.transfer_queue),
+            None => (GpuPreference::Any, -1, TransferQueueMode::Dedicated),
+        };
+        let mut required: Vec<String> = self.gpu_config.iter()
+            .flat_map(|gpu| gpu.required_features.iter().map(|(name, _)| name.clone()))
+            .collect();
+        let optional: Vec<String> = self.gpu_config.iter()
+            .flat_map(|gpu| gpu.optional_features.iter().map(|(name, _)| name.clone()))
+            .collect();
+        // Bindless textures (and storage buffers) depend on descriptor indexing
+        if !self.image_resources.is_empty() {
+            let mut features = vec!["runtimeDescriptorArray", "descriptorBindingPartiallyBound",
+                                    "descriptorBindingSampledImageUpdateAfterBind", "shaderSampledImageArrayNonUniformIndexing"];
+            if !self.gpu_buffers.is_empty() {
+                features.extend(["descriptorBindingStorageBufferUpdateAfterBind", "shaderStorageBufferArrayNonUniformIndexing"]);
+            }
+            for feature in features {
+                if !required.iter().any(|f| f == feature) {
+                    required.push(feature.to_string());
+                }
+            }
         }
+        // Pipeline statistics pools can't be created without the feature
+        if self.uses_pipeline_statistics() && !required.iter().any(|f| f == "pipelineStatisticsQuery") {
+            required.push("pipelineStatisticsQuery".to_string());
+        }
+        // Nor wireframe/point pipelines
+        if self.uses_non_solid_fill() && !required.iter().any(|f| f == "fillModeNonSolid") {
+            required.push("fillModeNonSolid".to_string());
+        }
+        
+        output.push_str(&format!("\n{}", self.section("GPU device selection")));
+        output.push_str("#include \"stdlib/gpu_config.h\"\n");
+        let feature_array = |output: &mut String, name: &str, features: &[String]| -> String {
+            if features.is_empty() {
+                return "nullptr".to_string();
+            }
+            let quoted: Vec<String> = features.iter().map(|f| format!("\"{}\"", f)).collect();
+            output.push_str(&format!("static const char* const {}[] = {{ {} }};\n", name, quoted.join(", ")));
+            name.to_string()
+        };
+        let required_array = feature_array(&mut output, "g_gpu_required_features", &required);
+        let optional_array = feature_array(&mut output, "g_gpu_optional_features", &optional);
+        let prefer_str = match prefer {
+            GpuPreference::Any => "HEIDIC_GPU_PREFER_ANY",
+            GpuPreference::Discrete => "HEIDIC_GPU_PREFER_DISCRETE",
+            GpuPreference::Integrated => "HEIDIC_GPU_PREFER_INTEGRATED",
+        };
+        output.push_str("static const HeidicGpuConfig g_gpu_config = {\n");
+        output.push_str(&format!("    {},\n", prefer_str));
+        output.push_str(&format!("    {},  // device index (-1 = score all devices)\n", device_index));
+        output.push_str(&format!("    {}, {},\n", required_array, required.len()));
+        output.push_str(&format!("    {}, {},\n", optional_array, optional.len()));
+        output.push_str(&format!("    {},\n", match transfer_queue {
+            TransferQueueMode::Dedicated => "HEIDIC_GPU_TRANSFER_DEDICATED",
+            TransferQueueMode::Shared => "HEIDIC_GPU_TRANSFER_SHARED",
+        }));
+        output.push_str("};\n");
+        output.push('\n');
+        output
     }
     
-    fn generate_resource(&self, res: &ResourceDef) -> String {
-        // Map resource type to C++ class name
-        let cpp_resource_type = match res.resource_type.as_str() {
-            "Texture" => "TextureResource",
-            "Mesh" => "MeshResource",
-            "Sound" => "AudioResource",
-            "Music" => "AudioResource",
-            "Video" => "VideoResource",
-            _ => {
-                // Unknown resource type - use as-is (might be custom)
-                &res.resource_type
+    // Generate the HeidicWindowConfig heidic_open_window creates the window and swapchain from
+    fn generate_window_config(&self, window: &WindowConfig) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("\n{}", self.section("Window")));
+        output.push_str("#include \"stdlib/window_config.h\"\n");
+        output.push_str("static const HeidicWindowConfig g_window_config = {\n");
+        output.push_str(&format!("    \"{}\",\n", window.title));
+        output.push_str(&format!("    {}, {},\n", window.width, window.height));
+        output.push_str(&format!("    {},  // vsync\n", window.vsync as i32));
+        output.push_str(&format!("    {},  // resizable\n", window.resizable as i32));
+        output.push_str("};\n");
+        output.push('\n');
+        output
+    }
+    
+    // `frame` is a reference to the arena, so FrameArena parameters can be passed it
+    fn generate_frame_arena(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("\n{}", self.section("Frame arena")));
+        output.push_str(&format!("static HeidicFrameArena {};\n", frame_arena::GLOBAL));
+        output.push_str(&format!("static HeidicFrameArena& {} = {};\n", frame_arena::OBJECT, frame_arena::GLOBAL));
+        output.push('\n');
+        output
+    }
+    
+    fn generate_gpu_queries(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("\n{}", self.section("GPU query pools")));
+        output.push_str("#include \"stdlib/gpu_query.h\"\n");
+        for query in &self.gpu_queries {
+            let (kind, statistic) = match &query.kind {
+                GpuQueryKind::Occlusion => ("HEIDIC_QUERY_OCCLUSION", "0"),
+                GpuQueryKind::Timestamp => ("HEIDIC_QUERY_TIMESTAMP", "0"),
+                GpuQueryKind::PipelineStatistics(statistic, _) => {
+                    let flag = PIPELINE_STATISTICS.iter()
+                        .find(|(name, _)| name == statistic)
+                        .map(|(_, flag)| *flag)
+                        .unwrap_or("0");
+                    ("HEIDIC_QUERY_PIPELINE_STATISTICS", flag)
+                }
+            };
+            output.push_str(&format!(
+                "static HeidicQueryPool g_gpu_query_{} = {{ \"{}\", {}, {}, {}, -1 }};\n",
+                query.name, query.name, kind, statistic, query.count
+            ));
+        }
+        output.push('\n');
+        output
+    }
+    
+    // begin_query(Pool, i) / end_query(Pool, i) / query_result(Pool, i): returns the call prefix
+    // (runtime function and pool descriptor) and the index argument still to be generated
+    // GPU query and storage buffer intrinsics take a pool/buffer name as their first argument:
+    // returns the call up to that argument and the remaining argument still to be generated
+    fn gpu_intrinsic_call<'a>(&self, name: &str, args: &'a [Expression]) -> Option<(String, Option<&'a Expression>)> {
+        if self.function_params.contains_key(name) {
+            return None;
+        }
+        match (name, args) {
+            ("begin_query" | "end_query" | "query_result", [Expression::Variable(pool, _), index]) => {
+                Some((format!("heidic_{}(&g_gpu_query_{}", name, pool), Some(index)))
+            }
+            ("readback", [Expression::Variable(buffer, _)]) => {
+                let element = self.gpu_buffer_element(buffer)?;
+                Some((format!("heidic_readback<{}>(&g_gpu_buffer_{}", element, buffer), None))
             }
+            ("upload", [Expression::Variable(buffer, _), data]) => {
+                let element = self.gpu_buffer_element(buffer)?;
+                // An upload can replace the VkBuffer, so the bindless heap is rewritten after it
+                let upload = if self.image_resources.is_empty() { "heidic_upload" } else { "heidic_bindless_upload" };
+                Some((format!("{}<{}>(&g_gpu_buffer_{}", upload, element, buffer), Some(data)))
+            }
+            _ => None,
+        }
+    }
+    
+    // expect(cond) / expect_frame(reference, tolerance) / screenshot(path), given their
+    // generated arguments; failed checks are reported with their source line
+    fn test_intrinsic_call(&self, name: &str, args: &[Expression], generated: &[String], line: usize) -> Option<String> {
+        if self.function_params.contains_key(name) {
+            return None;
+        }
+        // Paths are const char*: literals pass through, strings need .c_str()
+        let path = |i: usize| match &args[i] {
+            Expression::Literal(Literal::String(_), _) => generated[i].clone(),
+            _ => format!("({}).c_str()", generated[i]),
         };
+        match (name, generated.len()) {
+            ("expect", 1) => Some(format!("heidic_expect({}, \"line {}: expect failed\")", generated[0], line)),
+            ("expect_frame", 2) => Some(format!("heidic_expect(heidic_expect_frame({}, {}) != 0, \"line {}: expect_frame failed\")",
+                path(0), generated[1], line)),
+            ("screenshot", 1) => Some(format!("heidic_screenshot({})", path(0))),
+            _ => None,
+        }
+    }
+    
+    fn gpu_buffer_element(&self, buffer: &str) -> Option<&str> {
+        self.gpu_buffers.iter().find(|(name, _)| name == buffer).map(|(_, element)| element.as_str())
+    }
+    
+    fn generate_gpu_buffers(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("\n{}", self.section("GPU storage buffers (upload/readback)")));
+        output.push_str("#include \"stdlib/gpu_buffer.h\"\n");
+        for (name, _) in &self.gpu_buffers {
+            output.push_str(&format!("static HeidicGpuBuffer g_gpu_buffer_{} = {{ \"{}\", VK_NULL_HANDLE, VK_NULL_HANDLE, 0 }};\n", name, name));
+            output.push_str(&format!("extern \"C\" VkBuffer get_gpu_buffer_{}() {{ return g_gpu_buffer_{}.buffer; }}\n", name, name));
+        }
+        output.push('\n');
+        output
+    }
+    
+    fn layouts(&self) -> LayoutEngine<'_> {
+        LayoutEngine::new(&self.structs, &self.components, self.target_abi)
+    }
+    
+    fn generate_resource(&self, res: &ResourceDef) -> String {
+        let cpp_resource_type = cpp_resource_type(&res.resource_type);
         
         // Generate: Resource<TextureResource> g_resource_MyTexture("path/to/file.dds");
         // Use lowercase name for the global variable (HEIDIC convention)
         let global_name = format!("g_resource_{}", res.name.to_lowercase());
-        format!("Resource<{}> {}(\"{}\");\n", cpp_resource_type, global_name, res.path)
+        match &res.texture_options {
+            Some(options) => {
+                let filter = match options.filter {
+                    TextureFilter::Linear => "VK_FILTER_LINEAR",
+                    TextureFilter::Nearest => "VK_FILTER_NEAREST",
+                };
+                let wrap = match options.wrap {
+                    TextureWrap::Repeat => "VK_SAMPLER_ADDRESS_MODE_REPEAT",
+                    TextureWrap::Clamp => "VK_SAMPLER_ADDRESS_MODE_CLAMP_TO_EDGE",
+                    TextureWrap::Mirror => "VK_SAMPLER_ADDRESS_MODE_MIRRORED_REPEAT",
+                };
+                format!("Resource<{}> {}(\"{}\", HeidicTextureOptions{{{}, {}, {}}});\n",
+                    cpp_resource_type, global_name, res.path, filter, wrap, options.mips)
+            }
+            None => format!("Resource<{}> {}(\"{}\");\n", cpp_resource_type, global_name, res.path),
+        }
     }
     
     fn generate_resource_accessor(&self, res: &ResourceDef) -> String {
         // Generate accessor function so resource can be accessed in HEIDIC
         // Example: extern "C" Resource<MeshResource>* get_resource_mymesh() { return &g_resource_mymesh; }
         let global_name = format!("g_resource_{}", res.name.to_lowercase());
-        let cpp_resource_type = match res.resource_type.as_str() {
-            "Texture" => "TextureResource",
-            "Mesh" => "MeshResource",
-            "Sound" => "AudioResource",
-            "Music" => "AudioResource",
-            "Video" => "VideoResource",
-            _ => &res.resource_type
-        };
+        let cpp_resource_type = cpp_resource_type(&res.resource_type);
         
         // Generate extern C function for HEIDIC access
         format!(
@@ -1278,6 +3347,89 @@ impl CodeGenerator {
         )
     }
     
+    /// play/stop/pause/resume and volume helpers for a Sound or Music resource
+    fn generate_audio_helpers(&self, res: &ResourceDef) -> String {
+        let mut output = String::new();
+        let name_lower = res.name.to_lowercase();
+        let accessor_name = format!("get_resource_{}", name_lower);
+        let gain = audio_channel_gain(res);
+        
+        // Play functions: once, or looping until stopped; a spatial sound also plays at an entity
+        let mut plays = vec![(format!("play_resource_{}", name_lower), "", false, None),
+                             (format!("play_resource_{}_looping", name_lower), "", true, None)];
+        if res.is_spatial {
+            let emitter = spatial_audio::emitter_global(res);
+            for (function, looping) in spatial_audio::play_at_functions(res) {
+                plays.push((function, "EntityId entity", looping, Some(emitter.clone())));
+            }
+        }
+        for (play_func_name, params, looping, emitter) in plays {
+            output.push_str(&format!("extern \"C\" int32_t {}({}) {{\n", play_func_name, params));
+            output.push_str(&format!("    auto* res = {}();\n", accessor_name));
+            output.push_str("    if (!res) { std::cerr << \"[Audio] Resource pointer is null\" << std::endl; return 0; }\n");
+            output.push_str("    auto* audio = res->get();\n");
+            output.push_str("    if (!audio) { std::cerr << \"[Audio] AudioResource is null - resource failed to load. Check if file exists and format is supported (WAV works, OGG requires SDL3_mixer)\" << std::endl; return 0; }\n");
+            output.push_str(&format!("    audio->setMixGain(g_audio_mixer.{}());\n", gain));
+            match (res.is_spatial, &emitter) {
+                (true, Some(emitter)) => {
+                    output.push_str(&format!("    {} = entity;\n", emitter));
+                    output.push_str("    audio->setPositional(true);\n");
+                    output.push_str("    heidic_update_spatial_audio();\n");
+                }
+                (true, None) => {
+                    output.push_str(&format!("    {} = INVALID_ENTITY;\n", spatial_audio::emitter_global(res)));
+                    output.push_str("    audio->setPositional(false);\n");
+                }
+                _ => {}
+            }
+            output.push_str(&format!("    bool result = audio->play({});\n", looping));
+            output.push_str("    if (!result) { std::cerr << \"[Audio] play() returned false\" << std::endl; }\n");
+            output.push_str("    return result ? 1 : 0;\n");
+            output.push_str("}\n\n");
+        }
+        
+        // Stop, pause and resume
+        for (action, method) in [("stop", "stop"), ("pause", "pause"), ("resume", "resume")] {
+            output.push_str(&format!("extern \"C\" void {}_resource_{}() {{\n", action, name_lower));
+            output.push_str(&format!("    auto* res = {}();\n", accessor_name));
+            output.push_str("    if (!res) return;\n");
+            output.push_str("    auto* audio = res->get();\n");
+            output.push_str(&format!("    if (audio) audio->{}();\n", method));
+            output.push_str("}\n\n");
+        }
+        
+        // Volume (1.0 = as recorded), on top of the mixer
+        output.push_str(&format!("extern \"C\" void set_volume_resource_{}(float volume) {{\n", name_lower));
+        output.push_str(&format!("    auto* res = {}();\n", accessor_name));
+        output.push_str("    if (!res) return;\n");
+        output.push_str("    auto* audio = res->get();\n");
+        output.push_str("    if (audio) audio->setVolume(volume);\n");
+        output.push_str("}\n\n");
+        output
+    }
+    
+    /// The master and channel volume setters; a change is applied to the clips already loaded, the
+    /// rest pick the mixer up when they play
+    fn generate_audio_mixer(&self, audio_resources: &[&ResourceDef]) -> String {
+        let mut output = String::new();
+        output.push_str("static void apply_audio_mix() {\n");
+        for res in audio_resources {
+            let global_name = format!("g_resource_{}", res.name.to_lowercase());
+            output.push_str(&format!(
+                "    if ({}.isLoaded()) {}.get()->setMixGain(g_audio_mixer.{}());\n",
+                global_name, global_name, audio_channel_gain(res)
+            ));
+        }
+        output.push_str("}\n\n");
+        for (function, field) in [("set_master_volume", "master"), ("set_sound_volume", "sound"), ("set_music_volume", "music")] {
+            output.push_str(&format!("extern \"C\" void {}(float volume) {{\n", function));
+            output.push_str(&format!("    g_audio_mixer.{} = std::max(0.0f, volume);\n", field));
+            output.push_str("    apply_audio_mix();\n");
+            output.push_str("}\n\n");
+        }
+        output
+    }
+    
     fn generate_bindless_infrastructure(&self) -> String {
         let mut output = String::new();
         
@@ -1289,34 +3441,74 @@ impl CodeGenerator {
         }
         output.push_str("\n");
         
+        // Storage buffers take binding 1, indexed in declaration order
+        let buffers = !self.gpu_buffers.is_empty();
+        if buffers {
+            output.push_str("// Bindless storage buffer index constants\n");
+            for (index, (name, _)) in self.gpu_buffers.iter().enumerate() {
+                output.push_str(&format!("constexpr uint32_t {}_BUFFER_INDEX = {};\n", name.to_uppercase(), index));
+            }
+            output.push('\n');
+        }
+        
         // Generate global bindless descriptor set layout and descriptor set
         output.push_str("// Global bindless descriptor set\n");
         output.push_str("static VkDescriptorSetLayout g_bindless_descriptor_set_layout = VK_NULL_HANDLE;\n");
         output.push_str("static VkDescriptorSet g_bindless_descriptor_set = VK_NULL_HANDLE;\n");
         output.push_str("static VkDescriptorPool g_bindless_descriptor_pool = VK_NULL_HANDLE;\n");
         output.push_str("static constexpr uint32_t MAX_BINDLESS_TEXTURES = 1024;\n");
+        if buffers {
+            output.push_str("static constexpr uint32_t MAX_BINDLESS_BUFFERS = 1024;\n");
+        }
         output.push_str("\n");
         
         // Generate function to create bindless descriptor set layout
         output.push_str("void create_bindless_descriptor_set_layout() {\n");
-        output.push_str("    VkDescriptorSetLayoutBinding binding = {};\n");
-        output.push_str("    binding.binding = 0;\n");
-        output.push_str("    binding.descriptorType = VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;\n");
-        output.push_str("    binding.descriptorCount = MAX_BINDLESS_TEXTURES;\n");
-        output.push_str("    binding.stageFlags = VK_SHADER_STAGE_ALL;\n");
-        output.push_str("    binding.pImmutableSamplers = nullptr;\n");
+        if buffers {
+            output.push_str("    VkDescriptorSetLayoutBinding bindings[2] = {};\n");
+            output.push_str("    bindings[0].binding = 0;\n");
+            output.push_str("    bindings[0].descriptorType = VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;\n");
+            output.push_str("    bindings[0].descriptorCount = MAX_BINDLESS_TEXTURES;\n");
+            output.push_str("    bindings[0].stageFlags = VK_SHADER_STAGE_ALL;\n");
+            output.push_str("    bindings[0].pImmutableSamplers = nullptr;\n");
+            output.push_str("    bindings[1].binding = 1;\n");
+            output.push_str("    bindings[1].descriptorType = VK_DESCRIPTOR_TYPE_STORAGE_BUFFER;\n");
+            output.push_str("    bindings[1].descriptorCount = MAX_BINDLESS_BUFFERS;\n");
+            output.push_str("    bindings[1].stageFlags = VK_SHADER_STAGE_ALL;\n");
+        } else {
+            output.push_str("    VkDescriptorSetLayoutBinding binding = {};\n");
+            output.push_str("    binding.binding = 0;\n");
+            output.push_str("    binding.descriptorType = VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;\n");
+            output.push_str("    binding.descriptorCount = MAX_BINDLESS_TEXTURES;\n");
+            output.push_str("    binding.stageFlags = VK_SHADER_STAGE_ALL;\n");
+            output.push_str("    binding.pImmutableSamplers = nullptr;\n");
+        }
         output.push_str("\n");
         output.push_str("    VkDescriptorSetLayoutBindingFlagsCreateInfoEXT bindingFlags = {};\n");
         output.push_str("    bindingFlags.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO_EXT;\n");
-        output.push_str("    bindingFlags.bindingCount = 1;\n");
-        output.push_str("    VkDescriptorBindingFlagsEXT flags = VK_DESCRIPTOR_BINDING_PARTIALLY_BOUND_BIT_EXT | VK_DESCRIPTOR_BINDING_UPDATE_AFTER_BIND_BIT_EXT;\n");
-        output.push_str("    bindingFlags.pBindingFlags = &flags;\n");
+        if buffers {
+            output.push_str("    bindingFlags.bindingCount = 2;\n");
+            output.push_str("    VkDescriptorBindingFlagsEXT flags[2] = {\n");
+            output.push_str("        VK_DESCRIPTOR_BINDING_PARTIALLY_BOUND_BIT_EXT | VK_DESCRIPTOR_BINDING_UPDATE_AFTER_BIND_BIT_EXT,\n");
+            output.push_str("        VK_DESCRIPTOR_BINDING_PARTIALLY_BOUND_BIT_EXT | VK_DESCRIPTOR_BINDING_UPDATE_AFTER_BIND_BIT_EXT,\n");
+            output.push_str("    };\n");
+            output.push_str("    bindingFlags.pBindingFlags = flags;\n");
+        } else {
+            output.push_str("    bindingFlags.bindingCount = 1;\n");
+            output.push_str("    VkDescriptorBindingFlagsEXT flags = VK_DESCRIPTOR_BINDING_PARTIALLY_BOUND_BIT_EXT | VK_DESCRIPTOR_BINDING_UPDATE_AFTER_BIND_BIT_EXT;\n");
+            output.push_str("    bindingFlags.pBindingFlags = &flags;\n");
+        }
         output.push_str("\n");
         output.push_str("    VkDescriptorSetLayoutCreateInfo layoutInfo = {};\n");
         output.push_str("    layoutInfo.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_CREATE_INFO;\n");
         output.push_str("    layoutInfo.pNext = &bindingFlags;\n");
-        output.push_str("    layoutInfo.bindingCount = 1;\n");
-        output.push_str("    layoutInfo.pBindings = &binding;\n");
+        if buffers {
+            output.push_str("    layoutInfo.bindingCount = 2;\n");
+            output.push_str("    layoutInfo.pBindings = bindings;\n");
+        } else {
+            output.push_str("    layoutInfo.bindingCount = 1;\n");
+            output.push_str("    layoutInfo.pBindings = &binding;\n");
+        }
         output.push_str("    layoutInfo.flags = VK_DESCRIPTOR_SET_LAYOUT_CREATE_UPDATE_AFTER_BIND_POOL_BIT_EXT;\n");
         output.push_str("\n");
         output.push_str("    if (vkCreateDescriptorSetLayout(g_device, &layoutInfo, nullptr, &g_bindless_descriptor_set_layout) != VK_SUCCESS) {\n");
@@ -1327,15 +3519,28 @@ impl CodeGenerator {
         
         // Generate function to allocate bindless descriptor set
         output.push_str("void allocate_bindless_descriptor_set() {\n");
-        output.push_str("    VkDescriptorPoolSize poolSize = {};\n");
-        output.push_str("    poolSize.type = VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;\n");
-        output.push_str("    poolSize.descriptorCount = MAX_BINDLESS_TEXTURES;\n");
+        if buffers {
+            output.push_str("    VkDescriptorPoolSize poolSizes[2] = {};\n");
+            output.push_str("    poolSizes[0].type = VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;\n");
+            output.push_str("    poolSizes[0].descriptorCount = MAX_BINDLESS_TEXTURES;\n");
+            output.push_str("    poolSizes[1].type = VK_DESCRIPTOR_TYPE_STORAGE_BUFFER;\n");
+            output.push_str("    poolSizes[1].descriptorCount = MAX_BINDLESS_BUFFERS;\n");
+        } else {
+            output.push_str("    VkDescriptorPoolSize poolSize = {};\n");
+            output.push_str("    poolSize.type = VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;\n");
+            output.push_str("    poolSize.descriptorCount = MAX_BINDLESS_TEXTURES;\n");
+        }
         output.push_str("\n");
         output.push_str("    VkDescriptorPoolCreateInfo poolInfo = {};\n");
         output.push_str("    poolInfo.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO;\n");
         output.push_str("    poolInfo.flags = VK_DESCRIPTOR_POOL_CREATE_UPDATE_AFTER_BIND_BIT_EXT;\n");
-        output.push_str("    poolInfo.poolSizeCount = 1;\n");
-        output.push_str("    poolInfo.pPoolSizes = &poolSize;\n");
+        if buffers {
+            output.push_str("    poolInfo.poolSizeCount = 2;\n");
+            output.push_str("    poolInfo.pPoolSizes = poolSizes;\n");
+        } else {
+            output.push_str("    poolInfo.poolSizeCount = 1;\n");
+            output.push_str("    poolInfo.pPoolSizes = &poolSize;\n");
+        }
         output.push_str("    poolInfo.maxSets = 1;\n");
         output.push_str("\n");
         output.push_str("    if (vkCreateDescriptorPool(g_device, &poolInfo, nullptr, &g_bindless_descriptor_pool) != VK_SUCCESS) {\n");
@@ -1363,18 +3568,18 @@ impl CodeGenerator {
         for (index, res) in self.image_resources.iter().enumerate() {
             let global_name = format!("g_resource_{}", res.name.to_lowercase());
             output.push_str(&format!("    // Register {}\n", res.name));
-            output.push_str(&format!("    VkDescriptorImageInfo imageInfo_{} = {};\n", index, res.name.to_lowercase()));
+            output.push_str(&format!("    VkDescriptorImageInfo imageInfo_{} = {{}};\n", res.name.to_lowercase()));
             output.push_str(&format!("    imageInfo_{}.imageLayout = VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL;\n", res.name.to_lowercase()));
             output.push_str(&format!("    if ({}.get() != nullptr) {{\n", global_name));
-            output.push_str(&format!("        imageInfo_{}.imageView = {}.get()->imageView;\n", res.name.to_lowercase(), global_name));
-            output.push_str(&format!("        imageInfo_{}.sampler = {}.get()->sampler;\n", res.name.to_lowercase(), global_name));
+            output.push_str(&format!("        imageInfo_{}.imageView = {}.get()->getImageView();\n", res.name.to_lowercase(), global_name));
+            output.push_str(&format!("        imageInfo_{}.sampler = {}.get()->getSampler();\n", res.name.to_lowercase(), global_name));
             output.push_str(&format!("    }} else {{\n"));
             output.push_str(&format!("        imageInfo_{}.imageView = VK_NULL_HANDLE;\n", res.name.to_lowercase()));
             output.push_str(&format!("        imageInfo_{}.sampler = VK_NULL_HANDLE;\n", res.name.to_lowercase()));
             output.push_str(&format!("    }}\n"));
             output.push_str(&format!("    imageInfos.push_back(imageInfo_{});\n", res.name.to_lowercase()));
             output.push_str("\n");
-            output.push_str(&format!("    VkWriteDescriptorSet write_{} = {};\n", index, res.name.to_lowercase()));
+            output.push_str(&format!("    VkWriteDescriptorSet write_{} = {{}};\n", res.name.to_lowercase()));
             output.push_str(&format!("    write_{}.sType = VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET;\n", res.name.to_lowercase()));
             output.push_str(&format!("    write_{}.dstSet = g_bindless_descriptor_set;\n", res.name.to_lowercase()));
             output.push_str(&format!("    write_{}.dstBinding = 0;\n", res.name.to_lowercase()));
@@ -1390,11 +3595,102 @@ impl CodeGenerator {
         output.push_str("}\n");
         output.push_str("\n");
         
+        // Textures loaded at runtime take the slots after the declared ones; a released slot is
+        // handed out again before the heap grows
+        output.push_str("// Runtime texture registration (slots after the declared textures, released ones reused first)\n");
+        output.push_str("static constexpr uint32_t BINDLESS_INVALID_INDEX = UINT32_MAX;\n");
+        output.push_str(&format!("static uint32_t g_bindless_next_texture = {};\n", self.image_resources.len()));
+        output.push_str("static std::vector<uint32_t> g_bindless_free_textures;\n");
+        output.push('\n');
+        output.push_str("// Writes the texture into a free slot of the heap and returns its index for shaders, or\n");
+        output.push_str("// BINDLESS_INVALID_INDEX if the heap is full or the texture isn't loaded\n");
+        output.push_str("extern \"C\" uint32_t register_bindless_texture(TextureResource* texture) {\n");
+        output.push_str("    if (texture == nullptr || !texture->isLoaded() || g_bindless_descriptor_set == VK_NULL_HANDLE) {\n");
+        output.push_str("        return BINDLESS_INVALID_INDEX;\n");
+        output.push_str("    }\n");
+        output.push_str("    uint32_t index;\n");
+        output.push_str("    if (!g_bindless_free_textures.empty()) {\n");
+        output.push_str("        index = g_bindless_free_textures.back();\n");
+        output.push_str("        g_bindless_free_textures.pop_back();\n");
+        output.push_str("    } else if (g_bindless_next_texture < MAX_BINDLESS_TEXTURES) {\n");
+        output.push_str("        index = g_bindless_next_texture++;\n");
+        output.push_str("    } else {\n");
+        output.push_str("        std::cerr << \"[Bindless] ERROR: all \" << MAX_BINDLESS_TEXTURES << \" texture slots are in use\" << std::endl;\n");
+        output.push_str("        return BINDLESS_INVALID_INDEX;\n");
+        output.push_str("    }\n");
+        output.push_str("    VkDescriptorImageInfo imageInfo = texture->getDescriptorImageInfo();\n");
+        output.push_str("    VkWriteDescriptorSet write = {};\n");
+        output.push_str("    write.sType = VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET;\n");
+        output.push_str("    write.dstSet = g_bindless_descriptor_set;\n");
+        output.push_str("    write.dstBinding = 0;\n");
+        output.push_str("    write.dstArrayElement = index;\n");
+        output.push_str("    write.descriptorType = VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;\n");
+        output.push_str("    write.descriptorCount = 1;\n");
+        output.push_str("    write.pImageInfo = &imageInfo;\n");
+        output.push_str("    vkUpdateDescriptorSets(g_device, 1, &write, 0, nullptr);\n");
+        output.push_str("    return index;\n");
+        output.push_str("}\n");
+        output.push('\n');
+        // The slot is only recycled: shaders mustn't sample it again until it's re-registered
+        output.push_str("// Returns a slot from register_bindless_texture() to the free list; the declared textures keep theirs\n");
+        output.push_str("extern \"C\" void unregister_bindless_texture(uint32_t index) {\n");
+        output.push_str(&format!("    if (index < {} || index >= g_bindless_next_texture) {{\n", self.image_resources.len()));
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push_str("    if (std::find(g_bindless_free_textures.begin(), g_bindless_free_textures.end(), index) == g_bindless_free_textures.end()) {\n");
+        output.push_str("        g_bindless_free_textures.push_back(index);\n");
+        output.push_str("    }\n");
+        output.push_str("}\n");
+        output.push('\n');
+        
+        // Storage buffers are allocated by their first upload() and replaced when they grow, so
+        // each one is (re)written whenever its VkBuffer isn't the one the heap holds
+        if buffers {
+            output.push_str("void register_bindless_buffers() {\n");
+            output.push_str("    if (g_bindless_descriptor_set == VK_NULL_HANDLE) {\n");
+            output.push_str("        return;  // init_bindless_system() registers what was uploaded before it\n");
+            output.push_str("    }\n");
+            output.push_str(&format!("    static VkBuffer registered[{}] = {{}};\n", self.gpu_buffers.len()));
+            output.push_str(&format!("    HeidicGpuBuffer* buffers[{}] = {{ {} }};\n", self.gpu_buffers.len(),
+                self.gpu_buffers.iter().map(|(name, _)| format!("&g_gpu_buffer_{}", name)).collect::<Vec<_>>().join(", ")));
+            output.push_str(&format!("    for (uint32_t i = 0; i < {}; ++i) {{\n", self.gpu_buffers.len()));
+            output.push_str("        if (buffers[i]->buffer == VK_NULL_HANDLE || buffers[i]->buffer == registered[i]) {\n");
+            output.push_str("            continue;\n");
+            output.push_str("        }\n");
+            output.push_str("        VkDescriptorBufferInfo bufferInfo = {};\n");
+            output.push_str("        bufferInfo.buffer = buffers[i]->buffer;\n");
+            output.push_str("        bufferInfo.offset = 0;\n");
+            output.push_str("        bufferInfo.range = VK_WHOLE_SIZE;\n");
+            output.push_str("        VkWriteDescriptorSet write = {};\n");
+            output.push_str("        write.sType = VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET;\n");
+            output.push_str("        write.dstSet = g_bindless_descriptor_set;\n");
+            output.push_str("        write.dstBinding = 1;\n");
+            output.push_str("        write.dstArrayElement = i;\n");
+            output.push_str("        write.descriptorType = VK_DESCRIPTOR_TYPE_STORAGE_BUFFER;\n");
+            output.push_str("        write.descriptorCount = 1;\n");
+            output.push_str("        write.pBufferInfo = &bufferInfo;\n");
+            output.push_str("        vkUpdateDescriptorSets(g_device, 1, &write, 0, nullptr);\n");
+            output.push_str("        registered[i] = buffers[i]->buffer;\n");
+            output.push_str("    }\n");
+            output.push_str("}\n");
+            output.push('\n');
+            output.push_str("// upload(buffer, data) with the bindless heap pointed at the buffer afterwards\n");
+            output.push_str("template<typename T>\n");
+            output.push_str("inline void heidic_bindless_upload(HeidicGpuBuffer* buffer, const std::vector<T>& data) {\n");
+            output.push_str("    heidic_upload<T>(buffer, data);\n");
+            output.push_str("    register_bindless_buffers();\n");
+            output.push_str("}\n");
+            output.push('\n');
+        }
+        
         // Generate initialization function
         output.push_str("void init_bindless_system() {\n");
         output.push_str("    create_bindless_descriptor_set_layout();\n");
         output.push_str("    allocate_bindless_descriptor_set();\n");
         output.push_str("    register_bindless_textures();\n");
+        if buffers {
+            output.push_str("    register_bindless_buffers();\n");
+        }
         output.push_str("}\n");
         output.push_str("\n");
         
@@ -1413,6 +3709,10 @@ impl CodeGenerator {
         output.push_str(&format!("static VkPipeline g_pipeline_{} = VK_NULL_HANDLE;\n", pipeline_name_lower));
         output.push_str(&format!("static VkPipelineLayout g_pipeline_layout_{} = VK_NULL_HANDLE;\n", pipeline_name_lower));
         output.push_str(&format!("static VkDescriptorSetLayout g_descriptor_set_layout_{} = VK_NULL_HANDLE;\n", pipeline_name_lower));
+        if !pipeline.bindings().is_empty() {
+            output.push_str(&format!("static VkDescriptorPool g_descriptor_pool_{} = VK_NULL_HANDLE;\n", pipeline_name_lower));
+            output.push_str(&format!("static VkDescriptorSet g_descriptor_set_{} = VK_NULL_HANDLE;\n", pipeline_name_lower));
+        }
         
         // Generate shader module variables
         for shader in &pipeline.shaders {
@@ -1428,9 +3728,28 @@ impl CodeGenerator {
         }
         output.push_str("\n");
         
+        // The push constant range covers every stage of the pipeline
+        let all_stages: Vec<&str> = pipeline.shaders.iter().map(|shader| match shader.stage {
+            ShaderStage::Vertex => "VK_SHADER_STAGE_VERTEX_BIT",
+            ShaderStage::Fragment => "VK_SHADER_STAGE_FRAGMENT_BIT",
+            ShaderStage::Compute => "VK_SHADER_STAGE_COMPUTE_BIT",
+            ShaderStage::Geometry => "VK_SHADER_STAGE_GEOMETRY_BIT",
+            ShaderStage::TessellationControl => "VK_SHADER_STAGE_TESSELLATION_CONTROL_BIT",
+            ShaderStage::TessellationEvaluation => "VK_SHADER_STAGE_TESSELLATION_EVALUATION_BIT",
+        }).collect();
+        let push_constants = pipeline.push_constants()
+            .and_then(|block| Some((block, crate::layout::push_constant_offsets(&block.fields).ok()?)));
+        if let Some((block, (offsets, _))) = &push_constants {
+            output.push_str(&self.generate_push_constants_struct(pipeline, block, offsets));
+        }
+        
         // Generate descriptor set layout creation (if layout is specified)
         if let Some(layout) = &pipeline.layout {
             output.push_str(&format!("static void create_descriptor_set_layout_{}() {{\n", pipeline_name_lower));
+            // A hot-reload rebuild keeps the layout its descriptor sets were allocated with
+            output.push_str(&format!("    if (g_descriptor_set_layout_{} != VK_NULL_HANDLE) {{\n", pipeline_name_lower));
+            output.push_str("        return;\n");
+            output.push_str("    }\n");
             output.push_str(&format!("    std::vector<VkDescriptorSetLayoutBinding> bindings;\n"));
             
             for binding in &layout.bindings {
@@ -1546,12 +3865,41 @@ impl CodeGenerator {
         output.push_str("\n    // Pipeline state setup\n");
         output.push_str("    VkPipelineVertexInputStateCreateInfo vertexInputInfo = {};\n");
         output.push_str("    vertexInputInfo.sType = VK_STRUCTURE_TYPE_PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO;\n");
-        output.push_str("    vertexInputInfo.vertexBindingDescriptionCount = 0;\n");
-        output.push_str("    vertexInputInfo.vertexAttributeDescriptionCount = 0;\n");
+        match pipeline.vertex_input.as_ref().and_then(|input| self.structs.get(&input.struct_name)) {
+            Some(vertex) => {
+                // One interleaved binding of the struct; attribute locations follow field order
+                output.push_str("    VkVertexInputBindingDescription bindingDescription = {};\n");
+                output.push_str("    bindingDescription.binding = 0;\n");
+                output.push_str(&format!("    bindingDescription.stride = sizeof({});\n", vertex.name));
+                output.push_str("    bindingDescription.inputRate = VK_VERTEX_INPUT_RATE_VERTEX;\n");
+                output.push_str("    VkVertexInputAttributeDescription attributeDescriptions[] = {\n");
+                for (location, field) in vertex.fields.iter().enumerate() {
+                    let format = crate::layout::vertex_format(&field.ty).unwrap_or("VK_FORMAT_UNDEFINED");
+                    output.push_str(&format!("        {{{}, 0, {}, static_cast<uint32_t>(offsetof({}, {}))}},\n",
+                        location, format, vertex.name, field.name));
+                }
+                output.push_str("    };\n");
+                output.push_str("    vertexInputInfo.vertexBindingDescriptionCount = 1;\n");
+                output.push_str("    vertexInputInfo.pVertexBindingDescriptions = &bindingDescription;\n");
+                output.push_str(&format!("    vertexInputInfo.vertexAttributeDescriptionCount = {};\n", vertex.fields.len()));
+                output.push_str("    vertexInputInfo.pVertexAttributeDescriptions = attributeDescriptions;\n");
+            }
+            None => {
+                output.push_str("    vertexInputInfo.vertexBindingDescriptionCount = 0;\n");
+                output.push_str("    vertexInputInfo.vertexAttributeDescriptionCount = 0;\n");
+            }
+        }
         output.push_str("\n");
         output.push_str("    VkPipelineInputAssemblyStateCreateInfo inputAssembly = {};\n");
         output.push_str("    inputAssembly.sType = VK_STRUCTURE_TYPE_PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO;\n");
-        output.push_str("    inputAssembly.topology = VK_PRIMITIVE_TOPOLOGY_TRIANGLE_LIST;\n");
+        let state = &pipeline.state;
+        output.push_str(&format!("    inputAssembly.topology = {};\n", match state.topology {
+            Topology::Triangles => "VK_PRIMITIVE_TOPOLOGY_TRIANGLE_LIST",
+            Topology::TriangleStrip => "VK_PRIMITIVE_TOPOLOGY_TRIANGLE_STRIP",
+            Topology::Lines => "VK_PRIMITIVE_TOPOLOGY_LINE_LIST",
+            Topology::LineStrip => "VK_PRIMITIVE_TOPOLOGY_LINE_STRIP",
+            Topology::Points => "VK_PRIMITIVE_TOPOLOGY_POINT_LIST",
+        }));
         output.push_str("    inputAssembly.primitiveRestartEnable = VK_FALSE;\n");
         output.push_str("\n");
         output.push_str("    VkViewport viewport = {};\n");
@@ -1577,9 +3925,17 @@ impl CodeGenerator {
         output.push_str("    rasterizer.sType = VK_STRUCTURE_TYPE_PIPELINE_RASTERIZATION_STATE_CREATE_INFO;\n");
         output.push_str("    rasterizer.depthClampEnable = VK_FALSE;\n");
         output.push_str("    rasterizer.rasterizerDiscardEnable = VK_FALSE;\n");
-        output.push_str("    rasterizer.polygonMode = VK_POLYGON_MODE_FILL;\n");
+        output.push_str(&format!("    rasterizer.polygonMode = {};\n", match state.polygon {
+            PolygonMode::Fill => "VK_POLYGON_MODE_FILL",
+            PolygonMode::Wireframe => "VK_POLYGON_MODE_LINE",  // Needs fillModeNonSolid (requested by the gpu config)
+            PolygonMode::Points => "VK_POLYGON_MODE_POINT",
+        }));
         output.push_str("    rasterizer.lineWidth = 1.0f;\n");
-        output.push_str("    rasterizer.cullMode = VK_CULL_MODE_BACK_BIT;\n");  // Back-face culling for performance
+        output.push_str(&format!("    rasterizer.cullMode = {};\n", match state.cull {
+            CullMode::Back => "VK_CULL_MODE_BACK_BIT",  // Back-face culling for performance
+            CullMode::Front => "VK_CULL_MODE_FRONT_BIT",
+            CullMode::None => "VK_CULL_MODE_NONE",
+        }));
         output.push_str("    rasterizer.frontFace = VK_FRONT_FACE_COUNTER_CLOCKWISE;\n");  // glTF/OpenGL convention
         output.push_str("    rasterizer.depthBiasEnable = VK_FALSE;\n");
         output.push_str("\n");
@@ -1590,15 +3946,32 @@ impl CodeGenerator {
         output.push_str("\n");
         output.push_str("    VkPipelineDepthStencilStateCreateInfo depthStencil = {};\n");
         output.push_str("    depthStencil.sType = VK_STRUCTURE_TYPE_PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO;\n");
-        output.push_str("    depthStencil.depthTestEnable = VK_TRUE;\n");  // Enable depth testing for correct 3D rendering
-        output.push_str("    depthStencil.depthWriteEnable = VK_TRUE;\n");  // Write depth for occlusion
+        // Depth testing for correct 3D rendering, writing depth for occlusion (depth_test: off disables both)
+        let depth = if state.depth_test { "VK_TRUE" } else { "VK_FALSE" };
+        output.push_str(&format!("    depthStencil.depthTestEnable = {};\n", depth));
+        output.push_str(&format!("    depthStencil.depthWriteEnable = {};\n", depth));
         output.push_str("    depthStencil.depthCompareOp = VK_COMPARE_OP_LESS_OR_EQUAL;\n");  // Standard depth test
         output.push_str("    depthStencil.depthBoundsTestEnable = VK_FALSE;\n");
         output.push_str("    depthStencil.stencilTestEnable = VK_FALSE;\n");
         output.push_str("\n");
         output.push_str("    VkPipelineColorBlendAttachmentState colorBlendAttachment = {};\n");
         output.push_str("    colorBlendAttachment.colorWriteMask = VK_COLOR_COMPONENT_R_BIT | VK_COLOR_COMPONENT_G_BIT | VK_COLOR_COMPONENT_B_BIT | VK_COLOR_COMPONENT_A_BIT;\n");
-        output.push_str("    colorBlendAttachment.blendEnable = VK_FALSE;\n");
+        match state.blend {
+            BlendMode::Opaque => output.push_str("    colorBlendAttachment.blendEnable = VK_FALSE;\n"),
+            BlendMode::Alpha | BlendMode::Additive => {
+                let (dst_color, dst_alpha) = match state.blend {
+                    BlendMode::Alpha => ("VK_BLEND_FACTOR_ONE_MINUS_SRC_ALPHA", "VK_BLEND_FACTOR_ONE_MINUS_SRC_ALPHA"),
+                    _ => ("VK_BLEND_FACTOR_ONE", "VK_BLEND_FACTOR_ONE"),
+                };
+                output.push_str("    colorBlendAttachment.blendEnable = VK_TRUE;\n");
+                output.push_str("    colorBlendAttachment.srcColorBlendFactor = VK_BLEND_FACTOR_SRC_ALPHA;\n");
+                output.push_str(&format!("    colorBlendAttachment.dstColorBlendFactor = {};\n", dst_color));
+                output.push_str("    colorBlendAttachment.colorBlendOp = VK_BLEND_OP_ADD;\n");
+                output.push_str("    colorBlendAttachment.srcAlphaBlendFactor = VK_BLEND_FACTOR_ONE;\n");
+                output.push_str(&format!("    colorBlendAttachment.dstAlphaBlendFactor = {};\n", dst_alpha));
+                output.push_str("    colorBlendAttachment.alphaBlendOp = VK_BLEND_OP_ADD;\n");
+            }
+        }
         output.push_str("\n");
         output.push_str("    VkPipelineColorBlendStateCreateInfo colorBlending = {};\n");
         output.push_str("    colorBlending.sType = VK_STRUCTURE_TYPE_PIPELINE_COLOR_BLEND_STATE_CREATE_INFO;\n");
@@ -1614,7 +3987,17 @@ impl CodeGenerator {
             output.push_str(&format!("    pipelineLayoutInfo.sType = VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO;\n"));
             output.push_str(&format!("    pipelineLayoutInfo.setLayoutCount = 1;\n"));
             output.push_str(&format!("    pipelineLayoutInfo.pSetLayouts = &g_descriptor_set_layout_{};\n", pipeline_name_lower));
-            output.push_str(&format!("    pipelineLayoutInfo.pushConstantRangeCount = 0;\n"));
+            match &push_constants {
+                Some((_, (_, size))) => {
+                    output.push_str("    VkPushConstantRange pushConstantRange = {};\n");
+                    output.push_str(&format!("    pushConstantRange.stageFlags = {};\n", all_stages.join(" | ")));
+                    output.push_str("    pushConstantRange.offset = 0;\n");
+                    output.push_str(&format!("    pushConstantRange.size = {};\n", size));
+                    output.push_str("    pipelineLayoutInfo.pushConstantRangeCount = 1;\n");
+                    output.push_str("    pipelineLayoutInfo.pPushConstantRanges = &pushConstantRange;\n");
+                }
+                None => output.push_str("    pipelineLayoutInfo.pushConstantRangeCount = 0;\n"),
+            }
         } else {
             output.push_str(&format!("    VkPipelineLayoutCreateInfo pipelineLayoutInfo = {{}};\n"));
             output.push_str(&format!("    pipelineLayoutInfo.sType = VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO;\n"));
@@ -1646,9 +4029,7 @@ impl CodeGenerator {
         output.push_str(&format!("    if (vkCreateGraphicsPipelines(g_device, VK_NULL_HANDLE, 1, &pipelineInfo, nullptr, &g_pipeline_{}) != VK_SUCCESS) {{\n", pipeline_name_lower));
         output.push_str(&format!("        std::cerr << \"[Pipeline {}] ERROR: Failed to create graphics pipeline!\" << std::endl;\n", pipeline_name));
         output.push_str(&format!("        vkDestroyPipelineLayout(g_device, g_pipeline_layout_{}, nullptr);\n", pipeline_name_lower));
-        if let Some(_) = &pipeline.layout {
-            output.push_str(&format!("        vkDestroyDescriptorSetLayout(g_device, g_descriptor_set_layout_{}, nullptr);\n", pipeline_name_lower));
-        }
+        output.push_str(&format!("        g_pipeline_layout_{} = VK_NULL_HANDLE;\n", pipeline_name_lower));
         for shader in &pipeline.shaders {
             let stage_name = match shader.stage {
                 ShaderStage::Vertex => "vert",
@@ -1659,12 +4040,17 @@ impl CodeGenerator {
                 ShaderStage::TessellationEvaluation => "tese",
             };
             output.push_str(&format!("        vkDestroyShaderModule(g_device, g_shader_module_{}_{}, nullptr);\n", pipeline_name_lower, stage_name));
+            output.push_str(&format!("        g_shader_module_{}_{} = VK_NULL_HANDLE;\n", pipeline_name_lower, stage_name));
         }
         output.push_str("        return;\n");
         output.push_str("    }\n");
         output.push_str(&format!("    std::cout << \"[Pipeline {}] Created successfully!\" << std::endl;\n", pipeline_name));
         output.push_str("}\n\n");
         
+        if self.hot_shaders.iter().any(|shader| self.hot_shader_pipelines(shader).iter().any(|p| p.name == pipeline.name)) {
+            output.push_str(&self.generate_pipeline_rebuild(pipeline));
+        }
+        
         // Generate helper functions for HEIDIC access
         output.push_str(&format!("// Helper functions for HEIDIC access\n"));
         output.push_str(&format!("extern \"C\" VkPipeline get_pipeline_{}() {{\n", pipeline_name_lower));
@@ -1673,140 +4059,661 @@ impl CodeGenerator {
         
         output.push_str(&format!("extern \"C\" void bind_pipeline_{}(VkCommandBuffer commandBuffer) {{\n", pipeline_name_lower));
         output.push_str(&format!("    vkCmdBindPipeline(commandBuffer, VK_PIPELINE_BIND_POINT_GRAPHICS, g_pipeline_{});\n", pipeline_name_lower));
+        if !pipeline.bindings().is_empty() {
+            // The descriptor set is bound with the pipeline once create_descriptor_sets_<pipeline>() made it
+            output.push_str(&format!("    if (g_descriptor_set_{} != VK_NULL_HANDLE) {{\n", pipeline_name_lower));
+            output.push_str(&format!("        vkCmdBindDescriptorSets(commandBuffer, VK_PIPELINE_BIND_POINT_GRAPHICS, g_pipeline_layout_{}, 0, 1, &g_descriptor_set_{}, 0, nullptr);\n",
+                pipeline_name_lower, pipeline_name_lower));
+            output.push_str("    }\n");
+        }
+        output.push_str("}\n\n");
+        
+        if !pipeline.bindings().is_empty() {
+            output.push_str(&self.generate_descriptor_set_helpers(pipeline));
+        }
+        
+        if let Some((_, (_, size))) = &push_constants {
+            output.push_str(&format!("extern \"C\" void {}(VkCommandBuffer commandBuffer, const {}& data) {{\n",
+                pipeline.push_constants_function(), pipeline.push_constants_struct()));
+            output.push_str(&format!("    vkCmdPushConstants(commandBuffer, g_pipeline_layout_{}, {}, 0, {}, &data);\n",
+                pipeline_name_lower, all_stages.join(" | "), size));
+            output.push_str("}\n\n");
+        }
+        
+        output
+    }
+    
+    // create_descriptor_sets_<pipeline>(): a pool sized for the layout's bindings and the one set
+    // allocated from it; update_binding_<pipeline>(binding, buffer): writes a uniform or storage
+    // binding (whole buffer). Updates must happen while no recorded command buffer uses the set.
+    // Uniform bindings of a `uniform` block's type are written with that block's buffer on creation.
+    /// Declared pipelines that load a hot shader's .spv, rebuilt when it changes
+    fn hot_shader_pipelines(&self, shader: &ShaderDef) -> Vec<&PipelineDef> {
+        let spv = shaders::loaded_path(&shader.path);
+        self.pipelines.iter()
+            .filter(|pipeline| pipeline.shaders.iter().any(|s| shaders::loaded_path(&s.path) == spv))
+            .collect()
+    }
+    
+    /// rebuild_pipeline_<name>(): recreates the pipeline from the shaders on disk. The descriptor
+    /// set layout (and the sets allocated with it) stay; a failed rebuild keeps the running pipeline.
+    fn generate_pipeline_rebuild(&self, pipeline: &PipelineDef) -> String {
+        let name = pipeline.name.to_lowercase();
+        let stages: Vec<&str> = pipeline.shaders.iter().map(|shader| match shader.stage {
+            ShaderStage::Vertex => "vert",
+            ShaderStage::Fragment => "frag",
+            ShaderStage::Compute => "comp",
+            ShaderStage::Geometry => "geom",
+            ShaderStage::TessellationControl => "tesc",
+            ShaderStage::TessellationEvaluation => "tese",
+        }).collect();
+        let mut output = String::new();
+        output.push_str(&format!("// Shader hot-reload: recreate {} from the shaders on disk\n", pipeline.name));
+        output.push_str(&format!("static bool rebuild_pipeline_{}() {{\n", name));
+        output.push_str("    vkDeviceWaitIdle(g_device);\n");
+        output.push_str(&format!("    VkPipeline old_pipeline = g_pipeline_{};\n", name));
+        output.push_str(&format!("    VkPipelineLayout old_layout = g_pipeline_layout_{};\n", name));
+        for stage in &stages {
+            output.push_str(&format!("    VkShaderModule old_{} = g_shader_module_{}_{};\n", stage, name, stage));
+        }
+        output.push_str(&format!("    g_pipeline_{} = VK_NULL_HANDLE;\n", name));
+        output.push_str(&format!("    g_pipeline_layout_{} = VK_NULL_HANDLE;\n", name));
+        for stage in &stages {
+            output.push_str(&format!("    g_shader_module_{}_{} = VK_NULL_HANDLE;\n", name, stage));
+        }
+        output.push_str(&format!("    create_pipeline_{}();\n", name));
+        output.push_str(&format!("    if (g_pipeline_{} == VK_NULL_HANDLE) {{\n", name));
+        // Whatever the failed attempt created before it stopped (destroying VK_NULL_HANDLE is a no-op)
+        output.push_str(&format!("        vkDestroyPipelineLayout(g_device, g_pipeline_layout_{}, nullptr);\n", name));
+        for stage in &stages {
+            output.push_str(&format!("        vkDestroyShaderModule(g_device, g_shader_module_{}_{}, nullptr);\n", name, stage));
+        }
+        output.push_str(&format!("        g_pipeline_{} = old_pipeline;\n", name));
+        output.push_str(&format!("        g_pipeline_layout_{} = old_layout;\n", name));
+        for stage in &stages {
+            output.push_str(&format!("        g_shader_module_{}_{} = old_{};\n", name, stage, stage));
+        }
+        output.push_str(&format!("        std::cerr << \"[Shader Hot-Reload] Keeping the previous {} pipeline\" << std::endl;\n", pipeline.name));
+        output.push_str("        return false;\n");
+        output.push_str("    }\n");
+        output.push_str("    vkDestroyPipeline(g_device, old_pipeline, nullptr);\n");
+        output.push_str("    vkDestroyPipelineLayout(g_device, old_layout, nullptr);\n");
+        for stage in &stages {
+            output.push_str(&format!("    vkDestroyShaderModule(g_device, old_{}, nullptr);\n", stage));
+        }
+        output.push_str("    return true;\n");
+        output.push_str("}\n\n");
+        output
+    }
+    
+    fn generate_descriptor_set_helpers(&self, pipeline: &PipelineDef) -> String {
+        use crate::ast::BindingType;
+        
+        let pipeline_name = &pipeline.name;
+        let lower = pipeline_name.to_lowercase();
+        let descriptor_type = |binding_type: &BindingType| match binding_type {
+            BindingType::Uniform(_) => "VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER",
+            BindingType::Storage(_) => "VK_DESCRIPTOR_TYPE_STORAGE_BUFFER",
+            BindingType::Sampler2D => "VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER",
+        };
+        let uniform_bindings: Vec<(u32, &UniformDef)> = pipeline.bindings().iter()
+            .filter_map(|binding| match &binding.binding_type {
+                BindingType::Uniform(ty) => Some((binding.binding, self.uniforms.iter().find(|u| u.name == *ty)?)),
+                _ => None,
+            })
+            .collect();
+        let mut output = String::new();
+        
+        if !uniform_bindings.is_empty() {
+            output.push_str(&format!("extern \"C\" void {}(uint32_t binding, VkBuffer buffer);\n", pipeline.update_binding_function()));
+        }
+        output.push_str(&format!("extern \"C\" void {}() {{\n", pipeline.descriptor_sets_function()));
+        output.push_str(&format!("    if (g_descriptor_set_{} != VK_NULL_HANDLE) {{\n", lower));
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push_str(&format!("    if (g_descriptor_set_layout_{} == VK_NULL_HANDLE) {{\n", lower));
+        output.push_str(&format!("        std::cerr << \"[Pipeline {}] ERROR: {}() needs the pipeline created first!\" << std::endl;\n",
+            pipeline_name, pipeline.descriptor_sets_function()));
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push_str("    std::vector<VkDescriptorPoolSize> poolSizes;\n");
+        for binding in pipeline.bindings() {
+            output.push_str(&format!("    poolSizes.push_back({{{}, 1}});\n", descriptor_type(&binding.binding_type)));
+        }
+        output.push_str("    VkDescriptorPoolCreateInfo poolInfo = {};\n");
+        output.push_str("    poolInfo.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO;\n");
+        output.push_str("    poolInfo.poolSizeCount = static_cast<uint32_t>(poolSizes.size());\n");
+        output.push_str("    poolInfo.pPoolSizes = poolSizes.data();\n");
+        output.push_str("    poolInfo.maxSets = 1;\n");
+        output.push_str(&format!("    if (vkCreateDescriptorPool(g_device, &poolInfo, nullptr, &g_descriptor_pool_{}) != VK_SUCCESS) {{\n", lower));
+        output.push_str(&format!("        std::cerr << \"[Pipeline {}] ERROR: Failed to create descriptor pool!\" << std::endl;\n", pipeline_name));
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push_str("    VkDescriptorSetAllocateInfo allocInfo = {};\n");
+        output.push_str("    allocInfo.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_SET_ALLOCATE_INFO;\n");
+        output.push_str(&format!("    allocInfo.descriptorPool = g_descriptor_pool_{};\n", lower));
+        output.push_str("    allocInfo.descriptorSetCount = 1;\n");
+        output.push_str(&format!("    allocInfo.pSetLayouts = &g_descriptor_set_layout_{};\n", lower));
+        output.push_str(&format!("    if (vkAllocateDescriptorSets(g_device, &allocInfo, &g_descriptor_set_{}) != VK_SUCCESS) {{\n", lower));
+        output.push_str(&format!("        std::cerr << \"[Pipeline {}] ERROR: Failed to allocate descriptor set!\" << std::endl;\n", pipeline_name));
+        output.push_str(&format!("        vkDestroyDescriptorPool(g_device, g_descriptor_pool_{}, nullptr);\n", lower));
+        output.push_str(&format!("        g_descriptor_pool_{} = VK_NULL_HANDLE;\n", lower));
+        output.push_str(&format!("        g_descriptor_set_{} = VK_NULL_HANDLE;\n", lower));
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        for (binding, uniform) in &uniform_bindings {
+            output.push_str(&format!("    {}({}, heidic_uniform_buffer_handle(&{}));\n", pipeline.update_binding_function(), binding, uniform.buffer_global()));
+        }
+        output.push_str("}\n\n");
+        
+        output.push_str(&format!("extern \"C\" void {}(uint32_t binding, VkBuffer buffer) {{\n", pipeline.update_binding_function()));
+        output.push_str(&format!("    if (g_descriptor_set_{} == VK_NULL_HANDLE) {{\n", lower));
+        output.push_str(&format!("        std::cerr << \"[Pipeline {}] ERROR: call {}() before {}()!\" << std::endl;\n",
+            pipeline_name, pipeline.descriptor_sets_function(), pipeline.update_binding_function()));
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push_str("    VkDescriptorType descriptorType;\n");
+        output.push_str("    switch (binding) {\n");
+        for binding in pipeline.bindings() {
+            if binding.binding_type != BindingType::Sampler2D {
+                output.push_str(&format!("        case {}: descriptorType = {}; break;\n", binding.binding, descriptor_type(&binding.binding_type)));
+            }
+        }
+        output.push_str("        default:\n");
+        output.push_str(&format!("            std::cerr << \"[Pipeline {}] ERROR: binding \" << binding << \" is not a uniform or storage buffer binding!\" << std::endl;\n", pipeline_name));
+        output.push_str("            return;\n");
+        output.push_str("    }\n");
+        output.push_str("    VkDescriptorBufferInfo bufferInfo = {};\n");
+        output.push_str("    bufferInfo.buffer = buffer;\n");
+        output.push_str("    bufferInfo.offset = 0;\n");
+        output.push_str("    bufferInfo.range = VK_WHOLE_SIZE;\n");
+        output.push_str("    VkWriteDescriptorSet write = {};\n");
+        output.push_str("    write.sType = VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET;\n");
+        output.push_str(&format!("    write.dstSet = g_descriptor_set_{};\n", lower));
+        output.push_str("    write.dstBinding = binding;\n");
+        output.push_str("    write.dstArrayElement = 0;\n");
+        output.push_str("    write.descriptorType = descriptorType;\n");
+        output.push_str("    write.descriptorCount = 1;\n");
+        output.push_str("    write.pBufferInfo = &bufferInfo;\n");
+        output.push_str("    vkUpdateDescriptorSets(g_device, 1, &write, 0, nullptr);\n");
         output.push_str("}\n\n");
         
         output
     }
     
+    // The push_constants struct as the shaders read it: members at the std430 offsets (checked)
+    fn generate_push_constants_struct(&self, pipeline: &PipelineDef, block: &PushConstants, offsets: &[usize]) -> String {
+        let name = pipeline.push_constants_struct();
+        let mut output = format!("struct {} {{\n", name);
+        for field in &block.fields {
+            output.push_str(&format!("    {} {};\n", self.shader_block_member(&field.ty), field.name));
+        }
+        output.push_str("};\n");
+        for (field, offset) in block.fields.iter().zip(offsets) {
+            output.push_str(&layout_check(&format!("offsetof({}, {})", name, field.name), *offset));
+        }
+        output.push('\n');
+        output
+    }
+    
+    // A push constant or uniform member at its GLSL offset: Mat4 as the 64 bytes of a GLSL mat4
+    // (stdlib/push_constants.h), bool as 4 bytes, vectors aligned as GLSL aligns them
+    fn shader_block_member(&self, ty: &Type) -> String {
+        match ty {
+            Type::Bool => "uint32_t".to_string(),
+            Type::Vec2 => "alignas(8) Vec2".to_string(),
+            Type::Vec3 | Type::Vec4 => format!("alignas(16) {}", self.type_to_cpp(ty)),
+            Type::Mat4 => "alignas(16) HeidicGpuMat4".to_string(),
+            ty => self.type_to_cpp(ty),
+        }
+    }
+    
+    // uniform Name { ... }: the struct at the std140 offsets and size (checked), its buffer and
+    // update_<name>(value), which copies a value into the buffer
+    fn generate_uniform(&self, uniform: &UniformDef) -> String {
+        let Ok((offsets, size)) = crate::layout::uniform_offsets(&uniform.fields) else { return String::new() };
+        let name = &uniform.name;
+        let mut output = format!("struct alignas(16) {} {{\n", name);
+        for field in &uniform.fields {
+            output.push_str(&format!("    {} {};\n", self.shader_block_member(&field.ty), field.name));
+        }
+        output.push_str("};\n");
+        for (field, offset) in uniform.fields.iter().zip(&offsets) {
+            output.push_str(&layout_check(&format!("offsetof({}, {})", name, field.name), *offset));
+        }
+        output.push_str(&layout_check(&format!("sizeof({})", name), size));
+        output.push_str(&format!("static HeidicUniformBuffer {} = {{ \"{}\", sizeof({}), VK_NULL_HANDLE, VK_NULL_HANDLE, nullptr }};\n",
+            uniform.buffer_global(), name, name));
+        output.push_str(&format!("extern \"C\" void {}(const {}& value) {{\n", uniform.update_function(), name));
+        output.push_str(&format!("    heidic_uniform_buffer_write(&{}, &value);\n", uniform.buffer_global()));
+        output.push_str("}\n\n");
+        output
+    }
+    
     fn is_component_soa(&self, component_name: &str) -> bool {
         self.components.get(component_name)
             .map(|c| c.is_soa)
             .unwrap_or(false)
     }
     
+    // A @[launch] function's kernel and the view of its query it takes: the query's component
+    // arrays as device pointers, under the names query access generates (q.positions.x[i],
+    // q.velocities[i].x), so the body compiles as it would on the host. `for entity in q` becomes
+    // a grid-stride loop (generate_query_loop_header), each thread taking every Nth entity.
+    // GPU-resident components are reached through their mirrors (HeidicCudaResident), which
+    // index the same way.
     fn generate_cuda_kernel(&mut self, f: &FunctionDef) -> String {
         let mut output = String::new();
-        let kernel_name = f.cuda_kernel.as_ref().unwrap();
-        
-        // Generate CUDA kernel function
-        output.push_str(&format!("__global__ void {}_kernel(", kernel_name));
-        
-        // Parameters (convert query types to device pointers)
-        for (i, param) in f.params.iter().enumerate() {
-            if i > 0 {
-                output.push_str(", ");
-            }
-            // Convert query types to device pointers
-            if let Type::Query(_) = param.ty {
-                // For queries, generate device pointer parameters
-                output.push_str(&format!("{}* d_{}", self.type_to_cpp(&param.ty), param.name));
+        let Some(launch) = &f.cuda_kernel else { return output };
+        let Some((query, component_types)) = cuda::kernel_query(f) else { return output };
+        let view = cuda::view_type(launch, &query.name);
+        let array_type = |element: &str| if self.uses_gpu_sync { format!("HeidicCudaResident<{}>", element) } else { format!("{}*", element) };
+        if self.uses_gpu_sync {
+            output.push_str(&format!("// {}'s view of query '{}': its entities' rows in the component mirrors\n", launch.kernel, query.name));
+        } else {
+            output.push_str(&format!("// {}'s view of query '{}': device copies of its component arrays\n", launch.kernel, query.name));
+        }
+        output.push_str(&format!("struct {} {{\n", view));
+        for component in self.kernel_components(component_types) {
+            let arrays = cuda::device_arrays(&query.name, &component);
+            if component.is_soa {
+                output.push_str("    struct {\n");
+                for array in &arrays {
+                    output.push_str(&format!("        {} {};\n", array_type(&self.type_to_cpp(&array.element)), array.field.as_deref().unwrap_or_default()));
+                }
+                output.push_str(&format!("    }} {};\n", component_array_name(&component.name)));
             } else {
-                output.push_str(&format!("{} {}", self.type_to_cpp(&param.ty), param.name));
+                for array in &arrays {
+                    output.push_str(&format!("    {} {};\n", array_type(&self.type_to_cpp(&array.element)), array.member));
+                }
             }
         }
-        output.push_str(") {\n");
-        
-        // Get thread index
-        output.push_str("    int idx = blockIdx.x * blockDim.x + threadIdx.x;\n");
-        output.push_str("    if (idx >= /* size */) return;  // TODO: Add size parameter\n");
-        output.push_str("\n");
+        output.push_str("    size_t count;\n");
+        output.push_str("    __host__ __device__ size_t size() const { return count; }\n");
+        output.push_str("};\n\n");
         
-        // Generate kernel body (simplified - just generate statements)
+        let params: Vec<String> = f.params.iter().map(|param| match param.ty {
+            Type::Query(_) => format!("{} {}", view, param.name),
+            _ => format!("{} {}", self.type_to_cpp(&param.ty), param.name),
+        }).collect();
+        output.push_str(&format!("__global__ void {}_kernel({}) {{\n", launch.kernel, params.join(", ")));
+        self.query_params = HashMap::from([(query.name.clone(), component_types.to_vec())]);
+        self.cuda_query = Some(query.name.clone());
+        // Device code can't call the host's checked arithmetic helpers
+        let checked = self.checked_arithmetic.take();
         for stmt in &f.body {
             output.push_str(&self.generate_statement(stmt, 1));
         }
+        self.checked_arithmetic = checked;
+        self.cuda_query = None;
+        self.query_params.clear();
         
         output.push_str("}\n\n");
         output
     }
     
-    fn generate_cuda_launch_wrapper(&self, f: &FunctionDef) -> String {
+    // The .cu side of a @[launch] function: copy the host arrays to the device, run the kernel
+    // over `count` entities and copy the arrays back, checking every CUDA call. An async launch
+    // queues all of it on the kernel's stream and returns; the wait bridge finishes it. Over
+    // GPU-resident components only the query's entity ids are copied: the kernel works on the
+    // mirrors, which stay on the device.
+    fn generate_cuda_bridge(&self, f: &FunctionDef) -> String {
         let mut output = String::new();
-        let kernel_name = f.cuda_kernel.as_ref().unwrap();
+        let Some(launch) = &f.cuda_kernel else { return output };
+        let Some((query, component_types)) = cuda::kernel_query(f) else { return output };
+        let kernel = format!("{}_kernel", launch.kernel);
+        let components = self.kernel_components(component_types);
+        let arrays: Vec<cuda::DeviceArray> = components.iter()
+            .flat_map(|component| cuda::device_arrays(&query.name, component))
+            .collect();
+        let copied = if self.uses_gpu_sync { &[][..] } else { &arrays[..] };
         
-        // Generate CPU-side launch wrapper
-        output.push_str(&format!("void {}_launch(", f.name));
-        
-        // Parameters
-        for (i, param) in f.params.iter().enumerate() {
-            if i > 0 {
-                output.push_str(", ");
-            }
-            output.push_str(&format!("{} {}", self.type_to_cpp(&param.ty), param.name));
+        if launch.is_async {
+            output.push_str(&format!("static HeidicCudaStream {};\n\n", cuda::stream_global(launch)));
         }
-        output.push_str(") {\n");
-        
-        // Allocate device memory for CUDA components
-        for comp in &self.cuda_components {
-            output.push_str(&format!("    // Allocate device memory for {}\n", comp.name));
-            for field in &comp.fields {
-                if let Type::Array(_) = field.ty {
-                    output.push_str(&format!("    {}* d_{}_{};\n", 
-                        self.type_to_cpp(&field.ty), comp.name.to_lowercase(), field.name));
-                    output.push_str(&format!("    cudaMalloc(&d_{}_{}, sizeof({}) * /* size */);\n",
-                        comp.name.to_lowercase(), field.name, self.type_to_cpp(&field.ty)));
+        output.push_str(&format!("{} {{\n", self.cuda_bridge_signature(f)));
+        for array in copied {
+            output.push_str(&format!("    static HeidicCudaBuffer<{}> {};\n", self.type_to_cpp(&array.element), array.buffer));
+        }
+        if self.uses_gpu_sync {
+            output.push_str(&format!("    static HeidicCudaBuffer<uint32_t> d_{}_entities;\n", query.name));
+        }
+        output.push_str("    if (heidic_count == 0) return;\n");
+        if launch.is_async {
+            output.push_str(&format!("    cudaStream_t heidic_stream = {}.get();\n", cuda::stream_global(launch)));
+        } else {
+            output.push_str("    cudaStream_t heidic_stream = 0;\n");
+        }
+        for array in copied {
+            output.push_str(&format!("    if (!{}.upload({}, heidic_count, heidic_stream)) return;\n", array.buffer, array.host));
+        }
+        if self.uses_gpu_sync {
+            output.push_str(&format!("    if (!d_{q}_entities.upload({q}_entities, heidic_count, heidic_stream)) return;\n", q = query.name));
+        }
+        output.push_str(&format!("    {} d_{};\n", cuda::view_type(launch, &query.name), query.name));
+        if self.uses_gpu_sync {
+            for component in &components {
+                let mirrors = cuda::resident_arrays(component);
+                for (array, mirror) in arrays.iter().filter(|array| array.component == component.name).zip(&mirrors) {
+                    output.push_str(&format!("    d_{q}.{} = HeidicCudaResident<{}>{{{}.data(), {}.data(), d_{q}_entities.data()}};\n",
+                        array.member, self.type_to_cpp(&array.element), mirror.buffer, cuda::resident_rows(component), q = query.name));
                 }
             }
+        } else {
+            for array in &arrays {
+                output.push_str(&format!("    d_{}.{} = {}.data();\n", query.name, array.member, array.buffer));
+            }
+        }
+        output.push_str(&format!("    d_{}.count = heidic_count;\n", query.name));
+        let args: Vec<String> = f.params.iter().map(|param| match param.ty {
+            Type::Query(_) => format!("d_{}", param.name),
+            _ => param.name.clone(),
+        }).collect();
+        output.push_str(&format!("    {}<<<heidic_cuda_blocks(heidic_count), HEIDIC_CUDA_BLOCK_SIZE, 0, heidic_stream>>>({});\n", kernel, args.join(", ")));
+        output.push_str(&format!("    if (!heidic_cuda_check(cudaGetLastError(), \"launching {}\")) return;\n", kernel));
+        for array in copied {
+            output.push_str(&format!("    if (!{}.download({}, heidic_count, heidic_stream)) return;\n", array.buffer, array.host));
+        }
+        if !launch.is_async {
+            output.push_str(&format!("    heidic_cuda_check(cudaStreamSynchronize(heidic_stream), \"running {}\");\n", kernel));
         }
+        output.push_str("}\n\n");
+        if launch.is_async {
+            output.push_str(&format!("extern \"C\" void {}() {{\n", cuda::bridge_wait(launch)));
+            output.push_str(&format!("    heidic_cuda_check(cudaStreamSynchronize({}.get()), \"running {}\");\n", cuda::stream_global(launch), kernel));
+            output.push_str("}\n\n");
+        }
+        output
+    }
+    
+    // The host side of a @[launch] function: hand the query's arrays to the .cu's bridge, which
+    // copies the results back into them. Over GPU-resident components it hands over the query's
+    // entities instead, once the mirrors are known to hold them all; the results stay on the GPU
+    // until sync_to_cpu.
+    fn generate_cuda_launch_wrapper(&self, f: &FunctionDef) -> String {
+        let mut output = String::new();
+        let Some(launch) = &f.cuda_kernel else { return output };
+        let Some((query, component_types)) = cuda::kernel_query(f) else { return output };
+        let components = self.kernel_components(component_types);
         
-        // Copy data to device
-        output.push_str("    // Copy data to device\n");
-        for comp in &self.cuda_components {
-            for field in &comp.fields {
-                if let Type::Array(_) = field.ty {
-                    output.push_str(&format!("    cudaMemcpy(d_{}_{}, /* host_ptr */, sizeof({}) * /* size */, cudaMemcpyHostToDevice);\n",
-                        comp.name.to_lowercase(), field.name, self.type_to_cpp(&field.ty)));
-                }
+        output.push_str(&format!("{};\n", self.cuda_bridge_signature(f)));
+        if launch.is_async {
+            output.push_str(&format!("extern \"C\" void {}();\n", cuda::bridge_wait(launch)));
+        }
+        output.push('\n');
+        output.push_str(&format!("{} {{\n", self.cuda_launch_signature(f)));
+        if self.uses_gpu_sync {
+            for component in &components {
+                output.push_str(&format!("    if (!{}.covers(heidic_cuda_host({q}.entities), {q}.size(), \"{}<{}>()\")) return;\n",
+                    cuda::residency_global(component), SyncDirection::ToGpu.name(), component.name, q = query.name));
             }
         }
+        let args: Vec<String> = f.params.iter().flat_map(|param| match param.ty {
+            Type::Query(_) if self.uses_gpu_sync => vec![format!("heidic_cuda_host({}.entities)", param.name), format!("{}.size()", param.name)],
+            Type::Query(_) => components.iter()
+                .flat_map(|component| cuda::device_arrays(&param.name, component))
+                .map(|array| format!("heidic_cuda_host({}.{})", param.name, array.member))
+                .chain([format!("{}.size()", param.name)])
+                .collect(),
+            _ => vec![param.name.clone()],
+        }).collect();
+        output.push_str(&format!("    {}({});\n", cuda::bridge_launch(launch), args.join(", ")));
+        // The kernel may have written any of them; changed<T> queries see them as changed (mirrored
+        // components are marked when sync_to_cpu brings them back)
+        for component in components.iter().filter(|component| !self.uses_gpu_sync && self.changed_components.contains(&component.name)) {
+            output.push_str(&format!("    for (size_t i = 0; i < {q}.size(); ++i) g_storage.mark_changed<{}>({q}.entities[i]);\n", component.name, q = query.name));
+        }
+        output.push_str("}\n\n");
+        if launch.is_async {
+            output.push_str(&format!("// Wait for the {} calls queued so far: their queries' arrays hold the results after it\n", cuda::launch_function(f)));
+            output.push_str(&format!("void {}() {{\n", cuda::wait_function(f)));
+            output.push_str(&format!("    {}();\n", cuda::bridge_wait(launch)));
+            output.push_str("}\n\n");
+        }
+        output
+    }
+    
+    // The host side of a @[launch] function with --gpu-backend=vulkan: upload the query's arrays
+    // into the kernel's storage buffers, dispatch its compute shader and read the arrays back. An
+    // async launch leaves the readback to its wait, which the next launch also runs first, since
+    // it reuses the buffers.
+    fn generate_compute_launch(&self, f: &FunctionDef) -> String {
+        let mut output = String::new();
+        let Some(launch) = &f.cuda_kernel else { return output };
+        let Some((query, component_types)) = cuda::kernel_query(f) else { return output };
+        let components = self.kernel_components(component_types);
+        let arrays: Vec<cuda::DeviceArray> = components.iter()
+            .flat_map(|component| cuda::device_arrays(&query.name, component))
+            .collect();
+        let params = vulkan_compute::params_type(launch);
+        let kernel = vulkan_compute::kernel_global(launch);
         
-        // Launch kernel
-        output.push_str(&format!("    // Launch {} kernel\n", kernel_name));
-        output.push_str("    int blockSize = 256;\n");
-        output.push_str("    int numBlocks = (/* size */ + blockSize - 1) / blockSize;\n");
-        output.push_str(&format!("    {}_kernel<<<numBlocks, blockSize>>>(", kernel_name));
+        output.push_str(&format!("// {}'s push constants, laid out as in {}\n", launch.kernel, vulkan_compute::shader_path(launch)));
+        output.push_str(&format!("struct {} {{\n", params));
+        output.push_str("    uint32_t heidic_count;\n");
+        for param in f.params.iter().filter(|param| !matches!(param.ty, Type::Query(_))) {
+            // A GLSL bool is 32 bits
+            let ty = if matches!(param.ty, Type::Bool) { "uint32_t".to_string() } else { self.type_to_cpp(&param.ty) };
+            output.push_str(&format!("    {} {};\n", ty, param.name));
+        }
+        output.push_str("};\n\n");
+        output.push_str(&format!("static HeidicComputeKernel {} = {{ \"{}\", \"{}\", {}, sizeof({}), nullptr }};\n",
+            kernel, launch.kernel, vulkan_compute::spirv_path(launch), arrays.len(), params));
+        for array in &arrays {
+            output.push_str(&format!("static HeidicGpuBuffer {} = {{ \"{}.{}\", VK_NULL_HANDLE, VK_NULL_HANDLE, 0 }};\n",
+                vulkan_compute::buffer_global(launch, array), query.name, array.member));
+        }
+        if launch.is_async {
+            output.push_str(&format!("static void* {}[{}] = {{}};\n", vulkan_compute::results_global(launch), arrays.len()));
+            output.push_str(&format!("static size_t {} = 0;\n", vulkan_compute::pending_global(launch)));
+        }
+        output.push('\n');
         
-        // Kernel arguments
-        for (i, param) in f.params.iter().enumerate() {
-            if i > 0 {
-                output.push_str(", ");
+        let read_back = |output: &mut String, pad: &str, host: &dyn Fn(usize, &cuda::DeviceArray) -> String, count: &str| {
+            for (i, array) in arrays.iter().enumerate() {
+                output.push_str(&format!("{}heidic_gpu_buffer_read(&{}, {}, {} * sizeof({}));\n",
+                    pad, vulkan_compute::buffer_global(launch, array), host(i, array), count, self.type_to_cpp(&array.element)));
             }
-            if let Type::Query(_) = param.ty {
-                output.push_str(&format!("d_{}", param.name));
-            } else {
-                output.push_str(&param.name);
+        };
+        output.push_str(&format!("{} {{\n", self.cuda_launch_signature(f)));
+        if launch.is_async {
+            output.push_str(&format!("    {}();\n", cuda::wait_function(f)));
+        }
+        output.push_str(&format!("    if ({}.size() == 0) return;\n", query.name));
+        for array in &arrays {
+            output.push_str(&format!("    heidic_gpu_buffer_write(&{}, heidic_compute_host({q}.{}), {q}.size() * sizeof({}));\n",
+                vulkan_compute::buffer_global(launch, array), array.member, self.type_to_cpp(&array.element), q = query.name));
+        }
+        let values: Vec<String> = std::iter::once(format!("static_cast<uint32_t>({}.size())", query.name))
+            .chain(f.params.iter().filter(|param| !matches!(param.ty, Type::Query(_))).map(|param| match param.ty {
+                Type::Bool => format!("{} ? 1u : 0u", param.name),
+                _ => param.name.clone(),
+            }))
+            .collect();
+        output.push_str(&format!("    {} heidic_params{{{}}};\n", params, values.join(", ")));
+        let buffers: Vec<String> = arrays.iter().map(|array| format!("&{}", vulkan_compute::buffer_global(launch, array))).collect();
+        output.push_str(&format!("    HeidicGpuBuffer* heidic_buffers[] = {{{}}};\n", buffers.join(", ")));
+        output.push_str(&format!("    if (!heidic_compute_dispatch(&{}, heidic_buffers, &heidic_params, {}.size())) return;\n", kernel, query.name));
+        if launch.is_async {
+            for (i, array) in arrays.iter().enumerate() {
+                output.push_str(&format!("    {}[{}] = heidic_compute_host({}.{});\n", vulkan_compute::results_global(launch), i, query.name, array.member));
             }
+            output.push_str(&format!("    {} = {}.size();\n", vulkan_compute::pending_global(launch), query.name));
+        } else {
+            let host = |_: usize, array: &cuda::DeviceArray| format!("heidic_compute_host({}.{})", query.name, array.member);
+            read_back(&mut output, "    ", &host, &format!("{}.size()", query.name));
+        }
+        // The shader may have written any of them; changed<T> queries see them as changed
+        for component in components.iter().filter(|component| self.changed_components.contains(&component.name)) {
+            output.push_str(&format!("    for (size_t i = 0; i < {q}.size(); ++i) g_storage.mark_changed<{}>({q}.entities[i]);\n", component.name, q = query.name));
+        }
+        output.push_str("}\n\n");
+        if launch.is_async {
+            let pending = vulkan_compute::pending_global(launch);
+            output.push_str(&format!("// Read back the last {} call: its query's arrays hold the results after it\n", cuda::launch_function(f)));
+            output.push_str(&format!("void {}() {{\n", cuda::wait_function(f)));
+            output.push_str(&format!("    if ({} == 0) return;\n", pending));
+            let host = |i: usize, _: &cuda::DeviceArray| format!("{}[{}]", vulkan_compute::results_global(launch), i);
+            read_back(&mut output, "    ", &host, &pending);
+            output.push_str(&format!("    {} = 0;\n", pending));
+            output.push_str("}\n\n");
         }
-        output.push_str(");\n");
+        output
+    }
+    
+    // The .cu's entry point for a launch: the query as one host pointer per array (its entity ids
+    // over GPU-resident components) and its size
+    fn cuda_bridge_signature(&self, f: &FunctionDef) -> String {
+        let Some(launch) = &f.cuda_kernel else { return String::new() };
+        let params: Vec<String> = f.params.iter().flat_map(|param| match &param.ty {
+            Type::Query(_) if self.uses_gpu_sync => vec![format!("const uint32_t* {}_entities", param.name), "size_t heidic_count".to_string()],
+            Type::Query(component_types) => self.kernel_components(component_types).iter()
+                .flat_map(|component| cuda::device_arrays(&param.name, component))
+                .map(|array| format!("{}* {}", self.type_to_cpp(&array.element), array.host))
+                .chain(["size_t heidic_count".to_string()])
+                .collect(),
+            ty => vec![format!("{} {}", self.type_to_cpp(ty), param.name)],
+        }).collect();
+        format!("extern \"C\" void {}({})", cuda::bridge_launch(launch), params.join(", "))
+    }
+    
+    // The query is taken by reference: the results are copied back into its arrays
+    fn cuda_launch_signature(&self, f: &FunctionDef) -> String {
+        let params: Vec<String> = f.params.iter().map(|param| match param.ty {
+            Type::Query(_) => format!("{}& {}", self.type_to_cpp(&param.ty), param.name),
+            _ => format!("{} {}", self.type_to_cpp(&param.ty), param.name),
+        }).collect();
+        format!("void {}({})", cuda::launch_function(f), params.join(", "))
+    }
+    
+    // The @[cuda] components of a kernel's query, in query order (the type checker has rejected
+    // filters and components that aren't @[cuda])
+    fn kernel_components(&self, component_types: &[Type]) -> Vec<ComponentDef> {
+        component_types.iter().filter_map(|ty| match ty {
+            Type::Struct(name) | Type::Component(name) => self.cuda_components.iter().find(|c| &c.name == name).cloned(),
+            _ => None,
+        }).collect()
+    }
+    
+    // The host side of a GPU-resident component: sync_to_gpu<C>() gathers every entity's C from
+    // g_storage into arrays the .cu copies into the mirror, remembering which entity went to which
+    // row; sync_to_cpu<C>() has the mirror copied back and writes each row into its entity
+    fn generate_gpu_sync_functions(&self, component: &ComponentDef) -> String {
+        let mut output = String::new();
+        let name = &component.name;
+        let arrays = cuda::resident_arrays(component);
+        let residency = cuda::residency_global(component);
+        let (to_gpu, to_cpu) = (SyncDirection::ToGpu, SyncDirection::ToCpu);
+        let staging = |size: &str| arrays.iter()
+            .map(|array| format!("    std::vector<{}> {}{};\n", self.type_to_cpp(&array.element), array.host, size))
+            .collect::<String>();
+        let args = |extra: &[String]| arrays.iter().map(|array| format!("{}.data()", array.host)).chain(extra.iter().cloned()).collect::<Vec<_>>().join(", ");
         
-        // Copy data back from device
-        output.push_str("    // Copy data back from device\n");
-        for comp in &self.cuda_components {
-            for field in &comp.fields {
-                if let Type::Array(_) = field.ty {
-                    output.push_str(&format!("    cudaMemcpy(/* host_ptr */, d_{}_{}, sizeof({}) * /* size */, cudaMemcpyDeviceToHost);\n",
-                        comp.name.to_lowercase(), field.name, self.type_to_cpp(&field.ty)));
-                }
+        output.push_str(&format!("{};\n", self.gpu_sync_signature(component, to_gpu)));
+        output.push_str(&format!("{};\n\n", self.gpu_sync_signature(component, to_cpu)));
+        output.push_str(&format!("static HeidicCudaResidency {};\n\n", residency));
+        
+        output.push_str(&format!("// Copy every entity's {} into its device mirror, where kernels work on it until {}<{}>()\n", name, to_cpu.name(), name));
+        output.push_str(&format!("void {}() {{\n", cuda::sync_function(to_gpu, name)));
+        output.push_str(&format!("    {}.begin(g_storage.entity_limit());\n", residency));
+        output.push_str(&staging(""));
+        output.push_str(&format!("    g_storage.for_each<{}>([&](EntityId entity, const auto& value) {{\n", name));
+        output.push_str(&format!("        {}.add(entity);\n", residency));
+        for array in &arrays {
+            match &array.field {
+                Some(field) => output.push_str(&format!("        {}.push_back(value.{});\n", array.host, field)),
+                None => output.push_str(&format!("        {}.push_back(value);\n", array.host)),
             }
         }
+        output.push_str("    });\n");
+        output.push_str(&format!("    {}({});\n", cuda::bridge_sync(to_gpu, name),
+            args(&[format!("{}.size()", residency), format!("{}.rows.data()", residency), format!("{}.rows.size()", residency)])));
+        output.push_str("}\n\n");
         
-        // Free device memory
-        output.push_str("    // Free device memory\n");
-        for comp in &self.cuda_components {
-            for field in &comp.fields {
-                if let Type::Array(_) = field.ty {
-                    output.push_str(&format!("    cudaFree(d_{}_{});\n",
-                        comp.name.to_lowercase(), field.name));
-                }
+        output.push_str(&format!("// Copy the device mirror of {} back into the entities it was taken from (those that still have one)\n", name));
+        output.push_str(&format!("void {}() {{\n", cuda::sync_function(to_cpu, name)));
+        output.push_str(&staging(&format!("({}.size())", residency)));
+        output.push_str(&format!("    if (!{}({})) return;\n", cuda::bridge_sync(to_cpu, name), args(&[format!("{}.size()", residency)])));
+        output.push_str(&format!("    for (size_t i = 0; i < {}.size(); ++i) {{\n", residency));
+        output.push_str(&format!("        EntityId entity = {}.entities[i];\n", residency));
+        if component.is_soa {
+            output.push_str(&format!("        auto row = g_storage.get_row<{}>(entity);\n", name));
+            output.push_str("        if (!row) continue;\n");
+            for array in &arrays {
+                output.push_str(&format!("        row->{} = {}[i];\n", array.field.as_deref().unwrap_or_default(), array.host));
+            }
+            output.push_str(&format!("        g_storage.add_component<{}>(entity, *row);\n", name));
+        } else {
+            output.push_str(&format!("        {}* value = g_storage.get_component<{}>(entity);\n", name, name));
+            output.push_str("        if (!value) continue;\n");
+            for array in &arrays {
+                output.push_str(&format!("        *value = {}[i];\n", array.host));
+            }
+            if self.changed_components.contains(name) {
+                output.push_str(&format!("        g_storage.mark_changed<{}>(entity);\n", name));
             }
         }
-        
+        output.push_str("    }\n");
         output.push_str("}\n\n");
         output
     }
     
+    // The .cu side of a GPU-resident component: its mirror, kept between syncs, and the bridges
+    // copying it. Both wait for kernels still using the mirror first.
+    fn generate_gpu_mirror(&self, component: &ComponentDef) -> String {
+        let mut output = String::new();
+        let name = &component.name;
+        let arrays = cuda::resident_arrays(component);
+        let rows = cuda::resident_rows(component);
+        if component.is_soa {
+            output.push_str(&format!("// Device mirror of {}: one array per field, and the row of each entity id in them\n", name));
+        } else {
+            output.push_str(&format!("// Device mirror of {}: its values, and the row of each entity id in them\n", name));
+        }
+        for array in &arrays {
+            output.push_str(&format!("static HeidicCudaBuffer<{}> {};\n", self.type_to_cpp(&array.element), array.buffer));
+        }
+        output.push_str(&format!("static HeidicCudaBuffer<uint32_t> {};\n\n", rows));
+        
+        for direction in [SyncDirection::ToGpu, SyncDirection::ToCpu] {
+            let call = format!("{}<{}>()", direction.name(), name);
+            output.push_str(&format!("{} {{\n", self.gpu_sync_signature(component, direction)));
+            output.push_str(&format!("    if (!heidic_cuda_check(cudaDeviceSynchronize(), \"waiting for kernels before {}\")) return false;\n", call));
+            for array in &arrays {
+                match direction {
+                    SyncDirection::ToGpu => output.push_str(&format!("    if (!{}.upload({}, heidic_count, 0)) return false;\n", array.buffer, array.host)),
+                    SyncDirection::ToCpu => output.push_str(&format!("    if (!{}.download({}, heidic_count, 0)) return false;\n", array.buffer, array.host)),
+                }
+            }
+            if direction == SyncDirection::ToGpu {
+                output.push_str(&format!("    if (!{}.upload(heidic_rows, heidic_limit, 0)) return false;\n", rows));
+            }
+            output.push_str(&format!("    return heidic_cuda_check(cudaStreamSynchronize(0), \"running {}\");\n", call));
+            output.push_str("}\n\n");
+        }
+        output
+    }
+    
+    // The .cu's entry point for one direction of a GPU-resident component's sync: an array of
+    // each field's values, in mirror row order (and, to the GPU, each entity id's row)
+    fn gpu_sync_signature(&self, component: &ComponentDef, direction: SyncDirection) -> String {
+        let constness = if direction == SyncDirection::ToGpu { "const " } else { "" };
+        let mut params: Vec<String> = cuda::resident_arrays(component).iter()
+            .map(|array| format!("{}{}* {}", constness, self.type_to_cpp(&array.element), array.host))
+            .collect();
+        params.push("size_t heidic_count".to_string());
+        if direction == SyncDirection::ToGpu {
+            params.extend(["const uint32_t* heidic_rows".to_string(), "size_t heidic_limit".to_string()]);
+        }
+        format!("extern \"C\" bool {}({})", cuda::bridge_sync(direction, &component.name), params.join(", "))
+    }
+    
     fn generate_function(&mut self, f: &FunctionDef, indent: usize) -> String {
         let mut output = String::new();
+        let strict_fp = f.strict_fp || self.strict_fp;
+        if strict_fp {
+            output.push_str("HEIDIC_STRICT_FP_BEGIN\n");
+        }
         
         // Rename HEIDIC main to avoid conflict with C++ main
         let func_name = if f.name == "main" {
@@ -1822,18 +4729,22 @@ impl CodeGenerator {
             self.type_to_cpp(&f.return_type)
         };
         
-        output.push_str(&format!("{} {}(", return_type, func_name));
+        output.push_str(&format!("{}{} {}(", self.interface_template_header(&f.params), return_type, func_name));
         
         // Parameters
         for (i, param) in f.params.iter().enumerate() {
             if i > 0 {
                 output.push_str(", ");
             }
-            output.push_str(&format!("{} {}", 
-                self.type_to_cpp(&param.ty), 
-                param.name));
+            output.push_str(&self.param_to_cpp(param));
         }
         output.push_str(") {\n");
+        self.reload_checks = !f.no_reload_check && !self.inner_loop_functions.contains(&f.name);
+        self.query_params = f.params.iter().filter_map(|param| match &param.ty {
+            Type::Query(component_types) => Some((param.name.clone(), component_types.clone())),
+            _ => None,
+        }).collect();
+        output.push_str(&self.generate_change_detection(indent + 1));
         
         // Inject ECS initialization if we have hot components and this is main
         if f.name == "main" && !self.hot_components.is_empty() {
@@ -1925,34 +4836,45 @@ impl CodeGenerator {
                 output.push_str(&self.generate_statement(stmt, indent + 1));
             }
         }
+        self.reload_checks = false;
+        self.query_params.clear();
         
         // If it's main with void return type, add return 0
         if f.name == "main" && matches!(f.return_type, Type::Void) {
             output.push_str(&format!("{}    return 0;\n", self.indent(indent + 1)));
         }
         
-        output.push_str("}\n\n");
+        output.push_str("}\n");
+        if strict_fp {
+            output.push_str("HEIDIC_STRICT_FP_END\n");
+        }
+        output.push('\n');
         output
     }
     
     fn generate_statement_with_entity(&mut self, stmt: &Statement, indent: usize, entity_name: &str, query_name: &str) -> String {
         // Generate statement but replace entity.Component.field with query.component_arrays[entity_index].field
         match stmt {
-            Statement::Let { name, ty, value, .. } => {
+            Statement::Let { name, mutable, ty, value, .. } => {
                 // Handle let statements with entity access in value
-                let type_str = if let Some(t) = ty {
-                    format!("{} ", self.type_to_cpp(t))
-                } else {
-                    String::new()
-                };
+                let constness = if self.is_const_local(*mutable) { "const " } else { "" };
+                // A frame allocation is a HeidicSlice, not the std::vector an array type names
+                let type_str = ty.as_ref().filter(|_| !matches!(value, Expression::FrameAlloc { .. }))
+                    .map_or("auto".to_string(), |t| self.type_to_cpp(t));
                 let value_str = self.generate_expression_with_entity(value, entity_name, query_name);
-                format!("{}    {} {}= {};\n", self.indent(indent), type_str, name, value_str)
+                format!("{}    {}{} {} = {};\n", self.indent(indent), constness, type_str, name, value_str)
             }
             Statement::Assign { target, value, .. } => {
                 // Handle entity.Component.field = value
                 let target_str = self.generate_expression_with_entity(target, entity_name, query_name);
                 let value_str = self.generate_expression_with_entity(value, entity_name, query_name);
-                format!("{}    {} = {};\n", self.indent(indent), target_str, value_str)
+                let mut output = format!("{}    {} = {};\n", self.indent(indent), target_str, value_str);
+                // Writes to a component some query filters with changed<T> are recorded for it
+                // (a kernel's writes are recorded by its launch, on the host)
+                if let Some(component) = written_component(target, entity_name).filter(|c| self.changed_components.contains(*c) && self.cuda_query.is_none()) {
+                    output.push_str(&format!("{}    g_storage.mark_changed<{}>({}_entity);\n", self.indent(indent), component, entity_name));
+                }
+                output
             }
             _ => {
                 // For other statements, use regular generation but with entity context
@@ -1964,11 +4886,30 @@ impl CodeGenerator {
     fn generate_statement_with_entity_fallback(&mut self, stmt: &Statement, indent: usize, entity_name: &str, query_name: &str) -> String {
         // Fallback for statements that need entity context but aren't handled above
         match stmt {
+            Statement::Expression(Expression::Match { expr, arms, .. }, _) => {
+                self.generate_match_statement(expr, arms, indent, Some((entity_name, query_name)))
+            }
+            Statement::Destructure { pattern, value, .. } => {
+                self.generate_destructure(pattern, value, indent, Some((entity_name, query_name)))
+            }
             Statement::Expression(expr, ..) => {
                 format!("{}    {};\n",
                     self.indent(indent),
                     self.generate_expression_with_entity(expr, entity_name, query_name))
             }
+            Statement::If { condition, then_block, else_block, .. } if self.constant_condition(condition).is_some() => {
+                let taken = if self.constant_condition(condition) == Some(true) { Some(then_block) } else { else_block.as_ref() };
+                let mut output = String::new();
+                if let Some(block) = taken {
+                    output.push_str(&format!("{}    {{\n", self.indent(indent)));
+                    for stmt in block {
+                        output.push_str(&self.generate_statement_with_entity(stmt, indent + 1, entity_name, query_name));
+                    }
+                    output.push_str(&format!("{}    }}\n", self.indent(indent)));
+                }
+                output
+            }
+            Statement::While { condition, .. } if self.constant_condition(condition) == Some(false) => String::new(),
             Statement::If { condition, then_block, else_block, .. } => {
                 let mut output = format!("{}    if ({}) {{\n", 
                     self.indent(indent),
@@ -1996,13 +4937,18 @@ impl CodeGenerator {
                 output.push_str(&format!("{}    }}\n", self.indent(indent)));
                 output
             }
+            Statement::For { iterator, collection, chunk_size: Some(size), body, .. } => {
+                let collection_expr = self.generate_expression_with_entity(collection, entity_name, query_name);
+                let size_expr = self.generate_expression_with_entity(size, entity_name, query_name);
+                self.generate_chunk_loop(iterator, &collection_expr, &size_expr, body, indent)
+            }
             Statement::For { iterator, collection, body, .. } => {
                 // Nested for loop - generate with entity context
-                let collection_expr = self.generate_expression_with_entity(collection, entity_name, query_name);
+                let query = hierarchy::children_query(collection).map_or(collection, |(query, _)| query);
+                let collection_expr = self.generate_expression_with_entity(query, entity_name, query_name);
                 let mut output = format!("{}    // Nested query iteration: for {} in {}\n", 
                     self.indent(indent), iterator, collection_expr);
-                output.push_str(&format!("{}    for (size_t {}_index = 0; {}_index < {}.size(); ++{}_index) {{\n",
-                    self.indent(indent), iterator, iterator, collection_expr, iterator));
+                output.push_str(&self.generate_query_loop_header(iterator, collection, &collection_expr, indent));
                 for stmt in body {
                     // Nested for loop gets its own entity context
                     output.push_str(&self.generate_statement_with_entity(stmt, indent + 1, iterator, &collection_expr));
@@ -2019,12 +4965,21 @@ impl CodeGenerator {
                     format!("{}    return 0;\n", self.indent(indent))
                 }
             }
-            Statement::Break(_) => {
+            // Only a loop used as a value takes a break value, and that loop is a lambda
+            Statement::Break(Some(value), _) => {
+                format!("{}    return {};\n", self.indent(indent), self.lambda_return_value(value, Some((entity_name, query_name))))
+            }
+            Statement::Break(None, _) => {
                 format!("{}    break;\n", self.indent(indent))
             }
             Statement::Continue(_) => {
                 format!("{}    continue;\n", self.indent(indent))
             }
+            Statement::Emit(event, ..) => {
+                let Expression::StructLiteral { name, .. } = event else { return String::new() };
+                format!("{}    {}.emit({});\n", self.indent(indent), event_queue(name),
+                    self.generate_expression_with_entity(event, entity_name, query_name))
+            }
             Statement::Defer(expr, ..) => {
                 // Generate RAII-based defer: auto defer_N = make_defer([&]() { expr; });
                 let defer_id = self.defer_counter;
@@ -2051,6 +5006,9 @@ impl CodeGenerator {
                 output.push_str(&format!("{}    }}\n", self.indent(indent)));
                 output
             }
+            Statement::SpawnBatch { count, index, body, .. } => {
+                self.generate_spawn_batch(count, index, body, indent, Some((entity_name, query_name)))
+            }
             Statement::Let { .. } => {
                 // These are handled in generate_statement_with_entity
                 self.generate_statement(stmt, indent)
@@ -2062,9 +5020,101 @@ impl CodeGenerator {
         }
     }
     
+    // Releases what an iteration of an outermost loop allocated from the frame arena when it ends
+    fn frame_scope(&self, indent: usize) -> String {
+        format!("{}    HeidicFrameScope heidic_frame_scope({});\n", self.indent(indent), frame_arena::GLOBAL)
+    }
+    
+    // for chunk in q.chunks(n): the query in consecutive slices of up to n entities, so a system
+    // can hand pointer+length to an extern routine instead of making a call per entity
+    fn generate_chunk_loop(&mut self, iterator: &str, query: &str, size: &str, body: &[Statement], indent: usize) -> String {
+        let pad = format!("{}    ", self.indent(indent));
+        let mut output = format!("{}// Chunked query iteration: for {} in {}.chunks({})\n", pad, iterator, query, size);
+        output.push_str(&format!("{}for (size_t {it}_start = 0, {it}_step = heidic_chunk_step({}); {it}_start < {q}.size(); {it}_start += {it}_step) {{\n",
+            pad, size, it = iterator, q = query));
+        output.push_str(&format!("{}    const int32_t {it}_count = heidic_chunk_count({}.size(), {it}_start, {it}_step);\n", pad, query, it = iterator));
+        self.chunk_iterators.push(iterator.to_string());
+        let reload_checks = std::mem::replace(&mut self.reload_checks, false);
+        for stmt in body {
+            output.push_str(&self.generate_statement_with_entity(stmt, indent + 1, iterator, query));
+        }
+        self.reload_checks = reload_checks;
+        self.chunk_iterators.pop();
+        output.push_str(&format!("{}}}\n", pad));
+        output
+    }
+
+    // With --checked-arithmetic, integer arithmetic goes through stdlib/checked_arithmetic.h
+    fn binary_expression(&self, op: &BinaryOp, left: String, right: String, location: SourceLocation) -> String {
+        let checked = match op {
+            BinaryOp::Add => Some("add"),
+            BinaryOp::Sub => Some("sub"),
+            BinaryOp::Mul => Some("mul"),
+            BinaryOp::Div => Some("div"),
+            BinaryOp::Mod => Some("mod"),
+            _ => None,
+        };
+        if let (Some(name), Some(source)) = (checked, &self.checked_arithmetic) {
+            return format!("heidic_checked_{}({}, {}, \"{}:{}:{}\")", name, left, right, source, location.line, location.column);
+        }
+        let op_str = match op {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        };
+        format!("({} {} {})", left, op_str, right)
+    }
+    
+    // Arrays passed to an extern fn become a pointer to their first element
+    fn extern_arg(&self, function: &str, index: usize, arg: String) -> String {
+        let is_array = self.extern_functions.iter()
+            .find(|ext| ext.name == function)
+            .and_then(|ext| ext.params.get(index))
+            .is_some_and(|param| matches!(param.ty, Type::Array(_)));
+        if is_array {
+            format!("heidic_data({})", arg)
+        } else {
+            arg
+        }
+    }
+
+    // Members of a chunk: chunk.count, chunk.positions (a slice of an AoS component) and
+    // chunk.velocities.x (a slice of one field array of a component_soa)
+    fn chunk_member_access(&self, object: &Expression, member: &str, entity_name: &str, query_name: &str) -> Option<String> {
+        if !self.chunk_iterators.iter().any(|it| it == entity_name) {
+            return None;
+        }
+        let slice = |array: String| format!("heidic_slice({}, {it}_start, {it}_count)", array, it = entity_name);
+        match object {
+            Expression::Variable(name, _) if name == entity_name => Some(match member {
+                "count" => format!("{}_count", entity_name),
+                _ => slice(format!("{}.{}", query_name, member)),
+            }),
+            Expression::MemberAccess { object: inner, member: array, .. }
+                if matches!(inner.as_ref(), Expression::Variable(name, _) if name == entity_name) =>
+            {
+                Some(slice(format!("{}.{}.{}", query_name, array, member)))
+            }
+            _ => None,
+        }
+    }
+
     fn generate_expression_with_entity(&mut self, expr: &Expression, entity_name: &str, query_name: &str) -> String {
         match expr {
             Expression::MemberAccess { object, member, .. } => {
+                if let Some(access) = self.chunk_member_access(object, member, entity_name, query_name) {
+                    return access;
+                }
                 // Check if this is entity.Component.field pattern
                 if let Expression::MemberAccess { object: inner_obj, member: component_name, .. } = object.as_ref() {
                     // This is entity.Component.field (nested member access)
@@ -2074,21 +5124,13 @@ impl CodeGenerator {
                             // Check if component is SOA
                             let is_soa = self.is_component_soa(component_name);
                             
-                            // Convert to lowercase and pluralize (Position -> positions, Velocity -> velocities)
-                            let component_lower = component_name.to_lowercase();
-                            let component_plural = if component_lower.ends_with('y') {
-                                // Velocity -> velocities (y -> ies)
-                                format!("{}ies", &component_lower[..component_lower.len()-1])
-                            } else if component_lower.ends_with('s') || component_lower.ends_with('x') || component_lower.ends_with('z') || component_lower.ends_with('h') {
-                                format!("{}es", component_lower)
-                            } else {
-                                format!("{}s", component_lower)
-                            };
+                            let component_plural = component_array_name(component_name);
                             
                             // Generate access pattern based on SOA vs AoS
-                            if is_soa {
-                                // SOA: query.velocities.x[entity_index] (field is array, index at end)
-                                format!("{}.{}.{}[{}_index]", query_name, component_plural, member, entity_name)
+                            if let Some(component) = self.components.get(component_name).filter(|_| is_soa) {
+                                // SOA: query.velocities.x[entity_index] (field is array, index at end),
+                                // or query.velocities.heidic_cold[entity_index].x for a @cold field
+                                format!("{}.{}.{}", query_name, component_plural, soa_element(component, member, &format!("{}_index", entity_name)))
                             } else {
                                 // AoS: query.positions[entity_index].x (index first, then field)
                                 format!("{}.{}[{}_index].{}", query_name, component_plural, entity_name, member)
@@ -2106,9 +5148,9 @@ impl CodeGenerator {
                 } else {
                     // Single level member access, check if object is entity.Component
                     let obj_expr = self.generate_expression_with_entity(object, entity_name, query_name);
-                    if obj_expr == entity_name {
-                        // This is entity.Component (without field) - shouldn't happen in valid code
-                        format!("{}.{}", obj_expr, member)
+                    if obj_expr == entity_name && self.components.contains_key(member) && !self.is_component_soa(member) {
+                        // entity.Component (without field) - the whole component, e.g. passed to an interface method
+                        format!("{}.{}[{}_index]", query_name, component_array_name(member), entity_name)
                     } else {
                         format!("{}.{}", obj_expr, member)
                     }
@@ -2122,35 +5164,52 @@ impl CodeGenerator {
                     name.clone()
                 }
             }
-            Expression::BinaryOp { op, left, right, .. } => {
-                let op_str = match op {
-                    BinaryOp::Add => "+",
-                    BinaryOp::Sub => "-",
-                    BinaryOp::Mul => "*",
-                    BinaryOp::Div => "/",
-                    BinaryOp::Mod => "%",
-                    BinaryOp::Eq => "==",
-                    BinaryOp::Ne => "!=",
-                    BinaryOp::Lt => "<",
-                    BinaryOp::Le => "<=",
-                    BinaryOp::Gt => ">",
-                    BinaryOp::Ge => ">=",
-                    BinaryOp::And => "&&",
-                    BinaryOp::Or => "||",
-                };
-                format!("({} {} {})", 
-                    self.generate_expression_with_entity(left, entity_name, query_name),
-                    op_str,
-                    self.generate_expression_with_entity(right, entity_name, query_name))
+            Expression::BinaryOp { op, left, right, location } => {
+                let (left, right) = (self.generate_expression_with_entity(left, entity_name, query_name), self.generate_expression_with_entity(right, entity_name, query_name));
+                self.binary_expression(op, left, right, *location)
             }
-            Expression::Call { name, args, .. } => {
+            Expression::Call { name, args, location } => {
+                if let Some((prefix, rest)) = self.gpu_intrinsic_call(name, args) {
+                    return match rest {
+                        Some(arg) => format!("{}, {})", prefix, self.generate_expression_with_entity(arg, entity_name, query_name)),
+                        None => format!("{})", prefix),
+                    };
+                }
+                if matches!(name.as_str(), "expect" | "expect_frame" | "screenshot") {
+                    let generated: Vec<String> = args.iter().map(|arg| self.generate_expression_with_entity(arg, entity_name, query_name)).collect();
+                    if let Some(call) = self.test_intrinsic_call(name, args, &generated, location.line) {
+                        return call;
+                    }
+                }
+                if name == "reserve_entities" && args.len() == 1 && !self.function_params.contains_key(name) {
+                    return format!("g_storage.reserve_entities(static_cast<uint32_t>({}))",
+                        self.generate_expression_with_entity(&args[0], entity_name, query_name));
+                }
+                if name == "run_systems" && args.is_empty() && !self.function_params.contains_key(name) {
+                    return "heidic_run_systems()".to_string();
+                }
+                if let Some(call) = self.hierarchy_call(name, args).or_else(|| self.spatial_play_call(name, args)) {
+                    return call;
+                }
+                if input_map::method(name).is_some() {
+                    let args: Vec<String> = args.iter().map(|arg| self.generate_expression_with_entity(arg, entity_name, query_name)).collect();
+                    if let Some(call) = self.input_call(name, &args) {
+                        return call;
+                    }
+                }
+                if let Some(callee) = self.window_function(name) {
+                    let args: Vec<String> = args.iter().map(|arg| self.generate_expression_with_entity(arg, entity_name, query_name)).collect();
+                    return format!("{}({})", callee, args.join(", "));
+                }
                 // Generate function call with entity context for arguments
+                let args = self.call_args_with_defaults(name, args);
                 let mut output = format!("{}(", name);
                 for (i, arg) in args.iter().enumerate() {
                     if i > 0 {
                         output.push_str(", ");
                     }
-                    output.push_str(&self.generate_expression_with_entity(arg, entity_name, query_name));
+                    let arg_expr = self.generate_expression_with_entity(arg, entity_name, query_name);
+                    output.push_str(&self.extern_arg(name, i, arg_expr));
                 }
                 output.push_str(")");
                 output
@@ -2160,82 +5219,621 @@ impl CodeGenerator {
                     self.generate_expression_with_entity(array, entity_name, query_name),
                     self.generate_expression_with_entity(index, entity_name, query_name))
             }
-            Expression::UnaryOp { op, expr, .. } => {
-                let op_str = match op {
-                    UnaryOp::Neg => "-",
-                    UnaryOp::Not => "!",
-                };
-                format!("{}({})", op_str, self.generate_expression_with_entity(expr, entity_name, query_name))
+            Expression::UnaryOp { op, expr, location } => {
+                let operand = self.generate_expression_with_entity(expr, entity_name, query_name);
+                match (op, &self.checked_arithmetic) {
+                    (UnaryOp::Neg, Some(source)) => format!("heidic_checked_neg({}, \"{}:{}:{}\")", operand, source, location.line, location.column),
+                    (UnaryOp::Neg, None) => format!("-({})", operand),
+                    (UnaryOp::Not, _) => format!("!({})", operand),
+                }
             }
+            Expression::Cast { expr, ty, .. } => {
+                format!("static_cast<{}>({})", self.type_to_cpp(ty), self.generate_expression_with_entity(expr, entity_name, query_name))
+            }
+            Expression::LayoutOf { query, ty, field, .. } => self.generate_layout_of(*query, ty, field.as_deref()),
+            Expression::GpuSync { direction, component, .. } => format!("{}()", cuda::sync_function(*direction, &self.type_to_cpp(component))),
+            Expression::FrameAlloc { arena, element, count, .. } => {
+                format!("{}.{}<{}>({})", self.generate_expression_with_entity(arena, entity_name, query_name),
+                    frame_arena::METHOD, self.type_to_cpp(element), self.generate_expression_with_entity(count, entity_name, query_name))
+            }
+            Expression::If { condition, then_branch, else_branch, .. } => {
+                self.generate_if_expression(condition, then_branch, else_branch, Some((entity_name, query_name)))
+            }
+            Expression::Block { body, .. } => self.generate_block_expression(body, Some((entity_name, query_name))),
+            Expression::Loop { body, .. } => self.generate_loop_expression(body, Some((entity_name, query_name))),
             Expression::Literal(lit, _) => {
-                match lit {
-                    Literal::Int(n) => n.to_string(),
-                    Literal::Float(n) => n.to_string(),
-                    Literal::Bool(b) => b.to_string(),
-                    Literal::String(s) => format!("\"{}\"", s),
-                }
+                literal_to_cpp(lit)
             }
             Expression::Match { expr, arms, .. } => {
-                // Generate C++ code for match expression (same as in generate_expression)
-                let expr_str = self.generate_expression_with_entity(expr, entity_name, query_name);
-                let mut output = String::new();
-                
-                for (i, arm) in arms.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(" else ");
-                    }
-                    
-                    output.push_str("if (");
-                    
-                    // Generate pattern match condition
-                    match &arm.pattern {
-                        crate::ast::Pattern::Literal(lit, _) => {
-                            let lit_str = match lit {
-                                crate::ast::Literal::Int(n) => n.to_string(),
-                                crate::ast::Literal::Float(n) => n.to_string(),
-                                crate::ast::Literal::Bool(b) => b.to_string(),
-                                crate::ast::Literal::String(s) => format!("\"{}\"", s),
-                            };
-                            output.push_str(&format!("{} == {}", expr_str, lit_str));
-                        }
-                        crate::ast::Pattern::Variable(var_name, _) => {
-                            // Variable binding - always matches, bind variable
-                            output.push_str(&format!("({} = {}, true)", var_name, expr_str));
-                        }
-                        crate::ast::Pattern::Wildcard(_) => {
-                            // Wildcard - always matches
-                            output.push_str("true");
-                        }
-                        crate::ast::Pattern::Ident(name, _) => {
-                            // Identifier (enum variant, constant) - compare with identifier
-                            output.push_str(&format!("{} == {}", expr_str, name));
-                        }
-                    }
-                    
-                    output.push_str(") {\n");
-                    
-                    // Generate body
-                    for stmt in &arm.body {
-                        output.push_str(&self.generate_statement(stmt, 1));
-                        output.push_str("\n");
-                    }
-                    
-                    output.push_str("}");
-                }
-                
-                output
+                self.generate_match_value(expr, arms, Some((entity_name, query_name)))
             }
             _ => self.generate_expression(expr)
         }
     }
     
+    // `entity` is the (entity, query) context inside a query loop, where component access goes
+    // through the query's arrays
+    fn generate_expression_in(&mut self, expr: &Expression, entity: Option<(&str, &str)>) -> String {
+        match entity {
+            Some((entity_name, query_name)) => self.generate_expression_with_entity(expr, entity_name, query_name),
+            None => self.generate_expression(expr),
+        }
+    }
+
+    fn generate_statement_in(&mut self, stmt: &Statement, indent: usize, entity: Option<(&str, &str)>) -> String {
+        match entity {
+            Some((entity_name, query_name)) => self.generate_statement_with_entity(stmt, indent, entity_name, query_name),
+            None => self.generate_statement(stmt, indent),
+        }
+    }
+
+    // A value returned from a generated lambda. String literals become std::string so that every
+    // return statement deduces the same type.
+    fn lambda_return_value(&mut self, value: &Expression, entity: Option<(&str, &str)>) -> String {
+        let generated = self.generate_expression_in(value, entity);
+        match value {
+            Expression::Literal(Literal::String(_), _) => format!("std::string({})", generated),
+            _ => generated,
+        }
+    }
+
+    // `(c ? a : b)` when both branches are a bare value; otherwise a lambda invoked in place, so
+    // the branch statements run first
+    fn generate_if_expression(&mut self, condition: &Expression, then_branch: &ValueBlock, else_branch: &ValueBlock, entity: Option<(&str, &str)>) -> String {
+        let condition = self.generate_expression_in(condition, entity);
+        if then_branch.statements.is_empty() && else_branch.statements.is_empty() {
+            let then_value = self.generate_expression_in(&then_branch.value, entity);
+            let else_value = self.generate_expression_in(&else_branch.value, entity);
+            return format!("({} ? {} : {})", condition, then_value, else_value);
+        }
+
+        let mut output = String::from("[&]() {\n");
+        output.push_str(&format!("    if ({}) {{\n", condition));
+        for stmt in &then_branch.statements {
+            output.push_str(&self.generate_statement_in(stmt, 1, entity));
+        }
+        output.push_str(&format!("        return {};\n", self.lambda_return_value(&then_branch.value, entity)));
+        output.push_str("    }\n");
+        for stmt in &else_branch.statements {
+            output.push_str(&self.generate_statement_in(stmt, 0, entity));
+        }
+        output.push_str(&format!("    return {};\n", self.lambda_return_value(&else_branch.value, entity)));
+        output.push_str("}()");
+        output
+    }
+
+    // A block with statements is a lambda invoked in place; a bare `{ value }` is just the value
+    fn generate_block_expression(&mut self, body: &ValueBlock, entity: Option<(&str, &str)>) -> String {
+        if body.statements.is_empty() {
+            return format!("({})", self.generate_expression_in(&body.value, entity));
+        }
+        let mut output = String::from("[&]() {\n");
+        for stmt in &body.statements {
+            output.push_str(&self.generate_statement_in(stmt, 0, entity));
+        }
+        output.push_str(&format!("    return {};\n", self.lambda_return_value(&body.value, entity)));
+        output.push_str("}()");
+        output
+    }
+
+    // A loop used as a value is a lambda invoked in place: `break value;` returns from it. Its
+    // loops run inside the enclosing one, which keeps the hot-reload checks.
+    fn generate_loop_expression(&mut self, body: &[Statement], entity: Option<(&str, &str)>) -> String {
+        let reload_checks = std::mem::replace(&mut self.reload_checks, false);
+        let mut output = String::from("[&]() {\n");
+        output.push_str("    while (true) {\n");
+        for stmt in body {
+            output.push_str(&self.generate_statement_in(stmt, 1, entity));
+        }
+        output.push_str("    }\n");
+        output.push_str("}()");
+        self.reload_checks = reload_checks;
+        output
+    }
+
+    // Test for one match arm against the already-evaluated scrutinee (None: the arm always matches)
+    fn match_arm_condition(scrutinee: &str, pattern: &Pattern) -> Option<String> {
+        match pattern {
+            Pattern::Literal(lit, _) => Some(format!("{} == {}", scrutinee, literal_to_cpp(lit))),
+            // Identifier (enum variant, constant) - compare with identifier
+            Pattern::Ident(name, _) => Some(format!("{} == {}", scrutinee, name)),
+            Pattern::Range { start, end, inclusive, .. } => Some(format!("{} >= {} && {} {} {}",
+                scrutinee, literal_to_cpp(start), scrutinee, if *inclusive { "<=" } else { "<" }, literal_to_cpp(end))),
+            Pattern::Or(alternatives, _) => {
+                let mut tests = Vec::new();
+                for alternative in alternatives {
+                    let test = Self::match_arm_condition(scrutinee, alternative)?;
+                    tests.push(if matches!(alternative, Pattern::Range { .. }) { format!("({})", test) } else { test });
+                }
+                Some(format!("({})", tests.join(" || ")))
+            }
+            Pattern::Struct { fields, .. } => {
+                let tests: Vec<String> = fields.iter()
+                    .filter_map(|field| Self::match_arm_condition(&format!("{}.{}", scrutinee, field.field), &field.pattern))
+                    .collect();
+                (!tests.is_empty()).then(|| tests.join(" && "))
+            }
+            Pattern::Wildcard(_) | Pattern::Variable(..) => None,
+        }
+    }
+
+    // `auto x = heidic_match.field;` for each variable an arm's pattern binds
+    fn match_arm_bindings(pattern: &Pattern, pad: &str) -> String {
+        pattern.bindings().into_iter()
+            .map(|(path, name, _)| format!("{}[[maybe_unused]] auto {} = {};\n", pad, name, field_path("heidic_match", &path)))
+            .collect()
+    }
+
+    // `let Position { x, y, .. } = p;` reads each bound field into its own local. A value other
+    // than a variable or field is evaluated once, into a temporary.
+    fn generate_destructure(&mut self, pattern: &Pattern, value: &Expression, indent: usize, entity: Option<(&str, &str)>) -> String {
+        let pad = format!("{}    ", self.indent(indent));
+        let value_expr = self.generate_expression_in(value, entity);
+        let bindings = pattern.bindings();
+        if bindings.is_empty() {
+            return format!("{}{};\n", pad, value_expr);
+        }
+        let mut output = String::new();
+        let source = if matches!(value, Expression::Variable(..) | Expression::MemberAccess { .. }) {
+            value_expr
+        } else {
+            let names: Vec<&str> = bindings.iter().map(|(_, name, _)| *name).collect();
+            let temporary = format!("heidic_destructure_{}", names.join("_"));
+            output.push_str(&format!("{}auto {} = {};\n", pad, temporary, value_expr));
+            temporary
+        };
+        for (path, name, _) in bindings {
+            output.push_str(&format!("{}auto {} = {};\n", pad, name, field_path(&source, &path)));
+        }
+        output
+    }
+
+    // The if-chain test for one arm: its pattern and its guard (None: the arm always matches).
+    // A guard can read the arm's binding, so then the binding moves into the test,
+    // `if (auto n = heidic_match; n > 10)`, and the bool says it's bound there.
+    fn match_arm_test(&mut self, arm: &MatchArm, entity: Option<(&str, &str)>) -> (Option<String>, bool) {
+        let pattern = Self::match_arm_condition("heidic_match", &arm.pattern);
+        let Some(guard) = &arm.guard else { return (pattern, false) };
+        let guard = self.generate_expression_in(guard, entity);
+        let bindings = arm.pattern.bindings();
+        let guard = match bindings.as_slice() {
+            [] => guard,
+            [(path, name, _)] => {
+                let pattern = pattern.map_or(String::new(), |pattern| format!("{} && ", pattern));
+                return (Some(format!("[[maybe_unused]] auto {} = {}; {}{}", name, field_path("heidic_match", path), pattern, guard)), true);
+            }
+            // Several bindings can't share one if-init, so the guard reads them in a lambda and
+            // the arm body binds them again
+            _ => format!("[&]() {{ {}return {}; }}()", Self::match_arm_bindings(&arm.pattern, "").replace('\n', " "), guard),
+        };
+        let condition = match pattern {
+            Some(pattern) => format!("{} && {}", pattern, guard),
+            None => guard,
+        };
+        (Some(condition), false)
+    }
+
+    // Matches on integer literals and or-patterns of them (optionally ending in a catch-all)
+    // become a switch, which the C++ compiler can turn into a jump table. Returns the arms that
+    // get case labels, with their labels: repeated literals and arms after the catch-all can't be
+    // reached, and duplicate labels don't compile. Guards and ranges need the if-else chain.
+    fn switch_arms(arms: &[MatchArm]) -> Option<Vec<(String, &MatchArm)>> {
+        if arms.iter().any(|arm| arm.guard.is_some()) {
+            return None;
+        }
+        let mut cases = Vec::new();
+        let mut values = std::collections::HashSet::new();
+        for arm in arms {
+            let literals = match &arm.pattern {
+                Pattern::Literal(literal, _) => vec![literal],
+                Pattern::Or(alternatives, _) => alternatives.iter()
+                    .map(|alternative| match alternative {
+                        Pattern::Literal(literal, _) => Some(literal),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?,
+                Pattern::Wildcard(_) | Pattern::Variable(..) => {
+                    cases.push(("default:".to_string(), arm));
+                    break;
+                }
+                _ => return None,
+            };
+            let mut labels = Vec::new();
+            for literal in literals {
+                let Literal::Int(value, _) = literal else { return None };
+                if values.insert(*value) {
+                    labels.push(format!("case {}:", literal_to_cpp(literal)));
+                }
+            }
+            if !labels.is_empty() {
+                cases.push((labels.join(" "), arm));
+            }
+        }
+        if values.is_empty() { None } else { Some(cases) }
+    }
+
+    // match as a statement: an if-else chain (or switch) over the scrutinee, evaluated once
+    fn generate_match_statement(&mut self, scrutinee: &Expression, arms: &[MatchArm], indent: usize, entity: Option<(&str, &str)>) -> String {
+        let pad = format!("{}    ", self.indent(indent));
+        let mut output = format!("{}{{\n", pad);
+        output.push_str(&format!("{}    auto heidic_match = {};\n", pad, self.generate_expression_in(scrutinee, entity)));
+        // A break in an arm leaves the enclosing loop, which it can't do from inside a switch
+        let breaks_out = arms.iter().any(|arm| breaks_enclosing_loop(&arm.body));
+        if let Some(cases) = Self::switch_arms(arms).filter(|_| !breaks_out) {
+            output.push_str(&format!("{}    switch (heidic_match) {{\n", pad));
+            for (label, arm) in cases {
+                output.push_str(&format!("{}        {} {{\n", pad, label));
+                if let Pattern::Variable(name, _) = &arm.pattern {
+                    output.push_str(&format!("{}            [[maybe_unused]] auto {} = heidic_match;\n", pad, name));
+                }
+                for stmt in &arm.body {
+                    output.push_str(&self.generate_statement_in(stmt, indent + 3, entity));
+                }
+                if let Some(value) = &arm.value {
+                    output.push_str(&format!("{}            {};\n", pad, self.generate_expression_in(value, entity)));
+                }
+                output.push_str(&format!("{}            break;\n", pad));
+                output.push_str(&format!("{}        }}\n", pad));
+            }
+            output.push_str(&format!("{}    }}\n", pad));
+            output.push_str(&format!("{}}}\n", pad));
+            return output;
+        }
+        output.push_str(&pad);
+        for (i, arm) in arms.iter().enumerate() {
+            let (condition, bound) = self.match_arm_test(arm, entity);
+            match (&condition, i) {
+                (Some(condition), 0) => output.push_str(&format!("    if ({}) {{\n", condition)),
+                (Some(condition), _) => output.push_str(&format!(" else if ({}) {{\n", condition)),
+                (None, 0) => output.push_str("    {\n"),
+                (None, _) => output.push_str(" else {\n"),
+            }
+            if !bound {
+                output.push_str(&Self::match_arm_bindings(&arm.pattern, &format!("{}        ", pad)));
+            }
+            for stmt in &arm.body {
+                output.push_str(&self.generate_statement_in(stmt, indent + 2, entity));
+            }
+            if let Some(value) = &arm.value {
+                output.push_str(&format!("{}        {};\n", pad, self.generate_expression_in(value, entity)));
+            }
+            output.push_str(&format!("{}    }}", pad));
+            if condition.is_none() {
+                break;  // Later arms can't be reached
+            }
+        }
+        output.push('\n');
+        output.push_str(&format!("{}}}\n", pad));
+        output
+    }
+
+    // Opens `for entity in q`. Queries list their entities in `q.entities`, parallel to the
+    // component arrays; filters skip the entities they reject, and loops that may write a
+    // changed<T>-tracked component name the entity so the write can be recorded. With entity
+    // hierarchies every loop names its entity for set_parent(), and q.children(parent) is q
+    // filtered to the entities whose parent is `parent`.
+    fn generate_query_loop_header(&self, iterator: &str, collection: &Expression, collection_expr: &str, indent: usize) -> String {
+        let pad = self.indent(indent);
+        let (query, parent) = match hierarchy::children_query(collection) {
+            Some((query, parent)) => (query, Some(parent)),
+            None => (collection, None),
+        };
+        let component_types = match query {
+            Expression::Variable(name, _) => self.query_params.get(name).cloned().unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        // With changed<T> filters the loop is skipped outright when some T's version shows no
+        // entity's T changed since the previous call
+        let any_changed: Vec<String> = component_types.iter().filter_map(|ty| match ty {
+            Type::QueryFilter(QueryFilter::Changed, component) => {
+                Some(format!("g_storage.component_version<{}>() > heidic_{}_since", self.type_to_cpp(component), collection_expr))
+            }
+            _ => None,
+        }).collect();
+        // In a CUDA kernel each thread starts at its own entity and strides over the whole grid
+        if self.cuda_query.as_deref() == Some(collection_expr) {
+            return format!("{}    for (size_t {it}_index = blockIdx.x * blockDim.x + threadIdx.x; {it}_index < {q}.size(); {it}_index += blockDim.x * gridDim.x) {{\n",
+                pad, it = iterator, q = collection_expr);
+        }
+        let mut output = if any_changed.is_empty() {
+            format!("{}    for (size_t {it}_index = 0; {it}_index < {q}.size(); ++{it}_index) {{\n",
+                pad, it = iterator, q = collection_expr)
+        } else {
+            format!("{}    for (size_t {it}_index = 0, {it}_end = ({changed}) ? {q}.size() : 0; {it}_index < {it}_end; ++{it}_index) {{\n",
+                pad, it = iterator, q = collection_expr, changed = any_changed.join(" && "))
+        };
+        let parent_filter = match parent {
+            Some(Expression::Variable(parent, _)) => Some(format!("heidic_parent_of(g_storage, {}_entity) != {}_entity", iterator, parent)),
+            _ => None,
+        };
+        let filters: Vec<String> = parent_filter.into_iter().chain(component_types.iter().filter_map(|ty| match ty {
+            Type::QueryFilter(QueryFilter::With, component) => {
+                Some(format!("!g_storage.has_component<{}>({}_entity)", self.type_to_cpp(component), iterator))
+            }
+            Type::QueryFilter(QueryFilter::Without, component) => {
+                Some(format!("g_storage.has_component<{}>({}_entity)", self.type_to_cpp(component), iterator))
+            }
+            Type::QueryFilter(QueryFilter::Changed, component) => {
+                Some(format!("!g_storage.changed_since<{}>({}_entity, heidic_{}_since)", self.type_to_cpp(component), iterator, collection_expr))
+            }
+            _ => None,
+        })).collect();
+        let tracked = component_types.iter().any(|ty| matches!(ty, Type::Struct(n) | Type::Component(n) if self.changed_components.contains(n)));
+        if self.uses_hierarchy || !self.spatial_sounds.is_empty() {
+            output.push_str(&format!("{}        [[maybe_unused]] const EntityId {it}_entity = {}.entities[{it}_index];\n", pad, collection_expr, it = iterator));
+        } else if !filters.is_empty() || tracked {
+            output.push_str(&format!("{}        const EntityId {it}_entity = {}.entities[{it}_index];\n", pad, collection_expr, it = iterator));
+        }
+        if !filters.is_empty() {
+            output.push_str(&format!("{}        if ({}) {{\n{}            continue;\n{}        }}\n", pad, filters.join(" || "), pad, pad));
+        }
+        output
+    }
+    
+    // child.set_parent(parent) / child.clear_parent(); the type checker has made sure both are
+    // entities of enclosing query loops
+    fn hierarchy_call(&self, name: &str, args: &[Expression]) -> Option<String> {
+        if self.function_params.contains_key(name) {
+            return None;
+        }
+        let entities: Vec<String> = args.iter().map(|arg| match arg {
+            Expression::Variable(entity, _) => format!("{}_entity", entity),
+            _ => "INVALID_ENTITY".to_string(),
+        }).collect();
+        match (name, entities.as_slice()) {
+            ("set_parent", [child, parent]) => Some(format!("heidic_set_parent(g_storage, {}, {})", child, parent)),
+            ("clear_parent", [child]) => Some(format!("heidic_clear_parent(g_storage, {})", child)),
+            _ => None,
+        }
+    }
+    
+    // play_resource_<name>_at(entity): the type checker has made sure the entity is one of an
+    // enclosing query loop
+    fn spatial_play_call(&self, name: &str, args: &[Expression]) -> Option<String> {
+        if self.function_params.contains_key(name) {
+            return None;
+        }
+        let is_play_at = self.spatial_sounds.iter()
+            .any(|res| spatial_audio::play_at_functions(res).iter().any(|(function, _)| function == name));
+        match args {
+            [Expression::Variable(entity, _)] if is_play_at => Some(format!("{}({}_entity)", name, entity)),
+            _ => None,
+        }
+    }
+    
+    // input.attach(window), input.update() and the actions' accessors
+    // open_window()/close_window(window) of a window block, unless the program defines its own
+    fn window_function(&self, name: &str) -> Option<&'static str> {
+        if self.window_config.is_none() || self.function_params.contains_key(name) {
+            return None;
+        }
+        WindowConfig::FUNCTIONS.iter().find(|(function, _)| *function == name).map(|(_, callee)| *callee)
+    }
+    
+    fn input_call(&self, name: &str, args: &[String]) -> Option<String> {
+        let method = input_map::method(name)?;
+        let input = self.input.as_ref()?;
+        match method {
+            "attach" => return Some(format!("{}.attach({})", input_map::DEVICES_GLOBAL, args.join(", "))),
+            "update" => return Some(format!("{}()", input_map::UPDATE_FUNCTION)),
+            _ => {}
+        }
+        input.actions.iter().find_map(|action| {
+            let global = input_map::action_global(action);
+            if action.is_axis() {
+                return (method == action.name).then_some(global);
+            }
+            match method.strip_prefix(action.name.as_str()) {
+                Some("") => Some(format!("{}.down", global)),
+                Some("_pressed") => Some(format!("{}.pressed()", global)),
+                Some("_released") => Some(format!("{}.released()", global)),
+                _ => None,
+            }
+        })
+    }
+    
+    // One global per action and the update that reads the devices into them: a button is held when
+    // any of its bindings is, an axis follows whichever binding is pushed furthest
+    fn generate_input(&self, input: &InputDef) -> String {
+        let mut output = format!("static HeidicInputDevices {};\n", input_map::DEVICES_GLOBAL);
+        for action in &input.actions {
+            if action.is_axis() {
+                output.push_str(&format!("static float {} = 0.0f;\n", input_map::action_global(action)));
+            } else {
+                output.push_str(&format!("static HeidicInputButton {};\n", input_map::action_global(action)));
+            }
+        }
+        output.push('\n');
+        output.push_str(&format!("static void {}() {{\n", input_map::UPDATE_FUNCTION));
+        output.push_str(&format!("    {}.update();\n", input_map::DEVICES_GLOBAL));
+        for action in &input.actions {
+            let bindings: Vec<String> = action.bindings.iter().map(|binding| binding.to_string()).collect();
+            let global = input_map::action_global(action);
+            let value = if action.is_axis() {
+                let values: Vec<String> = action.bindings.iter().map(|binding| match binding {
+                    InputBinding::Source(source) => input_map::source_expression(source),
+                    InputBinding::Axis(negative, positive) => format!("heidic_input_axis({}, {})",
+                        input_map::source_expression(negative), input_map::source_expression(positive)),
+                }).collect();
+                match values.as_slice() {
+                    [value] => format!("{} = {}", global, value),
+                    _ => format!("{} = heidic_input_strongest({{{}}})", global, values.join(", ")),
+                }
+            } else {
+                let held: Vec<String> = action.bindings.iter().map(|binding| match binding {
+                    InputBinding::Source(source) if source.is_gamepad_axis() => format!("{} > 0.5f", input_map::source_expression(source)),
+                    InputBinding::Source(source) => input_map::source_expression(source),
+                    InputBinding::Axis(..) => "false".to_string(),  // Makes it an axis action
+                }).collect();
+                format!("{}.set({})", global, held.join(" || "))
+            };
+            output.push_str(&format!("    {};  // {}: {}\n", value, action.name, bindings.join(" | ")));
+        }
+        output.push_str("}\n\n");
+        output
+    }
+    
+    // Spatial sounds: every run_systems() sets each playing one's attenuation and pan from its
+    // entity's Position relative to the first entity with an AudioListener
+    fn generate_spatial_audio_update(&self) -> String {
+        let mut output = self.section("Spatial audio");
+        output.push_str("static void heidic_update_spatial_audio() {\n");
+        output.push_str("    const Position* listener = nullptr;\n");
+        output.push_str("    AudioListener settings{};\n");
+        output.push_str("    g_storage.for_each<AudioListener>([&](EntityId entity, AudioListener& candidate) {\n");
+        output.push_str("        if (!listener) {\n");
+        output.push_str("            listener = g_storage.get_component<Position>(entity);\n");
+        output.push_str("            settings = candidate;\n");
+        output.push_str("        }\n");
+        output.push_str("    });\n");
+        output.push_str("    if (!listener) return;  // Without a listener, spatial sounds play unattenuated\n");
+        for res in &self.spatial_sounds {
+            let global_name = format!("g_resource_{}", res.name.to_lowercase());
+            let emitter = spatial_audio::emitter_global(res);
+            output.push_str(&format!("    if ({}.isLoaded() && {} != INVALID_ENTITY) {{\n", global_name, emitter));
+            output.push_str(&format!("        if (const Position* position = g_storage.get_component<Position>({})) {{\n", emitter));
+            output.push_str("            const HeidicSpatialMix mix = heidic_spatial_mix(static_cast<float>(position->x - listener->x),\n");
+            output.push_str("                static_cast<float>(position->y - listener->y), static_cast<float>(position->z - listener->z), settings.yaw, settings.range);\n");
+            output.push_str(&format!("            {}.get()->setSpatial(mix.gain, mix.pan);\n", global_name));
+            output.push_str("        }\n");
+            output.push_str("    }\n");
+        }
+        output.push_str("}\n\n");
+        output
+    }
+    
+    // changed<T> compares against the change tick from the previous call of this function
+    fn generate_change_detection(&self, indent: usize) -> String {
+        let mut queries: Vec<(&String, &Vec<Type>)> = self.query_params.iter()
+            .filter(|(_, component_types)| component_types.iter().any(|ty| matches!(ty, Type::QueryFilter(QueryFilter::Changed, _))))
+            .collect();
+        queries.sort_by_key(|(name, _)| *name);
+        let pad = format!("{}    ", self.indent(indent));
+        let mut output = String::new();
+        for (name, _) in queries {
+            output.push_str(&format!("{}// {}: changed<T> matches components added or assigned since the previous call\n", pad, name));
+            output.push_str(&format!("{}static uint32_t heidic_{}_last_tick = 0;\n", pad, name));
+            output.push_str(&format!("{}const uint32_t heidic_{}_since = heidic_{}_last_tick;\n", pad, name, name));
+            output.push_str(&format!("{}heidic_{}_last_tick = g_storage.change_tick();\n", pad, name));
+        }
+        output
+    }
+    
+    // spawn_batch: one contiguous block of entity ids and storages sized for the whole batch up
+    // front, then a plain loop that initializes each entity. Component literals at the top of
+    // init are added straight into their storage, without a storage lookup per entity.
+    fn generate_spawn_batch(&mut self, count: &Expression, index: &str, body: &[Statement], indent: usize, entity: Option<(&str, &str)>) -> String {
+        let pad = format!("{}    ", self.indent(indent));
+        let attached: Vec<String> = body.iter().filter_map(|stmt| match stmt {
+            Statement::Expression(Expression::StructLiteral { name, .. }, _) if self.components.contains_key(name) => Some(name.clone()),
+            _ => None,
+        }).collect();
+
+        let mut output = format!("{}{{\n", pad);
+        output.push_str(&format!("{}    const int64_t heidic_batch_requested = {};\n", pad, self.generate_expression_in(count, entity)));
+        output.push_str(&format!("{}    const uint32_t heidic_batch_count = heidic_batch_requested > 0 ? static_cast<uint32_t>(heidic_batch_requested) : 0;\n", pad));
+        output.push_str(&format!("{}    const EntityId heidic_batch_first = g_storage.create_entities(heidic_batch_count);\n", pad));
+        // The whole batch counts as added at one tick for changed<T> filters
+        let tracked = attached.iter().any(|component| self.changed_components.contains(component));
+        if tracked {
+            output.push_str(&format!("{}    const uint32_t heidic_batch_tick = g_storage.advance_tick();\n", pad));
+        }
+        for component in &attached {
+            // A reference into the sparse-set storage, or the archetype storage's inserter
+            output.push_str(&format!("{}    auto&& heidic_storage_{} = g_storage.storage<{}>();\n", pad, component, component));
+            output.push_str(&format!("{}    heidic_storage_{}.reserve(heidic_batch_count, heidic_batch_first + heidic_batch_count - 1);\n", pad, component));
+        }
+        output.push_str(&format!("{}    for (int32_t {} = 0; {} < static_cast<int32_t>(heidic_batch_count); ++{}) {{\n", pad, index, index, index));
+        output.push_str(&format!("{}        const EntityId heidic_entity = heidic_batch_first + static_cast<EntityId>({});\n", pad, index));
+        let reload_checks = std::mem::replace(&mut self.reload_checks, false);
+        for stmt in body {
+            match stmt {
+                Statement::Expression(literal @ Expression::StructLiteral { name, fields, .. }, _) if attached.contains(name) => {
+                    let value = match self.components.get(name).filter(|component| component.is_soa).cloned() {
+                        // A component_soa is added from one entity's values: its Row, in declaration order
+                        Some(component) => {
+                            let values: Vec<String> = component.fields.iter()
+                                .filter_map(|field| fields.iter().find(|(name, _)| *name == field.name))
+                                .map(|(name, value)| format!(".{} = {}", name, self.generate_expression_in(value, entity)))
+                                .collect();
+                            format!("{}::Row {{{}}}", name, values.join(", "))
+                        }
+                        None => self.generate_expression_in(literal, entity),
+                    };
+                    let tick = if tracked { ", heidic_batch_tick" } else { "" };
+                    output.push_str(&format!("{}        heidic_storage_{}.add(heidic_entity, {}{});\n", pad, name, value, tick));
+                }
+                _ => output.push_str(&self.generate_statement_in(stmt, indent + 2, entity)),
+            }
+        }
+        self.reload_checks = reload_checks;
+        output.push_str(&format!("{}    }}\n", pad));
+        output.push_str(&format!("{}}}\n", pad));
+        output
+    }
+
+    // match as a value: a lambda invoked in place that returns the matching arm's value. The type
+    // checker guarantees some arm matches, so the last arm needs no test.
+    fn generate_match_value(&mut self, scrutinee: &Expression, arms: &[MatchArm], entity: Option<(&str, &str)>) -> String {
+        let mut output = String::from("[&]() {\n");
+        output.push_str(&format!("    auto heidic_match = {};\n", self.generate_expression_in(scrutinee, entity)));
+        if let Some(cases) = Self::switch_arms(arms) {
+            output.push_str("    switch (heidic_match) {\n");
+            for (label, arm) in cases {
+                output.push_str(&format!("        {} {{\n", label));
+                if let Pattern::Variable(name, _) = &arm.pattern {
+                    output.push_str(&format!("            [[maybe_unused]] auto {} = heidic_match;\n", name));
+                }
+                for stmt in &arm.body {
+                    output.push_str(&self.generate_statement_in(stmt, 2, entity));
+                }
+                if let Some(value) = &arm.value {
+                    output.push_str(&format!("            return {};\n", self.lambda_return_value(value, entity)));
+                }
+                output.push_str("        }\n");
+            }
+            output.push_str("    }\n");
+            output.push_str("}()");
+            return output;
+        }
+        let reachable = arms.iter().position(|arm| arm.guard.is_none() && Self::match_arm_condition("", &arm.pattern).is_none())
+            .map_or(arms.len(), |catch_all| catch_all + 1);
+        for (i, arm) in arms[..reachable].iter().enumerate() {
+            let (condition, bound) = match self.match_arm_test(arm, entity) {
+                (condition, bound) if i + 1 < reachable => (condition, bound),
+                _ => (None, false),
+            };
+            output.push_str(&match &condition {
+                Some(condition) => format!("    if ({}) {{\n", condition),
+                None => "    {\n".to_string(),
+            });
+            if !bound {
+                output.push_str(&Self::match_arm_bindings(&arm.pattern, "        "));
+            }
+            for stmt in &arm.body {
+                output.push_str(&self.generate_statement_in(stmt, 1, entity));
+            }
+            if let Some(value) = &arm.value {
+                output.push_str(&format!("        return {};\n", self.lambda_return_value(value, entity)));
+            }
+            output.push_str("    }\n");
+        }
+        output.push_str("}()");
+        output
+    }
+
     fn generate_statement(&mut self, stmt: &Statement, indent: usize) -> String {
         match stmt {
-            Statement::Let { name, ty, value, .. } => {
-                let type_str = if let Some(ty) = ty {
-                    self.type_to_cpp(ty)
+            Statement::Let { name, mutable, ty, value, .. } => {
+                // A frame allocation is a HeidicSlice, not the std::vector an array type names
+                let type_str = match ty {
+                    Some(ty) if !matches!(value, Expression::FrameAlloc { .. }) => self.type_to_cpp(ty),
+                    _ => "auto".to_string(),
+                };
+                let type_str = if self.is_const_local(*mutable) {
+                    format!("const {}", type_str)
                 } else {
-                    "auto".to_string()
+                    type_str
                 };
                 // Check if we need to wrap value in optional (implicit wrapping)
                 let value_expr = self.generate_expression(value);
@@ -2267,12 +5865,26 @@ impl CodeGenerator {
                 
                 output
             }
+            Statement::Destructure { pattern, value, .. } => self.generate_destructure(pattern, value, indent, None),
             Statement::Assign { target, value, .. } => {
                 format!("{}    {} = {};\n",
                     self.indent(indent),
                     self.generate_expression(target),
                     self.generate_expression(value))
             }
+            Statement::If { condition, then_block, else_block, .. } if self.constant_condition(condition).is_some() => {
+                let taken = if self.constant_condition(condition) == Some(true) { Some(then_block) } else { else_block.as_ref() };
+                let mut output = String::new();
+                if let Some(block) = taken {
+                    output.push_str(&format!("{}    {{\n", self.indent(indent)));
+                    for stmt in block {
+                        output.push_str(&self.generate_statement(stmt, indent + 1));
+                    }
+                    output.push_str(&format!("{}    }}\n", self.indent(indent)));
+                }
+                output
+            }
+            Statement::While { condition, .. } if self.constant_condition(condition) == Some(false) => String::new(),
             Statement::If { condition, then_block, else_block, .. } => {
                 let mut output = format!("{}    if ({}) {{\n", 
                     self.indent(indent),
@@ -2293,56 +5905,64 @@ impl CodeGenerator {
                 let mut output = format!("{}    while ({}) {{\n", 
                     self.indent(indent),
                     self.generate_expression(condition));
-                // Add hot-reload check at the start of while loop if we have hot systems or hot shaders
-                if !self.hot_systems.is_empty() {
-                    // Add check at the start of each while loop iteration
-                    output.push_str(&format!("{}        check_and_reload_hot_system();\n", self.indent(indent + 1)));
+                // Only the outermost loops check for reloads; inner loops run to completion in between
+                let reload_checks = std::mem::replace(&mut self.reload_checks, false);
+                if reload_checks && self.has_hot_reload() {
+                    output.push_str(&format!("{}    heidic_check_reloads();\n", self.indent(indent + 1)));
                 }
-                if !self.hot_shaders.is_empty() {
-                    // Add shader hot-reload check at the start of each while loop iteration
-                    output.push_str(&format!("{}        check_and_reload_hot_shaders();\n", self.indent(indent + 1)));
-                }
-                if !self.hot_components.is_empty() {
-                    // Add component hot-reload check at the start of each while loop iteration
-                    output.push_str(&format!("{}        check_and_migrate_hot_components();\n", self.indent(indent + 1)));
-                }
-                if self.has_resources {
-                    // Add resource hot-reload check at the start of each while loop iteration
-                    output.push_str(&format!("{}        check_and_reload_resources();\n", self.indent(indent + 1)));
+                if reload_checks && self.uses_frame_arena {
+                    output.push_str(&self.frame_scope(indent + 1));
                 }
                 for stmt in body {
                     output.push_str(&self.generate_statement(stmt, indent + 1));
                 }
+                self.reload_checks = reload_checks;
                 output.push_str(&format!("{}    }}\n", self.indent(indent)));
                 output
             }
-            Statement::For { iterator, collection, body, .. } => {
-                // Generate query iteration: for entity in q { ... }
+            Statement::For { iterator, collection, chunk_size: Some(size), body, .. } => {
                 let collection_expr = self.generate_expression(collection);
+                let size_expr = self.generate_expression(size);
+                self.generate_chunk_loop(iterator, &collection_expr, &size_expr, body, indent)
+            }
+            Statement::For { iterator, collection, body, .. } => {
+                // Generate query iteration: for entity in q { ... } (or q.children(parent), which iterates q)
+                let query = hierarchy::children_query(collection).map_or(collection, |(query, _)| query);
+                let collection_expr = self.generate_expression(query);
                 
                 // Generate iteration loop with index variable
                 let mut output = format!("{}    // Query iteration: for {} in {}\n", 
                     self.indent(indent), iterator, collection_expr);
-                output.push_str(&format!("{}    for (size_t {}_index = 0; {}_index < {}.size(); ++{}_index) {{\n",
-                    self.indent(indent), iterator, iterator, collection_expr, iterator));
+                output.push_str(&self.generate_query_loop_header(iterator, collection, &collection_expr, indent));
                 
                 // Generate body - entity access will be handled in expression generation
                 // We need to track that we're in a query loop for entity access
+                let reload_checks = std::mem::replace(&mut self.reload_checks, false);
                 for stmt in body {
                     // Replace entity.Component.field with query.component_arrays[entity_index].field
                     output.push_str(&self.generate_statement_with_entity(stmt, indent + 1, iterator, &collection_expr));
                 }
+                self.reload_checks = reload_checks;
                 output.push_str(&format!("{}    }}\n", self.indent(indent)));
                 output
             }
             Statement::Loop { body, .. } => {
                 let mut output = format!("{}    while (true) {{\n", self.indent(indent));
+                let reload_checks = std::mem::replace(&mut self.reload_checks, false);
+                if reload_checks && self.has_hot_reload() {
+                    output.push_str(&format!("{}    heidic_check_reloads();\n", self.indent(indent + 1)));
+                }
+                if reload_checks && self.uses_frame_arena {
+                    output.push_str(&self.frame_scope(indent + 1));
+                }
                 for stmt in body {
                     output.push_str(&self.generate_statement(stmt, indent + 1));
                 }
+                self.reload_checks = reload_checks;
                 output.push_str(&format!("{}    }}\n", self.indent(indent)));
                 output
             }
+            Statement::SpawnBatch { count, index, body, .. } => self.generate_spawn_batch(count, index, body, indent, None),
             Statement::Return(expr, ..) => {
                 if let Some(expr) = expr {
                     format!("{}    return {};\n",
@@ -2352,6 +5972,9 @@ impl CodeGenerator {
                     format!("{}    return 0;\n", self.indent(indent))
                 }
             }
+            Statement::Expression(Expression::Match { expr, arms, .. }, _) => {
+                self.generate_match_statement(expr, arms, indent, None)
+            }
             Statement::Expression(expr, ..) => {
                 let expr_str = self.generate_expression(expr);
                 // If this is a call to heidic_render_balls and we have hot components, wrap it with ECS code
@@ -2443,6 +6066,10 @@ impl CodeGenerator {
                     format!("{}    {};\n", self.indent(indent), expr_str)
                 }
             }
+            Statement::Emit(event, ..) => {
+                let Expression::StructLiteral { name, .. } = event else { return String::new() };
+                format!("{}    {}.emit({});\n", self.indent(indent), event_queue(name), self.generate_expression(event))
+            }
             Statement::Defer(expr, ..) => {
                 // Generate RAII-based defer: auto defer_N = make_defer([&]() { expr; });
                 let defer_id = self.defer_counter;
@@ -2461,61 +6088,117 @@ impl CodeGenerator {
                 output.push_str(&format!("{}    }}\n", self.indent(indent)));
                 output
             }
-            Statement::Break(_) => {
+            Statement::Break(Some(value), _) => {
+                format!("{}    return {};\n", self.indent(indent), self.lambda_return_value(value, None))
+            }
+            Statement::Break(None, _) => {
                 format!("{}    break;\n", self.indent(indent))
             }
             Statement::Continue(_) => {
                 format!("{}    continue;\n", self.indent(indent))
             }
-        }
-    }
-    
-    fn generate_expression(&mut self, expr: &Expression) -> String {
-        match expr {
-            Expression::Literal(lit, _) => {
-                match lit {
-                    Literal::Int(n) => n.to_string(),
-                    Literal::Float(n) => n.to_string(),
-                    Literal::Bool(b) => b.to_string(),
-                    Literal::String(s) => format!("\"{}\"", s),
-                }
-            }
-            Expression::Variable(name, _) => name.clone(),
-            Expression::BinaryOp { op, left, right, .. } => {
-                let op_str = match op {
-                    BinaryOp::Add => "+",
-                    BinaryOp::Sub => "-",
-                    BinaryOp::Mul => "*",
-                    BinaryOp::Div => "/",
-                    BinaryOp::Mod => "%",
-                    BinaryOp::Eq => "==",
-                    BinaryOp::Ne => "!=",
-                    BinaryOp::Lt => "<",
-                    BinaryOp::Le => "<=",
-                    BinaryOp::Gt => ">",
-                    BinaryOp::Ge => ">=",
-                    BinaryOp::And => "&&",
-                    BinaryOp::Or => "||",
-                };
-                format!("({} {} {})", 
-                    self.generate_expression(left),
-                    op_str,
-                    self.generate_expression(right))
-            }
-            Expression::UnaryOp { op, expr, .. } => {
-                let op_str = match op {
-                    UnaryOp::Neg => "-",
-                    UnaryOp::Not => "!",
-                };
-                format!("{}({})", op_str, self.generate_expression(expr))
+        }
+    }
+    
+    // Fill in the arguments the caller omitted: default values for trailing parameters, and
+    // the single instance for each res<T> parameter
+    fn call_args_with_defaults(&self, name: &str, args: &[Expression]) -> Vec<Expression> {
+        let Some(params) = self.function_params.get(name) else {
+            return args.to_vec();
+        };
+        let mut given = args.iter();
+        let mut full_args = Vec::new();
+        for param in params {
+            if let Some(resource) = resources::resource_name(&param.ty) {
+                full_args.push(Expression::Variable(resources::instance_name(resource), crate::error::SourceLocation::unknown()));
+                continue;
+            }
+            match (given.next(), &param.default) {
+                (Some(arg), _) => full_args.push(arg.clone()),
+                (None, Some(default)) => full_args.push(default.clone()),
+                (None, None) => break,
+            }
+        }
+        full_args
+    }
+    
+    fn generate_expression(&mut self, expr: &Expression) -> String {
+        match expr {
+            Expression::Literal(lit, _) => {
+                literal_to_cpp(lit)
+            }
+            Expression::Variable(name, _) => {
+                // Hot-reloadable functions used as values forward through their function pointer,
+                // so a stored callback keeps working after the DLL is reloaded
+                if self.calls_through_hot_pointer(name) {
+                    format!("[](auto&&... args) {{ return g_{}(std::forward<decltype(args)>(args)...); }}", name)
+                } else {
+                    name.clone()
+                }
+            }
+            Expression::BinaryOp { op, left, right, location } => {
+                let (left, right) = (self.generate_expression(left), self.generate_expression(right));
+                self.binary_expression(op, left, right, *location)
+            }
+            Expression::UnaryOp { op, expr, location } => {
+                let operand = self.generate_expression(expr);
+                match (op, &self.checked_arithmetic) {
+                    (UnaryOp::Neg, Some(source)) => format!("heidic_checked_neg({}, \"{}:{}:{}\")", operand, source, location.line, location.column),
+                    (UnaryOp::Neg, None) => format!("-({})", operand),
+                    (UnaryOp::Not, _) => format!("!({})", operand),
+                }
+            }
+            Expression::Cast { expr, ty, .. } => {
+                format!("static_cast<{}>({})", self.type_to_cpp(ty), self.generate_expression(expr))
+            }
+            Expression::LayoutOf { query, ty, field, .. } => self.generate_layout_of(*query, ty, field.as_deref()),
+            Expression::GpuSync { direction, component, .. } => format!("{}()", cuda::sync_function(*direction, &self.type_to_cpp(component))),
+            Expression::FrameAlloc { arena, element, count, .. } => {
+                format!("{}.{}<{}>({})", self.generate_expression(arena), frame_arena::METHOD, self.type_to_cpp(element), self.generate_expression(count))
             }
-            Expression::Call { name, args, .. } => {
-                // Check if this is a hot-reloadable function
-                let is_hot = self.hot_systems.iter().any(|s| {
-                    s.functions.iter().any(|f| f.name == *name)
-                });
+            Expression::If { condition, then_branch, else_branch, .. } => {
+                self.generate_if_expression(condition, then_branch, else_branch, None)
+            }
+            Expression::Block { body, .. } => self.generate_block_expression(body, None),
+            Expression::Loop { body, .. } => self.generate_loop_expression(body, None),
+            Expression::Call { name, args, location } => {
+                if let Some((prefix, rest)) = self.gpu_intrinsic_call(name, args) {
+                    return match rest {
+                        Some(arg) => format!("{}, {})", prefix, self.generate_expression(arg)),
+                        None => format!("{})", prefix),
+                    };
+                }
+                if matches!(name.as_str(), "expect" | "expect_frame" | "screenshot") {
+                    let generated: Vec<String> = args.iter().map(|arg| self.generate_expression(arg)).collect();
+                    if let Some(call) = self.test_intrinsic_call(name, args, &generated, location.line) {
+                        return call;
+                    }
+                }
+                if name == "reserve_entities" && args.len() == 1 && !self.function_params.contains_key(name) {
+                    return format!("g_storage.reserve_entities(static_cast<uint32_t>({}))", self.generate_expression(&args[0]));
+                }
+                if name == "run_systems" && args.is_empty() && !self.function_params.contains_key(name) {
+                    return "heidic_run_systems()".to_string();
+                }
+                if let Some(call) = self.hierarchy_call(name, args).or_else(|| self.spatial_play_call(name, args)) {
+                    return call;
+                }
+                if input_map::method(name).is_some() {
+                    let args: Vec<String> = args.iter().map(|arg| self.generate_expression(arg)).collect();
+                    if let Some(call) = self.input_call(name, &args) {
+                        return call;
+                    }
+                }
+                if let Some(callee) = self.window_function(name) {
+                    let args: Vec<String> = args.iter().map(|arg| self.generate_expression(arg)).collect();
+                    return format!("{}({})", callee, args.join(", "));
+                }
+                
+                // Omitted trailing arguments take the callee's default values
+                let args = self.call_args_with_defaults(name, args);
                 
-                if is_hot {
+                // Check if this is a hot-reloadable function
+                if self.calls_through_hot_pointer(name) {
                     // Use function pointer for hot-reloadable functions
                     let mut output = format!("g_{}(", name);
                     for (i, arg) in args.iter().enumerate() {
@@ -2529,9 +6212,13 @@ impl CodeGenerator {
                 }
                 
                 // Handle built-in print function
+                if name == "print" && self.runtime == RuntimeProfile::Minimal {
+                    let args: Vec<String> = args.iter().map(|arg| self.generate_expression(arg)).collect();
+                    return format!("heidic_print({})", args.join(", "));
+                }
                 if name == "print" {
                     let mut output = String::from("std::cout");
-                    for arg in args {
+                    for arg in &args {
                         output.push_str(" << ");
                         output.push_str(&self.generate_expression(arg));
                     }
@@ -2581,7 +6268,7 @@ impl CodeGenerator {
                         output.push_str(&format!("{}.c_str()", arg_expr));
                     } else {
                         // String literal or other type - fine as-is
-                        output.push_str(&arg_expr);
+                        output.push_str(&self.extern_arg(name, i, arg_expr));
                     }
                 }
                 output.push_str(")");
@@ -2650,58 +6337,7 @@ impl CodeGenerator {
                 
                 output
             }
-            Expression::Match { expr, arms, .. } => {
-                // Generate C++ code for match expression
-                // Convert to: if-else chain
-                let expr_str = self.generate_expression(expr);
-                let mut output = String::new();
-                
-                for (i, arm) in arms.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(" else ");
-                    }
-                    
-                    output.push_str("if (");
-                    
-                    // Generate pattern match condition
-                    match &arm.pattern {
-                        crate::ast::Pattern::Literal(lit, _) => {
-                            let lit_str = match lit {
-                                crate::ast::Literal::Int(n) => n.to_string(),
-                                crate::ast::Literal::Float(n) => n.to_string(),
-                                crate::ast::Literal::Bool(b) => b.to_string(),
-                                crate::ast::Literal::String(s) => format!("\"{}\"", s),
-                            };
-                            output.push_str(&format!("{} == {}", expr_str, lit_str));
-                        }
-                        crate::ast::Pattern::Variable(var_name, _) => {
-                            // Variable binding - always matches, bind variable
-                            // Generate: (var_name = expr, true)
-                            output.push_str(&format!("({} = {}, true)", var_name, expr_str));
-                        }
-                        crate::ast::Pattern::Wildcard(_) => {
-                            // Wildcard - always matches
-                            output.push_str("true");
-                        }
-                        crate::ast::Pattern::Ident(name, _) => {
-                            // Identifier (enum variant, constant) - compare with identifier
-                            output.push_str(&format!("{} == {}", expr_str, name));
-                        }
-                    }
-                    
-                    output.push_str(") {\n");
-                    
-                    // Generate body
-                    for stmt in &arm.body {
-                        output.push_str(&self.generate_statement(stmt, 1));
-                        output.push_str("\n");
-                    }
-                    
-                    output.push_str("}");
-                }
-                
-                output
-            }
+            Expression::Match { expr, arms, .. } => self.generate_match_value(expr, arms, None),
             Expression::StructLiteral { name, fields, .. } => {
                 // Check if this is a built-in struct type that uses constructor syntax
                 match name.as_str() {
@@ -2737,6 +6373,13 @@ impl CodeGenerator {
         // For extern C functions, use C-compatible types
         match ty {
             Type::String => "const char*".to_string(),
+            // Arrays and chunk slices cross as a pointer to the first element; pass the length separately
+            Type::Array(element) => format!("{}*", self.type_to_cpp_for_extern(element)),
+            // Callbacks cross the C boundary as raw function pointers
+            Type::Function(params, ret) => {
+                let param_types: Vec<String> = params.iter().map(|p| self.type_to_cpp_for_extern(p)).collect();
+                format!("std::add_pointer_t<{}({})>", self.type_to_cpp_for_extern(ret), param_types.join(", "))
+            }
             _ => self.type_to_cpp(ty)
         }
     }
@@ -2767,11 +6410,25 @@ impl CodeGenerator {
                     match ty {
                         Type::Component(name) => query_name.push_str(name),
                         Type::Struct(name) => query_name.push_str(name),
+                        Type::QueryFilter(..) => query_name.push_str(&self.type_to_cpp(ty)),
                         _ => query_name.push_str("Unknown"),
                     }
                 }
                 query_name
             }
+            // Part of a query type's name: Query_Position_WithoutFrozen
+            Type::QueryFilter(filter, component) => {
+                let filter = filter.name();
+                format!("{}{}{}", filter[..1].to_uppercase(), &filter[1..], self.type_to_cpp(component))
+            }
+            // Resources are passed by reference to their single instance
+            Type::Res(resource) => format!("{}&", self.type_to_cpp(resource)),
+            Type::Function(params, ret) => {
+                let param_types: Vec<String> = params.iter().map(|p| self.type_to_cpp(p)).collect();
+                format!("std::function<{}({})>", self.type_to_cpp(ret), param_types.join(", "))
+            }
+            // Only bound by chunk loops, whose members are generated in place
+            Type::QueryChunk(_) => "auto".to_string(),
             Type::Void => "void".to_string(),
             // Vulkan types
             Type::VkInstance => "VkInstance".to_string(),
@@ -2810,5 +6467,785 @@ impl CodeGenerator {
     fn indent(&self, level: usize) -> String {
         "    ".repeat(level)
     }
+    
+    // run_systems(): each scheduled system's entry functions, in run order, with the frame time
+    // and the resources they take
+    fn generate_scheduler(&self) -> String {
+        let mut output = self.section("System schedule (run_systems)");
+        output.push_str("static void heidic_run_systems() {\n");
+        output.push_str("    static auto last_frame = std::chrono::steady_clock::now();\n");
+        output.push_str("    auto now = std::chrono::steady_clock::now();\n");
+        output.push_str("    float dt = std::chrono::duration<float>(now - last_frame).count();\n");
+        output.push_str("    last_frame = now;\n");
+        output.push_str("    (void)dt;\n");
+        output.push_str(&self.generate_builtin_resource_updates());
+        if self.input.is_some() {
+            output.push_str(&format!("    {}();\n", input_map::UPDATE_FUNCTION));
+        }
+        for event in &self.events {
+            output.push_str(&format!("    {}.next_frame();\n", event_queue(&event.name)));
+        }
+        let mut stage = None;
+        for system in &self.scheduled_systems {
+            let system_stage = system.schedule.as_ref().and_then(|s| s.stage.as_deref()).unwrap_or("update");
+            if stage != Some(system_stage) {
+                output.push_str(&format!("    // {}\n", system_stage));
+                stage = Some(system_stage);
+            }
+            // Handlers first: each gets the events emitted since its system last ran
+            for handler in &system.handlers {
+                let function = event_handler_function(system, handler);
+                output.push_str("    {\n");
+                output.push_str("        static uint64_t cursor = 0;\n");
+                output.push_str(&format!("        {}.drain(cursor, {});\n", event_queue(&handler.event), function.name));
+                output.push_str("    }\n");
+            }
+            for f in &system.functions {
+                let Some(arguments) = schedule::entry_arguments(f) else {
+                    continue;
+                };
+                // Queries are gathered from g_storage right before the call and written back after it
+                let queries: Vec<(String, String)> = arguments.iter().filter_map(|argument| match argument {
+                    EntryArgument::Query(param) => Some((format!("heidic_query_{}", param.name), self.type_to_cpp(&param.ty))),
+                    _ => None,
+                }).collect();
+                let args: Vec<String> = arguments.iter().map(|argument| match argument {
+                    EntryArgument::FrameTime => "dt".to_string(),
+                    EntryArgument::Query(param) => format!("heidic_query_{}", param.name),
+                    EntryArgument::Resource(resource) => resources::instance_name(resource),
+                }).collect();
+                let args = args.join(", ");
+                let pad = if queries.is_empty() { "    " } else { "        " };
+                if !queries.is_empty() {
+                    output.push_str("    {\n");
+                }
+                for (local, query) in &queries {
+                    output.push_str(&format!("{}{} {} = {}();\n", pad, query, local, query_gather_function(query)));
+                }
+                if system.is_hot {
+                    // A DLL that failed to load leaves the pointer null
+                    output.push_str(&format!("{0}if (g_{1}) g_{1}({2});\n", pad, f.name, args));
+                } else {
+                    output.push_str(&format!("{}{}({});\n", pad, f.name, args));
+                }
+                for (local, _) in &queries {
+                    output.push_str(&format!("{}heidic_commit({});\n", pad, local));
+                }
+                if !queries.is_empty() {
+                    output.push_str("    }\n");
+                }
+            }
+        }
+        if !self.spatial_sounds.is_empty() {
+            output.push_str("    // Spatial sounds follow the Positions the systems just wrote\n");
+            output.push_str("    heidic_update_spatial_audio();\n");
+        }
+        output.push_str("}\n");
+        output.push('\n');
+        output
+    }
+    
+    // Event structs and their queues
+    fn generate_events(&self) -> String {
+        let mut output = self.section("Events");
+        for event in &self.events {
+            output.push_str(&self.generate_struct(&self.structs[&event.name], 0));
+            output.push_str(&format!("static EventQueue<{}> {};\n\n", event.name, event_queue(&event.name)));
+        }
+        output
+    }
+    
+    // One function per scene file creating its entities; the values were checked against the
+    // components by the type checker
+    fn generate_scenes(&self) -> String {
+        let mut output = self.section("Scenes");
+        for (i, scene) in self.scenes.iter().enumerate() {
+            output.push_str(&format!("// {}\n", scene.path));
+            output.push_str(&format!("static void heidic_spawn_scene_{}() {{\n", i));
+            for (index, entity) in scene.entities.iter().enumerate() {
+                output.push_str("    {\n");
+                output.push_str(&format!("        const EntityId heidic_entity = g_storage.create_entity();  // {}\n", scene::entity_label(entity, index)));
+                for (name, value) in &entity.components {
+                    let component = &self.components[name];
+                    let value_type = if component.is_soa { format!("{}::Row", name) } else { name.clone() };
+                    output.push_str(&format!("        {} heidic_{}{{}};\n", value_type, name.to_lowercase()));
+                    for (field_name, field_value) in value.as_object().into_iter().flatten() {
+                        let Some(field) = component.fields.iter().find(|f| f.name == *field_name) else { continue };
+                        let ty = scene::field_type(component, field);
+                        output.push_str(&format!("        heidic_{}.{} = {};\n", name.to_lowercase(), field_name, self.scene_value(field_value, &ty)));
+                    }
+                    output.push_str(&format!("        g_storage.add_component<{}>(heidic_entity, heidic_{});\n", name, name.to_lowercase()));
+                }
+                output.push_str("    }\n");
+            }
+            output.push_str("}\n\n");
+        }
+        output
+    }
+
+    // A scene file's JSON value as a C++ expression of type `ty`
+    fn scene_value(&self, value: &serde_json::Value, ty: &Type) -> String {
+        let float = |value: &serde_json::Value| format!("{:?}f", value.as_f64().unwrap_or(0.0) as f32);
+        let vector = |name: &str| {
+            let items: Vec<String> = value.as_array().into_iter().flatten().map(float).collect();
+            format!("{}({})", name, items.join(", "))
+        };
+        match ty {
+            Type::I32 => value.as_i64().unwrap_or(0).to_string(),
+            Type::I64 => format!("static_cast<int64_t>({})", value.as_i64().unwrap_or(0)),
+            Type::F32 => float(value),
+            Type::F64 => format!("{:?}", value.as_f64().unwrap_or(0.0)),
+            Type::Bool => value.as_bool().unwrap_or(false).to_string(),
+            Type::String => format!("std::string({})", value),
+            Type::Vec2 => vector("Vec2"),
+            Type::Vec3 => vector("Vec3"),
+            Type::Vec4 => vector("Vec4"),
+            Type::Array(element) => {
+                let items: Vec<String> = value.as_array().into_iter().flatten().map(|item| self.scene_value(item, element)).collect();
+                format!("{}{{{}}}", self.type_to_cpp(ty), items.join(", "))
+            }
+            Type::Struct(name) | Type::Component(name) => {
+                let fields = self.structs.get(name).map(|s| s.fields.as_slice())
+                    .or_else(|| self.components.get(name).map(|c| c.fields.as_slice()))
+                    .unwrap_or_default();
+                let assignments: String = value.as_object().into_iter().flatten()
+                    .filter_map(|(key, field_value)| {
+                        let field = fields.iter().find(|f| f.name == *key)?;
+                        Some(format!(" v.{} = {};", key, self.scene_value(field_value, &field.ty)))
+                    })
+                    .collect();
+                format!("[] {{ {} v{{}};{} return v; }}()", name, assignments)
+            }
+            _ => self.get_default_value_for_type(ty),
+        }
+    }
+
+    // memory_report() / memory_report(path): every component's storage (and, with archetype
+    // storage, every archetype's) handed to stdlib/memory_report.h, which prints it, writes the
+    // JSON and returns the total bytes
+    fn generate_memory_report(&self) -> String {
+        let mut output = self.section("Memory report (memory_report)");
+        output.push_str("#include \"stdlib/memory_report.h\"\n\n");
+        let mut names: Vec<String> = self.sorted_components().iter().map(|c| c.name.clone()).collect();
+        if self.uses_hierarchy {
+            names.extend(hierarchy::BUILTINS.iter().map(|name| name.to_string()));
+        }
+        let archetypes = self.ecs_storage == EcsStorage::Archetype;
+        if archetypes {
+            output.push_str("static std::string heidic_component_name(size_t type) {\n");
+            for name in &names {
+                output.push_str(&format!("    if (type == typeid({}).hash_code()) return \"{}\";\n", name, name));
+            }
+            output.push_str("    return \"?\";\n");
+            output.push_str("}\n\n");
+        }
+        output.push_str("static int64_t memory_report(const std::string& json_path = \"\") {\n");
+        output.push_str("    std::vector<HeidicMemoryEntry> components;\n");
+        for name in &names {
+            output.push_str(&format!("    components.push_back({{\"{}\", g_storage.memory_usage<{}>()}});\n", name, name));
+        }
+        output.push_str("    std::vector<HeidicMemoryEntry> archetypes;\n");
+        if archetypes {
+            output.push_str("    for (const auto& [types, memory] : g_storage.archetype_memory()) {\n");
+            output.push_str("        std::string name;\n");
+            output.push_str("        for (size_t type : types) {\n");
+            output.push_str("            name += (name.empty() ? \"\" : \"+\") + heidic_component_name(type);\n");
+            output.push_str("        }\n");
+            output.push_str("        archetypes.push_back({name, memory});\n");
+            output.push_str("    }\n");
+        }
+        output.push_str("    return heidic_memory_report(components, archetypes, g_storage.bookkeeping_bytes(), json_path);\n");
+        output.push_str("}\n\n");
+        output
+    }
+    
+    // save_world(path)/load_world(path): the entity allocator, then one block per component with
+    // its fields (from ComponentFields), its byte size and every entity's values. Loading skips a
+    // block whose fields changed since the save (unless a migrate function takes the saved
+    // layout), and builds the world in a storage of its own so a damaged file leaves the running
+    // one untouched.
+    fn generate_world(&self) -> String {
+        let mut output = self.section("Saved worlds (save_world / load_world)");
+        // (name, C++ members, expression for the field descriptor written before its values)
+        let mut components: Vec<(String, Vec<String>, String)> = Vec::new();
+        for component in self.sorted_components() {
+            if let Some((field, reason)) = save_schema::unsaved_world_field(component) {
+                output.push_str(&format!("// {} isn't saved: {}.{}: {}
+", component.name, component.name, field.name, reason));
+                continue;
+            }
+            let members = component.fields.iter().map(|field| field.name.clone()).collect();
+            components.push((component.name.clone(), members, format!("heidic_world_fields<{}>()", component.name)));
+        }
+        if self.uses_hierarchy {
+            for name in hierarchy::BUILTINS {
+                let members = hierarchy::builtin_members(name);
+                let descriptor: Vec<String> = members.iter().flat_map(|(member, ty)| [format!("\"{}\"", member), format!("\"{}\"", ty)]).collect();
+                components.push((name.to_string(), members.iter().map(|(member, _)| member.to_string()).collect(),
+                    format!("std::vector<std::string>{{{}}}", descriptor.join(", "))));
+            }
+        }
+        if !self.components.is_empty() {
+            output.push_str("template <typename T>\n");
+            output.push_str("static std::vector<std::string> heidic_world_fields() {\n");
+            output.push_str("    std::vector<std::string> fields;  // Name, type, name, type, ...\n");
+            output.push_str("    for (size_t i = 0; i < ComponentFields<T>::field_count; ++i) {\n");
+            output.push_str("        fields.push_back(ComponentFields<T>::get_fields()[i].name);\n");
+            output.push_str("        fields.push_back(ComponentFields<T>::get_fields()[i].type_name);\n");
+            output.push_str("    }\n");
+            output.push_str("    return fields;\n");
+            output.push_str("}\n\n");
+        }
+
+        output.push_str("static bool save_world(const std::string& path) {\n");
+        output.push_str("    HeidicSaveWriter writer(\"World\", 1);\n");
+        output.push_str("    writer.write(g_storage.entity_limit());\n");
+        output.push_str("    writer.write(g_storage.free_entities());\n");
+        output.push_str(&format!("    writer.write(static_cast<uint32_t>({}));\n", components.len()));
+        for (name, members, descriptor) in &components {
+            output.push_str("    {\n");
+            output.push_str(&format!("        writer.write(std::string(\"{}\"));\n", name));
+            output.push_str(&format!("        writer.write({});\n", descriptor));
+            output.push_str("        size_t size_at = writer.reserve_size();\n");
+            output.push_str("        uint32_t count = 0;\n");
+            output.push_str(&format!("        g_storage.for_each<{}>([&](EntityId, const auto&) {{ ++count; }});\n", name));
+            output.push_str("        writer.write(count);\n");
+            output.push_str(&format!("        g_storage.for_each<{}>([&](EntityId entity, const auto& value) {{\n", name));
+            output.push_str("            writer.write(entity);\n");
+            for member in members {
+                output.push_str(&format!("            writer.write(value.{});\n", member));
+            }
+            output.push_str("        });\n");
+            output.push_str("        writer.fill_size(size_at);\n");
+            output.push_str("    }\n");
+        }
+        output.push_str("    return writer.save_to(path);\n");
+        output.push_str("}\n\n");
+
+        output.push_str("static bool load_world(const std::string& path) {\n");
+        output.push_str("    HeidicSaveReader reader;\n");
+        output.push_str("    if (!reader.open(path, \"World\")) {\n");
+        output.push_str("        return false;\n");
+        output.push_str("    }\n");
+        output.push_str("    if (reader.version() != 1) {\n");
+        output.push_str("        fprintf(stderr, \"[save] %s is a version %u world; this program reads version 1\\n\", path.c_str(), reader.version());\n");
+        output.push_str("        return false;\n");
+        output.push_str("    }\n");
+        output.push_str("    EntityId limit = 0;\n");
+        output.push_str("    std::vector<EntityId> free_entities;\n");
+        output.push_str("    uint32_t component_count = 0;\n");
+        output.push_str("    reader.read(limit);\n");
+        output.push_str("    reader.read(free_entities);\n");
+        output.push_str("    reader.read(component_count);\n");
+        output.push_str("    EntityStorage world;\n");
+        output.push_str("    world.restore_entities(limit, std::move(free_entities), g_storage.change_tick());\n");
+        output.push_str("    for (uint32_t c = 0; c < component_count && reader.ok(); ++c) {\n");
+        output.push_str("        std::string name;\n");
+        output.push_str("        std::vector<std::string> fields;\n");
+        output.push_str("        uint32_t size = 0;\n");
+        output.push_str("        reader.read(name);\n");
+        output.push_str("        reader.read(fields);\n");
+        output.push_str("        reader.read(size);\n");
+        let mut blocks: Vec<WorldLoadBlock> = Vec::new();
+        for (name, members, descriptor) in &components {
+            let value_type = match self.components.get(name) {
+                Some(component) if component.is_soa => format!("{}::Row", name),
+                _ => name.clone(),
+            };
+            blocks.push(WorldLoadBlock { component: name, descriptor: descriptor.clone(), value_type, members: members.clone(), migrate: None });
+            for migrate in self.migrations.iter().filter(|migrate| migrate.function.name == *name) {
+                let Some(old) = migrate.old_struct().and_then(|old| self.structs.get(old)) else { continue };
+                let descriptor: Vec<String> = old.fields.iter()
+                    .flat_map(|field| [format!("\"{}\"", field.name), format!("\"{}\"", self.type_to_cpp(&field.ty))])
+                    .collect();
+                blocks.push(WorldLoadBlock {
+                    component: name,
+                    descriptor: format!("std::vector<std::string>{{{}}}", descriptor.join(", ")),
+                    value_type: old.name.clone(),
+                    members: old.fields.iter().map(|field| field.name.clone()).collect(),
+                    migrate: Some(migrate.cpp_name()),
+                });
+            }
+        }
+        for (i, WorldLoadBlock { component: name, descriptor, value_type, members, migrate }) in blocks.iter().enumerate() {
+            let keyword = if i == 0 { "if" } else { "} else if" };
+            output.push_str(&format!("        {} (name == \"{}\" && fields == {}) {{\n", keyword, name, descriptor));
+            output.push_str("            uint32_t count = 0;\n");
+            output.push_str("            reader.read(count);\n");
+            output.push_str("            for (uint32_t i = 0; i < count && reader.ok(); ++i) {\n");
+            output.push_str("                EntityId entity = INVALID_ENTITY;\n");
+            output.push_str("                reader.read(entity);\n");
+            output.push_str(&format!("                {} value{{}};\n", value_type));
+            for member in members {
+                output.push_str(&format!("                reader.read(value.{});\n", member));
+            }
+            output.push_str("                if (entity == INVALID_ENTITY || entity > limit) {\n");
+            output.push_str("                    fprintf(stderr, \"[save] %s has a component on entity %u, which doesn't exist\\n\", path.c_str(), entity);\n");
+            output.push_str("                    return false;\n");
+            output.push_str("                }\n");
+            match migrate {
+                Some(migrate) => output.push_str(&format!("                world.add_component<{}>(entity, {}(value));\n", name, migrate)),
+                None => output.push_str(&format!("                world.add_component<{}>(entity, value);\n", name)),
+            }
+            output.push_str("            }\n");
+        }
+        let indent = if blocks.is_empty() { "        {" } else { "        } else {" };
+        output.push_str(&format!("{}\n", indent));
+        output.push_str("            fprintf(stderr, \"[save] %s: skipping %s, which this program doesn't declare with the same fields\\n\", path.c_str(), name.c_str());\n");
+        output.push_str("            reader.skip(size);\n");
+        output.push_str("        }\n");
+        output.push_str("    }\n");
+        output.push_str("    if (!reader.ok()) {\n");
+        output.push_str("        fprintf(stderr, \"[save] %s is truncated or damaged\\n\", path.c_str());\n");
+        output.push_str("        return false;\n");
+        output.push_str("    }\n");
+        output.push_str("    g_storage = std::move(world);\n");
+        output.push_str("    return true;\n");
+        output.push_str("}\n\n");
+        output
+    }
+
+    // net_host/net_connect/net_update (stdlib/net.h): the server sends each peer every replicated
+    // component changed since the newest snapshot it acknowledged, and the removals it hasn't
+    // acknowledged yet; clients apply entries by component index, in sorted component order.
+    fn generate_replication(&self) -> String {
+        let mut output = self.section("Network replication (@[replicated])");
+        let components: Vec<&ComponentDef> = self.sorted_components().into_iter()
+            .filter(|component| component.is_replicated && replication::unreplicated_field(component).is_none())
+            .collect();
+        output.push_str("static HeidicNetHost g_net;\n\n");
+
+        // A client built from different component definitions can't read the snapshots
+        let schema: Vec<String> = components.iter()
+            .flat_map(|component| std::iter::once(format!("\"{}\"", component.name)).chain(
+                component.fields.iter().flat_map(|field| [format!("\"{}\"", field.name), format!("\"{}\"", crate::formatter::type_str(&field.ty))])))
+            .collect();
+        output.push_str("static std::vector<std::string> heidic_net_schema() {\n");
+        output.push_str(&format!("    return {{{}}};\n", schema.join(", ")));
+        output.push_str("}\n\n");
+
+        output.push_str("static bool net_host(int32_t port) { return g_net.host(static_cast<uint16_t>(port)); }\n");
+        output.push_str("static bool net_connect(const std::string& host, int32_t port) { return g_net.connect(host, static_cast<uint16_t>(port)); }\n");
+        output.push_str("static int32_t net_peer_count() { return static_cast<int32_t>(g_net.peers().size()); }\n");
+        output.push_str("static bool net_connected() { return g_net.connected(); }\n\n");
+
+        output.push_str("static void heidic_net_send_snapshot(HeidicNetPeer& peer) {\n");
+        output.push_str("    HeidicSnapshotWriter snapshot(++peer.sequence, g_storage.change_tick());\n");
+        for (index, component) in components.iter().enumerate() {
+            let name = &component.name;
+            output.push_str(&format!("    {{  // {}\n", name));
+            output.push_str(&format!("        auto& sent = peer.sent[{}];\n", index));
+            output.push_str(&format!("        g_storage.for_each<{}>([&](EntityId entity, const auto& value) {{\n", name));
+            output.push_str(&format!("            if (sent.count(entity) && !g_storage.changed_since<{}>(entity, peer.acked_tick)) {{\n", name));
+            output.push_str("                return;\n");
+            output.push_str("            }\n");
+            output.push_str("            sent[entity] = 0;\n");
+            output.push_str(&format!("            HeidicSaveWriter& packet = snapshot.entry({}, entity, true);\n", index));
+            for field in &component.fields {
+                output.push_str(&format!("            packet.write(value.{});\n", field.name));
+            }
+            output.push_str("        });\n");
+            output.push_str("        for (auto it = sent.begin(); it != sent.end();) {\n");
+            output.push_str(&format!("            if (g_storage.has_component<{}>(it->first)) {{\n", name));
+            output.push_str("                ++it;\n");
+            output.push_str("                continue;\n");
+            output.push_str("            }\n");
+            output.push_str("            if (it->second != 0 && peer.acked_sequence >= it->second) {\n");
+            output.push_str("                it = sent.erase(it);  // The peer has the removal\n");
+            output.push_str("                continue;\n");
+            output.push_str("            }\n");
+            output.push_str("            if (it->second == 0) {\n");
+            output.push_str("                it->second = snapshot.sequence();\n");
+            output.push_str("            }\n");
+            output.push_str(&format!("            snapshot.entry({}, it->first, false);\n", index));
+            output.push_str("            ++it;\n");
+            output.push_str("        }\n");
+            output.push_str("    }\n");
+        }
+        output.push_str("    for (const HeidicSaveWriter& packet : snapshot.finish()) {\n");
+        output.push_str("        g_net.send(peer.address, packet);\n");
+        output.push_str("    }\n");
+        output.push_str("}\n\n");
+
+        output.push_str("static void heidic_net_apply(HeidicSaveReader& packet) {\n");
+        output.push_str("    uint32_t entries = 0;\n");
+        output.push_str("    packet.read(entries);\n");
+        output.push_str("    for (uint32_t i = 0; i < entries && packet.ok(); ++i) {\n");
+        output.push_str("        uint32_t component = 0;\n");
+        output.push_str("        EntityId remote = INVALID_ENTITY;\n");
+        output.push_str("        bool present = false;\n");
+        output.push_str("        packet.read(component);\n");
+        output.push_str("        packet.read(remote);\n");
+        output.push_str("        packet.read(present);\n");
+        output.push_str("        if (!packet.ok()) {\n");
+        output.push_str("            return;\n");
+        output.push_str("        }\n");
+        output.push_str("        const EntityId entity = g_net.local_entity(remote, g_storage);\n");
+        for (index, component) in components.iter().enumerate() {
+            let name = &component.name;
+            let keyword = if index == 0 { "if" } else { "} else if" };
+            let value_type = if component.is_soa { format!("{}::Row", name) } else { name.clone() };
+            output.push_str(&format!("        {} (component == {}) {{\n", keyword, index));
+            output.push_str("            if (!present) {\n");
+            output.push_str(&format!("                g_storage.remove_component<{}>(entity);\n", name));
+            output.push_str("                continue;\n");
+            output.push_str("            }\n");
+            output.push_str(&format!("            {} value{{}};\n", value_type));
+            for field in &component.fields {
+                output.push_str(&format!("            packet.read(value.{});\n", field.name));
+            }
+            output.push_str("            if (packet.ok()) {\n");
+            output.push_str(&format!("                g_storage.add_component<{}>(entity, value);\n", name));
+            output.push_str("            }\n");
+        }
+        let indent = if components.is_empty() { "        {" } else { "        } else {" };
+        output.push_str(&format!("{}\n", indent));
+        output.push_str("            return;  // Not a component of this program; the rest can't be read\n");
+        output.push_str("        }\n");
+        output.push_str("    }\n");
+        output.push_str("}\n\n");
+
+        output.push_str("// Server: take connects and acks, then send every peer its snapshot. Client: apply the\n");
+        output.push_str("// snapshot packets that arrived, then acknowledge (or keep asking to connect).\n");
+        output.push_str("static void net_update() {\n");
+        output.push_str("    if (!g_net.is_open()) {\n");
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push_str("    HeidicSaveReader packet;\n");
+        output.push_str("    HeidicNetAddress from;\n");
+        output.push_str("    HeidicNetPacket kind;\n");
+        output.push_str("    while (g_net.receive(packet, from, kind)) {\n");
+        output.push_str("        if (g_net.is_server() && kind == HeidicNetPacket::Connect) {\n");
+        output.push_str("            std::vector<std::string> schema;\n");
+        output.push_str("            packet.read(schema);\n");
+        output.push_str("            if (schema != heidic_net_schema()) {\n");
+        output.push_str("                fprintf(stderr, \"[net] %s replicates different components; ignoring it\\n\", from.to_string().c_str());\n");
+        output.push_str("                continue;\n");
+        output.push_str("            }\n");
+        output.push_str(&format!("            g_net.add_peer(from, {});\n", components.len()));
+        output.push_str("        } else if (g_net.is_server() && kind == HeidicNetPacket::Ack) {\n");
+        output.push_str("            uint32_t sequence = 0;\n");
+        output.push_str("            uint32_t tick = 0;\n");
+        output.push_str("            packet.read(sequence);\n");
+        output.push_str("            packet.read(tick);\n");
+        output.push_str("            if (packet.ok()) {\n");
+        output.push_str("                g_net.acknowledge(from, sequence, tick);\n");
+        output.push_str("            }\n");
+        output.push_str("        } else if (!g_net.is_server() && kind == HeidicNetPacket::Snapshot) {\n");
+        output.push_str("            uint32_t sequence = 0;\n");
+        output.push_str("            uint32_t tick = 0;\n");
+        output.push_str("            uint32_t index = 0;\n");
+        output.push_str("            uint32_t count = 0;\n");
+        output.push_str("            packet.read(sequence);\n");
+        output.push_str("            packet.read(tick);\n");
+        output.push_str("            packet.read(index);\n");
+        output.push_str("            packet.read(count);\n");
+        output.push_str("            if (packet.ok() && g_net.begin_snapshot_packet(from, sequence, tick, index, count)) {\n");
+        output.push_str("                heidic_net_apply(packet);\n");
+        output.push_str("            }\n");
+        output.push_str("        }\n");
+        output.push_str("    }\n");
+        output.push_str("    if (g_net.is_server()) {\n");
+        output.push_str("        g_net.drop_silent_peers();\n");
+        output.push_str("        for (HeidicNetPeer& peer : g_net.peers()) {\n");
+        output.push_str("            heidic_net_send_snapshot(peer);\n");
+        output.push_str("        }\n");
+        output.push_str("    } else {\n");
+        output.push_str("        g_net.client_update(heidic_net_schema());\n");
+        output.push_str("    }\n");
+        output.push_str("}\n\n");
+        output
+    }
+
+    // Per schema: a reader for every version, a migration from each version to the next, and
+    // save_<schema>()/load_<schema>(); loading chains migrations up to the newest version
+    fn generate_save_schemas(&mut self, schemas: &BTreeMap<&str, Vec<&SaveSchemaDef>>) -> String {
+        let mut output = self.section("Save data (save_schema)");
+        for (name, versions) in schemas {
+            let newest = versions.last().map_or(0, |schema| schema.version);
+            for schema in versions {
+                let struct_name = save_schema::struct_name(schema, newest);
+                output.push_str(&format!("static bool heidic_read_{}(HeidicSaveReader& in, {}& out) {{\n", struct_name, struct_name));
+                for field in &schema.fields {
+                    output.push_str(&format!("    in.read(out.{});\n", field.name));
+                }
+                output.push_str("    return in.ok();\n");
+                output.push_str("}\n");
+            }
+            for pair in versions.windows(2) {
+                output.push_str(&self.generate_save_migration(pair[0], pair[1], newest));
+            }
+            
+            output.push_str(&format!("static bool {}(const std::string& path, const {}& data) {{\n", save_schema::save_function(name), name));
+            output.push_str(&format!("    HeidicSaveWriter out(\"{}\", {});\n", name, newest));
+            for field in versions.last().map_or(&[][..], |schema| &schema.fields[..]) {
+                output.push_str(&format!("    out.write(data.{});\n", field.name));
+            }
+            output.push_str("    return out.save_to(path);\n");
+            output.push_str("}\n");
+            
+            // Each version is read into its own struct and migrated one version at a time
+            output.push_str(&format!("static std::optional<{}> {}(const std::string& path) {{\n", name, save_schema::load_function(name)));
+            output.push_str("    HeidicSaveReader in;\n");
+            output.push_str(&format!("    if (!in.open(path, \"{}\")) {{\n", name));
+            output.push_str("        return std::nullopt;\n");
+            output.push_str("    }\n");
+            output.push_str("    switch (in.version()) {\n");
+            for (i, schema) in versions.iter().enumerate() {
+                let struct_name = save_schema::struct_name(schema, newest);
+                output.push_str(&format!("        case {}: {{\n", schema.version));
+                output.push_str(&format!("            {} v{}{{}};\n", struct_name, schema.version));
+                output.push_str(&format!("            if (!heidic_read_{}(in, v{})) {{\n", struct_name, schema.version));
+                output.push_str("                return std::nullopt;\n");
+                output.push_str("            }\n");
+                let mut value = format!("v{}", schema.version);
+                for next in &versions[i + 1..] {
+                    value = format!("heidic_migrate_{}({})", save_schema::struct_name(next, newest), value);
+                }
+                output.push_str(&format!("            return {};\n", value));
+                output.push_str("        }\n");
+            }
+            output.push_str("        default:\n");
+            output.push_str(&format!("            fprintf(stderr, \"[save] %s is {} v%u; this build reads up to v{}\\n\", path.c_str(), in.version());\n", name, newest));
+            output.push_str("            return std::nullopt;\n");
+            output.push_str("    }\n");
+            output.push_str("}\n\n");
+        }
+        output
+    }
+    
+    // `from vN(old, new) { ... }`: copies what carries over, then runs the block on the new struct
+    fn generate_save_migration(&mut self, old: &SaveSchemaDef, new: &SaveSchemaDef, newest: u32) -> String {
+        let old_struct = save_schema::struct_name(old, newest);
+        let new_struct = save_schema::struct_name(new, newest);
+        let (old_name, new_name) = new.migration.as_ref()
+            .map_or(("old", "new"), |migration| (migration.old.as_str(), migration.new.as_str()));
+        let mut output = String::new();
+        let renamed: Vec<&str> = [old_name, new_name].into_iter().filter(|name| CPP_KEYWORDS.contains(name)).collect();
+        for name in &renamed {
+            output.push_str(&format!("#define {} heidic_{}\n", name, name));
+        }
+        output.push_str(&format!("static {} heidic_migrate_{}(const {}& {}) {{\n", new_struct, new_struct, old_struct, old_name));
+        output.push_str(&format!("    {} {}{{}};\n", new_struct, new_name));
+        for field in save_schema::copied_fields(old, new) {
+            output.push_str(&format!("    {}.{} = {}.{};\n", new_name, field.name, old_name, field.name));
+        }
+        if let Some(migration) = &new.migration {
+            self.reload_checks = false;
+            self.query_params.clear();
+            for stmt in &migration.body {
+                output.push_str(&self.generate_statement(stmt, 0));
+            }
+        }
+        output.push_str(&format!("    return {};\n", new_name));
+        output.push_str("}\n");
+        for name in &renamed {
+            output.push_str(&format!("#undef {}\n", name));
+        }
+        output
+    }
+    
+    // Built-in resource types the program uses, then one zero-initialized instance of every resource
+    fn generate_resources(&self) -> String {
+        let mut output = self.section("Resources (res<T>)");
+        for s in &self.builtin_resources {
+            output.push_str(&self.generate_struct(s, 0));
+        }
+        for resource in &self.resource_types {
+            output.push_str(&format!("static {} {}{{}};\n", resource, resources::instance_name(resource)));
+        }
+        output.push('\n');
+        output
+    }
+    
+    // Time and Input are refreshed before any system runs
+    fn generate_builtin_resource_updates(&self) -> String {
+        let mut output = String::new();
+        for s in &self.builtin_resources {
+            let res = resources::instance_name(&s.name);
+            match s.name.as_str() {
+                "Time" => {
+                    output.push_str(&format!("    {}.delta = dt;\n", res));
+                    output.push_str(&format!("    {}.elapsed += dt;\n", res));
+                    output.push_str(&format!("    {}.frame += 1;\n", res));
+                }
+                "Input" => {
+                    output.push_str(&format!("    if ({}.window) {{\n", res));
+                    output.push_str("        double mouse_x = 0.0, mouse_y = 0.0;\n");
+                    output.push_str(&format!("        glfwGetCursorPos({}.window, &mouse_x, &mouse_y);\n", res));
+                    output.push_str(&format!("        {r}.mouse_dx = static_cast<float>(mouse_x) - {r}.mouse_x;\n", r = res));
+                    output.push_str(&format!("        {r}.mouse_dy = static_cast<float>(mouse_y) - {r}.mouse_y;\n", r = res));
+                    output.push_str(&format!("        {}.mouse_x = static_cast<float>(mouse_x);\n", res));
+                    output.push_str(&format!("        {}.mouse_y = static_cast<float>(mouse_y);\n", res));
+                    for (field, button) in [("mouse_left", "LEFT"), ("mouse_right", "RIGHT"), ("mouse_middle", "MIDDLE")] {
+                        output.push_str(&format!("        {}.{} = glfwGetMouseButton({}.window, GLFW_MOUSE_BUTTON_{}) == GLFW_PRESS;\n", res, field, res, button));
+                    }
+                    output.push_str("    }\n");
+                }
+                _ => {}
+            }
+        }
+        output
+    }
+    
+    // Consts were evaluated by the type checker; untyped ones take their literal's C++ type
+    fn generate_consts(&mut self) -> String {
+        if self.consts.is_empty() {
+            return String::new();
+        }
+        let mut output = self.section("Constants");
+        // Constants are folded (and overflow-checked) by the compiler, and must stay constexpr
+        let checked = self.checked_arithmetic.take();
+        for c in self.consts.clone() {
+            let ty = c.ty.as_ref().map_or_else(|| "auto".to_string(), |ty| self.type_to_cpp(ty));
+            // @tweak consts are variables the tweak table points at
+            let storage = if c.is_tweak { "static" } else { "constexpr" };
+            output.push_str(&format!("{} {} {} = {};\n", storage, ty, c.name, self.generate_expression(&c.value)));
+        }
+        self.checked_arithmetic = checked;
+        output.push('\n');
+        output
+    }
+    
+    // The table tweak_panel() and tweak_file() work on, after the consts it points at
+    fn generate_tweaks(&mut self) -> String {
+        let mut output = self.section("Tweakable constants (@tweak)");
+        output.push_str("static HeidicTweak heidic_tweak_entries[] = {\n");
+        for c in self.consts.clone().iter().filter(|c| c.is_tweak) {
+            output.push_str(&format!("    {{ \"{name}\", heidic_tweak_type_of({name}), &{name}, static_cast<double>({}) }},\n",
+                self.generate_expression(&c.value), name = c.name));
+        }
+        output.push_str("};\n");
+        output.push_str("static HeidicTweakTable heidic_tweaks = { heidic_tweak_entries, sizeof(heidic_tweak_entries) / sizeof(heidic_tweak_entries[0]), \"\", {} };\n");
+        output.push_str("static void tweak_panel() { heidic_tweak_panel(heidic_tweaks); }\n");
+        output.push_str("static bool tweak_file(const std::string& path) { return heidic_tweak_use_file(heidic_tweaks, path); }\n");
+        output.push('\n');
+        output
+    }
+    
+    // The type checker has already checked the layout exists; the value is emitted as a literal so
+    // it works in constexpr and array sizes, and a static_assert catches any disagreement with C++
+    fn generate_layout_of(&mut self, query: LayoutQuery, ty: &Type, field: Option<&str>) -> String {
+        let layouts = self.layouts();
+        let value = match (query, field) {
+            (LayoutQuery::Offset, Some(field)) => layouts.offset_of(ty, field),
+            (LayoutQuery::Align, _) => layouts.layout(ty).map(|layout| layout.align),
+            _ => layouts.layout(ty).map(|layout| layout.size),
+        };
+        let Ok(value) = value else {
+            return format!("/* {}: no layout */ 0", query.name());
+        };
+        let cpp_type = self.type_to_cpp(ty);
+        let cpp_query = match (query, field) {
+            // offsetof isn't portable for types holding standard library members
+            (LayoutQuery::Offset, Some(_)) if !layouts.is_standard_layout(ty) => return value.to_string(),
+            (LayoutQuery::Offset, Some(field)) => format!("offsetof({}, {})", cpp_type, field),
+            (LayoutQuery::Align, _) => format!("alignof({})", cpp_type),
+            _ => format!("sizeof({})", cpp_type),
+        };
+        let check = layout_check(&cpp_query, value);
+        if !self.layout_checks.contains(&check) {
+            self.layout_checks.push(check);
+        }
+        value.to_string()
+    }
+    
+    fn has_hot_reload(&self) -> bool {
+        !self.hot_systems.is_empty() || !self.hot_shaders.is_empty() || !self.hot_components.is_empty() || self.has_resources
+            || self.has_tweaks()
+    }
+    
+    fn has_tweaks(&self) -> bool {
+        self.consts.iter().any(|c| c.is_tweak)
+    }
+    
+    // Called at the top of each outermost while loop; runs every hot-reload check at most once per interval
+    fn reload_check_function(&self) -> String {
+        let mut output = String::new();
+        if self.target == Target::Wasm {
+            // Only tweak files reload in a browser (wasm::without_hot_reload), when the page asks
+            output.push_str("// The page harness calls heidic_reload() after writing changed files into the virtual file system\n");
+            output.push_str("static bool heidic_reload_requested = false;\n");
+            output.push_str("extern \"C\" EMSCRIPTEN_KEEPALIVE void heidic_reload() {\n");
+            output.push_str("    heidic_reload_requested = true;\n");
+            output.push_str("}\n");
+            output.push_str("static inline void heidic_check_reloads() {\n");
+            output.push_str("    if (!heidic_reload_requested) {\n");
+            output.push_str("        return;\n");
+            output.push_str("    }\n");
+            output.push_str("    heidic_reload_requested = false;\n");
+            if self.has_tweaks() {
+                output.push_str("    heidic_tweak_reload(heidic_tweaks);\n");
+            }
+            output.push_str("}\n");
+            output.push('\n');
+            return output;
+        }
+        output.push_str("// Hot-reload checks run at most this often (compile with -DHEIDIC_RELOAD_CHECK_INTERVAL_MS=0 to check every iteration)\n");
+        output.push_str("#ifndef HEIDIC_RELOAD_CHECK_INTERVAL_MS\n");
+        output.push_str("#define HEIDIC_RELOAD_CHECK_INTERVAL_MS 100\n");
+        output.push_str("#endif\n");
+        output.push_str("static inline void heidic_check_reloads() {\n");
+        output.push_str("    static std::chrono::steady_clock::time_point next_check{};\n");
+        output.push_str("    auto now = std::chrono::steady_clock::now();\n");
+        output.push_str("    if (now < next_check) {\n");
+        output.push_str("        return;\n");
+        output.push_str("    }\n");
+        output.push_str("    next_check = now + std::chrono::milliseconds(HEIDIC_RELOAD_CHECK_INTERVAL_MS);\n");
+        if !self.hot_systems.is_empty() {
+            output.push_str("    check_and_reload_hot_system();\n");
+        }
+        if !self.hot_shaders.is_empty() {
+            output.push_str("    check_and_reload_hot_shaders();\n");
+        }
+        if !self.hot_components.is_empty() {
+            output.push_str("    check_and_migrate_hot_components();\n");
+        }
+        if self.has_resources {
+            output.push_str("    check_and_reload_resources();\n");
+        }
+        if self.has_tweaks() {
+            output.push_str("    heidic_tweak_reload(heidic_tweaks);\n");
+        }
+        output.push_str("}\n");
+        output.push('\n');
+        output
+    }
+    
+    // Heading comment for a top-level section of the output (a banner under --emit-readable)
+    // Fail the C++ build up front when stdlib/ comes from a different engine release
+    fn generate_stdlib_version_check(&self) -> String {
+        let required = format!("{}.{}", STDLIB_VERSION_MAJOR, STDLIB_VERSION_MINOR);
+        let compiler = env!("CARGO_PKG_VERSION");
+        let mut output = String::new();
+        output.push_str("#if __has_include(\"stdlib/version.h\")\n");
+        output.push_str("#include \"stdlib/version.h\"\n");
+        output.push_str(&format!("static_assert(HEIDIC_STDLIB_VERSION_MAJOR == {}, \"stdlib/ major version mismatch: generated code (heidic {}) requires stdlib {}; use the stdlib/ shipped with this compiler\");\n",
+            STDLIB_VERSION_MAJOR, compiler, required));
+        output.push_str(&format!("static_assert(HEIDIC_STDLIB_VERSION_MINOR >= {}, \"stdlib/ is older than generated code (heidic {}) requires (stdlib {}); update stdlib/\");\n",
+            STDLIB_VERSION_MINOR, compiler, required));
+        output.push_str("#else\n");
+        output.push_str(&format!("#error \"stdlib/version.h not found: this stdlib/ predates version checks; generated code (heidic {}) requires stdlib {}\"\n",
+            compiler, required));
+        output.push_str("#endif\n");
+        output
+    }
+    
+    fn section(&self, title: &str) -> String {
+        if self.emit_style == EmitStyle::Readable {
+            let rule = format!("// {}\n", "=".repeat(76));
+            format!("{}// {}\n{}", rule, title, rule)
+        } else {
+            format!("// {}\n", title)
+        }
+    }
+    
+    // `if true` / `if false` / `while false` are folded away under --emit-readable
+    fn constant_condition(&self, condition: &Expression) -> Option<bool> {
+        match condition {
+            Expression::Literal(Literal::Bool(value), _) if self.emit_style == EmitStyle::Readable => Some(*value),
+            _ => None,
+        }
+    }
 }
 
@@ -0,0 +1,211 @@
+// Project configuration (heidic.toml)
+// Looked up next to the source file and then in each parent directory, so one file can
+// cover a whole project tree.
+//
+//   [project]
+//   name = "bouncing_balls"
+//   version = "1.2.0"
+//
+//   [output]
+//   header = """
+//   {project} {version} - Copyright Example Studio
+//   Generated {timestamp} by heidic {compiler_version} from {source} (hash {source_hash})
+//   """
+//
+//   [workspace]                     # `heidic_v2 workspace <dir>` builds every member
+//   members = ["game/game.hd", "server/server.hd"]
+//   shared = ["shared/components.hd"]
+//
+//   [hot_reload]                    # builds the running program starts itself (compile --hot-rebuild)
+//   compiler = "clang++"            # g++ when not set
+//   flags = ["-O1", "-fPIC"]
+//
+//   [build]                         # `heidic_v2 build` with no arguments builds this program
+//   entry = "src/main.hd"           # paths are relative to heidic.toml
+//   sources = ["src/systems"]       # every .hd file below these is part of the program
+//   output = "build"                # generated code, hot DLLs and the executable (default: next to entry)
+//   target = "native"               # or "wasm" (--target)
+//   stdlib = "../eden/stdlib"       # the engine's stdlib/ directory (default: stdlib/ next to entry)
+//   cxx_flags = ["-g", "-DGAME_DEBUG"]
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const CONFIG_FILE_NAME: &str = "heidic.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub project: ProjectSection,
+    #[serde(default)]
+    pub output: OutputSection,
+    pub workspace: Option<WorkspaceSection>,
+    #[serde(default)]
+    pub hot_reload: HotReloadSection,
+    pub build: Option<BuildSection>,
+    #[serde(skip)]
+    pub path: Option<PathBuf>,  // Where the config was loaded from (for error messages)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectSection {
+    pub name: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSection {
+    pub header: Option<String>,  // Template for the comment block at the top of generated files
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceSection {
+    pub members: Vec<String>,  // Programs to build, relative to heidic.toml
+    #[serde(default)]
+    pub shared: Vec<String>,   // Component/struct modules included in every member
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HotReloadSection {
+    pub compiler: Option<String>,  // C++ compiler command for hot DLL builds
+    #[serde(default)]
+    pub flags: Vec<String>,        // Added to every hot DLL build
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildSection {
+    pub entry: String,             // The program's main file, relative to heidic.toml
+    #[serde(default)]
+    pub sources: Vec<String>,      // Directories whose .hd files are the rest of the program
+    pub output: Option<String>,    // Directory for generated code and build products
+    pub target: Option<String>,    // native or wasm, as --target
+    pub stdlib: Option<String>,    // The stdlib/ directory generated code includes
+    #[serde(default)]
+    pub cxx_flags: Vec<String>,    // Added to every compile of the generated code
+}
+
+// Placeholders accepted in [output] header
+const HEADER_PLACEHOLDERS: &[&str] = &["project", "version", "timestamp", "compiler_version", "source", "source_hash"];
+
+impl ProjectConfig {
+    /// Load the nearest heidic.toml for a source file (defaults when there is none)
+    pub fn for_source(file_path: &str) -> Result<Self> {
+        let source_dir = Path::new(file_path).parent().unwrap_or(Path::new("."));
+        let start = if source_dir.as_os_str().is_empty() { Path::new(".") } else { source_dir };
+        match Self::find(start) {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config: ProjectConfig = toml::from_str(&text)
+            .with_context(|| format!("Invalid {}", path.display()))?;
+        config.path = Some(path.to_path_buf());
+        config.validate_header()?;
+        config.validate_build()?;
+        Ok(config)
+    }
+
+    /// The heidic.toml in `dir` or its nearest parent directory that has one
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        let start = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        start.ancestors().map(|dir| dir.join(CONFIG_FILE_NAME)).find(|candidate| candidate.is_file())
+    }
+
+    fn validate_build(&self) -> Result<()> {
+        let Some(build) = &self.build else { return Ok(()) };
+        let config_path = self.path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+        if let Some(target) = &build.target {
+            if crate::codegen::Target::from_name(target).is_none() {
+                bail!("{}: unknown target '{}' in [build] (expected {})", config_path, target, crate::codegen::Target::NAMES.join(", "));
+            }
+        }
+        Ok(())
+    }
+
+    // Catch template typos when the config is loaded rather than emitting them verbatim
+    fn validate_header(&self) -> Result<()> {
+        let Some(template) = &self.output.header else { return Ok(()) };
+        let config_path = self.path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+        let mut rest = template.as_str();
+        while let Some(open) = rest.find('{') {
+            let after = &rest[open + 1..];
+            let Some(close) = after.find('}') else {
+                bail!("{}: unclosed '{{' in [output] header", config_path);
+            };
+            let name = &after[..close];
+            if !HEADER_PLACEHOLDERS.contains(&name) {
+                bail!("{}: unknown placeholder '{{{}}}' in [output] header (available: {})",
+                    config_path, name, HEADER_PLACEHOLDERS.iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join(", "));
+            }
+            rest = &after[close + 1..];
+        }
+        Ok(())
+    }
+
+    /// The configured header for a file generated from `source_file`, as `//` comment lines
+    /// (valid in C++ and GLSL alike); empty when no header is configured
+    pub fn render_header(&self, source_file: &str, source: &str, build_time: SystemTime) -> String {
+        let Some(template) = &self.output.header else { return String::new() };
+        let source_name = Path::new(source_file).file_name().and_then(|n| n.to_str()).unwrap_or(source_file);
+        let text = template
+            .replace("{project}", self.project.name.as_deref().unwrap_or(""))
+            .replace("{version}", self.project.version.as_deref().unwrap_or(""))
+            .replace("{timestamp}", &utc_timestamp(build_time))
+            .replace("{compiler_version}", env!("CARGO_PKG_VERSION"))
+            .replace("{source}", source_name)
+            .replace("{source_hash}", &format!("{:016x}", crate::codegen::fnv1a_64(source)));
+        let mut header = String::new();
+        for line in text.trim_end().lines() {
+            if line.trim().is_empty() {
+                header.push_str("//\n");
+            } else {
+                header.push_str(&format!("// {}\n", line.trim_end()));
+            }
+        }
+        header.push('\n');
+        header
+    }
+}
+
+/// Time stamped into generated files. Reproducible builds use SOURCE_DATE_EPOCH (the
+/// reproducible-builds.org convention) or, without it, the Unix epoch.
+pub fn build_time(reproducible: bool) -> SystemTime {
+    if !reproducible {
+        return SystemTime::now();
+    }
+    let epoch_secs = std::env::var("SOURCE_DATE_EPOCH").ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    UNIX_EPOCH + std::time::Duration::from_secs(epoch_secs)
+}
+
+// ISO 8601 UTC (YYYY-MM-DDTHH:MM:SSZ) without a date/time dependency
+fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, day_secs) = (secs / 86_400, secs % 86_400);
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, day_secs / 3_600, day_secs % 3_600 / 60, day_secs % 60)
+}
@@ -0,0 +1,222 @@
+// Compile-time evaluation of constant expressions: `const` initializers and static_assert conditions.
+// Integers are evaluated as i64 and floats as f64; the type checker has already checked the types,
+// so this only has to compute values and explain why an expression can't be computed.
+
+use std::fmt;
+
+use crate::ast::*;
+use crate::error::SourceLocation;
+use crate::formatter::binary_symbol;
+use crate::layout::LayoutEngine;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Error,  // Depends on a const that already failed; evaluates silently so errors don't cascade
+}
+
+impl fmt::Display for ConstValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstValue::Int(value) => write!(f, "{}", value),
+            ConstValue::Float(value) if value.fract() == 0.0 && value.is_finite() => write!(f, "{:.1}", value),
+            ConstValue::Float(value) => write!(f, "{}", value),
+            ConstValue::Bool(value) => write!(f, "{}", value),
+            ConstValue::Error => write!(f, "<error>"),
+        }
+    }
+}
+
+impl ConstValue {
+    fn as_f64(self) -> Option<f64> {
+        match self {
+            ConstValue::Int(value) => Some(value as f64),
+            ConstValue::Float(value) => Some(value),
+            ConstValue::Bool(_) | ConstValue::Error => None,
+        }
+    }
+
+    /// The value stored in a const of type `ty` (ints widen to floats; i32 must fit)
+    pub fn convert(self, ty: &Type) -> Result<ConstValue, String> {
+        match (ty, self) {
+            (Type::I32, ConstValue::Int(value)) if i32::try_from(value).is_err() => {
+                Err(format!("{} doesn't fit in an i32", value))
+            }
+            (Type::F32 | Type::F64, ConstValue::Int(value)) => Ok(ConstValue::Float(value as f64)),
+            _ => Ok(self),
+        }
+    }
+}
+
+/// Why an expression couldn't be evaluated, pointing at the part that isn't constant
+#[derive(Debug)]
+pub struct ConstError {
+    pub location: SourceLocation,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl ConstError {
+    fn new(location: SourceLocation, message: String, suggestion: Option<&str>) -> Self {
+        Self { location, message, suggestion: suggestion.map(str::to_string) }
+    }
+}
+
+/// Evaluate `expr`, resolving names with `constant` (the value of an already evaluated const)
+/// and size_of/align_of/offset_of with `layouts`
+pub fn evaluate(expr: &Expression, constant: &dyn Fn(&str) -> Option<ConstValue>, layouts: &LayoutEngine) -> Result<ConstValue, ConstError> {
+    let evaluate = |expr: &Expression| evaluate(expr, constant, layouts);
+    match expr {
+        Expression::Literal(Literal::Int(value, _), _) => Ok(ConstValue::Int(*value)),
+        Expression::Literal(Literal::Float(value, _), _) => Ok(ConstValue::Float(*value)),
+        Expression::Literal(Literal::Bool(value), _) => Ok(ConstValue::Bool(*value)),
+        Expression::Variable(name, location) => constant(name).ok_or_else(|| ConstError::new(
+            *location,
+            format!("'{}' isn't a constant", name),
+            Some("Only literals and other consts can be used here; declare it with `const` at the top level"),
+        )),
+        Expression::UnaryOp { op, expr: operand, location } => match (op, evaluate(operand)?) {
+            (_, ConstValue::Error) => Ok(ConstValue::Error),
+            (UnaryOp::Neg, ConstValue::Int(value)) => value.checked_neg().map(ConstValue::Int)
+                .ok_or_else(|| ConstError::new(*location, format!("-({}) overflows i64", value), None)),
+            (UnaryOp::Neg, ConstValue::Float(value)) => Ok(ConstValue::Float(-value)),
+            (UnaryOp::Not, ConstValue::Bool(value)) => Ok(ConstValue::Bool(!value)),
+            _ => Err(mismatch(*location)),
+        },
+        Expression::BinaryOp { op, left, right, location } => {
+            let left = evaluate(left)?;
+            // && and || short-circuit like they do at runtime
+            match (op, left) {
+                (BinaryOp::And, ConstValue::Bool(false)) => return Ok(left),
+                (BinaryOp::Or, ConstValue::Bool(true)) => return Ok(left),
+                _ => {}
+            }
+            binary(op, left, evaluate(right)?, *location)
+        }
+        Expression::Cast { expr: operand, ty, location } => match (ty, evaluate(operand)?) {
+            (_, ConstValue::Error) => Ok(ConstValue::Error),
+            (Type::I32, value) => cast_int(value, *location).and_then(|v| {
+                i32::try_from(v).map(|_| ConstValue::Int(v))
+                    .map_err(|_| ConstError::new(*location, format!("{} doesn't fit in an i32", v), None))
+            }),
+            (Type::I64, value) => cast_int(value, *location).map(ConstValue::Int),
+            (Type::F32 | Type::F64, value) => value.as_f64().map(ConstValue::Float).ok_or_else(|| mismatch(*location)),
+            _ => Err(not_constant(*location, "This cast")),
+        },
+        Expression::If { condition, then_branch, else_branch, location } => {
+            if !then_branch.statements.is_empty() || !else_branch.statements.is_empty() {
+                return Err(not_constant(*location, "An if with statements in its branches"));
+            }
+            match evaluate(condition)? {
+                ConstValue::Bool(true) => evaluate(&then_branch.value),
+                ConstValue::Bool(false) => evaluate(&else_branch.value),
+                ConstValue::Error => Ok(ConstValue::Error),
+                _ => Err(mismatch(*location)),
+            }
+        }
+        Expression::Block { body, location } => {
+            if !body.statements.is_empty() {
+                return Err(not_constant(*location, "A block with statements"));
+            }
+            evaluate(&body.value)
+        }
+        Expression::LayoutOf { query, ty, field, location } => {
+            let value = match (query, field) {
+                (LayoutQuery::Offset, Some(field)) => layouts.offset_of(ty, field),
+                (LayoutQuery::Align, _) => layouts.layout(ty).map(|layout| layout.align),
+                _ => layouts.layout(ty).map(|layout| layout.size),
+            };
+            value.map(|value| ConstValue::Int(value as i64)).map_err(|reason| ConstError::new(
+                *location,
+                format!("{}<{}>() can't be computed at compile time: {}", query.name(), crate::formatter::type_str(ty), reason),
+                Some("Layouts are known for value types and the structs and components made of them"),
+            ))
+        }
+        Expression::Literal(Literal::String(_), location) => Err(not_constant(*location, "A string")),
+        Expression::Call { name, location, .. } => Err(ConstError::new(
+            *location,
+            format!("Call to '{}' can't be evaluated at compile time", name),
+            Some("Functions run at runtime; compute the value with literals and other consts"),
+        )),
+        other => Err(not_constant(other.location(), "This expression")),
+    }
+}
+
+fn binary(op: &BinaryOp, left: ConstValue, right: ConstValue, location: SourceLocation) -> Result<ConstValue, ConstError> {
+    use ConstValue::*;
+    let overflow = || ConstError::new(location, format!("{} {} {} overflows i64", left, binary_symbol(op), right), None);
+    match (left, right) {
+        (Error, _) | (_, Error) => Ok(Error),
+        (Int(a), Int(b)) => match op {
+            BinaryOp::Add => a.checked_add(b).map(Int).ok_or_else(overflow),
+            BinaryOp::Sub => a.checked_sub(b).map(Int).ok_or_else(overflow),
+            BinaryOp::Mul => a.checked_mul(b).map(Int).ok_or_else(overflow),
+            BinaryOp::Div | BinaryOp::Mod if b == 0 => Err(ConstError::new(location, format!("{} {} 0 divides by zero", a, binary_symbol(op)), None)),
+            BinaryOp::Div => a.checked_div(b).map(Int).ok_or_else(overflow),
+            BinaryOp::Mod => a.checked_rem(b).map(Int).ok_or_else(overflow),
+            _ => compare(op, a.cmp(&b), location),
+        },
+        (Bool(a), Bool(b)) => match op {
+            BinaryOp::And => Ok(Bool(a && b)),
+            BinaryOp::Or => Ok(Bool(a || b)),
+            BinaryOp::Eq => Ok(Bool(a == b)),
+            BinaryOp::Ne => Ok(Bool(a != b)),
+            _ => Err(mismatch(location)),
+        },
+        (Bool(_), _) | (_, Bool(_)) => Err(mismatch(location)),
+        // Mixed int and float operands are computed as floats
+        _ => {
+            let (a, b) = (left.as_f64().unwrap(), right.as_f64().unwrap());
+            match op {
+                BinaryOp::Add => Ok(Float(a + b)),
+                BinaryOp::Sub => Ok(Float(a - b)),
+                BinaryOp::Mul => Ok(Float(a * b)),
+                BinaryOp::Div => Ok(Float(a / b)),
+                BinaryOp::Mod => Ok(Float(a % b)),
+                _ => match a.partial_cmp(&b) {
+                    Some(ordering) => compare(op, ordering, location),
+                    None => Ok(Bool(matches!(op, BinaryOp::Ne))),  // NaN compares unequal to everything
+                },
+            }
+        }
+    }
+}
+
+fn compare(op: &BinaryOp, ordering: std::cmp::Ordering, location: SourceLocation) -> Result<ConstValue, ConstError> {
+    let result = match op {
+        BinaryOp::Eq => ordering.is_eq(),
+        BinaryOp::Ne => ordering.is_ne(),
+        BinaryOp::Lt => ordering.is_lt(),
+        BinaryOp::Le => ordering.is_le(),
+        BinaryOp::Gt => ordering.is_gt(),
+        BinaryOp::Ge => ordering.is_ge(),
+        _ => return Err(mismatch(location)),
+    };
+    Ok(ConstValue::Bool(result))
+}
+
+// Float-to-integer casts truncate toward zero, like the static_cast they compile to
+fn cast_int(value: ConstValue, location: SourceLocation) -> Result<i64, ConstError> {
+    match value {
+        ConstValue::Int(value) => Ok(value),
+        ConstValue::Bool(value) => Ok(value as i64),
+        ConstValue::Float(value) if value.is_finite() && value.trunc().abs() < 9.2e18 => Ok(value.trunc() as i64),
+        ConstValue::Float(value) => Err(ConstError::new(location, format!("{} doesn't fit in an integer", value), None)),
+        ConstValue::Error => unreachable!("poisoned operands are passed through before casting"),
+    }
+}
+
+fn not_constant(location: SourceLocation, what: &str) -> ConstError {
+    ConstError::new(
+        location,
+        format!("{} can't be evaluated at compile time", what),
+        Some("Use literals, other consts, arithmetic, comparisons and `as` casts"),
+    )
+}
+
+// Operand types were already reported by the type checker
+fn mismatch(location: SourceLocation) -> ConstError {
+    ConstError::new(location, "Operand types don't match the operator".to_string(), None)
+}
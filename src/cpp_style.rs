@@ -0,0 +1,171 @@
+// Output styles for generated C++ (`--emit-readable` / `--emit-compact`).
+// Codegen assembles its output from string fragments with hand-maintained indentation, so
+// nesting drifts between fragments. These passes work on the finished text with a tiny C++
+// scanner that only tracks what matters for layout: brackets, comments and literals.
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EmitStyle {
+    #[default]
+    Default,   // As generated
+    Readable,  // Re-indented from bracket depth, section banners, constant branches folded (codegen)
+    Compact,   // Comments, indentation and line breaks removed where C++ allows
+}
+
+pub fn restyle(code: &str, style: EmitStyle) -> String {
+    match style {
+        EmitStyle::Default => code.to_string(),
+        EmitStyle::Readable => readable(code),
+        EmitStyle::Compact => compact(code),
+    }
+}
+
+fn readable(code: &str) -> String {
+    let mut out = String::new();
+    let mut scanner = Scanner::default();
+    let mut depth: usize = 0;  // Unclosed { ( [ from previous lines
+    let mut pending_blank = false;
+
+    for line in code.lines() {
+        let starts_in_comment = scanner.in_block_comment;
+        let (_, skeleton) = scanner.scan(line);
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            pending_blank = !out.is_empty();
+            continue;
+        }
+
+        let skeleton = skeleton.trim_start();
+        let leading_closers = skeleton.chars().take_while(|c| matches!(c, '}' | ')' | ']')).count();
+        // At most one blank line, never right after an opening or before a closing brace
+        if pending_blank && !out.ends_with("{\n") && leading_closers == 0 {
+            out.push('\n');
+        }
+        pending_blank = false;
+
+        if starts_in_comment {
+            // Inside a /* */ comment: keep the author's layout
+            out.push_str(line.trim_end());
+        } else if trimmed.starts_with('#') {
+            out.push_str(trimmed);
+        } else {
+            let mut level = depth.saturating_sub(leading_closers);
+            if matches!(trimmed, "public:" | "private:" | "protected:") {
+                level = level.saturating_sub(1);
+            }
+            out.push_str(&INDENT.repeat(level));
+            out.push_str(trimmed);
+        }
+        out.push('\n');
+
+        for c in skeleton.chars() {
+            match c {
+                '{' | '(' | '[' => depth += 1,
+                '}' | ')' | ']' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+fn compact(code: &str) -> String {
+    let mut out = String::new();
+    let mut scanner = Scanner::default();
+    for line in code.lines() {
+        let (code, _) = scanner.scan(line);
+        let code = code.trim();
+        if code.is_empty() {
+            continue;
+        }
+        if code.starts_with('#') {
+            // Preprocessor directives must stay on lines of their own
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(code);
+            out.push('\n');
+            continue;
+        }
+        let joins_tightly = |c: char| matches!(c, '{' | '}' | ';' | ',' | '(' | ')');
+        let previous = out.chars().last();
+        let next = code.chars().next();
+        if let (Some(previous), Some(next)) = (previous, next) {
+            if previous != '\n' && !joins_tightly(previous) && !joins_tightly(next) {
+                out.push(' ');
+            }
+        }
+        out.push_str(code);
+    }
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Default)]
+struct Scanner {
+    in_block_comment: bool,  // Carried across lines
+}
+
+impl Scanner {
+    // Split a line into its code with comments removed, and a skeleton of that code with
+    // string/char literal contents blanked out so brackets inside literals aren't counted
+    fn scan(&mut self, line: &str) -> (String, String) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut code = String::new();
+        let mut skeleton = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            if self.in_block_comment {
+                if c == '*' && next == Some('/') {
+                    self.in_block_comment = false;
+                    code.push(' ');
+                    skeleton.push(' ');
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+            match (c, next) {
+                ('/', Some('/')) => break,
+                ('/', Some('*')) => {
+                    self.in_block_comment = true;
+                    i += 2;
+                }
+                ('"', _) | ('\'', _) => {
+                    code.push(c);
+                    skeleton.push(c);
+                    i += 1;
+                    while i < chars.len() {
+                        let d = chars[i];
+                        code.push(d);
+                        i += 1;
+                        if d == '\\' {
+                            if let Some(&escaped) = chars.get(i) {
+                                code.push(escaped);
+                                i += 1;
+                            }
+                            skeleton.push_str("  ");
+                        } else if d == c {
+                            skeleton.push(d);
+                            break;
+                        } else {
+                            skeleton.push(' ');
+                        }
+                    }
+                }
+                _ => {
+                    code.push(c);
+                    skeleton.push(c);
+                    i += 1;
+                }
+            }
+        }
+        (code, skeleton)
+    }
+}
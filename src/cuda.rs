@@ -0,0 +1,129 @@
+// CUDA kernels: `@[launch(kernel = integrate)] fn update_physics(q: query<Position, Velocity>, dt: f32)`
+// compiles to `integrate_kernel`, whose `for entity in q` loop gives each GPU thread its own
+// entities, and a host function `update_physics_launch(q, dt)` that copies the component arrays of
+// q to the device, runs the kernel over q.size() entities and copies the arrays back. The device
+// buffers are kept between calls (stdlib/cuda_launch.h). With `async = true` the launch returns as
+// soon as the work is queued on the kernel's own stream, and `update_physics_wait()` finishes it.
+//
+// Kernels go into their own translation unit, <file>.cu, built by nvcc. The .cpp only sees the
+// extern "C" bridge functions defined there, which take the query's arrays as plain host pointers,
+// so it builds with the host C++ compiler and no CUDA headers.
+//
+// A program that calls `sync_to_gpu<Position>()` or `sync_to_cpu<Position>()` keeps its @[cuda]
+// components on the GPU instead: each has a device mirror in the .cu, filled with every entity's
+// values by sync_to_gpu and copied back into the entities by sync_to_cpu. Launches then copy only
+// the query's entity ids, and kernels read and write the mirrors in place.
+
+use crate::ast::*;
+
+/// One array of a kernel's query as both sides see it
+pub struct DeviceArray {
+    pub component: String,
+    pub field: Option<String>,  // component_soa field; None for a whole AoS component
+    pub element: Type,
+    pub buffer: String,  // HeidicCudaBuffer holding the device copy
+    pub member: String,  // Path of the array in the query, on the host and in the kernel's view
+    pub host: String,    // Bridge parameter the host array is passed as
+}
+
+/// The query a kernel runs over: its parameter and component types
+pub fn kernel_query(f: &FunctionDef) -> Option<(&Param, &[Type])> {
+    f.params.iter().find_map(|param| match &param.ty {
+        Type::Query(component_types) => Some((param, component_types.as_slice())),
+        _ => None,
+    })
+}
+
+/// Types a kernel parameter or a device array element can have
+pub fn is_device_type(ty: &Type) -> bool {
+    matches!(ty, Type::I32 | Type::I64 | Type::F32 | Type::F64 | Type::Bool)
+}
+
+/// The arrays copied to the device for `component` of query `param`: one per field of a
+/// component_soa, the whole component array otherwise
+pub fn device_arrays(param: &str, component: &ComponentDef) -> Vec<DeviceArray> {
+    let array = component_array_name(&component.name);
+    if !component.is_soa {
+        return vec![DeviceArray {
+            component: component.name.clone(),
+            field: None,
+            element: Type::Struct(component.name.clone()),
+            buffer: format!("d_{}_{}", param, array),
+            host: format!("{}_{}", param, array),
+            member: array,
+        }];
+    }
+    component.fields.iter().filter_map(|field| match &field.ty {
+        Type::Array(element) => Some(DeviceArray {
+            component: component.name.clone(),
+            field: Some(field.name.clone()),
+            element: (**element).clone(),
+            buffer: format!("d_{}_{}_{}", param, component.name.to_lowercase(), field.name),
+            host: format!("{}_{}_{}", param, component.name.to_lowercase(), field.name),
+            member: format!("{}.{}", array, field.name),
+        }),
+        _ => None,
+    }).collect()
+}
+
+/// The struct the kernel receives its query as: device pointers with the host query's layout names
+pub fn view_type(launch: &CudaLaunch, param: &str) -> String {
+    format!("HeidicCudaView_{}_{}", launch.kernel, param)
+}
+
+/// The HeidicCudaStream async launches are queued on
+pub fn stream_global(launch: &CudaLaunch) -> String {
+    format!("g_cuda_stream_{}", launch.kernel)
+}
+
+pub fn launch_function(f: &FunctionDef) -> String {
+    format!("{}_launch", f.name)
+}
+
+pub fn wait_function(f: &FunctionDef) -> String {
+    format!("{}_wait", f.name)
+}
+
+/// The extern "C" function of the .cu that runs a launch on host pointers
+pub fn bridge_launch(launch: &CudaLaunch) -> String {
+    format!("heidic_cuda_launch_{}", launch.kernel)
+}
+
+/// The extern "C" function of the .cu behind an async launch's wait
+pub fn bridge_wait(launch: &CudaLaunch) -> String {
+    format!("heidic_cuda_wait_{}", launch.kernel)
+}
+
+/// The host function behind sync_to_gpu<C>() / sync_to_cpu<C>()
+pub fn sync_function(direction: SyncDirection, component: &str) -> String {
+    format!("heidic_{}_{}", direction.name(), component)
+}
+
+/// The extern "C" function of the .cu that copies a component between its host staging arrays and
+/// its device mirror
+pub fn bridge_sync(direction: SyncDirection, component: &str) -> String {
+    format!("heidic_cuda_{}_{}", direction.name(), component)
+}
+
+/// The host staging arrays and device mirror of a GPU-resident component, one per field of a
+/// component_soa and one for a whole AoS component
+pub fn resident_arrays(component: &ComponentDef) -> Vec<DeviceArray> {
+    let lower = component.name.to_lowercase();
+    device_arrays("resident", component).into_iter().map(|array| {
+        let name = match &array.field {
+            Some(field) => format!("{}_{}", lower, field),
+            None => lower.clone(),
+        };
+        DeviceArray { buffer: format!("g_cuda_resident_{}", name), host: name, ..array }
+    }).collect()
+}
+
+/// The device buffer mapping an entity to its row in a resident component's mirror
+pub fn resident_rows(component: &ComponentDef) -> String {
+    format!("g_cuda_resident_{}_rows", component.name.to_lowercase())
+}
+
+/// The host-side HeidicCudaResidency of a resident component
+pub fn residency_global(component: &ComponentDef) -> String {
+    format!("g_cuda_residency_{}", component.name.to_lowercase())
+}
@@ -0,0 +1,357 @@
+// heidic_v2 doctor: checks that generated C++ will build on this machine. Looks for the C++
+// compiler, the Vulkan SDK and glslc, GLFW, the CUDA toolkit, SDL3 and FFmpeg, and the stdlib/
+// headers next to the program, and says how to fix what's missing or too old. Given a program,
+// only what its generated code uses is required; everything else is reported as a warning.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ast::{Item, Program};
+use crate::codegen::{STDLIB_VERSION_MAJOR, STDLIB_VERSION_MINOR};
+use crate::shaders::ShaderCompiler;
+use crate::stdlib_stubs;
+
+/// Oldest compilers with complete C++17 (<optional>, <variant>, if constexpr, std::void_t)
+const MIN_GCC: u32 = 8;
+const MIN_CLANG: u32 = 7;
+/// VK_API_VERSION_1_2: descriptor indexing (gpu_config) and timeline semaphores
+const MIN_VULKAN: (u32, u32) = (1, 2);
+/// glfwCreateWindowSurface and glfwGetRequiredInstanceExtensions
+const MIN_GLFW: (u32, u32) = (3, 2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+pub struct Check {
+    pub status: Status,
+    pub name: &'static str,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: String) -> Self {
+        Self { status: Status::Ok, name, detail, fix: None }
+    }
+
+    fn problem(status: Status, name: &'static str, detail: String, fix: String) -> Self {
+        Self { status, name, detail, fix: Some(fix) }
+    }
+}
+
+/// What a program's generated code needs beyond the C++ compiler
+#[derive(Debug, Default)]
+pub struct Needs {
+    pub stdlib_headers: Vec<String>,  // stdlib/ headers it includes
+    pub shaders: bool,                // shader/pipeline declarations: compiled with glslc
+    pub cuda: bool,                   // @[cuda] components or @[launch] functions
+    pub audio: bool,                  // Sound/Music resources play through SDL3
+    pub video: bool,                  // Video resources decode with FFmpeg
+}
+
+impl Needs {
+    pub fn of(program: &Program, generated: &[&str]) -> Self {
+        let mut stdlib_headers: Vec<String> = generated.iter()
+            .flat_map(|code| stdlib_stubs::includes(code, "stdlib/"))
+            .map(str::to_string)
+            .collect();
+        stdlib_headers.sort();
+        stdlib_headers.dedup();
+        let resource = |types: &[&str]| program.items.iter()
+            .any(|item| matches!(item, Item::Resource(r) if types.contains(&r.resource_type.as_str())));
+        Self {
+            stdlib_headers,
+            shaders: program.items.iter().any(|item| matches!(item, Item::Shader(_) | Item::Pipeline(_))),
+            cuda: program.items.iter().any(|item| match item {
+                Item::Component(c) => c.is_cuda,
+                Item::Function(f) => f.cuda_kernel.is_some(),
+                _ => false,
+            }),
+            audio: resource(&["Sound", "Music"]),
+            video: resource(&["Video"]),
+        }
+    }
+}
+
+/// Run every check for programs in `dir`. Without `needs` (no program given) only the compiler,
+/// stdlib/, Vulkan and GLFW are required.
+pub fn run(dir: &Path, needs: Option<&Needs>) -> Vec<Check> {
+    let stubbed = stdlib_is_stubbed(dir);
+    let optional = |needed: bool| if needed { Status::Error } else { Status::Warning };
+    let engine = if stubbed { Status::Warning } else { Status::Error };
+    vec![
+        check_compiler(),
+        check_stdlib(dir, needs),
+        check_vulkan(engine),
+        check_glslc(optional(needs.is_some_and(|n| n.shaders) && !stubbed)),
+        check_glfw(engine),
+        check_cuda(optional(needs.is_some_and(|n| n.cuda))),
+        check_sdl(needs.is_none_or(|n| n.audio)),
+        check_ffmpeg(optional(needs.is_some_and(|n| n.video))),
+    ]
+}
+
+/// Print the checks with their remediation steps; returns the number of errors
+pub fn report(checks: &[Check]) -> usize {
+    for check in checks {
+        let mark = match check.status {
+            Status::Ok => "✅",
+            Status::Warning => "⚠️ ",
+            Status::Error => "❌",
+        };
+        println!("{} {}: {}", mark, check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("     → {}", fix);
+        }
+    }
+    let count = |status| checks.iter().filter(|check| check.status == status).count();
+    let (errors, warnings) = (count(Status::Error), count(Status::Warning));
+    println!();
+    if errors == 0 && warnings == 0 {
+        println!("Everything generated code needs is installed.");
+    } else {
+        println!("{} error(s), {} warning(s)", errors, warnings);
+    }
+    errors
+}
+
+/// stdlib/ was written by --gen-stdlib-stubs, so the engine SDKs aren't used
+fn stdlib_is_stubbed(dir: &Path) -> bool {
+    fs::read_to_string(dir.join("stdlib").join("vulkan.h"))
+        .is_ok_and(|header| header.lines().next().is_some_and(|line| line.contains("--gen-stdlib-stubs")))
+}
+
+fn check_compiler() -> Check {
+    const NAME: &str = "C++ compiler";
+    let configured = env::var("CXX").ok().filter(|cxx| !cxx.is_empty());
+    let candidates = match &configured {
+        Some(cxx) => vec![cxx.clone()],
+        None => vec!["g++".to_string(), "clang++".to_string()],
+    };
+    for cxx in &candidates {
+        let Some(output) = command_output(cxx, &["--version"]) else {
+            continue;
+        };
+        let banner = output.lines().next().unwrap_or_default().trim().to_string();
+        let (family, minimum) = if banner.contains("clang") { ("clang", MIN_CLANG) } else { ("g++", MIN_GCC) };
+        return match first_version(&banner) {
+            Some((major, _)) if major >= minimum => Check::ok(NAME, format!("{} ({})", banner, cxx)),
+            Some((major, minor)) => Check::problem(Status::Error, NAME,
+                format!("{} {}.{} has incomplete C++17 support ({})", family, major, minor, cxx),
+                format!("Install {} {} or newer and put it on PATH (or set CXX to it)", family, minimum)),
+            None => Check::problem(Status::Warning, NAME,
+                format!("couldn't tell whether '{}' supports C++17 ({})", cxx, banner),
+                format!("Generated code needs -std=c++17: g++ {}+ or clang {}+", MIN_GCC, MIN_CLANG)),
+        };
+    }
+    match configured {
+        Some(cxx) => Check::problem(Status::Error, NAME, format!("CXX is set to '{}', which doesn't run", cxx),
+            "Point CXX at an installed g++ or clang++, or unset it to use g++ from PATH".to_string()),
+        None => Check::problem(Status::Error, NAME, "no g++ or clang++ on PATH".to_string(),
+            format!("Install g++ {}+ (MinGW-w64 on Windows) or clang {}+, or set CXX to your compiler", MIN_GCC, MIN_CLANG)),
+    }
+}
+
+fn check_stdlib(dir: &Path, needs: Option<&Needs>) -> Check {
+    const NAME: &str = "stdlib/ headers";
+    let stdlib_dir = dir.join("stdlib");
+    let copy_fix = format!("Copy the stdlib/ directory shipped with this compiler to {} (or pass --gen-stdlib-stubs to compile for a logic-only build)",
+                           dir.display());
+    let Ok(version_header) = fs::read_to_string(stdlib_dir.join("version.h")) else {
+        return Check::problem(Status::Error, NAME, format!("{} not found", stdlib_dir.join("version.h").display()), copy_fix);
+    };
+    let define = |name: &str| version_header.lines()
+        .find_map(|line| line.trim().strip_prefix("#define ")?.strip_prefix(name)?.trim().parse::<u32>().ok());
+    let required = format!("{}.{}", STDLIB_VERSION_MAJOR, STDLIB_VERSION_MINOR);
+    let (Some(major), Some(minor)) = (define("HEIDIC_STDLIB_VERSION_MAJOR"), define("HEIDIC_STDLIB_VERSION_MINOR")) else {
+        return Check::problem(Status::Error, NAME, "stdlib/version.h has no HEIDIC_STDLIB_VERSION_MAJOR/MINOR".to_string(), copy_fix);
+    };
+    if major != STDLIB_VERSION_MAJOR || minor < STDLIB_VERSION_MINOR {
+        return Check::problem(Status::Error, NAME,
+            format!("stdlib {}.{} in {}, this compiler generates code for stdlib {}", major, minor, stdlib_dir.display(), required),
+            format!("Replace {} with the stdlib/ shipped with this compiler", stdlib_dir.display()));
+    }
+    let missing: Vec<&str> = needs.map(|needs| needs.stdlib_headers.iter()
+            .map(String::as_str)
+            .filter(|header| !stdlib_dir.join(header).exists())
+            .collect())
+        .unwrap_or_default();
+    if !missing.is_empty() {
+        return Check::problem(Status::Error, NAME,
+            format!("stdlib {}.{}, missing stdlib/{}", major, minor, missing.join(", stdlib/")), copy_fix);
+    }
+    let stubbed = if stdlib_is_stubbed(dir) { ", stubbed by --gen-stdlib-stubs" } else { "" };
+    Check::ok(NAME, format!("stdlib {}.{} in {}{}", major, minor, stdlib_dir.display(), stubbed))
+}
+
+fn check_vulkan(missing: Status) -> Check {
+    const NAME: &str = "Vulkan SDK";
+    let sdk = env::var_os("VULKAN_SDK").map(PathBuf::from);
+    let mut include_dirs: Vec<PathBuf> = sdk.iter().flat_map(|sdk| [sdk.join("Include"), sdk.join("include")]).collect();
+    include_dirs.extend(system_include_dirs());
+    let fix = "Install the Vulkan SDK (https://vulkan.lunarg.com/sdk/home) and set VULKAN_SDK to its directory";
+    let Some(core) = include_dirs.iter().map(|dir| dir.join("vulkan").join("vulkan_core.h")).find(|path| path.exists()) else {
+        let detail = match &sdk {
+            Some(sdk) => format!("no vulkan/vulkan_core.h under VULKAN_SDK ({})", sdk.display()),
+            None => "VULKAN_SDK is not set and no Vulkan headers were found".to_string(),
+        };
+        return Check::problem(missing, NAME, detail, fix.to_string());
+    };
+    // #define VK_HEADER_VERSION_COMPLETE VK_MAKE_API_VERSION(0, 1, 3, VK_HEADER_VERSION), or
+    // VK_MAKE_VERSION(1, 2, VK_HEADER_VERSION) before the variant was added
+    let header = fs::read_to_string(&core).unwrap_or_default();
+    let version = header.lines()
+        .find_map(|line| line.trim().strip_prefix("#define VK_HEADER_VERSION_COMPLETE"))
+        .and_then(|definition| {
+            let numbers: Vec<u32> = definition.split(|c: char| !c.is_ascii_digit()).filter_map(|n| n.parse().ok()).collect();
+            let skip = usize::from(definition.contains("VK_MAKE_API_VERSION"));
+            Some((*numbers.get(skip)?, *numbers.get(skip + 1)?))
+        });
+    let Some((major, minor)) = version else {
+        return Check::problem(Status::Warning, NAME, format!("couldn't read the header version of {}", core.display()), fix.to_string());
+    };
+    if (major, minor) < MIN_VULKAN {
+        return Check::problem(missing, NAME, format!("Vulkan {}.{} headers in {}", major, minor, core.display()),
+            format!("Install Vulkan SDK {}.{} or newer and set VULKAN_SDK to it", MIN_VULKAN.0, MIN_VULKAN.1));
+    }
+    Check::ok(NAME, format!("Vulkan {}.{} headers ({})", major, minor, core.display()))
+}
+
+fn check_glslc(missing: Status) -> Check {
+    const NAME: &str = "glslc";
+    // glslangValidator builds the shaders too when glslc is missing
+    let Some(compiler) = ShaderCompiler::find() else {
+        return Check::problem(missing, NAME, "neither glslc nor glslangValidator found in VULKAN_SDK/bin or on PATH".to_string(),
+            "Install the Vulkan SDK (it ships glslc) or shaderc, and put glslc on PATH".to_string());
+    };
+    let version = command_output(&compiler.path.to_string_lossy(), &["--version"])
+        .and_then(|output| output.lines().next().map(|line| line.trim().to_string()))
+        .unwrap_or_else(|| "version unknown".to_string());
+    Check::ok(NAME, format!("{} ({})", version, compiler.path.display()))
+}
+
+fn check_glfw(missing: Status) -> Check {
+    const NAME: &str = "GLFW";
+    let mut roots: Vec<PathBuf> = env::var_os("GLFW_PATH").map(PathBuf::from).into_iter().collect();
+    if cfg!(windows) {
+        roots.extend([PathBuf::from("C:\\glfw-3.4"), PathBuf::from("C:\\glfw")]);
+    }
+    let header = roots.iter()
+        .flat_map(|root| [root.join("include"), root.clone()])
+        .chain(system_include_dirs())
+        .map(|dir| dir.join("GLFW").join("glfw3.h"))
+        .find(|path| path.exists());
+    let Some(header) = header else {
+        return Check::problem(missing, NAME, "GLFW/glfw3.h not found (GLFW_PATH, system include directories)".to_string(),
+            format!("Install GLFW {}.{}+ (https://www.glfw.org/download) and set GLFW_PATH to its directory", MIN_GLFW.0, MIN_GLFW.1));
+    };
+    let source = fs::read_to_string(&header).unwrap_or_default();
+    let define = |name: &str| source.lines()
+        .find_map(|line| line.trim().strip_prefix("#define ")?.strip_prefix(name)?.trim().parse::<u32>().ok());
+    match (define("GLFW_VERSION_MAJOR"), define("GLFW_VERSION_MINOR")) {
+        (Some(major), Some(minor)) if (major, minor) < MIN_GLFW => Check::problem(missing, NAME,
+            format!("GLFW {}.{} ({})", major, minor, header.display()),
+            format!("Install GLFW {}.{} or newer: Vulkan surfaces need glfwCreateWindowSurface", MIN_GLFW.0, MIN_GLFW.1)),
+        (Some(major), Some(minor)) => Check::ok(NAME, format!("GLFW {}.{} ({})", major, minor, header.display())),
+        _ => Check::ok(NAME, format!("version unknown ({})", header.display())),
+    }
+}
+
+fn check_cuda(missing: Status) -> Check {
+    const NAME: &str = "CUDA toolkit";
+    let Some(nvcc) = find_nvcc() else {
+        return Check::problem(missing, NAME, "nvcc not found in CUDA_PATH/bin or on PATH (needed for @[cuda] and @[launch])".to_string(),
+            "Install the CUDA toolkit (https://developer.nvidia.com/cuda-downloads) and set CUDA_PATH".to_string());
+    };
+    // "Cuda compilation tools, release 12.3, V12.3.103"
+    let release = command_output(&nvcc.to_string_lossy(), &["--version"])
+        .and_then(|output| output.lines().find_map(|line| Some(line.split("release ").nth(1)?.split(',').next()?.to_string())));
+    match release {
+        Some(release) => Check::ok(NAME, format!("CUDA {} ({})", release, nvcc.display())),
+        None => Check::ok(NAME, format!("version unknown ({})", nvcc.display())),
+    }
+}
+
+/// Audio resources compile without SDL3 (audio_resource.h stubs it out) but play no sound
+fn check_sdl(audio: bool) -> Check {
+    const NAME: &str = "SDL3";
+    let mut roots: Vec<PathBuf> = env::var_os("SDL3_PATH").map(PathBuf::from).into_iter().collect();
+    if cfg!(windows) {
+        roots.extend([PathBuf::from("C:\\SDL3"), PathBuf::from("C:\\Program Files\\SDL3")]);
+    }
+    let header = roots.iter()
+        .map(|root| root.join("include"))
+        .chain(system_include_dirs())
+        .map(|dir| dir.join("SDL3").join("SDL.h"))
+        .find(|path| path.exists());
+    match header {
+        Some(header) => Check::ok(NAME, header.display().to_string()),
+        None => Check::problem(Status::Warning, NAME,
+            if audio { "SDL3/SDL.h not found: Sound and Music resources will be silent" } else { "SDL3/SDL.h not found (only audio uses it)" }.to_string(),
+            "Install SDL3 (https://libsdl.org) and set SDL3_PATH to its directory".to_string()),
+    }
+}
+
+fn check_ffmpeg(missing: Status) -> Check {
+    const NAME: &str = "FFmpeg";
+    let mut roots: Vec<PathBuf> = env::var_os("FFMPEG_PATH").map(PathBuf::from).into_iter().collect();
+    if cfg!(windows) {
+        roots.extend([PathBuf::from("C:\\ffmpeg"), PathBuf::from("C:\\Program Files\\ffmpeg")]);
+    }
+    let header = roots.iter()
+        .map(|root| root.join("include"))
+        .chain(system_include_dirs())
+        .map(|dir| dir.join("libavcodec").join("avcodec.h"))
+        .find(|path| path.exists());
+    match header {
+        Some(header) => Check::ok(NAME, header.display().to_string()),
+        None => Check::problem(missing, NAME, "libavcodec/avcodec.h not found (needed for Video resources)".to_string(),
+            "Install the FFmpeg development libraries and set FFMPEG_PATH to their directory".to_string()),
+    }
+}
+
+fn system_include_dirs() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        return Vec::new();
+    }
+    ["/usr/include", "/usr/local/include", "/opt/homebrew/include"].into_iter().map(PathBuf::from).collect()
+}
+
+/// nvcc from CUDA_PATH/bin, or from PATH
+pub fn find_nvcc() -> Option<PathBuf> {
+    env::var_os("CUDA_PATH")
+        .map(|cuda| PathBuf::from(cuda).join("bin").join(format!("nvcc{}", env::consts::EXE_SUFFIX)))
+        .filter(|path| path.exists())
+        .or_else(|| find_on_path("nvcc"))
+}
+
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    let file = format!("{}{}", program, env::consts::EXE_SUFFIX);
+    env::split_paths(&env::var_os("PATH")?).map(|dir| dir.join(&file)).find(|path| path.is_file())
+}
+
+/// stdout (or stderr, where some tools print their banner) of a successful run
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    Some(String::from_utf8_lossy(&text).into_owned())
+}
+
+/// First "<major>.<minor>" word of a version banner ("g++ (GCC) 12.2.0", "clang version 17.0.6")
+fn first_version(banner: &str) -> Option<(u32, u32)> {
+    banner.split_whitespace().find_map(|word| {
+        let mut parts = word.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.chars().take_while(char::is_ascii_digit).collect::<String>().parse().ok()?;
+        Some((major, minor))
+    })
+}
@@ -2,20 +2,24 @@
 // Provides enhanced error messages with source location, context, and suggestions
 
 use std::fs;
+use std::sync::{Arc, Mutex, PoisonError};
+use serde::Serialize;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct SourceLocation {
     pub line: usize,      // 1-based line number
     pub column: usize,    // 1-based column number (character position in line)
+    #[serde(skip)]
+    pub file: usize,      // Index of the file in the ErrorReporter (0: the program's main file)
 }
 
 impl SourceLocation {
     pub fn new(line: usize, column: usize) -> Self {
-        Self { line, column }
+        Self { line, column, file: 0 }
     }
     
     pub fn unknown() -> Self {
-        Self { line: 0, column: 0 }
+        Self { line: 0, column: 0, file: 0 }
     }
     
     pub fn is_unknown(&self) -> bool {
@@ -23,59 +27,172 @@ impl SourceLocation {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,  // Reported but doesn't fail compilation (unless --deny-warnings)
+}
+
+/// A reported error or warning, kept for tools that present diagnostics themselves
+/// (the language server, `check --error-format=json`)
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub location: SourceLocation,
+    pub message: String,
+    pub suggestion: Option<String>,
+    pub code: Option<&'static str>,  // E0001.., W0001.. - see error_codes.rs / `heidic_v2 explain`
+}
+
+impl Diagnostic {
+    pub fn new(location: SourceLocation, message: String, suggestion: Option<String>) -> Self {
+        Self { location, message, suggestion, code: None }
+    }
+}
+
+/// A second place a report points at, like the earlier definition a duplicate collides with
+#[derive(Debug, Clone, Copy)]
+pub struct SecondaryLocation<'a> {
+    pub location: SourceLocation,
+    pub label: &'a str,  // "first declared here", ...
+}
+
+/// How a report is presented besides its location, message and suggestion
+#[derive(Debug, Clone, Copy)]
+pub struct ReportOptions<'a> {
+    pub severity: Severity,
+    pub code: Option<&'static str>,  // See error_codes.rs
+    pub secondary: Option<SecondaryLocation<'a>>,
+}
+
+impl<'a> ReportOptions<'a> {
+    pub fn error() -> Self {
+        Self { severity: Severity::Error, code: None, secondary: None }
+    }
+    
+    pub fn coded(code: &'static str) -> Self {
+        Self { code: Some(code), ..Self::error() }
+    }
+    
+    pub fn warning(code: &'static str) -> Self {
+        Self { severity: Severity::Warning, ..Self::coded(code) }
+    }
+    
+    /// Also show `location`, under `label`
+    pub fn secondary(self, location: SourceLocation, label: &'a str) -> Self {
+        Self { secondary: Some(SecondaryLocation { location, label }), ..self }
+    }
+}
+
+/// Prints diagnostics with their source lines. Clones share the files, so a reporter can be handed
+/// to each thread of a parallel parse; buffered() ones hold their reports until they're flushed.
 #[derive(Clone)]
 pub struct ErrorReporter {
-    file_path: String,
-    source_lines: Vec<String>,
+    files: Arc<Vec<(String, Vec<String>)>>,  // Path and lines of each file, indexed by SourceLocation::file
+    buffer: Option<Arc<Mutex<String>>>,      // Reports not yet printed (buffered())
 }
 
 impl ErrorReporter {
     pub fn new(file_path: &str) -> anyhow::Result<Self> {
         let source = fs::read_to_string(file_path)?;
+        Ok(Self::from_source(file_path, &source))
+    }
+    
+    /// A reporter for a file the caller has already read
+    pub fn from_source(file_path: &str, source: &str) -> Self {
         let source_lines: Vec<String> = source.lines().map(|s| s.to_string()).collect();
-        
-        Ok(Self {
-            file_path: file_path.to_string(),
-            source_lines,
-        })
+        Self {
+            files: Arc::new(vec![(file_path.to_string(), source_lines)]),
+            buffer: None,
+        }
+    }
+    
+    /// Add another source file of the program (a module of a heidic.toml build); its index is the
+    /// `file` of the locations its lexer produces
+    pub fn add_source(&mut self, file_path: &str, source: &str) -> usize {
+        let files = Arc::make_mut(&mut self.files);
+        files.push((file_path.to_string(), source.lines().map(|s| s.to_string()).collect()));
+        files.len() - 1
+    }
+    
+    /// A reporter (for the same files) whose reports, and its clones', are kept until take_buffered()
+    pub fn buffered(&self) -> Self {
+        Self { files: Arc::clone(&self.files), buffer: Some(Arc::new(Mutex::new(String::new()))) }
+    }
+    
+    /// The reports buffered so far, in the order they were made
+    pub fn take_buffered(&self) -> String {
+        match &self.buffer {
+            Some(buffer) => std::mem::take(&mut *buffer.lock().unwrap_or_else(PoisonError::into_inner)),
+            None => String::new(),
+        }
+    }
+    
+    // A whole report at a time, so reports made on different threads never interleave
+    fn emit(&self, report: &str) {
+        match &self.buffer {
+            Some(buffer) => buffer.lock().unwrap_or_else(PoisonError::into_inner).push_str(report),
+            None => eprint!("{}", report),
+        }
+    }
+    
+    fn file(&self, location: SourceLocation) -> Option<(&str, &[String])> {
+        self.files.get(location.file).map(|(path, lines)| (path.as_str(), lines.as_slice()))
     }
     
     pub fn report_error(&self, location: SourceLocation, message: &str, suggestion: Option<&str>) {
-        self.report_error_with_secondary(location, message, suggestion, None, None);
+        self.report(location, message, suggestion, ReportOptions::error());
+    }
+    
+    pub fn report_coded_error(&self, code: &'static str, location: SourceLocation, message: &str, suggestion: Option<&str>) {
+        self.report(location, message, suggestion, ReportOptions::coded(code));
     }
     
-    pub fn report_error_with_secondary(
-        &self, 
-        location: SourceLocation, 
-        message: &str, 
-        suggestion: Option<&str>,
-        secondary_location: Option<SourceLocation>,
-        secondary_label: Option<&str>,
-    ) {
-        if location.is_unknown() {
-            eprintln!("❌ Error: {}", message);
+    pub fn report(&self, location: SourceLocation, message: &str, suggestion: Option<&str>, options: ReportOptions) {
+        let ReportOptions { severity, code, secondary } = options;
+        let mut out = String::new();
+        macro_rules! emit {
+            ($($arg:tt)*) => {{
+                out.push_str(&format!($($arg)*));
+                out.push('\n');
+            }};
+        }
+        let mut label = match severity {
+            Severity::Error => "❌ Error",
+            Severity::Warning => "⚠️  Warning",
+        }.to_string();
+        if let Some(code) = code {
+            label.push_str(&format!("[{}]", code));
+        }
+        // A location in a module this reporter wasn't given is reported without its source line
+        let Some((file_path, source_lines)) = self.file(location).filter(|_| !location.is_unknown()) else {
+            emit!("{}: {}", label, message);
             if let Some(sug) = suggestion {
-                eprintln!("💡 Suggestion: {}", sug);
+                emit!("💡 Suggestion: {}", sug);
+            }
+            if let Some(code) = code {
+                emit!("📖 For more information, run: heidic_v2 explain {}", code);
             }
-            eprintln!();
+            emit!("");
+            self.emit(&out);
             return;
-        }
+        };
         
         // Print error header with emoji for better visibility
-        eprintln!("❌ Error at {}:{}:{}:", 
-                 self.file_path, location.line, location.column);
+        emit!("{} at {}:{}:{}:", 
+                 label, file_path, location.line, location.column);
         
         // Print source line with context (show previous and next lines if available)
-        if location.line > 0 && location.line <= self.source_lines.len() {
+        if location.line > 0 && location.line <= source_lines.len() {
             // Show previous line for context
             if location.line > 1 {
-                let prev_line = &self.source_lines[location.line - 2];
-                eprintln!("  {} | {}", location.line - 1, prev_line);
+                let prev_line = &source_lines[location.line - 2];
+                emit!("  {} | {}", location.line - 1, prev_line);
             }
             
             // Show current line with error
-            let line_content = &self.source_lines[location.line - 1];
-            eprintln!("  {} | {}", location.line, line_content);
+            let line_content = &source_lines[location.line - 1];
+            emit!("  {} | {}", location.line, line_content);
             
             // Print caret pointing to error location
             let spaces = if location.column > 0 {
@@ -104,30 +221,30 @@ impl ErrorReporter {
             
             let line_num_spaces = location.line.to_string().len() + 3; // "  X | "
             let caret = " ".repeat(line_num_spaces + spaces) + &"^".repeat(caret_width);
-            eprintln!("{}", caret);
+            emit!("{}", caret);
             
             // Show next line for context
-            if location.line < self.source_lines.len() {
-                let next_line = &self.source_lines[location.line];
-                eprintln!("  {} | {}", location.line + 1, next_line);
+            if location.line < source_lines.len() {
+                let next_line = &source_lines[location.line];
+                emit!("  {} | {}", location.line + 1, next_line);
             }
         }
         
         // Print secondary location if provided
-        if let Some(sec_loc) = secondary_location {
-            if !sec_loc.is_unknown() && sec_loc.line > 0 && sec_loc.line <= self.source_lines.len() {
-                let label = secondary_label.unwrap_or("Note: defined here");
-                eprintln!("\n📌 {} at {}:{}:{}:", 
-                         label, self.file_path, sec_loc.line, sec_loc.column);
+        if let Some(SecondaryLocation { location: sec_loc, label }) = secondary {
+            let (file_path, source_lines) = self.file(sec_loc).unwrap_or((file_path, &[]));
+            if !sec_loc.is_unknown() && sec_loc.line > 0 && sec_loc.line <= source_lines.len() {
+                emit!("\n📌 {} at {}:{}:{}:", 
+                         label, file_path, sec_loc.line, sec_loc.column);
                 
                 // Show context around secondary location
                 if sec_loc.line > 1 {
-                    let prev_line = &self.source_lines[sec_loc.line - 2];
-                    eprintln!("  {} | {}", sec_loc.line - 1, prev_line);
+                    let prev_line = &source_lines[sec_loc.line - 2];
+                    emit!("  {} | {}", sec_loc.line - 1, prev_line);
                 }
                 
-                let line_content = &self.source_lines[sec_loc.line - 1];
-                eprintln!("  {} | {}", sec_loc.line, line_content);
+                let line_content = &source_lines[sec_loc.line - 1];
+                emit!("  {} | {}", sec_loc.line, line_content);
                 
                 // Print caret for secondary location
                 let spaces = if sec_loc.column > 0 {
@@ -154,24 +271,28 @@ impl ErrorReporter {
                 
                 let line_num_spaces = sec_loc.line.to_string().len() + 3;
                 let caret = " ".repeat(line_num_spaces + spaces) + &"^".repeat(caret_width);
-                eprintln!("{}", caret);
+                emit!("{}", caret);
                 
-                if sec_loc.line < self.source_lines.len() {
-                    let next_line = &self.source_lines[sec_loc.line];
-                    eprintln!("  {} | {}", sec_loc.line + 1, next_line);
+                if sec_loc.line < source_lines.len() {
+                    let next_line = &source_lines[sec_loc.line];
+                    emit!("  {} | {}", sec_loc.line + 1, next_line);
                 }
             }
         }
         
         // Print error message
-        eprintln!("\n{}", message);
+        emit!("\n{}", message);
         
         // Print suggestion if provided
         if let Some(sug) = suggestion {
-            eprintln!("💡 Suggestion: {}", sug);
+            emit!("💡 Suggestion: {}", sug);
+        }
+        if let Some(code) = code {
+            emit!("📖 For more information, run: heidic_v2 explain {}", code);
         }
         
-        eprintln!(); // Blank line for readability
+        emit!(""); // Blank line for readability
+        self.emit(&out);
     }
 }
 
@@ -0,0 +1,57 @@
+// Diagnostic codes and their long-form explanations (`heidic_v2 explain E0008`)
+// Each explanation lives in src/error_codes/<code>.md and is embedded in the binary.
+// Codes are permanent: retire a code rather than reusing its number for a different rule.
+
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub title: &'static str,  // One line, shown by `explain --list`
+    pub explanation: &'static str,
+}
+
+pub const ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode { code: "E0001", title: "Undefined variable", explanation: include_str!("error_codes/E0001.md") },
+    ErrorCode { code: "E0002", title: "Undefined function", explanation: include_str!("error_codes/E0002.md") },
+    ErrorCode { code: "E0003", title: "Mismatched types", explanation: include_str!("error_codes/E0003.md") },
+    ErrorCode { code: "E0004", title: "Wrong number of arguments", explanation: include_str!("error_codes/E0004.md") },
+    ErrorCode { code: "E0005", title: "Argument type mismatch", explanation: include_str!("error_codes/E0005.md") },
+    ErrorCode { code: "E0006", title: "Return value doesn't match the function's return type", explanation: include_str!("error_codes/E0006.md") },
+    ErrorCode { code: "E0007", title: "Condition isn't a bool", explanation: include_str!("error_codes/E0007.md") },
+    ErrorCode { code: "E0008", title: "component_soa field isn't an array", explanation: include_str!("error_codes/E0008.md") },
+    ErrorCode { code: "E0009", title: "Frame-scoped allocation escapes its frame", explanation: include_str!("error_codes/E0009.md") },
+    ErrorCode { code: "E0010", title: "for loop over something that isn't a query", explanation: include_str!("error_codes/E0010.md") },
+    ErrorCode { code: "E0011", title: "Component accessed outside its query", explanation: include_str!("error_codes/E0011.md") },
+    ErrorCode { code: "E0012", title: "Interface used as a variable type", explanation: include_str!("error_codes/E0012.md") },
+    ErrorCode { code: "E0013", title: "Interface parameter in a @hot system", explanation: include_str!("error_codes/E0013.md") },
+    ErrorCode { code: "E0014", title: "impl doesn't match its interface", explanation: include_str!("error_codes/E0014.md") },
+    ErrorCode { code: "E0015", title: "Assignment to an immutable variable or parameter", explanation: include_str!("error_codes/E0015.md") },
+    ErrorCode { code: "E0016", title: "Invalid cast", explanation: include_str!("error_codes/E0016.md") },
+    ErrorCode { code: "E0017", title: "Static assertion failed", explanation: include_str!("error_codes/E0017.md") },
+    ErrorCode { code: "E0018", title: "Expression isn't a compile-time constant", explanation: include_str!("error_codes/E0018.md") },
+    ErrorCode { code: "E0019", title: "System schedule can't be satisfied", explanation: include_str!("error_codes/E0019.md") },
+    ErrorCode { code: "E0020", title: "Field type can't be exported", explanation: include_str!("error_codes/E0020.md") },
+    ErrorCode { code: "E0021", title: "Save schema change without a migration", explanation: include_str!("error_codes/E0021.md") },
+    ErrorCode { code: "E0022", title: "Scene file doesn't match the program's components", explanation: include_str!("error_codes/E0022.md") },
+    ErrorCode { code: "E0023", title: "Field type can't be replicated", explanation: include_str!("error_codes/E0023.md") },
+    ErrorCode { code: "E0024", title: "Invalid component migration", explanation: include_str!("error_codes/E0024.md") },
+    ErrorCode { code: "E0025", title: "Name collides with generated C++", explanation: include_str!("error_codes/E0025.md") },
+    ErrorCode { code: "E0026", title: "Shader doesn't compile", explanation: include_str!("error_codes/E0026.md") },
+    ErrorCode { code: "E0027", title: "Push constants too large", explanation: include_str!("error_codes/E0027.md") },
+    ErrorCode { code: "E0028", title: "Component pool too small", explanation: include_str!("error_codes/E0028.md") },
+    ErrorCode { code: "E0029", title: "No field with that name", explanation: include_str!("error_codes/E0029.md") },
+    ErrorCode { code: "E0030", title: "Field given more than once", explanation: include_str!("error_codes/E0030.md") },
+    ErrorCode { code: "E0031", title: "match used as a value doesn't handle every case", explanation: include_str!("error_codes/E0031.md") },
+    ErrorCode { code: "W0001", title: "Unused variable or parameter", explanation: include_str!("error_codes/W0001.md") },
+    ErrorCode { code: "W0002", title: "Unreachable code", explanation: include_str!("error_codes/W0002.md") },
+    ErrorCode { code: "W0003", title: "Unreachable match arm", explanation: include_str!("error_codes/W0003.md") },
+    ErrorCode { code: "W0004", title: "Component left out of saved worlds", explanation: include_str!("error_codes/W0004.md") },
+];
+
+/// Look up a code, accepting any case and a missing leading zero run ("e8" -> E0008)
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    let code = code.trim().to_ascii_uppercase();
+    let (prefix, number) = code.split_at(code.find(|c: char| c.is_ascii_digit())?);
+    let number: u32 = number.parse().ok()?;
+    let prefix = if prefix.is_empty() { "E" } else { prefix };
+    let normalized = format!("{}{:04}", prefix, number);
+    ERROR_CODES.iter().find(|entry| entry.code == normalized)
+}
@@ -0,0 +1,1168 @@
+// HEIDIC source formatter (`heidic_v2 fmt`): re-prints the parsed AST with canonical
+// indentation, spacing and attribute placement. The AST doesn't carry comments, so they come
+// from the lexer's trivia channel and are re-attached by source line; the token stream is used
+// to find the lines of closing braces and of members that have no location of their own.
+
+use anyhow::{bail, Result};
+
+use crate::ast::*;
+use crate::error::{ErrorReporter, SourceLocation};
+use crate::frame_arena;
+use crate::hierarchy;
+use crate::lexer::{Comment, Lexer, Token, TokenWithLocation};
+use crate::parser::Parser;
+
+const INDENT: &str = "    ";
+
+// Binding strength used to decide where parentheses are needed
+const PREC_CAST: u8 = 7;
+const PREC_UNARY: u8 = 8;
+const PREC_POSTFIX: u8 = 9;
+
+/// Format a HEIDIC source file. Parse errors go through `reporter` when one is given.
+pub fn format_source(source: &str, reporter: Option<ErrorReporter>) -> Result<String> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize()?;
+    let mut parser = Parser::new(tokens.clone());
+    if let Some(reporter) = reporter {
+        parser.set_error_reporter(reporter);
+    }
+    let program = parser.parse()?;
+
+    let mut formatter = Formatter::new(source, &tokens, lexer.comments());
+    let formatted = formatter.program(&program, parser.item_locations());
+
+    // Never hand back output that no longer parses
+    let reparsed = Lexer::new(&formatted).tokenize().and_then(|tokens| Parser::new(tokens).parse());
+    if reparsed.is_err() {
+        bail!("Formatting produced output that doesn't parse; the file was left unchanged");
+    }
+    Ok(formatted)
+}
+
+struct Formatter<'a> {
+    source_lines: Vec<&'a str>,
+    tokens: &'a [TokenWithLocation],
+    comments: &'a [Comment],
+    next_comment: usize,
+    out: String,
+    indent: usize,
+    last_line: usize,  // Last source line reproduced (for blank-line preservation)
+    block_start: bool,  // A block was just opened: no blank line before its first member
+    force_blank: bool,  // Separate the next line from the previous one (between items)
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str, tokens: &'a [TokenWithLocation], comments: &'a [Comment]) -> Self {
+        Self {
+            source_lines: source.lines().collect(),
+            tokens,
+            comments,
+            next_comment: 0,
+            out: String::new(),
+            indent: 0,
+            last_line: 0,
+            block_start: false,
+            force_blank: false,
+        }
+    }
+
+    fn program(&mut self, program: &Program, item_locations: &[SourceLocation]) -> String {
+        let mut previous_one_liner = false;
+        for (i, item) in program.items.iter().enumerate() {
+            let location = item_locations.get(i).copied().unwrap_or_else(SourceLocation::unknown);
+            // Runs of one-line declarations (externs, shaders, ...) stay grouped as written
+            let one_liner = matches!(item, Item::ExternFunction(_) | Item::Resource(_) | Item::GpuQuery(_)
+                | Item::Shader(ShaderDef { inline: None, .. })
+                | Item::Const(_) | Item::StaticAssert(_) | Item::Scene(_));
+            self.force_blank = i > 0 && !(one_liner && previous_one_liner);
+            previous_one_liner = one_liner;
+            self.start(location.line);
+            self.item(item, location);
+        }
+        self.flush_comments_before(usize::MAX);
+        std::mem::take(&mut self.out)
+    }
+
+    // ---- Line emission -------------------------------------------------------------------
+
+    // Prepare to emit content from source `line`: leading comments first, then spacing
+    fn start(&mut self, line: usize) {
+        self.flush_comments_before(line);
+        self.separate(line);
+    }
+
+    // Keep one blank line where the source had one (or more), and between items
+    fn separate(&mut self, line: usize) {
+        let gap = self.last_line > 0 && line > self.last_line + 1;
+        if !self.out.is_empty() && !self.block_start && (self.force_blank || gap) {
+            self.out.push('\n');
+        }
+        self.force_blank = false;
+        self.block_start = false;
+    }
+
+    fn flush_comments_before(&mut self, line: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.location.line >= line {
+                break;
+            }
+            self.next_comment += 1;
+            self.separate(comment.location.line);
+            self.out.push_str(&INDENT.repeat(self.indent));
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+            self.last_line = comment.location.line;
+        }
+    }
+
+    // Write one (possibly multi-line) piece of output that starts on source `line`,
+    // keeping a comment that trailed that line in the source
+    fn write_line(&mut self, text: &str, line: usize) {
+        self.out.push_str(&INDENT.repeat(self.indent));
+        self.out.push_str(text);
+        if let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.trailing && comment.location.line == line {
+                self.out.push_str("  ");
+                self.out.push_str(&comment.text);
+                self.next_comment += 1;
+            }
+        }
+        self.out.push('\n');
+        self.last_line = self.last_line.max(line);
+    }
+
+    fn open(&mut self, header: &str, line: usize) {
+        self.write_line(header, line);
+        self.indent += 1;
+        self.block_start = true;
+    }
+
+    fn close(&mut self, suffix: &str, close: Option<SourceLocation>) {
+        if let Some(close) = close {
+            self.flush_comments_before(close.line);
+        }
+        self.indent -= 1;
+        let line = close.map(|c| c.line).unwrap_or(self.last_line);
+        if self.block_start && self.out.ends_with("{\n") {
+            // Empty block stays on the header line: `{}`
+            self.out.pop();
+            self.out.push('}');
+            self.out.push_str(suffix);
+            self.out.push('\n');
+            self.last_line = self.last_line.max(line);
+        } else {
+            self.write_line(&format!("}}{}", suffix), line);
+        }
+        self.block_start = false;
+    }
+
+    // ---- Token-stream lookups ------------------------------------------------------------
+
+    fn first_brace_at_or_after(&self, from: SourceLocation) -> Option<usize> {
+        self.tokens.iter().position(|t| {
+            (t.location.line, t.location.column) >= (from.line, from.column) && t.token == Token::LBrace
+        })
+    }
+
+    // Location of the `}` matching the first `{` at or after `from`
+    fn closing_brace_after(&self, from: SourceLocation) -> Option<SourceLocation> {
+        let start = self.first_brace_at_or_after(from)?;
+        let mut depth = 0;
+        for token in &self.tokens[start..] {
+            match token.token {
+                Token::LBrace => depth += 1,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(token.location);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    // Location of the first `}` at or after `from` that closes a block opened before it
+    fn enclosing_close_brace(&self, from: SourceLocation) -> Option<SourceLocation> {
+        let mut depth = 0;
+        for token in self.tokens {
+            if (token.location.line, token.location.column) < (from.line, from.column) {
+                continue;
+            }
+            match token.token {
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth == 0 => return Some(token.location),
+                Token::RBrace => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    // Token indices directly inside the first `{ ... }` at or after `from` that satisfy `is_member`
+    fn members(&self, from: SourceLocation, is_member: impl Fn(usize) -> bool) -> Vec<usize> {
+        let mut found = Vec::new();
+        let Some(start) = self.first_brace_at_or_after(from) else { return found };
+        let mut depth = 0;
+        for i in start..self.tokens.len() {
+            match self.tokens[i].token {
+                Token::LBrace => depth += 1,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ if depth == 1 && is_member(i) => found.push(i),
+                _ => {}
+            }
+        }
+        found
+    }
+
+    // `name:` at the start of a field or option (after `{`, `,` or `;`)
+    fn is_key_start(&self, i: usize) -> bool {
+        matches!(self.tokens[i].token, Token::Ident(_))
+            && matches!(self.tokens.get(i + 1).map(|t| &t.token), Some(Token::Colon))
+            && (matches!(self.tokens[i - 1].token, Token::LBrace | Token::Comma | Token::Semicolon)
+                || (matches!(&self.tokens[i - 1].token, Token::Ident(attr) if attr == "cold") && matches!(self.tokens[i - 2].token, Token::At))
+                || matches!(self.tokens[i - 1].token, Token::RBracket))
+    }
+
+    // The first token of a field's `@[...]` / `@cold` attributes, or the field name without any
+    fn attributes_start(&self, mut i: usize) -> usize {
+        loop {
+            match &self.tokens[i - 1].token {
+                Token::Ident(attr) if attr == "cold" && matches!(self.tokens[i - 2].token, Token::At) => i -= 2,
+                Token::RBracket => match (1..i).rev().find(|&j| matches!(self.tokens[j].token, Token::LBracket)) {
+                    Some(j) if matches!(self.tokens[j - 1].token, Token::At) => i = j - 1,
+                    _ => return i,
+                },
+                _ => return i,
+            }
+        }
+    }
+
+    fn member_line(&self, members: &[usize], index: usize) -> usize {
+        members.get(index).map(|&i| self.tokens[i].location.line).unwrap_or(self.last_line)
+    }
+
+    // Header line for open(); 0 when the first member shares it, so that member keeps its comment
+    fn header_line(location: SourceLocation, first_member: Option<usize>) -> usize {
+        if first_member == Some(location.line) { 0 } else { location.line }
+    }
+
+    // ---- Items ---------------------------------------------------------------------------
+
+    fn item(&mut self, item: &Item, location: SourceLocation) {
+        let line = location.line;
+        match item {
+            Item::Struct(s) => {
+                if s.is_export {
+                    self.write_line("@[export]", line);
+                }
+                self.fields_block(&format!("struct {} {{", s.name), location, &s.fields);
+            }
+            Item::Component(c) => {
+                if c.is_export {
+                    self.write_line("@[export]", line);
+                }
+                if c.is_cuda {
+                    self.write_line("@[cuda]", line);
+                }
+                if c.is_replicated {
+                    self.write_line("@[replicated]", line);
+                }
+                if let Some(capacity) = c.pool_capacity {
+                    self.write_line(&format!("@[pool(capacity = {})]", capacity), line);
+                }
+                let keyword = if c.is_soa { "component_soa" } else { "component" };
+                let header = format!("{}{} {} {{", hot_prefix(c.is_hot), keyword, c.name);
+                self.fields_block(&header, location, &c.fields);
+            }
+            Item::System(s) => {
+                if s.strict_fp {
+                    self.write_line("@strict_fp", line);
+                }
+                if let Some(schedule) = &s.schedule {
+                    if let Some(stage) = &schedule.stage {
+                        self.write_line(&format!("@[stage({})]", stage), line);
+                    }
+                    if !schedule.before.is_empty() {
+                        self.write_line(&format!("@[before({})]", schedule.before.join(", ")), line);
+                    }
+                    if !schedule.after.is_empty() {
+                        self.write_line(&format!("@[after({})]", schedule.after.join(", ")), line);
+                    }
+                }
+                // Functions and `on Event(ev)` handlers, in source order
+                let members = self.members(location, |i| match &self.tokens[i].token {
+                    Token::Fn => true,
+                    Token::Ident(name) => name == "on",
+                    _ => false,
+                });
+                let first_line = members.first().map(|&t| self.tokens[t].location.line);
+                self.open(&format!("{}system {} {{", hot_prefix(s.is_hot), s.name), Self::header_line(location, first_line));
+                let (mut functions, mut handlers) = (s.functions.iter(), s.handlers.iter());
+                for (i, &t) in members.iter().enumerate() {
+                    let member_location = self.tokens[t].location;
+                    self.force_blank = i > 0;
+                    if self.tokens[t].token == Token::Fn {
+                        let Some(f) = functions.next() else { continue };
+                        self.start(member_location.line);
+                        self.function(f, member_location);
+                    } else if let Some(handler) = handlers.next() {
+                        self.start(member_location.line);
+                        self.open(&format!("on {}({}) {{", handler.event, handler.param), member_location.line);
+                        self.statements(&handler.body);
+                        self.close("", self.closing_brace_after(member_location));
+                    }
+                }
+                self.close("", self.closing_brace_after(location));
+            }
+            Item::Shader(sh) => match &sh.inline {
+                Some(inline) => {
+                    self.open(&format!("{}shader {} {} {{", hot_prefix(sh.is_hot), stage_name(&sh.stage), inline.name), line);
+                    // The GLSL is kept byte for byte, including its own indentation
+                    self.start(inline.location.line);
+                    let end_line = inline.location.line + inline.glsl.matches('\n').count();
+                    self.write_line(&format!("glsl {{{}}}", inline.glsl), end_line);
+                    self.close("", self.closing_brace_after(location));
+                }
+                None => {
+                    let text = format!("{}shader {} \"{}\"", hot_prefix(sh.is_hot), stage_name(&sh.stage), sh.path);
+                    self.write_line(&text, line);
+                }
+            },
+            Item::Function(f) => {
+                if let Some(launch) = &f.cuda_kernel {
+                    let flags = if launch.is_async { ", async = true" } else { "" };
+                    self.write_line(&format!("@[launch(kernel = {}{})]", launch.kernel, flags), line);
+                }
+                if f.no_reload_check {
+                    self.write_line("@[no_reload_check]", line);
+                }
+                if f.strict_fp {
+                    self.write_line("@strict_fp", line);
+                }
+                self.function(f, location);
+            }
+            Item::Migrate(migrate) => {
+                let f = &migrate.function;
+                let header = format!("migrate {}({}): {} {{", f.name, self.params(&f.params), type_str(&f.return_type));
+                self.open(&header, location.line);
+                self.statements(&f.body);
+                self.close("", self.closing_brace_after(location));
+            }
+            Item::ExternFunction(ext) => {
+                let mut text = format!("extern fn {}({}): {}", ext.name, self.params(&ext.params), type_str(&ext.return_type));
+                if let Some(library) = &ext.library {
+                    text.push_str(&format!(" from \"{}\"", library));
+                }
+                text.push(';');
+                self.write_line(&text, line);
+            }
+            Item::Resource(res) => {
+                if res.is_spatial {
+                    self.write_line("@[spatial]", line);
+                }
+                let options = res.texture_options.map(|options| format!(" {}", texture_options(&options))).unwrap_or_default();
+                let text = format!("{}resource {}: {} = \"{}\"{};", hot_prefix(res.is_hot), res.name, res.resource_type, res.path, options);
+                self.write_line(&text, line);
+            }
+            Item::Pipeline(p) => self.pipeline(p, location),
+            Item::Gpu(gpu) => self.gpu_config(gpu, location),
+            Item::Window(window) => self.window_config(window, location),
+            Item::Interface(iface) => {
+                let first_line = iface.methods.first().map(|m| m.location.line);
+                self.open(&format!("interface {} {{", iface.name), Self::header_line(location, first_line));
+                for method in &iface.methods {
+                    self.start(method.location.line);
+                    let text = format!("fn {}({}): {};", method.name, self.params(&method.params), type_str(&method.return_type));
+                    self.write_line(&text, method.location.line);
+                }
+                self.close("", self.closing_brace_after(location));
+            }
+            Item::Impl(imp) => {
+                let first_line = imp.methods.first().map(|(_, l)| l.line);
+                self.open(&format!("impl {} for {} {{", imp.interface, imp.target), Self::header_line(location, first_line));
+                for (i, (method, method_location)) in imp.methods.iter().enumerate() {
+                    self.force_blank = i > 0;
+                    self.start(method_location.line);
+                    self.function(method, *method_location);
+                }
+                self.close("", self.closing_brace_after(location));
+            }
+            Item::GpuQuery(query) => {
+                let kind = match &query.kind {
+                    GpuQueryKind::Occlusion => "Occlusion".to_string(),
+                    GpuQueryKind::Timestamp => "Timestamp".to_string(),
+                    GpuQueryKind::PipelineStatistics(statistic, _) => format!("PipelineStatistics({})", statistic),
+                };
+                let mut text = format!("gpu_query {}", kind);
+                if kind.split('(').next() != Some(query.name.as_str()) {
+                    text.push_str(&format!(" {}", query.name));
+                }
+                if query.count != 64 {
+                    text.push_str(&format!("[{}]", query.count));
+                }
+                text.push(';');
+                self.write_line(&text, line);
+            }
+            Item::Event(event) => self.fields_block(&format!("event {} {{", event.name), location, &event.fields),
+            Item::Uniform(uniform) => self.fields_block(&format!("uniform {} {{", uniform.name), location, &uniform.fields),
+            Item::Input(input) => self.input_block(input, location),
+            Item::SaveSchema(schema) => self.save_schema(schema, location),
+            Item::Scene(scene) => self.write_line(&format!("scene \"{}\";", scene.path), line),
+            Item::Test(test) => {
+                self.open(&format!("test \"{}\" {{", test.name), line);
+                self.statements(&test.body);
+                self.close("", self.closing_brace_after(location));
+            }
+            Item::Const(c) => {
+                let ty = c.ty.as_ref().map(|ty| format!(": {}", type_str(ty))).unwrap_or_default();
+                let tweak = if c.is_tweak { "@tweak " } else { "" };
+                let text = format!("{}const {}{} = {};", tweak, c.name, ty, self.expr(&c.value));
+                self.write_line(&text, line);
+            }
+            Item::StaticAssert(assert) => {
+                let mut text = format!("static_assert({}", self.expr(&assert.condition));
+                if let Some(message) = &assert.message {
+                    text.push_str(&format!(", \"{}\"", message));
+                }
+                text.push_str(");");
+                self.write_line(&text, line);
+            }
+        }
+    }
+
+    fn fields_block(&mut self, header: &str, location: SourceLocation, fields: &[Field]) {
+        let members = self.members(location, |i| self.is_key_start(i));
+        let starts: Vec<usize> = members.iter().map(|&i| self.attributes_start(i)).collect();
+        let first_line = starts.first().map(|&i| self.tokens[i].location.line);
+        self.open(header, Self::header_line(location, first_line));
+        for (i, field) in fields.iter().enumerate() {
+            self.start(self.member_line(&starts, i));
+            let line = self.member_line(&members, i);
+            self.write_line(&format!("{}{}: {},", field_attributes(field), field.name, type_str(&field.ty)), line);
+        }
+        self.close("", self.closing_brace_after(location));
+    }
+
+    fn input_block(&mut self, input: &InputDef, location: SourceLocation) {
+        let members = self.members(location, |i| self.is_key_start(i));
+        self.open("input {", Self::header_line(location, members.first().map(|&i| self.tokens[i].location.line)));
+        for (i, action) in input.actions.iter().enumerate() {
+            let line = self.member_line(&members, i);
+            self.start(line);
+            let bindings: Vec<String> = action.bindings.iter().map(|binding| binding.to_string()).collect();
+            self.write_line(&format!("{}: {};", action.name, bindings.join(" | ")), line);
+        }
+        self.close("", self.closing_brace_after(location));
+    }
+
+    // Fields, then the `from vN(old, new) { ... }` migration block
+    fn save_schema(&mut self, schema: &SaveSchemaDef, location: SourceLocation) {
+        let name = if schema.name == "SaveData" { String::new() } else { format!("{} ", schema.name) };
+        let header = format!("save_schema {}v{} {{", name, schema.version);
+        let Some(migration) = &schema.migration else {
+            self.fields_block(&header, location, &schema.fields);
+            return;
+        };
+        let members = self.members(location, |i| self.is_key_start(i));
+        let first_line = members.first().map(|&i| self.tokens[i].location.line).unwrap_or(migration.location.line);
+        self.open(&header, Self::header_line(location, Some(first_line)));
+        for (i, field) in schema.fields.iter().enumerate() {
+            let line = self.member_line(&members, i);
+            self.start(line);
+            self.write_line(&format!("{}: {},", field.name, type_str(&field.ty)), line);
+        }
+        self.force_blank = !schema.fields.is_empty();
+        self.start(migration.location.line);
+        self.open(&format!("from v{}({}, {}) {{", migration.from, migration.old, migration.new), migration.location.line);
+        self.statements(&migration.body);
+        self.close("", self.closing_brace_after(migration.location));
+        self.close("", self.closing_brace_after(location));
+    }
+
+    fn function(&mut self, f: &FunctionDef, location: SourceLocation) {
+        let header = format!("{}fn {}({}): {} {{", hot_prefix(f.is_hot), f.name, self.params(&f.params), type_str(&f.return_type));
+        self.open(&header, location.line);
+        self.statements(&f.body);
+        self.close("", self.closing_brace_after(location));
+    }
+
+    fn params(&mut self, params: &[Param]) -> String {
+        let mut rendered = Vec::new();
+        for param in params {
+            if param.name == "self" {
+                rendered.push("self".to_string());
+                continue;
+            }
+            let mutable = if param.mutable { "mut " } else { "" };
+            let mut text = format!("{}{}: {}", mutable, param.name, type_str(&param.ty));
+            if let Some(default) = &param.default {
+                text.push_str(&format!(" = {}", self.expr(default)));
+            }
+            rendered.push(text);
+        }
+        rendered.join(", ")
+    }
+
+    fn pipeline(&mut self, pipeline: &PipelineDef, location: SourceLocation) {
+        let members = self.members(location, |i| matches!(self.tokens[i].token, Token::Shader | Token::Layout));
+        let shader_lines: Vec<usize> = members.iter().copied().filter(|&i| self.tokens[i].token == Token::Shader).collect();
+        let layout_token = members.iter().copied().find(|&i| self.tokens[i].token == Token::Layout);
+
+        self.open(&format!("pipeline {} {{", pipeline.name), location.line);
+        let mut clauses: Vec<(usize, String)> = pipeline.shaders.iter().enumerate().map(|(i, shader)| {
+            let source = match &shader.inline {
+                Some(name) => name.clone(),
+                None => format!("\"{}\"", shader.path),
+            };
+            (self.member_line(&shader_lines, i), format!("shader {} {}", stage_name(&shader.stage), source))
+        }).collect();
+        if let Some(input) = &pipeline.vertex_input {
+            clauses.push((input.location.line, format!("vertex_input {}", input.struct_name)));
+        }
+        // State keys; defaults are left out like gpu { } options
+        let keys = self.members(location, |i| {
+            matches!(self.tokens[i].token, Token::Ident(_)) && matches!(self.tokens.get(i + 1).map(|t| &t.token), Some(Token::Colon))
+        });
+        let key_line = |name: &str| {
+            keys.iter()
+                .find(|&&i| matches!(&self.tokens[i].token, Token::Ident(key) if key == name))
+                .map(|&i| self.tokens[i].location.line)
+                .unwrap_or(self.last_line)
+        };
+        let state = &pipeline.state;
+        let defaults = PipelineState::default();
+        let mut options = Vec::new();
+        if state.blend != defaults.blend {
+            options.push(("blend", match state.blend {
+                BlendMode::Opaque => "opaque",
+                BlendMode::Alpha => "alpha",
+                BlendMode::Additive => "additive",
+            }));
+        }
+        if state.cull != defaults.cull {
+            options.push(("cull", match state.cull {
+                CullMode::Back => "back",
+                CullMode::Front => "front",
+                CullMode::None => "none",
+            }));
+        }
+        if state.topology != defaults.topology {
+            options.push(("topology", match state.topology {
+                Topology::Triangles => "triangles",
+                Topology::TriangleStrip => "triangle_strip",
+                Topology::Lines => "lines",
+                Topology::LineStrip => "line_strip",
+                Topology::Points => "points",
+            }));
+        }
+        if state.depth_test != defaults.depth_test {
+            options.push(("depth_test", if state.depth_test { "on" } else { "off" }));
+        }
+        if state.polygon != defaults.polygon {
+            options.push(("polygon", match state.polygon {
+                PolygonMode::Fill => "fill",
+                PolygonMode::Wireframe => "wireframe",
+                PolygonMode::Points => "points",
+            }));
+        }
+        for (key, value) in options {
+            clauses.push((key_line(key), format!("{}: {};", key, value)));
+        }
+        clauses.sort_by_key(|(line, _)| *line);  // Source order
+        for (line, text) in clauses {
+            self.start(line);
+            self.write_line(&text, line);
+        }
+        if let Some(layout) = &pipeline.layout {
+            let layout_location = layout_token.map(|i| self.tokens[i].location).unwrap_or(location);
+            self.start(layout_location.line);
+            self.open("layout {", layout_location.line);
+            let mut push_constants = layout.push_constants.as_ref();
+            for binding in &layout.bindings {
+                if let Some(block) = push_constants.filter(|block| block.location.line < binding.location.line) {
+                    self.start(block.location.line);
+                    self.fields_block("push_constants {", block.location, &block.fields);
+                    push_constants = None;
+                }
+                let mut text = format!("binding {}: ", binding.binding);
+                match &binding.binding_type {
+                    BindingType::Uniform(ty) => text.push_str(&format!("uniform {}", ty)),
+                    BindingType::Storage(ty) => text.push_str(&format!("storage {}[]", ty)),
+                    BindingType::Sampler2D => text.push_str("sampler2D"),
+                }
+                if !binding.name.is_empty() {
+                    text.push_str(&format!(" {}", binding.name));
+                }
+                self.start(binding.location.line);
+                self.write_line(&text, binding.location.line);
+            }
+            if let Some(block) = push_constants {
+                self.start(block.location.line);
+                self.fields_block("push_constants {", block.location, &block.fields);
+            }
+            self.close("", self.closing_brace_after(layout_location));
+        }
+        self.close("", self.closing_brace_after(location));
+    }
+
+    fn gpu_config(&mut self, gpu: &GpuConfig, location: SourceLocation) {
+        let keys = self.members(location, |i| self.is_key_start(i));
+        let key_line = |name: &str| {
+            keys.iter()
+                .find(|&&i| matches!(&self.tokens[i].token, Token::Ident(key) if key == name))
+                .map(|&i| self.tokens[i].location.line)
+        };
+
+        // Defaults are left out: they're indistinguishable from omitted options
+        let mut options = Vec::new();
+        match gpu.prefer {
+            GpuPreference::Any => {}
+            GpuPreference::Discrete => options.push(("prefer", "discrete".to_string())),
+            GpuPreference::Integrated => options.push(("prefer", "integrated".to_string())),
+        }
+        if let Some(index) = gpu.device_index {
+            options.push(("device", index.to_string()));
+        }
+        if gpu.transfer_queue == TransferQueueMode::Shared {
+            options.push(("transfer_queue", "shared".to_string()));
+        }
+        let feature_list = |features: &[(String, SourceLocation)]| {
+            let names: Vec<&str> = features.iter().map(|(name, _)| name.as_str()).collect();
+            format!("[{}]", names.join(", "))
+        };
+        if !gpu.required_features.is_empty() {
+            options.push(("required_features", feature_list(&gpu.required_features)));
+        }
+        if !gpu.optional_features.is_empty() {
+            options.push(("optional_features", feature_list(&gpu.optional_features)));
+        }
+        let lines: Vec<usize> = options.iter().map(|(key, _)| key_line(key).unwrap_or(location.line)).collect();
+
+        self.open("gpu {", Self::header_line(location, lines.first().copied()));
+        for ((key, value), line) in options.iter().zip(lines) {
+            self.start(line);
+            self.write_line(&format!("{}: {},", key, value), line);
+        }
+        self.close("", self.closing_brace_after(location));
+    }
+
+    fn window_config(&mut self, window: &WindowConfig, location: SourceLocation) {
+        let keys = self.members(location, |i| self.is_key_start(i));
+        let key_line = |name: &str| {
+            keys.iter()
+                .find(|&&i| matches!(&self.tokens[i].token, Token::Ident(key) if key == name))
+                .map(|&i| self.tokens[i].location.line)
+        };
+
+        // Options are kept when written out or when they differ from the default
+        let options = [
+            ("title", format!("\"{}\"", window.title), window.title != "HEIDIC"),
+            ("width", window.width.to_string(), window.width != 1280),
+            ("height", window.height.to_string(), window.height != 720),
+            ("vsync", window.vsync.to_string(), !window.vsync),
+            ("resizable", window.resizable.to_string(), !window.resizable),
+        ];
+        let options: Vec<(&str, String, usize)> = options.into_iter()
+            .filter_map(|(key, value, changed)| match key_line(key) {
+                Some(line) => Some((key, value, line)),
+                None => changed.then_some((key, value, location.line)),
+            })
+            .collect();
+
+        self.open("window {", Self::header_line(location, options.first().map(|(_, _, line)| *line)));
+        for (key, value, line) in &options {
+            self.start(*line);
+            self.write_line(&format!("{}: {},", key, value), *line);
+        }
+        self.close("", self.closing_brace_after(location));
+    }
+
+    // ---- Statements ----------------------------------------------------------------------
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let location = statement.location();
+        let line = location.line;
+        self.start(line);
+        match statement {
+            Statement::Let { name, mutable, ty, value, .. } => {
+                let ty = ty.as_ref().map(|ty| format!(": {}", type_str(ty))).unwrap_or_default();
+                let mutable = if *mutable { "mut " } else { "" };
+                let text = format!("let {}{}{} = {};", mutable, name, ty, self.expr(value));
+                self.write_line(&text, line);
+            }
+            Statement::Destructure { pattern, mutable, value, .. } => {
+                let mutable = if *mutable { "mut " } else { "" };
+                let text = format!("let {}{} = {};", mutable, self.pattern(pattern), self.expr(value));
+                self.write_line(&text, line);
+            }
+            Statement::Assign { target, value, .. } => {
+                let text = format!("{} = {};", self.expr(target), self.expr(value));
+                self.write_line(&text, line);
+            }
+            Statement::If { condition, then_block, else_block, .. } => {
+                let header = format!("if {} {{", self.expr(condition));
+                self.open(&header, line);
+                self.statements(then_block);
+                let then_close = self.closing_brace_after(location);
+                match else_block {
+                    Some(else_block) => {
+                        self.close(" else {", then_close);
+                        self.indent += 1;
+                        self.block_start = true;
+                        self.statements(else_block);
+                        let else_close = then_close
+                            .and_then(|c| self.closing_brace_after(SourceLocation::new(c.line, c.column + 1)));
+                        self.close("", else_close);
+                    }
+                    None => self.close("", then_close),
+                }
+            }
+            Statement::While { condition, body, .. } => {
+                let header = format!("while {} {{", self.expr(condition));
+                self.open(&header, line);
+                self.statements(body);
+                self.close("", self.closing_brace_after(location));
+            }
+            Statement::For { iterator, collection, chunk_size, body, .. } => {
+                let header = match chunk_size {
+                    Some(size) => format!("for {} in {}.chunks({}) {{", iterator, self.expr(collection), self.expr(size)),
+                    None => format!("for {} in {} {{", iterator, self.expr(collection)),
+                };
+                self.open(&header, line);
+                self.statements(body);
+                self.close("", self.closing_brace_after(location));
+            }
+            Statement::Loop { body, .. } => {
+                self.open("loop {", line);
+                self.statements(body);
+                self.close("", self.closing_brace_after(location));
+            }
+            Statement::SpawnBatch { count, index, body, .. } => {
+                let header = format!("spawn_batch({}) {{", self.expr(count));
+                self.open(&header, line);
+                // `init` is the first token inside the batch's braces
+                let init = self.first_brace_at_or_after(location).and_then(|i| self.tokens.get(i + 1)).map(|t| t.location);
+                let init_line = init.map_or(line, |init| init.line);
+                self.start(init_line);
+                self.open(&format!("init({}) {{", index), init_line);
+                self.statements(body);
+                self.close("", init.and_then(|init| self.closing_brace_after(init)));
+                self.close("", self.closing_brace_after(location));
+            }
+            Statement::Block(body, _) => {
+                self.open("{", line);
+                self.statements(body);
+                self.close("", self.closing_brace_after(location));
+            }
+            Statement::Return(value, _) => {
+                let text = match value {
+                    Some(value) => format!("return {};", self.expr(value)),
+                    None => "return;".to_string(),
+                };
+                self.write_line(&text, line);
+            }
+            Statement::Break(value, _) => {
+                let text = match value {
+                    Some(value) => format!("break {};", self.expr(value)),
+                    None => "break;".to_string(),
+                };
+                self.write_line(&text, line);
+            }
+            Statement::Continue(_) => self.write_line("continue;", line),
+            Statement::Defer(expr, _) => {
+                let text = format!("defer {};", self.expr(expr));
+                self.write_line(&text, line);
+            }
+            Statement::Emit(event, _) => {
+                let text = format!("emit {};", self.expr(event));
+                self.write_line(&text, line);
+            }
+            Statement::Expression(expr, _) => {
+                let text = format!("{};", self.expr(expr));
+                self.write_line(&text, line);
+            }
+        }
+    }
+
+    // ---- Expressions ---------------------------------------------------------------------
+
+    fn expr(&mut self, expr: &Expression) -> String {
+        match expr {
+            Expression::Literal(literal, location) => self.literal(literal, *location),
+            Expression::Variable(name, _) => name.clone(),
+            Expression::BinaryOp { op, left, right, .. } => {
+                let precedence = binary_precedence(op);
+                let left = self.operand(left, precedence, false);
+                let right = self.operand(right, precedence, true);
+                format!("{} {} {}", left, binary_symbol(op), right)
+            }
+            Expression::UnaryOp { op, expr, .. } => {
+                let symbol = match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "!",
+                };
+                format!("{}{}", symbol, self.operand(expr, PREC_UNARY, false))
+            }
+            Expression::Cast { expr, ty, .. } => format!("{} as {}", self.operand(expr, PREC_CAST, false), type_str(ty)),
+            Expression::LayoutOf { query, ty, field, .. } => {
+                format!("{}<{}>({})", query.name(), type_str(ty), field.as_deref().unwrap_or(""))
+            }
+            Expression::FrameAlloc { arena, element, count, .. } => {
+                format!("{}.{}<{}>({})", self.operand(arena, PREC_POSTFIX, false), frame_arena::METHOD, type_str(element), self.expr(count))
+            }
+            Expression::GpuSync { direction, component, .. } => format!("{}<{}>()", direction.name(), type_str(component)),
+            // Entity methods go back to method syntax: e.set_parent(p)
+            Expression::Call { name, args, .. } if hierarchy::METHODS.contains(&name.as_str()) && !args.is_empty() => {
+                format!("{}.{}({})", self.operand(&args[0], PREC_POSTFIX, false), name, self.expr_list(&args[1..]))
+            }
+            Expression::Call { name, args, .. } => format!("{}({})", name, self.expr_list(args)),
+            Expression::MemberAccess { object, member, .. } => {
+                format!("{}.{}", self.operand(object, PREC_POSTFIX, false), member)
+            }
+            Expression::Index { array, index, .. } => {
+                format!("{}[{}]", self.operand(array, PREC_POSTFIX, false), self.expr(index))
+            }
+            Expression::ArrayLiteral { elements, .. } => format!("[{}]", self.expr_list(elements)),
+            Expression::StringInterpolation { parts, .. } => {
+                let mut text = String::from("\"");
+                for part in parts {
+                    match part {
+                        StringInterpolationPart::Literal(literal) => text.push_str(literal),
+                        StringInterpolationPart::Variable(name) => text.push_str(&format!("{{{}}}", name)),
+                    }
+                }
+                text.push('"');
+                text
+            }
+            Expression::Match { expr, arms, location } => self.match_expr(expr, arms, *location),
+            Expression::If { condition, then_branch, else_branch, .. } => {
+                let condition = self.expr(condition);
+                // Either both branches fit on the line or both get lines of their own
+                let multiline = !then_branch.statements.is_empty() || !else_branch.statements.is_empty();
+                let then_text = self.value_block(then_branch, multiline);
+                let else_text = match else_branch.value.as_ref() {
+                    nested @ Expression::If { .. } if else_branch.statements.is_empty() => self.expr(nested),
+                    _ => self.value_block(else_branch, multiline),
+                };
+                format!("if {} {} else {}", condition, then_text, else_text)
+            }
+            Expression::Block { body, .. } => self.value_block(body, !body.statements.is_empty()),
+            Expression::Loop { body, location } => self.loop_expression(body, *location),
+            Expression::StructLiteral { name, fields, .. } => {
+                // Vec2/Vec3/Vec4 constructor calls are parsed into struct literals
+                let names: Vec<&str> = fields.iter().map(|(field, _)| field.as_str()).collect();
+                let constructor_fields: &[&str] = match name.as_str() {
+                    "Vec2" => &["x", "y"],
+                    "Vec3" => &["x", "y", "z"],
+                    "Vec4" => &["x", "y", "z", "w"],
+                    _ => &[],
+                };
+                if !constructor_fields.is_empty() && names == constructor_fields {
+                    let values: Vec<Expression> = fields.iter().map(|(_, value)| value.clone()).collect();
+                    return format!("{}({})", name, self.expr_list(&values));
+                }
+                if fields.is_empty() {
+                    return format!("{} {{}}", name);
+                }
+                let mut rendered = Vec::new();
+                for (field, value) in fields {
+                    rendered.push(format!("{}: {}", field, self.expr(value)));
+                }
+                format!("{} {{ {} }}", name, rendered.join(", "))
+            }
+        }
+    }
+
+    fn expr_list(&mut self, exprs: &[Expression]) -> String {
+        let mut rendered = Vec::new();
+        for expr in exprs {
+            rendered.push(self.expr(expr));
+        }
+        rendered.join(", ")
+    }
+
+    // Parenthesize a subexpression that binds more loosely than its context
+    // (or equally, on the right of a left-associative operator)
+    fn operand(&mut self, expr: &Expression, context: u8, right_side: bool) -> String {
+        let precedence = match expr {
+            Expression::BinaryOp { op, .. } => binary_precedence(op),
+            Expression::UnaryOp { .. } => PREC_UNARY,
+            Expression::Cast { .. } => PREC_CAST,
+            _ => PREC_POSTFIX,
+        };
+        let text = self.expr(expr);
+        if precedence < context || (right_side && precedence == context) {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn match_expr(&mut self, scrutinee: &Expression, arms: &[MatchArm], location: SourceLocation) -> String {
+        let header = format!("match {} {{", self.expr(scrutinee));
+        let outer = std::mem::take(&mut self.out);
+        self.indent += 1;
+        self.block_start = true;
+        for arm in arms {
+            self.start(arm.location.line);
+            let mut pattern = self.pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                pattern = format!("{} if {}", pattern, self.expr(guard));
+            }
+            // An arm that is only a value stays on one line: `0 => { 10 }`
+            if let (true, Some(value)) = (arm.body.is_empty(), &arm.value) {
+                let text = format!("{} => {{ {} }}", pattern, self.expr(value));
+                self.write_line(&text, arm.location.line);
+                continue;
+            }
+            self.open(&format!("{} => {{", pattern), arm.location.line);
+            self.statements(&arm.body);
+            if let Some(value) = &arm.value {
+                let line = value.location().line;
+                self.start(line);
+                let text = self.expr(value);
+                self.write_line(&text, line);
+            }
+            self.close("", self.closing_brace_after(arm.location));
+        }
+        let close = self.closing_brace_after(location);
+        if let Some(close) = close {
+            self.flush_comments_before(close.line);
+            self.last_line = self.last_line.max(close.line);
+        }
+        self.indent -= 1;
+        self.block_start = false;
+        let body = std::mem::replace(&mut self.out, outer);
+        format!("{}\n{}{}}}", header, body, INDENT.repeat(self.indent))
+    }
+
+    // `{ value }` on one line, or the statements and the value on lines of their own
+    fn value_block(&mut self, block: &ValueBlock, multiline: bool) -> String {
+        if !multiline {
+            return format!("{{ {} }}", self.expr(&block.value));
+        }
+        let outer = std::mem::take(&mut self.out);
+        self.indent += 1;
+        self.block_start = true;
+        self.statements(&block.statements);
+        let line = block.value.location().line;
+        self.start(line);
+        let value = self.expr(&block.value);
+        self.write_line(&value, line);
+        if let Some(close) = self.enclosing_close_brace(block.value.location()) {
+            self.flush_comments_before(close.line);
+            self.last_line = self.last_line.max(close.line);
+        }
+        self.indent -= 1;
+        self.block_start = false;
+        let body = std::mem::replace(&mut self.out, outer);
+        format!("{{\n{}{}}}", body, INDENT.repeat(self.indent))
+    }
+
+    // Rendered like value_block: the body goes to its own buffer, then comes back as one string
+    fn loop_expression(&mut self, body: &[Statement], location: SourceLocation) -> String {
+        let outer = std::mem::take(&mut self.out);
+        self.indent += 1;
+        self.block_start = true;
+        self.statements(body);
+        if let Some(close) = self.closing_brace_after(location) {
+            self.flush_comments_before(close.line);
+            self.last_line = self.last_line.max(close.line);
+        }
+        self.indent -= 1;
+        self.block_start = false;
+        let body = std::mem::replace(&mut self.out, outer);
+        format!("loop {{\n{}{}}}", body, INDENT.repeat(self.indent))
+    }
+
+    fn pattern(&self, pattern: &Pattern) -> String {
+        match pattern {
+            Pattern::Literal(literal, location) => self.literal(literal, *location),
+            Pattern::Variable(name, _) | Pattern::Ident(name, _) => name.clone(),
+            Pattern::Wildcard(_) => "_".to_string(),
+            Pattern::Or(alternatives, _) => alternatives.iter().map(|alternative| self.pattern(alternative)).collect::<Vec<_>>().join(" | "),
+            Pattern::Range { start, end, inclusive, end_location, location } => format!("{}{}{}",
+                self.literal(start, *location), if *inclusive { "..=" } else { ".." }, self.literal(end, *end_location)),
+            Pattern::Struct { name, fields, rest, .. } => {
+                let mut parts: Vec<String> = fields.iter().map(|field| match &field.pattern {
+                    Pattern::Variable(binding, _) if *binding == field.field => binding.clone(),
+                    pattern => format!("{}: {}", field.field, self.pattern(pattern)),
+                }).collect();
+                if *rest {
+                    parts.push("..".to_string());
+                }
+                if parts.is_empty() { format!("{} {{}}", name) } else { format!("{} {{ {} }}", name, parts.join(", ")) }
+            }
+        }
+    }
+
+    // Numbers keep their source spelling (hex stays hex) when it still denotes the same value
+    fn literal(&self, literal: &Literal, location: SourceLocation) -> String {
+        match literal {
+            Literal::Int(n, suffix) => {
+                let suffix = suffix.as_ref().map(type_str).unwrap_or_default();
+                self.number_text(location)
+                    .filter(|text| text.strip_suffix(suffix.as_str()).and_then(parse_int) == Some(*n))
+                    .unwrap_or_else(|| format!("{}{}", n, suffix))
+            }
+            Literal::Float(f, suffix) => {
+                let suffix = suffix.as_ref().map(type_str).unwrap_or_default();
+                self.number_text(location)
+                    .filter(|text| text.strip_suffix(suffix.as_str()).and_then(|digits| digits.parse::<f64>().ok()) == Some(*f))
+                    .unwrap_or_else(|| format!("{:?}{}", f, suffix))
+            }
+            Literal::Bool(b) => b.to_string(),
+            Literal::String(s) => format!("\"{}\"", s),
+        }
+    }
+
+    fn number_text(&self, location: SourceLocation) -> Option<String> {
+        let line = self.source_lines.get(location.line.checked_sub(1)?)?;
+        let text: String = line.chars()
+            .skip(location.column.checked_sub(1)?)
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-')
+            .collect();
+        // A range pattern's start ends at its `..`
+        Some(text.split("..").next().unwrap_or_default().to_string())
+    }
+}
+
+fn parse_int(text: &str) -> Option<i64> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn hot_prefix(is_hot: bool) -> &'static str {
+    if is_hot { "@hot " } else { "" }
+}
+
+fn stage_name(stage: &ShaderStage) -> &'static str {
+    match stage {
+        ShaderStage::Vertex => "vertex",
+        ShaderStage::Fragment => "fragment",
+        ShaderStage::Compute => "compute",
+        ShaderStage::Geometry => "geometry",
+        ShaderStage::TessellationControl => "tessellation_control",
+        ShaderStage::TessellationEvaluation => "tessellation_evaluation",
+    }
+}
+
+fn binary_precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Or => 1,
+        BinaryOp::And => 2,
+        BinaryOp::Eq | BinaryOp::Ne => 3,
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 4,
+        BinaryOp::Add | BinaryOp::Sub => 5,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 6,
+    }
+}
+
+pub fn binary_symbol(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Eq => "==",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Le => "<=",
+        BinaryOp::Gt => ">",
+        BinaryOp::Ge => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+    }
+}
+
+/// An expression as `fmt` prints it; `source` is the file it was parsed from, whose number
+/// spellings (0x10, 1e3) are kept
+pub fn expression_str(source: &str, expr: &Expression) -> String {
+    Formatter::new(source, &[], &[]).expr(expr)
+}
+
+/// A component field's attributes as written before its name: `@[range(0.0, 1.0)] @[unit("m/s")] @cold `
+pub fn field_attributes(field: &Field) -> String {
+    let mut text = String::new();
+    if let Some((min, max)) = field.range {
+        let element = match &field.ty { Type::Array(element) => element.as_ref(), ty => ty };
+        let bound = |value: f64| match element {
+            Type::I32 | Type::I64 if value.fract() == 0.0 => format!("{}", value as i64),
+            _ => format!("{:?}", value),
+        };
+        text.push_str(&format!("@[range({}, {})] ", bound(min), bound(max)));
+    }
+    if let Some(unit) = &field.unit {
+        text.push_str(&format!("@[unit(\"{}\")] ", unit));
+    }
+    if field.cold {
+        text.push_str("@cold ");
+    }
+    text
+}
+
+/// A texture resource's options block, listing the ones that differ from the defaults
+pub fn texture_options(options: &TextureOptions) -> String {
+    let mut parts = Vec::new();
+    match options.filter {
+        TextureFilter::Linear => {}
+        TextureFilter::Nearest => parts.push("filter: nearest"),
+    }
+    match options.wrap {
+        TextureWrap::Repeat => {}
+        TextureWrap::Clamp => parts.push("wrap: clamp"),
+        TextureWrap::Mirror => parts.push("wrap: mirror"),
+    }
+    if options.mips {
+        parts.push("mips: true");
+    }
+    if parts.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", parts.join(", "))
+    }
+}
+
+// HEIDIC spelling of a type (Vulkan, GLFW and math type names are their own keywords)
+pub fn type_str(ty: &Type) -> String {
+    match ty {
+        Type::I32 => "i32".to_string(),
+        Type::I64 => "i64".to_string(),
+        Type::F32 => "f32".to_string(),
+        Type::F64 => "f64".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::String => "string".to_string(),
+        Type::Void => "void".to_string(),
+        Type::Array(element) => format!("[{}]", type_str(element)),
+        Type::Optional(inner) => format!("?{}", type_str(inner)),
+        Type::Struct(name) | Type::Component(name) => name.clone(),
+        Type::Query(components) => {
+            let names: Vec<String> = components.iter().map(type_str).collect();
+            format!("query<{}>", names.join(", "))
+        }
+        Type::QueryFilter(filter, component) => format!("{}<{}>", filter.name(), type_str(component)),
+        Type::Res(resource) => format!("res<{}>", type_str(resource)),
+        Type::Function(params, ret) => {
+            let names: Vec<String> = params.iter().map(type_str).collect();
+            format!("fn({}): {}", names.join(", "), type_str(ret))
+        }
+        _ => format!("{:?}", ty),
+    }
+}
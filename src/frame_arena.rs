@@ -0,0 +1,38 @@
+// Frame arena: `let positions = frame.alloc_array<Vec3>(count);` takes zeroed scratch memory for
+// this frame from the built-in `frame` bump allocator (stdlib/frame_arena.h). The outermost loops
+// of a program (the ones that get hot-reload checks) release whatever an iteration allocated when
+// it ends, so a game loop pays for a pointer bump per allocation and nothing per frame. A function
+// can take the arena as a parameter (`fn f(arena: FrameArena)`), and the type checker keeps
+// allocations from being returned out of the function that made them (E0009).
+
+use crate::ast::*;
+
+/// The built-in arena every function can allocate from
+pub const OBJECT: &str = "frame";
+
+/// The arena's type, for parameters that take it
+pub const TYPE: &str = "FrameArena";
+
+/// The method that allocates: frame.alloc_array<T>(n)
+pub const METHOD: &str = "alloc_array";
+
+/// The C++ HeidicFrameArena behind `frame`
+pub const GLOBAL: &str = "g_frame_arena";
+
+pub fn arena_type() -> Type {
+    Type::Struct(TYPE.to_string())
+}
+
+pub fn is_arena(ty: &Type) -> bool {
+    matches!(ty, Type::Struct(name) if name == TYPE)
+}
+
+/// Whether some function of the program takes the arena as a parameter
+pub fn is_passed(program: &Program) -> bool {
+    program.items.iter().any(|item| match item {
+        Item::Function(f) => f.params.iter().any(|p| is_arena(&p.ty)),
+        Item::System(s) => s.functions.iter().any(|f| f.params.iter().any(|p| is_arena(&p.ty))),
+        Item::Impl(i) => i.methods.iter().any(|(f, _)| f.params.iter().any(|p| is_arena(&p.ty))),
+        _ => false,
+    })
+}
@@ -0,0 +1,210 @@
+// Names the generated C++ uses for itself. A HEIDIC symbol spelled the same way becomes a
+// redefinition or shadows a runtime global, and the C++ compiler's error points at generated code
+// instead of the source, so the type checker rejects these names up front (E0025).
+
+use std::collections::HashSet;
+
+/// Runtime types and names shared with the engine's stdlib/ headers
+const EXACT: &[(&str, &str)] = &[
+    ("ComponentMetadata", "hot-reloadable component metadata"),
+    ("ComponentStorage", "the ECS runtime"),
+    ("ComponentId", "the ECS runtime"),
+    ("EntityStorage", "the ECS runtime"),
+    ("EntityId", "the ECS runtime"),
+    ("EventQueue", "event queues"),
+    ("Resource", "resource handles"),
+    ("make_defer", "defer statements"),
+    ("FrameArena", "the frame arena"),
+    ("g_device", "the Vulkan device"),
+];
+
+/// Prefixes of the helpers and types the runtime headers provide
+const PREFIXES: &[(&str, &str)] = &[
+    ("heidic_", "the compiler's helpers and temporaries"),
+    ("Heidic", "runtime types"),
+    ("hd_", "the program's generated symbols"),
+];
+
+/// The program's own globals and functions, renamed hd_<program>_... by `mangle` so two compiled
+/// programs can be linked into one process
+const SYMBOLS: &[(&str, &str)] = &[
+    ("g_storage", "the ECS world"),
+    ("g_entities", "the ECS world"),
+    ("g_net", "replication"),
+    ("g_reload_event", "hot reload"),
+    ("g_component_versions", "hot reload"),
+    ("g_last_update_time", "hot reload"),
+    ("g_heidic_test_failed", "test blocks"),
+    ("heidic_main", "the program's main"),
+    ("load_hot_system", "hot reload"),
+    ("unload_hot_system", "hot reload"),
+    ("check_and_reload_hot_system", "hot reload"),
+    ("rollback_last_reload", "hot reload"),
+    ("check_and_reload_hot_shaders", "hot shaders"),
+    ("check_and_reload_resources", "hot resources"),
+    ("check_and_migrate_hot_components", "hot components"),
+    ("init_component_versions", "hot components"),
+    ("register_all_components", "hot components"),
+    ("init_bindless_system", "bindless textures"),
+    ("register_bindless_textures", "bindless textures"),
+    ("register_bindless_buffers", "bindless textures"),
+    ("heidic_bindless_upload", "bindless textures"),
+    ("create_bindless_descriptor_set_layout", "bindless textures"),
+    ("allocate_bindless_descriptor_set", "bindless textures"),
+    ("g_audio_mixer", "the audio mixer"),
+    ("apply_audio_mix", "the audio mixer"),
+    ("set_master_volume", "the audio mixer"),
+    ("set_sound_volume", "the audio mixer"),
+    ("set_music_volume", "the audio mixer"),
+    ("g_frame_arena", "the frame arena"),
+];
+
+/// Prefixes of the program's globals and functions derived from item names (also mangled)
+const SYMBOL_PREFIXES: &[(&str, &str)] = &[
+    ("g_resource_", "resource globals"),
+    ("get_resource_", "resource accessors"),
+    ("play_resource_", "audio resource helpers"),
+    ("stop_resource_", "audio resource helpers"),
+    ("pause_resource_", "audio resource helpers"),
+    ("resume_resource_", "audio resource helpers"),
+    ("set_volume_resource_", "audio resource helpers"),
+    ("g_spatial_emitter_", "spatial audio"),
+    ("g_metadata_", "hot component metadata"),
+    ("g_prev_metadata_", "hot component metadata"),
+    ("g_prev_sig_storage_", "hot component metadata"),
+    ("g_hot_", "hot reload"),
+    ("g_dll_watch_", "hot reload"),
+    ("g_cuda_stream_", "CUDA kernel launches"),
+    ("heidic_cuda_launch_", "CUDA kernel launches"),
+    ("heidic_cuda_wait_", "CUDA kernel launches"),
+    ("heidic_sync_to_", "GPU-resident components"),
+    ("heidic_cuda_sync_to_", "GPU-resident components"),
+    ("g_cuda_resident_", "GPU-resident components"),
+    ("g_cuda_residency_", "GPU-resident components"),
+    ("g_compute_", "Vulkan compute launches"),
+    ("g_pipeline_", "pipelines"),
+    ("g_shader_module_", "pipelines"),
+    ("g_descriptor_set_layout_", "pipelines"),
+    ("g_descriptor_pool_", "pipelines"),
+    ("g_descriptor_set_", "pipelines"),
+    ("create_pipeline_", "pipelines"),
+    ("create_descriptor_set_layout_", "pipelines"),
+    ("rebuild_pipeline_", "pipelines"),
+    ("g_bindless_", "bindless textures"),
+    ("g_gpu_", "GPU buffers and queries"),
+    ("g_uniform_", "uniform buffers"),
+];
+
+/// What the generated C++ uses `name` for, if anything
+pub fn generated_use(name: &str) -> Option<&'static str> {
+    if name.strip_prefix("defer_").is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())) {
+        return Some("defer statements");
+    }
+    EXACT.iter().chain(SYMBOLS).find(|(exact, _)| *exact == name)
+        .or_else(|| PREFIXES.iter().chain(SYMBOL_PREFIXES).find(|(prefix, _)| name.starts_with(prefix)))
+        .map(|(_, usage)| *usage)
+}
+
+/// A name to use instead of a reserved one
+pub fn rename_suggestion(name: &str) -> String {
+    if let Some(n) = name.strip_prefix("defer_") {
+        return format!("deferred_{}", n);
+    }
+    let stripped = ["heidic_", "Heidic", "g_"].iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic()) && generated_use(rest).is_none());
+    match stripped {
+        Some(rest) => rest.to_string(),
+        None if name.starts_with(|c: char| c.is_ascii_uppercase()) => format!("Game{}", name),
+        None => format!("game_{}", name),
+    }
+}
+
+/// Prefix of the program's generated symbols: hd_<program>_
+pub fn symbol_prefix(program: &str) -> String {
+    let program: String = program.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if program.is_empty() { "hd_".to_string() } else { format!("hd_{}_", program) }
+}
+
+fn is_symbol(name: &str, extra: &HashSet<String>) -> bool {
+    SYMBOLS.iter().any(|(exact, _)| *exact == name)
+        || SYMBOL_PREFIXES.iter().any(|(prefix, _)| name.starts_with(prefix))
+        || extra.contains(name)
+}
+
+/// The name `mangle` gives one of the program's symbols
+pub fn mangled(name: &str, prefix: &str) -> String {
+    format!("{}{}", prefix, name.strip_prefix("g_").or_else(|| name.strip_prefix("heidic_")).unwrap_or(name))
+}
+
+/// The runtime's own symbols (before mangling) and the feature each one serves
+pub fn runtime_symbols() -> impl Iterator<Item = (&'static str, &'static str)> {
+    SYMBOLS.iter().copied()
+}
+
+/// Rename the program's symbols in generated C++ to `prefix` + the name without its g_/heidic_
+/// marker (g_storage -> hd_game_storage). `extra` adds names codegen derives from user items (the
+/// @hot fn pointers). Comments and string literals are copied unchanged: DLL exports are looked up
+/// by their literal names.
+pub fn mangle(code: &str, prefix: &str, extra: &HashSet<String>) -> String {
+    map_identifiers(code, |word| is_symbol(word, extra).then(|| mangled(word, prefix)))
+}
+
+/// Every identifier in generated C++, outside comments and string literals
+pub fn identifiers(code: &str) -> HashSet<String> {
+    let mut found = HashSet::new();
+    map_identifiers(code, |word| {
+        found.insert(word.to_string());
+        None
+    });
+    found
+}
+
+// Copy C++ code, replacing the identifiers `rename` returns a new name for
+fn map_identifiers(code: &str, mut rename: impl FnMut(&str) -> Option<String>) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len() + code.len() / 16);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start = i;
+        match (c, next) {
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(chars.len());
+            }
+            ('"', _) | ('\'', _) => {
+                i += 1;
+                while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(chars.len());
+            }
+            _ if c.is_ascii_alphanumeric() || c == '_' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match rename(&word) {
+                    Some(renamed) if !c.is_ascii_digit() => out.push_str(&renamed),
+                    _ => out.push_str(&word),
+                }
+                continue;
+            }
+            _ => i += 1,
+        }
+        out.extend(&chars[start..i]);
+    }
+    out
+}
@@ -0,0 +1,133 @@
+// heidic_v2 test-codegen: golden-file tests for the code generator. Every .hd file in a fixture
+// directory is compiled, and each file of code generated from it (the .cpp or .c, the .cu and the
+// hot DLL sources) is compared with golden/<fixture>/<file> in that directory. --update-golden
+// rewrites the golden files from the current output instead, for changes that are intended.
+//
+// A fixture's first line can name compile flags, so one directory covers several backends:
+//
+//   // flags: --backend=c
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+const FLAGS_PREFIX: &str = "// flags:";
+
+// Generated code, as opposed to the other files a compile writes (symbol maps, page harnesses)
+const CODE_EXTENSIONS: &[&str] = &["cpp", "c", "cu"];
+
+/// The fixtures in `dir`: its .hd files, sorted by name
+pub fn fixtures(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Failed to read fixture directory {}", dir.display()))?;
+    let mut fixtures = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "hd") {
+            fixtures.push(path);
+        }
+    }
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// The compile flags a fixture's first line names (none without a `// flags:` line)
+pub fn fixture_flags(source: &str) -> Vec<String> {
+    let first = source.lines().next().unwrap_or("");
+    match first.trim().strip_prefix(FLAGS_PREFIX) {
+        Some(flags) => flags.split_whitespace().map(str::to_string).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// The code files in `dir` as (file name, contents), sorted by name
+pub fn code_files(dir: &Path) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if !path.is_file() || !path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| CODE_EXTENSIONS.contains(&ext)) {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let contents = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        files.push((name, contents));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// How a fixture's generated code differs from its golden files
+pub enum Mismatch {
+    Missing(String),     // A golden file the compile no longer generates
+    Unexpected(String),  // A generated file with no golden file
+    Changed { file: String, line: usize, expected: String, actual: String },  // First differing line (1-based)
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Missing(file) => write!(f, "{} is no longer generated", file),
+            Mismatch::Unexpected(file) => write!(f, "{} is generated but has no golden file", file),
+            Mismatch::Changed { file, line, expected, actual } => {
+                write!(f, "{} differs at line {}:\n  expected: {}\n  actual:   {}", file, line, expected, actual)
+            }
+        }
+    }
+}
+
+/// Compare the generated files with the golden files in `golden_dir`
+pub fn compare(golden_dir: &Path, generated: &[(String, String)]) -> Result<Vec<Mismatch>> {
+    let golden = code_files(golden_dir)?;
+    let mut mismatches = Vec::new();
+    for (name, expected) in &golden {
+        let Some((_, actual)) = generated.iter().find(|(generated_name, _)| generated_name == name) else {
+            mismatches.push(Mismatch::Missing(name.clone()));
+            continue;
+        };
+        if actual == expected {
+            continue;
+        }
+        // Line by line; a file that only gained or lost lines at the end differs at its shorter end
+        let (mut expected_lines, mut actual_lines) = (expected.lines(), actual.lines());
+        let mut line = 1;
+        loop {
+            match (expected_lines.next(), actual_lines.next()) {
+                (Some(e), Some(a)) if e == a => line += 1,
+                (None, None) => break,  // Only line endings differ (a CRLF checkout): the same code
+                (e, a) => {
+                    mismatches.push(Mismatch::Changed {
+                        file: name.clone(),
+                        line,
+                        expected: e.unwrap_or("<end of file>").to_string(),
+                        actual: a.unwrap_or("<end of file>").to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+    }
+    for (name, _) in generated {
+        if !golden.iter().any(|(golden_name, _)| golden_name == name) {
+            mismatches.push(Mismatch::Unexpected(name.clone()));
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Replace the golden files in `golden_dir` with the generated ones
+pub fn update(golden_dir: &Path, generated: &[(String, String)]) -> Result<()> {
+    for (name, _) in code_files(golden_dir)? {
+        fs::remove_file(golden_dir.join(&name))
+            .with_context(|| format!("Failed to remove {}", golden_dir.join(&name).display()))?;
+    }
+    fs::create_dir_all(golden_dir).with_context(|| format!("Failed to create {}", golden_dir.display()))?;
+    for (name, contents) in generated {
+        let path = golden_dir.join(name);
+        fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
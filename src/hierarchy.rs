@@ -0,0 +1,48 @@
+// Entity hierarchy: `child.set_parent(parent)` and `child.clear_parent()` on the entities of query
+// loops, and `for child in q.children(parent)` to visit the entities of q parented to `parent`.
+// The relationship is kept in g_storage as two built-in components (stdlib/hierarchy.h): Parent
+// on every child and Children on every parent, so queries can filter on them (roots are
+// query<Transform, without<Parent>>) and transform propagation can walk parents before children.
+
+use crate::ast::*;
+
+/// Entity methods; the parser turns `e.set_parent(p)` into the call set_parent(e, p)
+pub const METHODS: &[&str] = &["set_parent", "clear_parent", "children"];
+
+pub const BUILTINS: &[&str] = &["Parent", "Children"];
+
+/// Built-in hierarchy components, used unless the program declares a struct or component of that
+/// name. Their fields only exist in C++: HEIDIC code uses them in with<>/without<> filters.
+pub fn builtin(name: &str) -> Option<ComponentDef> {
+    match name {
+        "Parent" | "Children" => Some(ComponentDef {
+            name: name.to_string(),
+            fields: Vec::new(),
+            is_soa: false,
+            is_hot: false,
+            is_cuda: false,
+            is_export: false,
+            is_replicated: false,
+            pool_capacity: None,
+            doc: None,
+        }),
+        _ => None,
+    }
+}
+
+/// The C++ members of a built-in component, with their types, which save_world writes
+pub fn builtin_members(name: &str) -> &'static [(&'static str, &'static str)] {
+    match name {
+        "Parent" => &[("entity", "uint32_t"), ("depth", "uint32_t")],
+        "Children" => &[("entities", "std::vector<uint32_t>")],
+        _ => &[],
+    }
+}
+
+/// `q.children(parent)` iterates q: the query and the parent entity
+pub fn children_query(collection: &Expression) -> Option<(&Expression, &Expression)> {
+    match collection {
+        Expression::Call { name, args, .. } if name == "children" && args.len() == 2 => Some((&args[0], &args[1])),
+        _ => None,
+    }
+}
@@ -0,0 +1,122 @@
+// Input mapping: `input { jump: Key.Space | Gamepad.A; move_x: axis(Key.A, Key.D); }` names the
+// program's actions and the keys, mouse buttons and gamepad controls bound to them. Each action
+// gets accessors on `input`: input.jump(), input.jump_pressed() and input.jump_released() for a
+// button, input.move_x() in [-1, 1] for an axis. input.attach(window) installs the GLFW callbacks
+// and every run_systems() updates the actions first (stdlib/input_map.h reads the devices).
+
+use crate::ast::*;
+
+/// The object the accessors are called on
+pub const OBJECT: &str = "input";
+
+/// Accessors every input block has, so no action can take their names
+pub const METHODS: &[&str] = &["attach", "update"];
+
+/// The C++ HeidicInputDevices the actions read
+pub const DEVICES_GLOBAL: &str = "heidic_input";
+
+/// The C++ function behind input.update(), which run_systems() calls first
+pub const UPDATE_FUNCTION: &str = "heidic_update_input";
+
+/// The GLFW codes of the controls a binding can name, per device
+const KEYS: &[(&str, i32)] = &[
+    ("Space", 32), ("Apostrophe", 39), ("Comma", 44), ("Minus", 45), ("Period", 46), ("Slash", 47),
+    ("Num0", 48), ("Num1", 49), ("Num2", 50), ("Num3", 51), ("Num4", 52),
+    ("Num5", 53), ("Num6", 54), ("Num7", 55), ("Num8", 56), ("Num9", 57),
+    ("Semicolon", 59), ("Equal", 61),
+    ("A", 65), ("B", 66), ("C", 67), ("D", 68), ("E", 69), ("F", 70), ("G", 71), ("H", 72), ("I", 73),
+    ("J", 74), ("K", 75), ("L", 76), ("M", 77), ("N", 78), ("O", 79), ("P", 80), ("Q", 81), ("R", 82),
+    ("S", 83), ("T", 84), ("U", 85), ("V", 86), ("W", 87), ("X", 88), ("Y", 89), ("Z", 90),
+    ("LeftBracket", 91), ("Backslash", 92), ("RightBracket", 93), ("GraveAccent", 96),
+    ("Escape", 256), ("Enter", 257), ("Tab", 258), ("Backspace", 259), ("Insert", 260), ("Delete", 261),
+    ("Right", 262), ("Left", 263), ("Down", 264), ("Up", 265),
+    ("PageUp", 266), ("PageDown", 267), ("Home", 268), ("End", 269),
+    ("F1", 290), ("F2", 291), ("F3", 292), ("F4", 293), ("F5", 294), ("F6", 295),
+    ("F7", 296), ("F8", 297), ("F9", 298), ("F10", 299), ("F11", 300), ("F12", 301),
+    ("LeftShift", 340), ("LeftControl", 341), ("LeftAlt", 342), ("LeftSuper", 343),
+    ("RightShift", 344), ("RightControl", 345), ("RightAlt", 346), ("RightSuper", 347),
+];
+
+const MOUSE_BUTTONS: &[(&str, i32)] = &[("Left", 0), ("Right", 1), ("Middle", 2)];
+
+const GAMEPAD_BUTTONS: &[(&str, i32)] = &[
+    ("A", 0), ("B", 1), ("X", 2), ("Y", 3), ("LeftBumper", 4), ("RightBumper", 5),
+    ("Back", 6), ("Start", 7), ("Guide", 8), ("LeftThumb", 9), ("RightThumb", 10),
+    ("DpadUp", 11), ("DpadRight", 12), ("DpadDown", 13), ("DpadLeft", 14),
+];
+
+/// Sticks and triggers, in GLFW's order
+pub const GAMEPAD_AXES: &[&str] = &["LeftX", "LeftY", "RightX", "RightY", "LeftTrigger", "RightTrigger"];
+
+pub const DEVICES: &[&str] = &["Key", "Mouse", "Gamepad"];
+
+/// The names a device's controls go by (None for an unknown device)
+pub fn control_names(device: &str) -> Option<Vec<&'static str>> {
+    let buttons = match device {
+        "Key" => KEYS,
+        "Mouse" => MOUSE_BUTTONS,
+        "Gamepad" => GAMEPAD_BUTTONS,
+        _ => return None,
+    };
+    let axes = if device == "Gamepad" { GAMEPAD_AXES } else { &[] };
+    Some(buttons.iter().map(|(name, _)| *name).chain(axes.iter().copied()).collect())
+}
+
+/// The GLFW code of a source's control (None if the device has no such control)
+pub fn code(source: &InputSource) -> Option<i32> {
+    if source.is_gamepad_axis() {
+        return GAMEPAD_AXES.iter().position(|axis| *axis == source.name).map(|i| i as i32);
+    }
+    let buttons = match source.device.as_str() {
+        "Key" => KEYS,
+        "Mouse" => MOUSE_BUTTONS,
+        "Gamepad" => GAMEPAD_BUTTONS,
+        _ => return None,
+    };
+    buttons.iter().find(|(name, _)| *name == source.name).map(|(_, code)| *code)
+}
+
+/// The accessors an action adds to `input`, without the `input.` prefix
+pub fn accessors(action: &InputAction) -> Vec<String> {
+    if action.is_axis() {
+        vec![action.name.clone()]
+    } else {
+        vec![action.name.clone(), format!("{}_pressed", action.name), format!("{}_released", action.name)]
+    }
+}
+
+/// The C++ global holding an action's state: a HeidicInputButton, or the axis value as a float
+pub fn action_global(action: &InputAction) -> String {
+    format!("heidic_action_{}", action.name)
+}
+
+/// `input.jump_pressed` -> `jump_pressed`
+pub fn method(call: &str) -> Option<&str> {
+    call.strip_prefix(OBJECT)?.strip_prefix('.')
+}
+
+/// The C++ reading of one source: a bool for buttons, a float for gamepad axes
+pub fn source_expression(source: &InputSource) -> String {
+    let code = code(source).unwrap_or(-1);
+    match source.device.as_str() {
+        "Key" => format!("{}.key({})", DEVICES_GLOBAL, code),
+        "Mouse" => format!("{}.mouseButton({})", DEVICES_GLOBAL, code),
+        _ if source.is_gamepad_axis() => format!("{}.gamepadAxis({})", DEVICES_GLOBAL, code),
+        _ => format!("{}.gamepadButton({})", DEVICES_GLOBAL, code),
+    }
+}
+
+impl std::fmt::Display for InputSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}.{}", self.device, self.name)
+    }
+}
+
+impl std::fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InputBinding::Source(source) => write!(f, "{}", source),
+            InputBinding::Axis(negative, positive) => write!(f, "axis({}, {})", negative, positive),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+// Layout model: the size, alignment and field offsets the C++ compiler gives generated structs.
+// Fields are laid out in declaration order, each at the next multiple of its alignment, and a
+// struct's size is rounded up to its largest field alignment (the C/C++ rule on every ABI we
+// target). Types whose layout belongs to the C++ standard library (string, arrays, fn types)
+// differ between libstdc++, libc++ and MSVC, so those come from the target ABI.
+
+use std::collections::HashMap;
+
+use crate::ast::*;
+use crate::formatter::type_str;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub size: usize,
+    pub align: usize,
+}
+
+impl Layout {
+    const fn new(size: usize, align: usize) -> Self {
+        Self { size, align }
+    }
+}
+
+/// The C++ standard library generated code is built against (`--target-abi`); all are 64-bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetAbi {
+    Gnu,   // libstdc++ (GCC, and Clang on Linux by default)
+    Llvm,  // libc++ (Apple Clang, Android NDK)
+    Msvc,  // Microsoft STL (release builds; debug iterators make containers larger)
+}
+
+impl TargetAbi {
+    pub const NAMES: &'static [&'static str] = &["gnu", "llvm", "msvc"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gnu" => Some(TargetAbi::Gnu),
+            "llvm" => Some(TargetAbi::Llvm),
+            "msvc" => Some(TargetAbi::Msvc),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetAbi::Gnu => "gnu",
+            TargetAbi::Llvm => "llvm",
+            TargetAbi::Msvc => "msvc",
+        }
+    }
+
+    /// The standard library the compiler's own platform normally uses
+    pub fn host() -> Self {
+        if cfg!(target_env = "msvc") {
+            TargetAbi::Msvc
+        } else if cfg!(any(target_os = "macos", target_os = "ios", target_os = "android")) {
+            TargetAbi::Llvm
+        } else {
+            TargetAbi::Gnu
+        }
+    }
+
+    // std::string keeps a short-string buffer inline; libc++ packs it into 24 bytes
+    fn string(self) -> Layout {
+        match self {
+            TargetAbi::Gnu | TargetAbi::Msvc => Layout::new(32, 8),
+            TargetAbi::Llvm => Layout::new(24, 8),
+        }
+    }
+
+    // std::vector is three pointers everywhere
+    fn vector(self) -> Layout {
+        Layout::new(24, 8)
+    }
+
+    // std::function stores small callables inline, in a buffer each library sizes differently
+    fn function(self) -> Layout {
+        match self {
+            TargetAbi::Gnu => Layout::new(32, 8),
+            TargetAbi::Llvm => Layout::new(48, 16),
+            TargetAbi::Msvc => Layout::new(64, 8),
+        }
+    }
+}
+
+/// Struct and component definitions by name, as the type checker and codegen know them
+pub struct LayoutEngine<'a> {
+    structs: &'a HashMap<String, StructDef>,
+    components: &'a HashMap<String, ComponentDef>,
+    abi: TargetAbi,
+}
+
+impl<'a> LayoutEngine<'a> {
+    pub fn new(structs: &'a HashMap<String, StructDef>, components: &'a HashMap<String, ComponentDef>, abi: TargetAbi) -> Self {
+        Self { structs, components, abi }
+    }
+
+    /// Size and alignment of `ty`, or why it has no compile-time layout
+    pub fn layout(&self, ty: &Type) -> Result<Layout, String> {
+        self.layout_at_depth(ty, 0)
+    }
+
+    /// Byte offset of `field` in the struct or component `ty`
+    pub fn offset_of(&self, ty: &Type, field: &str) -> Result<usize, String> {
+        let fields = self.fields(ty)?;
+        let (offsets, _) = self.field_offsets(fields, 0)?;
+        fields.iter().position(|f| f.name == field).map(|i| offsets[i])
+            .ok_or_else(|| format!("'{}' has no field '{}'", type_str(ty), field))
+    }
+
+    /// Offsets of a struct's fields, in declaration order, with the layout of the whole struct
+    pub fn field_offsets(&self, fields: &[Field], depth: usize) -> Result<(Vec<usize>, Layout), String> {
+        let mut offsets = Vec::with_capacity(fields.len());
+        let mut end = 0;
+        let mut align = 1;
+        for field in fields {
+            let layout = self.layout_at_depth(&field.ty, depth + 1)
+                .map_err(|reason| format!("field '{}': {}", field.name, reason))?;
+            let offset = align_up(end, layout.align);
+            offsets.push(offset);
+            end = offset + layout.size;
+            align = align.max(layout.align);
+        }
+        // An empty struct still occupies a byte in C++
+        Ok((offsets, Layout::new(align_up(end.max(1), align), align)))
+    }
+
+    /// Whether the C++ type is standard-layout, which offsetof needs: standard library members
+    /// (string, std::vector, std::function) aren't on every ABI
+    pub fn is_standard_layout(&self, ty: &Type) -> bool {
+        self.is_standard_layout_at_depth(ty, 0)
+    }
+
+    fn is_standard_layout_at_depth(&self, ty: &Type, depth: usize) -> bool {
+        match ty {
+            Type::String | Type::Array(_) | Type::Function(..) => false,
+            Type::Optional(inner) => self.is_standard_layout_at_depth(inner, depth + 1),
+            Type::Struct(_) | Type::Component(_) if depth <= 64 => self.fields(ty)
+                .map_or(true, |fields| fields.iter().all(|f| self.is_standard_layout_at_depth(&f.ty, depth + 1))),
+            _ => true,
+        }
+    }
+
+    fn fields(&self, ty: &Type) -> Result<&'a [Field], String> {
+        let name = match ty {
+            Type::Struct(name) | Type::Component(name) => name,
+            _ => return Err(format!("'{}' has no fields", type_str(ty))),
+        };
+        if let Some(s) = self.structs.get(name) {
+            return Ok(&s.fields);
+        }
+        match self.components.get(name) {
+            Some(c) if c.is_soa => Err(format!("component_soa '{}' is stored as one array per field, not as a struct", name)),
+            Some(c) => Ok(&c.fields),
+            None => Err(format!("unknown type '{}'", name)),
+        }
+    }
+
+    fn layout_at_depth(&self, ty: &Type, depth: usize) -> Result<Layout, String> {
+        // A struct can't contain itself by value; stop instead of recursing forever
+        if depth > 64 {
+            return Err("the type contains itself".to_string());
+        }
+        match ty {
+            Type::I32 | Type::F32 | Type::GLFWbool => Ok(Layout::new(4, 4)),
+            Type::I64 | Type::F64 => Ok(Layout::new(8, 8)),
+            Type::Bool => Ok(Layout::new(1, 1)),
+            // stdlib/math.h: plain floats (Mat4 keeps a glm::mat4 and a float[16] copy)
+            Type::Vec2 => Ok(Layout::new(8, 4)),
+            Type::Vec3 => Ok(Layout::new(12, 4)),
+            Type::Vec4 => Ok(Layout::new(16, 4)),
+            Type::Mat4 => Ok(Layout::new(128, 4)),
+            // std::optional<T> stores the value followed by its engaged flag on every standard library
+            Type::Optional(inner) => {
+                let inner = self.layout_at_depth(inner, depth + 1)?;
+                Ok(Layout::new(align_up(inner.size + 1, inner.align), inner.align))
+            }
+            Type::Struct(_) | Type::Component(_) => {
+                let fields = self.fields(ty)?;
+                Ok(self.field_offsets(fields, depth)?.1)
+            }
+            Type::String => Ok(self.abi.string()),
+            Type::Array(_) => Ok(self.abi.vector()),
+            Type::Function(..) => Ok(self.abi.function()),
+            Type::Error => Ok(Layout::new(0, 1)),
+            _ => Err(format!("'{}' has no fixed layout", type_str(ty))),
+        }
+    }
+}
+
+/// Vulkan format of a vertex attribute of this type (`vertex_input` in a pipeline); one shader
+/// location each. Mat4 isn't one: it spans four locations and stdlib/math.h stores extra data in it.
+pub fn vertex_format(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::F32 => Some("VK_FORMAT_R32_SFLOAT"),
+        Type::F64 => Some("VK_FORMAT_R64_SFLOAT"),
+        Type::I32 => Some("VK_FORMAT_R32_SINT"),
+        Type::I64 => Some("VK_FORMAT_R64_SINT"),
+        Type::Vec2 => Some("VK_FORMAT_R32G32_SFLOAT"),
+        Type::Vec3 => Some("VK_FORMAT_R32G32B32_SFLOAT"),
+        Type::Vec4 => Some("VK_FORMAT_R32G32B32A32_SFLOAT"),
+        _ => None,
+    }
+}
+
+/// Push constant bytes every Vulkan device has room for (the minimum maxPushConstantsSize)
+pub const MAX_PUSH_CONSTANT_BYTES: usize = 128;
+
+/// Offsets of a push_constants block's fields as shaders read them (GLSL std430: vec3, vec4 and
+/// mat4 align to 16 bytes and bool is 4), and the block's size: the end of its last field
+pub fn push_constant_offsets(fields: &[Field]) -> Result<(Vec<usize>, usize), String> {
+    shader_block_offsets(fields, "be a push constant")
+}
+
+/// Offsets of a uniform block's fields under GLSL std140, and the block's size rounded up to 16
+/// bytes. For scalars, vectors and Mat4 std140 places fields where std430 does; it only differs
+/// for arrays and nested structs, which uniform blocks don't hold.
+pub fn uniform_offsets(fields: &[Field]) -> Result<(Vec<usize>, usize), String> {
+    let (offsets, end) = shader_block_offsets(fields, "be in a uniform block")?;
+    Ok((offsets, align_up(end, 16)))
+}
+
+fn shader_block_offsets(fields: &[Field], usage: &str) -> Result<(Vec<usize>, usize), String> {
+    let mut offsets = Vec::with_capacity(fields.len());
+    let mut end = 0;
+    for field in fields {
+        let layout = match &field.ty {
+            Type::F32 | Type::I32 | Type::Bool => Layout::new(4, 4),
+            Type::F64 | Type::I64 | Type::Vec2 => Layout::new(8, 8),
+            Type::Vec3 => Layout::new(12, 16),
+            Type::Vec4 => Layout::new(16, 16),
+            Type::Mat4 => Layout::new(64, 16),
+            ty => return Err(format!("field '{}': '{}' can't {}", field.name, type_str(ty), usage)),
+        };
+        let offset = align_up(end, layout.align);
+        offsets.push(offset);
+        end = offset + layout.size;
+    }
+    Ok((offsets, end))
+}
+
+fn align_up(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
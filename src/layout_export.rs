@@ -0,0 +1,250 @@
+// --emit=layouts: the @[export] structs and components as a C header, GLSL declarations and a
+// JSON description, so C tools, shaders and network peers read HEIDIC data without redeclaring it.
+// Exported fields are limited to types whose bytes mean the same thing in all three: numbers,
+// vectors and other exported types. The GLSL uses scalar block layout (GL_EXT_scalar_block_layout),
+// the one GLSL layout that packs vec3 and nested structs the way C does.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::*;
+use crate::formatter::type_str;
+use crate::layout::{Layout, LayoutEngine, TargetAbi};
+
+/// Why `ty` can't be the type of an exported field, or None if it can
+pub fn unexportable_reason(ty: &Type, is_exported: impl Fn(&str) -> bool) -> Option<String> {
+    match ty {
+        Type::I32 | Type::I64 | Type::F32 | Type::F64 | Type::Vec2 | Type::Vec3 | Type::Vec4 => None,
+        Type::Bool => Some("bool is 1 byte in C and 4 bytes in GLSL".to_string()),
+        Type::Mat4 => Some("Mat4 is 128 bytes in generated C++ (a glm::mat4 and a float[16] copy) but 64 in GLSL".to_string()),
+        Type::Struct(name) | Type::Component(name) if !is_exported(name) => {
+            Some(format!("'{}' isn't marked @[export]", name))
+        }
+        Type::Struct(_) | Type::Component(_) => None,
+        Type::String | Type::Array(_) | Type::Function(..) | Type::Optional(_) => {
+            Some(format!("'{}' is laid out by the C++ standard library", type_str(ty)))
+        }
+        _ => Some(format!("'{}' has no fixed layout", type_str(ty))),
+    }
+}
+
+struct ExportedField {
+    name: String,
+    ty: Type,
+    offset: usize,
+    layout: Layout,
+}
+
+struct ExportedType {
+    name: String,
+    kind: &'static str,  // "struct" or "component"
+    layout: Layout,
+    fields: Vec<ExportedField>,
+}
+
+/// The exported types of a checked program with their layouts, dependencies first
+pub struct LayoutExport {
+    source: String,  // Source file name, for the generated-file banners
+    abi: TargetAbi,
+    types: Vec<ExportedType>,
+}
+
+impl LayoutExport {
+    pub fn new(program: &Program, source: &str, abi: TargetAbi) -> Result<Self, String> {
+        let mut structs = HashMap::new();
+        let mut components = HashMap::new();
+        let mut exported = Vec::new();
+        for item in &program.items {
+            match item {
+                Item::Struct(s) => {
+                    structs.insert(s.name.clone(), s.clone());
+                    if s.is_export {
+                        exported.push((s.name.clone(), "struct"));
+                    }
+                }
+                Item::Component(c) => {
+                    components.insert(c.name.clone(), c.clone());
+                    if c.is_export {
+                        exported.push((c.name.clone(), "component"));
+                    }
+                }
+                _ => {}
+            }
+        }
+        let engine = LayoutEngine::new(&structs, &components, abi);
+        let kinds: HashMap<&str, &'static str> = exported.iter().map(|(name, kind)| (name.as_str(), *kind)).collect();
+
+        // C needs a struct declared before another struct holds it by value
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for (name, _) in &exported {
+            visit(name, &kinds, &structs, &components, &mut visited, &mut order);
+        }
+
+        let mut types = Vec::new();
+        for name in order {
+            let fields = match structs.get(&name) {
+                Some(s) => &s.fields,
+                None => &components[&name].fields,
+            };
+            let (offsets, layout) = engine.field_offsets(fields, 0)
+                .map_err(|reason| format!("'{}': {}", name, reason))?;
+            let mut exported_fields = Vec::new();
+            for (field, offset) in fields.iter().zip(offsets) {
+                let field_layout = engine.layout(&field.ty)
+                    .map_err(|reason| format!("'{}.{}': {}", name, field.name, reason))?;
+                exported_fields.push(ExportedField { name: field.name.clone(), ty: field.ty.clone(), offset, layout: field_layout });
+            }
+            types.push(ExportedType { kind: kinds[name.as_str()], name, layout, fields: exported_fields });
+        }
+        Ok(Self { source: source.to_string(), abi, types })
+    }
+
+    /// C11 / C++ header: one struct per exported type, with its size and offsets asserted
+    pub fn c_header(&self) -> String {
+        let guard = format!("HEIDIC_LAYOUTS_{}_H", identifier(&self.source).to_uppercase());
+        let mut output = format!("// Generated by heidic_v2 --emit=layouts from {} (target ABI: {}). Do not edit.\n", self.source, self.abi.name());
+        output.push_str(&format!("#ifndef {}\n#define {}\n\n", guard, guard));
+        output.push_str("#include <stddef.h>\n#include <stdint.h>\n\n");
+        output.push_str("#ifdef __cplusplus\n#define HEIDIC_LAYOUT_ASSERT(cond, msg) static_assert(cond, msg)\n");
+        output.push_str("#else\n#define HEIDIC_LAYOUT_ASSERT(cond, msg) _Static_assert(cond, msg)\n#endif\n\n");
+
+        // stdlib/math.h vectors are plain floats
+        if self.uses(|ty| matches!(ty, Type::Vec2 | Type::Vec3 | Type::Vec4)) {
+            output.push_str("#ifndef HEIDIC_VECTOR_TYPES\n#define HEIDIC_VECTOR_TYPES\n");
+            output.push_str("typedef struct heidic_vec2 { float x, y; } heidic_vec2;\n");
+            output.push_str("typedef struct heidic_vec3 { float x, y, z; } heidic_vec3;\n");
+            output.push_str("typedef struct heidic_vec4 { float x, y, z, w; } heidic_vec4;\n");
+            output.push_str("#endif\n\n");
+        }
+
+        for ty in &self.types {
+            output.push_str(&format!("// {} {}: {} bytes, aligned to {}\n", ty.kind, ty.name, ty.layout.size, ty.layout.align));
+            output.push_str(&format!("typedef struct {} {{\n", ty.name));
+            for field in &ty.fields {
+                output.push_str(&format!("    {} {};  // offset {}\n", c_type(&field.ty), field.name, field.offset));
+            }
+            output.push_str(&format!("}} {};\n", ty.name));
+            output.push_str(&format!("HEIDIC_LAYOUT_ASSERT(sizeof({}) == {}, \"{} layout differs from HEIDIC's\");\n", ty.name, ty.layout.size, ty.name));
+            for field in &ty.fields {
+                output.push_str(&format!("HEIDIC_LAYOUT_ASSERT(offsetof({}, {}) == {}, \"{}.{} offset differs from HEIDIC's\");\n",
+                    ty.name, field.name, field.offset, ty.name, field.name));
+            }
+            output.push('\n');
+        }
+        output.push_str(&format!("#endif // {}\n", guard));
+        output
+    }
+
+    /// GLSL struct declarations, plus a storage block for each type whose binding the shader defines
+    pub fn glsl(&self) -> String {
+        let mut output = format!("// Generated by heidic_v2 --emit=layouts from {}. Do not edit.\n", self.source);
+        output.push_str("// Blocks holding these structs must use layout(scalar) to match the C and C++ layouts.\n");
+        output.push_str("// Define HEIDIC_<TYPE>_BINDING (and optionally HEIDIC_<TYPE>_SET) before including this file\n");
+        output.push_str("// to declare a storage buffer of that type.\n");
+        output.push_str("#extension GL_EXT_scalar_block_layout : require\n");
+        if self.uses(|ty| matches!(ty, Type::I64)) {
+            output.push_str("#extension GL_EXT_shader_explicit_arithmetic_types_int64 : require\n");
+        }
+        output.push('\n');
+
+        for ty in &self.types {
+            output.push_str(&format!("// {} {}: {} bytes\n", ty.kind, ty.name, ty.layout.size));
+            output.push_str(&format!("struct {} {{\n", ty.name));
+            for field in &ty.fields {
+                output.push_str(&format!("    {} {};  // offset {}\n", glsl_type(&field.ty), field.name, field.offset));
+            }
+            output.push_str("};\n");
+            let macro_name = format!("HEIDIC_{}", identifier(&ty.name).to_uppercase());
+            output.push_str(&format!("#ifdef {}_BINDING\n", macro_name));
+            output.push_str(&format!("#ifndef {}_SET\n#define {}_SET 0\n#endif\n", macro_name, macro_name));
+            output.push_str(&format!("layout(scalar, set = {m}_SET, binding = {m}_BINDING) buffer {n}Buffer {{\n    {n} {}_data[];\n}};\n",
+                identifier(&ty.name).to_lowercase(), m = macro_name, n = ty.name));
+            output.push_str("#endif\n\n");
+        }
+        output
+    }
+
+    /// Sizes, alignments and field offsets for tools and network peers
+    pub fn json(&self) -> serde_json::Value {
+        let types: Vec<serde_json::Value> = self.types.iter().map(|ty| serde_json::json!({
+            "name": ty.name,
+            "kind": ty.kind,
+            "size": ty.layout.size,
+            "align": ty.layout.align,
+            "fields": ty.fields.iter().map(|field| serde_json::json!({
+                "name": field.name,
+                "type": type_str(&field.ty),
+                "offset": field.offset,
+                "size": field.layout.size,
+                "align": field.layout.align,
+            })).collect::<Vec<_>>(),
+        })).collect();
+        serde_json::json!({
+            "source": self.source,
+            "target_abi": self.abi.name(),
+            "types": types,
+        })
+    }
+
+    fn uses(&self, predicate: impl Fn(&Type) -> bool) -> bool {
+        self.types.iter().flat_map(|ty| &ty.fields).any(|field| predicate(&field.ty))
+    }
+}
+
+// Depth-first: the exported types `name` holds by value, then `name` itself
+fn visit(
+    name: &str,
+    kinds: &HashMap<&str, &'static str>,
+    structs: &HashMap<String, StructDef>,
+    components: &HashMap<String, ComponentDef>,
+    visited: &mut HashSet<String>,
+    order: &mut Vec<String>,
+) {
+    if !kinds.contains_key(name) || !visited.insert(name.to_string()) {
+        return;
+    }
+    let fields = match (structs.get(name), components.get(name)) {
+        (Some(s), _) => &s.fields,
+        (None, Some(c)) => &c.fields,
+        (None, None) => return,
+    };
+    for field in fields {
+        if let Type::Struct(inner) | Type::Component(inner) = &field.ty {
+            visit(inner, kinds, structs, components, visited, order);
+        }
+    }
+    order.push(name.to_string());
+}
+
+fn c_type(ty: &Type) -> String {
+    match ty {
+        Type::I32 => "int32_t".to_string(),
+        Type::I64 => "int64_t".to_string(),
+        Type::F32 => "float".to_string(),
+        Type::F64 => "double".to_string(),
+        Type::Vec2 => "heidic_vec2".to_string(),
+        Type::Vec3 => "heidic_vec3".to_string(),
+        Type::Vec4 => "heidic_vec4".to_string(),
+        Type::Struct(name) | Type::Component(name) => name.clone(),
+        _ => type_str(ty),
+    }
+}
+
+fn glsl_type(ty: &Type) -> String {
+    match ty {
+        Type::I32 => "int".to_string(),
+        Type::I64 => "int64_t".to_string(),
+        Type::F32 => "float".to_string(),
+        Type::F64 => "double".to_string(),
+        Type::Vec2 => "vec2".to_string(),
+        Type::Vec3 => "vec3".to_string(),
+        Type::Vec4 => "vec4".to_string(),
+        Type::Struct(name) | Type::Component(name) => name.clone(),
+        _ => type_str(ty),
+    }
+}
+
+// A file or type name as a C identifier: game.hd -> game_hd
+fn identifier(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
@@ -1,15 +1,21 @@
 use logos::Logos;
-use anyhow::{Result, bail};
+use anyhow::Result;
+use serde::Serialize;
 
-#[derive(Logos, Debug, PartialEq, Clone)]
+#[derive(Logos, Debug, PartialEq, Clone, Serialize)]
 #[logos(skip r"[ \t\n\r]+")]
-#[logos(skip r"//[^\n]*")]
 pub enum Token {
     // Keywords
     #[token("fn")]
     Fn,
     #[token("let")]
     Let,
+    #[token("const")]
+    Const,
+    #[token("as")]
+    As,
+    #[token("mut")]
+    Mut,
     #[token("if")]
     If,
     #[token("else")]
@@ -20,6 +26,10 @@ pub enum Token {
     Loop,
     #[token("return")]
     Return,
+    #[token("break")]
+    Break,
+    #[token("continue")]
+    Continue,
     #[token("defer")]
     Defer,
     #[token("struct")]
@@ -68,6 +78,10 @@ pub enum Token {
     Binding,
     #[token("layout")]
     Layout,
+    #[token("interface")]
+    Interface,
+    #[token("impl")]
+    Impl,
     
     // Attributes
     #[token("@hot")]
@@ -152,14 +166,24 @@ pub enum Token {
     Int(i64),
     #[regex(r"-?\d+\.\d+", |lex| lex.slice().parse().ok())]
     Float(f64),
+    // Literal with a type suffix (10i64, 1.5f32): digits and suffix, split by the parser
+    #[regex(r"-?\d+(\.\d+)?(i32|i64|f32|f64)", |lex| {
+        let (digits, suffix) = lex.slice().split_at(lex.slice().len() - 3);
+        (digits.to_string(), suffix.to_string())
+    })]
+    SuffixedNumber((String, String)),
     #[token("true")]
     True,
     #[token("false")]
     False,
     #[token("null")]
     Null,
-    #[regex(r#""[^"]*""#, |lex| lex.slice()[1..lex.slice().len()-1].to_string())]
+    // Escapes are kept as written (\n stays two characters); \" doesn't end the literal
+    #[regex(r#""([^"\\]|\\.)*""#, |lex| lex.slice()[1..lex.slice().len()-1].to_string())]
     StringLit(String),
+    // Inline shader source: `glsl { ... }` up to its matching brace, kept verbatim
+    #[regex(r"glsl[ \t\r\n]*\{", glsl_block)]
+    GlslBlock(String),
     
     // Identifiers
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
@@ -192,6 +216,8 @@ pub enum Token {
     AndAnd,
     #[token("||")]
     OrOr,
+    #[token("|")]
+    Pipe,  // Separates the alternatives of a match pattern: 1 | 2 | 3
     #[token("!")]
     Bang,
     #[token("=")]
@@ -220,13 +246,80 @@ pub enum Token {
     Semicolon,
     #[token(".")]
     Dot,
+    #[token("..")]
+    DotDot,  // Range pattern, end excluded: 0..10
+    #[token("..=")]
+    DotDotEq,  // Range pattern, end included: 0..=9
+    
+    // Trivia (kept out of the parser's token stream, see Lexer::comments)
+    #[regex(r"//[^\n]*", |lex| lex.slice().to_string())]
+    Comment(String),
+    
+    // End of input: what the parser sees past the last token (never produced by the lexer)
+    Eof,
+}
+
+// The GLSL between `glsl {` and its matching `}`; braces inside comments don't count
+fn glsl_block(lex: &mut logos::Lexer<Token>) -> Option<String> {
+    let text = lex.remainder();
+    let bytes = text.as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = text[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+                continue;
+            }
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let glsl = text[..i].to_string();
+                    lex.bump(i + 1);
+                    return Some(glsl);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None  // Unterminated: a lex error at `glsl {`
 }
 
 pub struct Lexer {
     source: String,
+    comments: Vec<Comment>,
+    file: usize,  // SourceLocation::file of everything lexed
 }
 
+/// A `//` comment from the trivia channel (used by the formatter to reproduce comments)
 #[derive(Debug, Clone)]
+pub struct Comment {
+    pub text: String,  // Including the leading `//`
+    pub location: crate::error::SourceLocation,
+    pub trailing: bool,  // true if code precedes it on the same line
+}
+
+/// Source text that doesn't form a valid token (carries the location for tooling)
+#[derive(Debug)]
+pub struct LexError {
+    pub location: crate::error::SourceLocation,
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Lexical error at {}:{}", self.location.line, self.location.column)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct TokenWithLocation {
     pub token: Token,
     pub location: crate::error::SourceLocation,
@@ -236,9 +329,21 @@ impl Lexer {
     pub fn new(source: &str) -> Self {
         Self {
             source: source.to_string(),
+            comments: Vec::new(),
+            file: 0,
         }
     }
     
+    /// Lex one of several files of a program: locations carry its index in the ErrorReporter
+    pub fn set_file(&mut self, file: usize) {
+        self.file = file;
+    }
+    
+    fn location(&self, byte_pos: usize) -> crate::error::SourceLocation {
+        let (line, column) = self.byte_to_line_column(byte_pos);
+        crate::error::SourceLocation { file: self.file, ..crate::error::SourceLocation::new(line, column) }
+    }
+    
     fn byte_to_line_column(&self, byte_pos: usize) -> (usize, usize) {
         let mut line = 1;
         let mut column = 1;
@@ -262,24 +367,33 @@ impl Lexer {
         (line, column)
     }
     
+    /// Comments seen by the last tokenize() call, in source order
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+    
     pub fn tokenize(&mut self) -> Result<Vec<TokenWithLocation>> {
         let mut lexer = Token::lexer(&self.source);
-        let mut tokens = Vec::new();
+        let mut tokens: Vec<TokenWithLocation> = Vec::new();
+        self.comments.clear();
         
         while let Some(token_result) = lexer.next() {
             match token_result {
-                Ok(token) => {
-                    let span = lexer.span();
-                    let (line, column) = self.byte_to_line_column(span.start);
-                    tokens.push(TokenWithLocation {
-                        token,
-                        location: crate::error::SourceLocation::new(line, column),
+                Ok(Token::Comment(text)) => {
+                    let location = self.location(lexer.span().start);
+                    let trailing = tokens.last().is_some_and(|t| t.location.line == location.line);
+                    self.comments.push(Comment {
+                        text: text.trim_end().to_string(),
+                        location,
+                        trailing,
                     });
                 }
+                Ok(token) => {
+                    let location = self.location(lexer.span().start);
+                    tokens.push(TokenWithLocation { token, location });
+                }
                 Err(_) => {
-                    let span = lexer.span();
-                    let (line, column) = self.byte_to_line_column(span.start);
-                    bail!("Lexical error at {}:{}", line, column);
+                    return Err(LexError { location: self.location(lexer.span().start) }.into());
                 }
             }
         }
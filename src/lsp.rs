@@ -0,0 +1,441 @@
+// Language Server Protocol support for HEIDIC (`heidic_v2 lsp`)
+// Speaks JSON-RPC over stdio and reuses the lexer, parser and type checker to provide
+// diagnostics, go-to-definition (functions, components, structs, interfaces, ...) and hover types.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::ast::*;
+use crate::error::{Diagnostic, SourceLocation};
+use crate::formatter::{expression_str, field_attributes, texture_options};
+use crate::input_map;
+use crate::lexer::{LexError, Lexer, Token};
+use crate::parser::Parser;
+use crate::type_checker::TypeChecker;
+
+// LSP DiagnosticSeverity / TextDocumentSyncKind values
+const SEVERITY_ERROR: u32 = 1;
+const SEVERITY_WARNING: u32 = 2;
+const SYNC_FULL: u32 = 1;
+
+// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DefinitionKind {
+    Function,
+    Component,
+    Struct,
+    Interface,
+    System,
+    Resource,
+    Pipeline,
+}
+
+struct Definition {
+    name: String,
+    kind: DefinitionKind,
+    location: SourceLocation,
+}
+
+// Everything the server knows about one open document
+struct Analysis {
+    diagnostics: Vec<Diagnostic>,
+    definitions: Vec<Definition>,
+    program: Option<Program>,
+    type_checker: Option<TypeChecker>,
+}
+
+impl Analysis {
+    fn new(source: &str) -> Self {
+        let mut analysis = Analysis {
+            diagnostics: Vec::new(),
+            definitions: Vec::new(),
+            program: None,
+            type_checker: None,
+        };
+        
+        let mut lexer = Lexer::new(source);
+        let tokens = match lexer.tokenize() {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                let location = e.downcast_ref::<LexError>()
+                    .map(|lex| lex.location)
+                    .unwrap_or_else(SourceLocation::unknown);
+                analysis.diagnostics.push(Diagnostic::new(location, "Invalid token".to_string(), None));
+                return analysis;
+            }
+        };
+        
+        // Definitions come from the token stream so they're available even when parsing fails
+        for pair in tokens.windows(2) {
+            let kind = match pair[0].token {
+                Token::Fn => DefinitionKind::Function,
+                Token::Component | Token::ComponentSOA => DefinitionKind::Component,
+                Token::Struct => DefinitionKind::Struct,
+                Token::Interface => DefinitionKind::Interface,
+                Token::System => DefinitionKind::System,
+                Token::Resource => DefinitionKind::Resource,
+                Token::Pipeline => DefinitionKind::Pipeline,
+                _ => continue,
+            };
+            if let Token::Ident(ref name) = pair[1].token {
+                analysis.definitions.push(Definition { name: name.clone(), kind, location: pair[1].location });
+            }
+        }
+        
+        let mut parser = Parser::new(tokens);
+        parser.set_comments(lexer.comments());
+        let program = match parser.parse() {
+            Ok(program) => program,
+            Err(e) => {
+                if parser.errors().is_empty() {
+                    analysis.diagnostics.push(Diagnostic::new(SourceLocation::unknown(), e.to_string(), None));
+                } else {
+                    analysis.diagnostics.extend(parser.errors().iter().cloned());
+                }
+                return analysis;
+            }
+        };
+        
+        let mut type_checker = TypeChecker::new();
+        type_checker.set_item_locations(parser.item_locations().to_vec());
+        // Errors are collected on the checker; the returned summary error adds nothing
+        let _ = type_checker.check(&program);
+        analysis.diagnostics.extend(type_checker.errors().iter().cloned());
+        analysis.program = Some(program);
+        analysis.type_checker = Some(type_checker);
+        analysis
+    }
+    
+    fn hover_text(&self, source: &str, word: &str, line: usize) -> Option<String> {
+        let program = self.program.as_ref()?;
+        let checker = self.type_checker.as_ref()?;
+        let signature = |name: &str, params: &[Param], return_type: &Type| {
+            let params: Vec<String> = params.iter()
+                .map(|p| match &p.default {
+                    _ if p.name == "self" => "self".to_string(),
+                    Some(default) => format!("{}: {} = {}", p.name, checker.type_to_string(&p.ty), expression_str(source, default)),
+                    None => format!("{}: {}", p.name, checker.type_to_string(&p.ty)),
+                })
+                .collect();
+            format!("fn {}({}): {}", name, params.join(", "), checker.type_to_string(return_type))
+        };
+        let fields = |keyword: &str, name: &str, fields: &[Field]| {
+            let mut text = format!("{} {} {{\n", keyword, name);
+            for field in fields {
+                for line in field.doc.iter().flat_map(|doc| doc.lines()) {
+                    text.push_str(&format!("    /// {}\n", line));
+                }
+                text.push_str(&format!("    {}{}: {},\n", field_attributes(field), field.name, checker.type_to_string(&field.ty)));
+            }
+            text.push('}');
+            text
+        };
+        
+        for item in &program.items {
+            let text = match item {
+                Item::Function(f) if f.name == word => signature(&f.name, &f.params, &f.return_type),
+                Item::ExternFunction(f) if f.name == word => format!("extern {}", signature(&f.name, &f.params, &f.return_type)),
+                Item::System(s) => match s.functions.iter().find(|f| f.name == word) {
+                    Some(f) => format!("{}\n// in system {}", signature(&f.name, &f.params, &f.return_type), s.name),
+                    None => continue,
+                },
+                Item::Component(c) if c.name == word => {
+                    let doc: String = c.doc.iter().flat_map(|doc| doc.lines()).map(|line| format!("/// {}\n", line)).collect();
+                    doc + &fields(if c.is_soa { "component_soa" } else { "component" }, &c.name, &c.fields)
+                }
+                Item::Struct(s) if s.name == word => fields("struct", &s.name, &s.fields),
+                Item::Interface(iface) if iface.name == word => {
+                    let mut text = format!("interface {} {{\n", iface.name);
+                    for method in &iface.methods {
+                        text.push_str(&format!("    {};\n", signature(&method.name, &method.params, &method.return_type)));
+                    }
+                    text.push('}');
+                    text
+                }
+                Item::Interface(iface) => match iface.methods.iter().find(|m| m.name == word) {
+                    Some(m) => format!("{}\n// in interface {}", signature(&m.name, &m.params, &m.return_type), iface.name),
+                    None => continue,
+                },
+                Item::Resource(res) if res.name == word => {
+                    let options = res.texture_options.map(|options| format!(" {}", texture_options(&options))).unwrap_or_default();
+                    let spatial = if res.is_spatial { "@[spatial] " } else { "" };
+                    format!("{}resource {}: {} = \"{}\"{}", spatial, res.name, res.resource_type, res.path, options)
+                }
+                Item::Input(input) => match input.actions.iter().find(|a| input_map::accessors(a).iter().any(|accessor| accessor == word)) {
+                    Some(action) => {
+                        let bindings: Vec<String> = action.bindings.iter().map(|binding| binding.to_string()).collect();
+                        let (kind, ty) = if action.is_axis() { ("axis", "f32") } else { ("button", "bool") };
+                        format!("{}: {};\n// input {}: input.{}() -> {}", action.name, bindings.join(" | "), kind, word, ty)
+                    }
+                    None => continue,
+                },
+                Item::GpuQuery(query) if query.name == word => {
+                    let kind = match &query.kind {
+                        GpuQueryKind::Occlusion => "Occlusion".to_string(),
+                        GpuQueryKind::Timestamp => "Timestamp".to_string(),
+                        GpuQueryKind::PipelineStatistics(statistic, _) => format!("PipelineStatistics({})", statistic),
+                    };
+                    format!("gpu_query {} {}[{}]", kind, query.name, query.count)
+                }
+                _ => continue,
+            };
+            return Some(text);
+        }
+        
+        // Local variables: the closest let binding between the enclosing function and the cursor
+        let enclosing = self.definitions.iter()
+            .filter(|d| d.kind == DefinitionKind::Function && d.location.line <= line)
+            .max_by_key(|d| d.location.line)?;
+        let local = checker.declarations().iter()
+            .filter(|(location, name, _)| name == word && location.line <= line && location.line >= enclosing.location.line)
+            .max_by_key(|(location, _, _)| location.line);
+        if let Some((_, name, ty)) = local {
+            return Some(format!("let {}: {}", name, checker.type_to_string(ty)));
+        }
+        
+        // Parameters of the enclosing function
+        let params = program.items.iter().find_map(|item| match item {
+            Item::Function(f) if f.name == enclosing.name => Some(&f.params),
+            Item::System(s) => s.functions.iter().find(|f| f.name == enclosing.name).map(|f| &f.params),
+            _ => None,
+        })?;
+        let param = params.iter().find(|p| p.name == word)?;
+        Some(format!("{}: {}  // parameter of {}", param.name, checker.type_to_string(&param.ty), enclosing.name))
+    }
+}
+
+struct Server {
+    documents: HashMap<String, (String, Analysis)>,  // uri -> (text, analysis)
+    shutdown_requested: bool,
+}
+
+pub fn run() -> Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    let mut server = Server { documents: HashMap::new(), shutdown_requested: false };
+    
+    while let Some(message) = read_message(&mut input)? {
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("").to_string();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        
+        if method == "exit" {
+            // Exit code 1 if the client never asked for a shutdown (per the LSP spec)
+            std::process::exit(if server.shutdown_requested { 0 } else { 1 });
+        }
+        
+        match message.get("id").cloned() {
+            Some(id) => {
+                let response = match server.handle_request(&method, &params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err(code) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": format!("Unsupported request: {}", method) },
+                    }),
+                };
+                write_message(&mut output, &response)?;
+            }
+            None => {
+                for notification in server.handle_notification(&method, &params) {
+                    write_message(&mut output, &notification)?;
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+impl Server {
+    fn handle_request(&mut self, method: &str, params: &Value) -> std::result::Result<Value, i64> {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "positionEncoding": "utf-16",  // Columns are converted from chars (char_index, utf16_offset)
+                    "textDocumentSync": SYNC_FULL,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                },
+                "serverInfo": { "name": "heidic", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => Ok(self.hover(params).unwrap_or(Value::Null)),
+            "textDocument/definition" => Ok(self.definition(params).unwrap_or(Value::Null)),
+            _ => Err(METHOD_NOT_FOUND),
+        }
+    }
+    
+    // Returns the notifications to send back (diagnostics for changed documents)
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str().map(str::to_string),
+            // Full sync: the last change holds the whole document
+            "textDocument/didChange" => params["contentChanges"].as_array()
+                .and_then(|changes| changes.last())
+                .and_then(|change| change["text"].as_str())
+                .map(str::to_string),
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish_diagnostics(&uri, Vec::new())];
+            }
+            _ => None,
+        };
+        
+        let Some(text) = text else { return Vec::new() };
+        let analysis = Analysis::new(&text);
+        let warnings = analysis.type_checker.as_ref().map(|checker| checker.warnings()).unwrap_or_default();
+        let diagnostics = analysis.diagnostics.iter().map(|diagnostic| (diagnostic, SEVERITY_ERROR))
+            .chain(warnings.iter().map(|diagnostic| (diagnostic, SEVERITY_WARNING)))
+            .map(|(diagnostic, severity)| {
+                let message = match &diagnostic.suggestion {
+                    Some(suggestion) => format!("{}\nSuggestion: {}", diagnostic.message, suggestion),
+                    None => diagnostic.message.clone(),
+                };
+                let mut lsp_diagnostic = json!({
+                    "range": word_range(&text, diagnostic.location),
+                    "severity": severity,
+                    "source": "heidic",
+                    "message": message,
+                });
+                if let Some(code) = diagnostic.code {
+                    lsp_diagnostic["code"] = json!(code);
+                }
+                lsp_diagnostic
+            })
+            .collect();
+        self.documents.insert(uri.clone(), (text, analysis));
+        vec![publish_diagnostics(&uri, diagnostics)]
+    }
+    
+    fn hover(&self, params: &Value) -> Option<Value> {
+        let (text, analysis, line, character) = self.document_at(params)?;
+        let word = word_at(text, line, character)?;
+        let contents = analysis.hover_text(text, &word, line + 1)?;
+        Some(json!({ "contents": { "kind": "markdown", "value": format!("```heidic\n{}\n```", contents) } }))
+    }
+    
+    fn definition(&self, params: &Value) -> Option<Value> {
+        let (text, analysis, line, character) = self.document_at(params)?;
+        let word = word_at(text, line, character)?;
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let locations: Vec<Value> = analysis.definitions.iter()
+            .filter(|d| d.name == word)
+            .map(|d| json!({ "uri": uri, "range": word_range(text, d.location) }))
+            .collect();
+        if locations.is_empty() {
+            None
+        } else {
+            Some(Value::Array(locations))
+        }
+    }
+    
+    // (text, analysis, 0-based line, 0-based character) for a TextDocumentPositionParams
+    fn document_at(&self, params: &Value) -> Option<(&str, &Analysis, usize, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let (text, analysis) = self.documents.get(uri)?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        Some((text.as_str(), analysis, line, character))
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// LSP positions count UTF-16 code units; SourceLocation columns count chars. An offset inside a
+// surrogate pair rounds down to its char.
+fn char_index(line: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    line.chars().take_while(|c| {
+        units += c.len_utf16();
+        units <= utf16_offset
+    }).count()
+}
+
+// Past the end of the line, each char counts as one unit (ranges that cover the line break)
+fn utf16_offset(line: &str, char_index: usize) -> usize {
+    let units: usize = line.chars().take(char_index).map(char::len_utf16).sum();
+    units + char_index.saturating_sub(line.chars().count())
+}
+
+// Identifier under a 0-based cursor position (`character` in UTF-16 code units)
+fn word_at(text: &str, line: usize, character: usize) -> Option<String> {
+    let line = text.lines().nth(line)?;
+    let character = char_index(line, character);
+    let chars: Vec<char> = line.chars().collect();
+    let mut start = character.min(chars.len());
+    while start > 0 && is_word_char(chars[start - 1]) {
+        start -= 1;
+    }
+    let mut end = character.min(chars.len());
+    while end < chars.len() && is_word_char(chars[end]) {
+        end += 1;
+    }
+    if start == end {
+        None
+    } else {
+        Some(chars[start..end].iter().collect())
+    }
+}
+
+// LSP range covering the token at a 1-based SourceLocation (whole first line if unknown)
+fn word_range(text: &str, location: SourceLocation) -> Value {
+    if location.is_unknown() {
+        return json!({ "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } });
+    }
+    let line = location.line - 1;
+    let text = text.lines().nth(line).unwrap_or("");
+    let start = location.column.saturating_sub(1);
+    let width = text.chars().skip(start).take_while(|c| is_word_char(*c)).count().max(1);
+    json!({
+        "start": { "line": line, "character": utf16_offset(text, start) },
+        "end": { "line": line, "character": utf16_offset(text, start + width) },
+    })
+}
+
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);  // Client closed the stream
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            content_length = Some(value.trim().parse::<usize>().context("Invalid Content-Length header")?);
+        }
+    }
+    let length = content_length.context("Missing Content-Length header")?;
+    let mut body = vec![0u8; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body).context("Invalid JSON-RPC message")?))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
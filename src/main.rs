@@ -1,19 +1,54 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
+use rayon::prelude::*;
 
 mod lexer;
 mod parser;
 mod ast;
 mod type_checker;
 mod codegen;
+mod const_eval;
 mod error;
+mod lsp;
+mod formatter;
+mod cpp_style;
+mod config;
+mod stats;
+mod error_codes;
+mod workspace;
+mod schedule;
+mod layout;
+mod layout_export;
+mod resources;
+mod save_schema;
+mod scene;
+mod hierarchy;
+mod spatial_audio;
+mod input_map;
+mod frame_arena;
+mod cuda;
+mod vulkan_compute;
+mod c_codegen;
+mod wasm;
+mod build;
+mod generated_names;
+mod symbols;
+mod shaders;
+mod audit;
+mod replication;
+mod stdlib_stubs;
+mod doctor;
+mod golden;
 
 use lexer::Lexer;
 use parser::Parser;
 use type_checker::TypeChecker;
-use codegen::CodeGenerator;
-use error::ErrorReporter;
+use codegen::{CodeGenerator, CppGenerator};
+use error::{Diagnostic, ErrorReporter, Severity};
+use cpp_style::EmitStyle;
+use workspace::SharedModule;
+use layout::TargetAbi;
 
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
@@ -22,7 +57,38 @@ fn main() -> Result<()> {
         eprintln!("Usage: heidic_v2 <command> [args...]");
         eprintln!("Commands:");
         eprintln!("  compile <file>  - Compile a HEIDIC v2 source file");
+        eprintln!("  build <file>    - Compile, then build the executable with the C++ compiler (and nvcc for CUDA kernels)");
+        eprintln!("  build           - Build the program described by [build] in the nearest heidic.toml (modules, output, hot DLLs); modules are parsed in parallel, then type checked together");
         eprintln!("  run <file>      - Compile and run a HEIDIC v2 source file");
+        eprintln!("    --emit=tokens,ast,cpp,layouts,symbols  Stages to write (tokens/AST as <file>.tokens.json / <file>.ast.json)");
+        eprintln!("                     layouts: @[export] types as <file>.layouts.h / .layouts.glsl / .layouts.json");
+        eprintln!("                     symbols: <file>.symbols.json mapping generated C++ symbols to HEIDIC items (with cpp)");
+        eprintln!("    --emit-readable  Re-indent generated C++, add section banners, fold constant branches");
+        eprintln!("    --emit-compact   Strip comments and whitespace from generated C++");
+        eprintln!("    --reproducible   Byte-identical output for identical inputs (SOURCE_DATE_EPOCH for timestamps)");
+        eprintln!("    --stats-out <file.json>  Record LOC, item, attribute and feature usage for this file");
+        eprintln!("    --deny-warnings  Fail when there are warnings (unused variables, unreachable code)");
+        eprintln!("    --target-abi=gnu|llvm|msvc  C++ standard library for struct layouts (default: this platform's)");
+        eprintln!("    --ecs-storage=sparse|archetype  Entity storage: per-component sparse sets (default) or archetype columns");
+        eprintln!("    --gpu-backend=cuda|vulkan  @[launch] functions as CUDA kernels (default) or Vulkan compute shaders (any GPU)");
+        eprintln!("    --gen-stdlib-stubs  Write no-op stand-ins for stdlib/ headers missing next to the output (builds without the engine)");
+        eprintln!("    --hot-rebuild    The program recompiles its hot DLLs when the .hd or _hot.dll.cpp changes ([hot_reload] in heidic.toml)");
+        eprintln!("    --audit          List raw pointer flows (extern arrays, resource pointers, CUDA device pointers) in <file>.audit.json");
+        eprintln!("    --checked-arithmetic  Debug mode: i32/i64 overflow and division by zero stop the program at the .hd location");
+        eprintln!("    --strict-fp      Every function is @strict_fp: no fast-math or FMA contraction (lockstep, replays)");
+        eprintln!("    --runtime standard|minimal  minimal: no iostream, unordered_map, exceptions or Vulkan/GLFW headers (consoles, WASM)");
+        eprintln!("    --backend=cpp|c  Generate C++17 (default) or plain C99 <file>.c: structs, functions, interfaces; no ECS or renderer");
+        eprintln!("    --target=native|wasm  wasm: build with Emscripten for a browser (WebGPU, canvas) and write <file>.html to run it");
+        eprintln!("  test [--render] [--update-golden] <file> - Build a runner for the file's test blocks (takes compile flags)");
+        eprintln!("    --render         Also run tests that render and compare frames (offscreen window)");
+        eprintln!("    --update-golden  expect_frame() writes reference images instead of comparing");
+        eprintln!("  test-codegen [--update-golden] <dir> - Compare the C++ generated from each .hd in <dir> with <dir>/golden/ (compiler development)");
+        eprintln!("  workspace <dir> - Build every member of the [workspace] in <dir>/heidic.toml (takes compile flags)");
+        eprintln!("  lsp             - Start the language server (LSP over stdio)");
+        eprintln!("  check [--error-format=human|json] [--deny-warnings] [--target-abi=...] <file> - Report errors and warnings without generating code");
+        eprintln!("  fmt [--check] <file> - Format a HEIDIC v2 source file in place");
+        eprintln!("  explain <code>  - Explain an error or warning code (--list for all codes)");
+        eprintln!("  doctor [<file>] - Check the C++ compiler, SDKs and stdlib/ headers generated code builds with");
         return Ok(());
     }
     
@@ -30,97 +96,1282 @@ fn main() -> Result<()> {
     
     match command.as_str() {
         "compile" => {
-            if args.len() < 3 {
-                anyhow::bail!("Usage: heidic_v2 compile <file>");
-            }
-            let file_path = &args[2];
-            compile_file(file_path)?;
+            let (file_path, options) = CompileOptions::parse(&args[2..])
+                .context("Usage: heidic_v2 compile [--emit=tokens|ast|cpp|layouts|symbols] [--emit-readable|--emit-compact] [--reproducible] [--stats-out <file.json>] [--deny-warnings] [--target-abi=gnu|llvm|msvc] [--ecs-storage=sparse|archetype] [--gpu-backend=cuda|vulkan] [--gen-stdlib-stubs] [--hot-rebuild] [--audit] [--checked-arithmetic] [--strict-fp] [--runtime standard|minimal] [--backend=cpp|c] [--target=native|wasm] <file>")?;
+            compile_file(&file_path, &options)?;
+        }
+        "build" if args.len() == 2 => {
+            build_project()?;
+        }
+        "build" => {
+            let (file_path, options) = CompileOptions::parse(&args[2..])
+                .context("Usage: heidic_v2 build [compile flags] <file>, or heidic_v2 build for heidic.toml's [build]")?;
+            build_file(&file_path, &options)?;
         }
         "run" => {
-            if args.len() < 3 {
-                anyhow::bail!("Usage: heidic_v2 run <file>");
+            let (file_path, options) = CompileOptions::parse(&args[2..])
+                .context("Usage: heidic_v2 run [--emit=tokens|ast|cpp|layouts|symbols] [--emit-readable|--emit-compact] [--reproducible] [--stats-out <file.json>] [--deny-warnings] [--target-abi=gnu|llvm|msvc] [--ecs-storage=sparse|archetype] [--gpu-backend=cuda|vulkan] [--gen-stdlib-stubs] [--hot-rebuild] [--audit] [--checked-arithmetic] [--strict-fp] [--runtime standard|minimal] [--backend=cpp|c] [--target=native|wasm] <file>")?;
+            compile_and_run(&file_path, &options)?;
+        }
+        "test" => {
+            let mut test = codegen::TestOptions::default();
+            let mut rest = Vec::new();
+            for arg in &args[2..] {
+                match arg.as_str() {
+                    "--render" => test.render = true,
+                    "--update-golden" => test.update_golden = true,
+                    _ => rest.push(arg.clone()),
+                }
+            }
+            let (file_path, mut options) = CompileOptions::parse(&rest)
+                .context("Usage: heidic_v2 test [--render] [--update-golden] [compile flags] <file>")?;
+            if options.runtime == codegen::RuntimeProfile::Minimal {
+                anyhow::bail!("The test runner reports through iostream; build tests without --runtime minimal");
+            }
+            if options.backend == codegen::Backend::C {
+                anyhow::bail!("The test runner is C++; build tests without --backend=c");
+            }
+            if options.target == codegen::Target::Wasm {
+                anyhow::bail!("The test runner runs from the command line; build tests without --target=wasm");
+            }
+            options.test = Some(test);
+            compile_tests(&file_path, &options)?;
+        }
+        "test-codegen" => {
+            let update = args[2..].iter().any(|arg| arg == "--update-golden");
+            let dirs: Vec<&String> = args[2..].iter().filter(|arg| *arg != "--update-golden").collect();
+            let [dir] = dirs.as_slice() else {
+                anyhow::bail!("Usage: heidic_v2 test-codegen [--update-golden] <fixture dir>");
+            };
+            test_codegen(dir, update)?;
+        }
+        "workspace" => {
+            let (path, options) = CompileOptions::parse(&args[2..])
+                .context("Usage: heidic_v2 workspace [compile flags] <dir or heidic.toml>")?;
+            build_workspace(&path, &options)?;
+        }
+        "lsp" => {
+            lsp::run()?;
+        }
+        "check" => {
+            let mut json = false;
+            let mut deny_warnings = false;
+            let mut target_abi = TargetAbi::host();
+            let mut file_path = None;
+            for arg in &args[2..] {
+                match arg.as_str() {
+                    "--error-format=json" => json = true,
+                    "--error-format=human" => json = false,
+                    "--deny-warnings" => deny_warnings = true,
+                    flag if flag.starts_with("--target-abi=") => target_abi = parse_target_abi(flag)?,
+                    flag if flag.starts_with("--") => anyhow::bail!("Unknown option: {}", flag),
+                    _ => file_path = Some(arg),
+                }
+            }
+            let Some(file_path) = file_path else {
+                anyhow::bail!("Usage: heidic_v2 check [--error-format=human|json] [--deny-warnings] [--target-abi=gnu|llvm|msvc] <file>");
+            };
+            check_file(file_path, json, deny_warnings, target_abi)?;
+        }
+        "fmt" => {
+            let check = args[2..].iter().any(|arg| arg == "--check");
+            let Some(file_path) = args[2..].iter().find(|arg| !arg.starts_with("--")) else {
+                anyhow::bail!("Usage: heidic_v2 fmt [--check] <file>");
+            };
+            format_file(file_path, check)?;
+        }
+        "explain" => {
+            let Some(code) = args.get(2) else {
+                anyhow::bail!("Usage: heidic_v2 explain <code> (e.g. heidic_v2 explain E0008), or explain --list");
+            };
+            explain(code)?;
+        }
+        "doctor" => {
+            if args.len() > 3 {
+                anyhow::bail!("Usage: heidic_v2 doctor [<file>]");
             }
-            let file_path = &args[2];
-            compile_and_run(file_path)?;
+            run_doctor(args.get(2).map(String::as_str))?;
         }
         _ => {
-            anyhow::bail!("Unknown command: {}. Use 'compile' or 'run'", command);
+            anyhow::bail!("Unknown command: {}. Use 'compile', 'build', 'run', 'test', 'test-codegen', 'workspace', 'check', 'lsp', 'fmt', 'explain' or 'doctor'", command);
         }
     }
     
     Ok(())
 }
 
-fn compile_file(file_path: &str) -> Result<()> {
-    let source = fs::read_to_string(file_path)
-        .with_context(|| format!("Failed to read file: {}", file_path))?;
+/// Flags accepted by `compile` and `run`
+struct CompileOptions {
+    emit_style: EmitStyle,
+    emit_tokens: bool,  // --emit=tokens: write <file>.tokens.json
+    emit_ast: bool,     // --emit=ast: write <file>.ast.json
+    emit_cpp: bool,     // --emit=cpp (the default when --emit isn't given)
+    emit_layouts: bool, // --emit=layouts: write the @[export] types as C, GLSL and JSON
+    emit_symbols: bool, // --emit=symbols: map the generated C++ symbols to their items (implies cpp)
+    reproducible: bool, // --reproducible: no wall-clock time or absolute paths in any artifact
+    stats_out: Option<String>,  // --stats-out <file.json>: language usage report, merged across files
+    deny_warnings: bool,        // --deny-warnings: warnings fail the build
+    target_abi: TargetAbi,      // --target-abi: standard library the struct layouts are computed for
+    gen_stdlib_stubs: bool,     // --gen-stdlib-stubs: write stand-ins for missing stdlib/ headers
+    hot_rebuild: bool,          // --hot-rebuild: the running program compiles its hot DLLs when their sources change
+    audit: bool,                // --audit: list the raw pointer flows of the generated code (<file>.audit.json)
+    checked_arithmetic: bool,   // --checked-arithmetic: integer overflow stops the program at its source location
+    strict_fp: bool,            // --strict-fp: IEEE float semantics for the whole program
+    ecs_storage: codegen::EcsStorage,  // --ecs-storage: entity storage backend of the generated code
+    gpu_backend: codegen::GpuBackend,  // --gpu-backend: CUDA kernels or Vulkan compute shaders for @[launch] functions
+    runtime: codegen::RuntimeProfile,  // --runtime: standard or minimal (no iostream, unordered_map, exceptions)
+    backend: codegen::Backend,  // --backend: C++ or C99 output
+    target: codegen::Target,  // --target: native, or a browser build with Emscripten
+    test: Option<codegen::TestOptions>,  // `test`: generate the test runner (<file>.test.cpp)
+    modules: Vec<String>,       // heidic.toml [build] sources: the program's other .hd files
+    out_dir: Option<PathBuf>,   // heidic.toml [build] output: where generated code and the executable go
+    dll_dir: String,            // Where the program loads hot DLLs from, relative to the source's directory
+    include_dirs: Vec<PathBuf>, // heidic.toml [build] stdlib: where generated code finds stdlib/
+    cxx_flags: Vec<String>,     // heidic.toml [build] cxx_flags
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self { emit_style: EmitStyle::Default, emit_tokens: false, emit_ast: false, emit_cpp: true, emit_layouts: false, emit_symbols: false, reproducible: false, stats_out: None, deny_warnings: false, target_abi: TargetAbi::host(), gen_stdlib_stubs: false, hot_rebuild: false, audit: false, checked_arithmetic: false, strict_fp: false, ecs_storage: codegen::EcsStorage::default(), gpu_backend: codegen::GpuBackend::default(), runtime: codegen::RuntimeProfile::default(), backend: codegen::Backend::default(), target: codegen::Target::default(), test: None, modules: Vec::new(), out_dir: None, dll_dir: String::new(), include_dirs: Vec::new(), cxx_flags: Vec::new() }
+    }
+}
+
+impl CompileOptions {
+    /// Split command arguments into the source file and the options
+    fn parse(args: &[String]) -> Result<(String, Self)> {
+        let mut options = CompileOptions::default();
+        let mut file_path = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if let Some(stages) = arg.strip_prefix("--emit=") {
+                // Comma-separated: --emit=tokens,ast,cpp
+                options.emit_cpp = false;
+                for stage in stages.split(',') {
+                    match stage {
+                        "tokens" => options.emit_tokens = true,
+                        "ast" => options.emit_ast = true,
+                        "cpp" => options.emit_cpp = true,
+                        "layouts" => options.emit_layouts = true,
+                        "symbols" => {
+                            options.emit_symbols = true;
+                            options.emit_cpp = true;
+                        }
+                        _ => anyhow::bail!("Unknown --emit stage '{}' (expected tokens, ast, cpp, layouts or symbols)", stage),
+                    }
+                }
+                continue;
+            }
+            if arg == "--reproducible" {
+                options.reproducible = true;
+                continue;
+            }
+            if arg == "--deny-warnings" {
+                options.deny_warnings = true;
+                continue;
+            }
+            if arg == "--gen-stdlib-stubs" {
+                options.gen_stdlib_stubs = true;
+                continue;
+            }
+            if arg == "--hot-rebuild" {
+                options.hot_rebuild = true;
+                continue;
+            }
+            if arg == "--audit" {
+                options.audit = true;
+                continue;
+            }
+            if arg == "--checked-arithmetic" {
+                options.checked_arithmetic = true;
+                continue;
+            }
+            if arg == "--strict-fp" {
+                options.strict_fp = true;
+                continue;
+            }
+            if arg.starts_with("--target-abi=") {
+                options.target_abi = parse_target_abi(arg)?;
+                continue;
+            }
+            if let Some(name) = arg.strip_prefix("--ecs-storage=") {
+                options.ecs_storage = codegen::EcsStorage::from_name(name).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown --ecs-storage '{}' (expected {})", name, codegen::EcsStorage::NAMES.join(", "))
+                })?;
+                continue;
+            }
+            if let Some(name) = arg.strip_prefix("--gpu-backend=") {
+                options.gpu_backend = codegen::GpuBackend::from_name(name).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown --gpu-backend '{}' (expected {})", name, codegen::GpuBackend::NAMES.join(", "))
+                })?;
+                continue;
+            }
+            if let Some(name) = arg.strip_prefix("--backend=") {
+                options.backend = codegen::Backend::from_name(name).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown --backend '{}' (expected {})", name, codegen::Backend::NAMES.join(", "))
+                })?;
+                continue;
+            }
+            if let Some(name) = arg.strip_prefix("--target=") {
+                options.target = codegen::Target::from_name(name).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown --target '{}' (expected {})", name, codegen::Target::NAMES.join(", "))
+                })?;
+                continue;
+            }
+            if arg == "--runtime" || arg.starts_with("--runtime=") {
+                let name = match arg.strip_prefix("--runtime=") {
+                    Some(name) => name.to_string(),
+                    None => args.next().cloned().ok_or_else(|| anyhow::anyhow!("--runtime needs a profile ({})", codegen::RuntimeProfile::NAMES.join(", ")))?,
+                };
+                options.runtime = codegen::RuntimeProfile::from_name(&name).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown --runtime '{}' (expected {})", name, codegen::RuntimeProfile::NAMES.join(", "))
+                })?;
+                continue;
+            }
+            if arg == "--stats-out" || arg.starts_with("--stats-out=") {
+                let path = match arg.strip_prefix("--stats-out=") {
+                    Some(path) => path.to_string(),
+                    None => args.next().cloned().ok_or_else(|| anyhow::anyhow!("--stats-out needs a file path"))?,
+                };
+                options.stats_out = Some(path);
+                continue;
+            }
+            let style = match arg.as_str() {
+                "--emit-readable" => EmitStyle::Readable,
+                "--emit-compact" => EmitStyle::Compact,
+                flag if flag.starts_with("--") => anyhow::bail!("Unknown option: {}", flag),
+                _ => {
+                    if file_path.replace(arg.clone()).is_some() {
+                        anyhow::bail!("Expected a single source file");
+                    }
+                    continue;
+                }
+            };
+            if options.emit_style != EmitStyle::Default && options.emit_style != style {
+                anyhow::bail!("--emit-readable and --emit-compact can't be combined");
+            }
+            options.emit_style = style;
+        }
+        let file_path = file_path.ok_or_else(|| anyhow::anyhow!("Missing source file"))?;
+        if options.backend == codegen::Backend::C {
+            // The C++ runtime's features, which C output has no counterpart for
+            let flags = [
+                (options.checked_arithmetic, "--checked-arithmetic"),
+                (options.hot_rebuild, "--hot-rebuild"),
+                (options.emit_symbols, "--emit=symbols"),
+                (options.gpu_backend != codegen::GpuBackend::default(), "--gpu-backend"),
+                (options.runtime != codegen::RuntimeProfile::default(), "--runtime"),
+            ];
+            if let Some((_, flag)) = flags.iter().find(|(set, _)| *set) {
+                anyhow::bail!("{} isn't available with --backend=c", flag);
+            }
+        }
+        if options.target == codegen::Target::Wasm {
+            // Browsers load no DLLs, and the canvas and WebGPU headers need the standard runtime
+            let flags = [
+                (options.backend != codegen::Backend::default(), "--backend=c"),
+                (options.hot_rebuild, "--hot-rebuild"),
+                (options.runtime != codegen::RuntimeProfile::default(), "--runtime"),
+            ];
+            if let Some((_, flag)) = flags.iter().find(|(set, _)| *set) {
+                anyhow::bail!("{} isn't available with --target=wasm", flag);
+            }
+        }
+        Ok((file_path, options))
+    }
     
-    // Lexical analysis
-    let mut lexer = Lexer::new(&source);
-    let tokens = lexer.tokenize()?;
+    /// Where a generated file goes: next to the source, or in the [build] output directory
+    fn output_path(&self, file_path: &str, extension: &str) -> PathBuf {
+        let path = output_path(file_path, extension);
+        match (&self.out_dir, path.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => path,
+        }
+    }
     
-    // Initialize error reporter (shared between parser and type checker)
+    /// The executable's path for `build`, from its name
+    fn exe_path(&self, name: String) -> String {
+        match &self.out_dir {
+            Some(dir) => dir.join(name).display().to_string(),
+            None => name,
+        }
+    }
+}
+
+fn parse_target_abi(flag: &str) -> Result<TargetAbi> {
+    let name = flag.trim_start_matches("--target-abi=");
+    TargetAbi::from_name(name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown --target-abi '{}' (expected {})", name, TargetAbi::NAMES.join(", ")))
+}
+
+/// <source dir>/<source stem>.<extension>
+fn output_path(file_path: &str, extension: &str) -> PathBuf {
+    let source_path = Path::new(file_path);
+    let source_dir = source_path.parent().unwrap_or(Path::new("."));
+    let stem = source_path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+    source_dir.join(format!("{}.{}", stem, extension))
+}
+
+/// --emit=tokens / --emit=ast: pretty-printed JSON dumps of the front end's output.
+/// The AST is written before type checking so programs with type errors can still be inspected.
+fn dump_front_end(file_path: &str, source: &str, options: &CompileOptions) -> Result<()> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize()?;
+    if options.emit_tokens {
+        let path = output_path(file_path, "tokens.json");
+        fs::write(&path, serde_json::to_string_pretty(&tokens)?)
+            .with_context(|| format!("Failed to write output file: {}", path.display()))?;
+        println!("Wrote tokens to {}", path.display());
+    }
+    if options.emit_ast {
+        let error_reporter = ErrorReporter::new(file_path)
+            .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+        let mut parser = Parser::new(tokens);
+        parser.set_comments(lexer.comments());
+        parser.set_error_reporter(error_reporter);
+        let ast = parser.parse()?;
+        let path = output_path(file_path, "ast.json");
+        fs::write(&path, serde_json::to_string_pretty(&ast)?)
+            .with_context(|| format!("Failed to write output file: {}", path.display()))?;
+        println!("Wrote AST to {}", path.display());
+    }
+    Ok(())
+}
+
+/// --emit=layouts: the program's @[export] types as <file>.layouts.h, .layouts.glsl and .layouts.json
+fn write_layouts(file_path: &str, program: &ast::Program, target_abi: TargetAbi) -> Result<()> {
+    let source_name = Path::new(file_path).file_name().and_then(|s| s.to_str()).unwrap_or(file_path);
+    let export = layout_export::LayoutExport::new(program, source_name, target_abi)
+        .map_err(|reason| anyhow::anyhow!("Can't export layouts: {}", reason))?;
+    let outputs = [
+        ("layouts.h", export.c_header()),
+        ("layouts.glsl", export.glsl()),
+        ("layouts.json", serde_json::to_string_pretty(&export.json())?),
+    ];
+    for (extension, contents) in outputs {
+        let path = output_path(file_path, extension);
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write output file: {}", path.display()))?;
+        println!("Wrote layouts to {}", path.display());
+    }
+    Ok(())
+}
+
+/// --audit: the program's raw pointer flows as <file>.audit.json, and printed for review
+fn write_audit(file_path: &str, program: &ast::Program, item_locations: &[error::SourceLocation], backend: codegen::GpuBackend) -> Result<()> {
+    let source_name = Path::new(file_path).file_name().and_then(|s| s.to_str()).unwrap_or(file_path);
+    let findings = audit::audit(program, item_locations, backend);
+    let path = output_path(file_path, "audit.json");
+    fs::write(&path, serde_json::to_string_pretty(&audit::report_json(&findings, source_name))?)
+        .with_context(|| format!("Failed to write output file: {}", path.display()))?;
+    print!("{}", audit::report_text(&findings, source_name));
+    println!("Wrote safety audit to {}", path.display());
+    Ok(())
+}
+
+/// Build the GLSL sources of `shader` declarations into the .spv files next to them, reporting
+/// GLSL errors at the declaration. Without glslc or glslangValidator the shaders are left as they are.
+/// --runtime minimal: report the items it can't generate (codegen::minimal_runtime_conflicts)
+fn check_minimal_runtime(file_path: &str, program: &ast::Program, item_locations: &[error::SourceLocation]) -> Result<()> {
+    let conflicts = codegen::minimal_runtime_conflicts(program);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let error_reporter = ErrorReporter::new(file_path)
+        .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+    for (index, message, suggestion) in &conflicts {
+        let location = item_locations.get(*index).copied().unwrap_or_else(error::SourceLocation::unknown);
+        error_reporter.report_error(location, message, Some(suggestion));
+    }
+    anyhow::bail!("{} use(s) of the standard runtime with --runtime minimal", conflicts.len());
+}
+
+/// --backend=c: report what C output can't express (c_codegen::conflicts)
+fn check_c_backend(file_path: &str, program: &ast::Program, item_locations: &[error::SourceLocation]) -> Result<()> {
+    let conflicts = c_codegen::conflicts(program, item_locations);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let error_reporter = ErrorReporter::new(file_path)
+        .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+    for (location, message, suggestion) in &conflicts {
+        error_reporter.report_error(*location, message, Some(suggestion));
+    }
+    anyhow::bail!("{} construct(s) C output can't express with --backend=c", conflicts.len());
+}
+
+/// --target=wasm: report what a browser build can't run (wasm::conflicts)
+fn check_wasm(file_path: &str, program: &ast::Program, item_locations: &[error::SourceLocation]) -> Result<()> {
+    let conflicts = wasm::conflicts(program, item_locations);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let error_reporter = ErrorReporter::new(file_path)
+        .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+    for (location, message, suggestion) in &conflicts {
+        error_reporter.report_error(*location, message, Some(suggestion));
+    }
+    anyhow::bail!("{} use(s) of native-only features with --target=wasm", conflicts.len());
+}
+
+/// --gpu-backend=vulkan translates kernels to GLSL: report what it can't translate, and that the
+/// minimal runtime has no Vulkan to dispatch them on
+fn check_vulkan_compute(file_path: &str, program: &ast::Program, item_locations: &[error::SourceLocation], runtime: codegen::RuntimeProfile) -> Result<()> {
+    let mut conflicts = vulkan_compute::conflicts(program, item_locations);
+    if runtime == codegen::RuntimeProfile::Minimal {
+        for (index, item) in program.items.iter().enumerate() {
+            if let ast::Item::Function(f) = item {
+                if f.cuda_kernel.is_some() {
+                    let location = item_locations.get(index).copied().unwrap_or_else(error::SourceLocation::unknown);
+                    conflicts.push((location, format!("@[launch] fn '{}' can't dispatch a Vulkan compute shader with --runtime minimal", f.name),
+                        "--runtime minimal leaves out Vulkan; use --gpu-backend=cuda or the standard runtime".to_string()));
+                }
+            }
+        }
+    }
+    if conflicts.is_empty() {
+        return Ok(());
+    }
     let error_reporter = ErrorReporter::new(file_path)
         .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+    for (location, message, suggestion) in &conflicts {
+        error_reporter.report_error(*location, message, Some(suggestion));
+    }
+    anyhow::bail!("{} kernel construct(s) unavailable with --gpu-backend=vulkan", conflicts.len());
+}
+
+/// Write the compute shaders of @[launch] functions next to the .hd file (only when they changed)
+/// and build them like declared shaders; GLSL errors are reported at the function
+fn compile_compute_shaders(file_path: &str, program: &ast::Program, item_locations: &[error::SourceLocation], compiler: Option<&shaders::ShaderCompiler>) -> Result<()> {
+    let kernels = vulkan_compute::compute_shaders(program);
+    if kernels.is_empty() {
+        return Ok(());
+    }
+    let source_dir = Path::new(file_path).parent().unwrap_or(Path::new("."));
+    for (_, path, glsl) in &kernels {
+        let path = source_dir.join(path);
+        if fs::read_to_string(&path).ok().as_deref() != Some(glsl.as_str()) {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+            }
+            fs::write(&path, glsl).with_context(|| format!("Failed to write compute shader {}", path.display()))?;
+        }
+    }
+    let Some(compiler) = compiler else {
+        eprintln!("⚠️  Warning: glslc/glslangValidator not found: {} compute shader(s) not compiled to SPIR-V (see heidic_v2 doctor)", kernels.len());
+        return Ok(());
+    };
+    let error_reporter = ErrorReporter::new(file_path)
+        .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+    let mut failed = 0;
+    for (index, path, _) in &kernels {
+        let source = source_dir.join(path);
+        let output = source_dir.join(shaders::spv_path(path));
+        match compiler.compile(&ast::ShaderStage::Compute, &source, &output) {
+            Ok(()) => println!("Compiled compute shader {} to {}", source.display(), output.display()),
+            Err(errors) => {
+                failed += 1;
+                let location = item_locations.get(*index).copied().unwrap_or_else(error::SourceLocation::unknown);
+                for glsl in errors {
+                    let at = glsl.line.map(|line| format!("{}:{}", path, line)).unwrap_or_else(|| path.clone());
+                    error_reporter.report_coded_error("E0026", location, &format!("GLSL error in generated compute shader {}: {}", at, glsl.message),
+                        Some("The GLSL translation of this kernel was rejected; build with --gpu-backend=cuda until it is fixed"));
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} compute shader(s) failed to compile", failed);
+    }
+    Ok(())
+}
+
+fn compile_shaders(file_path: &str, program: &ast::Program, item_locations: &[error::SourceLocation], compiler: Option<&shaders::ShaderCompiler>) -> Result<()> {
+    let declared: Vec<_> = program.items.iter().enumerate()
+        .filter_map(|(index, item)| match item {
+            ast::Item::Shader(shader) if shaders::is_glsl(&shader.path) => Some((shader, item_locations.get(index).copied().unwrap_or_else(error::SourceLocation::unknown))),
+            _ => None,
+        })
+        .collect();
+    if declared.is_empty() {
+        return Ok(());
+    }
+    let source_dir = Path::new(file_path).parent().unwrap_or(Path::new("."));
+    // Inline GLSL is written out first (only when it changed, so hot reload sees real edits)
+    for (shader, _) in &declared {
+        let Some(inline) = &shader.inline else { continue };
+        let path = source_dir.join(&shader.path);
+        if fs::read_to_string(&path).ok().as_deref() != Some(inline.glsl.as_str()) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &inline.glsl).with_context(|| format!("Failed to write inline shader {}", path.display()))?;
+        }
+    }
+    let Some(compiler) = compiler else {
+        eprintln!("⚠️  Warning: glslc/glslangValidator not found: {} shader(s) not compiled to SPIR-V (see heidic_v2 doctor)", declared.len());
+        return Ok(());
+    };
     
-    // Parsing with error reporting
+    let error_reporter = ErrorReporter::new(file_path)
+        .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+    let mut failed = 0;
+    for (shader, location) in declared {
+        // Looked up like the runtime does: shaders/<path> first, then <path>
+        let Some(source) = [source_dir.join("shaders").join(&shader.path), source_dir.join(&shader.path)].into_iter().find(|path| path.is_file()) else {
+            eprintln!("⚠️  Warning: shader source {} not found next to {}; its .spv wasn't built", shader.path, file_path);
+            continue;
+        };
+        let output = source.with_file_name(shaders::spv_path(&source.file_name().unwrap_or_default().to_string_lossy()));
+        match compiler.compile(&shader.stage, &source, &output) {
+            Ok(()) => println!("Compiled shader {} to {}", source.display(), output.display()),
+            Err(errors) => {
+                failed += 1;
+                for glsl in errors {
+                    if let Some(inline) = &shader.inline {
+                        // Line n of the block is n - 1 lines below `glsl {`
+                        let location = glsl.line.and_then(|line| inline_glsl_location(inline, line)).unwrap_or(inline.location);
+                        error_reporter.report_coded_error("E0026", location, &format!("GLSL error in inline shader '{}': {}", inline.name, glsl.message),
+                            Some("Fix the GLSL in the glsl { } block; the previous .spv (if any) was left in place"));
+                        continue;
+                    }
+                    let at = glsl.line.map(|line| format!("{}:{}", shader.path, line)).unwrap_or_else(|| shader.path.clone());
+                    error_reporter.report_coded_error("E0026", location, &format!("GLSL error in {}: {}", at, glsl.message),
+                        Some("Fix the shader source; the previous .spv (if any) was left in place"));
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} shader(s) failed to compile", failed);
+    }
+    Ok(())
+}
+
+// Where line `line` of an inline shader's GLSL is in the .hd file, at its first non-blank column
+fn inline_glsl_location(inline: &ast::InlineShader, line: usize) -> Option<error::SourceLocation> {
+    let text = inline.glsl.lines().nth(line.checked_sub(1)?)?;
+    if line == 1 {
+        return Some(inline.location);  // Shares the line with `glsl {`
+    }
+    let column = text.len() - text.trim_start().len() + 1;
+    Some(error::SourceLocation { line: inline.location.line + line - 1, column, ..inline.location })
+}
+
+fn format_file(file_path: &str, check: bool) -> Result<()> {
+    let source = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read file: {}", file_path))?;
+    let error_reporter = ErrorReporter::new(file_path)
+        .with_context(|| format!("Failed to initialize error reporter for: {}", file_path))?;
+    let formatted = formatter::format_source(&source, Some(error_reporter))?;
+
+    if formatted == source {
+        println!("{} is already formatted", file_path);
+    } else if check {
+        anyhow::bail!("{} is not formatted (run `heidic_v2 fmt {}`)", file_path, file_path);
+    } else {
+        fs::write(file_path, &formatted)
+            .with_context(|| format!("Failed to write file: {}", file_path))?;
+        println!("Formatted {}", file_path);
+    }
+    Ok(())
+}
+
+fn explain(code: &str) -> Result<()> {
+    if code == "--list" {
+        for entry in error_codes::ERROR_CODES {
+            println!("{}  {}", entry.code, entry.title);
+        }
+        return Ok(());
+    }
+    let Some(entry) = error_codes::lookup(code) else {
+        anyhow::bail!("Unknown error code '{}'. Run 'heidic_v2 explain --list' to see all codes", code);
+    };
+    println!("{}: {}\n", entry.code, entry.title);
+    print!("{}", entry.explanation);
+    Ok(())
+}
+
+/// Lex and parse one file of a program (`file` is its index in the error reporter), then read the
+/// scene files it names: relative to it, so the type checker can validate them against the components
+fn parse_file(file_path: &str, source: &str, file: usize, error_reporter: &ErrorReporter) -> Result<(ast::Program, Vec<error::SourceLocation>)> {
+    let mut lexer = Lexer::new(source);
+    lexer.set_file(file);
+    let tokens = lexer.tokenize().with_context(|| format!("in {}", file_path))?;
     let mut parser = Parser::new(tokens);
+    parser.set_comments(lexer.comments());
     parser.set_error_reporter(error_reporter.clone());
-    let ast = parser.parse()?;
+    let mut program = parser.parse()?;
+    if let Err(e) = scene::load(&mut program, Path::new(file_path).parent().unwrap_or(Path::new("."))) {
+        error_reporter.report_error(e.location, &e.message, Some(&e.suggestion));
+        anyhow::bail!("{}", e.message);
+    }
+    Ok((program, parser.item_locations().to_vec()))
+}
+
+/// Lex, parse and type check a source file, reporting errors and warnings to the terminal.
+/// Workspace members are checked together with the workspace's shared modules, which come first;
+/// `modules` (heidic.toml [build] sources) are further files of the program, appended after it.
+/// Returns the program and where each of its items starts (unknown for shared modules' items).
+fn analyze(file_path: &str, source: &str, deny_warnings: bool, target_abi: TargetAbi, shared: &[SharedModule], modules: &[String]) -> Result<(ast::Program, Vec<error::SourceLocation>)> {
+    // Initialize error reporter (shared between parser and type checker); module i is file i + 1
+    let mut error_reporter = ErrorReporter::from_source(file_path, source);
+    let mut sources = vec![(file_path.to_string(), source.to_string())];
+    for module_path in modules {
+        let module_source = fs::read_to_string(module_path)
+            .with_context(|| format!("Failed to read source file: {}", module_path))?;
+        error_reporter.add_source(module_path, &module_source);
+        sources.push((module_path.clone(), module_source));
+    }
+    
+    // The files are lexed and parsed on the thread pool, each reporting into a buffer of its own;
+    // the buffers are printed in file order, so the diagnostics don't depend on scheduling.
+    // Type checking stays on this thread, over the merged program: a file's functions are checked
+    // against every file's declarations, and the checker's scopes and tables are one shared state.
+    let parsed: Vec<_> = sources.par_iter().enumerate().map(|(file, (path, source))| {
+        let reporter = error_reporter.buffered();
+        let parsed = parse_file(path, source, file, &reporter);
+        (parsed, reporter.take_buffered())
+    }).collect();
+    let mut ast = ast::Program { items: Vec::new() };
+    let mut item_locations = Vec::new();
+    let mut first_error = None;
+    for (parsed, reports) in parsed {
+        eprint!("{}", reports);
+        match parsed {
+            Ok((program, locations)) => {
+                ast.items.extend(program.items);
+                item_locations.extend(locations);
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if let Some(e) = first_error {
+        return Err(e);
+    }
+    
+    if !shared.is_empty() {
+        let conflicts = workspace::shared_conflicts(shared, &ast);
+        for (message, suggestion) in &conflicts {
+            error_reporter.report_error(error::SourceLocation::unknown(), message, Some(suggestion));
+        }
+        if !conflicts.is_empty() {
+            anyhow::bail!("{} definition(s) conflict with shared modules", conflicts.len());
+        }
+        let own_items = std::mem::take(&mut ast.items);
+        let shared_items = shared.iter().map(|module| module.items.len()).sum();
+        item_locations.splice(0..0, std::iter::repeat_n(error::SourceLocation::unknown(), shared_items));
+        ast.items = shared.iter().flat_map(|module| module.items.iter().cloned()).collect();
+        ast.items.extend(own_items);
+    }
     
     // Type checking with error reporting
     let mut type_checker = TypeChecker::new();
     type_checker.set_error_reporter(error_reporter);
+    type_checker.set_target_abi(target_abi);
+    type_checker.set_item_locations(item_locations.clone());
     type_checker.check(&ast)?;
     
-    // Code generation
-    let mut codegen = CodeGenerator::new();
-    let cpp_code = codegen.generate(&ast)?;
+    let warnings = type_checker.warnings().len();
+    if deny_warnings && warnings > 0 {
+        eprintln!("\n❌ Compilation failed: {} warning(s) denied by --deny-warnings\n", warnings);
+        anyhow::bail!("{} warning(s) treated as errors", warnings);
+    }
     
-    // Write output in the same directory as the source file
+    Ok((ast, item_locations))
+}
+
+fn check_file(file_path: &str, json: bool, deny_warnings: bool, target_abi: TargetAbi) -> Result<()> {
+    let source = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read file: {}", file_path))?;
+    
+    if !json {
+        analyze(file_path, &source, deny_warnings, target_abi, &[], &[])?;
+        println!("{}: no errors", file_path);
+        return Ok(());
+    }
+    
+    // JSON: one diagnostic object per line on stdout, nothing on the terminal reporter
+    let mut diagnostics = Vec::new();
+    let mut warnings = Vec::new();
+    match Lexer::new(&source).tokenize() {
+        Err(e) => {
+            let location = e.downcast_ref::<lexer::LexError>()
+                .map(|lex| lex.location)
+                .unwrap_or_else(error::SourceLocation::unknown);
+            diagnostics.push(Diagnostic::new(location, "Invalid token".to_string(), None));
+        }
+        Ok(tokens) => {
+            let mut parser = Parser::new(tokens);
+            match parser.parse() {
+                Err(e) if parser.errors().is_empty() => {
+                    diagnostics.push(Diagnostic::new(error::SourceLocation::unknown(), e.to_string(), None));
+                }
+                Err(_) => diagnostics.extend(parser.errors().iter().cloned()),
+                Ok(mut program) => {
+                    if let Err(e) = scene::load(&mut program, Path::new(file_path).parent().unwrap_or(Path::new("."))) {
+                        diagnostics.push(Diagnostic::new(e.location, e.message, Some(e.suggestion)));
+                    }
+                    let mut type_checker = TypeChecker::new();
+                    type_checker.set_target_abi(target_abi);
+                    type_checker.set_item_locations(parser.item_locations().to_vec());
+                    // Errors are collected on the checker; the returned summary adds nothing
+                    let _ = type_checker.check(&program);
+                    diagnostics.extend(type_checker.errors().iter().cloned());
+                    warnings.extend(type_checker.warnings().iter().cloned());
+                }
+            }
+        }
+    }
+    
+    let errors = diagnostics.iter().map(|diagnostic| (diagnostic, Severity::Error));
+    for (diagnostic, severity) in errors.chain(warnings.iter().map(|w| (w, Severity::Warning))) {
+        let diagnostic = serde_json::json!({
+            "file": file_path,
+            "line": diagnostic.location.line,
+            "column": diagnostic.location.column,
+            "severity": severity,
+            "code": diagnostic.code,
+            "message": diagnostic.message,
+            "suggestion": diagnostic.suggestion,
+        });
+        println!("{}", diagnostic);
+    }
+    if !diagnostics.is_empty() {
+        anyhow::bail!("{} error(s) in {}", diagnostics.len(), file_path);
+    }
+    if deny_warnings && !warnings.is_empty() {
+        anyhow::bail!("{} warning(s) in {} (--deny-warnings)", warnings.len(), file_path);
+    }
+    Ok(())
+}
+
+fn compile_file(file_path: &str, options: &CompileOptions) -> Result<()> {
+    compile_program(file_path, options, &[])?;
+    Ok(())
+}
+
+/// `build`: compile, then run the C++ compiler (and nvcc for CUDA kernels) on the generated code
+fn build_file(file_path: &str, options: &CompileOptions) -> Result<()> {
+    let (_, build) = compile_program(file_path, options, &[])?;
+    let Some(build) = build else {
+        anyhow::bail!("Nothing to build: --emit doesn't include cpp");
+    };
+    println!();
+    build.run(&build::Toolchain::find())
+}
+
+/// Compile one program (with any workspace shared modules) and return its checked AST, and how to
+/// build the generated code when C++ was emitted
+fn compile_program(file_path: &str, options: &CompileOptions, shared: &[SharedModule]) -> Result<(ast::Program, Option<build::Build>)> {
+    let source = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read file: {}", file_path))?;
+    
+    if options.emit_tokens || options.emit_ast {
+        dump_front_end(file_path, &source, options)?;
+        if !options.emit_cpp && !options.emit_layouts {
+            // Not type checked or generated; the parsed program is enough for workspace layout checks
+            return Ok((Parser::new(Lexer::new(&source).tokenize()?).parse()?, None));
+        }
+    }
+    
+    let (ast, item_locations) = analyze(file_path, &source, options.deny_warnings, options.target_abi, shared, &options.modules)?;
+    
+    if let Some(stats_path) = &options.stats_out {
+        // Only this file's own items; shared modules aren't part of it
+        let shared_items: usize = shared.iter().map(|module| module.items.len()).sum();
+        let own = ast::Program { items: ast.items[shared_items..].to_vec() };
+        stats::update_report(stats_path, file_path, stats::file_stats(&own, &source, options.target_abi))?;
+    }
+    
+    if options.audit {
+        write_audit(file_path, &ast, &item_locations, options.gpu_backend)?;
+    }
+    
+    if options.runtime == codegen::RuntimeProfile::Minimal {
+        check_minimal_runtime(file_path, &ast, &item_locations)?;
+    }
+    
+    if options.backend == codegen::Backend::C {
+        check_c_backend(file_path, &ast, &item_locations)?;
+    }
+    
+    if options.target == codegen::Target::Wasm {
+        check_wasm(file_path, &ast, &item_locations)?;
+    }
+    
+    if options.gpu_backend == codegen::GpuBackend::Vulkan {
+        check_vulkan_compute(file_path, &ast, &item_locations, options.runtime)?;
+    }
+    
+    let shader_compiler = shaders::ShaderCompiler::find();
+    compile_shaders(file_path, &ast, &item_locations, shader_compiler.as_ref())?;
+    if options.gpu_backend == codegen::GpuBackend::Vulkan {
+        compile_compute_shaders(file_path, &ast, &item_locations, shader_compiler.as_ref())?;
+    }
+    
+    if options.emit_layouts {
+        write_layouts(file_path, &ast, options.target_abi)?;
+        if !options.emit_cpp {
+            return Ok((ast, None));
+        }
+    }
+    
+    // Provenance header from heidic.toml, rendered once so every generated file carries the same one
+    let build_time = config::build_time(options.reproducible);
+    let project = config::ProjectConfig::for_source(file_path)?;
+    let header = project.render_header(file_path, &source, build_time);
+    
+    if options.backend == codegen::Backend::C {
+        let mut codegen = c_codegen::CGenerator::new();
+        codegen.set_target_abi(options.target_abi);
+        if options.strict_fp {
+            codegen.set_strict_fp();
+        }
+        let (output_path, _) = write_generated(&mut codegen, file_path, &ast, &header, options, None)?;
+        let build = build::Build {
+            source: output_path,
+            cuda: None,
+            exe: options.exe_path(test_exe_name(file_path, options)),
+            strict_fp: codegen.uses_strict_fp(),
+            include_dirs: options.include_dirs.clone(),
+            flags: options.cxx_flags.clone(),
+            ..Default::default()
+        };
+        print_build_steps(&build);
+        return Ok((ast, Some(build)));
+    }
+    
+    // Code generation
+    let mut codegen = CppGenerator::new();
+    codegen.set_emit_style(options.emit_style);
+    codegen.set_target_abi(options.target_abi);
+    codegen.set_ecs_storage(options.ecs_storage);
+    codegen.set_gpu_backend(options.gpu_backend);
+    codegen.set_runtime(options.runtime);
+    codegen.set_target(options.target);
+    codegen.set_dll_dir(&options.dll_dir);
+    codegen.set_shader_compiler(shader_compiler.map(|compiler| compiler.kind).unwrap_or_default());
+    if let Some(test) = options.test {
+        codegen.set_test_options(test);
+    }
+    if options.checked_arithmetic {
+        codegen.set_checked_arithmetic(&Path::new(file_path).file_name().unwrap_or_default().to_string_lossy());
+    }
+    if options.strict_fp {
+        codegen.set_strict_fp();
+    }
+    if options.hot_rebuild {
+        // The running program re-runs this compiler on the source, so it needs a path that stays valid
+        let heidic = match std::env::current_exe() {
+            Ok(exe) if !options.reproducible => exe.display().to_string(),
+            _ => "heidic_v2".to_string(),
+        };
+        codegen.set_hot_rebuild(codegen::HotRebuild {
+            compiler: project.hot_reload.compiler.clone().unwrap_or_else(|| "g++".to_string()),
+            flags: project.hot_reload.flags.clone(),
+            heidic,
+            source: Path::new(file_path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+        });
+    }
+    let (output_path, cpp_code) = write_generated(&mut codegen, file_path, &ast, &header, options, options.test.map(|_| "test.cpp"))?;
     let source_path = Path::new(file_path);
-    let source_dir = source_path.parent().unwrap_or(Path::new("."));
-    let output_path = source_dir.join(
-        source_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .map(|s| format!("{}.cpp", s))
-            .unwrap_or_else(|| "output.cpp".to_string())
-    );
-    
-    fs::write(&output_path, cpp_code)
-        .with_context(|| format!("Failed to write output file: {}", output_path.display()))?;
+    let source_dir = options.out_dir.as_deref().unwrap_or(source_path.parent().unwrap_or(Path::new(".")));
     
-    println!("Compiled {} to {}", file_path, output_path.display());
+    // CUDA kernels are a translation unit of their own, built by nvcc
+    let cuda = if codegen.uses_cuda() {
+        let cuda_code = cpp_style::restyle(&codegen.generate_cuda_source(), options.emit_style);
+        let cuda_path = output_path.with_extension("cu");
+        fs::write(&cuda_path, format!("{}{}", header, cuda_code))
+            .with_context(|| format!("Failed to write output file: {}", cuda_path.display()))?;
+        println!("Wrote CUDA kernels to {}", cuda_path.display());
+        Some((cuda_path, cuda_code))
+    } else {
+        None
+    };
     
     // Generate DLL files for hot-reloadable systems
+    let mut dll_sources = Vec::new();
+    let mut dll_paths = Vec::new();
     let hot_systems = codegen.get_hot_systems();
     if !hot_systems.is_empty() {
         println!("\nGenerating hot-reloadable system DLLs...");
         let hot_systems_clone = hot_systems.clone();
         for system in hot_systems_clone {
-            let dll_cpp = codegen.generate_hot_system_dll(&system);
+            let dll_cpp = cpp_style::restyle(&codegen.generate_hot_system_dll(&system), options.emit_style);
             let dll_name = format!("{}_hot.dll.cpp", system.name.to_lowercase());
             let dll_path = source_dir.join(&dll_name);
             
-            fs::write(&dll_path, dll_cpp)
+            fs::write(&dll_path, format!("{}{}", header, dll_cpp))
                 .with_context(|| format!("Failed to write DLL file: {}", dll_path.display()))?;
             
+            dll_sources.push((dll_name, dll_cpp));
+            dll_paths.push(dll_path.clone());
             println!("  Generated: {}", dll_path.display());
-            println!("  Compile DLL with: g++ -std=c++17 -shared -o {}.dll {} -Wl,--out-implib,{}.a", 
-                     system.name.to_lowercase(), dll_path.display(), system.name.to_lowercase());
+        }
+        if options.hot_rebuild {
+            println!("  The program rebuilds these DLLs itself when {} or a _hot.dll.cpp changes", file_path);
         }
     }
     
-    let exe_name = source_path.file_stem().unwrap().to_str().unwrap();
-    println!("\nCompile main with: g++ -std=c++17 -O3 {} -o {}", 
-             output_path.display(), exe_name);
+    if options.gen_stdlib_stubs {
+        let mut sources = vec![cpp_code.as_str()];
+        sources.extend(dll_sources.iter().map(|(_, code)| code.as_str()));
+        sources.extend(cuda.iter().map(|(_, code)| code.as_str()));
+        let report = stdlib_stubs::write_missing(source_dir, &sources)
+            .with_context(|| format!("Failed to write stdlib stubs in {}", source_dir.join("stdlib").display()))?;
+        for path in &report.written {
+            println!("Wrote stub {}", path.display());
+        }
+        if !report.unavailable.is_empty() {
+            eprintln!("⚠️  Warning: no stub for stdlib/{}: the program's resources need the engine's stdlib/",
+                      report.unavailable.join(", stdlib/"));
+        }
+    }
+    
+    if options.emit_symbols {
+        let mut files = vec![symbols::GeneratedFile {
+            name: output_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            code: &cpp_code,
+        }];
+        files.extend(dll_sources.iter().map(|(name, code)| symbols::GeneratedFile { name: name.clone(), code }));
+        files.extend(cuda.iter().map(|(path, code)| symbols::GeneratedFile {
+            name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            code,
+        }));
+        let source_name = source_path.file_name().and_then(|s| s.to_str()).unwrap_or(file_path);
+        let map = symbols::symbol_map(&ast, &item_locations, source_name, codegen.symbol_prefix(), &files);
+        let symbols_path = output_path.with_extension("symbols.json");
+        fs::write(&symbols_path, serde_json::to_string_pretty(&map)?)
+            .with_context(|| format!("Failed to write output file: {}", symbols_path.display()))?;
+        println!("Wrote symbol map to {}", symbols_path.display());
+    }
     
+    // The page that runs a browser build: <stem>.html next to <stem>.js and <stem>.wasm
+    if options.target == codegen::Target::Wasm {
+        let stem = source_path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        let harness_path = output_path.with_extension("html");
+        fs::write(&harness_path, wasm::page_harness(stem, &wasm::watched_files(&ast)))
+            .with_context(|| format!("Failed to write output file: {}", harness_path.display()))?;
+        println!("Wrote page harness to {}", harness_path.display());
+    }
+    
+    let build = build::Build {
+        source: output_path,
+        cuda: cuda.map(|(path, _)| path),
+        exe: options.exe_path(if options.target == codegen::Target::Wasm { format!("{}.js", test_exe_name(file_path, options)) } else { test_exe_name(file_path, options) }),
+        wasm: options.target == codegen::Target::Wasm,
+        strict_fp: codegen.uses_strict_fp(),
+        dlls: dll_paths,
+        dll_compiler: project.hot_reload.compiler.clone(),
+        dll_flags: project.hot_reload.flags.clone(),
+        include_dirs: options.include_dirs.clone(),
+        flags: options.cxx_flags.clone(),
+    };
+    print_build_steps(&build);
+    
+    Ok((ast, Some(build)))
+}
+
+/// Generate the program with `codegen` and write it next to the source file (or in the [build]
+/// output directory), after the provenance header: <stem>.cpp or <stem>.c, or `extension` when given
+fn write_generated(codegen: &mut dyn CodeGenerator, file_path: &str, ast: &ast::Program, header: &str, options: &CompileOptions, extension: Option<&str>) -> Result<(PathBuf, String)> {
+    codegen.set_program_name(&Path::new(file_path).file_stem().unwrap_or_default().to_string_lossy());
+    let code = cpp_style::restyle(&codegen.generate(ast)?, options.emit_style);
+    let output_path = options.output_path(file_path, extension.unwrap_or(codegen.extension()));
+    fs::write(&output_path, format!("{}{}", header, code))
+        .with_context(|| format!("Failed to write output file: {}", output_path.display()))?;
+    println!("Compiled {} to {}", file_path, output_path.display());
+    Ok((output_path, code))
+}
+
+/// The commands `compile` suggests for building by hand (what `build` runs)
+fn print_build_steps(build: &build::Build) {
+    match build.steps(&build::Toolchain::default()).as_slice() {
+        [step] => println!("\nCompile main with: {}", step),
+        steps => {
+            println!("\nCompile main with (or run heidic_v2 build):");
+            for step in steps {
+                println!("  {}", step);
+            }
+        }
+    }
+}
+
+/// Build every member of a workspace (`path` is its directory or its heidic.toml), then
+/// compare the layouts of components and structs the members have in common
+fn build_workspace(path: &str, options: &CompileOptions) -> Result<()> {
+    let config_path = if Path::new(path).is_dir() {
+        Path::new(path).join(config::CONFIG_FILE_NAME)
+    } else {
+        PathBuf::from(path)
+    };
+    let project = config::ProjectConfig::load(&config_path)?;
+    let Some(members) = &project.workspace else {
+        anyhow::bail!("{} has no [workspace] section", config_path.display());
+    };
+    let root = config_path.parent().unwrap_or(Path::new("."));
+    let member_path = |member: &str| root.join(member).to_string_lossy().into_owned();
+    
+    // Shared modules are checked once on their own, so their errors point at their own files
+    let mut shared = Vec::new();
+    for module in &members.shared {
+        let module_path = member_path(module);
+        let source = fs::read_to_string(&module_path)
+            .with_context(|| format!("Failed to read shared module: {}", module_path))?;
+        let (program, _) = analyze(&module_path, &source, options.deny_warnings, options.target_abi, &[], &[])?;
+        shared.push(SharedModule::new(module, program)?);
+    }
+    
+    // Keep going after a failed member so one run reports every member's errors
+    let mut built = Vec::new();
+    let mut failed = Vec::new();
+    for member in &members.members {
+        println!("\n=== {} ===", member);
+        match compile_program(&member_path(member), options, &shared) {
+            Ok((program, _)) => built.push((member.clone(), program)),
+            Err(e) => {
+                eprintln!("❌ {}: {}", member, e);
+                failed.push(member.as_str());
+            }
+        }
+    }
+    
+    let (consistent, divergences) = workspace::check_layouts(&built);
+    for divergence in &divergences {
+        eprintln!("⚠️  Warning: '{}' has different layouts across workspace members:", divergence.name);
+        for (member, layout) in &divergence.layouts {
+            eprintln!("  {}: {}", member, layout);
+        }
+        eprintln!("💡 Suggestion: Move '{}' into a shared module so every binary uses one definition\n", divergence.name);
+    }
+    
+    println!("\nWorkspace: {} of {} member(s) built, {} type(s) shared with matching layouts",
+             built.len(), members.members.len(), consistent);
+    if !failed.is_empty() {
+        anyhow::bail!("Workspace member(s) failed to build: {}", failed.join(", "));
+    }
+    if options.deny_warnings && !divergences.is_empty() {
+        anyhow::bail!("{} layout divergence(s) denied by --deny-warnings", divergences.len());
+    }
+    Ok(())
+}
+
+/// `build` with no arguments: the program [build] in the nearest heidic.toml describes. Its entry
+/// file and every .hd file below its source directories are compiled as one program into its output
+/// directory, then the hot DLLs and the executable are built with its stdlib/ and compiler flags.
+fn build_project() -> Result<()> {
+    let Some(config_path) = config::ProjectConfig::find(Path::new(".")) else {
+        anyhow::bail!("No {} here or in a parent directory (heidic_v2 build <file> builds a single file)", config::CONFIG_FILE_NAME);
+    };
+    let project = config::ProjectConfig::load(&config_path)?;
+    let Some(manifest) = &project.build else {
+        anyhow::bail!("{} has no [build] section (entry = \"main.hd\" is enough)", config_path.display());
+    };
+    let root = config_path.parent().unwrap_or(Path::new("."));
+    let entry = root.join(&manifest.entry);
+    if !entry.is_file() {
+        anyhow::bail!("{}: [build] entry {} not found", config_path.display(), entry.display());
+    }
+    
+    let mut options = CompileOptions::default();
+    if let Some(target) = &manifest.target {
+        options.target = codegen::Target::from_name(target).unwrap_or_default();  // Checked when loaded
+    }
+    let mut modules = Vec::new();
+    for dir in &manifest.sources {
+        let dir = root.join(dir);
+        if !dir.is_dir() {
+            anyhow::bail!("{}: [build] source directory {} not found", config_path.display(), dir.display());
+        }
+        collect_sources(&dir, &mut modules)?;
+    }
+    modules.sort();
+    modules.dedup();
+    modules.retain(|module| !same_file(module, &entry));
+    options.modules = modules.iter().map(|module| module.to_string_lossy().into_owned()).collect();
+    if let Some(output) = &manifest.output {
+        let out_dir = root.join(output);
+        fs::create_dir_all(&out_dir)
+            .with_context(|| format!("Failed to create output directory {}", out_dir.display()))?;
+        let out_dir = out_dir.canonicalize().unwrap_or(out_dir);
+        options.dll_dir = relative_path(&out_dir, entry.parent().unwrap_or(Path::new(".")));
+        options.out_dir = Some(out_dir);
+    }
+    
+    // Generated code includes "stdlib/...": the configured directory's parent, or the entry's
+    // directory when the code is generated elsewhere
+    let stdlib_parent = match &manifest.stdlib {
+        Some(stdlib) => {
+            let stdlib = root.join(stdlib);
+            if !stdlib.is_dir() || stdlib.file_name().is_none_or(|name| name != "stdlib") {
+                anyhow::bail!("{}: [build] stdlib must be a directory named stdlib ({} isn't)", config_path.display(), stdlib.display());
+            }
+            stdlib.parent().map(Path::to_path_buf)
+        }
+        None if options.out_dir.is_some() => entry.parent().map(Path::to_path_buf),
+        None => None,
+    };
+    options.include_dirs.extend(stdlib_parent);
+    options.cxx_flags = manifest.cxx_flags.clone();
+    
+    let entry_path = entry.to_string_lossy().into_owned();
+    println!("Building {} ({} module(s)) from {}", manifest.entry, options.modules.len(), config_path.display());
+    let (_, build) = compile_program(&entry_path, &options, &[])?;
+    let Some(build) = build else {
+        anyhow::bail!("Nothing to build: no C++ was generated");
+    };
+    println!();
+    build.run(&build::Toolchain::find())
+}
+
+// Every .hd file below `dir`
+fn collect_sources(dir: &Path, sources: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_sources(&path, sources)?;
+        } else if path.extension().is_some_and(|ext| ext == "hd") {
+            sources.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+// `path` relative to `base` with `/` separators ("" when they're the same directory)
+fn relative_path(path: &Path, base: &Path) -> String {
+    let absolute = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let (path, base) = (absolute(path), absolute(if base.as_os_str().is_empty() { Path::new(".") } else { base }));
+    let common = path.components().zip(base.components()).take_while(|(a, b)| a == b).count();
+    let up = base.components().skip(common).map(|_| "..".to_string());
+    let down = path.components().skip(common).map(|c| c.as_os_str().to_string_lossy().into_owned());
+    up.chain(down).collect::<Vec<_>>().join("/")
+}
+
+/// `test-codegen`: compile every fixture in `dir` and compare its generated code with the golden
+/// files (golden.rs), or rewrite them with `update`. A failed fixture doesn't stop the others.
+fn test_codegen(dir: &str, update: bool) -> Result<()> {
+    let dir = Path::new(dir);
+    let fixtures = golden::fixtures(dir)?;
+    if fixtures.is_empty() {
+        anyhow::bail!("No .hd fixtures in {}", dir.display());
+    }
+    // Each fixture's code is generated in a scratch directory, so the fixture directory only holds sources and golden files
+    let scratch = std::env::temp_dir().join(format!("heidic-test-codegen-{}", std::process::id()));
+    let mut failed = Vec::new();
+    for fixture in &fixtures {
+        let name = fixture.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        println!("\n=== {} ===", name);
+        let golden_dir = dir.join("golden").join(&name);
+        match generate_fixture(fixture, &scratch.join(&name)) {
+            Ok(generated) if update => {
+                golden::update(&golden_dir, &generated)?;
+                println!("Updated {} golden file(s) in {}", generated.len(), golden_dir.display());
+            }
+            Ok(generated) => {
+                let mismatches = golden::compare(&golden_dir, &generated)?;
+                for mismatch in &mismatches {
+                    eprintln!("❌ {}: {}", name, mismatch);
+                }
+                if !mismatches.is_empty() {
+                    failed.push(name);
+                }
+            }
+            Err(e) => {
+                eprintln!("❌ {}: {}", name, e);
+                failed.push(name);
+            }
+        }
+    }
+    // Best effort: a leftover scratch directory in the temp directory is harmless
+    let _ = fs::remove_dir_all(&scratch);
+    
+    if update {
+        println!("\nGolden files updated for {} of {} fixture(s)", fixtures.len() - failed.len(), fixtures.len());
+    } else {
+        println!("\nCodegen tests: {} passed, {} failed", fixtures.len() - failed.len(), failed.len());
+    }
+    if !failed.is_empty() && update {
+        anyhow::bail!("Fixture(s) didn't compile: {}", failed.join(", "));
+    }
+    if !failed.is_empty() {
+        anyhow::bail!("Fixture(s) failed: {} (run with --update-golden when the change is intended)", failed.join(", "));
+    }
     Ok(())
 }
 
-fn compile_and_run(file_path: &str) -> Result<()> {
-    compile_file(file_path)?;
+// Compile a fixture with its flags into `out_dir` and return the generated code files
+fn generate_fixture(fixture: &Path, out_dir: &Path) -> Result<Vec<(String, String)>> {
+    let source = fs::read_to_string(fixture)
+        .with_context(|| format!("Failed to read fixture: {}", fixture.display()))?;
+    let mut args = golden::fixture_flags(&source);
+    args.push(fixture.to_string_lossy().into_owned());
+    let (file_path, mut options) = CompileOptions::parse(&args)
+        .with_context(|| format!("Invalid // flags: line in {}", fixture.display()))?;
+    // Timestamps and the compiler's path stay out of the code, so it's the same on every machine
+    options.reproducible = true;
+    if out_dir.exists() {
+        fs::remove_dir_all(out_dir)?;
+    }
+    fs::create_dir_all(out_dir).with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    options.out_dir = Some(out_dir.to_path_buf());
+    compile_program(&file_path, &options, &[])?;
+    golden::code_files(out_dir)
+}
+
+// The executable name suggested for a compiled file: <stem>, or <stem>_test for test runners
+fn test_exe_name(file_path: &str, options: &CompileOptions) -> String {
+    let stem = Path::new(file_path).file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+    if options.test.is_some() { format!("{}_test", stem) } else { stem.to_string() }
+}
+
+/// `heidic_v2 test`: generate <file>.test.cpp, whose main runs the test blocks instead of the program
+fn compile_tests(file_path: &str, options: &CompileOptions) -> Result<()> {
+    let (program, _) = compile_program(file_path, options, &[])?;
+    let tests = program.items.iter().filter(|item| matches!(item, ast::Item::Test(_))).count();
+    if tests == 0 {
+        anyhow::bail!("{} has no test blocks (test \"name\" {{ ... }})", file_path);
+    }
+    println!("Run the {} test(s) with: ./{}", tests, test_exe_name(file_path, options));
+    Ok(())
+}
+
+/// `doctor`: with a file, require only what its generated code uses, looking for stdlib/ next to it
+fn run_doctor(file_path: Option<&str>) -> Result<()> {
+    let (dir, needs) = match file_path {
+        Some(file_path) => {
+            let source = fs::read_to_string(file_path)
+                .with_context(|| format!("Failed to read file: {}", file_path))?;
+            let (ast, _) = analyze(file_path, &source, false, TargetAbi::host(), &[], &[])?;
+            let mut codegen = CppGenerator::new();
+            let mut generated = vec![codegen.generate(&ast)?];
+            generated.extend(codegen.get_hot_systems().clone().iter().map(|system| codegen.generate_hot_system_dll(system)));
+            if codegen.uses_cuda() {
+                generated.push(codegen.generate_cuda_source());
+            }
+            let generated: Vec<&str> = generated.iter().map(String::as_str).collect();
+            let dir = Path::new(file_path).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf();
+            (dir, Some(doctor::Needs::of(&ast, &generated)))
+        }
+        None => (PathBuf::from("."), None),
+    };
+    if doctor::report(&doctor::run(&dir, needs.as_ref())) > 0 {
+        anyhow::bail!("generated code won't build on this machine until the errors above are fixed");
+    }
+    Ok(())
+}
+
+fn compile_and_run(file_path: &str, options: &CompileOptions) -> Result<()> {
+    compile_file(file_path, options)?;
     
     let exe_name = Path::new(file_path)
         .file_stem()
@@ -128,7 +1379,11 @@ fn compile_and_run(file_path: &str) -> Result<()> {
         .unwrap_or("output");
     
     // Note: In a real implementation, we'd compile and run automatically
-    println!("To run: ./{}", exe_name);
+    if options.target == codegen::Target::Wasm {
+        println!("To run: serve this directory over HTTP and open {}.html (add ?hot to reload on changes)", exe_name);
+    } else {
+        println!("To run: ./{}", exe_name);
+    }
     
     Ok(())
 }
@@ -1,14 +1,26 @@
 use crate::ast::*;
-use crate::lexer::{Token, TokenWithLocation};
-use crate::error::{SourceLocation, ErrorReporter};
+use crate::lexer::{Comment, Token, TokenWithLocation};
+use crate::error::{Diagnostic, SourceLocation, ErrorReporter};
+use crate::hierarchy;
+use crate::input_map;
+use crate::frame_arena;
 use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+// One entry of a block that may end in a value (if-expression branches, match arms)
+enum BlockEntry {
+    Statement(Statement),
+    Value(Expression),  // Last expression of the block, without ';'
+}
 
 pub struct Parser {
     tokens: Vec<TokenWithLocation>,
     current: usize,
     current_location: SourceLocation,
     error_reporter: Option<ErrorReporter>,
-    errors: Vec<(SourceLocation, String, Option<String>)>,  // (location, message, suggestion)
+    errors: Vec<Diagnostic>,
+    item_locations: Vec<SourceLocation>,  // First token (including attributes) of each parsed item
+    doc_lines: HashMap<usize, String>,  // Line -> text of a `///` comment on a line of its own
 }
 
 impl Parser {
@@ -22,15 +34,44 @@ impl Parser {
             current_location,
             error_reporter: None,
             errors: Vec::new(),
+            item_locations: Vec::new(),
+            doc_lines: HashMap::new(),
         }
     }
     
+    /// The lexer's comments, for the `///` doc comments attached to components and their fields
+    pub fn set_comments(&mut self, comments: &[Comment]) {
+        self.doc_lines = comments.iter()
+            .filter(|c| !c.trailing)
+            .filter_map(|c| {
+                let text = c.text.strip_prefix("///").filter(|text| !text.starts_with('/'))?;
+                Some((c.location.line, text.strip_prefix(' ').unwrap_or(text).trim_end().to_string()))
+            })
+            .collect();
+    }
+    
+    // The `///` lines directly above `line`, joined with newlines
+    fn doc_comment(&self, line: usize) -> Option<String> {
+        let first = (1..line).rev().take_while(|l| self.doc_lines.contains_key(l)).last()?;
+        Some((first..line).map(|l| self.doc_lines[&l].as_str()).collect::<Vec<_>>().join("\n"))
+    }
+    
     pub fn set_error_reporter(&mut self, reporter: ErrorReporter) {
         self.error_reporter = Some(reporter);
     }
     
+    /// Errors reported so far (used by the language server instead of the terminal reporter)
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+    
+    /// Where each item of the last parse() starts, parallel to Program::items
+    pub fn item_locations(&self) -> &[SourceLocation] {
+        &self.item_locations
+    }
+    
     fn report_error(&mut self, location: SourceLocation, message: String, suggestion: Option<String>) {
-        self.errors.push((location, message.clone(), suggestion.clone()));
+        self.errors.push(Diagnostic::new(location, message.clone(), suggestion.clone()));
         if let Some(ref reporter) = self.error_reporter {
             reporter.report_error(location, &message, suggestion.as_deref());
         }
@@ -45,38 +86,146 @@ impl Parser {
         let mut items = Vec::new();
         
         while !self.is_at_end() {
-            items.push(self.parse_item()?);
+            let location = self.current_token_location();
+            let start = self.current;
+            let errors_before = self.errors.len();
+            match self.parse_item() {
+                Ok(mut item) => {
+                    if let Item::Component(component) = &mut item {
+                        component.doc = self.doc_comment(location.line);
+                    }
+                    self.item_locations.push(location);
+                    items.push(item);
+                }
+                Err(e) => {
+                    // Keep going so one run reports every syntax error
+                    self.report_unreported(errors_before, &e);
+                    self.synchronize_item(start);
+                }
+            }
         }
         
+        if !self.errors.is_empty() {
+            bail!("Parsing failed with {} error(s). See errors above.", self.errors.len());
+        }
         Ok(Program { items })
     }
     
+    // Errors raised with a bare bail! still need to reach the error list
+    fn report_unreported(&mut self, errors_before: usize, error: &anyhow::Error) {
+        if self.errors.len() == errors_before {
+            let location = self.current_token_location();
+            self.report_error(location, error.to_string(), None);
+        }
+    }
+    
+    // An item keyword at the start of a line: where panic-mode recovery resumes
+    fn at_top_level_item(&self) -> bool {
+        let at_line_start = self.tokens.get(self.current).is_some_and(|t| t.location.column == 1);
+        at_line_start && match self.peek() {
+            Token::Struct | Token::Component | Token::ComponentSOA | Token::System | Token::Shader
+            | Token::Extern | Token::Fn | Token::Resource | Token::Pipeline | Token::Interface
+            | Token::Impl | Token::Hot | Token::At | Token::Const | Token::Uniform => true,
+            Token::Ident(name) if name == "test" || name == "scene" => matches!(self.peek_ahead(1), Some(Token::StringLit(_))),
+            Token::Ident(name) if name == "static_assert" => matches!(self.peek_ahead(1), Some(Token::LParen)),
+            Token::Ident(name) if name == "event" || name == "save_schema" => matches!(self.peek_ahead(1), Some(Token::Ident(_))),
+            Token::Ident(name) if name == "input" || name == "window" => matches!(self.peek_ahead(1), Some(Token::LBrace)),
+            Token::Ident(name) => name == "gpu" || name == "gpu_query",
+            _ => false,
+        }
+    }
+    
+    // Panic-mode recovery after a failed item: skip to the next item (always making progress)
+    fn synchronize_item(&mut self, start: usize) {
+        if self.current == start {
+            self.advance();
+        }
+        while !self.is_at_end() && !self.at_top_level_item() {
+            self.advance();
+        }
+    }
+    
+    // Panic-mode recovery after a failed statement: skip past the next `;` (or a nested
+    // `{ ... }`), stopping before the `}` that closes the enclosing block or at the next item
+    fn synchronize_statement(&mut self, start: usize) {
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                Token::Eof => return,
+                Token::RBrace if depth == 0 => return,
+                _ if depth == 0 && self.current > start && self.at_top_level_item() => return,
+                Token::LBrace => depth += 1,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.advance();
+                        return;
+                    }
+                }
+                Token::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+    
     fn parse_item(&mut self) -> Result<Item> {
         // Parse attributes first (if any)
         let attrs = self.parse_attributes();
         let is_hot = attrs.contains(&"hot".to_string());
         let is_cuda = attrs.contains(&"cuda".to_string());
+        let is_export = attrs.contains(&"export".to_string());
+        let is_tweak = attrs.contains(&"tweak".to_string());
+        let is_replicated = attrs.contains(&"replicated".to_string());
+        let strict_fp = attrs.contains(&"strict_fp".to_string());
+        let is_spatial = attrs.contains(&"spatial".to_string());
+        let pool_capacity = attrs.iter().find_map(|attr| attr.strip_prefix("pool:")?.parse::<u32>().ok());
+        if pool_capacity.is_some() && !matches!(self.peek(), Token::Component | Token::ComponentSOA) {
+            self.report_parse_error("@[pool] applies to components".to_string(),
+                Some("Put it on a component declaration: @[pool(capacity = 4096)] component Bullet { ... }".to_string()));
+        }
         
         match self.peek() {
             Token::Struct => {
                 self.advance();
-                Ok(Item::Struct(self.parse_struct()?))
+                let mut s = self.parse_struct()?;
+                s.is_export = is_export;
+                Ok(Item::Struct(s))
             }
             Token::Component => {
                 self.advance(); // consume 'component'
                 let mut comp = self.parse_component(false, is_hot)?;
                 comp.is_cuda = is_cuda;
+                comp.is_export = is_export;
+                comp.is_replicated = is_replicated;
+                comp.pool_capacity = pool_capacity;
                 Ok(Item::Component(comp))
             }
             Token::ComponentSOA => {
                 self.advance(); // consume 'component_soa'
                 let mut comp = self.parse_component(true, is_hot)?;
                 comp.is_cuda = is_cuda;
+                comp.is_export = is_export;
+                comp.is_replicated = is_replicated;
+                comp.pool_capacity = pool_capacity;
                 Ok(Item::Component(comp))
             }
             Token::System => {
+                let location = self.current_token_location();
                 self.advance();
-                Ok(Item::System(self.parse_system(false)?))
+                let mut system = self.parse_system(false, location)?;
+                system.schedule = system_schedule(&attrs);
+                if strict_fp {
+                    mark_strict_fp(&mut system);
+                }
+                // Handlers run when run_systems() runs their system, in the update stage by default
+                if !system.handlers.is_empty() && system.schedule.is_none() {
+                    system.schedule = Some(SystemSchedule { stage: None, before: Vec::new(), after: Vec::new() });
+                }
+                Ok(Item::System(system))
             }
             Token::Shader => {
                 self.advance();
@@ -84,6 +233,8 @@ impl Parser {
             }
             Token::Hot => {
                 // @hot system name { ... } or @hot shader vertex "path" { } or @hot resource Name: Type = "path";
+                // or @hot fn name(...) { ... }
+                let location = self.current_token_location();
                 self.advance();
                 if self.check(&Token::System) {
                     self.advance();
@@ -114,7 +265,12 @@ impl Parser {
                     }
                     self.expect(&Token::RBrace)?;
                     
-                    Ok(Item::System(SystemDef { name, functions, is_hot: true }))
+                    let schedule = system_schedule(&attrs);
+                    let mut system = SystemDef { name, functions, handlers: Vec::new(), is_hot: true, schedule, strict_fp: false, location };
+                    if strict_fp {
+                        mark_strict_fp(&mut system);
+                    }
+                    Ok(Item::System(system))
                 } else if self.check(&Token::Shader) {
                     self.advance();
                     Ok(Item::Shader(self.parse_shader(true)?))
@@ -126,12 +282,18 @@ impl Parser {
                     Ok(Item::Component(self.parse_component(true, true)?))
                 } else if self.check(&Token::Resource) {
                     self.advance();
-                    Ok(Item::Resource(self.parse_resource(true)?))
+                    Ok(Item::Resource(self.parse_resource(true, is_spatial)?))
+                } else if self.check(&Token::Fn) {
+                    self.advance();
+                    let mut func = self.parse_function()?;
+                    func.is_hot = true;
+                    func.strict_fp = strict_fp;
+                    Ok(Item::Function(func))
                 } else {
                     let location = self.current_token_location();
-                    let suggestion = Some("Use: @hot system Name { ... } or @hot shader vertex \"path\" { }".to_string());
-                    self.report_error(location, "Expected 'system', 'shader', 'component', or 'resource' after '@hot'".to_string(), suggestion);
-                    bail!("Expected 'system', 'shader', 'component', or 'resource' after '@hot'");
+                    let suggestion = Some("Use: @hot system Name { ... }, @hot fn name() { ... } or @hot shader vertex \"path\" { }".to_string());
+                    self.report_error(location, "Expected 'system', 'fn', 'shader', 'component', or 'resource' after '@hot'".to_string(), suggestion);
+                    bail!("Expected 'system', 'fn', 'shader', 'component', or 'resource' after '@hot'");
                 }
             }
             Token::Extern => {
@@ -143,25 +305,138 @@ impl Parser {
                 let mut func = self.parse_function()?;
                 // Check for @[launch(kernel = name)] in attributes
                 for attr in &attrs {
-                    if attr.starts_with("launch:") {
-                        let kernel_name = attr.strip_prefix("launch:").unwrap().to_string();
-                        func.cuda_kernel = Some(kernel_name);
+                    if let Some(kernel) = attr.strip_prefix("launch:") {
+                        let is_async = attrs.contains(&"launch_async".to_string());
+                        func.cuda_kernel = Some(CudaLaunch { kernel: kernel.to_string(), is_async });
                     }
                 }
+                func.no_reload_check = attrs.contains(&"no_reload_check".to_string());
+                func.strict_fp = strict_fp;
                 Ok(Item::Function(func))
             }
             Token::Resource => {
                 self.advance();
-                Ok(Item::Resource(self.parse_resource(false)?))
+                Ok(Item::Resource(self.parse_resource(false, is_spatial)?))
             }
             Token::Pipeline => {
                 self.advance();
                 Ok(Item::Pipeline(self.parse_pipeline()?))
             }
+            Token::Interface => {
+                self.advance();
+                Ok(Item::Interface(self.parse_interface()?))
+            }
+            Token::Impl => {
+                self.advance();
+                Ok(Item::Impl(self.parse_impl()?))
+            }
+            // 'gpu' is contextual so existing code can still use it as an identifier
+            Token::Ident(ref name) if name == "gpu" && matches!(self.peek_ahead(1), Some(Token::LBrace)) => {
+                self.advance();
+                Ok(Item::Gpu(self.parse_gpu_config()?))
+            }
+            Token::Ident(ref name) if name == "gpu_query" && matches!(self.peek_ahead(1), Some(Token::Ident(_))) => {
+                self.advance();
+                Ok(Item::GpuQuery(self.parse_gpu_query()?))
+            }
+            Token::Ident(ref name) if name == "test" && matches!(self.peek_ahead(1), Some(Token::StringLit(_))) => {
+                let location = self.current_token_location();
+                self.advance();
+                let Token::StringLit(name) = self.peek().clone() else { unreachable!() };
+                self.advance();
+                let body = self.parse_block()?;
+                Ok(Item::Test(TestDef { name, body, location }))
+            }
+            Token::Const => {
+                let location = self.current_token_location();
+                self.advance();
+                let name = self.expect_ident()?;
+                let ty = if self.check(&Token::Colon) {
+                    self.advance();
+                    Some(self.parse_type()?)
+                } else {
+                    None
+                };
+                self.expect(&Token::Eq)?;
+                let value = self.parse_expression()?;
+                self.expect(&Token::Semicolon)?;
+                Ok(Item::Const(ConstDef { name, ty, value, is_tweak, location }))
+            }
+            // 'event' is contextual so existing code can still use it as an identifier
+            Token::Ident(ref name) if name == "event" && matches!(self.peek_ahead(1), Some(Token::Ident(_))) => {
+                let location = self.current_token_location();
+                self.advance();
+                let StructDef { name, fields, .. } = self.parse_struct()?;
+                Ok(Item::Event(EventDef { name, fields, location }))
+            }
+            // uniform CameraUBO { view: Mat4, proj: Mat4 }
+            Token::Uniform => {
+                let location = self.current_token_location();
+                self.advance();
+                let StructDef { name, fields, .. } = self.parse_struct()?;
+                Ok(Item::Uniform(UniformDef { name, fields, location }))
+            }
+            // input { jump: Key.Space | Gamepad.A; move_x: axis(Key.A, Key.D); }
+            Token::Ident(ref name) if name == "input" && matches!(self.peek_ahead(1), Some(Token::LBrace)) => {
+                let location = self.current_token_location();
+                self.advance();
+                Ok(Item::Input(self.parse_input(location)?))
+            }
+            // window { title: "Game", width: 1280, height: 720, vsync: true, resizable: true }
+            Token::Ident(ref name) if name == "window" && matches!(self.peek_ahead(1), Some(Token::LBrace)) => {
+                self.advance();
+                Ok(Item::Window(self.parse_window_config()?))
+            }
+            // save_schema SaveGame v2 { ... }; without a name the schema is SaveData
+            Token::Ident(ref name) if name == "save_schema" && matches!(self.peek_ahead(1), Some(Token::Ident(_))) => {
+                let location = self.current_token_location();
+                self.advance();
+                Ok(Item::SaveSchema(self.parse_save_schema(location)?))
+            }
+            // scene "level1.hscene"; - scene::load reads the file before type checking
+            Token::Ident(ref name) if name == "scene" && matches!(self.peek_ahead(1), Some(Token::StringLit(_))) => {
+                let location = self.current_token_location();
+                self.advance();
+                let Token::StringLit(path) = self.peek().clone() else { unreachable!() };
+                self.advance();
+                self.expect(&Token::Semicolon)?;
+                Ok(Item::Scene(SceneDef { path, entities: Vec::new(), location }))
+            }
+            // migrate Health(old: HealthV1): Health { ... } - a function named after the component
+            Token::Ident(ref name) if name == "migrate" && matches!(self.peek_ahead(1), Some(Token::Ident(_)))
+                && matches!(self.peek_ahead(2), Some(Token::LParen)) => {
+                let location = self.current_token_location();
+                self.advance();
+                let function = self.parse_function()?;
+                Ok(Item::Migrate(MigrateDef { function, location }))
+            }
+            // static_assert(condition, "message"); the message is optional
+            Token::Ident(ref name) if name == "static_assert" && matches!(self.peek_ahead(1), Some(Token::LParen)) => {
+                let location = self.current_token_location();
+                self.advance();
+                self.expect(&Token::LParen)?;
+                let condition = self.parse_expression()?;
+                let message = if self.check(&Token::Comma) {
+                    self.advance();
+                    let Token::StringLit(message) = self.peek().clone() else {
+                        let message_location = self.current_token_location();
+                        self.report_error(message_location, "Expected a string message after ',' in static_assert".to_string(),
+                            Some("Use: static_assert(condition, \"what went wrong\");".to_string()));
+                        bail!("Expected a string message in static_assert");
+                    };
+                    self.advance();
+                    Some(message)
+                } else {
+                    None
+                };
+                self.expect(&Token::RParen)?;
+                self.expect(&Token::Semicolon)?;
+                Ok(Item::StaticAssert(StaticAssertDef { condition, message, location }))
+            }
             _ => {
                 let location = self.current_token_location();
                 let token_str = format!("{:?}", self.peek());
-                let suggestion = Some("Expected: struct, component, system, shader, fn, resource, or pipeline".to_string());
+                let suggestion = Some("Expected: struct, component, event, interface, impl, system, shader, fn, const, static_assert, save_schema, scene, resource, pipeline, gpu, gpu_query, or test".to_string());
                 self.report_error(location, format!("Unexpected token at item level: {}", token_str), suggestion);
                 bail!("Unexpected token at item level: {:?}", self.peek());
             }
@@ -169,7 +444,7 @@ impl Parser {
     }
     
     fn parse_struct(&mut self) -> Result<StructDef> {
-        let name = self.expect_ident()?;
+        let name = self.expect_type_name()?;
         self.expect(&Token::LBrace)?;
         
         let mut fields = Vec::new();
@@ -181,7 +456,7 @@ impl Parser {
         }
         self.expect(&Token::RBrace)?;
         
-        Ok(StructDef { name, fields })
+        Ok(StructDef { name, fields, is_export: false })
     }
     
     fn parse_attributes(&mut self) -> Vec<String> {
@@ -197,22 +472,38 @@ impl Parser {
                     self.advance();
                     
                     // Check for attribute parameters (e.g., launch(kernel = name))
-                    if self.check(&Token::LParen) {
+                    if attr_name == "pool" {
+                        // pool(capacity = 4096); a malformed one is reported and skipped
+                        match self.parse_pool_capacity() {
+                            Ok(capacity) => attrs.push(format!("pool:{}", capacity)),
+                            Err(_) => while !matches!(self.peek(), Token::RBracket | Token::Eof) {
+                                self.advance();
+                            },
+                        }
+                    } else if self.check(&Token::LParen) && attr_name != "launch" {
+                        // Name arguments (e.g., after(Input, Camera)) become one "attr:arg" entry each
                         self.advance(); // consume '('
-                        // Parse parameters (simplified: just look for kernel = name)
-                        if let Token::Ident(ref param) = *self.peek() {
-                            if param == "kernel" {
-                                self.advance(); // consume "kernel"
-                                if self.check(&Token::Eq) {
-                                    self.advance(); // consume '='
-                                    if let Token::Ident(ref kernel_name) = *self.peek() {
-                                        let name = kernel_name.clone();
-                                        self.advance(); // consume kernel name
-                                        attrs.push(format!("launch:{}", name));
-                                        self.expect(&Token::RParen).ok(); // consume ')'
-                                    }
+                        while let Token::Ident(ref arg) = *self.peek() {
+                            attrs.push(format!("{}:{}", attr_name, arg));
+                            self.advance();
+                            if !self.check(&Token::Comma) {
+                                break;
+                            }
+                            self.advance();
+                        }
+                        self.expect(&Token::RParen).ok(); // consume ')'
+                    } else if attr_name == "launch" {
+                        // launch(kernel = name, async = true); a malformed one is reported and skipped
+                        match self.parse_launch_arguments() {
+                            Ok((kernel, is_async)) => {
+                                attrs.push(format!("launch:{}", kernel));
+                                if is_async {
+                                    attrs.push("launch_async".to_string());
                                 }
                             }
+                            Err(_) => while !matches!(self.peek(), Token::RBracket | Token::Eof) {
+                                self.advance();
+                            },
                         }
                     } else {
                         attrs.push(attr_name);
@@ -223,6 +514,14 @@ impl Parser {
                 // Handle @hot (legacy)
                 self.advance();
                 attrs.push("hot".to_string());
+            } else if matches!(self.peek(), Token::Ident(name) if name == "tweak") {
+                // @tweak const NAME = value;
+                self.advance();
+                attrs.push("tweak".to_string());
+            } else if matches!(self.peek(), Token::Ident(name) if name == "strict_fp") {
+                // @strict_fp fn ... / @strict_fp system ...
+                self.advance();
+                attrs.push("strict_fp".to_string());
             } else {
                 // Not an attribute, put back the '@'
                 self.current -= 1;
@@ -232,41 +531,290 @@ impl Parser {
         attrs
     }
     
+    // (kernel = name) after @[launch, optionally followed by `, async = true`
+    fn parse_launch_arguments(&mut self) -> Result<(String, bool)> {
+        let usage = "Name the kernel: @[launch(kernel = update_physics)] or @[launch(kernel = update_physics, async = true)]".to_string();
+        if !self.check(&Token::LParen) || !matches!(self.peek_ahead(1), Some(Token::Ident(name)) if name == "kernel") {
+            self.report_parse_error("@[launch] needs a kernel name".to_string(), Some(usage));
+            bail!("@[launch] needs a kernel name");
+        }
+        self.advance();
+        self.advance();
+        self.expect(&Token::Eq)?;
+        let kernel = self.expect_ident()?;
+        let mut is_async = false;
+        if self.check(&Token::Comma) {
+            self.advance();
+            if !matches!(self.peek(), Token::Ident(name) if name == "async") {
+                self.report_parse_error(format!("Unknown @[launch] argument {:?}", self.peek()), Some(usage));
+                bail!("Unknown @[launch] argument");
+            }
+            self.advance();
+            self.expect(&Token::Eq)?;
+            is_async = match *self.peek() {
+                Token::True => true,
+                Token::False => false,
+                _ => {
+                    self.report_parse_error("async takes true or false".to_string(), Some(usage));
+                    bail!("async takes true or false");
+                }
+            };
+            self.advance();
+        }
+        self.expect(&Token::RParen)?;
+        Ok((kernel, is_async))
+    }
+    
+    // (capacity = N) after @[pool: the most components of the type alive at once
+    fn parse_pool_capacity(&mut self) -> Result<u32> {
+        let usage = "Give the pool its size: @[pool(capacity = 4096)]".to_string();
+        if !self.check(&Token::LParen) || !matches!(self.peek_ahead(1), Some(Token::Ident(name)) if name == "capacity") {
+            self.report_parse_error("@[pool] needs a capacity".to_string(), Some(usage));
+            bail!("@[pool] needs a capacity");
+        }
+        self.advance();
+        self.advance();
+        self.expect(&Token::Eq)?;
+        let capacity = match *self.peek() {
+            Token::Int(n) if n > 0 && n <= u32::MAX as i64 => n as u32,
+            _ => {
+                self.report_parse_error("The pool capacity must be a positive integer".to_string(), Some(usage));
+                bail!("The pool capacity must be a positive integer");
+            }
+        };
+        self.advance();
+        self.expect(&Token::RParen)?;
+        Ok(capacity)
+    }
+    
     fn parse_component(&mut self, is_soa: bool, is_hot: bool) -> Result<ComponentDef> {
-        let name = self.expect_ident()?;
+        let name = self.expect_type_name()?;
         self.expect(&Token::LBrace)?;
         
         let mut fields = Vec::new();
         while !self.check(&Token::RBrace) {
-            fields.push(self.parse_field()?);
+            let doc = self.doc_comment(self.current_token_location().line);
+            let mut cold = false;
+            let mut range = None;
+            let mut unit = None;
+            let mut range_location = None;
+            while self.check(&Token::At) {
+                let location = self.current_token_location();
+                match self.peek_ahead(1) {
+                    // @cold field: rarely accessed, kept out of the hot field arrays of a component_soa
+                    Some(Token::Ident(attr)) if attr == "cold" => {
+                        self.advance();
+                        self.advance();
+                        if !is_soa {
+                            let suggestion = Some(format!("Declare it component_soa {} to keep cold fields in a side array; a component's fields are one struct that query loops step over whole", name));
+                            self.report_error(location, format!("@cold needs a component_soa, but {} is a component", name), suggestion);
+                            bail!("@cold needs a component_soa");
+                        }
+                        cold = true;
+                    }
+                    // @[range(min, max)] / @[unit("m/s")]: inspector metadata in the component's reflection data
+                    Some(Token::LBracket) => {
+                        self.advance();
+                        self.advance();
+                        match self.expect_ident()?.as_str() {
+                            "range" => {
+                                self.expect(&Token::LParen)?;
+                                let min = self.parse_attribute_number()?;
+                                self.expect(&Token::Comma)?;
+                                let max = self.parse_attribute_number()?;
+                                self.expect(&Token::RParen)?;
+                                range = Some((min, max));
+                                range_location = Some(location);
+                            }
+                            "unit" => {
+                                self.expect(&Token::LParen)?;
+                                let Token::StringLit(text) = self.peek().clone() else {
+                                    self.report_parse_error("Expected the unit as a string".to_string(), Some("Write the unit in quotes: @[unit(\"m/s\")]".to_string()));
+                                    bail!("Expected the unit as a string");
+                                };
+                                self.advance();
+                                self.expect(&Token::RParen)?;
+                                unit = Some(text);
+                            }
+                            other => {
+                                let suggestion = Some("Component fields take @[range(min, max)], @[unit(\"...\")] and @cold".to_string());
+                                self.report_error(location, format!("Unknown field attribute '{}'", other), suggestion);
+                                bail!("Unknown field attribute '{}'", other);
+                            }
+                        }
+                        self.expect(&Token::RBracket)?;
+                    }
+                    _ => break,
+                }
+            }
+            let mut field = self.parse_field()?;
+            if let (Some((min, max)), Some(location)) = (range, range_location) {
+                // A component_soa field is an array; the range bounds its elements
+                let element = match &field.ty { Type::Array(element) => element.as_ref(), ty => ty };
+                if !matches!(element, Type::I32 | Type::I64 | Type::F32 | Type::F64 | Type::Vec2 | Type::Vec3 | Type::Vec4) {
+                    let suggestion = Some("A range bounds a number, or each component of a Vec2/Vec3/Vec4".to_string());
+                    self.report_error(location, format!("@[range] needs a number or vector field, but {} is {}", field.name, crate::formatter::type_str(&field.ty)), suggestion);
+                    bail!("@[range] needs a number or vector field");
+                }
+                if min >= max {
+                    let suggestion = Some(format!("Write the smaller bound first: @[range({}, {})]", max, min));
+                    self.report_error(location, format!("The range of {} is empty: {} is not below {}", field.name, min, max), suggestion);
+                    bail!("Empty field range");
+                }
+            }
+            field.cold = cold;
+            field.doc = doc;
+            field.range = range;
+            field.unit = unit;
+            fields.push(field);
             if !self.check(&Token::RBrace) {
                 self.expect(&Token::Comma)?;
             }
         }
         self.expect(&Token::RBrace)?;
         
-        Ok(ComponentDef { name, fields, is_soa, is_hot, is_cuda: false })
+        Ok(ComponentDef { name, fields, is_soa, is_hot, is_cuda: false, is_export: false, is_replicated: false, pool_capacity: None, doc: None })
+    }
+    
+    fn parse_interface(&mut self) -> Result<InterfaceDef> {
+        // Parse: interface Name { fn method(self, param: Type): ReturnType; ... }
+        let location = self.current_token_location();
+        let name = self.expect_ident()?;
+        self.expect(&Token::LBrace)?;
+        
+        let self_type = Type::Struct(name.clone());
+        let mut methods = Vec::new();
+        while !self.check(&Token::RBrace) {
+            if !self.check(&Token::Fn) {
+                let location = self.current_token_location();
+                let suggestion = Some("Declare a method signature: fn method_name(self, param: Type): ReturnType;".to_string());
+                self.report_error(location, "Expected method signature in interface".to_string(), suggestion);
+                bail!("Expected method signature in interface");
+            }
+            self.advance();
+            let method_location = self.current_token_location();
+            let (method_name, params, return_type) = self.parse_method_signature(&self_type)?;
+            if self.check(&Token::LBrace) {
+                let location = self.current_token_location();
+                let suggestion = Some(format!("Move the body into an impl block: impl {} for Component {{ ... }}", name));
+                self.report_error(location, format!("Interface method '{}' cannot have a body", method_name), suggestion);
+                bail!("Interface method '{}' cannot have a body", method_name);
+            }
+            // Optional semicolon after each signature
+            if self.check(&Token::Semicolon) {
+                self.advance();
+            }
+            methods.push(InterfaceMethod { name: method_name, params, return_type, location: method_location });
+        }
+        self.expect(&Token::RBrace)?;
+        
+        Ok(InterfaceDef { name, methods, location })
+    }
+    
+    fn parse_impl(&mut self) -> Result<ImplDef> {
+        // Parse: impl Interface for Target { fn method(self, param: Type): ReturnType { ... } ... }
+        let location = self.current_token_location();
+        let interface = self.expect_ident()?;
+        self.expect(&Token::For)?;
+        let target = self.expect_ident()?;
+        self.expect(&Token::LBrace)?;
+        
+        let self_type = Type::Struct(target.clone());
+        let mut methods = Vec::new();
+        while !self.check(&Token::RBrace) {
+            if !self.check(&Token::Fn) {
+                let location = self.current_token_location();
+                let suggestion = Some("Add a method: fn method_name(self, param: Type): ReturnType { ... }".to_string());
+                self.report_error(location, "Expected method in impl block".to_string(), suggestion);
+                bail!("Expected method in impl block");
+            }
+            self.advance();
+            let method_location = self.current_token_location();
+            let (name, params, return_type) = self.parse_method_signature(&self_type)?;
+            let body = self.parse_block()?;
+            methods.push((FunctionDef { name, params, return_type, body, cuda_kernel: None, no_reload_check: false, is_hot: false, strict_fp: false }, method_location));
+        }
+        self.expect(&Token::RBrace)?;
+        
+        Ok(ImplDef { interface, target, methods, location })
     }
     
-    fn parse_system(&mut self, is_hot: bool) -> Result<SystemDef> {
+    fn parse_method_signature(&mut self, self_type: &Type) -> Result<(String, Vec<Param>, Type)> {
+        // Parse: name(self, param: Type, ...): ReturnType - the receiver is untyped and always first
+        let name = self.expect_ident()?;
+        self.expect(&Token::LParen)?;
+        
+        let self_location = self.current_token_location();
+        if !matches!(self.peek(), Token::Ident(ref s) if s == "self") {
+            let location = self.current_token_location();
+            let suggestion = Some(format!("Add the receiver as the first parameter: fn {}(self, ...)", name));
+            self.report_error(location, format!("Method '{}' must take 'self' as its first parameter", name), suggestion);
+            bail!("Method '{}' must take 'self' as its first parameter", name);
+        }
+        self.advance();
+        let mut params = vec![Param { name: "self".to_string(), ty: self_type.clone(), default: None, mutable: false, location: self_location }];
+        
+        while self.check(&Token::Comma) {
+            self.advance();
+            let param_location = self.current_token_location();
+            let mutable = self.check(&Token::Mut);
+            if mutable {
+                self.advance();
+            }
+            let param_name = self.expect_ident()?;
+            self.expect(&Token::Colon)?;
+            let param_type = self.parse_type()?;
+            // Optional default value: name: Type = expr
+            let default = if self.check(&Token::Eq) {
+                self.advance();
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            params.push(Param { name: param_name, ty: param_type, default, mutable, location: param_location });
+        }
+        self.expect(&Token::RParen)?;
+        
+        let return_type = if self.check(&Token::Colon) {
+            self.advance();
+            self.parse_type()?
+        } else {
+            Type::Void
+        };
+        
+        Ok((name, params, return_type))
+    }
+    
+    fn parse_system(&mut self, is_hot: bool, location: SourceLocation) -> Result<SystemDef> {
         let name = self.expect_ident()?;
         self.expect(&Token::LBrace)?;
         
         let mut functions = Vec::new();
+        let mut handlers = Vec::new();
         while !self.check(&Token::RBrace) {
             if self.check(&Token::Fn) {
                 self.advance();
                 functions.push(self.parse_function()?);
+            } else if matches!(self.peek(), Token::Ident(name) if name == "on") && matches!(self.peek_ahead(1), Some(Token::Ident(_))) {
+                // on Collision(ev) { ... }
+                let location = self.current_token_location();
+                self.advance();
+                let event = self.expect_ident()?;
+                self.expect(&Token::LParen)?;
+                let param = self.expect_ident()?;
+                self.expect(&Token::RParen)?;
+                let body = self.parse_block()?;
+                handlers.push(EventHandler { event, param, body, location });
             } else {
                 let location = self.current_token_location();
-                let suggestion = Some("Add a function declaration: fn function_name() { ... }".to_string());
-                self.report_error(location, "Expected function in system".to_string(), suggestion);
-                bail!("Expected function in system");
+                let suggestion = Some("Add a function declaration: fn function_name() { ... }, or an event handler: on EventName(ev) { ... }".to_string());
+                self.report_error(location, "Expected function or event handler in system".to_string(), suggestion);
+                bail!("Expected function or event handler in system");
             }
         }
         self.expect(&Token::RBrace)?;
         
-        Ok(SystemDef { name, functions, is_hot })
+        Ok(SystemDef { name, functions, handlers, is_hot, schedule: None, strict_fp: false, location })
     }
     
     fn parse_shader(&mut self, is_hot: bool) -> Result<crate::ast::ShaderDef> {
@@ -305,6 +853,23 @@ impl Parser {
             }
         };
         
+        // Inline shader: shader vertex Name { glsl { ... } }
+        if let Token::Ident(name) = self.peek().clone() {
+            self.advance();
+            self.expect(&Token::LBrace)?;
+            let location = self.current_token_location();
+            let Token::GlslBlock(glsl) = self.peek().clone() else {
+                let suggestion = Some(format!("Embed the GLSL source: shader <stage> {} {{ glsl {{ ... }} }}", name));
+                self.report_error(location, "Expected 'glsl { ... }' in inline shader".to_string(), suggestion);
+                bail!("Expected 'glsl {{ ... }}' in inline shader");
+            };
+            self.advance();
+            self.expect(&Token::RBrace)?;
+            let path = crate::shaders::inline_path(&name, &stage);
+            let inline = Some(crate::ast::InlineShader { name, glsl, location });
+            return Ok(crate::ast::ShaderDef { stage, path, is_hot, inline });
+        }
+        
         // Parse shader path: "path/to/shader.glsl"
         let path = if let Token::StringLit(ref path) = *self.peek() {
             let path = path.clone();
@@ -333,16 +898,22 @@ impl Parser {
             self.advance();
         }
         
-        Ok(crate::ast::ShaderDef { stage, path, is_hot })
+        Ok(crate::ast::ShaderDef { stage, path, is_hot, inline: None })
     }
     
-    fn parse_resource(&mut self, is_hot: bool) -> Result<crate::ast::ResourceDef> {
+    fn parse_resource(&mut self, is_hot: bool, is_spatial: bool) -> Result<crate::ast::ResourceDef> {
         // Parse: resource Name: Type = "path";
         let name = self.expect_ident()?;
         self.expect(&Token::Colon)?;
         
         // Parse resource type (Texture, Mesh, etc.)
+        let type_location = self.current_token_location();
         let resource_type = self.expect_ident()?;
+        if is_spatial && resource_type != "Sound" {
+            let suggestion = Some("Remove @[spatial]: only Sound resources play at an entity's position".to_string());
+            self.report_error(type_location, format!("@[spatial] applies to Sound resources, but '{}' is {}", name, resource_type), suggestion);
+            bail!("@[spatial] applies to Sound resources");
+        }
         
         self.expect(&Token::Eq)?;
         
@@ -361,6 +932,18 @@ impl Parser {
             }
         };
         
+        let texture_options = if self.check(&Token::LBrace) {
+            if resource_type != "Texture" && resource_type != "Image" {
+                let location = self.current_token_location();
+                let suggestion = Some("Sampler options ({ filter, wrap, mips }) apply to Texture and Image resources".to_string());
+                self.report_error(location, format!("{} resources don't take options", resource_type), suggestion);
+                bail!("{} resources don't take options", resource_type);
+            }
+            Some(self.parse_texture_options()?)
+        } else {
+            None
+        };
+        
         self.expect(&Token::Semicolon)?;
         
         Ok(crate::ast::ResourceDef {
@@ -368,21 +951,380 @@ impl Parser {
             resource_type,
             path,
             is_hot,
+            texture_options,
+            is_spatial,
         })
     }
     
+    fn parse_texture_options(&mut self) -> Result<TextureOptions> {
+        // Parse: { filter: nearest, wrap: clamp, mips: true }
+        self.expect(&Token::LBrace)?;
+        let mut options = TextureOptions::default();
+        
+        while !self.check(&Token::RBrace) {
+            let key_location = self.current_token_location();
+            let key = self.expect_ident()?;
+            self.expect(&Token::Colon)?;
+            let value_location = self.current_token_location();
+            match key.as_str() {
+                "filter" => {
+                    let value = self.expect_ident()?;
+                    options.filter = match value.as_str() {
+                        "linear" => TextureFilter::Linear,
+                        "nearest" => TextureFilter::Nearest,
+                        _ => {
+                            let suggestion = Some("Use: filter: linear or filter: nearest".to_string());
+                            self.report_error(value_location, format!("Unknown texture filter '{}'", value), suggestion);
+                            bail!("Unknown texture filter '{}'", value);
+                        }
+                    };
+                }
+                "wrap" => {
+                    let value = self.expect_ident()?;
+                    options.wrap = match value.as_str() {
+                        "repeat" => TextureWrap::Repeat,
+                        "clamp" => TextureWrap::Clamp,
+                        "mirror" => TextureWrap::Mirror,
+                        _ => {
+                            let suggestion = Some("Use: wrap: repeat, wrap: clamp, or wrap: mirror".to_string());
+                            self.report_error(value_location, format!("Unknown texture wrap mode '{}'", value), suggestion);
+                            bail!("Unknown texture wrap mode '{}'", value);
+                        }
+                    };
+                }
+                "mips" => {
+                    options.mips = match self.peek() {
+                        Token::True => true,
+                        Token::False => false,
+                        _ => {
+                            let suggestion = Some("Use: mips: true or mips: false".to_string());
+                            self.report_error(value_location, "Expected true or false for mips".to_string(), suggestion);
+                            bail!("Expected true or false for mips");
+                        }
+                    };
+                    self.advance();
+                }
+                _ => {
+                    let suggestion = Some("Valid keys: filter, wrap, mips".to_string());
+                    self.report_error(key_location, format!("Unknown texture option '{}'", key), suggestion);
+                    bail!("Unknown texture option '{}'", key);
+                }
+            }
+            
+            if !self.check(&Token::RBrace) {
+                self.expect(&Token::Comma)?;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        
+        Ok(options)
+    }
+    
+    fn parse_gpu_config(&mut self) -> Result<GpuConfig> {
+        // Parse: gpu { prefer: discrete, device: 0, transfer_queue: dedicated, required_features: [a, b], optional_features: [c] }
+        let location = self.current_location;
+        self.expect(&Token::LBrace)?;
+        
+        let mut config = GpuConfig {
+            prefer: GpuPreference::Any,
+            device_index: None,
+            transfer_queue: TransferQueueMode::Dedicated,
+            required_features: Vec::new(),
+            optional_features: Vec::new(),
+            location,
+        };
+        
+        while !self.check(&Token::RBrace) {
+            let key_location = self.current_token_location();
+            let key = self.expect_ident()?;
+            self.expect(&Token::Colon)?;
+            match key.as_str() {
+                "prefer" => {
+                    let value_location = self.current_token_location();
+                    let value = self.expect_ident()?;
+                    config.prefer = match value.as_str() {
+                        "discrete" => GpuPreference::Discrete,
+                        "integrated" => GpuPreference::Integrated,
+                        "any" => GpuPreference::Any,
+                        _ => {
+                            let suggestion = Some("Use: prefer: discrete, prefer: integrated, or prefer: any".to_string());
+                            self.report_error(value_location, format!("Unknown GPU preference '{}'", value), suggestion);
+                            bail!("Unknown GPU preference '{}'", value);
+                        }
+                    };
+                }
+                "device" => {
+                    let token = self.peek().clone();
+                    match token {
+                        Token::Int(n) if n >= 0 => {
+                            self.advance();
+                            config.device_index = Some(n as u32);
+                        }
+                        _ => {
+                            let location = self.current_token_location();
+                            let suggestion = Some("Provide a device index: device: 0".to_string());
+                            self.report_error(location, "Expected a non-negative device index".to_string(), suggestion);
+                            bail!("Expected a non-negative device index");
+                        }
+                    }
+                }
+                "transfer_queue" => {
+                    let value_location = self.current_token_location();
+                    let value = self.expect_ident()?;
+                    config.transfer_queue = match value.as_str() {
+                        "dedicated" => TransferQueueMode::Dedicated,
+                        "shared" => TransferQueueMode::Shared,
+                        _ => {
+                            let suggestion = Some("Use: transfer_queue: dedicated or transfer_queue: shared".to_string());
+                            self.report_error(value_location, format!("Unknown transfer queue mode '{}'", value), suggestion);
+                            bail!("Unknown transfer queue mode '{}'", value);
+                        }
+                    };
+                }
+                "required_features" => {
+                    config.required_features = self.parse_gpu_feature_list()?;
+                }
+                "optional_features" => {
+                    config.optional_features = self.parse_gpu_feature_list()?;
+                }
+                _ => {
+                    let suggestion = Some("Valid keys: prefer, device, transfer_queue, required_features, optional_features".to_string());
+                    self.report_error(key_location, format!("Unknown gpu option '{}'", key), suggestion);
+                    bail!("Unknown gpu option '{}'", key);
+                }
+            }
+            
+            if !self.check(&Token::RBrace) {
+                // Optional comma or semicolon
+                if self.check(&Token::Comma) || self.check(&Token::Semicolon) {
+                    self.advance();
+                }
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        
+        Ok(config)
+    }
+    
+    fn parse_window_config(&mut self) -> Result<WindowConfig> {
+        // Parse: window { title: "Game", width: 1280, height: 720, vsync: true, resizable: true }
+        let location = self.current_location;
+        self.expect(&Token::LBrace)?;
+        
+        let mut config = WindowConfig {
+            title: "HEIDIC".to_string(),
+            width: 1280,
+            height: 720,
+            vsync: true,
+            resizable: true,
+            location,
+        };
+        
+        while !self.check(&Token::RBrace) {
+            let key_location = self.current_token_location();
+            let key = self.expect_ident()?;
+            self.expect(&Token::Colon)?;
+            let value_location = self.current_token_location();
+            match key.as_str() {
+                "title" => {
+                    let Token::StringLit(title) = self.peek().clone() else {
+                        let suggestion = Some("Quote the title: title: \"Game\"".to_string());
+                        self.report_error(value_location, "Expected a string for title".to_string(), suggestion);
+                        bail!("Expected a string for title");
+                    };
+                    self.advance();
+                    config.title = title;
+                }
+                "width" | "height" => {
+                    let size = match self.peek() {
+                        Token::Int(n) if *n > 0 && *n <= u32::MAX as i64 => *n as u32,
+                        _ => {
+                            let suggestion = Some(format!("Give the size in pixels: {}: {}", key, if key == "width" { 1280 } else { 720 }));
+                            self.report_error(value_location, format!("Expected a positive {} in pixels", key), suggestion);
+                            bail!("Expected a positive {} in pixels", key);
+                        }
+                    };
+                    self.advance();
+                    if key == "width" {
+                        config.width = size;
+                    } else {
+                        config.height = size;
+                    }
+                }
+                "vsync" | "resizable" => {
+                    let value = match self.peek() {
+                        Token::True => true,
+                        Token::False => false,
+                        _ => {
+                            let suggestion = Some(format!("Use: {}: true or {}: false", key, key));
+                            self.report_error(value_location, format!("Expected true or false for {}", key), suggestion);
+                            bail!("Expected true or false for {}", key);
+                        }
+                    };
+                    self.advance();
+                    if key == "vsync" {
+                        config.vsync = value;
+                    } else {
+                        config.resizable = value;
+                    }
+                }
+                _ => {
+                    let suggestion = Some("Valid keys: title, width, height, vsync, resizable".to_string());
+                    self.report_error(key_location, format!("Unknown window option '{}'", key), suggestion);
+                    bail!("Unknown window option '{}'", key);
+                }
+            }
+            
+            if !self.check(&Token::RBrace) {
+                // Optional comma or semicolon
+                if self.check(&Token::Comma) || self.check(&Token::Semicolon) {
+                    self.advance();
+                }
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        
+        Ok(config)
+    }
+    
+    // Each action is `name: binding | binding;`, a binding being a control (Key.Space, Mouse.Left,
+    // Gamepad.LeftX) or axis(negative, positive). Control names are checked by the type checker.
+    fn parse_input(&mut self, location: SourceLocation) -> Result<InputDef> {
+        self.expect(&Token::LBrace)?;
+        let mut actions = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let action_location = self.current_token_location();
+            let name = self.expect_ident()?;
+            self.expect(&Token::Colon)?;
+            let mut bindings = vec![self.parse_input_binding()?];
+            while self.check(&Token::Pipe) {
+                self.advance();
+                bindings.push(self.parse_input_binding()?);
+            }
+            self.expect(&Token::Semicolon)?;
+            actions.push(InputAction { name, bindings, location: action_location });
+        }
+        self.expect(&Token::RBrace)?;
+        Ok(InputDef { actions, location })
+    }
+    
+    fn parse_input_binding(&mut self) -> Result<InputBinding> {
+        if matches!(self.peek(), Token::Ident(name) if name == "axis") && matches!(self.peek_ahead(1), Some(Token::LParen)) {
+            self.advance();
+            self.advance();
+            let negative = self.parse_input_source()?;
+            self.expect(&Token::Comma)?;
+            let positive = self.parse_input_source()?;
+            self.expect(&Token::RParen)?;
+            return Ok(InputBinding::Axis(negative, positive));
+        }
+        Ok(InputBinding::Source(self.parse_input_source()?))
+    }
+    
+    fn parse_input_source(&mut self) -> Result<InputSource> {
+        let location = self.current_token_location();
+        let device = self.expect_ident()?;
+        if !self.check(&Token::Dot) {
+            let suggestion = Some("Bind a key, mouse button or gamepad control, e.g. Key.Space, Mouse.Left or Gamepad.A".to_string());
+            self.report_error(location, format!("Expected an input control, found '{}'", device), suggestion);
+            bail!("Expected an input control");
+        }
+        self.advance();
+        let name = self.expect_ident()?;
+        Ok(InputSource { device, name, location })
+    }
+    
+    fn parse_gpu_query(&mut self) -> Result<GpuQueryDef> {
+        // Parse: gpu_query Kind [(statistic)] [Name] [[count]];
+        let location = self.current_location;
+        let kind_location = self.current_token_location();
+        let kind_name = self.expect_ident()?;
+        let kind = match kind_name.as_str() {
+            "Occlusion" => GpuQueryKind::Occlusion,
+            "Timestamp" => GpuQueryKind::Timestamp,
+            "PipelineStatistics" => {
+                if self.check(&Token::LParen) {
+                    self.advance();
+                    let statistic_location = self.current_token_location();
+                    let statistic = self.expect_ident()?;
+                    self.expect(&Token::RParen)?;
+                    GpuQueryKind::PipelineStatistics(statistic, statistic_location)
+                } else {
+                    GpuQueryKind::PipelineStatistics("fragment_shader_invocations".to_string(), kind_location)
+                }
+            }
+            _ => {
+                let suggestion = Some("Use: gpu_query Occlusion, gpu_query Timestamp, or gpu_query PipelineStatistics(statistic)".to_string());
+                self.report_error(kind_location, format!("Unknown GPU query kind '{}'", kind_name), suggestion);
+                bail!("Unknown GPU query kind '{}'", kind_name);
+            }
+        };
+        
+        let name = if let Token::Ident(_) = self.peek() {
+            self.expect_ident()?
+        } else {
+            kind_name.clone()
+        };
+        
+        let mut count = 64;
+        if self.check(&Token::LBracket) {
+            self.advance();
+            let token = self.peek().clone();
+            match token {
+                Token::Int(n) if n > 0 => {
+                    self.advance();
+                    count = n as u32;
+                }
+                _ => {
+                    let location = self.current_token_location();
+                    let suggestion = Some(format!("Provide a positive slot count: gpu_query {} {}[16];", kind_name, name));
+                    self.report_error(location, "Expected a positive query slot count".to_string(), suggestion);
+                    bail!("Expected a positive query slot count");
+                }
+            }
+            self.expect(&Token::RBracket)?;
+        }
+        self.expect(&Token::Semicolon)?;
+        
+        Ok(GpuQueryDef { name, kind, count, location })
+    }
+    
+    fn parse_gpu_feature_list(&mut self) -> Result<Vec<(String, SourceLocation)>> {
+        // Parse: [featureName, featureName, ...] (Vulkan feature names, e.g. samplerAnisotropy)
+        self.expect(&Token::LBracket)?;
+        let mut features = Vec::new();
+        while !self.check(&Token::RBracket) {
+            let location = self.current_token_location();
+            features.push((self.expect_ident()?, location));
+            if !self.check(&Token::RBracket) {
+                self.expect(&Token::Comma)?;
+            }
+        }
+        self.expect(&Token::RBracket)?;
+        Ok(features)
+    }
+    
     fn parse_pipeline(&mut self) -> Result<crate::ast::PipelineDef> {
-        use crate::ast::{PipelineDef, PipelineShader, PipelineLayout, LayoutBinding, BindingType, ShaderStage};
+        use crate::ast::{PipelineDef, PipelineShader, PipelineLayout, LayoutBinding, BindingType, ShaderStage, VertexInput, PipelineState, PushConstants};
         
-        // Parse: pipeline name { shader vertex "path"; shader fragment "path"; layout { ... } }
+        // Parse: pipeline name { shader vertex "path"; shader fragment "path"; vertex_input Vertex; blend: alpha; layout { ... } }
         let name = self.expect_ident()?;
         self.expect(&Token::LBrace)?;
         
         let mut shaders = Vec::new();
         let mut layout = None;
+        let mut vertex_input = None;
+        let mut state = PipelineState::default();
         
         while !self.check(&Token::RBrace) {
-            if self.check(&Token::Shader) {
+            if matches!(self.peek(), Token::Ident(_)) && matches!(self.peek_ahead(1), Some(Token::Colon)) {
+                self.parse_pipeline_state_key(&mut state)?;
+            } else if matches!(self.peek(), Token::Ident(clause) if clause == "vertex_input") {
+                self.advance();
+                let location = self.current_token_location();
+                let struct_name = self.expect_ident()?;
+                vertex_input = Some(VertexInput { struct_name, location });
+            } else if self.check(&Token::Shader) {
                 self.advance();
                 
                 // Parse shader stage
@@ -419,29 +1361,57 @@ impl Parser {
                     }
                 };
                 
-                // Parse shader path
+                // Parse shader path, or the name of an inline shader
+                let location = self.current_token_location();
                 let path_token = self.peek().clone();
-                let path = match path_token {
+                let (path, inline) = match path_token {
                     Token::StringLit(p) => {
                         self.advance();
-                        p
+                        (p, None)
+                    }
+                    Token::Ident(name) => {
+                        self.advance();
+                        (crate::shaders::spv_path(&crate::shaders::inline_path(&name, &stage)), Some(name))
                     }
                     _ => {
                         let location = self.current_token_location();
-                        let suggestion = Some("Provide a string literal path: shader vertex \"path/to/shader.vert\"".to_string());
+                        let suggestion = Some("Provide a path or an inline shader name: shader vertex \"path/to/shader.vert\"".to_string());
                         self.report_error(location, "Expected string literal for shader path".to_string(), suggestion);
                         bail!("Expected string literal for shader path");
                     }
                 };
                 
-                shaders.push(PipelineShader { stage, path });
+                shaders.push(PipelineShader { stage, path, inline, location });
             } else if self.check(&Token::Layout) {
                 self.advance();
                 self.expect(&Token::LBrace)?;
                 
                 let mut bindings = Vec::new();
+                let mut push_constants = None;
                 while !self.check(&Token::RBrace) {
+                    // Parse: push_constants { name: Type, ... }
+                    if matches!(self.peek(), Token::Ident(clause) if clause == "push_constants") {
+                        let location = self.current_token_location();
+                        if push_constants.is_some() {
+                            let suggestion = Some("A pipeline has one push constant range; put every field in one block".to_string());
+                            self.report_error(location, format!("Duplicate push_constants in pipeline '{}' layout", name), suggestion);
+                            bail!("Duplicate push_constants in pipeline '{}' layout", name);
+                        }
+                        self.advance();
+                        self.expect(&Token::LBrace)?;
+                        let mut fields = Vec::new();
+                        while !self.check(&Token::RBrace) {
+                            fields.push(self.parse_field()?);
+                            if !self.check(&Token::RBrace) {
+                                self.expect(&Token::Comma)?;
+                            }
+                        }
+                        self.expect(&Token::RBrace)?;
+                        push_constants = Some(PushConstants { fields, location });
+                        continue;
+                    }
                     // Parse: binding N: type ResourceName
+                    let binding_location = self.current_token_location();
                     self.expect(&Token::Binding)?;
                     let binding_num_token = self.peek().clone();
                     let binding_num = match binding_num_token {
@@ -487,8 +1457,10 @@ impl Parser {
                         bail!("Expected binding type: uniform, storage, or sampler2D");
                     };
                     
-                    // Parse resource name (optional, for reference)
-                    let resource_name = if matches!(self.peek(), Token::Ident(_)) {
+                    // Parse resource name (optional, for reference); `push_constants {` starts the next entry
+                    let starts_push_constants = matches!(self.peek(), Token::Ident(clause) if clause == "push_constants")
+                        && matches!(self.peek_ahead(1), Some(Token::LBrace));
+                    let resource_name = if matches!(self.peek(), Token::Ident(_)) && !starts_push_constants {
                         let name = self.expect_ident()?;
                         name
                     } else {
@@ -499,6 +1471,7 @@ impl Parser {
                         binding: binding_num,
                         binding_type,
                         name: resource_name,
+                        location: binding_location,
                     });
                     
                     if !self.check(&Token::RBrace) {
@@ -523,18 +1496,69 @@ impl Parser {
                     }
                 }
                 
-                layout = Some(PipelineLayout { bindings });
+                layout = Some(PipelineLayout { bindings, push_constants });
             } else {
                 let location = self.current_token_location();
-                let suggestion = Some("Use: shader vertex \"path\" or layout { binding ... }".to_string());
-                self.report_error(location, "Expected 'shader' or 'layout' in pipeline declaration".to_string(), suggestion);
-                bail!("Expected 'shader' or 'layout' in pipeline declaration");
+                let suggestion = Some("Use: shader vertex \"path\", vertex_input StructName, blend: alpha; or layout { binding ... }".to_string());
+                self.report_error(location, "Expected 'shader', 'vertex_input' or 'layout' in pipeline declaration".to_string(), suggestion);
+                bail!("Expected 'shader', 'vertex_input' or 'layout' in pipeline declaration");
             }
         }
         
         self.expect(&Token::RBrace)?;
         
-        Ok(PipelineDef { name, shaders, layout })
+        Ok(PipelineDef { name, shaders, layout, vertex_input, state })
+    }
+    
+    // Parse: blend: opaque|alpha|additive; cull: back|front|none; topology: triangles|triangle_strip|lines|line_strip|points;
+    // depth_test: on|off; polygon: fill|wireframe|points (the ';' is optional)
+    fn parse_pipeline_state_key(&mut self, state: &mut crate::ast::PipelineState) -> Result<()> {
+        use crate::ast::{BlendMode, CullMode, Topology, PolygonMode};
+        
+        let key_location = self.current_token_location();
+        let key = self.expect_ident()?;
+        self.expect(&Token::Colon)?;
+        match key.as_str() {
+            "blend" => {
+                state.blend = self.pipeline_state_value(&key, &[("opaque", BlendMode::Opaque), ("alpha", BlendMode::Alpha), ("additive", BlendMode::Additive)])?;
+            }
+            "cull" => {
+                state.cull = self.pipeline_state_value(&key, &[("back", CullMode::Back), ("front", CullMode::Front), ("none", CullMode::None)])?;
+            }
+            "topology" => {
+                state.topology = self.pipeline_state_value(&key, &[
+                    ("triangles", Topology::Triangles), ("triangle_strip", Topology::TriangleStrip),
+                    ("lines", Topology::Lines), ("line_strip", Topology::LineStrip), ("points", Topology::Points),
+                ])?;
+            }
+            "depth_test" => {
+                state.depth_test = self.pipeline_state_value(&key, &[("on", true), ("off", false)])?;
+            }
+            "polygon" => {
+                state.polygon = self.pipeline_state_value(&key, &[("fill", PolygonMode::Fill), ("wireframe", PolygonMode::Wireframe), ("points", PolygonMode::Points)])?;
+            }
+            _ => {
+                let suggestion = Some("Pipeline keys: blend, cull, topology, depth_test, polygon".to_string());
+                self.report_error(key_location, format!("Unknown pipeline key '{}'", key), suggestion);
+                bail!("Unknown pipeline key '{}'", key);
+            }
+        }
+        if self.check(&Token::Semicolon) {
+            self.advance();
+        }
+        Ok(())
+    }
+    
+    fn pipeline_state_value<T: Copy>(&mut self, key: &str, values: &[(&str, T)]) -> Result<T> {
+        let location = self.current_token_location();
+        let value = self.expect_ident()?;
+        if let Some(&(_, parsed)) = values.iter().find(|(name, _)| *name == value) {
+            return Ok(parsed);
+        }
+        let names: Vec<&str> = values.iter().map(|(name, _)| *name).collect();
+        let suggestion = Some(format!("Use: {}: {}", key, names.join(" | ")));
+        self.report_error(location, format!("Unknown {} value '{}'", key, value), suggestion);
+        bail!("Unknown {} value '{}'", key, value);
     }
     
     fn parse_extern_function(&mut self) -> Result<ExternFunctionDef> {
@@ -545,12 +1569,23 @@ impl Parser {
         let mut params = Vec::new();
         if !self.check(&Token::RParen) {
             loop {
+                let param_location = self.current_token_location();
                 let param_name = self.expect_ident()?;
                 self.expect(&Token::Colon)?;
                 let param_type = self.parse_type()?;
+                // Optional default value: name: Type = expr
+                let default = if self.check(&Token::Eq) {
+                    self.advance();
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
                 params.push(Param {
                     name: param_name,
                     ty: param_type,
+                    default,
+                    mutable: false,
+                    location: param_location,
                 });
                 
                 if !self.check(&Token::Comma) {
@@ -603,12 +1638,27 @@ impl Parser {
         let mut params = Vec::new();
         if !self.check(&Token::RParen) {
             loop {
+                let param_location = self.current_token_location();
+                let mutable = self.check(&Token::Mut);
+                if mutable {
+                    self.advance();
+                }
                 let param_name = self.expect_ident()?;
                 self.expect(&Token::Colon)?;
                 let param_type = self.parse_type()?;
+                // Optional default value: name: Type = expr
+                let default = if self.check(&Token::Eq) {
+                    self.advance();
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
                 params.push(Param {
                     name: param_name,
                     ty: param_type,
+                    default,
+                    mutable,
+                    location: param_location,
                 });
                 
                 if !self.check(&Token::Comma) {
@@ -617,31 +1667,109 @@ impl Parser {
                 self.advance();
             }
         }
-        self.expect(&Token::RParen)?;
-        
-        let return_type = if self.check(&Token::Colon) {
+        self.expect(&Token::RParen)?;
+        
+        let return_type = if self.check(&Token::Colon) {
+            self.advance();
+            self.parse_type()?
+        } else {
+            Type::Void
+        };
+        
+        let body = self.parse_block()?;
+        
+        Ok(FunctionDef {
+            name,
+            params,
+            return_type,
+            body,
+            cuda_kernel: None,  // Will be set by caller if @[launch] attribute present
+            no_reload_check: false,  // Set by caller if @[no_reload_check] attribute present
+            is_hot: false,  // Set by caller for @hot fn
+            strict_fp: false,
+        })
+    }
+    
+    fn parse_save_schema(&mut self, location: SourceLocation) -> Result<SaveSchemaDef> {
+        let first = self.expect_ident()?;
+        let (name, version_location, version) = if let Token::Ident(version) = self.peek().clone() {
+            let version_location = self.current_token_location();
+            self.advance();
+            (first, version_location, version)
+        } else {
+            ("SaveData".to_string(), location, first)
+        };
+        let version = self.schema_version(&version, version_location)?;
+        self.expect(&Token::LBrace)?;
+        
+        let mut fields = Vec::new();
+        let mut migration = None;
+        while !self.check(&Token::RBrace) {
+            // from v1(old, new) { ... }
+            if matches!(self.peek(), Token::Ident(word) if word == "from") && matches!(self.peek_ahead(2), Some(Token::LParen)) {
+                let migration_location = self.current_token_location();
+                self.advance();
+                let from_location = self.current_token_location();
+                let from = self.expect_ident()?;
+                let from = self.schema_version(&from, from_location)?;
+                self.expect(&Token::LParen)?;
+                let old = self.expect_ident()?;
+                self.expect(&Token::Comma)?;
+                let new = self.expect_ident()?;
+                self.expect(&Token::RParen)?;
+                let body = self.parse_block()?;
+                if migration.is_some() {
+                    let suggestion = Some("Each version migrates from the one before it, so a schema has one from block".to_string());
+                    self.report_error(migration_location, "Duplicate migration block".to_string(), suggestion);
+                    bail!("Duplicate migration block");
+                }
+                migration = Some(SchemaMigration { from, old, new, body, location: migration_location });
+                continue;
+            }
+            fields.push(self.parse_field()?);
+            if !self.check(&Token::RBrace) {
+                self.expect(&Token::Comma)?;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        Ok(SaveSchemaDef { name, version, fields, migration, location })
+    }
+    
+    // v3 -> 3
+    fn schema_version(&mut self, text: &str, location: SourceLocation) -> Result<u32> {
+        match text.strip_prefix('v').and_then(|number| number.parse::<u32>().ok()) {
+            Some(version) if version > 0 => Ok(version),
+            _ => {
+                let suggestion = Some("Versions are numbered from v1: save_schema SaveGame v1 { ... }".to_string());
+                self.report_error(location, format!("Expected a schema version like v1, found '{}'", text), suggestion);
+                bail!("Expected a schema version");
+            }
+        }
+    }
+    
+    // A bound of @[range(min, max)]: an int or float literal, optionally negative
+    fn parse_attribute_number(&mut self) -> Result<f64> {
+        let negative = self.check(&Token::Minus);
+        if negative {
             self.advance();
-            self.parse_type()?
-        } else {
-            Type::Void
+        }
+        let value = match *self.peek() {
+            Token::Int(n) => n as f64,
+            Token::Float(n) => n,
+            _ => {
+                self.report_parse_error("Expected a number".to_string(), Some("Bound the field with number literals: @[range(0.0, 1.0)]".to_string()));
+                bail!("Expected a number");
+            }
         };
-        
-        let body = self.parse_block()?;
-        
-        Ok(FunctionDef {
-            name,
-            params,
-            return_type,
-            body,
-            cuda_kernel: None,  // Will be set by caller if @[launch] attribute present
-        })
+        self.advance();
+        Ok(if negative { -value } else { value })
     }
     
     fn parse_field(&mut self) -> Result<Field> {
         let name = self.expect_ident()?;
         self.expect(&Token::Colon)?;
         let ty = self.parse_type()?;
-        Ok(Field { name, ty })
+        Ok(Field { name, ty, cold: false, doc: None, range: None, unit: None })
     }
     
     fn parse_type(&mut self) -> Result<Type> {
@@ -767,12 +1895,25 @@ impl Parser {
                 Ok(Type::Mat4)
             }
             Token::Query => {
-                // Parse query<Component1, Component2, ...>
+                // Parse query<Component1, Component2, ...>, with filters like without<Frozen> among them
                 self.advance();
                 self.expect(&Token::Lt)?;
                 let mut component_types = Vec::new();
                 loop {
-                    let ty = self.parse_type()?;
+                    let filter = match self.peek() {
+                        Token::Ident(name) if matches!(self.peek_ahead(1), Some(Token::Lt)) => QueryFilter::from_name(name),
+                        _ => None,
+                    };
+                    let ty = match filter {
+                        Some(filter) => {
+                            self.advance();
+                            self.expect(&Token::Lt)?;
+                            let component = self.parse_type()?;
+                            self.expect(&Token::Gt)?;
+                            Type::QueryFilter(filter, Box::new(component))
+                        }
+                        None => self.parse_type()?,
+                    };
                     component_types.push(ty);
                     if self.check(&Token::Comma) {
                         self.advance();
@@ -783,6 +1924,14 @@ impl Parser {
                 self.expect(&Token::Gt)?;
                 Ok(Type::Query(component_types))
             }
+            Token::Ident(ref name) if name == "res" && matches!(self.peek_ahead(1), Some(Token::Lt)) => {
+                // Parse res<Resource>
+                self.advance();
+                self.expect(&Token::Lt)?;
+                let resource = self.parse_type()?;
+                self.expect(&Token::Gt)?;
+                Ok(Type::Res(Box::new(resource)))
+            }
             Token::Ident(ref name) => {
                 let name_clone = name.clone();
                 self.advance();
@@ -794,6 +1943,26 @@ impl Parser {
                 self.expect(&Token::RBracket)?;
                 Ok(Type::Array(Box::new(element_type)))
             }
+            Token::Fn => {
+                // Parse function type: fn(ParamType, ...): ReturnType (return type defaults to void)
+                self.advance();
+                self.expect(&Token::LParen)?;
+                let mut param_types = Vec::new();
+                while !self.check(&Token::RParen) {
+                    param_types.push(self.parse_type()?);
+                    if !self.check(&Token::RParen) {
+                        self.expect(&Token::Comma)?;
+                    }
+                }
+                self.expect(&Token::RParen)?;
+                let return_type = if self.check(&Token::Colon) {
+                    self.advance();
+                    self.parse_type()?
+                } else {
+                    Type::Void
+                };
+                Ok(Type::Function(param_types, Box::new(return_type)))
+            }
             Token::Question => {
                 // Parse optional type: ?Type
                 self.advance();
@@ -803,7 +1972,7 @@ impl Parser {
             _ => {
                 let location = self.current_token_location();
                 let token_str = format!("{:?}", self.peek());
-                let suggestion = Some("Expected: i32, i64, f32, f64, bool, string, void, or a type name".to_string());
+                let suggestion = Some("Expected: i32, i64, f32, f64, bool, string, void, fn(...): Type, or a type name".to_string());
                 self.report_error(location, format!("Unexpected token in type: {}", token_str), suggestion);
                 bail!("Unexpected token in type: {:?}", self.peek());
             }
@@ -815,7 +1984,25 @@ impl Parser {
         let mut statements = Vec::new();
         
         while !self.check(&Token::RBrace) {
-            statements.push(self.parse_statement()?);
+            if self.is_at_end() || self.at_top_level_item() {
+                // Unclosed block; item-level recovery resumes from here
+                let location = self.current_token_location();
+                let suggestion = Some("Add the missing '}'".to_string());
+                self.report_error(location, format!("Expected '}}' to close block, got {:?}", self.peek()), suggestion);
+                bail!("Expected '}}' to close block");
+            }
+            let start = self.current;
+            let errors_before = self.errors.len();
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(e) => {
+                    self.report_unreported(errors_before, &e);
+                    self.synchronize_statement(start);
+                    if self.is_at_end() || self.at_top_level_item() {
+                        return Err(e);
+                    }
+                }
+            }
         }
         self.expect(&Token::RBrace)?;
         
@@ -827,11 +2014,40 @@ impl Parser {
         Statement::Block(statements, location)
     }
     
+    // A bare `{ ... }` in statement position is a nested scope with no value; an expression left at
+    // its end is evaluated and discarded
+    fn parse_block_statement(&mut self) -> Result<Statement> {
+        let location = self.current_token_location();
+        let (statements, value) = self.parse_block_with_value()?;
+        Ok(self.block_statement(statements, value, location))
+    }
+    
+    fn block_statement(&self, mut statements: Vec<Statement>, value: Option<Expression>, location: SourceLocation) -> Statement {
+        if let Some(value) = value {
+            let value_location = value.location();
+            statements.push(Statement::Expression(value, value_location));
+        }
+        self.create_block_statement(statements, location)
+    }
+    
     fn parse_statement(&mut self) -> Result<Statement> {
         let stmt_location = self.current_token_location();
         match self.peek() {
+            Token::LBrace => self.parse_block_statement(),
             Token::Let => {
                 self.advance();
+                let mutable = self.check(&Token::Mut);
+                if mutable {
+                    self.advance();
+                }
+                // let Position { x, y, .. } = p;
+                if matches!(self.peek(), Token::Ident(_)) && matches!(self.peek_ahead(1), Some(Token::LBrace)) {
+                    let pattern = self.parse_single_pattern()?;
+                    self.expect(&Token::Eq)?;
+                    let value = self.parse_expression()?;
+                    self.expect(&Token::Semicolon)?;
+                    return Ok(Statement::Destructure { pattern, mutable, value, location: stmt_location });
+                }
                 let name = self.expect_ident()?;
                 let ty = if self.check(&Token::Colon) {
                     self.advance();
@@ -842,7 +2058,7 @@ impl Parser {
                 self.expect(&Token::Eq)?;
                 let value = self.parse_expression()?;
                 self.expect(&Token::Semicolon)?;
-                Ok(Statement::Let { name, ty, value, location: stmt_location })
+                Ok(Statement::Let { name, mutable, ty, value, location: stmt_location })
             }
             Token::If => {
                 self.advance();
@@ -888,15 +2104,41 @@ impl Parser {
                 self.advance();
                 let iterator = self.expect_ident()?;
                 self.expect(&Token::In)?;
-                let collection = self.parse_expression()?;
+                // for chunk in q.chunks(1024): the query in contiguous slices of up to 1024 entities
+                let is_chunks = matches!(self.peek(), Token::Ident(_))
+                    && matches!(self.peek_ahead(1), Some(Token::Dot))
+                    && matches!(self.peek_ahead(2), Some(Token::Ident(ref s)) if s == "chunks")
+                    && matches!(self.peek_ahead(3), Some(Token::LParen));
+                let (collection, chunk_size) = if is_chunks {
+                    let query_location = self.current_token_location();
+                    let query = self.expect_ident()?;
+                    self.advance();  // .
+                    self.advance();  // chunks
+                    self.expect(&Token::LParen)?;
+                    let size = self.parse_expression()?;
+                    self.expect(&Token::RParen)?;
+                    (Expression::Variable(query, query_location), Some(size))
+                } else {
+                    (self.parse_expression()?, None)
+                };
                 let body = self.parse_block()?;
-                Ok(Statement::For { iterator, collection, body, location: stmt_location })
+                Ok(Statement::For { iterator, collection, chunk_size, body, location: stmt_location })
             }
             Token::Loop => {
                 self.advance();
                 let body = self.parse_block()?;
                 Ok(Statement::Loop { body, location: stmt_location })
             }
+            Token::Ident(ref name) if name == "spawn_batch" && self.is_spawn_batch_start() => {
+                self.parse_spawn_batch(stmt_location)
+            }
+            // emit Collision { a: x, b: y };
+            Token::Ident(ref name) if name == "emit" && self.is_emit_start() => {
+                self.advance();
+                let event = self.parse_expression()?;
+                self.expect(&Token::Semicolon)?;
+                Ok(Statement::Emit(event, stmt_location))
+            }
             Token::Return => {
                 self.advance();
                 let expr = if !self.check(&Token::Semicolon) {
@@ -907,6 +2149,21 @@ impl Parser {
                 self.expect(&Token::Semicolon)?;
                 Ok(Statement::Return(expr, stmt_location))
             }
+            Token::Break => {
+                self.advance();
+                let value = if !self.check(&Token::Semicolon) {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                self.expect(&Token::Semicolon)?;
+                Ok(Statement::Break(value, stmt_location))
+            }
+            Token::Continue => {
+                self.advance();
+                self.expect(&Token::Semicolon)?;
+                Ok(Statement::Continue(stmt_location))
+            }
             Token::Defer => {
                 self.advance();
                 let expr = self.parse_expression()?;
@@ -1062,7 +2319,7 @@ impl Parser {
     }
     
     fn parse_factor(&mut self) -> Result<Expression> {
-        let mut expr = self.parse_unary()?;
+        let mut expr = self.parse_cast()?;
         
         while self.check(&Token::Star) || self.check(&Token::Slash) || self.check(&Token::Percent) {
             let location = self.current_token_location();
@@ -1081,7 +2338,7 @@ impl Parser {
                 }
                 _ => unreachable!(),
             };
-            let right = self.parse_unary()?;
+            let right = self.parse_cast()?;
             expr = Expression::BinaryOp {
                 op,
                 left: Box::new(expr),
@@ -1093,6 +2350,18 @@ impl Parser {
         Ok(expr)
     }
     
+    // `as` binds tighter than arithmetic and looser than unary minus: -x as f32 * y
+    fn parse_cast(&mut self) -> Result<Expression> {
+        let mut expr = self.parse_unary()?;
+        while self.check(&Token::As) {
+            let location = self.current_token_location();
+            self.advance();
+            let ty = self.parse_type()?;
+            expr = Expression::Cast { expr: Box::new(expr), ty, location };
+        }
+        Ok(expr)
+    }
+    
     fn parse_unary(&mut self) -> Result<Expression> {
         if self.check(&Token::Bang) {
             let location = self.current_token_location();
@@ -1137,6 +2406,21 @@ impl Parser {
                 }
                 self.expect(&Token::RParen)?;
                 
+                // e.set_parent(p): entity methods are calls taking the entity first
+                if let Expression::MemberAccess { object, member, location } = &expr {
+                    if hierarchy::METHODS.contains(&member.as_str()) {
+                        let (name, location) = (member.clone(), *location);
+                        let args = std::iter::once(object.as_ref().clone()).chain(args).collect();
+                        expr = Expression::Call { name, args, location };
+                        continue;
+                    }
+                    // input.jump_pressed(): the accessors of the program's input block
+                    if matches!(object.as_ref(), Expression::Variable(name, _) if name == input_map::OBJECT) {
+                        let (name, location) = (format!("{}.{}", input_map::OBJECT, member), *location);
+                        expr = Expression::Call { name, args, location };
+                        continue;
+                    }
+                }
                 if let Expression::Variable(name, _var_location) = expr {
                     let call_location = self.current_token_location();
                     // Check if this is a struct constructor (Vec2, Vec3, Vec4)
@@ -1201,6 +2485,10 @@ impl Parser {
                 let dot_location = self.current_token_location();
                 self.advance();
                 let member = self.expect_ident()?;
+                if member == frame_arena::METHOD && self.check(&Token::Lt) {
+                    expr = self.parse_frame_alloc(expr, dot_location)?;
+                    continue;
+                }
                 expr = Expression::MemberAccess {
                     object: Box::new(expr),
                     member,
@@ -1224,17 +2512,77 @@ impl Parser {
         Ok(expr)
     }
     
+    // size_of<T>(), align_of<T>(), offset_of<T>(field)
+    fn parse_layout_of(&mut self, query: LayoutQuery, location: SourceLocation) -> Result<Expression> {
+        self.advance(); // consume the intrinsic's name
+        self.expect(&Token::Lt)?;
+        let ty = self.parse_type()?;
+        self.expect(&Token::Gt)?;
+        self.expect(&Token::LParen)?;
+        let field = if query == LayoutQuery::Offset {
+            if !matches!(self.peek(), Token::Ident(_)) {
+                let field_location = self.current_token_location();
+                self.report_error(field_location, "offset_of needs the name of a field".to_string(),
+                    Some(format!("Use: offset_of<{}>(field_name)", crate::formatter::type_str(&ty))));
+                bail!("offset_of needs the name of a field");
+            }
+            Some(self.expect_ident()?)
+        } else {
+            None
+        };
+        self.expect(&Token::RParen)?;
+        Ok(Expression::LayoutOf { query, ty, field, location })
+    }
+    
+    // sync_to_gpu<T>(), sync_to_cpu<T>()
+    fn parse_gpu_sync(&mut self, direction: SyncDirection, location: SourceLocation) -> Result<Expression> {
+        self.advance(); // consume the intrinsic's name
+        self.expect(&Token::Lt)?;
+        let component = self.parse_type()?;
+        self.expect(&Token::Gt)?;
+        self.expect(&Token::LParen)?;
+        if !self.check(&Token::RParen) {
+            let argument_location = self.current_token_location();
+            self.report_error(argument_location, format!("{} takes no arguments: it copies every entity's component", direction.name()),
+                Some(format!("Use: {}<{}>()", direction.name(), crate::formatter::type_str(&component))));
+            bail!("{} takes no arguments", direction.name());
+        }
+        self.expect(&Token::RParen)?;
+        Ok(Expression::GpuSync { direction, component, location })
+    }
+    
+    // arena.alloc_array<T>(count), after the method's name
+    fn parse_frame_alloc(&mut self, arena: Expression, location: SourceLocation) -> Result<Expression> {
+        self.expect(&Token::Lt)?;
+        let element = self.parse_type()?;
+        self.expect(&Token::Gt)?;
+        self.expect(&Token::LParen)?;
+        if self.check(&Token::RParen) {
+            let count_location = self.current_token_location();
+            self.report_error(count_location, "alloc_array needs the number of elements".to_string(),
+                Some(format!("Use: frame.alloc_array<{}>(count)", crate::formatter::type_str(&element))));
+            bail!("alloc_array needs the number of elements");
+        }
+        let count = self.parse_expression()?;
+        self.expect(&Token::RParen)?;
+        Ok(Expression::FrameAlloc { arena: Box::new(arena), element, count: Box::new(count), location })
+    }
+    
     fn parse_primary(&mut self) -> Result<Expression> {
         let location = self.current_token_location();
         let token = self.peek().clone();
         match token {
             Token::Int(n) => {
                 self.advance();
-                Ok(Expression::Literal(Literal::Int(n), location))
+                Ok(Expression::Literal(Literal::Int(n, None), location))
             }
             Token::Float(n) => {
                 self.advance();
-                Ok(Expression::Literal(Literal::Float(n), location))
+                Ok(Expression::Literal(Literal::Float(n, None), location))
+            }
+            Token::SuffixedNumber((digits, suffix)) => {
+                self.advance();
+                Ok(Expression::Literal(self.suffixed_literal(&digits, &suffix, location)?, location))
             }
             Token::True => {
                 self.advance();
@@ -1261,6 +2609,15 @@ impl Parser {
                 }
             }
             Token::Ident(name) => {
+                if self.is_struct_literal_start(&name) {
+                    return self.parse_struct_literal();
+                }
+                if let Some(query) = LayoutQuery::from_name(&name).filter(|_| matches!(self.peek_ahead(1), Some(Token::Lt))) {
+                    return self.parse_layout_of(query, location);
+                }
+                if let Some(direction) = SyncDirection::from_name(&name).filter(|_| matches!(self.peek_ahead(1), Some(Token::Lt))) {
+                    return self.parse_gpu_sync(direction, location);
+                }
                 self.advance();
                 Ok(Expression::Variable(name, location))
             }
@@ -1308,6 +2665,20 @@ impl Parser {
             Token::Match => {
                 self.parse_match_expression()
             }
+            Token::If => {
+                self.parse_if_expression()
+            }
+            // `loop { ... break value; }`: the loop's value is the one it breaks with
+            Token::Loop => {
+                self.advance();
+                let body = self.parse_block()?;
+                Ok(Expression::Loop { body, location })
+            }
+            // `{ let a = compute(); a * 2.0 }`: the block's last expression is its value
+            Token::LBrace => {
+                let body = self.parse_value_block()?;
+                Ok(Expression::Block { body, location })
+            }
             _ => {
                 let location = self.current_token_location();
                 let token_str = format!("{:?}", self.peek());
@@ -1318,6 +2689,226 @@ impl Parser {
         }
     }
     
+    // 'emit' is contextual: `emit Name {` starts an emit statement, anything else is an identifier
+    fn is_emit_start(&self) -> bool {
+        matches!(self.peek_ahead(1), Some(Token::Ident(_))) && matches!(self.peek_ahead(2), Some(Token::LBrace))
+    }
+    
+    // `spawn_batch` is only a keyword when followed by `( ... ) {`, so a function of that name
+    // can still be called
+    fn is_spawn_batch_start(&self) -> bool {
+        if !matches!(self.peek_ahead(1), Some(Token::LParen)) {
+            return false;
+        }
+        let mut depth = 0;
+        let mut offset = 1;
+        while let Some(token) = self.peek_ahead(offset) {
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return matches!(self.peek_ahead(offset + 1), Some(Token::LBrace));
+                    }
+                }
+                Token::Semicolon | Token::Eof => return false,
+                _ => {}
+            }
+            offset += 1;
+        }
+        false
+    }
+
+    // spawn_batch(count) { init(index) { ... } }
+    fn parse_spawn_batch(&mut self, location: SourceLocation) -> Result<Statement> {
+        self.advance();
+        self.expect(&Token::LParen)?;
+        let count = self.parse_expression()?;
+        self.expect(&Token::RParen)?;
+        self.expect(&Token::LBrace)?;
+        if !matches!(self.peek(), Token::Ident(ref s) if s == "init") {
+            let at = self.current_token_location();
+            let suggestion = Some("Describe each entity in an init block: spawn_batch(n) { init(i) { Position { x: i as f32 }; } }".to_string());
+            self.report_error(at, format!("Expected 'init' in spawn_batch, got {:?}", self.peek()), suggestion);
+            bail!("Expected 'init' in spawn_batch, got {:?}", self.peek());
+        }
+        self.advance();
+        self.expect(&Token::LParen)?;
+        let index = self.expect_ident()?;
+        self.expect(&Token::RParen)?;
+        let body = self.parse_block()?;
+        self.expect(&Token::RBrace)?;
+        Ok(Statement::SpawnBatch { count, index, body, location })
+    }
+
+    // Struct literals look like `Name { field: value, ... }`. To avoid confusing them with
+    // blocks after conditions (`if ready { ... }`), only treat `Name {` as a literal when the
+    // name is capitalized (declarations are held to that by expect_type_name) and the brace is
+    // followed by `}` or `field:`.
+    fn is_struct_literal_start(&self, name: &str) -> bool {
+        if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+            return false;
+        }
+        let token_at = |offset: usize| self.tokens.get(self.current + offset).map(|t| &t.token);
+        if !matches!(token_at(1), Some(Token::LBrace)) {
+            return false;
+        }
+        match token_at(2) {
+            Some(Token::RBrace) => true,
+            Some(Token::Ident(_)) => matches!(token_at(3), Some(Token::Colon)),
+            _ => false,
+        }
+    }
+
+    // 10i64, 2f32, 1.5f64: the suffix fixes the literal's type
+    fn suffixed_literal(&mut self, digits: &str, suffix: &str, location: SourceLocation) -> Result<Literal> {
+        let ty = match suffix {
+            "i32" => Type::I32,
+            "i64" => Type::I64,
+            "f32" => Type::F32,
+            _ => Type::F64,
+        };
+        if matches!(ty, Type::F32 | Type::F64) {
+            return Ok(Literal::Float(digits.parse()?, Some(ty)));
+        }
+        if digits.contains('.') {
+            let suggestion = Some(format!("Use a float suffix ({}f32 or {}f64), or cast: {} as {}", digits, digits, digits, suffix));
+            self.report_error(location, format!("Integer suffix '{}' on a float literal", suffix), suggestion);
+            bail!("Integer suffix '{}' on a float literal", suffix);
+        }
+        match digits.parse() {
+            Ok(n) => Ok(Literal::Int(n, Some(ty))),
+            Err(_) => {
+                self.report_error(location, format!("Integer literal '{}{}' is out of range", digits, suffix), None);
+                bail!("Integer literal '{}{}' is out of range", digits, suffix);
+            }
+        }
+    }
+    
+    fn parse_struct_literal(&mut self) -> Result<Expression> {
+        let location = self.current_token_location();
+        let name = self.expect_ident()?;
+        self.expect(&Token::LBrace)?;
+
+        let mut fields = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let field_name = self.expect_ident()?;
+            self.expect(&Token::Colon)?;
+            let value = self.parse_expression()?;
+            fields.push((field_name, value));
+            if !self.check(&Token::RBrace) {
+                self.expect(&Token::Comma)?;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+
+        Ok(Expression::StructLiteral { name, fields, location })
+    }
+
+    // `if c { a } else { b }` in expression position (an `if` starting a statement is the statement form)
+    fn parse_if_expression(&mut self) -> Result<Expression> {
+        let location = self.current_token_location();
+        self.advance(); // consume 'if'
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_value_block()?;
+        if !self.check(&Token::Else) {
+            let else_location = self.current_token_location();
+            let suggestion = Some("An if used as a value needs a value for both cases: add else { ... }".to_string());
+            self.report_error(else_location, "if-expression is missing its else branch".to_string(), suggestion);
+            bail!("if-expression is missing its else branch");
+        }
+        self.advance();
+        let else_branch = if self.check(&Token::If) {
+            // else if: the nested if-expression is the whole else branch
+            let value = self.parse_if_expression()?;
+            ValueBlock { statements: Vec::new(), value: Box::new(value) }
+        } else {
+            self.parse_value_block()?
+        };
+        Ok(Expression::If { condition: Box::new(condition), then_branch, else_branch, location })
+    }
+
+    // `{ statements; value }`: the block's value is its last expression, written without ';'
+    fn parse_value_block(&mut self) -> Result<ValueBlock> {
+        let (statements, value) = self.parse_block_with_value()?;
+        match value {
+            Some(value) => Ok(ValueBlock { statements, value: Box::new(value) }),
+            None => {
+                let location = self.tokens[self.current - 1].location;  // The closing '}'
+                let suggestion = Some("End the block with the value it produces, without a trailing ';'".to_string());
+                self.report_error(location, "Expected a value at the end of this block".to_string(), suggestion);
+                bail!("Expected a value at the end of this block");
+            }
+        }
+    }
+
+    // A block that may end in a value. Statements led by a keyword (let, if, while, ...) parse as
+    // usual, so a nested if-expression as the final value needs parentheses or `else if`.
+    fn parse_block_with_value(&mut self) -> Result<(Vec<Statement>, Option<Expression>)> {
+        self.expect(&Token::LBrace)?;
+        let mut statements = Vec::new();
+        while !self.check(&Token::RBrace) {
+            if self.is_at_end() || self.at_top_level_item() {
+                let location = self.current_token_location();
+                let suggestion = Some("Add the missing '}'".to_string());
+                self.report_error(location, format!("Expected '}}' to close block, got {:?}", self.peek()), suggestion);
+                bail!("Expected '}}' to close block");
+            }
+            let start = self.current;
+            let errors_before = self.errors.len();
+            match self.parse_block_entry() {
+                Ok(BlockEntry::Statement(statement)) => statements.push(statement),
+                Ok(BlockEntry::Value(value)) => {
+                    self.expect(&Token::RBrace)?;
+                    return Ok((statements, Some(value)));
+                }
+                Err(e) => {
+                    self.report_unreported(errors_before, &e);
+                    self.synchronize_statement(start);
+                    if self.is_at_end() || self.at_top_level_item() {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        Ok((statements, None))
+    }
+
+    fn parse_block_entry(&mut self) -> Result<BlockEntry> {
+        // A nested `{ ... }` is the value only if it ends in one and closes this block too
+        if self.check(&Token::LBrace) {
+            let location = self.current_token_location();
+            let (statements, value) = self.parse_block_with_value()?;
+            return Ok(match value {
+                Some(value) if self.check(&Token::RBrace) => {
+                    BlockEntry::Value(Expression::Block { body: ValueBlock { statements, value: Box::new(value) }, location })
+                }
+                value => BlockEntry::Statement(self.block_statement(statements, value, location)),
+            });
+        }
+        let keyword_led = matches!(self.peek(), Token::Let | Token::If | Token::While | Token::For | Token::Loop
+            | Token::Return | Token::Break | Token::Continue | Token::Defer)
+            || (matches!(self.peek(), Token::Ident(name) if name == "emit") && self.is_emit_start());
+        if keyword_led {
+            return Ok(BlockEntry::Statement(self.parse_statement()?));
+        }
+        let stmt_location = self.current_token_location();
+        let expr = self.parse_expression()?;
+        if self.check(&Token::RBrace) {
+            return Ok(BlockEntry::Value(expr));
+        }
+        if self.check(&Token::Eq) {
+            self.advance();
+            let value = self.parse_expression()?;
+            self.expect(&Token::Semicolon)?;
+            Ok(BlockEntry::Statement(Statement::Assign { target: expr, value, location: stmt_location }))
+        } else {
+            self.expect(&Token::Semicolon)?;
+            Ok(BlockEntry::Statement(Statement::Expression(expr, stmt_location)))
+        }
+    }
+
     fn parse_match_expression(&mut self) -> Result<Expression> {
         use crate::ast::{MatchArm, Expression};
         let match_location = self.current_token_location();
@@ -1333,13 +2924,19 @@ impl Parser {
         while !self.check(&Token::RBrace) {
             let arm_location = self.current_token_location();
             
-            // Parse pattern
+            // Parse pattern, and the guard that must also hold: `n if n > 10 =>`
             let pattern = self.parse_pattern()?;
+            let guard = if self.check(&Token::If) {
+                self.advance();
+                Some(Box::new(self.parse_expression()?))
+            } else {
+                None
+            };
             
             // Expect => arrow (can be = followed by >, or a single => token if we add it)
             // For now, parse = followed by >
             if !self.check(&Token::Eq) {
-                let suggestion = Some("Use: pattern => { body }".to_string());
+                let suggestion = Some("Use: pattern => { body } or pattern if condition => { body }".to_string());
                 self.report_error(arm_location, "Expected '=>' after pattern".to_string(), suggestion);
                 bail!("Expected '=>' after pattern at {:?}", arm_location);
             }
@@ -1351,10 +2948,10 @@ impl Parser {
             }
             self.advance(); // consume '>'
             
-            // Parse body (block of statements)
-            let body = self.parse_block()?;
+            // Parse body (block of statements, optionally ending in the arm's value)
+            let (body, value) = self.parse_block_with_value()?;
             
-            arms.push(MatchArm { pattern, body, location: arm_location });
+            arms.push(MatchArm { pattern, guard, body, value: value.map(Box::new), location: arm_location });
             
             // Optional comma between arms
             if self.check(&Token::Comma) {
@@ -1367,19 +2964,51 @@ impl Parser {
         Ok(Expression::Match { expr: Box::new(expr), arms, location: match_location })
     }
     
+    // A pattern, or alternatives of one separated by '|': 1 | 2 | 3
     fn parse_pattern(&mut self) -> Result<Pattern> {
+        use crate::ast::Pattern;
+        let pattern_location = self.current_token_location();
+        let first = self.parse_single_pattern()?;
+        if !self.check(&Token::Pipe) {
+            return Ok(first);
+        }
+        let mut alternatives = vec![first];
+        while self.check(&Token::Pipe) {
+            self.advance();
+            alternatives.push(self.parse_single_pattern()?);
+        }
+        // Which alternative matched isn't known, so none of them can bind the value
+        if let Some((_, name, location)) = alternatives.iter().flat_map(Pattern::bindings).next() {
+            let suggestion = Some(format!("Match the values with literals, or bind the whole value in an arm of its own: {} => {{ ... }}", name));
+            self.report_error(location, format!("'{}' can't bind the value inside an or-pattern", name), suggestion);
+            bail!("Binding '{}' inside an or-pattern at {:?}", name, location);
+        }
+        Ok(Pattern::Or(alternatives, pattern_location))
+    }
+
+    fn parse_single_pattern(&mut self) -> Result<Pattern> {
         use crate::ast::{Pattern, Literal};
         let pattern_location = self.current_token_location();
         let token = self.peek().clone();
         
         match token {
-            Token::Int(n) => {
-                self.advance();
-                Ok(Pattern::Literal(Literal::Int(n), pattern_location))
-            }
-            Token::Float(n) => {
+            Token::Int(_) | Token::Float(_) | Token::SuffixedNumber(_) => {
+                let start = self.parse_number_pattern()?;
+                let inclusive = match self.peek() {
+                    Token::DotDot => false,
+                    Token::DotDotEq => true,
+                    _ => return Ok(Pattern::Literal(start, pattern_location)),
+                };
                 self.advance();
-                Ok(Pattern::Literal(Literal::Float(n), pattern_location))
+                if !matches!(self.peek(), Token::Int(_) | Token::Float(_) | Token::SuffixedNumber(_)) {
+                    let end_location = self.current_token_location();
+                    let suggestion = Some("Ranges need both ends: 0..10 (10 excluded) or 0..=9 (9 included)".to_string());
+                    self.report_error(end_location, format!("Expected the end of the range pattern, got {:?}", self.peek()), suggestion);
+                    bail!("Expected the end of a range pattern at {:?}", end_location);
+                }
+                let end_location = self.current_token_location();
+                let end = self.parse_number_pattern()?;
+                Ok(Pattern::Range { start, end, inclusive, end_location, location: pattern_location })
             }
             Token::True => {
                 self.advance();
@@ -1398,6 +3027,8 @@ impl Parser {
                 // Check if it's a wildcard
                 if name == "_" {
                     Ok(Pattern::Wildcard(pattern_location))
+                } else if self.check(&Token::LBrace) {
+                    self.parse_struct_pattern(name, pattern_location)
                 } else {
                     // For now, treat all identifiers as variable bindings
                     // This allows: match x { value => { ... } }
@@ -1406,12 +3037,81 @@ impl Parser {
                 }
             }
             _ => {
-                let suggestion = Some("Expected: literal, identifier, or wildcard (_)".to_string());
+                let suggestion = Some("Expected: literal, range (0..10), identifier, or wildcard (_)".to_string());
                 self.report_error(pattern_location, format!("Unexpected token in pattern: {:?}", token), suggestion);
                 bail!("Unexpected token in pattern: {:?}", token);
             }
         }
     }
+
+    // Hit { entity, point: p, .. } (the name is already consumed): `field` binds a variable of the
+    // same name, `field: pattern` matches the field, and `..` skips the fields not listed
+    fn parse_struct_pattern(&mut self, name: String, location: SourceLocation) -> Result<Pattern> {
+        self.expect(&Token::LBrace)?;
+        let mut fields = Vec::new();
+        let mut rest = false;
+        while !self.check(&Token::RBrace) {
+            if self.check(&Token::DotDot) {
+                self.advance();
+                rest = true;
+                break;
+            }
+            let field_location = self.current_token_location();
+            let field = self.expect_ident()?;
+            let pattern = if self.check(&Token::Colon) {
+                self.advance();
+                self.parse_pattern()?
+            } else {
+                Pattern::Variable(field.clone(), field_location)
+            };
+            fields.push(FieldPattern { field, pattern, location: field_location });
+            if !self.check(&Token::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        if !self.check(&Token::RBrace) {
+            let at = self.current_token_location();
+            let suggestion = Some(format!("Write the fields as {} {{ a, b: pattern, .. }} with '..' last", name));
+            self.report_error(at, format!("Expected '}}' to close the {} pattern, got {:?}", name, self.peek()), suggestion);
+            bail!("Unclosed struct pattern at {:?}", at);
+        }
+        self.advance();
+        Ok(Pattern::Struct { name, fields, rest, location })
+    }
+
+    fn parse_number_pattern(&mut self) -> Result<crate::ast::Literal> {
+        use crate::ast::Literal;
+        let location = self.current_token_location();
+        let literal = match self.peek().clone() {
+            Token::Int(n) => Literal::Int(n, None),
+            Token::Float(n) => Literal::Float(n, None),
+            Token::SuffixedNumber((digits, suffix)) => self.suffixed_literal(&digits, &suffix, location)?,
+            token => bail!("Expected a number in pattern, got {:?}", token),
+        };
+        self.advance();
+        Ok(literal)
+    }
+    
+    // The name of a struct, component, event or uniform. These are capitalized: `Name {` is read as a
+    // struct literal only for a capitalized name (see is_struct_literal_start), so a lowercase type
+    // could never be built. Reported without stopping, so the rest of the declaration is checked.
+    fn expect_type_name(&mut self) -> Result<String> {
+        let location = self.current_token_location();
+        let name = self.expect_ident()?;
+        if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+            let mut capitalized = name.clone();
+            if let Some(first) = capitalized.get_mut(0..1) {
+                first.make_ascii_uppercase();
+            }
+            self.report_error(
+                location,
+                format!("Type name '{}' must start with an uppercase letter", name),
+                Some(format!("Rename it to '{}': `{} {{ ... }}` is read as a struct literal only for capitalized names", capitalized, capitalized)),
+            );
+        }
+        Ok(name)
+    }
     
     fn expect_ident(&mut self) -> Result<String> {
         let token = self.peek().clone();
@@ -1447,7 +3147,11 @@ impl Parser {
     }
     
     fn peek(&self) -> &Token {
-        &self.tokens[self.current].token
+        self.tokens.get(self.current).map(|t| &t.token).unwrap_or(&Token::Eof)
+    }
+    
+    fn peek_ahead(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.current + offset).map(|t| &t.token)
     }
     
     fn advance(&mut self) {
@@ -1545,3 +3249,23 @@ impl Parser {
     }
 }
 
+
+// @strict_fp on a system applies to all of its functions (handlers follow system.strict_fp)
+fn mark_strict_fp(system: &mut SystemDef) {
+    system.strict_fp = true;
+    for f in &mut system.functions {
+        f.strict_fp = true;
+    }
+}
+
+// Scheduling attributes of a system; None when it has none and isn't run by run_systems()
+fn system_schedule(attrs: &[String]) -> Option<SystemSchedule> {
+    let args = |prefix: &str| -> Vec<String> {
+        attrs.iter().filter_map(|attr| attr.strip_prefix(prefix)).map(str::to_string).collect()
+    };
+    let (stage, before, after) = (args("stage:"), args("before:"), args("after:"));
+    if stage.is_empty() && before.is_empty() && after.is_empty() {
+        return None;
+    }
+    Some(SystemSchedule { stage: stage.into_iter().next(), before, after })
+}
@@ -0,0 +1,30 @@
+// Network replication: `@[replicated] component Position { ... }` is sent from a server to its
+// clients. net_host(port) makes the program a server and net_connect(host, port) a client; each
+// net_update() then has the server send every client the replicated components changed since the
+// newest snapshot that client acknowledged, and has the client apply what arrived. Snapshots are
+// written in the save format (stdlib/save_data.h) over the UDP transport of stdlib/net.h, and
+// clients keep the server's entities under ids of their own.
+
+use crate::ast::*;
+use crate::save_schema;
+
+/// Built-in functions; a program's own function of the same name takes precedence
+pub const FUNCTIONS: &[&str] = &["net_host", "net_connect", "net_update", "net_peer_count", "net_connected"];
+
+/// Parameter types, return type and usage of a built-in function
+pub fn signature(name: &str) -> Option<(Vec<Type>, Type, &'static str)> {
+    match name {
+        "net_host" => Some((vec![Type::I32], Type::Bool, "Usage: if !net_host(7777) { ... } to serve clients on UDP port 7777")),
+        "net_connect" => Some((vec![Type::String, Type::I32], Type::Bool, "Usage: net_connect(\"127.0.0.1\", 7777);")),
+        "net_update" => Some((Vec::new(), Type::Void, "Usage: net_update(); once per frame, on the server and on clients")),
+        "net_peer_count" => Some((Vec::new(), Type::I32, "Usage: let clients = net_peer_count(); on the server")),
+        "net_connected" => Some((Vec::new(), Type::Bool, "Usage: if net_connected() { ... } on a client")),
+        _ => None,
+    }
+}
+
+/// The field that can't be sent, with the reason; None if every field can. Snapshots carry
+/// fields the way saved worlds do.
+pub fn unreplicated_field(component: &ComponentDef) -> Option<(&Field, String)> {
+    save_schema::unsaved_world_field(component)
+}
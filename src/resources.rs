@@ -0,0 +1,79 @@
+// Resources: singletons a function takes as `res<T>` parameters instead of reaching for globals.
+// Generated code keeps one instance of each resource type (heidic_res_<T>, zero-initialized) and
+// passes it by reference; callers leave res<T> arguments out and codegen supplies them, so
+// run_systems() calls a system with resources the same way as one without.
+// Time and Input are built in, and run_systems() refreshes them at the start of every frame.
+
+use crate::ast::*;
+
+pub const BUILTINS: &[&str] = &["Time", "Input"];
+
+/// Built-in resource types, used unless the program declares a struct or component of that name
+pub fn builtin(name: &str) -> Option<StructDef> {
+    let field = |name: &str, ty: Type| Field { name: name.to_string(), ty, cold: false, doc: None, range: None, unit: None };
+    let fields = match name {
+        // Seconds since the previous run_systems(), seconds since the first, and run_systems() calls
+        "Time" => vec![
+            field("delta", Type::F32),
+            field("elapsed", Type::F64),
+            field("frame", Type::I64),
+        ],
+        // Mouse state, read from `window` (set it once the window exists; keys: glfwGetKey(input.window, key))
+        "Input" => vec![
+            field("window", Type::GLFWwindow),
+            field("mouse_x", Type::F32),
+            field("mouse_y", Type::F32),
+            field("mouse_dx", Type::F32),
+            field("mouse_dy", Type::F32),
+            field("mouse_left", Type::Bool),
+            field("mouse_right", Type::Bool),
+            field("mouse_middle", Type::Bool),
+        ],
+        _ => return None,
+    };
+    Some(StructDef { name: name.to_string(), fields, is_export: false })
+}
+
+/// The resource type's name: res<Time> -> Time
+pub fn resource_name(ty: &Type) -> Option<&str> {
+    match ty {
+        Type::Res(resource) => match resource.as_ref() {
+            Type::Struct(name) | Type::Component(name) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The C++ variable holding a resource's single instance
+pub fn instance_name(resource: &str) -> String {
+    format!("heidic_res_{}", resource)
+}
+
+/// Every resource type some function, method or test in the program takes, sorted
+pub fn resource_types(program: &Program) -> Vec<String> {
+    let mut names: Vec<String> = program.items.iter()
+        .flat_map(|item| -> Vec<&FunctionDef> {
+            match item {
+                Item::Function(f) => vec![f],
+                Item::System(s) => s.functions.iter().collect(),
+                Item::Impl(i) => i.methods.iter().map(|(f, _)| f).collect(),
+                _ => Vec::new(),
+            }
+        })
+        .flat_map(|f| &f.params)
+        .filter_map(|param| resource_name(&param.ty).map(str::to_string))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Whether the program declares `name` itself (so a built-in of that name isn't used)
+pub fn is_declared(program: &Program, name: &str) -> bool {
+    program.items.iter().any(|item| match item {
+        Item::Struct(s) => s.name == name,
+        Item::Component(c) => c.name == name,
+        _ => false,
+    })
+}
@@ -0,0 +1,103 @@
+// Versioned save data: every `save_schema Name vN { ... }` declares one version of a file format.
+// The newest version is the struct `Name` programs use; older versions become Name_v1, Name_v2, ...
+// save_name(path, data) writes the newest version, and load_name(path) reads any declared version
+// and migrates it forward one version at a time: fields that keep their name and a compatible type
+// are copied, new fields start zeroed, and a `from vN(old, new) { ... }` block fixes up the rest.
+// The same rule the hot-reload component migration uses, applied to files instead of entities.
+
+use std::collections::BTreeMap;
+
+use crate::ast::*;
+use crate::formatter::type_str;
+
+/// Every schema's versions, oldest first, by schema name
+pub fn schemas(program: &Program) -> BTreeMap<&str, Vec<&SaveSchemaDef>> {
+    let mut schemas: BTreeMap<&str, Vec<&SaveSchemaDef>> = BTreeMap::new();
+    for item in &program.items {
+        if let Item::SaveSchema(schema) = item {
+            schemas.entry(schema.name.as_str()).or_default().push(schema);
+        }
+    }
+    for versions in schemas.values_mut() {
+        versions.sort_by_key(|schema| schema.version);
+    }
+    schemas
+}
+
+/// The struct a version is read into: the newest is the schema's own name
+pub fn struct_name(schema: &SaveSchemaDef, newest: u32) -> String {
+    if schema.version == newest {
+        schema.name.clone()
+    } else {
+        format!("{}_v{}", schema.name, schema.version)
+    }
+}
+
+pub fn save_function(schema: &str) -> String {
+    format!("save_{}", schema.to_lowercase())
+}
+
+pub fn load_function(schema: &str) -> String {
+    format!("load_{}", schema.to_lowercase())
+}
+
+/// Why a field of type `ty` can't be saved, or None if it can
+pub fn unsavable_reason(ty: &Type) -> Option<String> {
+    match ty {
+        Type::I32 | Type::I64 | Type::F32 | Type::F64 | Type::Bool | Type::String
+        | Type::Vec2 | Type::Vec3 | Type::Vec4 => None,
+        Type::Array(element) if !matches!(element.as_ref(), Type::Array(_)) => unsavable_reason(element),
+        Type::Array(_) => Some("nested arrays can't be saved".to_string()),
+        Type::Struct(name) | Type::Component(name) => {
+            Some(format!("'{}' isn't a save format of its own; copy its fields into the schema", name))
+        }
+        _ => Some("only numbers, bools, strings, vectors and arrays of them can be saved".to_string()),
+    }
+}
+
+/// The field that keeps a component out of saved worlds (save_world), with the reason; None if
+/// every field can be saved. A component_soa is saved one entity's values at a time.
+pub fn unsaved_world_field(component: &ComponentDef) -> Option<(&Field, String)> {
+    component.fields.iter().find_map(|field| {
+        let ty = match &field.ty {
+            Type::Array(element) if component.is_soa => element.as_ref(),
+            ty => ty,
+        };
+        unsavable_reason(ty).map(|reason| (field, reason))
+    })
+}
+
+/// Whether a field can be copied from `old` to `new` without losing information
+pub fn copies_to(old: &Type, new: &Type) -> bool {
+    type_str(old) == type_str(new) || matches!((old, new), (Type::I32, Type::I64) | (Type::I32, Type::F64) | (Type::F32, Type::F64))
+}
+
+/// Fields of `new` that `old` also has, with a type that copies over
+pub fn copied_fields<'a>(old: &SaveSchemaDef, new: &'a SaveSchemaDef) -> Vec<&'a Field> {
+    new.fields.iter()
+        .filter(|field| old.fields.iter().any(|o| o.name == field.name && copies_to(&o.ty, &field.ty)))
+        .collect()
+}
+
+/// Fields that kept their name but changed to a type the old value doesn't copy to: (old, new)
+pub fn changed_fields<'a>(old: &'a SaveSchemaDef, new: &'a SaveSchemaDef) -> Vec<(&'a Field, &'a Field)> {
+    new.fields.iter()
+        .filter_map(|field| old.fields.iter().find(|o| o.name == field.name).map(|o| (o, field)))
+        .filter(|(o, field)| !copies_to(&o.ty, &field.ty))
+        .collect()
+}
+
+/// Whether a migration block assigns `new.field` anywhere (a whole-field assignment)
+pub fn assigns_field(body: &[Statement], new: &str, field: &str) -> bool {
+    body.iter().any(|statement| match statement {
+        Statement::Assign { target: Expression::MemberAccess { object, member, .. }, .. } => {
+            member == field && matches!(object.as_ref(), Expression::Variable(name, _) if name == new)
+        }
+        Statement::If { then_block, else_block, .. } => {
+            assigns_field(then_block, new, field) || else_block.as_ref().is_some_and(|block| assigns_field(block, new, field))
+        }
+        Statement::While { body, .. } | Statement::For { body, .. } | Statement::Loop { body, .. }
+        | Statement::Block(body, _) => assigns_field(body, new, field),
+        _ => false,
+    })
+}
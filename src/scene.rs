@@ -0,0 +1,117 @@
+// Scene files: `scene "level1.hscene";` spawns the entities a JSON file lists when the program
+// starts, so levels and prefabs can be edited without touching code:
+//
+//   { "entities": [
+//       { "name": "player", "components": { "Position": { "x": 0.0, "y": 1.5 }, "Health": { "hp": 100 } } },
+//       { "components": { "Position": { "x": 4.0 } } }
+//   ] }
+//
+// Fields left out start zeroed, the way a component literal's do. Numbers, bools and strings are
+// written as themselves, vectors as arrays of numbers ([1.0, 2.0, 3.0]), structs as objects and
+// arrays as JSON arrays. A component_soa's fields take one entity's value, not an array.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::ast::*;
+use crate::error::SourceLocation;
+
+/// A scene file that can't be read; reported at its `scene` item
+pub struct SceneError {
+    pub location: SourceLocation,
+    pub message: String,
+    pub suggestion: String,
+}
+
+/// Read every scene file the program names (relative to `source_dir`) into its SceneDef
+pub fn load(program: &mut Program, source_dir: &Path) -> Result<(), SceneError> {
+    for item in &mut program.items {
+        let Item::Scene(scene) = item else { continue };
+        let path = source_dir.join(&scene.path);
+        let text = fs::read_to_string(&path).map_err(|e| SceneError {
+            location: scene.location,
+            message: format!("Can't read scene file {}: {}", path.display(), e),
+            suggestion: "Scene paths are relative to the source file".to_string(),
+        })?;
+        scene.entities = parse(&text).map_err(|message| SceneError {
+            location: scene.location,
+            message: format!("Invalid scene file {}: {}", path.display(), message),
+            suggestion: "A scene is { \"entities\": [ { \"name\": \"player\", \"components\": { \"Position\": { \"x\": 1.0 } } } ] }".to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+fn parse(text: &str) -> Result<Vec<SceneEntity>, String> {
+    let root: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let Some(entities) = root.get("entities").and_then(Value::as_array) else {
+        return Err("expected an object with an \"entities\" array".to_string());
+    };
+    entities.iter().enumerate().map(|(i, entity)| {
+        let name = match entity.get("name") {
+            None => None,
+            Some(Value::String(name)) => Some(name.clone()),
+            Some(_) => return Err(format!("entity {}: \"name\" must be a string", i)),
+        };
+        let components = match entity.get("components") {
+            None => serde_json::Map::new(),
+            Some(Value::Object(components)) => components.clone(),
+            Some(_) => return Err(format!("entity {}: \"components\" must be an object of component name -> fields", i)),
+        };
+        Ok(SceneEntity { name, components })
+    }).collect()
+}
+
+/// How errors and generated code refer to an entity: its name, or its position in the file
+pub fn entity_label(entity: &SceneEntity, index: usize) -> String {
+    entity.name.clone().unwrap_or_else(|| format!("#{}", index))
+}
+
+/// The type a scene gives a component field: one element of a component_soa's field array
+pub fn field_type(component: &ComponentDef, field: &Field) -> Type {
+    match &field.ty {
+        Type::Array(element) if component.is_soa => element.as_ref().clone(),
+        ty => ty.clone(),
+    }
+}
+
+/// Why `value` can't be written to a field of type `ty` (None if it can). `fields_of` finds the
+/// fields of a struct type.
+pub fn mismatch<'a>(value: &Value, ty: &Type, fields_of: &dyn Fn(&str) -> Option<&'a [Field]>) -> Option<String> {
+    let vector = |len: usize| match value.as_array() {
+        Some(items) if items.len() == len && items.iter().all(Value::is_number) => None,
+        _ => Some(format!("expected an array of {} numbers, found {}", len, value)),
+    };
+    match ty {
+        Type::I32 if value.as_i64().is_some_and(|n| i32::try_from(n).is_ok()) => None,
+        Type::I64 if value.is_i64() => None,
+        Type::I32 | Type::I64 => Some(format!("expected an integer, found {}", value)),
+        Type::F32 | Type::F64 if value.is_number() => None,
+        Type::F32 | Type::F64 => Some(format!("expected a number, found {}", value)),
+        Type::Bool if value.is_boolean() => None,
+        Type::Bool => Some(format!("expected true or false, found {}", value)),
+        Type::String if value.is_string() => None,
+        Type::String => Some(format!("expected a string, found {}", value)),
+        Type::Vec2 => vector(2),
+        Type::Vec3 => vector(3),
+        Type::Vec4 => vector(4),
+        Type::Array(element) => match value.as_array() {
+            Some(items) => items.iter().enumerate()
+                .find_map(|(i, item)| mismatch(item, element, fields_of).map(|why| format!("[{}]: {}", i, why))),
+            None => Some(format!("expected an array, found {}", value)),
+        },
+        Type::Struct(name) | Type::Component(name) => match (value.as_object(), fields_of(name)) {
+            (Some(object), Some(fields)) => object.iter().find_map(|(key, field_value)| {
+                match fields.iter().find(|field| field.name == *key) {
+                    Some(field) => mismatch(field_value, &field.ty, fields_of).map(|why| format!(".{}: {}", key, why)),
+                    None => Some(format!("'{}' has no field '{}'", name, key)),
+                }
+            }),
+            (None, Some(_)) => Some(format!("expected an object with {}'s fields, found {}", name, value)),
+            (_, None) => Some(format!("'{}' can't be written in a scene file", name)),
+        },
+        _ => Some("only numbers, bools, strings, vectors, structs and arrays of them can be set from a scene file".to_string()),
+    }
+}
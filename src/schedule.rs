@@ -0,0 +1,157 @@
+// System scheduling: run_systems() calls every system marked with @[stage(...)], @[before(...)]
+// or @[after(...)], or with `on Event(ev)` handlers, once per frame. Stages run in a fixed order; within the frame, before/after
+// constraints are sorted topologically and unconstrained systems keep their declaration order.
+// The type checker reports schedules that can't be satisfied; codegen emits the sorted calls.
+
+use std::collections::HashMap;
+
+use crate::ast::*;
+use crate::error::SourceLocation;
+use crate::resources;
+
+/// Stages in the order run_systems() runs them
+pub const STAGES: &[&str] = &["pre_update", "update", "post_update", "render"];
+
+pub struct ScheduleError {
+    pub code: Option<&'static str>,
+    pub location: SourceLocation,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+/// What run_systems() passes for one parameter of an entry function
+pub enum EntryArgument<'a> {
+    FrameTime,                // `dt: f32`: seconds since the previous frame
+    Query(&'a Param),         // query<...>: gathered from the entity storage for the call
+    Resource(&'a str),        // res<T>: the resource's single instance
+}
+
+/// The arguments run_systems() passes to `f`, one per parameter, or None when `f` isn't a
+/// function run_systems() calls. Those return void and take the frame time at most once, plus
+/// any number of query<...> and res<T> parameters: `fn step(dt: f32): void`,
+/// `fn step(q: query<Position>, time: res<Time>): void`. Other functions of a scheduled system
+/// are helpers it calls itself.
+pub fn entry_arguments(f: &FunctionDef) -> Option<Vec<EntryArgument<'_>>> {
+    if !matches!(f.return_type, Type::Void) {
+        return None;
+    }
+    let mut frame_time = false;
+    f.params.iter().map(|param| match &param.ty {
+        Type::Query(_) => Some(EntryArgument::Query(param)),
+        Type::Res(_) => resources::resource_name(&param.ty).map(EntryArgument::Resource),
+        Type::F32 if !frame_time => {
+            frame_time = true;
+            Some(EntryArgument::FrameTime)
+        }
+        _ => None,
+    }).collect()
+}
+
+pub fn is_system_entry(f: &FunctionDef) -> bool {
+    entry_arguments(f).is_some()
+}
+
+fn stage_index(system: &SystemDef) -> Option<usize> {
+    let stage = system.schedule.as_ref()?.stage.as_deref().unwrap_or("update");
+    STAGES.iter().position(|s| *s == stage)
+}
+
+/// Scheduled systems in run order, or every reason the schedule can't be satisfied
+pub fn schedule<'a>(systems: &[&'a SystemDef]) -> Result<Vec<&'a SystemDef>, Vec<ScheduleError>> {
+    let scheduled: Vec<&SystemDef> = systems.iter().copied().filter(|s| s.schedule.is_some()).collect();
+    let index: HashMap<&str, usize> = scheduled.iter().enumerate().map(|(i, s)| (s.name.as_str(), i)).collect();
+    let mut errors = Vec::new();
+
+    let mut stages = Vec::new();
+    for system in &scheduled {
+        let stage = stage_index(system);
+        if stage.is_none() {
+            let name = system.schedule.as_ref().and_then(|s| s.stage.clone()).unwrap_or_default();
+            errors.push(ScheduleError {
+                code: None,
+                location: system.location,
+                message: format!("Unknown stage '{}' for system '{}'", name, system.name),
+                suggestion: Some(format!("Stages run in this order: {}", STAGES.join(", "))),
+            });
+        }
+        stages.push(stage.unwrap_or(1));
+    }
+
+    // Edges point from the system that runs first
+    let mut edges: Vec<Vec<usize>> = vec![Vec::new(); scheduled.len()];
+    for (i, system) in scheduled.iter().enumerate() {
+        let schedule = system.schedule.as_ref().unwrap();
+        let constraints = schedule.before.iter().map(|name| (name, true)).chain(schedule.after.iter().map(|name| (name, false)));
+        for (other, before) in constraints {
+            let Some(&j) = index.get(other.as_str()) else {
+                let relation = if before { "before" } else { "after" };
+                let (message, suggestion) = if systems.iter().any(|s| s.name == *other) {
+                    (format!("System '{}' is scheduled {} '{}', which isn't scheduled", system.name, relation, other),
+                     format!("Give '{}' a @[stage(...)] so run_systems() runs it", other))
+                } else {
+                    (format!("System '{}' is scheduled {} unknown system '{}'", system.name, relation, other),
+                     format!("Scheduled systems: {}", scheduled.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")))
+                };
+                errors.push(ScheduleError { code: None, location: system.location, message, suggestion: Some(suggestion) });
+                continue;
+            };
+            let (first, second) = if before { (i, j) } else { (j, i) };
+            if stages[first] > stages[second] {
+                errors.push(ScheduleError {
+                    code: Some("E0019"),
+                    location: system.location,
+                    message: format!("System '{}' ({} stage) can't run before '{}' ({} stage)",
+                        scheduled[first].name, STAGES[stages[first]], scheduled[second].name, STAGES[stages[second]]),
+                    suggestion: Some("Stages run in order, so move both systems into the same stage or drop the constraint".to_string()),
+                });
+                continue;
+            }
+            edges[first].push(second);
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // Kahn's algorithm, always taking the earliest (stage, declaration) among the ready systems
+    let mut incoming = vec![0; scheduled.len()];
+    for &next in edges.iter().flatten() {
+        incoming[next] += 1;
+    }
+    let mut done = vec![false; scheduled.len()];
+    let mut order = Vec::new();
+    while let Some(next) = (0..scheduled.len()).filter(|&i| !done[i] && incoming[i] == 0).min_by_key(|&i| (stages[i], i)) {
+        done[next] = true;
+        order.push(scheduled[next]);
+        for &after in &edges[next] {
+            incoming[after] -= 1;
+        }
+    }
+    if order.len() < scheduled.len() {
+        let cycle = find_cycle(&edges, &done);
+        let names: Vec<&str> = cycle.iter().chain(cycle.first()).map(|&i| scheduled[i].name.as_str()).collect();
+        return Err(vec![ScheduleError {
+            code: Some("E0019"),
+            location: scheduled[cycle[0]].location,
+            message: format!("System ordering has a cycle: {}", names.join(" -> ")),
+            suggestion: Some("Each system here must run before the next one; remove one of the @[before]/@[after] constraints".to_string()),
+        }]);
+    }
+    Ok(order)
+}
+
+// Every system left after the sort still has a predecessor left, so walking predecessors must
+// eventually revisit one; that loop, reversed, is a cycle in run order
+fn find_cycle(edges: &[Vec<usize>], done: &[bool]) -> Vec<usize> {
+    let mut path = vec![(0..done.len()).find(|&i| !done[i]).unwrap()];
+    loop {
+        let current = *path.last().unwrap();
+        let previous = (0..edges.len()).find(|&p| !done[p] && edges[p].contains(&current)).unwrap();
+        if let Some(start) = path.iter().position(|&i| i == previous) {
+            let mut cycle = path.split_off(start);
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(previous);
+    }
+}
@@ -0,0 +1,153 @@
+// GLSL -> SPIR-V for declared shaders. At compile time each `shader` declaration with a GLSL
+// path is built with glslc (or glslangValidator) into the .spv the program loads and shader hot
+// reload watches; the compiler's diagnostics become errors at the declaration (E0026). Hot shaders
+// also get a runtime hook that rebuilds the .spv when the GLSL source is saved. Inline shaders
+// (`glsl { ... }` in the .hd file) are written out first and their errors point into the block.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ast::ShaderStage;
+use crate::doctor::find_on_path;
+
+/// Which GLSL compiler builds the shaders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompilerKind {
+    #[default]
+    Glslc,             // shaderc's glslc (Vulkan SDK)
+    GlslangValidator,  // Khronos reference compiler
+}
+
+impl CompilerKind {
+    pub fn program(self) -> &'static str {
+        match self {
+            CompilerKind::Glslc => "glslc",
+            CompilerKind::GlslangValidator => "glslangValidator",
+        }
+    }
+
+    /// Arguments that compile `source` (of `stage`) to SPIR-V at `output`
+    pub fn args(self, stage: &ShaderStage, source: &str, output: &str) -> Vec<String> {
+        let stage = stage_name(stage).to_string();
+        match self {
+            CompilerKind::Glslc => vec![format!("-fshader-stage={}", stage), source.to_string(), "-o".to_string(), output.to_string()],
+            CompilerKind::GlslangValidator => vec!["-V".to_string(), "-S".to_string(), stage, "-o".to_string(), output.to_string(), source.to_string()],
+        }
+    }
+}
+
+pub struct ShaderCompiler {
+    pub kind: CompilerKind,
+    pub path: PathBuf,
+}
+
+impl ShaderCompiler {
+    /// glslc, then glslangValidator, from VULKAN_SDK/bin or PATH
+    pub fn find() -> Option<Self> {
+        [CompilerKind::Glslc, CompilerKind::GlslangValidator].into_iter().find_map(|kind| {
+            let file = format!("{}{}", kind.program(), env::consts::EXE_SUFFIX);
+            let from_sdk = env::var_os("VULKAN_SDK")
+                .map(|sdk| PathBuf::from(sdk).join("bin").join(&file))
+                .filter(|path| path.is_file());
+            from_sdk.or_else(|| find_on_path(kind.program())).map(|path| ShaderCompiler { kind, path })
+        })
+    }
+
+    /// Build `source` into `output`; the compiler's errors if it fails
+    pub fn compile(&self, stage: &ShaderStage, source: &Path, output: &Path) -> Result<(), Vec<GlslError>> {
+        let args = self.kind.args(stage, &source.to_string_lossy(), &output.to_string_lossy());
+        let result = Command::new(&self.path).args(&args).output().map_err(|e| vec![GlslError {
+            line: None,
+            message: format!("couldn't run {}: {}", self.path.display(), e),
+        }])?;
+        if result.status.success() {
+            return Ok(());
+        }
+        let text = format!("{}{}", String::from_utf8_lossy(&result.stdout), String::from_utf8_lossy(&result.stderr));
+        let mut errors: Vec<GlslError> = text.lines().filter_map(parse_error).collect();
+        if errors.is_empty() {
+            errors.push(GlslError { line: None, message: text.trim().lines().next().unwrap_or("compilation failed").to_string() });
+        }
+        Err(errors)
+    }
+}
+
+/// One error from the GLSL compiler, with its line in the shader source when given
+#[derive(Debug)]
+pub struct GlslError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Declared paths that are GLSL sources (a .spv is loaded as it is)
+pub fn is_glsl(path: &str) -> bool {
+    !path.to_lowercase().ends_with(".spv")
+}
+
+/// Compiled SPIR-V for a shader source; the stage extension is kept (my_shader.vert.spv) so a
+/// .vert and .frag of the same name don't collide
+pub fn spv_path(source: &str) -> String {
+    match source.strip_suffix(".glsl") {
+        Some(stem) => format!("{}.spv", stem),
+        None => format!("{}.spv", source),
+    }
+}
+
+/// The .spv the runtime loads for a declared path: the one built from a GLSL source, or the path
+/// itself when it's already SPIR-V
+pub fn loaded_path(path: &str) -> String {
+    if is_glsl(path) {
+        spv_path(path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// Where an inline shader's GLSL is written, next to the .hd file: inline_shaders/Basic.vert
+pub fn inline_path(name: &str, stage: &ShaderStage) -> String {
+    format!("inline_shaders/{}.{}", name, stage_name(stage))
+}
+
+fn stage_name(stage: &ShaderStage) -> &'static str {
+    match stage {
+        ShaderStage::Vertex => "vert",
+        ShaderStage::Fragment => "frag",
+        ShaderStage::Compute => "comp",
+        ShaderStage::Geometry => "geom",
+        ShaderStage::TessellationControl => "tesc",
+        ShaderStage::TessellationEvaluation => "tese",
+    }
+}
+
+// glslc:            "shaders/a.vert:12: error: 'foo' : undeclared identifier"
+// glslangValidator: "ERROR: shaders/a.vert:12: 'foo' : undeclared identifier"
+// Summary lines ("1 error generated.", "ERROR: 1 compilation errors.  No code generated.") are skipped
+fn parse_error(line: &str) -> Option<GlslError> {
+    let line = line.trim();
+    let (glslang, rest) = match line.strip_prefix("ERROR: ") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // The first ":<digits>: " ends the file name (which may itself contain a drive letter colon)
+    let position = rest.match_indices(':').find_map(|(colon, _)| {
+        let after = &rest[colon + 1..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        (digits > 0 && after[digits..].starts_with(": ")).then(|| (after[..digits].parse().ok(), colon + 1 + digits + 2))
+    });
+    match position {
+        Some((line_number, start)) => {
+            let message = &rest[start..];
+            let message = match message.strip_prefix("error: ") {
+                Some(message) => message,
+                None if glslang => message,
+                None => return None,  // glslc warnings and notes
+            };
+            Some(GlslError { line: line_number, message: message.trim().to_string() })
+        }
+        None => {
+            let message = rest.split_once("error: ").map(|(_, message)| message)?;
+            Some(GlslError { line: None, message: message.trim().to_string() })
+        }
+    }
+}
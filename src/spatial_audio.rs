@@ -0,0 +1,70 @@
+// Spatial audio: `@[spatial] resource Step: Sound = "step.wav";` is a sound that plays at an
+// entity. play_resource_step_at(entity) (or play_resource_step_looping_at) starts it on the entity
+// of a query loop, and every run_systems() then sets its attenuation and pan from where that
+// entity's Position is relative to the entity holding the built-in AudioListener component
+// (stdlib/audio_resource.h does the mixing). Sounds fall off with distance and are silent at the
+// listener's range; pan follows the listener's yaw.
+
+use crate::ast::*;
+
+pub const LISTENER: &str = "AudioListener";
+
+/// The built-in listener component, used unless the program declares its own. `yaw` turns the
+/// listener (radians; 0 faces -Z with +X on its right) and `range` is the distance at which
+/// sounds go silent (0 means 50 units).
+pub fn listener() -> ComponentDef {
+    let field = |name: &str| Field { name: name.to_string(), ty: Type::F32, cold: false, doc: None, range: None, unit: None };
+    ComponentDef {
+        name: LISTENER.to_string(),
+        fields: vec![field("yaw"), field("range")],
+        is_soa: false,
+        is_hot: false,
+        is_cuda: false,
+        is_export: false,
+        is_replicated: false,
+        pool_capacity: None,
+        doc: Some("The entity spatial sounds are heard from".to_string()),
+    }
+}
+
+/// The program's @[spatial] sounds, in declaration order
+pub fn spatial_sounds(program: &Program) -> Vec<&ResourceDef> {
+    program.items.iter().filter_map(|item| match item {
+        Item::Resource(res) if res.is_spatial => Some(res),
+        _ => None,
+    }).collect()
+}
+
+/// The functions that start a spatial sound at an entity, with whether each loops
+pub fn play_at_functions(res: &ResourceDef) -> [(String, bool); 2] {
+    let lower = res.name.to_lowercase();
+    [(format!("play_resource_{}_at", lower), false), (format!("play_resource_{}_looping_at", lower), true)]
+}
+
+/// The C++ global holding the entity a spatial sound plays at
+pub fn emitter_global(res: &ResourceDef) -> String {
+    format!("g_spatial_emitter_{}", res.name.to_lowercase())
+}
+
+/// Why the program's Position component can't place sounds, if it can't: the update reads its
+/// x, y and z as numbers through g_storage.get_component
+pub fn position_problem(program: &Program) -> Option<String> {
+    let position = program.items.iter().find_map(|item| match item {
+        Item::Component(c) if c.name == "Position" => Some(c),
+        _ => None,
+    });
+    let Some(position) = position else {
+        return Some("the program has no Position component".to_string());
+    };
+    if position.is_soa {
+        return Some("Position is a component_soa".to_string());
+    }
+    let missing: Vec<&str> = ["x", "y", "z"].into_iter()
+        .filter(|axis| !position.fields.iter().any(|f| f.name == *axis && matches!(f.ty, Type::F32 | Type::F64 | Type::I32 | Type::I64)))
+        .collect();
+    if missing.is_empty() {
+        None
+    } else {
+        Some(format!("Position needs number fields x, y and z (missing {})", missing.join(", ")))
+    }
+}
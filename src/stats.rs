@@ -0,0 +1,417 @@
+// Local language-usage report (`--stats-out stats.json`)
+// Nothing leaves the machine: each compile adds or replaces its file's entry in the given JSON
+// file and recomputes the totals, so one report can cover a whole codebase.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::ast::*;
+use crate::hierarchy;
+use crate::layout::{LayoutEngine, TargetAbi};
+
+/// Usage statistics for one source file
+pub fn file_stats(program: &Program, source: &str, abi: TargetAbi) -> Value {
+    let mut blank = 0;
+    let mut comment = 0;
+    let mut code = 0;
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            blank += 1;
+        } else if line.starts_with("//") {
+            comment += 1;
+        } else {
+            code += 1;
+        }
+    }
+
+    let mut counter = UsageCounter::default();
+    let mut items = Map::new();
+    let bump = |map: &mut Map<String, Value>, key: &str| {
+        let count = map.get(key).and_then(Value::as_u64).unwrap_or(0);
+        map.insert(key.to_string(), json!(count + 1));
+    };
+    let mut attributes = Map::new();
+    for item in &program.items {
+        let kind = match item {
+            Item::Struct(_) => "structs",
+            Item::Component(c) if c.is_soa => "soa_components",
+            Item::Component(_) => "components",
+            Item::System(_) => "systems",
+            Item::Shader(_) => "shaders",
+            Item::Function(_) => "functions",
+            Item::ExternFunction(_) => "extern_functions",
+            Item::Resource(_) => "resources",
+            Item::Pipeline(_) => "pipelines",
+            Item::Gpu(_) => "gpu_blocks",
+            Item::Interface(_) => "interfaces",
+            Item::Impl(_) => "impls",
+            Item::GpuQuery(_) => "gpu_queries",
+            Item::Test(_) => "tests",
+            Item::Const(_) => "consts",
+            Item::StaticAssert(_) => "static_asserts",
+            Item::Event(_) => "events",
+            Item::Uniform(_) => "uniforms",
+            Item::Input(_) => "input_blocks",
+            Item::Window(_) => "window_blocks",
+            Item::SaveSchema(_) => "save_schemas",
+            Item::Scene(_) => "scenes",
+            Item::Migrate(_) => "migrations",
+        };
+        bump(&mut items, kind);
+
+        let hot = match item {
+            Item::Component(c) => c.is_hot,
+            Item::System(s) => s.is_hot,
+            Item::Shader(s) => s.is_hot,
+            Item::Resource(r) => r.is_hot,
+            Item::Function(f) => f.is_hot,
+            _ => false,
+        };
+        if hot {
+            bump(&mut attributes, "hot");
+        }
+        if matches!(item, Item::Component(c) if c.is_cuda) {
+            bump(&mut attributes, "cuda");
+        }
+        if matches!(item, Item::Struct(s) if s.is_export) || matches!(item, Item::Component(c) if c.is_export) {
+            bump(&mut attributes, "export");
+        }
+        if matches!(item, Item::Component(c) if c.is_replicated) {
+            bump(&mut attributes, "replicated");
+        }
+        if matches!(item, Item::Component(c) if c.pool_capacity.is_some()) {
+            bump(&mut attributes, "pool");
+        }
+        if matches!(item, Item::Resource(r) if r.is_spatial) {
+            bump(&mut attributes, "spatial");
+        }
+        if matches!(item, Item::Const(c) if c.is_tweak) {
+            bump(&mut attributes, "tweak");
+        }
+        if matches!(item, Item::Function(f) if f.cuda_kernel.is_some()) {
+            bump(&mut attributes, "launch");
+        }
+        if matches!(item, Item::System(s) if s.schedule.is_some()) {
+            bump(&mut attributes, "scheduled");
+        }
+        if matches!(item, Item::Function(f) if f.no_reload_check) {
+            bump(&mut attributes, "no_reload_check");
+        }
+        if matches!(item, Item::Function(f) if f.strict_fp) || matches!(item, Item::System(s) if s.strict_fp) {
+            bump(&mut attributes, "strict_fp");
+        }
+        if let Item::Component(c) = item {
+            c.fields.iter().filter(|f| f.cold).for_each(|_| bump(&mut attributes, "cold"));
+        }
+
+        match item {
+            Item::Function(f) => counter.function(f),
+            Item::System(s) => {
+                s.functions.iter().for_each(|f| counter.function(f));
+                s.handlers.iter().for_each(|h| counter.statements(&h.body));
+            }
+            Item::SaveSchema(schema) => schema.migration.iter().for_each(|m| counter.statements(&m.body)),
+            Item::Impl(i) => i.methods.iter().for_each(|(f, _)| counter.function(f)),
+            Item::Test(t) => counter.statements(&t.body),
+            Item::Migrate(m) => counter.function(&m.function),
+            _ => {}
+        }
+    }
+
+    let count = |map: &Map<String, Value>, key: &str| map.get(key).and_then(Value::as_u64).unwrap_or(0);
+    let mut report = json!({
+        "lines": { "total": blank + comment + code, "code": code, "comment": comment, "blank": blank },
+        "items": items,
+        "attributes": attributes,
+        "features": {
+            "cuda": count(&attributes, "cuda") + count(&attributes, "launch") > 0,
+            "hot_reload": count(&attributes, "hot") > 0,
+            "pipelines": count(&items, "pipelines") > 0,
+            "soa": count(&items, "soa_components") > 0,
+            "interfaces": count(&items, "interfaces") > 0,
+            "gpu_queries": count(&items, "gpu_queries") > 0,
+            "ecs_queries": counter.queries > 0,
+            "events": count(&items, "events") > 0,
+            "save_schemas": count(&items, "save_schemas") > 0,
+            "scenes": count(&items, "scenes") > 0,
+            "replication": count(&attributes, "replicated") > 0,
+            "resources": counter.resources > 0,
+            "entity_hierarchy": counter.hierarchy_calls > 0,
+            "spatial_audio": count(&attributes, "spatial") > 0,
+            "input_mapping": count(&items, "input_blocks") > 0,
+            "window_config": count(&items, "window_blocks") > 0,
+            "frame_arena": counter.frame_allocs > 0,
+            "component_pools": count(&attributes, "pool") > 0,
+            "gpu_resident_components": counter.gpu_syncs > 0,
+        },
+        "constructs": {
+            "defer": counter.defers,
+            "emit": counter.emits,
+            "match": counter.matches,
+            "match_guard": counter.match_guards,
+            "if_expression": counter.if_expressions,
+            "block_expression": counter.block_expressions,
+            "loop_expression": counter.loop_expressions,
+            "spawn_batch": counter.spawn_batches,
+            "string_interpolation": counter.interpolations,
+            "frame_alloc": counter.frame_allocs,
+            "gpu_sync": counter.gpu_syncs,
+            "optional_types": counter.optionals,
+            "default_params": counter.default_params,
+        },
+    });
+    let cold_split = cold_split(program, abi);
+    if !cold_split.is_empty() {
+        report["cold_fields"] = Value::Object(cold_split);
+    }
+    report
+}
+
+// The per-entity layout of each component_soa with @cold fields, before and after they moved
+// into the side array: how many arrays a loop over the hot fields steps through and their bytes
+fn cold_split(program: &Program, abi: TargetAbi) -> Map<String, Value> {
+    let structs = program.items.iter()
+        .filter_map(|item| match item { Item::Struct(s) => Some((s.name.clone(), s.clone())), _ => None })
+        .collect();
+    let components = program.items.iter()
+        .filter_map(|item| match item { Item::Component(c) => Some((c.name.clone(), c.clone())), _ => None })
+        .collect();
+    let layouts = LayoutEngine::new(&structs, &components, abi);
+    let elements = |fields: &[&Field]| -> Vec<Field> {
+        fields.iter().map(|f| match &f.ty {
+            Type::Array(element) => Field { ty: element.as_ref().clone(), ..(*f).clone() },
+            _ => (*f).clone(),
+        }).collect()
+    };
+    let bytes = |fields: &[Field]| -> usize { fields.iter().map(|f| layouts.layout(&f.ty).map_or(0, |l| l.size)).sum() };
+
+    let mut split = Map::new();
+    for item in &program.items {
+        let Item::Component(c) = item else { continue };
+        if !c.has_cold_fields() {
+            continue;
+        }
+        let all = elements(&c.fields.iter().collect::<Vec<_>>());
+        let hot = elements(&c.fields.iter().filter(|f| !f.cold).collect::<Vec<_>>());
+        let cold = elements(&c.fields.iter().filter(|f| f.cold).collect::<Vec<_>>());
+        let cold_struct = layouts.field_offsets(&cold, 0).map_or(0, |(_, layout)| layout.size);
+        split.insert(c.name.clone(), json!({
+            "cold": cold.iter().map(|f| f.name.clone()).collect::<Vec<_>>(),
+            "before": { "hot_arrays": all.len(), "hot_bytes_per_entity": bytes(&all) },
+            "after": { "hot_arrays": hot.len(), "hot_bytes_per_entity": bytes(&hot), "cold_bytes_per_entity": cold_struct },
+        }));
+    }
+    split
+}
+
+/// Add or replace `file`'s entry in the report at `report_path` and refresh the totals
+pub fn update_report(report_path: &str, file: &str, stats: Value) -> Result<()> {
+    let mut report = match fs::read_to_string(report_path) {
+        Ok(text) => serde_json::from_str::<Value>(&text)
+            .with_context(|| format!("{} is not a stats report", report_path))?,
+        Err(_) => json!({}),
+    };
+    let files = report.as_object_mut()
+        .with_context(|| format!("{} is not a stats report", report_path))?
+        .entry("files")
+        .or_insert_with(|| json!({}));
+    let files = files.as_object_mut()
+        .with_context(|| format!("{} is not a stats report", report_path))?;
+    files.insert(file.to_string(), stats);
+
+    // Numbers are summed and flags count the files using the feature
+    let mut totals = Value::Object(Map::new());
+    for stats in files.values() {
+        accumulate(&mut totals, stats);
+    }
+    totals["files"] = json!(files.len());
+    report["totals"] = totals;
+
+    if let Some(parent) = Path::new(report_path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(report_path, serde_json::to_string_pretty(&report)?)
+        .with_context(|| format!("Failed to write {}", report_path))
+}
+
+fn accumulate(total: &mut Value, value: &Value) {
+    match value {
+        Value::Object(fields) => {
+            if !total.is_object() {
+                *total = Value::Object(Map::new());
+            }
+            for (key, field) in fields {
+                accumulate(total.as_object_mut().unwrap().entry(key.clone()).or_insert(Value::Null), field);
+            }
+        }
+        Value::Number(n) => *total = json!(total.as_u64().unwrap_or(0) + n.as_u64().unwrap_or(0)),
+        Value::Bool(b) => *total = json!(total.as_u64().unwrap_or(0) + *b as u64),
+        _ => {}
+    }
+}
+
+#[derive(Default)]
+struct UsageCounter {
+    defers: u64,
+    emits: u64,
+    matches: u64,
+    match_guards: u64,
+    if_expressions: u64,
+    block_expressions: u64,
+    loop_expressions: u64,
+    spawn_batches: u64,
+    interpolations: u64,
+    optionals: u64,
+    default_params: u64,
+    queries: u64,
+    resources: u64,
+    hierarchy_calls: u64,
+    frame_allocs: u64,
+    gpu_syncs: u64,
+}
+
+impl UsageCounter {
+    fn function(&mut self, f: &FunctionDef) {
+        for param in &f.params {
+            self.ty(&param.ty);
+            if let Some(default) = &param.default {
+                self.default_params += 1;
+                self.expression(default);
+            }
+        }
+        self.ty(&f.return_type);
+        self.statements(&f.body);
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        match ty {
+            Type::Optional(inner) => {
+                self.optionals += 1;
+                self.ty(inner);
+            }
+            Type::Query(_) => self.queries += 1,
+            Type::Res(_) => self.resources += 1,
+            Type::Array(inner) => self.ty(inner),
+            Type::Function(params, ret) => {
+                params.iter().for_each(|p| self.ty(p));
+                self.ty(ret);
+            }
+            _ => {}
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Let { ty, value, .. } => {
+                    if let Some(ty) = ty {
+                        self.ty(ty);
+                    }
+                    self.expression(value);
+                }
+                Statement::Assign { target, value, .. } => {
+                    self.expression(target);
+                    self.expression(value);
+                }
+                Statement::If { condition, then_block, else_block, .. } => {
+                    self.expression(condition);
+                    self.statements(then_block);
+                    if let Some(else_block) = else_block {
+                        self.statements(else_block);
+                    }
+                }
+                Statement::While { condition, body, .. } => {
+                    self.expression(condition);
+                    self.statements(body);
+                }
+                Statement::For { collection, body, .. } => {
+                    self.expression(collection);
+                    self.statements(body);
+                }
+                Statement::Loop { body, .. } | Statement::Block(body, _) => self.statements(body),
+                Statement::SpawnBatch { count, body, .. } => {
+                    self.spawn_batches += 1;
+                    self.expression(count);
+                    self.statements(body);
+                }
+                Statement::Return(Some(value), _) | Statement::Expression(value, _)
+                | Statement::Destructure { value, .. } => self.expression(value),
+                Statement::Defer(value, _) => {
+                    self.defers += 1;
+                    self.expression(value);
+                }
+                Statement::Emit(event, _) => {
+                    self.emits += 1;
+                    self.expression(event);
+                }
+                Statement::Break(Some(value), _) => self.expression(value),
+                Statement::Return(None, _) | Statement::Break(None, _) | Statement::Continue(_) => {}
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::BinaryOp { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::UnaryOp { expr, .. } | Expression::Cast { expr, .. } => self.expression(expr),
+            Expression::Call { name, args, .. } => {
+                if hierarchy::METHODS.contains(&name.as_str()) {
+                    self.hierarchy_calls += 1;
+                }
+                args.iter().for_each(|a| self.expression(a));
+            }
+            Expression::MemberAccess { object, .. } => self.expression(object),
+            Expression::Index { array, index, .. } => {
+                self.expression(array);
+                self.expression(index);
+            }
+            Expression::ArrayLiteral { elements, .. } => elements.iter().for_each(|e| self.expression(e)),
+            Expression::StringInterpolation { .. } => self.interpolations += 1,
+            Expression::Match { expr, arms, .. } => {
+                self.matches += 1;
+                self.expression(expr);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.match_guards += 1;
+                        self.expression(guard);
+                    }
+                    self.statements(&arm.body);
+                    if let Some(value) = &arm.value {
+                        self.expression(value);
+                    }
+                }
+            }
+            Expression::If { condition, then_branch, else_branch, .. } => {
+                self.if_expressions += 1;
+                self.expression(condition);
+                for block in [then_branch, else_branch] {
+                    self.statements(&block.statements);
+                    self.expression(&block.value);
+                }
+            }
+            Expression::Loop { body, .. } => {
+                self.loop_expressions += 1;
+                self.statements(body);
+            }
+            Expression::Block { body, .. } => {
+                self.block_expressions += 1;
+                self.statements(&body.statements);
+                self.expression(&body.value);
+            }
+            Expression::FrameAlloc { arena, count, .. } => {
+                self.frame_allocs += 1;
+                self.expression(arena);
+                self.expression(count);
+            }
+            Expression::GpuSync { .. } => self.gpu_syncs += 1,
+            Expression::StructLiteral { fields, .. } => fields.iter().for_each(|(_, value)| self.expression(value)),
+            Expression::Literal(..) | Expression::Variable(..) | Expression::LayoutOf { .. } => {}
+        }
+    }
+}
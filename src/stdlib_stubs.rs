@@ -0,0 +1,93 @@
+// --gen-stdlib-stubs: compilable stand-ins for the stdlib/ headers generated code includes, so
+// logic-only programs (and CI) build without the EDEN engine checkout. The header-only parts of
+// the stdlib are written as they are; headers that wrap Vulkan, GLFW, GLM or the renderer are
+// replaced by stdlib/stubs/, which keep their types and constants with no-op functions.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Header name (under stdlib/) and the contents written for it
+const STUBS: &[(&str, &str)] = &[
+    ("version.h", include_str!("../stdlib/version.h")),
+    ("vulkan.h", include_str!("../stdlib/stubs/vulkan.h")),
+    ("glfw.h", include_str!("../stdlib/stubs/glfw.h")),
+    ("math.h", include_str!("../stdlib/stubs/math.h")),
+    ("imgui.h", include_str!("../stdlib/imgui.h")),
+    ("transfer_queue.h", include_str!("../stdlib/stubs/transfer_queue.h")),
+    ("frame_capture.h", include_str!("../stdlib/stubs/frame_capture.h")),
+    ("gpu_config.h", include_str!("../stdlib/stubs/gpu_config.h")),
+    ("gpu_query.h", include_str!("../stdlib/stubs/gpu_query.h")),
+    ("gpu_buffer.h", include_str!("../stdlib/stubs/gpu_buffer.h")),
+    ("vulkan_compute.h", include_str!("../stdlib/stubs/vulkan_compute.h")),
+    ("uniform_buffer.h", include_str!("../stdlib/stubs/uniform_buffer.h")),
+    ("input_map.h", include_str!("../stdlib/stubs/input_map.h")),
+    ("window_config.h", include_str!("../stdlib/stubs/window_config.h")),
+    ("entity_storage.h", include_str!("../stdlib/entity_storage.h")),
+    ("archetype_storage.h", include_str!("../stdlib/archetype_storage.h")),
+    ("hierarchy.h", include_str!("../stdlib/hierarchy.h")),
+    ("events.h", include_str!("../stdlib/events.h")),
+    ("tweak.h", include_str!("../stdlib/tweak.h")),
+    ("save_data.h", include_str!("../stdlib/save_data.h")),
+    ("net.h", include_str!("../stdlib/net.h")),
+    ("slice.h", include_str!("../stdlib/slice.h")),
+    ("frame_arena.h", include_str!("../stdlib/frame_arena.h")),
+    ("cuda_bridge.h", include_str!("../stdlib/cuda_bridge.h")),
+    ("cuda_launch.h", include_str!("../stdlib/cuda_launch.h")),
+    ("checked_arithmetic.h", include_str!("../stdlib/checked_arithmetic.h")),
+    ("strict_fp.h", include_str!("../stdlib/strict_fp.h")),
+    ("memory_report.h", include_str!("../stdlib/memory_report.h")),
+    ("push_constants.h", include_str!("../stdlib/push_constants.h")),
+    ("content_hash.h", include_str!("../stdlib/content_hash.h")),
+    ("component_registry.h", include_str!("../stdlib/component_registry.h")),
+    ("type_map.h", include_str!("../stdlib/type_map.h")),
+    ("minimal_runtime.h", include_str!("../stdlib/minimal_runtime.h")),
+    ("canvas.h", include_str!("../stdlib/canvas.h")),
+    ("webgpu.h", include_str!("../stdlib/webgpu.h")),
+];
+
+#[derive(Debug, Default)]
+pub struct StubReport {
+    pub written: Vec<PathBuf>,
+    /// Missing headers with no stub (asset resources need the engine's loaders)
+    pub unavailable: Vec<String>,
+}
+
+/// Headers named by `#include "<prefix><name>"` lines, in order
+pub fn includes<'a>(code: &'a str, prefix: &str) -> Vec<&'a str> {
+    code.lines()
+        .filter_map(|line| line.trim().strip_prefix("#include \"")?.strip_prefix(prefix)?.strip_suffix('"'))
+        .collect()
+}
+
+/// Write a stub into `<dir>/stdlib/` for every stdlib header the generated sources include that
+/// isn't there yet, along with the stdlib headers those stubs include themselves. Existing files
+/// are never overwritten.
+pub fn write_missing(dir: &Path, sources: &[&str]) -> io::Result<StubReport> {
+    let stdlib_dir = dir.join("stdlib");
+    let mut pending: Vec<&str> = sources.iter().flat_map(|code| includes(code, "stdlib/")).collect();
+    let mut seen = Vec::new();
+    let mut report = StubReport::default();
+    while let Some(name) = pending.pop() {
+        if seen.contains(&name) {
+            continue;
+        }
+        seen.push(name);
+        let path = stdlib_dir.join(name);
+        if path.exists() {
+            continue;
+        }
+        let Some((_, contents)) = STUBS.iter().find(|(stub, _)| *stub == name) else {
+            report.unavailable.push(name.to_string());
+            continue;
+        };
+        fs::create_dir_all(&stdlib_dir)?;
+        fs::write(&path, contents)?;
+        report.written.push(path);
+        // Their other includes are system or third-party headers (ImGui, behind USE_IMGUI)
+        pending.extend(includes(contents, "").into_iter().filter(|name| STUBS.iter().any(|(stub, _)| stub == name)));
+    }
+    report.written.sort();
+    report.unavailable.sort();
+    Ok(report)
+}
@@ -0,0 +1,269 @@
+// Generated-symbol map (`--emit=symbols`): <file>.symbols.json lists the C++ symbols a compile
+// produced and the HEIDIC item each one came from, so the LSP and debugger scripts can turn native
+// stack traces back into source terms. Candidates follow codegen's naming rules; only the ones that
+// appear in the generated code are listed.
+
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::ast::*;
+use crate::codegen::HOT_FUNCTIONS_MODULE;
+use crate::cuda;
+use crate::vulkan_compute;
+use crate::error::SourceLocation;
+use crate::generated_names;
+use crate::input_map;
+use crate::save_schema;
+use crate::spatial_audio;
+
+/// One generated C++ file: its name next to the source and its contents
+pub struct GeneratedFile<'a> {
+    pub name: String,
+    pub code: &'a str,
+}
+
+/// What a candidate symbol is in the C++
+#[derive(Clone, Copy)]
+enum Kind {
+    Function,
+    Global,
+    Type,
+    DescriptorSetLayout,
+    HotPointer,  // Host-side pointer to a DLL export
+    DllExport,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Function => "function",
+            Kind::Global => "global",
+            Kind::Type => "type",
+            Kind::DescriptorSetLayout => "descriptor_set_layout",
+            Kind::HotPointer => "hot_pointer",
+            Kind::DllExport => "dll_export",
+        }
+    }
+}
+
+struct Candidate {
+    symbol: String,
+    kind: Kind,
+    file: Option<String>,  // The DLL source for exports; None for the program's own .cpp
+    item: String,          // "system Movement", "fn main", ...
+    location: SourceLocation,
+}
+
+/// The symbol map for a compiled program. `item_locations` parallels `program.items` (unknown for
+/// workspace shared modules); `files[0]` is the program's .cpp, the rest its hot DLL sources and
+/// the .cu of its CUDA kernels.
+pub fn symbol_map(program: &Program, item_locations: &[SourceLocation], source: &str, prefix: &str, files: &[GeneratedFile]) -> Value {
+    let mut candidates = Vec::new();
+    let mut add = |symbol: String, kind: Kind, file: Option<String>, item: &str, location: SourceLocation| {
+        candidates.push(Candidate { symbol, kind, file, item: item.to_string(), location });
+    };
+    let own = |name: &str| generated_names::mangled(name, prefix);
+    let hot_functions_dll = format!("{}_hot.dll.cpp", HOT_FUNCTIONS_MODULE);
+    let cuda_file = files.iter().map(|file| file.name.clone()).find(|name| name.ends_with(".cu"));
+    let (mut tests, mut scenes) = (0, 0);
+
+    for (index, item) in program.items.iter().enumerate() {
+        let location = item_locations.get(index).copied().unwrap_or_else(SourceLocation::unknown);
+        match item {
+            Item::Function(f) => {
+                let label = format!("fn {}", f.name);
+                if f.name == "main" {
+                    add(own("heidic_main"), Kind::Function, None, &label, location);
+                } else if f.is_hot {
+                    add(f.name.clone(), Kind::DllExport, Some(hot_functions_dll.clone()), &label, location);
+                    add(own(&format!("g_{}", f.name)), Kind::HotPointer, None, &label, location);
+                } else {
+                    add(f.name.clone(), Kind::Function, None, &label, location);
+                }
+                if let Some(launch) = &f.cuda_kernel {
+                    add(cuda::launch_function(f), Kind::Function, None, &label, location);
+                    add(cuda::wait_function(f), Kind::Function, None, &label, location);
+                    add(format!("{}_kernel", launch.kernel), Kind::Function, cuda_file.clone(), &label, location);
+                    add(own(&cuda::bridge_launch(launch)), Kind::Function, cuda_file.clone(), &label, location);
+                    add(own(&cuda::bridge_wait(launch)), Kind::Function, cuda_file.clone(), &label, location);
+                    add(own(&vulkan_compute::kernel_global(launch)), Kind::Global, None, &label, location);
+                    add(vulkan_compute::params_type(launch), Kind::Type, None, &label, location);
+                    add(own(&vulkan_compute::results_global(launch)), Kind::Global, None, &label, location);
+                    add(own(&vulkan_compute::pending_global(launch)), Kind::Global, None, &label, location);
+                    if let Some((query, component_types)) = cuda::kernel_query(f) {
+                        let components = program.items.iter().filter_map(|item| match item {
+                            Item::Component(c) if component_types.iter().any(|ty| matches!(ty, Type::Struct(name) | Type::Component(name) if *name == c.name)) => Some(c),
+                            _ => None,
+                        });
+                        for array in components.flat_map(|c| cuda::device_arrays(&query.name, c)) {
+                            add(own(&vulkan_compute::buffer_global(launch, &array)), Kind::Global, None, &label, location);
+                        }
+                    }
+                }
+            }
+            Item::System(s) => {
+                let label = format!("system {}", s.name);
+                let dll = format!("{}_hot.dll.cpp", s.name.to_lowercase());
+                for f in &s.functions {
+                    let label = format!("fn {} in {}", f.name, label);
+                    if s.is_hot {
+                        add(f.name.clone(), Kind::DllExport, Some(dll.clone()), &label, location);
+                        add(own(&format!("g_{}", f.name)), Kind::HotPointer, None, &label, location);
+                    } else {
+                        add(f.name.clone(), Kind::Function, None, &label, location);
+                    }
+                }
+                for handler in &s.handlers {
+                    let label = format!("on {} in {}", handler.event, label);
+                    add(format!("heidic_{}_on_{}", s.name, handler.event), Kind::Function, None, &label, handler.location);
+                }
+                if s.is_hot {
+                    add(own(&format!("g_dll_watch_{}", s.name.to_lowercase())), Kind::Global, None, &label, location);
+                }
+            }
+            Item::Struct(s) => add(s.name.clone(), Kind::Type, None, &format!("struct {}", s.name), location),
+            Item::Component(c) => {
+                let label = format!("component {}", c.name);
+                let lower = c.name.to_lowercase();
+                add(c.name.clone(), Kind::Type, None, &label, location);
+                for name in ["g_metadata_", "g_prev_metadata_", "g_prev_sig_storage_"] {
+                    add(own(&format!("{}{}", name, lower)), Kind::Global, None, &label, location);
+                }
+                add(format!("migrate_{}", lower), Kind::Function, None, &label, location);
+                if c.is_cuda {
+                    for direction in [SyncDirection::ToGpu, SyncDirection::ToCpu] {
+                        add(own(&cuda::sync_function(direction, &c.name)), Kind::Function, None, &label, location);
+                        add(own(&cuda::bridge_sync(direction, &c.name)), Kind::Function, cuda_file.clone(), &label, location);
+                    }
+                    add(own(&cuda::residency_global(c)), Kind::Global, None, &label, location);
+                    for array in cuda::resident_arrays(c) {
+                        add(own(&array.buffer), Kind::Global, cuda_file.clone(), &label, location);
+                    }
+                    add(own(&cuda::resident_rows(c)), Kind::Global, cuda_file.clone(), &label, location);
+                }
+            }
+            Item::Event(e) => {
+                let label = format!("event {}", e.name);
+                add(e.name.clone(), Kind::Type, None, &label, e.location);
+                add(format!("heidic_events_{}", e.name), Kind::Global, None, &label, e.location);
+            }
+            Item::Uniform(u) => {
+                let label = format!("uniform {}", u.name);
+                add(u.name.clone(), Kind::Type, None, &label, u.location);
+                add(own(&u.buffer_global()), Kind::Global, None, &label, u.location);
+                add(u.update_function(), Kind::Function, None, &label, u.location);
+            }
+            Item::Input(input) => {
+                add(input_map::DEVICES_GLOBAL.to_string(), Kind::Global, None, "input", input.location);
+                add(input_map::UPDATE_FUNCTION.to_string(), Kind::Function, None, "input", input.location);
+                for action in &input.actions {
+                    add(input_map::action_global(action), Kind::Global, None, &format!("input {}", action.name), action.location);
+                }
+            }
+            Item::Resource(r) => {
+                let label = format!("resource {}", r.name);
+                let lower = r.name.to_lowercase();
+                add(own(&format!("g_resource_{}", lower)), Kind::Global, None, &label, location);
+                for accessor in ["get_resource_", "play_resource_", "stop_resource_", "pause_resource_", "resume_resource_", "set_volume_resource_"] {
+                    add(own(&format!("{}{}", accessor, lower)), Kind::Function, None, &label, location);
+                }
+                add(own(&format!("play_resource_{}_looping", lower)), Kind::Function, None, &label, location);
+                if r.is_spatial {
+                    add(own(&spatial_audio::emitter_global(r)), Kind::Global, None, &label, location);
+                    for (function, _) in spatial_audio::play_at_functions(r) {
+                        add(own(&function), Kind::Function, None, &label, location);
+                    }
+                }
+                for video in ["play_video_", "pause_video_", "stop_video_", "seek_video_", "update_video_", "get_video_width_",
+                              "get_video_height_", "get_video_duration_", "get_video_current_time_", "is_video_playing_"] {
+                    add(format!("{}{}", video, lower), Kind::Function, None, &label, location);
+                }
+            }
+            Item::Pipeline(p) => {
+                let label = format!("pipeline {}", p.name);
+                let lower = p.name.to_lowercase();
+                add(own(&format!("g_pipeline_{}", lower)), Kind::Global, None, &label, location);
+                add(own(&format!("g_pipeline_layout_{}", lower)), Kind::Global, None, &label, location);
+                add(own(&format!("g_descriptor_set_layout_{}", lower)), Kind::DescriptorSetLayout, None, &label, location);
+                add(own(&format!("create_pipeline_{}", lower)), Kind::Function, None, &label, location);
+                add(own(&format!("create_descriptor_set_layout_{}", lower)), Kind::Function, None, &label, location);
+                add(own(&format!("rebuild_pipeline_{}", lower)), Kind::Function, None, &label, location);
+                add(format!("bind_pipeline_{}", lower), Kind::Function, None, &label, location);
+                if !p.bindings().is_empty() {
+                    add(own(&format!("g_descriptor_pool_{}", lower)), Kind::Global, None, &label, location);
+                    add(own(&format!("g_descriptor_set_{}", lower)), Kind::Global, None, &label, location);
+                    add(p.descriptor_sets_function(), Kind::Function, None, &label, location);
+                    add(p.update_binding_function(), Kind::Function, None, &label, location);
+                }
+                if p.push_constants().is_some() {
+                    add(p.push_constants_struct(), Kind::Type, None, &label, location);
+                    add(p.push_constants_function(), Kind::Function, None, &label, location);
+                }
+            }
+            Item::Const(c) => add(c.name.clone(), Kind::Global, None, &format!("const {}", c.name), c.location),
+            Item::GpuQuery(q) => add(own(&format!("g_gpu_query_{}", q.name)), Kind::Global, None, &format!("gpu_query {}", q.name), q.location),
+            Item::SaveSchema(schema) => {
+                let label = format!("save_schema {}", schema.name);
+                add(schema.name.clone(), Kind::Type, None, &label, schema.location);
+                add(save_schema::save_function(&schema.name), Kind::Function, None, &label, schema.location);
+                add(save_schema::load_function(&schema.name), Kind::Function, None, &label, schema.location);
+            }
+            Item::Scene(scene) => {
+                add(format!("heidic_spawn_scene_{}", scenes), Kind::Function, None, &format!("scene \"{}\"", scene.path), scene.location);
+                scenes += 1;
+            }
+            Item::Test(test) => {
+                add(format!("heidic_test_{}", tests), Kind::Function, None, &format!("test \"{}\"", test.name), test.location);
+                tests += 1;
+            }
+            Item::Migrate(m) => add(m.function.name.clone(), Kind::Function, None, &format!("migrate {}", m.function.name), m.location),
+            Item::ExternFunction(_) | Item::Shader(_) | Item::Gpu(_) | Item::Window(_) | Item::Interface(_) | Item::Impl(_) | Item::StaticAssert(_) => {}
+        }
+    }
+
+    // Only what the generated files actually contain
+    let identifiers: Vec<HashSet<String>> = files.iter().map(|file| generated_names::identifiers(file.code)).collect();
+    let program_file = files.first().map(|file| file.name.as_str()).unwrap_or_default();
+    let mut seen = HashSet::new();
+    let mut symbols = Vec::new();
+    for candidate in candidates {
+        let file = candidate.file.as_deref().unwrap_or(program_file);
+        let Some(index) = files.iter().position(|f| f.name == file) else { continue };
+        if !identifiers[index].contains(&candidate.symbol) || !seen.insert((file.to_string(), candidate.symbol.clone())) {
+            continue;
+        }
+        let origin = (!candidate.location.is_unknown())
+            .then(|| json!({ "file": source, "line": candidate.location.line, "column": candidate.location.column }));
+        symbols.push(json!({
+            "symbol": candidate.symbol,
+            "kind": candidate.kind.name(),
+            "file": file,
+            "item": candidate.item,
+            "source": origin,
+        }));
+    }
+
+    // The runtime's own functions and globals belong to no item
+    let mut runtime = Vec::new();
+    for (name, feature) in generated_names::runtime_symbols() {
+        let symbol = own(name);
+        if identifiers.first().is_some_and(|ids| ids.contains(&symbol)) && !seen.contains(&(program_file.to_string(), symbol.clone())) {
+            runtime.push(json!({ "symbol": symbol, "file": program_file, "feature": feature }));
+        }
+    }
+    for (file, ids) in files.iter().zip(&identifiers).skip(1) {
+        let mut exports: Vec<&String> = ids.iter().filter(|id| id.starts_with("heidic_hot_abi_")).collect();
+        exports.sort();
+        for export in exports {
+            runtime.push(json!({ "symbol": export, "file": file.name, "feature": "hot reload ABI check" }));
+        }
+    }
+
+    json!({
+        "source": source,
+        "prefix": prefix,
+        "files": files.iter().map(|file| file.name.as_str()).collect::<Vec<_>>(),
+        "symbols": symbols,
+        "runtime": runtime,
+    })
+}
@@ -1,7 +1,21 @@
 use crate::ast::*;
-use crate::error::{SourceLocation, ErrorReporter};
+use crate::const_eval::{self, ConstValue};
+use crate::cuda;
+use crate::layout::{LayoutEngine, TargetAbi};
+use crate::generated_names;
+use crate::frame_arena;
+use crate::hierarchy;
+use crate::input_map;
+use crate::layout_export;
+use crate::replication;
+use crate::resources;
+use crate::save_schema;
+use crate::scene;
+use crate::spatial_audio;
+use crate::error::{Diagnostic, SourceLocation, ErrorReporter, ReportOptions, SecondaryLocation, Severity};
+use crate::schedule;
 use anyhow::{Result, bail};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 // Calculate Levenshtein distance between two strings
 fn levenshtein_distance(s1: &str, s2: &str) -> usize {
@@ -50,31 +64,133 @@ fn find_closest_match(target: &str, candidates: &[String], max_distance: usize)
     best_match.map(|(name, _)| name)
 }
 
-pub struct TypeChecker {
+// Feature names accepted by gpu { required_features: [...], optional_features: [...] }:
+// VkPhysicalDeviceFeatures members, then VkPhysicalDeviceDescriptorIndexingFeatures members
+// (must match the feature table in vulkan/eden_vulkan_helpers.cpp)
+const VULKAN_DEVICE_FEATURES: &[&str] = &[
+    "robustBufferAccess", "fullDrawIndexUint32", "imageCubeArray", "independentBlend",
+    "geometryShader", "tessellationShader", "sampleRateShading", "dualSrcBlend", "logicOp",
+    "multiDrawIndirect", "drawIndirectFirstInstance", "depthClamp", "depthBiasClamp",
+    "fillModeNonSolid", "depthBounds", "wideLines", "largePoints", "alphaToOne", "multiViewport",
+    "samplerAnisotropy", "textureCompressionETC2", "textureCompressionASTC_LDR",
+    "textureCompressionBC", "occlusionQueryPrecise", "pipelineStatisticsQuery",
+    "vertexPipelineStoresAndAtomics", "fragmentStoresAndAtomics",
+    "shaderTessellationAndGeometryPointSize", "shaderImageGatherExtended",
+    "shaderStorageImageExtendedFormats", "shaderStorageImageMultisample",
+    "shaderStorageImageReadWithoutFormat", "shaderStorageImageWriteWithoutFormat",
+    "shaderUniformBufferArrayDynamicIndexing", "shaderSampledImageArrayDynamicIndexing",
+    "shaderStorageBufferArrayDynamicIndexing", "shaderStorageImageArrayDynamicIndexing",
+    "shaderClipDistance", "shaderCullDistance", "shaderFloat64", "shaderInt64", "shaderInt16",
+    "shaderResourceResidency", "shaderResourceMinLod", "sparseBinding", "sparseResidencyBuffer",
+    "sparseResidencyImage2D", "sparseResidencyImage3D", "sparseResidency2Samples",
+    "sparseResidency4Samples", "sparseResidency8Samples", "sparseResidency16Samples",
+    "sparseResidencyAliased", "variableMultisampleRate", "inheritedQueries",
+    "shaderInputAttachmentArrayDynamicIndexing", "shaderUniformTexelBufferArrayDynamicIndexing",
+    "shaderStorageTexelBufferArrayDynamicIndexing", "shaderUniformBufferArrayNonUniformIndexing",
+    "shaderSampledImageArrayNonUniformIndexing", "shaderStorageBufferArrayNonUniformIndexing",
+    "shaderStorageImageArrayNonUniformIndexing", "shaderInputAttachmentArrayNonUniformIndexing",
+    "shaderUniformTexelBufferArrayNonUniformIndexing",
+    "shaderStorageTexelBufferArrayNonUniformIndexing",
+    "descriptorBindingUniformBufferUpdateAfterBind", "descriptorBindingSampledImageUpdateAfterBind",
+    "descriptorBindingStorageImageUpdateAfterBind", "descriptorBindingStorageBufferUpdateAfterBind",
+    "descriptorBindingUniformTexelBufferUpdateAfterBind",
+    "descriptorBindingStorageTexelBufferUpdateAfterBind",
+    "descriptorBindingUpdateUnusedWhilePending", "descriptorBindingPartiallyBound",
+    "descriptorBindingVariableDescriptorCount", "runtimeDescriptorArray",
+];
+
+// One block's bindings; blocks nest, and inner bindings shadow outer ones until the block ends
+struct Scope {
     symbols: HashMap<String, Type>,
+    immutable: HashMap<String, (SourceLocation, bool)>,  // Lets and parameters without `mut`: declaration, is_param
+    first_local: usize,  // Index into `locals` where this block's bindings start
+}
+
+// A parameter or let binding, tracked for unused-variable warnings
+struct LocalBinding {
+    name: String,
+    location: SourceLocation,
+    is_param: bool,
+    used: bool,
+}
+
+pub struct TypeChecker {
+    scopes: Vec<Scope>,  // Innermost last; the function's parameters and top-level lets share the outermost
     functions: HashMap<String, FunctionDef>,
     structs: HashMap<String, StructDef>,
     components: HashMap<String, ComponentDef>,
-    errors: Vec<(SourceLocation, String, Option<String>)>,  // (location, message, suggestion)
+    interfaces: HashMap<String, InterfaceDef>,
+    gpu_queries: HashMap<String, GpuQueryDef>,  // gpu_query pools, by name
+    events: HashMap<String, SourceLocation>,  // Event types (also registered as structs), by name
+    consts: HashMap<String, (Type, ConstValue, SourceLocation)>,  // Top-level consts with their evaluated values
+    tweaks: HashSet<String>,  // @tweak consts: their values change at runtime, so they aren't compile-time constants
+    pools: HashMap<String, (u32, SourceLocation)>,  // @[pool] components: capacity and declaration
+    gpu_buffers: HashMap<String, (String, SourceLocation)>,  // Named storage bindings: element type, first binding
+    buffer_bindings: HashMap<String, (String, Vec<u32>)>,  // update_binding_<pipeline> -> pipeline, its uniform/storage bindings
+    inline_shaders: Vec<(String, ShaderStage, SourceLocation)>,  // `shader <stage> Name { glsl { ... } }`
+    impls: HashMap<(String, String), SourceLocation>,  // (interface, target) -> impl block location
+    errors: Vec<Diagnostic>,
+    warnings: Vec<Diagnostic>,  // Don't fail the check
+    locals: Vec<LocalBinding>,  // Parameters and let bindings of the function being checked, in declaration order
     error_reporter: Option<ErrorReporter>,
     frame_scoped_vars: std::collections::HashSet<String>,  // Track variables allocated via frame.alloc_array
-    // Track ALL variable declarations for better scope error messages
-    all_declared_vars: HashMap<String, SourceLocation>,  // Variable name -> declaration location
-    current_scope_depth: usize,  // Track nesting level for scope-aware errors
+    frame_arena: Type,  // The type of the built-in `frame`, which names that aren't declared fall back to
+    // Scope-aware messages for undefined variables
+    out_of_scope: HashMap<String, SourceLocation>,  // Lets from blocks that have ended in the current function
+    function_lets: Vec<(String, SourceLocation)>,  // Every let in the current function, for use-before-declaration
+    declarations: Vec<(SourceLocation, String, Type)>,  // Every let binding with its resolved type (for hover)
+    in_test: bool,  // Checking a test block (expect and expect_frame are only available there)
+    hot_system: Option<String>,  // Checking a function of this @hot system (compiled into its own DLL)
+    hot_function: Option<String>,  // Checking this top-level @hot fn (compiled into the hot functions DLL)
+    host_functions: HashSet<String>,  // Program functions compiled into the executable, which a @hot fn can't call
+    extern_functions: HashSet<String>,  // extern fns, whose array parameters are pointers they can write through
+    entity_iterators: Vec<String>,  // Iterators of the enclosing `for entity in q` loops (entities for set_parent)
+    loop_breaks: Vec<Option<Vec<(Type, SourceLocation)>>>,  // Enclosing loops; Some for loops used as values, with their break values
+    hierarchy_shadowed: Option<String>,  // A built-in hierarchy component the program declares itself
+    spatial_plays: HashSet<String>,  // play_resource_<name>_at functions of @[spatial] sounds, which take a loop entity
+    has_hot_systems: bool,  // rollback_last_reload() needs a @hot system DLL to roll back
+    target_abi: TargetAbi,  // Standard library layouts for size_of/align_of/offset_of
+    item_locations: Vec<SourceLocation>,  // Where each item starts, parallel to Program::items (for items without a location)
 }
 
 impl TypeChecker {
     pub fn new() -> Self {
         Self {
-            symbols: HashMap::new(),
+            scopes: Vec::new(),
             functions: HashMap::new(),
             structs: HashMap::new(),
             components: HashMap::new(),
+            interfaces: HashMap::new(),
+            gpu_queries: HashMap::new(),
+            events: HashMap::new(),
+            consts: HashMap::new(),
+            tweaks: HashSet::new(),
+            pools: HashMap::new(),
+            gpu_buffers: HashMap::new(),
+            buffer_bindings: HashMap::new(),
+            inline_shaders: Vec::new(),
+            impls: HashMap::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            locals: Vec::new(),
             error_reporter: None,
             frame_scoped_vars: std::collections::HashSet::new(),
-            all_declared_vars: HashMap::new(),
-            current_scope_depth: 0,
+            frame_arena: frame_arena::arena_type(),
+            out_of_scope: HashMap::new(),
+            function_lets: Vec::new(),
+            declarations: Vec::new(),
+            in_test: false,
+            hot_system: None,
+            hot_function: None,
+            host_functions: HashSet::new(),
+            extern_functions: HashSet::new(),
+            entity_iterators: Vec::new(),
+            loop_breaks: Vec::new(),
+            hierarchy_shadowed: None,
+            spatial_plays: HashSet::new(),
+            has_hot_systems: false,
+            target_abi: TargetAbi::host(),
+            item_locations: Vec::new(),
         }
     }
     
@@ -82,50 +198,136 @@ impl TypeChecker {
         self.error_reporter = Some(reporter);
     }
     
+    pub fn set_target_abi(&mut self, abi: TargetAbi) {
+        self.target_abi = abi;
+    }
+    
+    /// Where each item starts (Parser::item_locations), so errors about a struct, component or
+    /// function can point at it
+    pub fn set_item_locations(&mut self, locations: Vec<SourceLocation>) {
+        self.item_locations = locations;
+    }
+    
+    fn layouts(&self) -> LayoutEngine<'_> {
+        LayoutEngine::new(&self.structs, &self.components, self.target_abi)
+    }
+    
+    /// Errors reported by the last check (used by the language server instead of the terminal reporter)
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+    
+    /// Warnings reported by the last check (unused variables, unreachable code)
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+    
+    /// Let bindings seen by the last check, with their declared or inferred types
+    pub fn declarations(&self) -> &[(SourceLocation, String, Type)] {
+        &self.declarations
+    }
+    
     fn report_error(&mut self, location: SourceLocation, message: String, suggestion: Option<String>) {
-        self.errors.push((location, message.clone(), suggestion.clone()));
-        if let Some(ref reporter) = self.error_reporter {
-            reporter.report_error(location, &message, suggestion.as_deref());
-        }
+        self.report(location, message, suggestion, ReportOptions::error());
+    }
+    
+    /// Report an error with a code from error_codes.rs (explained by `heidic_v2 explain <code>`)
+    fn report_coded_error(&mut self, code: &'static str, location: SourceLocation, message: String, suggestion: Option<String>) {
+        self.report(location, message, suggestion, ReportOptions::coded(code));
+    }
+    
+    fn report_warning(&mut self, code: &'static str, location: SourceLocation, message: String, suggestion: Option<String>) {
+        self.report(location, message, suggestion, ReportOptions::warning(code));
     }
     
-    fn report_error_with_secondary(
-        &mut self, 
-        location: SourceLocation, 
-        message: String, 
-        suggestion: Option<String>,
-        secondary_location: Option<SourceLocation>,
-        secondary_label: Option<&str>,
-    ) {
-        self.errors.push((location, message.clone(), suggestion.clone()));
+    /// Print a report and keep it as an error or warning, for tools that present them themselves
+    fn report(&mut self, location: SourceLocation, message: String, suggestion: Option<String>, options: ReportOptions) {
         if let Some(ref reporter) = self.error_reporter {
-            reporter.report_error_with_secondary(
-                location, 
-                &message, 
-                suggestion.as_deref(),
-                secondary_location,
-                secondary_label,
-            );
+            reporter.report(location, &message, suggestion.as_deref(), options);
+        }
+        let diagnostic = Diagnostic { location, message, suggestion, code: options.code };
+        match options.severity {
+            Severity::Error => self.errors.push(diagnostic),
+            Severity::Warning => self.warnings.push(diagnostic),
         }
     }
     
     pub fn check(&mut self, program: &Program) -> Result<()> {
         // Clear any previous errors
         self.errors.clear();
+        self.warnings.clear();
+        self.declarations.clear();
         
         // First pass: collect all definitions
+        let mut gpu_block_location: Option<SourceLocation> = None;
+        let mut input_block_location: Option<SourceLocation> = None;
+        let mut window_block_location: Option<SourceLocation> = None;
+        let mut test_locations: HashMap<&str, SourceLocation> = HashMap::new();
         for item in &program.items {
             match item {
                 Item::Struct(s) => {
                     self.structs.insert(s.name.clone(), s.clone());
                 }
+                Item::SaveSchema(_) => {
+                    // Registered with the schema's other versions below
+                }
+                Item::Scene(_) | Item::Migrate(_) => {
+                    // Checked against the components in the second pass
+                }
+                Item::Uniform(u) => {
+                    // Uniform blocks are filled as structs and copied to their buffer with update_<name>(value)
+                    self.structs.insert(u.name.clone(), StructDef { name: u.name.clone(), fields: u.fields.clone(), is_export: false });
+                    let update = FunctionDef {
+                        name: u.update_function(),
+                        params: vec![Param { name: "value".to_string(), ty: Type::Struct(u.name.clone()), default: None, mutable: false, location: SourceLocation::unknown() }],
+                        return_type: Type::Void,
+                        body: Vec::new(),
+                        cuda_kernel: None,
+                        no_reload_check: false,
+                        is_hot: false,
+                        strict_fp: false,
+                    };
+                    self.functions.insert(update.name.clone(), update);
+                }
+                Item::Input(input) => {
+                    if let Some(first) = input_block_location {
+                        self.report(
+                            input.location,
+                            "Duplicate input block: a program's actions are declared in one place".to_string(),
+                            Some("Move these actions into the first input { ... } block".to_string()),
+                            ReportOptions::error().secondary(first, "first input block here"),
+                        );
+                    } else {
+                        input_block_location = Some(input.location);
+                        self.register_input_accessors(input);
+                    }
+                }
+                Item::Window(window) => {
+                    if let Some(first) = window_block_location {
+                        self.report(
+                            window.location,
+                            "Duplicate window block: a program opens one window".to_string(),
+                            Some("Merge the options into a single window { ... } block".to_string()),
+                            ReportOptions::error().secondary(first, "first window block here"),
+                        );
+                    } else {
+                        window_block_location = Some(window.location);
+                        self.register_window_functions();
+                    }
+                }
+                Item::Event(e) => {
+                    // Events are plain structs that travel through a queue
+                    self.structs.insert(e.name.clone(), StructDef { name: e.name.clone(), fields: e.fields.clone(), is_export: false });
+                    self.events.insert(e.name.clone(), e.location);
+                }
                 Item::Component(c) => {
                     // Validate SOA components: all fields must be arrays
                     if c.is_soa {
                         for field in &c.fields {
                             if !matches!(field.ty, Type::Array(_)) {
                                 let location = SourceLocation::unknown(); // TODO: get from AST
-                                self.report_error(
+                                self.report_coded_error(
+                                    "E0008",
                                     location,
                                     format!("SOA component '{}' field '{}' must be an array type (use [Type] instead of Type)", 
                                             c.name, field.name),
@@ -142,6 +344,11 @@ impl TypeChecker {
                 }
                 Item::Function(f) => {
                     self.functions.insert(f.name.clone(), f.clone());
+                    if f.is_hot {
+                        self.has_hot_systems = true;
+                    } else {
+                        self.host_functions.insert(f.name.clone());
+                    }
                 }
                 Item::ExternFunction(ext) => {
                     // Create a function def from extern for type checking
@@ -151,17 +358,26 @@ impl TypeChecker {
                         return_type: ext.return_type.clone(),
                         body: Vec::new(), // Extern functions have no body
                         cuda_kernel: None,
+                        no_reload_check: false,
+                        is_hot: false,
+                        strict_fp: false,
                     };
                     self.functions.insert(ext.name.clone(), func_def);
+                    self.extern_functions.insert(ext.name.clone());
                 }
                 Item::System(s) => {
                     for func in &s.functions {
                         self.functions.insert(func.name.clone(), func.clone());
+                        self.host_functions.insert(func.name.clone());
                     }
+                    self.has_hot_systems |= s.is_hot;
                 }
                 Item::Shader(shader) => {
                     // Validate that shader stage matches file extension
                     self.validate_shader_stage(shader)?;
+                    if let Some(inline) = &shader.inline {
+                        self.declare_inline_shader(inline, &shader.stage);
+                    }
                 }
                 Item::Resource(res) => {
                     // Resources don't need type checking - they're just declarations
@@ -174,6 +390,9 @@ impl TypeChecker {
                         return_type: Type::I32, // Return pointer as i32 (opaque handle)
                         body: Vec::new(), // Generated function, no body
                         cuda_kernel: None,
+                        no_reload_check: false,
+                        is_hot: false,
+                        strict_fp: false,
                     };
                     self.functions.insert(accessor_name, func_def);
                     
@@ -186,6 +405,9 @@ impl TypeChecker {
                             return_type: Type::I32, // Returns 1 on success, 0 on failure
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(play_func_name, play_func);
                         
@@ -196,8 +418,62 @@ impl TypeChecker {
                             return_type: Type::Void,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(stop_func_name, stop_func);
+                        
+                        // play_resource_NAME_at(entity) / play_resource_NAME_looping_at(entity) for @[spatial] sounds
+                        if res.is_spatial {
+                            self.spatial_plays.extend(spatial_audio::play_at_functions(res).into_iter().map(|(name, _)| name));
+                        }
+                        
+                        // play_resource_NAME_looping() -> i32 (plays until stopped)
+                        let looping_func = FunctionDef {
+                            name: format!("play_resource_{}_looping", res.name.to_lowercase()),
+                            params: Vec::new(),
+                            return_type: Type::I32,
+                            body: Vec::new(),
+                            cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
+                        };
+                        self.functions.insert(looping_func.name.clone(), looping_func);
+                        
+                        // pause_resource_NAME() / resume_resource_NAME() -> void
+                        for action in ["pause", "resume"] {
+                            let func = FunctionDef {
+                                name: format!("{}_resource_{}", action, res.name.to_lowercase()),
+                                params: Vec::new(),
+                                return_type: Type::Void,
+                                body: Vec::new(),
+                                cuda_kernel: None,
+                                no_reload_check: false,
+                                is_hot: false,
+                                strict_fp: false,
+                            };
+                            self.functions.insert(func.name.clone(), func);
+                        }
+                        
+                        // set_volume_resource_NAME(volume: f32) -> void, and the mixer's
+                        // set_master_volume / set_sound_volume / set_music_volume(volume: f32)
+                        let volume_setters = [format!("set_volume_resource_{}", res.name.to_lowercase()),
+                                              "set_master_volume".to_string(), "set_sound_volume".to_string(), "set_music_volume".to_string()];
+                        for name in volume_setters {
+                            let func = FunctionDef {
+                                name: name.clone(),
+                                params: vec![Param { name: "volume".to_string(), ty: Type::F32, default: None, mutable: false, location: SourceLocation::unknown() }],
+                                return_type: Type::Void,
+                                body: Vec::new(),
+                                cuda_kernel: None,
+                                no_reload_check: false,
+                                is_hot: false,
+                                strict_fp: false,
+                            };
+                            self.functions.insert(name, func);
+                        }
                     }
                     
                     // Register helper functions for video resources
@@ -207,10 +483,13 @@ impl TypeChecker {
                         // play_video_NAME(loop: i32) -> i32
                         let play_func = FunctionDef {
                             name: format!("play_video_{}", name_lower),
-                            params: vec![Param { name: "loop".to_string(), ty: Type::I32 }],
+                            params: vec![Param { name: "loop".to_string(), ty: Type::I32, default: None, mutable: false, location: SourceLocation::unknown() }],
                             return_type: Type::I32,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(play_func.name.clone(), play_func);
                         
@@ -221,6 +500,9 @@ impl TypeChecker {
                             return_type: Type::Void,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(pause_func.name.clone(), pause_func);
                         
@@ -231,16 +513,22 @@ impl TypeChecker {
                             return_type: Type::Void,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(stop_func.name.clone(), stop_func);
                         
                         // seek_video_NAME(seconds: f64) -> void
                         let seek_func = FunctionDef {
                             name: format!("seek_video_{}", name_lower),
-                            params: vec![Param { name: "seconds".to_string(), ty: Type::F64 }],
+                            params: vec![Param { name: "seconds".to_string(), ty: Type::F64, default: None, mutable: false, location: SourceLocation::unknown() }],
                             return_type: Type::Void,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(seek_func.name.clone(), seek_func);
                         
@@ -251,6 +539,9 @@ impl TypeChecker {
                             return_type: Type::I32,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(update_func.name.clone(), update_func);
                         
@@ -261,6 +552,9 @@ impl TypeChecker {
                             return_type: Type::I32,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(get_frame_func.name.clone(), get_frame_func);
                         
@@ -271,6 +565,9 @@ impl TypeChecker {
                             return_type: Type::I32,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(get_width_func.name.clone(), get_width_func);
                         
@@ -281,6 +578,9 @@ impl TypeChecker {
                             return_type: Type::I32,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(get_height_func.name.clone(), get_height_func);
                         
@@ -291,6 +591,9 @@ impl TypeChecker {
                             return_type: Type::F64,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(get_duration_func.name.clone(), get_duration_func);
                         
@@ -301,6 +604,9 @@ impl TypeChecker {
                             return_type: Type::F64,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(get_time_func.name.clone(), get_time_func);
                         
@@ -311,93 +617,2587 @@ impl TypeChecker {
                             return_type: Type::I32,
                             body: Vec::new(),
                             cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
                         };
                         self.functions.insert(is_playing_func.name.clone(), is_playing_func);
                     }
                 }
-                Item::Pipeline(_) => {
+                Item::Pipeline(pipeline) => {
                     // Pipelines don't need type checking - they're just declarations
                     // Validation happens at codegen time (shader paths, binding types, etc.)
+                    // push_constants { ... } is filled as a struct and pushed with push_constants_<pipeline>(cmd, data)
+                    if let Some(block) = pipeline.push_constants() {
+                        let name = pipeline.push_constants_struct();
+                        self.structs.insert(name.clone(), StructDef { name: name.clone(), fields: block.fields.clone(), is_export: false });
+                        let param = |name: &str, ty: Type| Param { name: name.to_string(), ty, default: None, mutable: false, location: SourceLocation::unknown() };
+                        let push = FunctionDef {
+                            name: pipeline.push_constants_function(),
+                            params: vec![param("cmd", Type::VkCommandBuffer), param("data", Type::Struct(name))],
+                            return_type: Type::Void,
+                            body: Vec::new(),
+                            cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
+                        };
+                        self.functions.insert(push.name.clone(), push);
+                    }
+                    // Bindings get create_descriptor_sets_<pipeline>() and update_binding_<pipeline>(binding, buffer)
+                    if !pipeline.bindings().is_empty() {
+                        let param = |name: &str, ty: Type| Param { name: name.to_string(), ty, default: None, mutable: false, location: SourceLocation::unknown() };
+                        let helper = |name: String, params: Vec<Param>| FunctionDef {
+                            name,
+                            params,
+                            return_type: Type::Void,
+                            body: Vec::new(),
+                            cuda_kernel: None,
+                            no_reload_check: false,
+                            is_hot: false,
+                            strict_fp: false,
+                        };
+                        let create = helper(pipeline.descriptor_sets_function(), Vec::new());
+                        let update = helper(pipeline.update_binding_function(), vec![param("binding", Type::I32), param("buffer", Type::VkBuffer)]);
+                        self.functions.insert(create.name.clone(), create);
+                        self.functions.insert(update.name.clone(), update);
+                        let buffers = pipeline.bindings().iter()
+                            .filter(|binding| binding.binding_type != BindingType::Sampler2D)
+                            .map(|binding| binding.binding)
+                            .collect();
+                        self.buffer_bindings.insert(pipeline.update_binding_function(), (pipeline.name.clone(), buffers));
+                    }
+                    // Named storage bindings become GPU buffers shared by every pipeline using the name
+                    for binding in pipeline.layout.iter().flat_map(|layout| layout.bindings.iter()) {
+                        let BindingType::Storage(element) = &binding.binding_type else { continue };
+                        if binding.name.is_empty() {
+                            continue;
+                        }
+                        match self.gpu_buffers.get(&binding.name).cloned() {
+                            Some((existing, first)) if existing != *element => {
+                                self.report(
+                                    binding.location,
+                                    format!("Storage buffer '{}' is declared as '{}[]' here but '{}[]' elsewhere", binding.name, element, existing),
+                                    Some("Bindings with the same name share one GPU buffer; use one element type or rename one".to_string()),
+                                    ReportOptions::error().secondary(first, "first declared here"),
+                                );
+                            }
+                            Some(_) => {}
+                            None => {
+                                self.gpu_buffers.insert(binding.name.clone(), (element.clone(), binding.location));
+                            }
+                        }
+                    }
+                }
+                Item::Gpu(gpu) => {
+                    if let Some(first) = gpu_block_location {
+                        self.report(
+                            gpu.location,
+                            "Duplicate gpu block: device selection can only be configured once".to_string(),
+                            Some("Merge the options into a single gpu { ... } block".to_string()),
+                            ReportOptions::error().secondary(first, "first gpu block here"),
+                        );
+                    } else {
+                        gpu_block_location = Some(gpu.location);
+                    }
+                    self.check_gpu_features(gpu);
+                }
+                Item::Interface(iface) => {
+                    if let Some(existing) = self.interfaces.get(&iface.name) {
+                        let first = existing.location;
+                        self.report(
+                            iface.location,
+                            format!("Duplicate interface '{}'", iface.name),
+                            Some("Rename one of the interfaces or merge their methods".to_string()),
+                            ReportOptions::error().secondary(first, "first declared here"),
+                        );
+                    } else {
+                        self.interfaces.insert(iface.name.clone(), iface.clone());
+                    }
+                }
+                Item::Impl(_) => {
+                    // Validated after all interfaces, structs and components are known
+                }
+                Item::GpuQuery(query) => {
+                    if let Some(existing) = self.gpu_queries.get(&query.name) {
+                        let first = existing.location;
+                        self.report(
+                            query.location,
+                            format!("Duplicate gpu_query '{}'", query.name),
+                            Some(format!("Give the pool its own name: gpu_query <Kind> {}2;", query.name)),
+                            ReportOptions::error().secondary(first, "first declared here"),
+                        );
+                    } else {
+                        self.gpu_queries.insert(query.name.clone(), query.clone());
+                    }
+                    if let GpuQueryKind::PipelineStatistics(statistic, location) = &query.kind {
+                        if !PIPELINE_STATISTICS.iter().any(|(name, _)| name == statistic) {
+                            let known: Vec<String> = PIPELINE_STATISTICS.iter().map(|(name, _)| name.to_string()).collect();
+                            let suggestion = if let Some(closest) = find_closest_match(statistic, &known, 3) {
+                                format!("Did you mean '{}'?", closest)
+                            } else {
+                                format!("Valid statistics: {}", known.join(", "))
+                            };
+                            self.report_error(
+                                *location,
+                                format!("Unknown pipeline statistic '{}'", statistic),
+                                Some(suggestion),
+                            );
+                        }
+                    }
+                }
+                Item::Const(c) => self.check_const(c),
+                Item::StaticAssert(_) => {
+                    // Checked after every const is known
+                }
+                Item::Test(test) => {
+                    if let Some(first) = test_locations.get(test.name.as_str()) {
+                        self.report(
+                            test.location,
+                            format!("Duplicate test \"{}\"", test.name),
+                            Some("Test names identify results and must be unique; rename one".to_string()),
+                            ReportOptions::error().secondary(*first, "first declared here"),
+                        );
+                    } else {
+                        test_locations.insert(&test.name, test.location);
+                    }
+                }
+            }
+        }
+        
+        // Built-in resources (res<Time>, res<Input>) are structs unless the program declares its own
+        for name in resources::resource_types(program) {
+            if let Some(builtin) = resources::builtin(&name).filter(|_| !resources::is_declared(program, &name)) {
+                self.structs.insert(name, builtin);
+            }
+        }
+        
+        // Built-in hierarchy components (with<Parent>, without<Parent>) unless the program declares its own
+        for name in hierarchy::BUILTINS {
+            match hierarchy::builtin(name).filter(|_| !resources::is_declared(program, name)) {
+                Some(component) => {
+                    self.components.insert(name.to_string(), component);
+                }
+                None => self.hierarchy_shadowed = Some(name.to_string()),
+            }
+        }
+        self.check_spatial_audio(program);
+        
+        self.register_save_schemas(program);
+        
+        // Interface methods become callable functions; impl blocks are validated against them
+        for item in &program.items {
+            if let Item::Interface(iface) = item {
+                self.register_interface_methods(iface);
+            }
+        }
+        for item in &program.items {
+            if let Item::Impl(imp) = item {
+                self.check_impl(imp);
+            }
+        }
+        self.check_schedule(program);
+        self.check_exports(program);
+        self.check_replicated(program);
+        self.check_migrations(program);
+        self.check_pools(program);
+        self.check_cuda(program);
+        self.check_generated_names(program);
+        
+        // Second pass: type check
+        for (i, item) in program.items.iter().enumerate() {
+            match item {
+                Item::Function(f) if f.is_hot => {
+                    let location = self.item_locations.get(i).copied().unwrap_or_else(SourceLocation::unknown);
+                    self.check_hot_free_function(f, location);
+                    self.hot_function = Some(f.name.clone());
+                    let result = self.check_function(f);
+                    self.hot_function = None;
+                    result?;
+                }
+                Item::Function(f) => {
+                    self.check_function(f)?;
+                }
+                Item::System(s) => {
+                    self.hot_system = s.is_hot.then(|| s.name.clone());
+                    for func in &s.functions {
+                        if s.is_hot {
+                            self.check_hot_function_params(Some(&s.name), func);
+                        }
+                        self.check_function(func)?;
+                    }
+                    self.hot_system = None;
+                    let mut handled: HashMap<&str, SourceLocation> = HashMap::new();
+                    for handler in &s.handlers {
+                        if let Some(first) = handled.get(handler.event.as_str()) {
+                            self.report(
+                                handler.location,
+                                format!("System '{}' handles '{}' twice", s.name, handler.event),
+                                Some("Each event is delivered once per system; merge the handlers".to_string()),
+                                ReportOptions::error().secondary(*first, "first handler here"),
+                            );
+                            continue;
+                        }
+                        handled.insert(&handler.event, handler.location);
+                        self.check_event_handler(&s.name, handler)?;
+                    }
+                }
+                Item::Interface(iface) => {
+                    for method in &iface.methods {
+                        self.reset_scopes();
+                        self.check_param_defaults(&method.name, &method.params);
+                    }
+                }
+                Item::Impl(imp) => {
+                    for (method, _) in &imp.methods {
+                        self.check_function(method)?;
+                    }
+                }
+                Item::ExternFunction(ext) => {
+                    self.reset_scopes();
+                    self.check_param_defaults(&ext.name, &ext.params);
+                }
+                Item::Resource(_) => {
+                    // Resources don't need type checking in second pass
+                }
+                Item::Pipeline(pipeline) => {
+                    // Inline shaders are all declared by now, wherever they appear
+                    for shader in &pipeline.shaders {
+                        if let Some(name) = &shader.inline {
+                            self.check_inline_shader_use(name, shader);
+                        }
+                    }
+                    if let Some(input) = &pipeline.vertex_input {
+                        self.check_vertex_input(input);
+                    }
+                    if let Some(block) = pipeline.push_constants() {
+                        self.check_push_constants(pipeline, block);
+                    }
+                }
+                Item::Uniform(uniform) => self.check_uniform(uniform),
+                Item::Input(input) => self.check_input(input),
+                Item::Test(test) => {
+                    self.check_test(test)?;
+                }
+                Item::StaticAssert(assert) => self.check_static_assert(assert),
+                Item::SaveSchema(schema) => self.check_schema_migration(program, schema)?,
+                Item::Scene(scene) => self.check_scene(scene),
+                Item::Migrate(migrate) => self.check_function(&migrate.function)?,
+                _ => {}
+            }
+        }
+        
+        // Report all errors if any
+        if !self.errors.is_empty() {
+            // Errors have already been printed by ErrorReporter, but we can add a summary
+            // (callers without a reporter, like the LSP and `check --error-format=json`, read errors())
+            if self.error_reporter.is_some() {
+                eprintln!("\n❌ Compilation failed with {} error(s):\n", self.errors.len());
+            }
+            bail!("Compilation failed with {} error(s). See errors above.", self.errors.len());
+        }
+        
+        Ok(())
+    }
+    
+    fn register_interface_methods(&mut self, iface: &InterfaceDef) {
+        let mut seen: HashMap<&str, SourceLocation> = HashMap::new();
+        for method in &iface.methods {
+            if let Some(first) = seen.get(method.name.as_str()) {
+                self.report(
+                    method.location,
+                    format!("Duplicate method '{}' in interface '{}'", method.name, iface.name),
+                    Some("Each interface method must have a unique name".to_string()),
+                    ReportOptions::error().secondary(*first, "first declared here"),
+                );
+                continue;
+            }
+            seen.insert(&method.name, method.location);
+            
+            // Calls are resolved by name, so a method can't share a name with a function
+            if self.functions.contains_key(&method.name) {
+                self.report_error(
+                    method.location,
+                    format!("Interface method '{}' conflicts with an existing function of the same name", method.name),
+                    Some(format!("Rename the method or the function (e.g. '{}_{}')", iface.name.to_lowercase(), method.name)),
+                );
+                continue;
+            }
+            self.functions.insert(method.name.clone(), FunctionDef {
+                name: method.name.clone(),
+                params: method.params.clone(),
+                return_type: method.return_type.clone(),
+                body: Vec::new(),  // Dispatched to the impl for the receiver's type
+                cuda_kernel: None,
+                no_reload_check: false,
+                is_hot: false,
+                strict_fp: false,
+            });
+        }
+    }
+    
+    fn check_impl(&mut self, imp: &ImplDef) {
+        let iface = match self.interfaces.get(&imp.interface) {
+            Some(iface) => iface.clone(),
+            None => {
+                let candidates: Vec<String> = self.interfaces.keys().cloned().collect();
+                let suggestion = match find_closest_match(&imp.interface, &candidates, 3) {
+                    Some(closest) => format!("Did you mean '{}'?", closest),
+                    None => format!("Declare it first: interface {} {{ ... }}", imp.interface),
+                };
+                self.report_error(imp.location, format!("Unknown interface '{}'", imp.interface), Some(suggestion));
+                return;
+            }
+        };
+        
+        if !self.components.contains_key(&imp.target) && !self.structs.contains_key(&imp.target) {
+            let candidates: Vec<String> = self.components.keys().chain(self.structs.keys()).cloned().collect();
+            let suggestion = match find_closest_match(&imp.target, &candidates, 3) {
+                Some(closest) => format!("Did you mean '{}'?", closest),
+                None => "Interfaces can only be implemented for components and structs".to_string(),
+            };
+            self.report_error(
+                imp.location,
+                format!("Cannot implement '{}' for unknown type '{}'", imp.interface, imp.target),
+                Some(suggestion),
+            );
+            return;
+        }
+        
+        let key = (imp.interface.clone(), imp.target.clone());
+        if let Some(first) = self.impls.get(&key).copied() {
+            self.report(
+                imp.location,
+                format!("Duplicate implementation of '{}' for '{}'", imp.interface, imp.target),
+                Some("Remove one of the impl blocks".to_string()),
+                ReportOptions::error().secondary(first, "first implemented here"),
+            );
+            return;
+        }
+        self.impls.insert(key, imp.location);
+        
+        for (method, location) in &imp.methods {
+            let Some(declared) = iface.methods.iter().find(|m| m.name == method.name) else {
+                let candidates: Vec<String> = iface.methods.iter().map(|m| m.name.clone()).collect();
+                let suggestion = match find_closest_match(&method.name, &candidates, 3) {
+                    Some(closest) => format!("Did you mean '{}'?", closest),
+                    None => format!("Add it to the interface: fn {}(self, ...)", method.name),
+                };
+                self.report_coded_error(
+                    "E0014",
+                    *location,
+                    format!("Method '{}' is not a member of interface '{}'", method.name, iface.name),
+                    Some(suggestion),
+                );
+                continue;
+            };
+            
+            // Signatures must match exactly, apart from the receiver's type
+            let matches = declared.params.len() == method.params.len()
+                && declared.params.iter().zip(method.params.iter()).skip(1)
+                    .all(|(a, b)| self.types_identical(&a.ty, &b.ty))
+                && self.types_identical(&declared.return_type, &method.return_type);
+            if !matches {
+                let expected = self.method_signature_to_string(&declared.name, &declared.params, &declared.return_type);
+                let found = self.method_signature_to_string(&method.name, &method.params, &method.return_type);
+                self.report(
+                    *location,
+                    format!("Method '{}' does not match its declaration in interface '{}': expected '{}', found '{}'",
+                           method.name, iface.name, expected, found),
+                    Some(format!("Change the signature to: {}", expected)),
+                    ReportOptions::error().secondary(declared.location, "declared here"),
+                );
+            }
+        }
+        
+        let missing: Vec<String> = iface.methods.iter()
+            .filter(|m| !imp.methods.iter().any(|(f, _)| f.name == m.name))
+            .map(|m| self.method_signature_to_string(&m.name, &m.params, &m.return_type))
+            .collect();
+        if !missing.is_empty() {
+            self.report_coded_error(
+                "E0014",
+                imp.location,
+                format!("Implementation of '{}' for '{}' is missing {} method(s)", imp.interface, imp.target, missing.len()),
+                Some(format!("Add: {}", missing.join("; "))),
+            );
+        }
+    }
+    
+    fn method_signature_to_string(&self, name: &str, params: &[Param], return_type: &Type) -> String {
+        let mut parts = vec!["self".to_string()];
+        for param in params.iter().skip(1) {
+            parts.push(format!("{}: {}", param.name, self.type_to_string(&param.ty)));
+        }
+        format!("fn {}({}): {}", name, parts.join(", "), self.type_to_string(return_type))
+    }
+    
+    // A top-level @hot fn is loaded from the hot functions DLL, which the program only calls into
+    // once it's running
+    fn check_hot_free_function(&mut self, f: &FunctionDef, location: SourceLocation) {
+        if f.name == "main" {
+            self.report_error(
+                location,
+                "main can't be @hot: it runs before the hot functions DLL is loaded".to_string(),
+                Some("Move the code to reload into a @hot fn that main calls".to_string()),
+            );
+        }
+        if f.cuda_kernel.is_some() {
+            self.report_error(
+                location,
+                format!("@hot fn '{}' can't launch a CUDA kernel", f.name),
+                Some("Remove @hot, or launch the kernel from a function that isn't @hot".to_string()),
+            );
+        }
+        if let Some(default) = f.params.iter().find(|param| param.default.is_some()) {
+            self.report_error(
+                location,
+                format!("@hot fn '{}' can't give parameter '{}' a default value", f.name, default.name),
+                Some("Calls go through a function pointer, which has no default arguments; pass the value at each call".to_string()),
+            );
+        }
+        self.check_hot_function_params(None, f);
+    }
+
+    fn check_hot_function_params(&mut self, system: Option<&str>, func: &FunctionDef) {
+        // Hot system functions are exported through a C ABI, which can't carry generic parameters
+        for param in &func.params {
+            if let Type::Struct(name) = &param.ty {
+                if self.interfaces.contains_key(name) {
+                    self.report_coded_error(
+                        "E0013",
+                        SourceLocation::unknown(),
+                        match system {
+                            Some(system) => format!("Hot system '{}' function '{}' cannot take interface parameter '{}: {}'",
+                                                    system, func.name, param.name, name),
+                            None => format!("@hot fn '{}' cannot take interface parameter '{}: {}'", func.name, param.name, name),
+                        },
+                        Some(match system {
+                            Some(_) => "Take the concrete component type instead, or move the function out of the @hot system".to_string(),
+                            None => "Take the concrete component type instead, or remove @hot".to_string(),
+                        }),
+                    );
+                }
+            }
+        }
+    }
+    
+    // input.attach(window), input.update(), and each action's accessors: input.jump(),
+    // input.jump_pressed() and input.jump_released() for a button, input.move_x() for an axis
+    // open_window(): GLFWwindow and close_window(window); a program's own functions of those names win
+    fn register_window_functions(&mut self) {
+        let window = Param { name: "window".to_string(), ty: Type::GLFWwindow, default: None, mutable: false, location: SourceLocation::unknown() };
+        let [(open, _), (close, _)] = WindowConfig::FUNCTIONS;
+        for (name, params, return_type) in [(open, Vec::new(), Type::GLFWwindow), (close, vec![window], Type::Void)] {
+            let function = FunctionDef {
+                name: name.to_string(),
+                params,
+                return_type,
+                body: Vec::new(),
+                cuda_kernel: None,
+                no_reload_check: false,
+                is_hot: false,
+                strict_fp: false,
+            };
+            self.functions.entry(function.name.clone()).or_insert(function);
+        }
+    }
+    
+    fn register_input_accessors(&mut self, input: &InputDef) {
+        let function = |name: String, params: Vec<Param>, return_type: Type| FunctionDef {
+            name: format!("{}.{}", input_map::OBJECT, name),
+            params,
+            return_type,
+            body: Vec::new(),
+            cuda_kernel: None,
+            no_reload_check: false,
+            is_hot: false,
+            strict_fp: false,
+        };
+        let window = Param { name: "window".to_string(), ty: Type::GLFWwindow, default: None, mutable: false, location: SourceLocation::unknown() };
+        let mut accessors = vec![
+            function("attach".to_string(), vec![window], Type::Void),
+            function("update".to_string(), Vec::new(), Type::Void),
+        ];
+        for action in &input.actions {
+            let ty = if action.is_axis() { Type::F32 } else { Type::Bool };
+            accessors.extend(input_map::accessors(action).into_iter().map(|accessor| function(accessor, Vec::new(), ty.clone())));
+        }
+        for accessor in accessors {
+            self.functions.entry(accessor.name.clone()).or_insert(accessor);
+        }
+    }
+    
+    fn check_input(&mut self, input: &InputDef) {
+        // Accessor name -> the action it belongs to
+        let mut taken: HashMap<String, &InputAction> = HashMap::new();
+        for action in &input.actions {
+            if input_map::METHODS.contains(&action.name.as_str()) {
+                self.report_error(
+                    action.location,
+                    format!("Input action '{}' has the name of input.{}()", action.name, action.name),
+                    Some(format!("Rename the action, e.g. {}_action", action.name)),
+                );
+                continue;
+            }
+            if let Some(first) = taken.get(&action.name).filter(|first| first.name == action.name) {
+                self.report(
+                    action.location,
+                    format!("Duplicate input action '{}'", action.name),
+                    Some("List every binding of an action in one place: jump: Key.Space | Gamepad.A;".to_string()),
+                    ReportOptions::error().secondary(first.location, "first declared here"),
+                );
+                continue;
+            }
+            for accessor in input_map::accessors(action) {
+                if let Some(other) = taken.get(&accessor) {
+                    self.report_error(
+                        action.location,
+                        format!("Input actions '{}' and '{}' both have an accessor input.{}()", other.name, action.name, accessor),
+                        Some(format!("Rename '{}'", action.name)),
+                    );
+                    break;
+                }
+                taken.insert(accessor, action);
+            }
+            
+            let is_axis = action.is_axis();
+            for binding in &action.bindings {
+                match binding {
+                    InputBinding::Source(source) => {
+                        self.check_input_source(source);
+                        if is_axis && !source.is_gamepad_axis() && input_map::code(source).is_some() {
+                            self.report_error(
+                                source.location,
+                                format!("Input action '{}' is an axis, but {} is a button", action.name, source),
+                                Some(format!("Pair buttons up as an axis: axis({}, ...)", source)),
+                            );
+                        }
+                    }
+                    InputBinding::Axis(negative, positive) => {
+                        for source in [negative, positive] {
+                            self.check_input_source(source);
+                            if source.is_gamepad_axis() {
+                                self.report_error(
+                                    source.location,
+                                    format!("axis() takes two buttons, but {} is already an axis", source),
+                                    Some(format!("Bind it on its own: {}: {} | axis(...);", action.name, source)),
+                                );
+                            }
+                        }
+                    }
                 }
             }
         }
-        
-        // Second pass: type check
-        for item in &program.items {
-            match item {
-                Item::Function(f) => {
-                    self.check_function(f)?;
-                }
-                Item::System(s) => {
-                    for func in &s.functions {
-                        self.check_function(func)?;
+    }
+    
+    fn check_input_source(&mut self, source: &InputSource) {
+        let Some(names) = input_map::control_names(&source.device) else {
+            let known: Vec<String> = input_map::DEVICES.iter().map(|d| d.to_string()).collect();
+            let suggestion = match find_closest_match(&source.device, &known, 3) {
+                Some(closest) => format!("Did you mean '{}.{}'?", closest, source.name),
+                None => "Bind a Key, Mouse or Gamepad control, e.g. Key.Space, Mouse.Left or Gamepad.A".to_string(),
+            };
+            self.report_error(source.location, format!("Unknown input device '{}'", source.device), Some(suggestion));
+            return;
+        };
+        if input_map::code(source).is_none() {
+            let known: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+            let suggestion = match find_closest_match(&source.name, &known, 3) {
+                Some(closest) => format!("Did you mean '{}.{}'?", source.device, closest),
+                None => format!("{} controls are: {}", source.device, names.join(", ")),
+            };
+            self.report_error(source.location, format!("Unknown input control '{}'", source), Some(suggestion));
+        }
+    }
+    
+    fn check_gpu_features(&mut self, gpu: &GpuConfig) {
+        let known: Vec<String> = VULKAN_DEVICE_FEATURES.iter().map(|f| f.to_string()).collect();
+        for (name, location) in gpu.required_features.iter().chain(gpu.optional_features.iter()) {
+            if !VULKAN_DEVICE_FEATURES.contains(&name.as_str()) {
+                let suggestion = if let Some(closest) = find_closest_match(name, &known, 3) {
+                    format!("Did you mean '{}'?", closest)
+                } else {
+                    "Use a VkPhysicalDeviceFeatures or VkPhysicalDeviceDescriptorIndexingFeatures member name, e.g. samplerAnisotropy".to_string()
+                };
+                self.report_error(
+                    *location,
+                    format!("Unknown GPU feature '{}'", name),
+                    Some(suggestion),
+                );
+            }
+        }
+        for (name, location) in &gpu.optional_features {
+            if gpu.required_features.iter().any(|(required, _)| required == name) {
+                self.report_error(
+                    *location,
+                    format!("GPU feature '{}' is listed as both required and optional", name),
+                    Some(format!("Remove '{}' from one of the lists", name)),
+                );
+            }
+        }
+    }
+    
+    fn check_query_intrinsic(&mut self, name: &str, args: &[Expression], location: SourceLocation, return_type: Type) -> Result<Type> {
+        if args.len() != 2 {
+            self.report_error(
+                location,
+                format!("Argument count mismatch for '{}': expected 2 arguments, got {}", name, args.len()),
+                Some(format!("Usage: {}(Occlusion, 0) where Occlusion is a gpu_query pool", name)),
+            );
+            return Ok(Type::Error);
+        }
+        
+        let mut has_error = false;
+        let pool = match &args[0] {
+            Expression::Variable(pool, _) => self.gpu_queries.get(pool).cloned().ok_or_else(|| pool.clone()),
+            _ => Err(String::new()),
+        };
+        let pool = match pool {
+            Ok(pool) => Some(pool),
+            Err(pool_name) => {
+                let known: Vec<String> = self.gpu_queries.keys().cloned().collect();
+                let suggestion = if let Some(closest) = find_closest_match(&pool_name, &known, 3) {
+                    format!("Did you mean '{}'?", closest)
+                } else if known.is_empty() {
+                    "Declare a query pool first, e.g. gpu_query Occlusion;".to_string()
+                } else {
+                    let mut sorted = known;
+                    sorted.sort();
+                    format!("Declared gpu_query pools: {}", sorted.join(", "))
+                };
+                let message = if pool_name.is_empty() {
+                    format!("First argument of '{}' must name a gpu_query pool", name)
+                } else {
+                    format!("Unknown gpu_query pool '{}'", pool_name)
+                };
+                self.report_error(args[0].location(), message, Some(suggestion));
+                has_error = true;
+                None
+            }
+        };
+        
+        let index_type = self.check_expression(&args[1])?;
+        if !matches!(index_type, Type::I32 | Type::I64 | Type::Error) {
+            self.report_error(
+                args[1].location(),
+                format!("Query index for '{}' must be an integer, got '{}'", name, self.type_to_string(&index_type)),
+                Some("Pass the query slot index, e.g. 0".to_string()),
+            );
+            has_error = true;
+        }
+        if let (Some(pool), Expression::Literal(Literal::Int(index, _), index_location)) = (&pool, &args[1]) {
+            if *index < 0 || *index >= pool.count as i64 {
+                self.report_error(
+                    *index_location,
+                    format!("Query index {} out of range for gpu_query '{}' with {} slots", index, pool.name, pool.count),
+                    Some(format!("Use an index from 0 to {}, or declare more slots: gpu_query ... {}[{}];", pool.count - 1, pool.name, index + 1)),
+                );
+                has_error = true;
+            }
+        }
+        
+        Ok(if has_error { Type::Error } else { return_type })
+    }
+    
+    fn check_buffer_intrinsic(&mut self, name: &str, args: &[Expression], location: SourceLocation) -> Result<Type> {
+        let usage = if name == "readback" {
+            "Usage: let data: [Particle] = readback(particles); where particles is a named storage binding"
+        } else {
+            "Usage: upload(particles, data); where particles is a named storage binding and data is a [Particle]"
+        };
+        let expected_args = if name == "readback" { 1 } else { 2 };
+        if args.len() != expected_args {
+            self.report_error(
+                location,
+                format!("Argument count mismatch for '{}': expected {} arguments, got {}", name, expected_args, args.len()),
+                Some(usage.to_string()),
+            );
+            return Ok(Type::Error);
+        }
+        
+        let buffer = match &args[0] {
+            Expression::Variable(buffer, _) => buffer.clone(),
+            _ => String::new(),
+        };
+        let Some((element, _)) = self.gpu_buffers.get(&buffer).cloned() else {
+            let known: Vec<String> = self.gpu_buffers.keys().cloned().collect();
+            let suggestion = if let Some(closest) = find_closest_match(&buffer, &known, 3) {
+                format!("Did you mean '{}'?", closest)
+            } else if known.is_empty() {
+                "Name a storage binding in a pipeline layout, e.g. binding 0: storage Particle[] particles".to_string()
+            } else {
+                let mut sorted = known;
+                sorted.sort();
+                format!("Declared storage buffers: {}", sorted.join(", "))
+            };
+            let message = if buffer.is_empty() {
+                format!("First argument of '{}' must name a storage buffer", name)
+            } else {
+                format!("Unknown storage buffer '{}'", buffer)
+            };
+            self.report_error(args[0].location(), message, Some(suggestion));
+            for arg in &args[1..] {
+                self.check_expression(arg)?;
+            }
+            return Ok(Type::Error);
+        };
+        
+        // Elements are copied byte-for-byte, so the element type must be a plain HEIDIC struct/component
+        if !self.structs.contains_key(&element) && !self.components.contains_key(&element) {
+            self.report_error(
+                location,
+                format!("Storage buffer '{}' has element type '{}', which is not a declared struct or component", buffer, element),
+                Some(format!("Declare it to match the shader's layout: struct {} {{ ... }}", element)),
+            );
+            return Ok(Type::Error);
+        }
+        let array_type = Type::Array(Box::new(Type::Struct(element.clone())));
+        
+        if name == "readback" {
+            return Ok(array_type);
+        }
+        let data_type = self.check_expression(&args[1])?;
+        if !matches!(data_type, Type::Error) && !self.types_compatible(&array_type, &data_type) {
+            self.report_error(
+                args[1].location(),
+                format!("'upload' to '{}' expects '{}', got '{}'", buffer, self.type_to_string(&array_type), self.type_to_string(&data_type)),
+                Some(format!("Pass an array of {} values", element)),
+            );
+            return Ok(Type::Error);
+        }
+        Ok(Type::Void)
+    }
+    
+    fn check_test_intrinsic(&mut self, name: &str, args: &[Expression], location: SourceLocation, param_types: Vec<Type>, return_type: Type) -> Result<Type> {
+        let usage = match name {
+            "expect" => "Usage: expect(score == 10);",
+            "expect_frame" => "Usage: expect_frame(\"golden/title.png\", 0.01); tolerance is the mean per-channel difference (0-1)",
+            _ => "Usage: screenshot(\"frame.png\"); saves the next presented frame",
+        };
+        if name != "screenshot" && !self.in_test {
+            self.report_error(
+                location,
+                format!("'{}' can only be used inside a test block", name),
+                Some(format!("Move the check into a test: test \"name\" {{ ... {}(...); }}", name)),
+            );
+            return Ok(Type::Error);
+        }
+        if args.len() != param_types.len() {
+            self.report_coded_error(
+                "E0004",
+                location,
+                format!("Argument count mismatch for '{}': expected {} arguments, got {}", name, param_types.len(), args.len()),
+                Some(usage.to_string()),
+            );
+            return Ok(Type::Error);
+        }
+        let mut has_error = false;
+        for (arg, expected) in args.iter().zip(param_types.iter()) {
+            let arg_type = self.check_expression(arg)?;
+            if !self.types_compatible(expected, &arg_type) {
+                self.report_coded_error(
+                    "E0005",
+                    arg.location(),
+                    format!("'{}' expects a '{}', got '{}'", name, self.type_to_string(expected), self.type_to_string(&arg_type)),
+                    Some(usage.to_string()),
+                );
+                has_error = true;
+            }
+        }
+        Ok(if has_error { Type::Error } else { return_type })
+    }
+    
+    pub fn type_to_string(&self, ty: &Type) -> String {
+        match ty {
+            Type::I32 => "i32".to_string(),
+            Type::I64 => "i64".to_string(),
+            Type::F32 => "f32".to_string(),
+            Type::F64 => "f64".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::String => "string".to_string(),
+            Type::Array(elem) => format!("[{}]", self.type_to_string(elem)),
+            Type::Optional(inner) => format!("?{}", self.type_to_string(inner)),
+            Type::Struct(name) => name.clone(),
+            Type::Component(name) => name.clone(),
+            Type::Query(components) => {
+                let comp_names: Vec<String> = components.iter()
+                    .map(|c| self.type_to_string(c))
+                    .collect();
+                format!("query<{}>", comp_names.join(", "))
+            },
+            Type::QueryChunk(components) => {
+                let comp_names: Vec<String> = components.iter()
+                    .map(|c| self.type_to_string(c))
+                    .collect();
+                format!("chunk of query<{}>", comp_names.join(", "))
+            },
+            Type::QueryFilter(filter, component) => format!("{}<{}>", filter.name(), self.type_to_string(component)),
+            Type::Res(resource) => format!("res<{}>", self.type_to_string(resource)),
+            Type::Function(params, ret) => {
+                let param_names: Vec<String> = params.iter()
+                    .map(|p| self.type_to_string(p))
+                    .collect();
+                format!("fn({}): {}", param_names.join(", "), self.type_to_string(ret))
+            },
+            Type::Void => "void".to_string(),
+            Type::Error => "<error>".to_string(),
+            _ => format!("{:?}", ty),
+        }
+    }
+    
+    fn check_function(&mut self, func: &FunctionDef) -> Result<()> {
+        self.reset_scopes();
+        self.frame_scoped_vars.clear();  // Reset frame-scoped tracking for each function
+        self.function_lets.clear();
+        collect_lets(&func.body, &mut self.function_lets);
+        
+        // Default values are checked before parameters enter scope (they can't refer to each other)
+        self.check_param_defaults(&func.name, &func.params);
+        
+        // Add parameters to symbol table
+        for param in &func.params {
+            if let Type::Query(component_types) = &param.ty {
+                self.check_query_filters(component_types, param.location);
+            }
+            // A res<T> parameter is the resource itself inside the function
+            let param_type = match &param.ty {
+                Type::Res(resource) => self.check_resource_param(param, resource),
+                ty => ty.clone(),
+            };
+            self.declare(&param.name, param_type);
+            self.declare_local(&param.name, param.location, true);
+            // Method receivers are passed by reference precisely so methods can mutate them
+            if param.name != "self" {
+                self.set_mutability(&param.name, param.mutable, param.location, true);
+            }
+        }
+        
+        // Store function return type for return statement validation
+        let function_return_type = func.return_type.clone();
+        
+        // Check function body (continue even if errors occur)
+        for stmt in &func.body {
+            // Pass function return type to check_statement for return validation
+            if let Err(_) = self.check_statement_with_return_type(stmt, &function_return_type) {
+                // Continue checking other statements (error recovery)
+            }
+        }
+        
+        self.pop_scope();
+        self.check_reachability(&func.body);
+        
+        Ok(())
+    }
+    
+    // Resources are structs or components (or the built-in Time and Input), with no default value
+    fn check_resource_param(&mut self, param: &Param, resource: &Type) -> Type {
+        let name = match resource {
+            Type::Struct(name) | Type::Component(name) => name.clone(),
+            _ => String::new(),
+        };
+        if !self.structs.contains_key(&name) && !self.components.contains_key(&name) {
+            let candidates: Vec<String> = self.structs.keys().chain(self.components.keys()).cloned()
+                .chain(resources::BUILTINS.iter().map(|name| name.to_string()))
+                .collect();
+            let suggestion = match find_closest_match(&name, &candidates, 3) {
+                Some(closest) => format!("Did you mean '{}'?", closest),
+                None => "Resources are structs or components (or the built-in Time and Input): res<Time>".to_string(),
+            };
+            self.report_error(
+                param.location,
+                format!("Parameter '{}': res<{}> must name a struct or component", param.name, self.type_to_string(resource)),
+                Some(suggestion),
+            );
+            return Type::Error;
+        }
+        if let Some(default) = &param.default {
+            self.report_error(
+                default.location(),
+                format!("Parameter '{}': res<{}> can't have a default value", param.name, name),
+                Some("Callers never pass resources; the single instance is supplied automatically".to_string()),
+            );
+        }
+        resource.clone()
+    }
+    
+    // with/without/changed name components, and a query needs at least one component to iterate
+    fn check_query_filters(&mut self, component_types: &[Type], location: SourceLocation) {
+        let mut queried = Vec::new();
+        let mut excluded = Vec::new();
+        for ty in component_types {
+            match ty {
+                Type::Struct(name) | Type::Component(name) => queried.push(name.clone()),
+                Type::QueryFilter(filter, component) => {
+                    let name = self.type_to_string(component);
+                    if !self.components.contains_key(&name) {
+                        let candidates: Vec<String> = self.components.keys().cloned().collect();
+                        let suggestion = match find_closest_match(&name, &candidates, 3) {
+                            Some(closest) => format!("Did you mean '{}'?", closest),
+                            None => "Filters take a component: query<Position, without<Frozen>>".to_string(),
+                        };
+                        self.report_error(location, format!("{}<{}> in a query must name a component", filter.name(), name), Some(suggestion));
+                    } else if *filter == QueryFilter::Without {
+                        excluded.push(name);
+                    } else {
+                        queried.push(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(name) = excluded.iter().find(|name| queried.contains(name)) {
+            self.report_error(
+                location,
+                format!("Query requires '{}' and also excludes it with without<{}>, so it never matches", name, name),
+                Some(format!("Remove '{}' or without<{}> from the query", name, name)),
+            );
+        }
+        if !component_types.iter().any(|ty| matches!(ty, Type::Struct(_) | Type::Component(_))) {
+            self.report_error(
+                location,
+                "A query needs at least one component besides its filters".to_string(),
+                Some("List the component to iterate: query<Position, without<Frozen>>".to_string()),
+            );
+        }
+    }
+    
+    // A test body is checked like a fn with no parameters that returns nothing
+    // tweak_panel(): void draws the tweak editor; tweak_file(path): bool persists tweaks in `path`
+    fn check_tweak_intrinsic(&mut self, name: &str, args: &[Expression], location: SourceLocation) -> Result<Type> {
+        let (params, return_type, usage) = match name {
+            "tweak_panel" => (vec![], Type::Void, "tweak_panel(); once per frame, between ImGui_NewFrame() and ImGui_Render()"),
+            _ => (vec![Type::String], Type::Bool, "tweak_file(\"tweaks.cfg\"); returns false when the file doesn't exist yet"),
+        };
+        if self.tweaks.is_empty() {
+            self.report_error(
+                location,
+                format!("'{}' needs at least one @tweak const", name),
+                Some("Mark the consts to adjust at runtime: @tweak const GRAVITY: f32 = -9.8;".to_string()),
+            );
+            return Ok(Type::Error);
+        }
+        if args.len() != params.len() {
+            self.report_coded_error(
+                "E0004",
+                location,
+                format!("Argument count mismatch for '{}': expected {} arguments, got {}", name, params.len(), args.len()),
+                Some(format!("Usage: {}", usage)),
+            );
+            return Ok(Type::Error);
+        }
+        for (arg, param) in args.iter().zip(&params) {
+            let arg_type = self.check_expression(arg)?;
+            if !matches!(arg_type, Type::Error) && !self.types_compatible(param, &arg_type) {
+                self.report_coded_error(
+                    "E0005",
+                    arg.location(),
+                    format!("'{}' expects a {}, got '{}'", name, self.type_to_string(param), self.type_to_string(&arg_type)),
+                    Some(format!("Usage: {}", usage)),
+                );
+                return Ok(Type::Error);
+            }
+        }
+        Ok(return_type)
+    }
+    
+    fn check_test(&mut self, test: &TestDef) -> Result<()> {
+        let body = FunctionDef {
+            name: format!("test \"{}\"", test.name),
+            params: Vec::new(),
+            return_type: Type::Void,
+            body: test.body.clone(),
+            cuda_kernel: None,
+            no_reload_check: false,
+            is_hot: false,
+            strict_fp: false,
+        };
+        self.in_test = true;
+        let result = self.check_function(&body);
+        self.in_test = false;
+        result
+    }
+    
+    /// Each schema version is a struct (the newest under the schema's own name), and the schema
+    /// gets save_<name>/load_<name>. Field type changes need a migration (E0021).
+    fn register_save_schemas(&mut self, program: &Program) {
+        for (name, versions) in save_schema::schemas(program) {
+            let newest = versions.last().map_or(0, |schema| schema.version);
+            for (i, schema) in versions.iter().enumerate() {
+                let previous = i.checked_sub(1).map(|p| versions[p]);
+                if let Some(previous) = previous.filter(|p| p.version == schema.version) {
+                    self.report(
+                        schema.location,
+                        format!("Duplicate save_schema {} v{}", name, schema.version),
+                        Some(format!("Saved files record their version; give the new one v{}", newest + 1)),
+                        ReportOptions::error().secondary(previous.location, "first declared here"),
+                    );
+                    continue;
+                }
+                for field in &schema.fields {
+                    if let Some(reason) = save_schema::unsavable_reason(&field.ty) {
+                        self.report_error(
+                            schema.location,
+                            format!("Field '{}.{}' can't be saved: {}", name, field.name, reason),
+                            None,
+                        );
+                    }
+                }
+                let struct_name = save_schema::struct_name(schema, newest);
+                self.structs.insert(struct_name.clone(), StructDef { name: struct_name, fields: schema.fields.clone(), is_export: false });
+                self.check_schema_change(name, previous, schema);
+            }
+            let param = |name: &str, ty: Type| Param { name: name.to_string(), ty, default: None, mutable: false, location: SourceLocation::unknown() };
+            let function = |name: String, params: Vec<Param>, return_type: Type| FunctionDef {
+                name, params, return_type, body: Vec::new(), cuda_kernel: None, no_reload_check: false, is_hot: false, strict_fp: false,
+            };
+            let save = save_schema::save_function(name);
+            let load = save_schema::load_function(name);
+            let data = Type::Struct(name.to_string());
+            self.functions.insert(save.clone(), function(save, vec![param("path", Type::String), param("data", data.clone())], Type::Bool));
+            self.functions.insert(load.clone(), function(load, vec![param("path", Type::String)], Type::Optional(Box::new(data))));
+        }
+    }
+    
+    // A version's migration comes from the version before it and sets every field whose type changed
+    fn check_schema_change(&mut self, name: &str, previous: Option<&SaveSchemaDef>, schema: &SaveSchemaDef) {
+        let migration = schema.migration.as_ref();
+        match (previous, migration) {
+            (None, Some(migration)) => {
+                self.report_error(
+                    migration.location,
+                    format!("{} v{} is the oldest version, so there's nothing to migrate from", name, schema.version),
+                    Some(format!("Declare save_schema {} v{} {{ ... }}, or remove the from block", name, migration.from)),
+                );
+            }
+            (Some(previous), Some(migration)) if migration.from != previous.version => {
+                self.report_error(
+                    migration.location,
+                    format!("{} v{} migrates from v{}, but the version before it is v{}", name, schema.version, migration.from, previous.version),
+                    Some(format!("Files are migrated one version at a time: from v{}({}, {}) {{ ... }}", previous.version, migration.old, migration.new)),
+                );
+            }
+            _ => {}
+        }
+        let Some(previous) = previous else { return };
+        for (old, new) in save_schema::changed_fields(previous, schema) {
+            if migration.is_some_and(|m| save_schema::assigns_field(&m.body, &m.new, &new.name)) {
+                continue;
+            }
+            let new_binding = migration.map_or("new", |m| m.new.as_str());
+            self.report_coded_error(
+                "E0021",
+                migration.map_or(schema.location, |m| m.location),
+                format!("{} v{} changes '{}' from {} to {} without a migration",
+                    name, schema.version, new.name, self.type_to_string(&old.ty), self.type_to_string(&new.ty)),
+                Some(match migration {
+                    Some(_) => format!("Set it in the from block: {}.{} = ...;", new_binding, new.name),
+                    None => format!("Add a migration: from v{}(old, new) {{ new.{} = ...; }}", previous.version, new.name),
+                }),
+            );
+        }
+    }
+    
+    // `from v1(old, new) { ... }` is checked as a function of the old version's value and the new one
+    fn check_schema_migration(&mut self, program: &Program, schema: &SaveSchemaDef) -> Result<()> {
+        let Some(migration) = &schema.migration else { return Ok(()) };
+        let schemas = save_schema::schemas(program);
+        let versions = &schemas[schema.name.as_str()];
+        let newest = versions.last().map_or(0, |s| s.version);
+        let Some(previous) = versions.iter().find(|s| s.version == migration.from) else { return Ok(()) };
+        let param = |name: &str, ty: String, mutable: bool| Param {
+            name: name.to_string(), ty: Type::Struct(ty), default: None, mutable, location: migration.location,
+        };
+        let body = FunctionDef {
+            name: format!("{} v{} migration", schema.name, schema.version),
+            params: vec![
+                param(&migration.old, save_schema::struct_name(previous, newest), false),
+                param(&migration.new, save_schema::struct_name(schema, newest), true),
+            ],
+            return_type: Type::Void,
+            body: migration.body.clone(),
+            cuda_kernel: None,
+            no_reload_check: false,
+            is_hot: false,
+            strict_fp: false,
+        };
+        self.check_function(&body)
+    }
+    
+    // Every component a scene file sets must be declared, with fields of the declared types (E0022)
+    fn check_scene(&mut self, scene: &SceneDef) {
+        for (index, entity) in scene.entities.iter().enumerate() {
+            let label = scene::entity_label(entity, index);
+            for (name, value) in &entity.components {
+                let Some(component) = self.components.get(name).cloned() else {
+                    let mut names: Vec<&String> = self.components.keys().collect();
+                    names.sort();
+                    let names: Vec<&str> = names.into_iter().map(String::as_str).collect();
+                    self.report_coded_error(
+                        "E0022",
+                        scene.location,
+                        format!("{}: entity {} has unknown component '{}'", scene.path, label, name),
+                        Some(format!("Declared components: {}", names.join(", "))),
+                    );
+                    continue;
+                };
+                let Some(fields) = value.as_object() else {
+                    self.report_coded_error(
+                        "E0022",
+                        scene.location,
+                        format!("{}: entity {} sets {} to {}, not an object of fields", scene.path, label, name, value),
+                        Some(format!("Write \"{}\": {{ \"field\": value, ... }}, or {{}} for all zeroes", name)),
+                    );
+                    continue;
+                };
+                for (field_name, field_value) in fields {
+                    let Some(field) = component.fields.iter().find(|f| f.name == *field_name) else {
+                        let names: Vec<&str> = component.fields.iter().map(|f| f.name.as_str()).collect();
+                        self.report_coded_error(
+                            "E0022",
+                            scene.location,
+                            format!("{}: entity {} sets {}.{}, which isn't a field of {}", scene.path, label, name, field_name, name),
+                            Some(format!("{} has: {}", name, names.join(", "))),
+                        );
+                        continue;
+                    };
+                    let ty = scene::field_type(&component, field);
+                    let structs = &self.structs;
+                    let fields_of = |name: &str| structs.get(name).map(|s| s.fields.as_slice());
+                    if let Some(why) = scene::mismatch(field_value, &ty, &fields_of) {
+                        self.report_coded_error(
+                            "E0022",
+                            scene.location,
+                            format!("{}: entity {} sets {}.{} ({}): {}", scene.path, label, name, field_name, self.type_to_string(&ty), why),
+                            Some(format!("Give {}.{} a {} value, or leave it out to start zeroed", name, field_name, self.type_to_string(&ty))),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    
+    // set_parent(child, parent) and clear_parent(child), written as methods of the child entity
+    fn check_hierarchy_call(&mut self, name: &str, args: &[Expression], location: SourceLocation) -> Result<Type> {
+        if name == "children" {
+            self.report_error(
+                location,
+                "children() can only be iterated".to_string(),
+                Some("Loop over the children: for child in q.children(parent) { ... }".to_string()),
+            );
+            return Ok(Type::Error);
+        }
+        let usage = if name == "set_parent" { "child.set_parent(parent)" } else { "child.clear_parent()" };
+        let expected = if name == "set_parent" { 2 } else { 1 };
+        if args.len() != expected {
+            // The entity the method is called on isn't an argument as written
+            self.report_coded_error(
+                "E0004",
+                location,
+                format!("Argument count mismatch for '{}': expected {} argument(s), got {}", name, expected - 1, args.len().saturating_sub(1)),
+                Some(format!("Usage: {} inside a query loop", usage)),
+            );
+            return Ok(Type::Error);
+        }
+        for arg in args {
+            self.check_entity(name, arg, usage)?;
+        }
+        self.check_hierarchy_builtins(location);
+        Ok(Type::Void)
+    }
+    
+    // for child in q.children(parent): iterates q, so the loop's type is q's
+    fn check_children_query(&mut self, query: &Expression, parent: &Expression) -> Type {
+        let usage = "for child in q.children(parent) { ... }";
+        let query_type = self.check_expression(query).unwrap_or(Type::Error);
+        if self.check_entity("children", parent, usage).is_err() {
+            return Type::Error;
+        }
+        self.check_hierarchy_builtins(query.location());
+        let entity = match query {
+            Expression::Variable(name, _) if self.entity_iterators.contains(name) => Some(name),
+            _ => None,
+        };
+        match (query_type, entity) {
+            (Type::Error, _) => Type::Error,
+            (_, Some(entity)) => {
+                self.report_error(
+                    query.location(),
+                    format!("children() is called on the query to find children in, but '{}' is an entity", entity),
+                    Some(format!("Name the query: {}", usage)),
+                );
+                Type::Error
+            }
+            (query_type @ Type::Query(_), None) => query_type,
+            (query_type, None) => {
+                self.report_error(
+                    query.location(),
+                    format!("children() is called on a query, got '{}'", self.type_to_string(&query_type)),
+                    Some(format!("Name the query to look for children in: {}", usage)),
+                );
+                Type::Error
+            }
+        }
+    }
+    
+    // Hierarchy methods take the entities of query loops (generated code has their ids)
+    fn check_entity(&mut self, method: &str, arg: &Expression, usage: &str) -> Result<()> {
+        let ty = self.check_expression(arg)?;
+        let is_entity = matches!(arg, Expression::Variable(name, _) if self.entity_iterators.contains(name));
+        if !is_entity && !matches!(ty, Type::Error) {
+            self.report_error(
+                arg.location(),
+                format!("{}() takes the entity of a query loop, got '{}'", method, self.type_to_string(&ty)),
+                Some(format!("Use the loop variable of a `for entity in q` loop: {}", usage)),
+            );
+        }
+        Ok(())
+    }
+    
+    // The hierarchy is kept in the built-in Parent and Children components
+    fn check_hierarchy_builtins(&mut self, location: SourceLocation) {
+        if let Some(name) = self.hierarchy_shadowed.take() {
+            self.report_error(
+                location,
+                format!("Entity hierarchies need the built-in '{}' component, but this program declares its own", name),
+                Some(format!("Rename the program's '{}'", name)),
+            );
+        }
+    }
+    
+    // @[spatial] sounds are heard from the built-in AudioListener component and follow their
+    // entity's Position
+    fn check_spatial_audio(&mut self, program: &Program) {
+        let first = program.items.iter().enumerate().find_map(|(i, item)| match item {
+            Item::Resource(res) if res.is_spatial => Some((i, res)),
+            _ => None,
+        });
+        let Some((i, first)) = first else { return };
+        let location = self.item_locations.get(i).copied().unwrap_or_else(SourceLocation::unknown);
+        if resources::is_declared(program, spatial_audio::LISTENER) {
+            self.report_error(
+                location,
+                format!("Spatial sounds need the built-in '{}' component, but this program declares its own", spatial_audio::LISTENER),
+                Some(format!("Rename the program's '{}'", spatial_audio::LISTENER)),
+            );
+        } else {
+            self.components.insert(spatial_audio::LISTENER.to_string(), spatial_audio::listener());
+        }
+        if let Some(problem) = spatial_audio::position_problem(program) {
+            self.report_error(
+                location,
+                format!("@[spatial] sound '{}' plays at its entity's Position, but {}", first.name, problem),
+                Some("Declare a component Position { x: f32, y: f32, z: f32 }".to_string()),
+            );
+        }
+    }
+    
+    // play_resource_<name>_at(entity): the entity of an enclosing query loop
+    fn check_spatial_play(&mut self, name: &str, args: &[Expression], location: SourceLocation) -> Result<Type> {
+        let usage = format!("{}(entity)", name);
+        if args.len() != 1 {
+            self.report_coded_error(
+                "E0004",
+                location,
+                format!("Argument count mismatch for '{}': expected 1 argument(s), got {}", name, args.len()),
+                Some(format!("Usage: {}", usage)),
+            );
+            return Ok(Type::Error);
+        }
+        self.check_entity(name, &args[0], &usage)?;
+        Ok(Type::I32)
+    }
+    
+    // `on Collision(ev) { ... }` is checked as a function taking the event
+    fn check_event_handler(&mut self, system: &str, handler: &EventHandler) -> Result<()> {
+        if !self.events.contains_key(&handler.event) {
+            self.report_undefined_event(&handler.event, handler.location);
+            return Ok(());
+        }
+        let body = FunctionDef {
+            name: format!("{}.on {}", system, handler.event),
+            params: vec![Param {
+                name: handler.param.clone(),
+                ty: Type::Struct(handler.event.clone()),
+                default: None,
+                mutable: false,
+                location: handler.location,
+            }],
+            return_type: Type::Void,
+            body: handler.body.clone(),
+            cuda_kernel: None,
+            no_reload_check: false,
+            is_hot: false,
+            strict_fp: false,
+        };
+        self.check_function(&body)
+    }
+    
+    fn report_undefined_event(&mut self, name: &str, location: SourceLocation) {
+        let events: Vec<String> = self.events.keys().cloned().collect();
+        let suggestion = match find_closest_match(name, &events, 3) {
+            Some(closest) => format!("Did you mean '{}'?", closest),
+            None if self.structs.contains_key(name) || self.components.contains_key(name) => {
+                format!("'{}' isn't an event; declare it with: event {} {{ ... }}", name, name)
+            }
+            None => format!("Declare it first: event {} {{ ... }}", name),
+        };
+        self.report_error(location, format!("Undefined event: '{}'", name), Some(suggestion));
+    }
+    
+    fn reset_scopes(&mut self) {
+        self.scopes.clear();
+        self.locals.clear();
+        self.out_of_scope.clear();
+        self.push_scope();
+    }
+    
+    fn push_scope(&mut self) {
+        self.scopes.push(Scope { symbols: HashMap::new(), immutable: HashMap::new(), first_local: self.locals.len() });
+    }
+    
+    fn pop_scope(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            self.end_local_scope(scope.first_local);
+        }
+    }
+    
+    // Locals shadow top-level consts
+    fn lookup(&self, name: &str) -> Option<&Type> {
+        self.scopes.iter().rev().find_map(|scope| scope.symbols.get(name))
+            .or_else(|| self.consts.get(name).map(|(ty, _, _)| ty))
+            .or_else(|| (name == frame_arena::OBJECT).then_some(&self.frame_arena))
+    }
+    
+    fn declare(&mut self, name: &str, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.symbols.insert(name.to_string(), ty);
+        }
+    }
+    
+    fn set_mutability(&mut self, name: &str, mutable: bool, location: SourceLocation, is_param: bool) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if mutable {
+                scope.immutable.remove(name);
+            } else {
+                scope.immutable.insert(name.to_string(), (location, is_param));
+            }
+        }
+    }
+    
+    // The declaration of the binding `name` resolves to, if it was declared without `mut`
+    fn immutable_binding(&self, name: &str) -> Option<(SourceLocation, bool)> {
+        let scope = self.scopes.iter().rev().find(|scope| scope.symbols.contains_key(name))?;
+        scope.immutable.get(name).copied()
+    }
+    
+    /// Reject assignments whose target is rooted in an immutable binding (x, x.field, x[i])
+    fn check_assignment_target(&mut self, target: &Expression, location: SourceLocation) {
+        let mut root = target;
+        loop {
+            match root {
+                Expression::MemberAccess { object, .. } => root = object,
+                Expression::Index { array, .. } => root = array,
+                _ => break,
+            }
+        }
+        let Expression::Variable(name, _) = root else { return };
+        let is_local = self.scopes.iter().any(|scope| scope.symbols.contains_key(name));
+        if let Some((_, _, declared)) = self.consts.get(name).filter(|_| !is_local) {
+            let declared = *declared;
+            self.report(
+                location,
+                format!("Cannot assign to constant '{}'", name),
+                Some(format!("Use a variable for values that change: let mut {} = {};", name.to_lowercase(), name)),
+                ReportOptions::coded("E0015").secondary(declared, "const declared here"),
+            );
+            return;
+        }
+        let Some((declared, is_param)) = self.immutable_binding(name) else { return };
+        let (message, suggestion, label) = if is_param {
+            (format!("Cannot assign to immutable parameter '{}'", name),
+             format!("Declare the parameter as 'mut {}' to allow assignment", name),
+             "parameter declared here")
+        } else {
+            (format!("Cannot assign to immutable variable '{}'", name),
+             format!("Declare it with 'let mut {}' to allow assignment", name),
+             "declared here")
+        };
+        self.report(location, message, Some(suggestion), ReportOptions::coded("E0015").secondary(declared, label));
+    }
+    
+    // Arguments a call can modify: a method's self, queries and interface-typed parameters are
+    // references to the caller's value, and an extern fn gets a pointer into an array
+    fn borrows_mutably(&self, function: &str, param: &Param) -> bool {
+        match &param.ty {
+            Type::Struct(name) if self.interfaces.contains_key(name) => true,
+            Type::Query(_) => true,
+            Type::Array(_) => self.extern_functions.contains(function),
+            _ => param.name == "self",
+        }
+    }
+    
+    /// Reject passing an immutable binding (x, x.field, x[i]) where the callee can modify it. A query
+    /// or interface-typed parameter already refers to a binding its caller had to declare `mut`.
+    fn check_mutable_borrow(&mut self, arg: &Expression, function: &str) {
+        let mut root = arg;
+        loop {
+            match root {
+                Expression::MemberAccess { object, .. } => root = object,
+                Expression::Index { array, .. } => root = array,
+                _ => break,
+            }
+        }
+        let Expression::Variable(name, _) = root else { return };
+        let Some((declared, is_param)) = self.immutable_binding(name) else { return };
+        let by_reference = match self.lookup(name) {
+            Some(Type::Struct(ty)) => self.interfaces.contains_key(ty),
+            Some(Type::Query(_)) => true,
+            _ => false,
+        };
+        if is_param && by_reference {
+            return;
+        }
+        let (suggestion, label) = if is_param {
+            (format!("Declare the parameter as 'mut {}' to let '{}' modify it", name, function), "parameter declared here")
+        } else {
+            (format!("Declare it with 'let mut {}' to let '{}' modify it", name, function), "declared here")
+        };
+        self.report(
+            arg.location(),
+            format!("Cannot pass immutable variable '{}' to '{}', which can modify it", name, function),
+            Some(suggestion),
+            ReportOptions::coded("E0015").secondary(declared, label),
+        );
+    }
+    
+    // Names visible at this point, for typo suggestions
+    fn visible_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.scopes.iter().flat_map(|scope| scope.symbols.keys().cloned()).collect();
+        names.extend(self.consts.keys().cloned());
+        names.sort();
+        names.dedup();
+        names
+    }
+    
+    /// Systems run by run_systems() need an entry function and a satisfiable order
+    fn check_schedule(&mut self, program: &Program) {
+        let systems: Vec<&SystemDef> = program.items.iter().filter_map(|item| match item {
+            Item::System(s) => Some(s),
+            _ => None,
+        }).collect();
+        for system in systems.iter().filter(|s| s.schedule.is_some()) {
+            if !system.functions.iter().any(schedule::is_system_entry) && system.handlers.is_empty() {
+                self.report_error(
+                    system.location,
+                    format!("Scheduled system '{}' has no function for run_systems() to call", system.name),
+                    Some("Add a fn that takes nothing or the frame time, plus any queries and resources: fn step(dt: f32): void { ... }".to_string()),
+                );
+            }
+        }
+        if let Err(errors) = schedule::schedule(&systems) {
+            for error in errors {
+                match error.code {
+                    Some(code) => self.report_coded_error(code, error.location, error.message, error.suggestion),
+                    None => self.report_error(error.location, error.message, error.suggestion),
+                }
+            }
+        }
+    }
+    
+    /// @[pool] components never grow past their capacity, so scenes that start with more of
+    /// them than that can't load (spawn_batch counts are checked with the batch)
+    fn check_pools(&mut self, program: &Program) {
+        for (i, item) in program.items.iter().enumerate() {
+            if let Item::Component(c) = item {
+                if let Some(capacity) = c.pool_capacity {
+                    let location = self.item_locations.get(i).copied().unwrap_or_else(SourceLocation::unknown);
+                    self.pools.insert(c.name.clone(), (capacity, location));
+                }
+            }
+        }
+        // Each scene on its own: a program can load one level after another into the same world
+        for item in &program.items {
+            let Item::Scene(scene) = item else { continue };
+            let mut spawned: Vec<(&str, usize)> = Vec::new();
+            for entity in &scene.entities {
+                for name in entity.components.keys().filter(|name| self.pools.contains_key(*name)) {
+                    match spawned.iter_mut().find(|(spawned_name, _)| spawned_name == name) {
+                        Some((_, count)) => *count += 1,
+                        None => spawned.push((name, 1)),
+                    }
+                }
+            }
+            for (name, count) in spawned {
+                let (capacity, declared) = self.pools[name];
+                if count > capacity as usize {
+                    self.pool_overflow(name, capacity, declared, scene.location, format!("{} spawns {}", scene.path, count));
+                }
+            }
+        }
+    }
+    
+    /// @[cuda] components are copied to the GPU as flat arrays, and a @[launch] function's kernel
+    /// runs a thread per entity of its one query, so its query, parameters and top-level
+    /// statements must all make sense on the device
+    fn check_cuda(&mut self, program: &Program) {
+        let mut kernels: HashMap<&str, &str> = HashMap::new();
+        for (i, item) in program.items.iter().enumerate() {
+            let location = self.item_locations.get(i).copied().unwrap_or_else(SourceLocation::unknown);
+            match item {
+                Item::Component(c) if c.is_cuda => self.check_cuda_component(c, location),
+                Item::Function(f) => {
+                    let Some(launch) = &f.cuda_kernel else { continue };
+                    if let Some(other) = kernels.insert(&launch.kernel, &f.name) {
+                        self.report_error(
+                            location,
+                            format!("Kernel '{}' is already launched by fn '{}'", launch.kernel, other),
+                            Some(format!("Give it its own name: @[launch(kernel = {}_{})]", launch.kernel, f.name)),
+                        );
+                    }
+                    self.check_cuda_kernel(f, location);
+                }
+                _ => {}
+            }
+        }
+    }
+    
+    fn check_cuda_component(&mut self, c: &ComponentDef, location: SourceLocation) {
+        for field in &c.fields {
+            let element = match &field.ty {
+                Type::Array(element) if c.is_soa => element.as_ref(),
+                ty => ty,
+            };
+            if field.cold || !cuda::is_device_type(element) {
+                let what = if field.cold { "a @cold field".to_string() } else { format!("a field of type '{}'", self.type_to_string(&field.ty)) };
+                self.report_error(
+                    location,
+                    format!("@[cuda] component '{}' can't copy {} ('{}') to the GPU", c.name, what, field.name),
+                    Some(if c.is_soa { "Fields of a @[cuda] component_soa are arrays of numbers or bools, e.g. x: [f32]" } else { "Fields of a @[cuda] component are numbers or bools, e.g. x: f32" }.to_string()),
+                );
+            }
+        }
+    }
+    
+    fn check_cuda_kernel(&mut self, f: &FunctionDef, location: SourceLocation) {
+        let Some(launch) = &f.cuda_kernel else { return };
+        let Some((query, component_types)) = cuda::kernel_query(f) else {
+            self.report_error(
+                location,
+                format!("@[launch] fn '{}' needs a query parameter: kernel '{}' runs a thread per entity of it", f.name, launch.kernel),
+                Some(format!("Take the entities to process: fn {}(q: query<Position, Velocity>)", f.name)),
+            );
+            return;
+        };
+        for param in &f.params {
+            match &param.ty {
+                Type::Query(_) if param.name == query.name => {}
+                Type::Query(_) => self.report_error(
+                    param.location,
+                    format!("@[launch] fn '{}' takes a second query '{}'; a kernel runs over one", f.name, param.name),
+                    Some("Launch a kernel per query, or list all the components in one query".to_string()),
+                ),
+                ty if !cuda::is_device_type(ty) => self.report_error(
+                    param.location,
+                    format!("Kernel parameter '{}' has type '{}', which isn't passed to the GPU", param.name, self.type_to_string(ty)),
+                    Some("Kernel parameters are numbers or bools; put per-entity data in a @[cuda] component".to_string()),
+                ),
+                _ => {}
+            }
+        }
+        for ty in component_types {
+            match ty {
+                Type::QueryFilter(filter, _) => self.report_error(
+                    query.location,
+                    format!("Kernel query '{}' can't filter with {}: the GPU can't see the entity storage", query.name, self.type_to_string(ty)),
+                    Some(format!("Remove {}<...>, or filter on the host before launching", filter.name())),
+                ),
+                Type::Struct(name) | Type::Component(name) if !self.components.get(name).is_some_and(|c| c.is_cuda) => self.report_error(
+                    query.location,
+                    format!("Component '{}' of kernel query '{}' isn't @[cuda]", name, query.name),
+                    Some(format!("Mark it so its arrays can be copied to the GPU: @[cuda] component {} {{ ... }}", name)),
+                ),
+                _ => {}
+            }
+        }
+        if !matches!(f.return_type, Type::Void) {
+            self.report_error(
+                location,
+                format!("@[launch] fn '{}' can't return a value: its kernel runs on many threads at once", f.name),
+                Some(format!("Write results into the components of '{}'", query.name)),
+            );
+        }
+        // Anything but a loop over the query runs once in every thread
+        for stmt in &f.body {
+            let spread = matches!(stmt, Statement::For { collection: Expression::Variable(name, _), chunk_size: None, .. } if *name == query.name);
+            if !spread && !matches!(stmt, Statement::Let { .. }) {
+                self.report_error(
+                    stmt.location(),
+                    format!("Kernel '{}' would run this once per GPU thread", launch.kernel),
+                    Some(format!("Keep lets and `for entity in {}` at the top of a kernel; do the rest before calling {}", query.name, cuda::launch_function(f))),
+                );
+            }
+        }
+    }
+    
+    fn pool_overflow(&mut self, component: &str, capacity: u32, declared: SourceLocation, at: SourceLocation, what: String) {
+        self.report(
+            at,
+            format!("Component pool '{}' holds {}, but {}", component, capacity, what),
+            Some(format!("Raise the capacity to the most {} components alive at once, or spawn fewer", component)),
+            ReportOptions::coded("E0028").secondary(declared, "pool declared here"),
+        );
+    }
+    
+    /// @[replicated] components are sent in snapshots, which only carry fields a save can hold
+    fn check_replicated(&mut self, program: &Program) {
+        for item in &program.items {
+            let Item::Component(component) = item else { continue };
+            if !component.is_replicated {
+                continue;
+            }
+            if let Some((field, reason)) = replication::unreplicated_field(component) {
+                self.report_coded_error(
+                    "E0023",
+                    SourceLocation::unknown(),
+                    format!("Field '{}.{}' can't be replicated: {}", component.name, field.name, reason),
+                    Some(format!("Keep '{}' in a component that isn't @[replicated], or send the values it's built from", field.name)),
+                );
+            }
+        }
+    }
+
+    /// `migrate Health(old: HealthV1): Health` needs a component and a struct declaring its old
+    /// layout. load_world reads saved worlds into that struct, so its fields must be saveable.
+    /// Program symbols that collide with names of the generated C++ (E0025): the names it uses
+    /// for itself, and one name declared by two kinds of item
+    fn check_generated_names(&mut self, program: &Program) {
+        // A pipeline declares a struct and a function for push_constants, two functions for its bindings;
+        // a uniform declares its update function
+        let derived_names: Vec<Vec<(String, &str, SourceLocation)>> = program.items.iter().enumerate().map(|(i, item)| match item {
+            Item::Pipeline(p) => {
+                let mut names = Vec::new();
+                if let Some(block) = p.push_constants() {
+                    names.push((p.push_constants_struct(), "push_constants struct", block.location));
+                    names.push((p.push_constants_function(), "push_constants function", block.location));
+                }
+                if !p.bindings().is_empty() {
+                    let start = self.item_locations.get(i).copied().unwrap_or_else(SourceLocation::unknown);
+                    names.push((p.descriptor_sets_function(), "descriptor set function", start));
+                    names.push((p.update_binding_function(), "descriptor set function", start));
+                }
+                names
+            }
+            Item::Uniform(u) => vec![(u.update_function(), "uniform update function", u.location)],
+            _ => Vec::new(),
+        }).collect();
+        let mut declared: HashMap<&str, (&str, SourceLocation)> = HashMap::new();
+        let mut resources: HashMap<String, (&str, SourceLocation)> = HashMap::new();
+        for (i, item) in program.items.iter().enumerate() {
+            let start = self.item_locations.get(i).copied().unwrap_or_else(SourceLocation::unknown);
+            let names: Vec<(&str, &str, SourceLocation)> = match item {
+                Item::Struct(s) => vec![(&s.name, "struct", start)],
+                Item::Component(c) => vec![(&c.name, "component", start)],
+                Item::Event(e) => vec![(&e.name, "event", e.location)],
+                Item::Uniform(u) => vec![(&u.name, "uniform", u.location)],
+                Item::Function(f) => vec![(&f.name, "function", start)],
+                Item::ExternFunction(ext) => vec![(&ext.name, "extern function", start)],
+                Item::Resource(r) => vec![(&r.name, "resource", start)],
+                Item::Const(c) => vec![(&c.name, "const", c.location)],
+                Item::Interface(iface) => vec![(&iface.name, "interface", iface.location)],
+                Item::System(system) => std::iter::once((system.name.as_str(), "system", system.location))
+                    .chain(system.functions.iter().map(|f| (f.name.as_str(), "system function", system.location)))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let names = names.into_iter().chain(derived_names[i].iter().map(|(name, kind, location)| (name.as_str(), *kind, *location)));
+            for (name, kind, location) in names {
+                // Extern functions name existing C++ symbols, which may well be the runtime's own
+                let usage = generated_names::generated_use(name).filter(|_| kind != "extern function");
+                if let Some(usage) = usage {
+                    self.report_coded_error(
+                        "E0025",
+                        location,
+                        format!("{} name '{}' collides with a name the generated C++ uses for {}", capitalize(kind), name, usage),
+                        Some(format!("Rename the {}, e.g. to '{}'", kind, generated_names::rename_suggestion(name))),
+                    );
+                    continue;
+                }
+                if name == "main" && kind != "function" {
+                    self.report_coded_error(
+                        "E0025",
+                        location,
+                        format!("A {} can't be named 'main': the generated C++ defines main() itself", kind),
+                        Some(format!("Rename the {}, e.g. to '{}'", kind, generated_names::rename_suggestion(name))),
+                    );
+                    continue;
+                }
+                match declared.get(name) {
+                    Some(&(first_kind, first)) if kinds_collide(first_kind, kind) => self.report(
+                        location,
+                        format!("'{}' is declared as both a {} and a {}", name, first_kind, kind),
+                        Some(format!("Rename one of them, e.g. the {} to '{}'", kind, kind_suffixed(name, kind))),
+                        ReportOptions::coded("E0025").secondary(first, &format!("{} '{}' declared here", first_kind, name)),
+                    ),
+                    Some(_) => {}
+                    None => {
+                        declared.insert(name, (kind, location));
+                    }
+                }
+                if kind != "resource" {
+                    continue;
+                }
+                // Resource globals are named after the lowercased resource name
+                match resources.get(&name.to_lowercase()) {
+                    Some(&(first_name, first)) if first_name != name => self.report(
+                        location,
+                        format!("Resources '{}' and '{}' both become the global resource_{} in the generated C++", first_name, name, name.to_lowercase()),
+                        Some("Rename one of them: resource names must differ in more than case".to_string()),
+                        ReportOptions::coded("E0025").secondary(first, "other resource declared here"),
+                    ),
+                    Some(_) => {}
+                    None => {
+                        resources.insert(name.to_lowercase(), (name, location));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_migrations(&mut self, program: &Program) {
+        let mut seen: HashMap<(&str, &str), SourceLocation> = HashMap::new();
+        for item in &program.items {
+            let Item::Migrate(migrate) = item else { continue };
+            let f = &migrate.function;
+            let Some(component) = self.components.get(&f.name).cloned() else {
+                self.report_coded_error(
+                    "E0024",
+                    migrate.location,
+                    format!("Can't migrate '{}': it isn't a component", f.name),
+                    Some("Name the migration after the component it produces: migrate Health(old: HealthV1): Health { ... }".to_string()),
+                );
+                continue;
+            };
+            if component.is_soa {
+                self.report_coded_error(
+                    "E0024",
+                    migrate.location,
+                    format!("component_soa '{}' can't have a migrate function", f.name),
+                    Some("Keep the names of the fields that carry over; they are copied to the new layout".to_string()),
+                );
+                continue;
+            }
+            let old = match (f.params.as_slice(), migrate.old_struct()) {
+                ([_], Some(old)) if self.structs.contains_key(old) => old,
+                _ => {
+                    self.report_coded_error(
+                        "E0024",
+                        migrate.location,
+                        format!("migrate {} must take one parameter: the old layout, declared as a struct", f.name),
+                        Some(format!("Declare the old fields as struct {}V1 {{ ... }}, then: migrate {}(old: {}V1): {} {{ ... }}", f.name, f.name, f.name, f.name)),
+                    );
+                    continue;
+                }
+            };
+            if !matches!(&f.return_type, Type::Struct(name) | Type::Component(name) if *name == f.name) {
+                self.report_coded_error(
+                    "E0024",
+                    migrate.location,
+                    format!("migrate {} must return '{}', not '{}'", f.name, f.name, self.type_to_string(&f.return_type)),
+                    Some(format!("migrate {}(old: {}): {} {{ ... }}", f.name, old, f.name)),
+                );
+            }
+            let old_fields = self.structs[old].fields.clone();
+            if let Some((field, reason)) = old_fields.iter().find_map(|field| save_schema::unsavable_reason(&field.ty).map(|reason| (field, reason))) {
+                self.report_coded_error(
+                    "E0024",
+                    migrate.location,
+                    format!("Field '{}.{}' of the old layout can't be read from a saved world: {}", old, field.name, reason),
+                    Some("Old layouts can only hold fields a save can: numbers, bools, strings, vectors and arrays of them".to_string()),
+                );
+            }
+            if let Some(first) = seen.insert((f.name.as_str(), old), migrate.location) {
+                self.report(
+                    migrate.location,
+                    format!("'{}' already has a migration from '{}'", f.name, old),
+                    Some("Keep one migrate function per old layout".to_string()),
+                    ReportOptions::coded("E0024").secondary(first, "first migration here"),
+                );
+            }
+        }
+    }
+
+    /// net_host/net_connect/net_update/... (stdlib/net.h)
+    fn check_net_call(&mut self, name: &str, args: &[Expression], location: SourceLocation) -> Result<Type> {
+        let Some((param_types, return_type, usage)) = replication::signature(name) else { return Ok(Type::Error) };
+        if args.len() != param_types.len() {
+            self.report_coded_error(
+                "E0004",
+                location,
+                format!("Argument count mismatch for '{}': expected {} arguments, got {}", name, param_types.len(), args.len()),
+                Some(usage.to_string()),
+            );
+            return Ok(Type::Error);
+        }
+        let mut has_error = false;
+        for (arg, expected) in args.iter().zip(param_types.iter()) {
+            let arg_type = self.check_expression(arg)?;
+            if matches!(arg_type, Type::Error) {
+                has_error = true;
+            } else if !self.types_compatible(expected, &arg_type) {
+                self.report_coded_error(
+                    "E0005",
+                    arg.location(),
+                    format!("'{}' expects a '{}', got '{}'", name, self.type_to_string(expected), self.type_to_string(&arg_type)),
+                    Some(usage.to_string()),
+                );
+                has_error = true;
+            }
+        }
+        Ok(if has_error { Type::Error } else { return_type })
+    }
+
+    /// @[export] types are written as C, GLSL and JSON (--emit=layouts), so every field needs a
+    /// type all three lay out the same way
+    fn check_exports(&mut self, program: &Program) {
+        let is_exported = |name: &str| program.items.iter().any(|item| match item {
+            Item::Struct(s) => s.is_export && s.name == name,
+            Item::Component(c) => c.is_export && !c.is_soa && c.name == name,
+            _ => false,
+        });
+        for item in &program.items {
+            let (name, fields) = match item {
+                Item::Struct(s) if s.is_export => (&s.name, &s.fields),
+                Item::Component(c) if c.is_export && c.is_soa => {
+                    self.report_coded_error(
+                        "E0020",
+                        SourceLocation::unknown(),
+                        format!("component_soa '{}' can't be exported: it's stored as one array per field, not as a struct", c.name),
+                        Some(format!("Export a component with the per-entity fields instead: @[export] component {} {{ ... }}", c.name)),
+                    );
+                    continue;
+                }
+                Item::Component(c) if c.is_export => (&c.name, &c.fields),
+                _ => continue,
+            };
+            for field in fields {
+                if let Some(reason) = layout_export::unexportable_reason(&field.ty, is_exported) {
+                    let suggestion = match &field.ty {
+                        Type::Bool => "Use an i32 (0 or 1) for flags in exported types".to_string(),
+                        Type::Mat4 => "Export the matrix as four Vec4 columns".to_string(),
+                        Type::Struct(inner) | Type::Component(inner) => format!("Mark '{}' @[export] as well", inner),
+                        _ => "Exported fields can be i32, i64, f32, f64, Vec2, Vec3, Vec4 or other @[export] types".to_string(),
+                    };
+                    self.report_coded_error(
+                        "E0020",
+                        SourceLocation::unknown(),
+                        format!("Field '{}.{}' can't be exported: {}", name, field.name, reason),
+                        Some(suggestion),
+                    );
+                }
+            }
+        }
+    }
+    
+    /// Consts are evaluated in declaration order, so an initializer can use the consts above it
+    fn check_const(&mut self, c: &ConstDef) {
+        if let Some((_, _, first)) = self.consts.get(&c.name) {
+            let first = *first;
+            self.report(
+                c.location,
+                format!("Duplicate const '{}'", c.name),
+                Some("Rename one of the consts or remove the duplicate".to_string()),
+                ReportOptions::error().secondary(first, "first declared here"),
+            );
+            return;
+        }
+        if c.is_tweak {
+            self.tweaks.insert(c.name.clone());
+        }
+        self.reset_scopes();
+        let errors_before = self.errors.len();
+        let value_type = self.check_expression(&c.value).unwrap_or(Type::Error);
+        let ty = match &c.ty {
+            Some(declared) => {
+                if !self.types_compatible(declared, &value_type) {
+                    let suggestion = self.narrowing_suggestion(declared, &value_type)
+                        .unwrap_or_else(|| format!("Give the const a {} value", self.type_to_string(declared)));
+                    self.report_coded_error(
+                        "E0003",
+                        c.location,
+                        format!("Type mismatch: cannot assign '{}' to '{}'", self.type_to_string(&value_type), self.type_to_string(declared)),
+                        Some(suggestion),
+                    );
+                }
+                declared.clone()
+            }
+            None => value_type,
+        };
+        if !matches!(ty, Type::I32 | Type::I64 | Type::F32 | Type::F64 | Type::Bool | Type::Error) {
+            self.report_coded_error(
+                "E0018",
+                c.location,
+                format!("Const '{}' has type '{}', which has no compile-time value", c.name, self.type_to_string(&ty)),
+                Some("Consts hold numbers (i32, i64, f32, f64) and bools; use a let or a resource for other values".to_string()),
+            );
+        }
+        let value = if self.errors.len() > errors_before {
+            ConstValue::Error
+        } else {
+            let value = self.evaluate_const(&c.value);
+            match value.convert(&ty) {
+                Ok(value) => value,
+                Err(message) => {
+                    self.report_coded_error("E0018", c.value.location(), format!("Const '{}': {}", c.name, message),
+                        Some(format!("Declare it as i64: const {}: i64 = ...;", c.name)));
+                    ConstValue::Error
+                }
+            }
+        };
+        let ty = if value == ConstValue::Error { Type::Error } else { ty };
+        self.declarations.push((c.location, c.name.clone(), ty.clone()));
+        self.consts.insert(c.name.clone(), (ty, value, c.location));
+    }
+    
+    // The value of a constant expression, reporting E0018 for the part that can't be computed
+    fn evaluate_const(&mut self, expr: &Expression) -> ConstValue {
+        let (consts, tweaks) = (&self.consts, &self.tweaks);
+        let tweak_used = std::cell::RefCell::new(None);
+        let constant = |name: &str| {
+            if tweaks.contains(name) {
+                *tweak_used.borrow_mut() = Some(name.to_string());
+                return None;
+            }
+            consts.get(name).map(|(_, value, _)| *value)
+        };
+        match const_eval::evaluate(expr, &constant, &self.layouts()) {
+            Ok(value) => value,
+            Err(error) => {
+                match tweak_used.into_inner() {
+                    Some(tweak) if error.message.contains(&format!("'{}'", tweak)) => self.report_coded_error(
+                        "E0018",
+                        error.location,
+                        format!("'{}' is a @tweak const, so its value isn't known until the program runs", tweak),
+                        Some(format!("Use a plain const here, or drop @tweak from '{}'", tweak)),
+                    ),
+                    _ => self.report_coded_error("E0018", error.location, error.message, error.suggestion),
+                }
+                ConstValue::Error
+            }
+        }
+    }
+    
+    fn check_static_assert(&mut self, assert: &StaticAssertDef) {
+        self.reset_scopes();
+        let errors_before = self.errors.len();
+        let condition_type = self.check_expression(&assert.condition).unwrap_or(Type::Error);
+        if !matches!(condition_type, Type::Bool | Type::Error) {
+            self.report_coded_error(
+                "E0007",
+                assert.condition.location(),
+                format!("static_assert condition must be bool, got '{}'", self.type_to_string(&condition_type)),
+                Some("Compare the value: static_assert(MAX_LIGHTS <= 16, \"...\");".to_string()),
+            );
+        }
+        if self.errors.len() > errors_before || self.evaluate_const(&assert.condition) != ConstValue::Bool(false) {
+            return;
+        }
+        let message = match &assert.message {
+            Some(message) => format!("Static assertion failed: {}", message),
+            None => "Static assertion failed".to_string(),
+        };
+        // For a comparison, show what each side evaluated to
+        let suggestion = match &assert.condition {
+            Expression::BinaryOp { op, left, right, .. } if !matches!(op, BinaryOp::And | BinaryOp::Or) => {
+                let (left, right) = (self.evaluate_const(left), self.evaluate_const(right));
+                format!("The condition evaluated to {} {} {}", left, crate::formatter::binary_symbol(op), right)
+            }
+            _ => "The condition evaluated to false".to_string(),
+        };
+        self.report_coded_error("E0017", assert.location, message, Some(suggestion));
+    }
+    
+    /// spawn_batch runs its init block once per new entity. Component literals written as
+    /// statements at the top of the block are attached to that entity, once per component.
+    fn check_spawn_batch(&mut self, count: &Expression, index: &str, body: &[Statement], location: SourceLocation) {
+        let count_type = self.check_expression(count).unwrap_or(Type::Error);
+        if !matches!(count_type, Type::I32 | Type::I64 | Type::Error) {
+            self.report_coded_error(
+                "E0003",
+                count.location(),
+                format!("spawn_batch count must be an integer, got '{}'", self.type_to_string(&count_type)),
+                Some("Pass the number of entities to create: spawn_batch(1000) { init(i) { ... } }".to_string()),
+            );
+        }
+        if let Some((keyword, at)) = escaping_statement(body, false) {
+            self.report_error(
+                at,
+                format!("'{}' can't be used inside spawn_batch", keyword),
+                Some("Every entity in the batch is created; compute differing values with if-expressions instead of leaving init early".to_string()),
+            );
+        }
+
+        self.push_scope();
+        self.declare(index, Type::I32);
+        self.set_mutability(index, false, location, false);
+        let mut attached: Vec<(String, SourceLocation)> = Vec::new();
+        for stmt in body {
+            if let Statement::Expression(Expression::StructLiteral { name, fields, .. }, at) = stmt {
+                if let Some(component) = self.components.get(name) {
+                    let row_fields = component.is_soa.then(|| soa_row_fields(component));
+                    if let Some((_, first)) = attached.iter().find(|(n, _)| n == name) {
+                        let first = *first;
+                        self.report(
+                            *at,
+                            format!("Component '{}' is attached twice in spawn_batch", name),
+                            Some("Each entity holds one of each component; merge the fields into a single literal".to_string()),
+                            ReportOptions::error().secondary(first, "first attached here"),
+                        );
+                    } else {
+                        attached.push((name.clone(), *at));
+                    }
+                    // A component_soa is attached from one entity's values, an element of each field array
+                    if let Some(row_fields) = row_fields {
+                        let _ = self.check_struct_literal_fields(name, &row_fields, fields, *at);
+                        continue;
+                    }
+                }
+            } else if let Some((name, at)) = component_literal_statement(std::slice::from_ref(stmt), &self.components) {
+                self.report_error(
+                    at,
+                    format!("Component '{}' must be attached at the top of the init block", name),
+                    Some("Components are attached to every entity in the batch; choose field values with an if-expression instead".to_string()),
+                );
+            }
+            // Statement errors are reported; keep checking the rest (error recovery)
+            let _ = self.check_statement(stmt);
+        }
+        self.pop_scope();
+        
+        // A batch larger than a pool it attaches to can't fit, however many are alive already
+        let Some(count_value) = self.constant_int(count) else { return };
+        for (name, _) in attached {
+            if let Some(&(capacity, declared)) = self.pools.get(&name).filter(|(capacity, _)| count_value > *capacity as i64) {
+                self.pool_overflow(&name, capacity, declared, count.location(), format!("this spawn_batch creates {}", count_value));
+            }
+        }
+    }
+    
+    // The value of an integer expression when it's known at compile time (literals and consts)
+    fn constant_int(&self, expr: &Expression) -> Option<i64> {
+        let constant = |name: &str| {
+            let shadowed = self.tweaks.contains(name) || self.scopes.iter().any(|scope| scope.symbols.contains_key(name));
+            if shadowed { None } else { self.consts.get(name).map(|(_, value, _)| *value) }
+        };
+        match const_eval::evaluate(expr, &constant, &self.layouts()) {
+            Ok(ConstValue::Int(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Check a value block in its own scope and return the type of its value. Value blocks are
+    /// generated as a lambda, so they can't return, break or continue.
+    fn check_value_block(&mut self, block: &ValueBlock, construct: &str, statement_form: &str) -> Type {
+        self.check_no_escape(&block.statements, construct, statement_form);
+        self.push_scope();
+        for stmt in &block.statements {
+            // Statement errors are reported; keep checking the rest (error recovery)
+            let _ = self.check_statement(stmt);
+        }
+        let ty = self.check_expression(&block.value).unwrap_or(Type::Error);
+        self.pop_scope();
+        ty
+    }
+
+    // Value-producing blocks are generated as lambdas, so they can't leave the enclosing function or loop
+    fn check_no_escape(&mut self, stmts: &[Statement], construct: &str, statement_form: &str) {
+        if let Some((keyword, location)) = escaping_statement(stmts, false) {
+            self.report_error(
+                location,
+                format!("'{}' can't be used inside {}", keyword, construct),
+                Some(format!("Use {} when a branch needs to leave the function or loop", statement_form)),
+            );
+        }
+    }
+
+    /// Literal and range patterns must be comparable with the scrutinee (integer patterns are
+    /// generated as `case` labels), and arms that can never be chosen are reported. A guarded arm
+    /// can always fall through to the ones after it.
+    fn check_match_patterns(&mut self, scrutinee_type: &Type, arms: &[MatchArm]) {
+        for (i, arm) in arms.iter().enumerate() {
+            let earlier: Vec<&MatchArm> = arms[..i].iter().filter(|a| a.guard.is_none()).collect();
+            if let Some(catch_all) = earlier.iter().find(|a| a.pattern.is_irrefutable()) {
+                self.report_warning(
+                    "W0003",
+                    arm.location,
+                    format!("Unreachable match arm: the arm on line {} already matches every value", catch_all.location.line),
+                    Some("Remove this arm, or move it before the catch-all arm".to_string()),
+                );
+                continue;
+            }
+            if !self.check_pattern_values(&arm.pattern, scrutinee_type) || arm.pattern.is_irrefutable() {
+                continue;
+            }
+
+            // Each alternative of an or-pattern may already be handled by a different arm
+            let alternatives = match &arm.pattern {
+                Pattern::Or(alternatives, _) => alternatives.iter().collect(),
+                pattern => vec![pattern],
+            };
+            let covering: Vec<Option<&MatchArm>> = alternatives.iter()
+                .map(|alternative| earlier.iter().copied().find(|a| pattern_covers(&a.pattern, alternative)))
+                .collect();
+            if let Some(Some(first)) = covering.first().filter(|_| covering.iter().all(Option::is_some)) {
+                self.report_warning(
+                    "W0003",
+                    arm.location,
+                    format!("Unreachable match arm: every value it matches is already matched by the arm on line {}", first.location.line),
+                    Some("Remove this arm, or merge its body into the first one".to_string()),
+                );
+                continue;
+            }
+            for (alternative, first) in alternatives.iter().zip(&covering) {
+                if let Some(first) = first {
+                    self.report_warning(
+                        "W0003",
+                        pattern_location(alternative),
+                        format!("Unreachable pattern: its values are already matched by the arm on line {}", first.location.line),
+                        Some("Remove this alternative from the or-pattern".to_string()),
+                    );
+                }
+            }
+        }
+    }
+
+    // The literals of a pattern must fit the scrutinee's type and ranges must hold some number;
+    // returns false after reporting an error
+    fn check_pattern_values(&mut self, pattern: &Pattern, scrutinee_type: &Type) -> bool {
+        let (literals, location) = match pattern {
+            Pattern::Literal(literal, location) => (vec![literal], *location),
+            Pattern::Range { start, end, location, .. } => (vec![start, end], *location),
+            Pattern::Or(alternatives, _) => {
+                return alternatives.iter().all(|alternative| self.check_pattern_values(alternative, scrutinee_type));
+            }
+            Pattern::Struct { name, fields, rest, location } => {
+                return self.check_struct_pattern(name, fields, *rest, *location, scrutinee_type);
+            }
+            Pattern::Variable(..) | Pattern::Wildcard(_) | Pattern::Ident(..) => return true,
+        };
+        for literal in literals {
+            let pattern_type = literal_type(literal);
+            if !literal_pattern_fits(&pattern_type, scrutinee_type) {
+                self.report_coded_error(
+                    "E0003",
+                    location,
+                    format!("Pattern of type '{}' can't match a value of type '{}'",
+                        self.type_to_string(&pattern_type), self.type_to_string(scrutinee_type)),
+                    Some(format!("Use {} literals as the patterns of this match", self.type_to_string(scrutinee_type))),
+                );
+                return false;
+            }
+        }
+        if let Pattern::Range { start, end, inclusive, location, .. } = pattern {
+            let (Some(low), Some(high)) = (literal_number(start), literal_number(end)) else { return true };
+            if low > high || (low == high && !inclusive) {
+                self.report_coded_error(
+                    "E0003",
+                    *location,
+                    format!("Range pattern {}{}{} matches no value", low, if *inclusive { "..=" } else { ".." }, high),
+                    Some("Put the smaller end first: 0..10 excludes 10, 0..=10 includes it".to_string()),
+                );
+                return false;
+            }
+        }
+        true
+    }
+
+    // A struct pattern names the matched value's own struct or component and fields it has, and
+    // lists every field unless it ends in `..`
+    fn check_struct_pattern(&mut self, name: &str, fields: &[FieldPattern], rest: bool, location: SourceLocation, scrutinee_type: &Type) -> bool {
+        let Some(declared) = self.declared_fields(name) else {
+            let mut candidates: Vec<String> = self.structs.keys().cloned().collect();
+            candidates.extend(self.components.keys().cloned());
+            let suggestion = match find_closest_match(name, &candidates, 3) {
+                Some(closest) => format!("Did you mean '{}'? Use: {} {{ ... }}", closest, closest),
+                None => format!("Did you mean to declare it? Use: struct {} {{ ... }}", name),
+            };
+            self.report_error(location, format!("Undefined struct: '{}'", name), Some(suggestion));
+            return false;
+        };
+        if !matches!(scrutinee_type, Type::Error | Type::Struct(_) | Type::Component(_))
+            || matches!(scrutinee_type, Type::Struct(other) | Type::Component(other) if other != name) {
+            self.report_coded_error(
+                "E0003",
+                location,
+                format!("Pattern of type '{}' can't match a value of type '{}'", name, self.type_to_string(scrutinee_type)),
+                Some(format!("A {} {{ ... }} pattern only matches {} values", name, name)),
+            );
+            return false;
+        }
+        let mut ok = true;
+        let mut seen: HashMap<&str, SourceLocation> = HashMap::new();
+        for field in fields {
+            if let Some(first) = seen.get(field.field.as_str()) {
+                self.report(
+                    field.location,
+                    format!("Field '{}' appears more than once in the {} pattern", field.field, name),
+                    Some(format!("Remove the repeated '{}'", field.field)),
+                    ReportOptions::coded("E0030").secondary(*first, "first listed here"),
+                );
+                ok = false;
+                continue;
+            }
+            seen.insert(&field.field, field.location);
+            let Some(declared_field) = declared.iter().find(|f| f.name == field.field) else {
+                let candidates: Vec<String> = declared.iter().map(|f| f.name.clone()).collect();
+                let suggestion = match find_closest_match(&field.field, &candidates, 3) {
+                    Some(closest) => format!("Did you mean '{}'?", closest),
+                    None => format!("Available fields: {}", candidates.join(", ")),
+                };
+                self.report_coded_error("E0029", field.location, format!("'{}' has no field named '{}'", name, field.field), Some(suggestion));
+                ok = false;
+                continue;
+            };
+            ok &= self.check_pattern_values(&field.pattern, &declared_field.ty);
+        }
+        let missing: Vec<&str> = declared.iter().map(|f| f.name.as_str()).filter(|field| !seen.contains_key(field)).collect();
+        if !rest && !missing.is_empty() {
+            let listed: Vec<&str> = fields.iter().map(|f| f.field.as_str()).collect();
+            let example = if listed.is_empty() { "..".to_string() } else { format!("{}, ..", listed.join(", ")) };
+            self.report_error(
+                location,
+                format!("The {} pattern doesn't list {}", name, missing.iter().map(|f| format!("'{}'", f)).collect::<Vec<_>>().join(", ")),
+                Some(format!("List them, or add '..' to skip the rest: {} {{ {} }}", name, example)),
+            );
+            ok = false;
+        }
+        ok
+    }
+
+    // Fields of a struct or component (struct patterns accept either)
+    fn declared_fields(&self, name: &str) -> Option<Vec<Field>> {
+        self.structs.get(name).map(|s| s.fields.clone())
+            .or_else(|| self.components.get(name).map(|c| c.fields.clone()))
+    }
+
+    // The type of each variable a pattern binds, given the type of the value it matches
+    fn pattern_binding_types(&self, pattern: &Pattern, ty: &Type) -> Vec<(String, Type, SourceLocation)> {
+        pattern.bindings().into_iter().map(|(path, name, location)| {
+            let mut ty = ty.clone();
+            for field in path {
+                ty = match &ty {
+                    Type::Struct(owner) | Type::Component(owner) => self.declared_fields(owner)
+                        .and_then(|fields| fields.into_iter().find(|f| f.name == field))
+                        .map_or(Type::Error, |f| f.ty),
+                    _ => Type::Error,
+                };
+            }
+            (name.to_string(), ty, location)
+        }).collect()
+    }
+
+    /// Check a match and return its type: the arms' common value type when the match is used
+    /// as a value, void when it's a statement
+    fn check_match(&mut self, scrutinee: &Expression, arms: &[MatchArm], location: SourceLocation, as_value: bool) -> Type {
+        let scrutinee_type = self.check_expression(scrutinee).unwrap_or(Type::Error);
+        self.check_match_patterns(&scrutinee_type, arms);
+        let mut result: Option<(Type, SourceLocation)> = None;
+        let mut failed = false;
+        for arm in arms {
+            if as_value {
+                self.check_no_escape(&arm.body, "a match used as a value", "a match statement");
+            }
+            self.push_scope();
+            for (var_name, ty, _) in self.pattern_binding_types(&arm.pattern, &scrutinee_type) {
+                self.declare(&var_name, ty);
+            }
+            if let Some(guard) = &arm.guard {
+                let guard_type = self.check_expression(guard).unwrap_or(Type::Error);
+                if !matches!(guard_type, Type::Bool | Type::Error) {
+                    self.report_coded_error(
+                        "E0007",
+                        guard.location(),
+                        format!("Match guard must be bool, got '{}'", self.type_to_string(&guard_type)),
+                        Some("Use a boolean condition: n if n > 10 => { ... }".to_string()),
+                    );
+                }
+            }
+            for stmt in &arm.body {
+                // Statement errors are reported; keep checking the rest (error recovery)
+                let _ = self.check_statement(stmt);
+            }
+            let arm_type = match &arm.value {
+                Some(value) => self.check_expression(value).unwrap_or(Type::Error),
+                None => Type::Void,
+            };
+            self.pop_scope();
+            if !as_value || failed || matches!(arm_type, Type::Error) {
+                continue;
+            }
+
+            let value_location = arm.value.as_ref().map(|v| v.location()).unwrap_or(arm.location);
+            if matches!(arm_type, Type::Void) {
+                self.report_coded_error(
+                    "E0003",
+                    value_location,
+                    "match arm has no value (its type is 'void')".to_string(),
+                    Some("End every arm's block with the value it produces, without a trailing ';'".to_string()),
+                );
+                failed = true;
+                continue;
+            }
+            match &result {
+                None => result = Some((arm_type, value_location)),
+                Some((expected, first_location)) if !self.types_identical(expected, &arm_type) => {
+                    let message = format!(
+                        "match arms have different types: '{}' and '{}'",
+                        self.type_to_string(expected),
+                        self.type_to_string(&arm_type)
+                    );
+                    let suggestion = if is_numeric(expected) && is_numeric(&arm_type) {
+                        format!("Convert this arm explicitly, e.g. (...) as {}", self.type_to_string(expected))
+                    } else {
+                        "Every arm of a match used as a value must produce the same type".to_string()
+                    };
+                    let first_location = *first_location;
+                    self.report(
+                        value_location,
+                        message,
+                        Some(suggestion),
+                        ReportOptions::coded("E0003").secondary(first_location, "first arm's value"),
+                    );
+                    failed = true;
+                }
+                Some(_) => {}
+            }
+        }
+        if !as_value {
+            return Type::Void;
+        }
+
+        // A value is needed whatever the scrutinee is, and guards may all fail
+        let unguarded = || arms.iter().filter(|arm| arm.guard.is_none());
+        let catch_all = unguarded().any(|arm| arm.pattern.is_irrefutable());
+        let bool_literal = |value: bool| unguarded().any(|arm| pattern_covers(&arm.pattern, &Pattern::Literal(Literal::Bool(value), arm.location)));
+        let exhaustive = catch_all || (bool_literal(true) && bool_literal(false));
+        if !exhaustive {
+            self.report_coded_error(
+                "E0031",
+                location,
+                "match used as a value doesn't handle every case".to_string(),
+                Some(if arms.iter().any(|arm| arm.guard.is_some()) {
+                    "Add a catch-all arm without a guard: _ => { value }".to_string()
+                } else {
+                    "Add a catch-all arm: _ => { value }".to_string()
+                }),
+            );
+            return Type::Error;
+        }
+        match result {
+            Some((ty, _)) if !failed => ty,
+            _ => Type::Error,
+        }
+    }
+
+    /// Check a nested block in its own scope, continuing past statement errors
+    fn check_block(&mut self, stmts: &[Statement]) {
+        self.push_scope();
+        for stmt in stmts {
+            // Statement errors are reported; keep checking the rest (error recovery)
+            let _ = self.check_statement(stmt);
+        }
+        self.pop_scope();
+    }
+    
+    // A statement loop: `break value;` inside it would leave this loop, not a loop used as a value
+    fn check_loop_body(&mut self, body: &[Statement]) {
+        self.loop_breaks.push(None);
+        self.check_block(body);
+        self.loop_breaks.pop();
+    }
+
+    /// `loop { ... break value; }` as an expression: every break gives a value of the same type
+    fn check_loop_expression(&mut self, body: &[Statement], location: SourceLocation) -> Type {
+        let escape = escaping_statement(body, true);
+        if let Some((keyword, at)) = escape {
+            self.report_error(
+                at,
+                format!("'{}' can't be used inside a loop used as a value", keyword),
+                Some("Use a loop statement when the body needs to leave the function".to_string()),
+            );
+        }
+        self.loop_breaks.push(Some(Vec::new()));
+        self.check_block(body);
+        let breaks = self.loop_breaks.pop().flatten().unwrap_or_default();
+        if escape.is_some() && breaks.is_empty() {
+            return Type::Error;
+        }
+        let Some((first_type, first_location)) = breaks.first().cloned() else {
+            self.report_coded_error(
+                "E0003",
+                location,
+                "loop used as a value never breaks with one".to_string(),
+                Some("Leave the loop with the value it produces: break value;".to_string()),
+            );
+            return Type::Error;
+        };
+        if breaks.iter().any(|(ty, _)| matches!(ty, Type::Error)) {
+            return Type::Error;
+        }
+        for (ty, at) in &breaks[1..] {
+            if !self.types_compatible(&first_type, ty) {
+                self.report(
+                    *at,
+                    format!("loop breaks with '{}' here but '{}' before",
+                           self.type_to_string(ty), self.type_to_string(&first_type)),
+                    Some("Every break of a loop used as a value must give the same type".to_string()),
+                    ReportOptions::coded("E0003").secondary(first_location, "first break value"),
+                );
+                return Type::Error;
+            }
+        }
+        first_type
+    }
+
+    // A let binding. Shadowing needs a nested block: C++ rejects a redeclaration in the same scope.
+    fn declare_let(&mut self, name: &str, mutable: bool, location: SourceLocation) {
+        let first_local = self.scopes.last().map_or(0, |scope| scope.first_local);
+        let redeclared = self.scopes.last().is_some_and(|scope| scope.symbols.contains_key(name));
+        if redeclared {
+            let previous = self.locals[first_local..].iter().rev()
+                .find(|binding| binding.name == name)
+                .map(|binding| binding.location);
+            let secondary = previous.map(|location| SecondaryLocation { location, label: "previously declared here" });
+            let options = ReportOptions { secondary, ..ReportOptions::error() };
+            self.report(
+                location,
+                format!("'{}' is already declared in this scope", name),
+                Some(format!("Assign to it instead ({} = ...), or use a different name", name)),
+                options,
+            );
+        }
+        self.declare_local(name, location, false);
+        self.set_mutability(name, mutable, location, false);
+    }
+
+    fn declare_local(&mut self, name: &str, location: SourceLocation, is_param: bool) {
+        if let Some(usage) = generated_names::generated_use(name) {
+            let kind = if is_param { "parameter" } else { "variable" };
+            self.report_coded_error(
+                "E0025",
+                location,
+                format!("{} name '{}' collides with a name the generated C++ uses for {}", capitalize(kind), name, usage),
+                Some(format!("Rename the {}, e.g. to '{}'", kind, generated_names::rename_suggestion(name))),
+            );
+        }
+        self.locals.push(LocalBinding { name: name.to_string(), location, is_param, used: false });
+    }
+    
+    // Reads resolve to the most recent binding of the name, matching the symbol table
+    fn mark_used(&mut self, name: &str) {
+        if let Some(binding) = self.locals.iter_mut().rev().find(|b| b.name == name) {
+            binding.used = true;
+        }
+    }
+    
+    /// Warn about bindings declared since `start` that were never read, and forget them
+    fn end_local_scope(&mut self, start: usize) {
+        for binding in self.locals.split_off(start) {
+            if !binding.is_param {
+                self.out_of_scope.insert(binding.name.clone(), binding.location);
+            }
+            // `self` is required by the method signature; a leading underscore opts out
+            if binding.used || binding.name == "self" || binding.name.starts_with('_') {
+                continue;
+            }
+            let (message, suggestion) = if binding.is_param {
+                (format!("Parameter '{}' is never used", binding.name),
+                 format!("Remove the parameter, or rename it to '_{}' if it's needed for the signature", binding.name))
+            } else {
+                (format!("Variable '{}' is never used", binding.name),
+                 format!("Remove the binding, or rename it to '_{}' to keep the value", binding.name))
+            };
+            self.report_warning("W0001", binding.location, message, Some(suggestion));
+        }
+    }
+    
+    /// Warn about statements that follow a return, break or continue in the same block
+    fn check_reachability(&mut self, stmts: &[Statement]) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            match stmt {
+                Statement::If { then_block, else_block, .. } => {
+                    self.check_reachability(then_block);
+                    if let Some(else_block) = else_block {
+                        self.check_reachability(else_block);
                     }
                 }
-                Item::Resource(_) => {
-                    // Resources don't need type checking in second pass
-                }
-                Item::Pipeline(_) => {
-                    // Pipelines don't need type checking in second pass
+                Statement::While { body, .. } | Statement::For { body, .. } | Statement::Loop { body, .. }
+                | Statement::Block(body, _) | Statement::SpawnBatch { body, .. } => self.check_reachability(body),
+                Statement::Expression(Expression::Match { arms, .. }, _) => {
+                    for arm in arms {
+                        self.check_reachability(&arm.body);
+                    }
                 }
                 _ => {}
             }
-        }
-        
-        // Report all errors if any
-        if !self.errors.is_empty() {
-            eprintln!("\n❌ Compilation failed with {} error(s):\n", self.errors.len());
-            // Errors have already been printed by ErrorReporter, but we can add a summary
-            bail!("Compilation failed with {} error(s). See errors above.", self.errors.len());
-        }
-        
-        Ok(())
-    }
-    
-    fn type_to_string(&self, ty: &Type) -> String {
-        match ty {
-            Type::I32 => "i32".to_string(),
-            Type::I64 => "i64".to_string(),
-            Type::F32 => "f32".to_string(),
-            Type::F64 => "f64".to_string(),
-            Type::Bool => "bool".to_string(),
-            Type::String => "string".to_string(),
-            Type::Array(elem) => format!("[{}]", self.type_to_string(elem)),
-            Type::Optional(inner) => format!("?{}", self.type_to_string(inner)),
-            Type::Struct(name) => name.clone(),
-            Type::Component(name) => name.clone(),
-            Type::Query(components) => {
-                let comp_names: Vec<String> = components.iter()
-                    .map(|c| self.type_to_string(c))
-                    .collect();
-                format!("query<{}>", comp_names.join(", "))
-            },
-            Type::Void => "void".to_string(),
-            Type::Error => "<error>".to_string(),
-            _ => format!("{:?}", ty),
+            let exit = match stmt {
+                Statement::Return(..) => "return",
+                Statement::Break(..) => "break",
+                Statement::Continue(_) => "continue",
+                _ => continue,
+            };
+            if let Some(next) = stmts.get(i + 1) {
+                self.report_warning(
+                    "W0002",
+                    next.location(),
+                    format!("Unreachable code after '{}'", exit),
+                    Some(format!("Remove the statements after '{}', or move them before it", exit)),
+                );
+            }
+            return;
         }
     }
     
-    fn check_function(&mut self, func: &FunctionDef) -> Result<()> {
-        self.symbols.clear();
-        self.frame_scoped_vars.clear();  // Reset frame-scoped tracking for each function
-        
-        // Add parameters to symbol table
-        for param in &func.params {
-            self.symbols.insert(param.name.clone(), param.ty.clone());
-        }
-        
-        // Store function return type for return statement validation
-        let function_return_type = func.return_type.clone();
-        
-        // Check function body (continue even if errors occur)
-        for stmt in &func.body {
-            // Pass function return type to check_statement for return validation
-            if let Err(_) = self.check_statement_with_return_type(stmt, &function_return_type) {
-                // Continue checking other statements (error recovery)
+    fn check_param_defaults(&mut self, func_name: &str, params: &[Param]) {
+        let mut first_default: Option<&Param> = None;
+        for param in params {
+            match &param.default {
+                Some(default) => {
+                    if first_default.is_none() {
+                        first_default = Some(param);
+                    }
+                    let default_type = match self.check_expression(default) {
+                        Ok(ty) => ty,
+                        Err(_) => continue,
+                    };
+                    if !matches!(default_type, Type::Error) && !self.types_compatible(&param.ty, &default_type) {
+                        self.report_error(
+                            default.location(),
+                            format!("Default value for parameter '{}' of '{}' has type '{}', expected '{}'",
+                                   param.name, func_name,
+                                   self.type_to_string(&default_type),
+                                   self.type_to_string(&param.ty)),
+                            Some(format!("Use a {} value: {}: {} = <value>",
+                                        self.type_to_string(&param.ty), param.name, self.type_to_string(&param.ty))),
+                        );
+                    }
+                }
+                // Resources aren't passed by callers, so they can follow defaulted parameters
+                None if matches!(param.ty, Type::Res(_)) => {}
+                None => {
+                    if let Some(defaulted) = first_default {
+                        let location = defaulted.default.as_ref().map(|e| e.location()).unwrap_or_else(SourceLocation::unknown);
+                        self.report_error(
+                            location,
+                            format!("Parameter '{}' of '{}' has no default value but follows defaulted parameter '{}'",
+                                   param.name, func_name, defaulted.name),
+                            Some(format!("Move '{}' before '{}', or give it a default value", param.name, defaulted.name)),
+                        );
+                        return;
+                    }
+                }
             }
         }
-        
-        Ok(())
     }
     
     fn check_statement_with_return_type(&mut self, stmt: &Statement, expected_return_type: &Type) -> Result<()> {
@@ -416,13 +3216,15 @@ impl TypeChecker {
                     if !matches!(return_type, Type::Error) {
                         // Validate return type matches function return type
                         if !self.types_compatible(expected_return_type, &return_type) {
-                            self.report_error(
+                            self.report_coded_error(
+                                "E0006",
                                 *location,
                                 format!("Return type mismatch: function returns '{}', but got '{}'", 
                                        self.type_to_string(expected_return_type),
                                        self.type_to_string(&return_type)),
-                                Some(format!("Return a {} value: return <value>;", 
-                                            self.type_to_string(expected_return_type))),
+                                Some(self.narrowing_suggestion(expected_return_type, &return_type)
+                                    .unwrap_or_else(|| format!("Return a {} value: return <value>;", 
+                                                               self.type_to_string(expected_return_type)))),
                             );
                         }
                     }
@@ -430,7 +3232,8 @@ impl TypeChecker {
                     // Check if returning a frame-scoped variable
                     if let Expression::Variable(var_name, _) = expr {
                         if self.frame_scoped_vars.contains(var_name) {
-                            self.report_error(
+                            self.report_coded_error(
+                                "E0009",
                                 *location,
                                 format!("Cannot return frame-scoped allocation '{}': frame-scoped memory is only valid within the current frame", var_name),
                                 Some(format!("Frame-scoped allocations (from frame.alloc_array) cannot be returned from functions. Consider using heap allocation or passing the FrameArena as a parameter.")),
@@ -438,7 +3241,8 @@ impl TypeChecker {
                         }
                     } else if self.is_frame_alloc_expression(expr) {
                         // Returning the result of frame.alloc_array directly
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0009",
                             *location,
                             "Cannot return frame-scoped allocation: frame-scoped memory is only valid within the current frame".to_string(),
                             Some("Frame-scoped allocations (from frame.alloc_array) cannot be returned from functions. Consider using heap allocation or passing the FrameArena as a parameter.".to_string()),
@@ -447,7 +3251,8 @@ impl TypeChecker {
                 } else {
                     // Return without value - check if function expects void
                     if !matches!(expected_return_type, Type::Void) {
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0006",
                             *location,
                             format!("Function must return '{}', but return statement has no value", 
                                    self.type_to_string(expected_return_type)),
@@ -467,25 +3272,41 @@ impl TypeChecker {
     
     fn check_statement(&mut self, stmt: &Statement) -> Result<()> {
         match stmt {
-            Statement::Let { name, ty, value, location } => {
-                let value_type = self.check_expression(value)?;
+            Statement::Let { name, mutable, ty, value, location } => {
+                // A value that failed to check was reported; the name is still declared (as Error),
+                // so its uses don't report it as undefined
+                let value_type = self.check_expression(value).unwrap_or(Type::Error);
                 
                 // Check if this is a frame-scoped allocation
                 if self.is_frame_alloc_expression(value) {
                     self.frame_scoped_vars.insert(name.clone());
                 }
                 
-                // Track ALL variable declarations for better scope error messages
-                self.all_declared_vars.insert(name.clone(), *location);
+                self.declare_let(name, *mutable, *location);
                 
                 // If value type is Error, still add to symbol table as Error to allow recovery
+                if let Some(Type::Struct(type_name)) = ty {
+                    if self.interfaces.contains_key(type_name) {
+                        self.report_coded_error(
+                            "E0012",
+                            *location,
+                            format!("Interface '{}' cannot be used as a variable type", type_name),
+                            Some(format!("Use the implementing type, or take '{}' as a function parameter", type_name)),
+                        );
+                        self.declare(name, Type::Error);
+                        return Ok(());
+                    }
+                }
                 if let Some(declared_type) = ty {
                     if !self.types_compatible(declared_type, &value_type) && !matches!(value_type, Type::Error) {
-                        let suggestion = format!("Use a {} variable or convert: {} = {}", 
-                                                  self.type_to_string(declared_type),
-                                                  name,
-                                                  self.suggest_value_for_type(declared_type));
-                        self.report_error(
+                        let suggestion = self.narrowing_suggestion(declared_type, &value_type).unwrap_or_else(|| {
+                            format!("Use a {} variable or convert: {} = {}", 
+                                    self.type_to_string(declared_type),
+                                    name,
+                                    self.suggest_value_for_type(declared_type))
+                        });
+                        self.report_coded_error(
+                            "E0003",
                             *location,
                             format!("Type mismatch: cannot assign '{}' to '{}'", 
                                    self.type_to_string(&value_type),
@@ -493,15 +3314,32 @@ impl TypeChecker {
                             Some(suggestion),
                         );
                     }
+                    self.declarations.push((*location, name.clone(), declared_type.clone()));
                     // Add declared type to symbol table (or Error if value was Error)
                     if matches!(value_type, Type::Error) {
-                        self.symbols.insert(name.clone(), Type::Error);
+                        self.declare(name, Type::Error);
                     } else {
-                        self.symbols.insert(name.clone(), declared_type.clone());
+                        self.declare(name, declared_type.clone());
                     }
                 } else {
                     // Infer type from value (may be Error)
-                    self.symbols.insert(name.clone(), value_type);
+                    self.declarations.push((*location, name.clone(), value_type.clone()));
+                    self.declare(name, value_type);
+                }
+            }
+            Statement::Destructure { pattern, mutable, value, location } => {
+                let value_type = self.check_expression(value)?;
+                if self.check_pattern_values(pattern, &value_type) && !pattern.is_irrefutable() {
+                    self.report_error(
+                        *location,
+                        "let can't test field values: its pattern must match every value".to_string(),
+                        Some("Use a match to test fields: match p { Position { x: 0.0, .. } => { ... } _ => { ... } }".to_string()),
+                    );
+                }
+                for (name, ty, binding_location) in self.pattern_binding_types(pattern, &value_type) {
+                    self.declare_let(&name, *mutable, binding_location);
+                    self.declarations.push((binding_location, name.clone(), ty.clone()));
+                    self.declare(&name, ty);
                 }
             }
             Statement::Assign { target, value, location } => {
@@ -509,6 +3347,7 @@ impl TypeChecker {
                     Ok(ty) => ty,
                     Err(_) => Type::Error,  // Continue checking value
                 };
+                self.check_assignment_target(target, *location);
                 let value_type = match self.check_expression(value) {
                     Ok(ty) => ty,
                     Err(_) => Type::Error,  // Continue checking
@@ -517,10 +3356,13 @@ impl TypeChecker {
                 // If either is Error, skip type checking (already reported)
                 if !matches!(target_type, Type::Error) && !matches!(value_type, Type::Error) {
                     if !self.types_compatible(&target_type, &value_type) {
-                        let suggestion = format!("Ensure types match: {} should be {}", 
-                                                self.type_to_string(&value_type),
-                                                self.type_to_string(&target_type));
-                        self.report_error(
+                        let suggestion = self.narrowing_suggestion(&target_type, &value_type).unwrap_or_else(|| {
+                            format!("Ensure types match: {} should be {}", 
+                                    self.type_to_string(&value_type),
+                                    self.type_to_string(&target_type))
+                        });
+                        self.report_coded_error(
+                            "E0003",
                             *location,
                             format!("Type mismatch in assignment: cannot assign '{}' to '{}'", 
                                    self.type_to_string(&value_type),
@@ -543,7 +3385,8 @@ impl TypeChecker {
                     let is_bool_or_optional = matches!(cond_type, Type::Bool) || matches!(cond_type, Type::Optional(_));
                     
                     if !is_bool_or_optional {
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0007",
                             *location,
                             format!("If condition must be bool or optional type, got '{}'", self.type_to_string(&cond_type)),
                             Some("Use a boolean expression: if (condition == true) or if (x > 0), or check optional: if optional { ... }".to_string()),
@@ -551,17 +3394,9 @@ impl TypeChecker {
                     }
                 }
                 // Continue checking blocks even if condition had error
-                for stmt in then_block {
-                    if let Err(_) = self.check_statement(stmt) {
-                        // Continue checking other statements
-                    }
-                }
+                self.check_block(then_block);
                 if let Some(else_block) = else_block {
-                    for stmt in else_block {
-                        if let Err(_) = self.check_statement(stmt) {
-                            // Continue checking other statements
-                        }
-                    }
+                    self.check_block(else_block);
                 }
             }
             Statement::While { condition, body, location } => {
@@ -573,7 +3408,8 @@ impl TypeChecker {
                 // If condition is Error, still check body (error recovery)
                 if !matches!(cond_type, Type::Error) {
                     if !matches!(cond_type, Type::Bool) {
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0007",
                             *location,
                             format!("While condition must be bool, got '{}'", self.type_to_string(&cond_type)),
                             Some("Use a boolean expression: while (condition == true) or while (x > 0)".to_string()),
@@ -581,38 +3417,64 @@ impl TypeChecker {
                     }
                 }
                 // Continue checking body even if condition had error
-                for stmt in body {
-                    if let Err(_) = self.check_statement(stmt) {
-                        // Continue checking other statements
-                    }
-                }
+                self.check_loop_body(body);
             }
-            Statement::For { iterator, collection, body, location } => {
-                // Check that collection is a query type
-                let collection_type = match self.check_expression(collection) {
-                    Ok(ty) => ty,
-                    Err(_) => Type::Error,  // Continue checking body
+            Statement::For { iterator, collection, chunk_size, body, location } => {
+                // Check that collection is a query type (or q.children(parent), which iterates q)
+                let children = hierarchy::children_query(collection).filter(|_| !self.functions.contains_key("children"));
+                let collection_type = match children {
+                    Some((query, parent)) => self.check_children_query(query, parent),
+                    None => self.check_expression(collection).unwrap_or(Type::Error),  // Continue checking body on errors
                 };
+                if let Some(size) = chunk_size {
+                    let size_type = self.check_expression(size).unwrap_or(Type::Error);
+                    if !matches!(size_type, Type::I32 | Type::I64 | Type::Error) {
+                        self.report_coded_error(
+                            "E0003",
+                            size.location(),
+                            format!("Chunk size must be an integer, got '{}'", self.type_to_string(&size_type)),
+                            Some("Pass the number of entities per chunk: for chunk in q.chunks(1024) { ... }".to_string()),
+                        );
+                    }
+                }
                 
                 // If collection is Error, still check body (error recovery)
+                // Filtered entities aren't contiguous, so there are no slices to hand out
+                if let (Some(size), Type::Query(component_types)) = (chunk_size, &collection_type) {
+                    if let Some(filter) = component_types.iter().find(|ty| matches!(ty, Type::QueryFilter(..))) {
+                        self.report_error(
+                            size.location(),
+                            format!("chunks() can't iterate a query with a {} filter", self.type_to_string(filter)),
+                            Some("Iterate entity by entity (for entity in q) or drop the filter".to_string()),
+                        );
+                    }
+                }
                 if let Type::Query(component_types) = collection_type {
                     // Add iterator to symbol table as an "entity" type
                     // For now, we'll use a special marker - in codegen we'll handle entity access
                     // Store the query components for codegen
-                    self.symbols.insert(iterator.clone(), Type::Query(component_types.clone()));
+                    self.push_scope();
+                    let iterator_type = match chunk_size {
+                        Some(_) => Type::QueryChunk(component_types.clone()),
+                        None => Type::Query(component_types.clone()),
+                    };
+                    self.declare(iterator, iterator_type);
+                    if chunk_size.is_none() {
+                        self.entity_iterators.push(iterator.clone());
+                    }
                     
                     // Check body with iterator in scope
-                    for stmt in body {
-                        if let Err(_) = self.check_statement(stmt) {
-                            // Continue checking other statements
-                        }
-                    }
+                    self.check_loop_body(body);
                     
                     // Remove iterator from scope after loop
-                    self.symbols.remove(iterator);
+                    if chunk_size.is_none() {
+                        self.entity_iterators.pop();
+                    }
+                    self.pop_scope();
                 } else if !matches!(collection_type, Type::Error) {
                     // Only report error if collection type is not Error (Error already reported)
-                    self.report_error(
+                    self.report_coded_error(
+                        "E0010",
                         *location,
                         format!("For loop collection must be a query type, got '{}'", self.type_to_string(&collection_type)),
                         Some("Use a query: for entity in query<Position, Velocity>".to_string()),
@@ -620,9 +3482,10 @@ impl TypeChecker {
                 }
             }
             Statement::Loop { body, .. } => {
-                for stmt in body {
-                    self.check_statement(stmt)?;
-                }
+                self.check_loop_body(body);
+            }
+            Statement::SpawnBatch { count, index, body, location } => {
+                self.check_spawn_batch(count, index, body, *location);
             }
             Statement::Return(expr, location) => {
                 // Return statement validation is now handled in check_statement_with_return_type
@@ -633,14 +3496,16 @@ impl TypeChecker {
                     // Check if returning a frame-scoped variable
                     if let Expression::Variable(var_name, _) = expr {
                         if self.frame_scoped_vars.contains(var_name) {
-                            self.report_error(
+                            self.report_coded_error(
+                                "E0009",
                                 *location,
                                 format!("Cannot return frame-scoped allocation '{}': frame-scoped memory is only valid within the current frame", var_name),
                                 Some(format!("Frame-scoped allocations (from frame.alloc_array) cannot be returned from functions. Consider using heap allocation or passing the FrameArena as a parameter.")),
                             );
                         }
                     } else if self.is_frame_alloc_expression(expr) {
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0009",
                             *location,
                             "Cannot return frame-scoped allocation: frame-scoped memory is only valid within the current frame".to_string(),
                             Some("Frame-scoped allocations (from frame.alloc_array) cannot be returned from functions. Consider using heap allocation or passing the FrameArena as a parameter.".to_string()),
@@ -648,18 +3513,38 @@ impl TypeChecker {
                     }
                 }
             }
+            Statement::Expression(Expression::Match { expr, arms, location }, _) => {
+                self.check_match(expr, arms, *location, false);
+            }
             Statement::Expression(expr, ..) => {
                 self.check_expression(expr)?;
             }
             Statement::Block(stmts, ..) => {
-                for stmt in stmts {
-                    if let Err(_) = self.check_statement(stmt) {
-                        // Continue checking other statements (error recovery)
-                    }
-                }
+                self.check_block(stmts);
             }
-            Statement::Break(_) => {
-                // Break statements don't need type checking
+            Statement::Break(value, location) => {
+                let value_type = match value {
+                    Some(value) => match self.check_expression(value) {
+                        Ok(ty) => Some(ty),
+                        Err(_) => Some(Type::Error),
+                    },
+                    None => None,
+                };
+                match (self.loop_breaks.last_mut(), value_type) {
+                    (Some(Some(breaks)), Some(ty)) => breaks.push((ty, *location)),
+                    (Some(Some(_)), None) => self.report_coded_error(
+                        "E0003",
+                        *location,
+                        "break in a loop used as a value needs a value".to_string(),
+                        Some("Give the value the loop produces: break value;".to_string()),
+                    ),
+                    (_, Some(_)) => self.report_error(
+                        *location,
+                        "break with a value only leaves a loop used as a value".to_string(),
+                        Some("Use the loop as a value: let found = loop { ... break value; };".to_string()),
+                    ),
+                    (_, None) => {}
+                }
             }
             Statement::Continue(_) => {
                 // Continue statements don't need type checking
@@ -670,6 +3555,21 @@ impl TypeChecker {
                     // Continue (error recovery)
                 }
             }
+            Statement::Emit(event, location) => {
+                match event {
+                    Expression::StructLiteral { name, .. } if !self.events.contains_key(name) => {
+                        self.report_undefined_event(name, *location);
+                    }
+                    Expression::StructLiteral { .. } => {
+                        let _ = self.check_expression(event);
+                    }
+                    _ => self.report_error(
+                        *location,
+                        "emit takes an event literal".to_string(),
+                        Some("Name the event and its fields: emit Collision { a: first, b: second };".to_string()),
+                    ),
+                }
+            }
         }
         Ok(())
     }
@@ -699,14 +3599,7 @@ impl TypeChecker {
         
         if !has_correct_ext && !is_spv && !is_generic {
             let location = SourceLocation::unknown(); // TODO: get from AST
-            let stage_name = match shader.stage {
-                ShaderStage::Vertex => "vertex",
-                ShaderStage::Fragment => "fragment",
-                ShaderStage::Compute => "compute",
-                ShaderStage::Geometry => "geometry",
-                ShaderStage::TessellationControl => "tessellation_control",
-                ShaderStage::TessellationEvaluation => "tessellation_evaluation",
-            };
+            let stage_name = stage_keyword(&shader.stage);
             
             self.report_error(
                 location,
@@ -727,6 +3620,116 @@ impl TypeChecker {
         Ok(())
     }
     
+    fn declare_inline_shader(&mut self, inline: &InlineShader, stage: &ShaderStage) {
+        // The same name may be used once per stage (Basic.vert and Basic.frag)
+        let first = self.inline_shaders.iter()
+            .find(|(name, existing, _)| *name == inline.name && existing == stage)
+            .map(|(_, _, location)| *location);
+        match first {
+            Some(first) => self.report(
+                inline.location,
+                format!("Inline {} shader '{}' is declared twice", stage_keyword(stage), inline.name),
+                Some("Both would be written to the same file; rename one".to_string()),
+                ReportOptions::error().secondary(first, "first declared here"),
+            ),
+            None => self.inline_shaders.push((inline.name.clone(), stage.clone(), inline.location)),
+        }
+    }
+    
+    fn check_inline_shader_use(&mut self, name: &str, shader: &PipelineShader) {
+        let declared: Vec<&ShaderStage> = self.inline_shaders.iter()
+            .filter(|(declared, _, _)| declared == name)
+            .map(|(_, stage, _)| stage)
+            .collect();
+        if declared.contains(&&shader.stage) {
+            return;
+        }
+        let stage = stage_keyword(&shader.stage);
+        let (message, suggestion) = match declared.first() {
+            Some(other) => (
+                format!("Inline shader '{}' is a {} shader, not a {} shader", name, stage_keyword(other), stage),
+                format!("Use it as 'shader {} {}' or declare a {} shader named '{}'", stage_keyword(other), name, stage, name),
+            ),
+            None => (
+                format!("Unknown inline shader '{}'", name),
+                format!("Declare it: shader {} {} {{ glsl {{ ... }} }}, or give a path: shader {} \"path\"", stage, name, stage),
+            ),
+        };
+        self.report_error(shader.location, message, Some(suggestion));
+    }
+    
+    // Every field of a vertex_input struct becomes one vertex attribute
+    fn check_vertex_input(&mut self, input: &VertexInput) {
+        let Some(vertex) = self.structs.get(&input.struct_name).cloned() else {
+            let suggestion = if self.components.contains_key(&input.struct_name) {
+                format!("'{}' is a component; declare the vertex layout as a struct", input.struct_name)
+            } else {
+                format!("Declare it: struct {} {{ position: Vec3, uv: Vec2 }}", input.struct_name)
+            };
+            self.report_error(input.location, format!("Unknown vertex_input struct '{}'", input.struct_name), Some(suggestion));
+            return;
+        };
+        if vertex.fields.is_empty() {
+            self.report_error(input.location, format!("vertex_input struct '{}' has no fields", input.struct_name),
+                Some("Add a field per vertex attribute, e.g. position: Vec3".to_string()));
+        }
+        for field in &vertex.fields {
+            if crate::layout::vertex_format(&field.ty).is_none() {
+                let suggestion = match field.ty {
+                    Type::Mat4 => "Split a per-vertex matrix into four Vec4 fields".to_string(),
+                    _ => "Vertex attributes are f32, f64, i32, i64, Vec2, Vec3 or Vec4".to_string(),
+                };
+                self.report_error(
+                    input.location,
+                    format!("Field '{}' of vertex_input struct '{}' has type '{}', which isn't a vertex attribute type", field.name, input.struct_name, self.type_to_string(&field.ty)),
+                    Some(suggestion),
+                );
+            }
+        }
+    }
+    
+    // Push constants are laid out as the shaders read them and must fit the 128 bytes every device has
+    fn check_push_constants(&mut self, pipeline: &PipelineDef, block: &PushConstants) {
+        for field in &block.fields {
+            if !matches!(field.ty, Type::F32 | Type::F64 | Type::I32 | Type::I64 | Type::Bool | Type::Vec2 | Type::Vec3 | Type::Vec4 | Type::Mat4) {
+                self.report_error(
+                    block.location,
+                    format!("Push constant '{}' of pipeline '{}' has type '{}'", field.name, pipeline.name, self.type_to_string(&field.ty)),
+                    Some("Push constants are scalars, vectors or Mat4; put larger data in a uniform or storage binding".to_string()),
+                );
+            }
+        }
+        let Ok((_, size)) = crate::layout::push_constant_offsets(&block.fields) else { return };
+        if size > crate::layout::MAX_PUSH_CONSTANT_BYTES {
+            self.report_coded_error(
+                "E0027",
+                block.location,
+                format!("push_constants of pipeline '{}' take {} bytes, more than the {} every GPU supports", pipeline.name, size, crate::layout::MAX_PUSH_CONSTANT_BYTES),
+                Some("Move the larger fields (matrices, arrays of values) into a uniform binding".to_string()),
+            );
+        }
+    }
+    
+    // A uniform block holds what std140 lays out the same in C++ and GLSL, and at least one field
+    fn check_uniform(&mut self, uniform: &UniformDef) {
+        if uniform.fields.is_empty() {
+            self.report_error(
+                uniform.location,
+                format!("Uniform '{}' has no fields", uniform.name),
+                Some("Add the values its shaders read, e.g. uniform Camera { view_proj: Mat4 }".to_string()),
+            );
+        }
+        for field in &uniform.fields {
+            if !matches!(field.ty, Type::F32 | Type::F64 | Type::I32 | Type::I64 | Type::Bool | Type::Vec2 | Type::Vec3 | Type::Vec4 | Type::Mat4) {
+                self.report_error(
+                    uniform.location,
+                    format!("Field '{}' of uniform '{}' has type '{}'", field.name, uniform.name, self.type_to_string(&field.ty)),
+                    Some("Uniform fields are scalars, vectors or Mat4; put arrays of values in a storage binding".to_string()),
+                );
+            }
+        }
+    }
+    
     fn suggest_value_for_type(&self, ty: &Type) -> String {
         match ty {
             Type::I32 => "0".to_string(),
@@ -741,20 +3744,194 @@ impl TypeChecker {
     
     fn check_expression(&mut self, expr: &Expression) -> Result<Type> {
         match expr {
-            Expression::Literal(lit, _) => {
-                Ok(match lit {
-                    Literal::Int(_) => Type::I32,
-                    Literal::Float(_) => Type::F32,
-                    Literal::Bool(_) => Type::Bool,
-                    Literal::String(_) => Type::String,
-                })
+            Expression::Literal(lit, _) => Ok(literal_type(lit)),
+            Expression::Cast { expr, ty, location } => {
+                let from = self.check_expression(expr)?;
+                if matches!(from, Type::Error) {
+                    return Ok(Type::Error);
+                }
+                // Numbers convert to each other; bools convert to integers (0 or 1)
+                let valid = is_numeric(ty) && (is_numeric(&from) || (matches!(from, Type::Bool) && matches!(ty, Type::I32 | Type::I64)));
+                if !valid {
+                    self.report_coded_error(
+                        "E0016",
+                        *location,
+                        format!("Cannot cast '{}' to '{}'", self.type_to_string(&from), self.type_to_string(ty)),
+                        Some("'as' converts between i32, i64, f32 and f64 (and from bool to an integer)".to_string()),
+                    );
+                    return Ok(Type::Error);
+                }
+                Ok(ty.clone())
+            }
+            Expression::LayoutOf { query, ty, field, location } => {
+                if let Type::Struct(name) | Type::Component(name) = ty {
+                    let declared = self.structs.get(name).map(|s| &s.fields).or_else(|| self.components.get(name).map(|c| &c.fields));
+                    let Some(declared) = declared else {
+                        let candidates: Vec<String> = self.structs.keys().chain(self.components.keys()).cloned().collect();
+                        let suggestion = find_closest_match(name, &candidates, 3).map(|closest| format!("Did you mean '{}'?", closest));
+                        self.report_error(*location, format!("Unknown type '{}' in {}", name, query.name()), suggestion);
+                        return Ok(Type::Error);
+                    };
+                    if let Some(field) = field.as_ref().filter(|field| !declared.iter().any(|f| &f.name == *field)) {
+                        let candidates: Vec<String> = declared.iter().map(|f| f.name.clone()).collect();
+                        let suggestion = match find_closest_match(field, &candidates, 3) {
+                            Some(closest) => format!("Did you mean '{}'?", closest),
+                            None => format!("Available fields: {}", candidates.join(", ")),
+                        };
+                        self.report_coded_error("E0029", *location, format!("'{}' has no field named '{}'", name, field), Some(suggestion));
+                        return Ok(Type::Error);
+                    }
+                } else if field.is_some() {
+                    self.report_error(
+                        *location,
+                        format!("offset_of needs a struct or component, got '{}'", self.type_to_string(ty)),
+                        Some("Use offset_of<Position>(x) with the struct that declares the field".to_string()),
+                    );
+                    return Ok(Type::Error);
+                }
+                // The value itself is computed when it's needed; here we only check a layout exists
+                if let Err(error) = const_eval::evaluate(expr, &|_| None, &self.layouts()) {
+                    self.report_coded_error("E0018", error.location, error.message, error.suggestion);
+                    return Ok(Type::Error);
+                }
+                Ok(Type::I32)
+            }
+            Expression::GpuSync { direction, component, location } => {
+                let cuda_component = match component {
+                    Type::Struct(name) | Type::Component(name) => self.components.get(name).filter(|c| c.is_cuda),
+                    _ => None,
+                };
+                if cuda_component.is_none() {
+                    let suggestion = match component {
+                        Type::Struct(name) | Type::Component(name) if self.components.contains_key(name) => {
+                            format!("Mark it so it gets a device mirror: @[cuda] component {} {{ ... }}", name)
+                        }
+                        _ => format!("Sync a @[cuda] component: {}<Position>()", direction.name()),
+                    };
+                    self.report_error(
+                        *location,
+                        format!("{} needs a @[cuda] component, got '{}'", direction.name(), self.type_to_string(component)),
+                        Some(suggestion),
+                    );
+                    return Ok(Type::Error);
+                }
+                Ok(Type::Void)
+            }
+            Expression::FrameAlloc { arena, element, count, location } => {
+                let arena_type = self.check_expression(arena)?;
+                let count_type = self.check_expression(count)?;
+                if !frame_arena::is_arena(&arena_type) && !matches!(arena_type, Type::Error) {
+                    self.report_error(
+                        arena.location(),
+                        format!("alloc_array needs a {}, got '{}'", frame_arena::TYPE, self.type_to_string(&arena_type)),
+                        Some("Allocate from the built-in arena (frame.alloc_array<Vec3>(100)) or a FrameArena parameter".to_string()),
+                    );
+                    return Ok(Type::Error);
+                }
+                if !matches!(count_type, Type::I32 | Type::I64 | Type::Error) {
+                    self.report_error(
+                        count.location(),
+                        format!("alloc_array needs an integer element count, got '{}'", self.type_to_string(&count_type)),
+                        Some(format!("Convert it: frame.alloc_array<{}>(n as i32)", self.type_to_string(element))),
+                    );
+                    return Ok(Type::Error);
+                }
+                // The arena releases memory without running destructors
+                let problem = match self.layouts().layout(element) {
+                    Err(reason) => Some(reason),
+                    Ok(_) if !self.layouts().is_standard_layout(element) => {
+                        Some("it owns memory (a string, array or function) that the arena would never free".to_string())
+                    }
+                    Ok(_) => None,
+                };
+                if let Some(problem) = problem {
+                    self.report_error(
+                        *location,
+                        format!("frame.alloc_array can't hold '{}': {}", self.type_to_string(element), problem),
+                        Some("Frame allocations hold numbers, vectors and structs made of them".to_string()),
+                    );
+                    return Ok(Type::Error);
+                }
+                // A hot DLL would get its own copy of the arena, which no loop resets
+                let builtin = matches!(arena.as_ref(), Expression::Variable(name, _) if name == frame_arena::OBJECT)
+                    && !self.scopes.iter().any(|scope| scope.symbols.contains_key(frame_arena::OBJECT));
+                let hot_code = self.hot_system.as_ref().map(|system| format!("system '{}'", system))
+                    .or_else(|| self.hot_function.as_ref().map(|f| format!("fn '{}'", f)));
+                if let (true, Some(hot_code)) = (builtin, hot_code) {
+                    self.report_error(
+                        *location,
+                        format!("@hot {} can't allocate from the built-in frame arena", hot_code),
+                        Some(format!("Take the arena as a parameter (arena: {}) and pass it `frame`", frame_arena::TYPE)),
+                    );
+                    return Ok(Type::Error);
+                }
+                Ok(Type::Array(Box::new(element.clone())))
+            }
+            Expression::If { condition, then_branch, else_branch, location } => {
+                let cond_type = self.check_expression(condition).unwrap_or(Type::Error);
+                if !matches!(cond_type, Type::Bool | Type::Error) {
+                    self.report_coded_error(
+                        "E0007",
+                        condition.location(),
+                        format!("If condition must be bool, got '{}'", self.type_to_string(&cond_type)),
+                        Some("Use a boolean expression: if x > 0 { ... } else { ... }".to_string()),
+                    );
+                }
+                let then_type = self.check_value_block(then_branch, "an if-expression", "an if statement");
+                let else_type = self.check_value_block(else_branch, "an if-expression", "an if statement");
+                if matches!(then_type, Type::Error) || matches!(else_type, Type::Error) {
+                    return Ok(Type::Error);
+                }
+                if matches!(then_type, Type::Void) {
+                    self.report_coded_error(
+                        "E0003",
+                        then_branch.value.location(),
+                        "if-expression branch has no value (its type is 'void')".to_string(),
+                        Some("Use an if statement instead, or end both branches with a value".to_string()),
+                    );
+                    return Ok(Type::Error);
+                }
+                if !self.types_identical(&then_type, &else_type) {
+                    let suggestion = if is_numeric(&then_type) && is_numeric(&else_type) {
+                        format!("Convert one branch explicitly, e.g. (...) as {}", self.type_to_string(&then_type))
+                    } else {
+                        "Both branches of an if-expression must produce the same type".to_string()
+                    };
+                    self.report(
+                        else_branch.value.location(),
+                        format!(
+                            "if and else branches have different types: '{}' and '{}'",
+                            self.type_to_string(&then_type),
+                            self.type_to_string(&else_type)
+                        ),
+                        Some(suggestion),
+                        ReportOptions::coded("E0003").secondary(*location, "if-expression starts here"),
+                    );
+                    return Ok(Type::Error);
+                }
+                Ok(then_type)
+            }
+            Expression::Loop { body, location } => Ok(self.check_loop_expression(body, *location)),
+            Expression::Block { body, .. } => {
+                let ty = self.check_value_block(body, "a block expression", "plain statements");
+                if matches!(ty, Type::Void) {
+                    self.report_coded_error(
+                        "E0003",
+                        body.value.location(),
+                        "block expression has no value (its last expression's type is 'void')".to_string(),
+                        Some("Use plain statements instead, or end the block with the value it produces".to_string()),
+                    );
+                    return Ok(Type::Error);
+                }
+                Ok(ty)
             }
             Expression::StringInterpolation { parts, location } => {
                 // Validate all variables in interpolation exist and are valid types
                 for part in parts {
                     if let crate::ast::StringInterpolationPart::Variable(var_name) = part {
                         // Check if variable exists
-                        if let Some(var_type) = self.symbols.get(var_name) {
+                        self.mark_used(var_name);
+                        if let Some(var_type) = self.lookup(var_name) {
                             // Validate that the type can be converted to string
                             // Allow numeric types, bool, and string
                             match var_type {
@@ -774,14 +3951,15 @@ impl TypeChecker {
                             }
                         } else {
                             // Find similar variable names
-                            let candidates: Vec<String> = self.symbols.keys().cloned().collect();
+                            let candidates = self.visible_names();
                             let suggestion = if let Some(closest) = find_closest_match(var_name, &candidates, 3) {
                                 format!("Did you mean '{}'? Use: {{}}", closest)
                             } else {
                                 format!("Did you mean to declare it first? Use: let {}: Type = value;", var_name)
                             };
                             
-                            self.report_error(
+                            self.report_coded_error(
+                                "E0001",
                                 *location,
                                 format!("Undefined variable '{}' in string interpolation", var_name),
                                 Some(suggestion),
@@ -793,62 +3971,56 @@ impl TypeChecker {
                 }
                 Ok(Type::String)
             }
-            Expression::Match { expr, arms, location: _ } => {
-                // Type check the expression being matched
-                let expr_type = self.check_expression(expr)?;
-                
-                // Validate all arms
-                let mut _has_wildcard = false;
-                
-                for arm in arms {
-                    // Type check the body
-                    // Create a new scope for pattern variables
-                    let old_symbols = self.symbols.clone();
-                    
-                    // If pattern binds a variable, add it to scope
-                    if let crate::ast::Pattern::Variable(var_name, _) = &arm.pattern {
-                        self.symbols.insert(var_name.clone(), expr_type.clone());
-                    }
-                    
-                    // Check body statements
-                    for stmt in &arm.body {
-                        self.check_statement(stmt)?;
-                    }
-                    
-                    // Restore symbols
-                    self.symbols = old_symbols;
-                    
-                    // Check for wildcard
-                    if matches!(arm.pattern, crate::ast::Pattern::Wildcard(_)) {
-                        _has_wildcard = true;
+            Expression::Match { expr, arms, location } => Ok(self.check_match(expr, arms, *location, true)),
+            Expression::Variable(name, location) => {
+                self.mark_used(name);
+                let hot_code = self.hot_system.as_ref().map(|system| format!("system '{}'", system))
+                    .or_else(|| self.hot_function.as_ref().map(|f| format!("fn '{}'", f)));
+                if let Some(hot_code) = hot_code.filter(|_| self.tweaks.contains(name)) {
+                    if !self.scopes.iter().any(|scope| scope.symbols.contains_key(name)) {
+                        self.report_error(
+                            *location,
+                            format!("@hot {} can't read @tweak const '{}'", hot_code, name),
+                            Some("Its DLL would get its own copy that tweaks don't reach; pass the value in as a parameter".to_string()),
+                        );
+                        return Ok(Type::Error);
                     }
                 }
-                
-                // Warn if no wildcard and not exhaustive (for enums)
-                // For now, just validate patterns are compatible
-                
-                // Return type is the common type of all arm bodies, or void if no return
-                // For now, return void (match as statement)
-                // TODO: Support match as expression with return types
-                Ok(Type::Void)
-            }
-            Expression::Variable(name, location) => {
-                match self.symbols.get(name) {
+                match self.lookup(name) {
                     Some(ty) => Ok(ty.clone()),
+                    // A function name used as a value is a function pointer
+                    None if self.functions.contains_key(name) => {
+                        let func = &self.functions[name];
+                        let params = func.params.iter().map(|p| p.ty.clone()).collect();
+                        Ok(Type::Function(params, Box::new(func.return_type.clone())))
+                    }
                     None => {
+                        // Declared later in the function: point at the declaration
+                        let declared_later = self.function_lets.iter()
+                            .find(|(decl_name, decl)| decl_name == name && (decl.line, decl.column) > (location.line, location.column))
+                            .map(|(_, decl)| *decl);
+                        if let (Some(decl_location), None) = (declared_later, self.out_of_scope.get(name)) {
+                            self.report(
+                                *location,
+                                format!("Variable '{}' is used before its declaration", name),
+                                Some(format!("Move 'let {} ...' above this line", name)),
+                                ReportOptions::coded("E0001").secondary(decl_location, "declared here"),
+                            );
+                            return Ok(Type::Error);
+                        }
+                        
                         // Check if variable was declared somewhere else (scope issue)
-                        let suggestion = if let Some(decl_location) = self.all_declared_vars.get(name) {
-                            // Variable was declared but is not in current scope
-                            // This means it was declared in a nested scope (like inside an if block)
+                        let suggestion = if let Some(decl_location) = self.out_of_scope.get(name) {
+                            // Declared in a nested block (like an if body) that has already ended
                             format!(
                                 "Variable '{}' was declared at line {}, but it's in a different scope.\n\
-                                 \x1b[36m💡 Fix:\x1b[0m Move the declaration (let {}: Type = ...) BEFORE the 'if' block\n\
-                                 \x1b[36m   so it's accessible in both the if block and where you're using it now.\x1b[0m",
+                                 Move the declaration (let {}: Type = ...) BEFORE the block it's declared in\n\
+                                 so it's accessible in both the block and where you're using it now.",
                                 name, decl_location.line, name
                             )
                         } else {
                             // Variable was never declared - check for typos
-                            let candidates: Vec<String> = self.symbols.keys().cloned().collect();
+                            let candidates = self.visible_names();
                             if let Some(closest) = find_closest_match(name, &candidates, 3) {
                                 format!("Did you mean '{}'? Use: {}", closest, closest)
                             } else {
@@ -856,7 +4028,8 @@ impl TypeChecker {
                             }
                         };
                         
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0001",
                             *location,
                             format!("Undefined variable: '{}'", name),
                             Some(suggestion),
@@ -877,9 +4050,9 @@ impl TypeChecker {
                 
                 match op {
                     BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
-                        if matches!(left_type, Type::I32 | Type::I64 | Type::F32 | Type::F64) &&
-                           matches!(right_type, Type::I32 | Type::I64 | Type::F32 | Type::F64) {
-                            Ok(left_type) // Simplified: return left type
+                        if is_numeric(&left_type) && is_numeric(&right_type) {
+                            // Mixed operands promote like C++: i32 < i64 < f32 < f64
+                            Ok(if numeric_rank(&right_type) > numeric_rank(&left_type) { right_type } else { left_type })
                         } else {
                             self.report_error(
                                 *location,
@@ -914,6 +4087,10 @@ impl TypeChecker {
             }
             Expression::UnaryOp { op, expr, location } => {
                 let expr_type = self.check_expression(expr)?;
+                // The operand's error is already reported
+                if matches!(expr_type, Type::Error) {
+                    return Ok(Type::Error);
+                }
                 match op {
                     UnaryOp::Neg => {
                         if matches!(expr_type, Type::I32 | Type::I64 | Type::F32 | Type::F64) {
@@ -924,7 +4101,7 @@ impl TypeChecker {
                                 format!("Negation requires numeric type, got '{}'", self.type_to_string(&expr_type)),
                                 Some("Use a numeric type (i32, i64, f32, f64) for negation".to_string()),
                             );
-                            bail!("Negation requires numeric type");
+                            Ok(Type::Error)
                         }
                     }
                     UnaryOp::Not => {
@@ -936,12 +4113,22 @@ impl TypeChecker {
                                 format!("Not requires bool type, got '{}'", self.type_to_string(&expr_type)),
                                 Some("Use a bool type for logical not (!)".to_string()),
                             );
-                            bail!("Not requires bool type");
+                            Ok(Type::Error)
                         }
                     }
                 }
             }
             Expression::Call { name, args, location } => {
+                if let Some(hot) = self.hot_function.clone().filter(|_| self.host_functions.contains(name)) {
+                    if !self.scopes.iter().any(|scope| scope.symbols.contains_key(name)) {
+                        self.report_error(
+                            *location,
+                            format!("@hot fn '{}' can't call '{}': it's compiled into the executable, not the hot functions DLL", hot, name),
+                            Some(format!("Mark '{}' @hot too, or pass what it computes in as a parameter", name)),
+                        );
+                    }
+                }
+                
                 // Handle built-in print function
                 if name == "print" {
                     // Print can take any number of arguments of any type
@@ -1076,10 +4263,294 @@ impl TypeChecker {
                 if let Ok(return_type) = imgui_result {
                     return Ok(return_type);
                 }
+
+                // Handle transfer-queue synchronization built-ins (stdlib/transfer_queue.h)
+                let transfer_signature = match name.as_str() {
+                    "submit_transfer" => Some((vec![], Type::Struct("TransferTicket".to_string()))),
+                    "transfer_ready" => Some((vec![Type::Struct("TransferTicket".to_string())], Type::Bool)),
+                    "wait_transfer" => Some((vec![Type::Struct("TransferTicket".to_string())], Type::Void)),
+                    "heidic_has_dedicated_transfer_queue" => Some((vec![], Type::Bool)),
+                    _ => None,
+                };
+                if let Some((param_types, return_type)) = transfer_signature {
+                    if !self.functions.contains_key(name) {
+                        if args.len() != param_types.len() {
+                            self.report_error(
+                                *location,
+                                format!("Argument count mismatch for '{}': expected {} arguments, got {}",
+                                       name, param_types.len(), args.len()),
+                                Some("Usage: let t: TransferTicket = submit_transfer(); wait_transfer(t);".to_string()),
+                            );
+                            return Ok(Type::Error);
+                        }
+                        for (arg, expected) in args.iter().zip(param_types.iter()) {
+                            let arg_type = self.check_expression(arg)?;
+                            if !self.types_compatible(expected, &arg_type) {
+                                self.report_error(
+                                    *location,
+                                    format!("'{}' expects a '{}', got '{}'",
+                                           name, self.type_to_string(expected), self.type_to_string(&arg_type)),
+                                    Some("Pass the ticket returned by submit_transfer()".to_string()),
+                                );
+                                return Ok(Type::Error);
+                            }
+                        }
+                        return Ok(return_type);
+                    }
+                }
+
+                // Handle GPU query intrinsics (stdlib/gpu_query.h): the first argument names a gpu_query pool
+                let query_return = match name.as_str() {
+                    "begin_query" | "end_query" => Some(Type::Void),
+                    "query_result" => Some(Type::I64),
+                    _ => None,
+                };
+                if let Some(return_type) = query_return {
+                    if !self.functions.contains_key(name) {
+                        return self.check_query_intrinsic(name, args, *location, return_type);
+                    }
+                }
+
+                // Handle GPU buffer intrinsics (stdlib/gpu_buffer.h): readback(buffer): [T], upload(buffer, data)
+                if (name == "readback" || name == "upload") && !self.functions.contains_key(name) {
+                    return self.check_buffer_intrinsic(name, args, *location);
+                }
+
+                // Hot-reload console command (generated with the @hot system runtime)
+                if name == "rollback_last_reload" && !self.functions.contains_key(name) {
+                    if !self.has_hot_systems {
+                        self.report_error(
+                            *location,
+                            "'rollback_last_reload' needs a @hot system to roll back".to_string(),
+                            Some("Mark the system you're live-editing with @hot: @hot system Name { ... }".to_string()),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    if !args.is_empty() {
+                        self.report_coded_error(
+                            "E0004",
+                            *location,
+                            format!("Argument count mismatch for 'rollback_last_reload': expected 0 arguments, got {}", args.len()),
+                            Some("Usage: rollback_last_reload(); returns false when there's nothing to roll back to".to_string()),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    return Ok(Type::Bool);
+                }
+
+                // update_binding_<pipeline>(N, buffer) with a literal N must name a buffer binding
+                if let (Some((pipeline, buffers)), Some(Expression::Literal(Literal::Int(binding, _), binding_location))) = (self.buffer_bindings.get(name), args.first()) {
+                    if !u32::try_from(*binding).is_ok_and(|binding| buffers.contains(&binding)) {
+                        let listed: Vec<String> = buffers.iter().map(|binding| binding.to_string()).collect();
+                        let suggestion = if listed.is_empty() {
+                            format!("Pipeline '{}' has no uniform or storage bindings", pipeline)
+                        } else {
+                            format!("Uniform and storage bindings of pipeline '{}': {}", pipeline, listed.join(", "))
+                        };
+                        self.report_error(
+                            *binding_location,
+                            format!("Binding {} of pipeline '{}' is not a uniform or storage buffer binding", binding, pipeline),
+                            Some(suggestion),
+                        );
+                    }
+                }
+
+                // @tweak const editor and persistence (stdlib/tweak.h)
+                if (name == "tweak_panel" || name == "tweak_file") && !self.functions.contains_key(name) {
+                    return self.check_tweak_intrinsic(name, args, *location);
+                }
+
+                // Run every scheduled system once, in stage and before/after order
+                if name == "run_systems" && !self.functions.contains_key(name) {
+                    if !args.is_empty() {
+                        self.report_coded_error(
+                            "E0004",
+                            *location,
+                            format!("Argument count mismatch for 'run_systems': expected 0 arguments, got {}", args.len()),
+                            Some("Usage: run_systems(); once per frame (systems get the frame time themselves)".to_string()),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    return Ok(Type::Void);
+                }
+
+                // Pre-size entity storage before a large spawn (stdlib/entity_storage.h)
+                if name == "reserve_entities" && !self.functions.contains_key(name) {
+                    if args.len() != 1 {
+                        self.report_coded_error(
+                            "E0004",
+                            *location,
+                            format!("Argument count mismatch for 'reserve_entities': expected 1 argument, got {}", args.len()),
+                            Some("Usage: reserve_entities(100000); before spawning that many entities".to_string()),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    let arg_type = self.check_expression(&args[0])?;
+                    if !matches!(arg_type, Type::I32 | Type::I64 | Type::Error) {
+                        self.report_coded_error(
+                            "E0005",
+                            args[0].location(),
+                            format!("'reserve_entities' expects an integer, got '{}'", self.type_to_string(&arg_type)),
+                            Some("Usage: reserve_entities(100000); before spawning that many entities".to_string()),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    return Ok(Type::Void);
+                }
+
+                // Saved worlds: every entity's components written to or read from one file
+                if (name == "save_world" || name == "load_world") && !self.functions.contains_key(name) {
+                    let usage = format!("Usage: if !{}(\"slot1.world\") {{ ... }}", name);
+                    if args.len() != 1 {
+                        self.report_coded_error(
+                            "E0004",
+                            *location,
+                            format!("Argument count mismatch for '{}': expected 1 argument, got {}", name, args.len()),
+                            Some(usage),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    let arg_type = self.check_expression(&args[0])?;
+                    if !matches!(arg_type, Type::String | Type::Error) {
+                        self.report_coded_error(
+                            "E0005",
+                            args[0].location(),
+                            format!("'{}' expects a file path (string), got '{}'", name, self.type_to_string(&arg_type)),
+                            Some(usage),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    if name == "save_world" {
+                        let mut components: Vec<&ComponentDef> = self.components.values().collect();
+                        components.sort_by(|a, b| a.name.cmp(&b.name));
+                        let unsaved: Vec<(String, String, String)> = components.into_iter()
+                            .filter_map(|c| save_schema::unsaved_world_field(c).map(|(field, reason)| (c.name.clone(), field.name.clone(), reason)))
+                            .collect();
+                        for (component, field, reason) in unsaved {
+                            self.report_warning(
+                                "W0004",
+                                *location,
+                                format!("save_world leaves out component '{}': field '{}': {}", component, field, reason),
+                                Some(format!("Keep {}'s saved state in fields of other types, or rebuild it after load_world", component)),
+                            );
+                        }
+                    }
+                    return Ok(Type::Bool);
+                }
+
+                // Entity storage memory per component: memory_report() or memory_report("memory.json")
+                if name == "memory_report" && !self.functions.contains_key(name) {
+                    let usage = "Usage: let bytes = memory_report(); or memory_report(\"memory.json\");".to_string();
+                    if args.len() > 1 {
+                        self.report_coded_error(
+                            "E0004",
+                            *location,
+                            format!("Argument count mismatch for 'memory_report': expected 0 or 1 arguments, got {}", args.len()),
+                            Some(usage),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    if let Some(arg) = args.first() {
+                        let arg_type = self.check_expression(arg)?;
+                        if !matches!(arg_type, Type::String | Type::Error) {
+                            self.report_coded_error(
+                                "E0005",
+                                arg.location(),
+                                format!("'memory_report' expects a JSON file path (string), got '{}'", self.type_to_string(&arg_type)),
+                                Some(usage),
+                            );
+                            return Ok(Type::Error);
+                        }
+                    }
+                    return Ok(Type::I64);
+                }
+
+                // Network replication (stdlib/net.h)
+                if replication::FUNCTIONS.contains(&name.as_str()) && !self.functions.contains_key(name) {
+                    return self.check_net_call(name, args, *location);
+                }
+
+                // Entity hierarchy (stdlib/hierarchy.h): child.set_parent(parent), child.clear_parent()
+                if hierarchy::METHODS.contains(&name.as_str()) && !self.functions.contains_key(name) {
+                    return self.check_hierarchy_call(name, args, *location);
+                }
+
+                // Spatial sounds (@[spatial] resources): play_resource_step_at(entity)
+                if self.spatial_plays.contains(name) && !self.functions.contains_key(name) {
+                    return self.check_spatial_play(name, args, *location);
+                }
+
+                // Handle test and frame capture intrinsics (stdlib/frame_capture.h)
+                let test_signature = match name.as_str() {
+                    "expect" => Some((vec![Type::Bool], Type::Void)),
+                    "expect_frame" => Some((vec![Type::String, Type::F32], Type::Bool)),
+                    "screenshot" => Some((vec![Type::String], Type::Void)),
+                    _ => None,
+                };
+                if let Some((param_types, return_type)) = test_signature {
+                    if !self.functions.contains_key(name) {
+                        return self.check_test_intrinsic(name, args, *location, param_types, return_type);
+                    }
+                }
+
+                // Calling a callback stored in a variable or parameter of function type
+                if let Some(Type::Function(param_types, return_type)) = self.lookup(name).cloned() {
+                    self.mark_used(name);
+                    if args.len() != param_types.len() {
+                        self.report_coded_error(
+                            "E0004",
+                            *location,
+                            format!("Argument count mismatch for callback '{}': expected {} arguments, got {}",
+                                   name, param_types.len(), args.len()),
+                            Some(format!("Call with {} arguments: {}(...)", param_types.len(), name)),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    let mut has_error = false;
+                    for (i, (arg, param_ty)) in args.iter().zip(param_types.iter()).enumerate() {
+                        let arg_type = self.check_expression(arg)?;
+                        if matches!(arg_type, Type::Error) {
+                            has_error = true;
+                            continue;
+                        }
+                        if !self.types_compatible(param_ty, &arg_type) {
+                            self.report_coded_error(
+                                "E0005",
+                                arg.location(),
+                                format!("Argument {} type mismatch in callback call '{}': expected '{}', got '{}'",
+                                       i + 1, name,
+                                       self.type_to_string(param_ty),
+                                       self.type_to_string(&arg_type)),
+                                Some(format!("Use a {} value for argument {}", self.type_to_string(param_ty), i + 1)),
+                            );
+                            has_error = true;
+                        }
+                    }
+                    if has_error {
+                        return Ok(Type::Error);
+                    }
+                    return Ok(*return_type);
+                }
                 
                 // Clone function def to avoid borrow checker issues
                 let func = match self.functions.get(name) {
                     Some(f) => f.clone(),
+                    None if input_map::method(name).is_some() && !self.functions.contains_key(&format!("{}.attach", input_map::OBJECT)) => {
+                        self.report_error(
+                            *location,
+                            format!("'{}' reads an input action, but the program has no input block", name),
+                            Some(format!("Declare the action: input {{ {}: Key.Space; }}", input_map::method(name).unwrap_or_default())),
+                        );
+                        return Ok(Type::Error);
+                    }
+                    None if WindowConfig::FUNCTIONS.iter().any(|(function, _)| function == name) => {
+                        self.report_error(
+                            *location,
+                            format!("'{}' comes with a window block, but the program has none", name),
+                            Some("Declare the window: window { title: \"Game\", width: 1280, height: 720 }".to_string()),
+                        );
+                        return Ok(Type::Error);
+                    }
                     None => {
                         // Find similar function names
                         let candidates: Vec<String> = self.functions.keys().cloned().collect();
@@ -1089,7 +4560,8 @@ impl TypeChecker {
                             format!("Did you mean to declare it? Use: fn {}() {{ ... }}", name)
                         };
                         
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0002",
                             *location,
                             format!("Undefined function: '{}'", name),
                             Some(suggestion),
@@ -1099,19 +4571,28 @@ impl TypeChecker {
                     }
                 };
                 
-                if args.len() != func.params.len() {
-                    self.report_error(
+                // Trailing parameters with default values may be omitted, and res<T> ones always are
+                let params: Vec<&Param> = func.params.iter().filter(|p| !matches!(p.ty, Type::Res(_))).collect();
+                let required = params.iter().filter(|p| p.default.is_none()).count();
+                if args.len() < required || args.len() > params.len() {
+                    let expected = if required == params.len() {
+                        format!("{}", required)
+                    } else {
+                        format!("{} to {}", required, params.len())
+                    };
+                    self.report_coded_error(
+                        "E0004",
                         *location,
                         format!("Argument count mismatch for function '{}': expected {} arguments, got {}", 
-                               name, func.params.len(), args.len()),
-                        Some(format!("Call with {} arguments: {}(...)", func.params.len(), name)),
+                               name, expected, args.len()),
+                        Some(format!("Call with {} arguments: {}(...)", expected, name)),
                     );
                     // Return Error type instead of bailing - allows error recovery
                     return Ok(Type::Error);
                 }
                 
                 let mut has_error = false;
-                for (i, (arg, param)) in args.iter().zip(func.params.iter()).enumerate() {
+                for (i, (arg, param)) in args.iter().zip(params.iter()).enumerate() {
                     let arg_type = self.check_expression(arg)?;
                     // If argument is Error type, propagate
                     if matches!(arg_type, Type::Error) {
@@ -1119,15 +4600,19 @@ impl TypeChecker {
                         continue;
                     }
                     if !self.types_compatible(&param.ty, &arg_type) {
-                        self.report_error(
+                        self.report_coded_error(
+                            "E0005",
                             arg.location(),
                             format!("Argument {} type mismatch in function call '{}': expected '{}', got '{}'", 
                                    i + 1, name,
                                    self.type_to_string(&param.ty),
                                    self.type_to_string(&arg_type)),
-                            Some(format!("Use a {} value for argument {}", self.type_to_string(&param.ty), i + 1)),
+                            Some(self.narrowing_suggestion(&param.ty, &arg_type)
+                                .unwrap_or_else(|| format!("Use a {} value for argument {}", self.type_to_string(&param.ty), i + 1))),
                         );
                         has_error = true;
+                    } else if self.borrows_mutably(name, param) {
+                        self.check_mutable_borrow(arg, name);
                     }
                 }
                 
@@ -1160,6 +4645,93 @@ impl TypeChecker {
                     }
                 }
                 
+                // entity.Component inside a query loop refers to the component itself
+                if let Type::Query(component_types) = &object_type {
+                    let in_query = component_types.iter().any(|ty| matches!(ty, Type::Struct(n) | Type::Component(n) if n == member));
+                    if in_query {
+                        return Ok(Type::Struct(member.clone()));
+                    }
+                    if self.components.contains_key(member) {
+                        let queried: Vec<String> = component_types.iter().map(|ty| self.type_to_string(ty)).collect();
+                        let filter = component_types.iter().find_map(|ty| match ty {
+                            Type::QueryFilter(filter, component) if matches!(component.as_ref(), Type::Struct(n) | Type::Component(n) if n == member) => Some(*filter),
+                            _ => None,
+                        });
+                        let suggestion = match filter {
+                            Some(filter) => format!("{}<{}> only selects entities; list '{}' itself to read or write it", filter.name(), member, member),
+                            None => format!("Add it to the query: query<{}, {}>", queried.join(", "), member),
+                        };
+                        self.report_coded_error(
+                            "E0011",
+                            *location,
+                            format!("Component '{}' is not part of this query ({})", member, self.type_to_string(&object_type)),
+                            Some(suggestion),
+                        );
+                        return Ok(Type::Error);
+                    }
+                }
+                
+                // chunk.count, and chunk.positions for each queried component (named like the query's
+                // arrays): a slice of the components, or for a component_soa its field arrays
+                if let Type::QueryChunk(component_types) = &object_type {
+                    if member == "count" {
+                        return Ok(Type::I32);
+                    }
+                    let names: Vec<String> = component_types.iter().filter_map(|ty| match ty {
+                        Type::Struct(n) | Type::Component(n) => Some(n.clone()),
+                        _ => None,
+                    }).collect();
+                    if let Some(name) = names.iter().find(|n| component_array_name(n) == *member) {
+                        if self.components.get(name).is_some_and(|c| c.is_soa) {
+                            return Ok(Type::Component(name.clone()));
+                        }
+                        return Ok(Type::Array(Box::new(Type::Struct(name.clone()))));
+                    }
+                    let members: Vec<String> = std::iter::once("count".to_string())
+                        .chain(names.iter().map(|n| component_array_name(n)))
+                        .collect();
+                    self.report_coded_error(
+                        "E0011",
+                        *location,
+                        format!("A chunk of {} has no member '{}'", self.type_to_string(&Type::Query(component_types.clone())), member),
+                        Some(format!("Chunks have: {}", members.join(", "))),
+                    );
+                    return Ok(Type::Error);
+                }
+
+                // A component_soa's fields are arrays (chunk.velocities.x)
+                if let Type::Component(name) = &object_type {
+                    if let Some(field) = self.components.get(name).and_then(|c| c.fields.iter().find(|f| f.name == *member)) {
+                        // @cold fields are interleaved in the side array, so there is no array of them to slice
+                        if field.cold {
+                            self.report_error(
+                                *location,
+                                format!("@cold field '{}.{}' has no array in a chunk", name, member),
+                                Some(format!("Read it per entity in a query loop (e.{}.{}), or drop @cold if chunk loops use it", name, member)),
+                            );
+                            return Ok(Type::Error);
+                        }
+                        return Ok(field.ty.clone());
+                    }
+                }
+                
+                // Struct fields, including those of resources (input.mouse_left)
+                if let Type::Struct(name) = &object_type {
+                    if let Some(field) = self.structs.get(name).and_then(|s| s.fields.iter().find(|f| f.name == *member)) {
+                        return Ok(field.ty.clone());
+                    }
+                    // A component reached through a query entity (entity.Mass.m); a component_soa's
+                    // field arrays hold one value per entity
+                    if let Some(component) = self.components.get(name) {
+                        if let Some(field) = component.fields.iter().find(|f| f.name == *member) {
+                            return Ok(match &field.ty {
+                                Type::Array(element) if component.is_soa => element.as_ref().clone(),
+                                ty => ty.clone(),
+                            });
+                        }
+                    }
+                }
+                
                 // For other member access, return placeholder for now
                 // TODO: Implement proper member access type checking
                 Ok(Type::F32) // Placeholder
@@ -1181,7 +4753,7 @@ impl TypeChecker {
                             format!("Index operation requires array type, got '{}'", self.type_to_string(&array_type)),
                             Some("Use an array type: array[index]".to_string()),
                         );
-                        bail!("Index operation requires array type");
+                        Ok(Type::Error)
                     }
                 }
             }
@@ -1216,7 +4788,7 @@ impl TypeChecker {
                     if !self.types_compatible(&first_type, &elem_type) {
                         // Show secondary location pointing to first element for context
                         let first_elem_location = elements[0].location();
-                        self.report_error_with_secondary(
+                        self.report(
                             elem.location(),
                             format!("Array literal element {} has type '{}', but first element has type '{}'", 
                                    i + 1,
@@ -1224,8 +4796,7 @@ impl TypeChecker {
                                    self.type_to_string(&first_type)),
                             Some(format!("All array elements must have the same type. Use type '{}' for all elements.", 
                                         self.type_to_string(&first_type))),
-                            Some(first_elem_location),
-                            Some("Note: first element (expected type)"),
+                            ReportOptions::error().secondary(first_elem_location, "Note: first element (expected type)"),
                         );
                         has_error = true;
                     }
@@ -1237,23 +4808,49 @@ impl TypeChecker {
                     Ok(Type::Array(Box::new(first_type)))
                 }
             }
-            Expression::StructLiteral { name, fields: _, location } => {
+            Expression::StructLiteral { name, fields, location } => {
                 // Infer type from struct name
                 // Check for built-in struct types first
+                let builtin = match name.as_str() {
+                    "Vec2" => Some(Type::Vec2),
+                    "Vec3" => Some(Type::Vec3),
+                    "Vec4" => Some(Type::Vec4),
+                    "Mat4" => Some(Type::Mat4),
+                    _ => None,
+                };
+                if let Some(ty) = builtin {
+                    // Components are plain floats; checked so variables read here count as used
+                    for (_, value) in fields {
+                        let _ = self.check_expression(value);
+                    }
+                    return Ok(ty);
+                }
                 match name.as_str() {
-                    "Vec2" => Ok(Type::Vec2),
-                    "Vec3" => Ok(Type::Vec3),
-                    "Vec4" => Ok(Type::Vec4),
-                    "Mat4" => Ok(Type::Mat4),
                     _ => {
-                        if self.structs.contains_key(name) {
-                            Ok(Type::Struct(name.clone()))
+                        // Struct and component literals share the same syntax
+                        let declared_fields = if let Some(s) = self.structs.get(name) {
+                            s.fields.clone()
+                        } else if let Some(c) = self.components.get(name) {
+                            c.fields.clone()
                         } else {
+                            let mut candidates: Vec<String> = self.structs.keys().cloned().collect();
+                            candidates.extend(self.components.keys().cloned());
+                            let suggestion = if let Some(closest) = find_closest_match(name, &candidates, 3) {
+                                format!("Did you mean '{}'? Use: {} {{ ... }}", closest, closest)
+                            } else {
+                                format!("Did you mean to declare it? Use: struct {} {{ ... }}", name)
+                            };
                             self.report_error(
                                 *location,
                                 format!("Undefined struct: '{}'", name),
-                                Some(format!("Did you mean to declare it? Use: struct {} {{ ... }}", name)),
+                                Some(suggestion),
                             );
+                            return Ok(Type::Error);
+                        };
+
+                        if self.check_struct_literal_fields(name, &declared_fields, fields, *location)? {
+                            Ok(Type::Struct(name.clone()))
+                        } else {
                             Ok(Type::Error)
                         }
                     }
@@ -1262,6 +4859,95 @@ impl TypeChecker {
         }
     }
     
+    // Validate the fields of a struct/component literal against the declaration.
+    // Omitted fields are allowed (they are value-initialized in the generated C++),
+    // but unknown, duplicate, and mistyped fields are reported here instead of
+    // surfacing later as C++ compile errors. Returns false if any error was reported.
+    fn check_struct_literal_fields(
+        &mut self,
+        struct_name: &str,
+        declared_fields: &[Field],
+        fields: &[(String, Expression)],
+        location: SourceLocation,
+    ) -> Result<bool> {
+        let mut is_valid = true;
+        let mut seen_fields: HashMap<&str, SourceLocation> = HashMap::new();
+
+        for (field_name, value) in fields {
+            let value_type = self.check_expression(value)?;
+            let field_location = if value.location().is_unknown() { location } else { value.location() };
+
+            if let Some(first_location) = seen_fields.get(field_name.as_str()) {
+                self.report(
+                    field_location,
+                    format!("Field '{}' is specified more than once in '{}' literal", field_name, struct_name),
+                    Some(format!("Remove the duplicate '{}' field", field_name)),
+                    ReportOptions::coded("E0030").secondary(*first_location, "Note: first specified here"),
+                );
+                is_valid = false;
+                continue;
+            }
+            seen_fields.insert(field_name, field_location);
+
+            let declared = match declared_fields.iter().find(|f| &f.name == field_name) {
+                Some(f) => f,
+                None => {
+                    let candidates: Vec<String> = declared_fields.iter().map(|f| f.name.clone()).collect();
+                    let suggestion = if let Some(closest) = find_closest_match(field_name, &candidates, 3) {
+                        format!("Did you mean '{}'? Use: {}: <value>", closest, closest)
+                    } else if candidates.is_empty() {
+                        format!("'{}' has no fields", struct_name)
+                    } else {
+                        format!("Available fields: {}", candidates.join(", "))
+                    };
+                    self.report_coded_error(
+                        "E0029",
+                        field_location,
+                        format!("'{}' has no field named '{}'", struct_name, field_name),
+                        Some(suggestion),
+                    );
+                    is_valid = false;
+                    continue;
+                }
+            };
+
+            if !self.types_compatible(&declared.ty, &value_type) {
+                self.report_coded_error(
+                    "E0003",
+                    field_location,
+                    format!("Type mismatch for field '{}' of '{}': expected '{}', got '{}'",
+                           field_name, struct_name,
+                           self.type_to_string(&declared.ty),
+                           self.type_to_string(&value_type)),
+                    Some(format!("Use a {} value: {}: {}",
+                                self.type_to_string(&declared.ty),
+                                field_name,
+                                self.suggest_value_for_type(&declared.ty))),
+                );
+                is_valid = false;
+            }
+        }
+
+        Ok(is_valid)
+    }
+
+    // Exact type equality, used where implicit conversions would change the ABI (function types)
+    fn types_identical(&self, a: &Type, b: &Type) -> bool {
+        self.type_to_string(a) == self.type_to_string(b)
+    }
+    
+    // Numeric mismatches are always narrowing (widening is implicit), fixed with an explicit cast
+    fn narrowing_suggestion(&self, expected: &Type, actual: &Type) -> Option<String> {
+        if !is_numeric(expected) || !is_numeric(actual) {
+            return None;
+        }
+        Some(format!("Converting '{}' to '{}' can lose {}; convert explicitly: <value> as {}",
+            self.type_to_string(actual),
+            self.type_to_string(expected),
+            if matches!(expected, Type::I32 | Type::I64) && matches!(actual, Type::F32 | Type::F64) { "the fraction" } else { "precision or range" },
+            self.type_to_string(expected)))
+    }
+    
     fn types_compatible(&self, expected: &Type, actual: &Type) -> bool {
         // Error type is compatible with everything (allows error recovery)
         if matches!(expected, Type::Error) || matches!(actual, Type::Error) {
@@ -1279,7 +4965,7 @@ impl TypeChecker {
             (Type::F64, Type::I32) => true,  // i32 -> f64 (widening)
             (Type::F64, Type::I64) => true,  // i64 -> f64 (widening)
             (Type::F32, Type::I32) => true,  // i32 -> f32 (widening)
-            (Type::F32, Type::F64) => true,  // f64 -> f32 (narrowing, may lose precision)
+            // Narrowing (f64 -> f32, i64 -> i32, float -> int) needs an explicit `as`
             (Type::Bool, Type::Bool) => true,
             (Type::String, Type::String) => true,
             (Type::Void, Type::Void) => true,
@@ -1299,8 +4985,15 @@ impl TypeChecker {
                     self.types_compatible(inner, actual)
                 }
             },
-            (Type::Struct(a), Type::Struct(b)) => a == b,
+            // A component or struct can be passed where an interface it implements is expected
+            (Type::Struct(a), Type::Struct(b)) => a == b || self.impls.contains_key(&(a.clone(), b.clone())),
             (Type::Component(a), Type::Component(b)) => a == b,
+            // Function types must match parameter-for-parameter (no implicit conversions through pointers)
+            (Type::Function(a_params, a_ret), Type::Function(b_params, b_ret)) => {
+                a_params.len() == b_params.len() &&
+                    a_params.iter().zip(b_params.iter()).all(|(a, b)| self.types_identical(a, b)) &&
+                    self.types_identical(a_ret, b_ret)
+            }
             // Vulkan types
             (Type::VkInstance, Type::VkInstance) => true,
             (Type::VkDevice, Type::VkDevice) => true,
@@ -1334,6 +5027,7 @@ impl TypeChecker {
     /// Check if an expression is a frame-scoped allocation (frame.alloc_array call)
     fn is_frame_alloc_expression(&self, expr: &Expression) -> bool {
         match expr {
+            Expression::FrameAlloc { .. } => true,
             Expression::MemberAccess { object, member, .. } => {
                 // Check if this is frame.alloc_array
                 if member == "alloc_array" {
@@ -1351,3 +5045,189 @@ impl TypeChecker {
         }
     }
 }
+
+// The first return (or break/continue outside a loop of their own) in `stmts`
+fn escaping_statement(stmts: &[Statement], in_loop: bool) -> Option<(&'static str, SourceLocation)> {
+    stmts.iter().find_map(|stmt| match stmt {
+        Statement::Return(_, location) => Some(("return", *location)),
+        Statement::Break(_, location) if !in_loop => Some(("break", *location)),
+        Statement::Continue(location) if !in_loop => Some(("continue", *location)),
+        Statement::If { then_block, else_block, .. } => escaping_statement(then_block, in_loop)
+            .or_else(|| else_block.as_ref().and_then(|block| escaping_statement(block, in_loop))),
+        Statement::While { body, .. } | Statement::For { body, .. } | Statement::Loop { body, .. } => escaping_statement(body, true),
+        Statement::Block(body, _) | Statement::SpawnBatch { body, .. } => escaping_statement(body, in_loop),
+        Statement::Expression(Expression::Match { arms, .. }, _) => {
+            arms.iter().find_map(|arm| escaping_statement(&arm.body, in_loop))
+        }
+        _ => None,
+    })
+}
+
+fn literal_type(literal: &Literal) -> Type {
+    match literal {
+        Literal::Int(_, suffix) => suffix.clone().unwrap_or(Type::I32),
+        Literal::Float(_, suffix) => suffix.clone().unwrap_or(Type::F32),
+        Literal::Bool(_) => Type::Bool,
+        Literal::String(_) => Type::String,
+    }
+}
+
+// Opaque and C types (VkResult) are left to the C++ compiler
+fn literal_pattern_fits(pattern: &Type, scrutinee: &Type) -> bool {
+    match (pattern, scrutinee) {
+        (Type::I32 | Type::I64, Type::I32 | Type::I64) | (Type::F32 | Type::F64, Type::F32 | Type::F64) => true,
+        (_, Type::I32 | Type::I64 | Type::F32 | Type::F64 | Type::Bool | Type::String) => std::mem::discriminant(pattern) == std::mem::discriminant(scrutinee),
+        _ => true,
+    }
+}
+
+fn literal_number(literal: &Literal) -> Option<f64> {
+    match literal {
+        Literal::Int(n, _) => Some(*n as f64),
+        Literal::Float(n, _) => Some(*n),
+        _ => None,
+    }
+}
+
+// Whether every value `pattern` matches is also matched by `earlier` (literals and ranges; `_`
+// and bindings are handled by the callers)
+fn pattern_covers(earlier: &Pattern, pattern: &Pattern) -> bool {
+    let in_range = |value: f64, start: &Literal, end: &Literal, inclusive: bool| {
+        match (literal_number(start), literal_number(end)) {
+            (Some(low), Some(high)) => low <= value && (value < high || (inclusive && value == high)),
+            _ => false,
+        }
+    };
+    match (earlier, pattern) {
+        (Pattern::Or(alternatives, _), _) => alternatives.iter().any(|alternative| pattern_covers(alternative, pattern)),
+        (_, Pattern::Or(alternatives, _)) => alternatives.iter().all(|alternative| pattern_covers(earlier, alternative)),
+        (Pattern::Literal(a, _), Pattern::Literal(b, _)) => same_literal(a, b),
+        (Pattern::Ident(a, _), Pattern::Ident(b, _)) => a == b,
+        (Pattern::Range { start, end, inclusive, .. }, Pattern::Literal(literal, _)) => {
+            literal_number(literal).is_some_and(|value| in_range(value, start, end, *inclusive))
+        }
+        (Pattern::Range { start, end, inclusive, .. }, Pattern::Range { start: low, end: high, inclusive: high_included, .. }) => {
+            match (literal_number(low), literal_number(high)) {
+                (Some(low), Some(high)) => in_range(low, start, end, *inclusive)
+                    && (in_range(high, start, end, *inclusive) || (!high_included && literal_number(end) == Some(high))),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
+
+// Two kinds of item that can't share a name. A system's name isn't a C++ symbol, so only its
+// functions clash with functions.
+fn kinds_collide(first: &str, second: &str) -> bool {
+    let is_function = |kind: &str| kind == "function" || kind == "system function";
+    first != second && !(first == "system" && is_function(second)) && !(is_function(first) && second == "system")
+}
+
+// `Player` declared as a resource -> `PlayerResource`; `spawn` as a system -> `spawn_system`
+fn kind_suffixed(name: &str, kind: &str) -> String {
+    let kind = kind.rsplit(' ').next().unwrap_or(kind);
+    if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        format!("{}{}", name, capitalize(kind))
+    } else {
+        format!("{}_{}", name, kind)
+    }
+}
+
+fn pattern_location(pattern: &Pattern) -> SourceLocation {
+    match pattern {
+        Pattern::Literal(_, location) | Pattern::Variable(_, location) | Pattern::Wildcard(location)
+        | Pattern::Ident(_, location) | Pattern::Or(_, location) | Pattern::Range { location, .. }
+        | Pattern::Struct { location, .. } => *location,
+    }
+}
+
+fn same_literal(a: &Literal, b: &Literal) -> bool {
+    match (a, b) {
+        (Literal::Int(a, _), Literal::Int(b, _)) => a == b,
+        (Literal::Float(a, _), Literal::Float(b, _)) => a == b,
+        (Literal::Bool(a), Literal::Bool(b)) => a == b,
+        (Literal::String(a), Literal::String(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn is_numeric(ty: &Type) -> bool {
+    matches!(ty, Type::I32 | Type::I64 | Type::F32 | Type::F64)
+}
+
+fn numeric_rank(ty: &Type) -> u8 {
+    match ty {
+        Type::I32 => 0,
+        Type::I64 => 1,
+        Type::F32 => 2,
+        _ => 3,
+    }
+}
+
+// Every let in a function body, including nested blocks and match arms
+fn collect_lets(stmts: &[Statement], out: &mut Vec<(String, SourceLocation)>) {
+    for stmt in stmts {
+        match stmt {
+            Statement::Let { name, location, .. } => out.push((name.clone(), *location)),
+            Statement::Destructure { pattern, .. } => {
+                out.extend(pattern.bindings().into_iter().map(|(_, name, location)| (name.to_string(), location)));
+            }
+            Statement::If { then_block, else_block, .. } => {
+                collect_lets(then_block, out);
+                if let Some(else_block) = else_block {
+                    collect_lets(else_block, out);
+                }
+            }
+            Statement::While { body, .. } | Statement::For { body, .. } | Statement::Loop { body, .. }
+            | Statement::Block(body, _) | Statement::SpawnBatch { body, .. } => collect_lets(body, out),
+            Statement::Expression(Expression::Match { arms, .. }, _) => {
+                for arm in arms {
+                    collect_lets(&arm.body, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// The first component literal used as a statement in `stmts`, including nested blocks
+fn component_literal_statement(stmts: &[Statement], components: &HashMap<String, ComponentDef>) -> Option<(String, SourceLocation)> {
+    stmts.iter().find_map(|stmt| match stmt {
+        Statement::Expression(Expression::StructLiteral { name, .. }, location)
+            if components.contains_key(name) => Some((name.clone(), *location)),
+        Statement::If { then_block, else_block, .. } => component_literal_statement(then_block, components)
+            .or_else(|| else_block.as_ref().and_then(|block| component_literal_statement(block, components))),
+        // A nested spawn_batch attaches its own literals
+        Statement::While { body, .. } | Statement::For { body, .. } | Statement::Loop { body, .. }
+        | Statement::Block(body, _) => component_literal_statement(body, components),
+        Statement::Expression(Expression::Match { arms, .. }, _) => {
+            arms.iter().find_map(|arm| component_literal_statement(&arm.body, components))
+        }
+        _ => None,
+    })
+}
+
+fn stage_keyword(stage: &ShaderStage) -> &'static str {
+    match stage {
+        ShaderStage::Vertex => "vertex",
+        ShaderStage::Fragment => "fragment",
+        ShaderStage::Compute => "compute",
+        ShaderStage::Geometry => "geometry",
+        ShaderStage::TessellationControl => "tessellation_control",
+        ShaderStage::TessellationEvaluation => "tessellation_evaluation",
+    }
+}
+
+// The fields of a component_soa's Row: one entity's values, an element of each field array
+fn soa_row_fields(component: &ComponentDef) -> Vec<Field> {
+    component.fields.iter().map(|field| match &field.ty {
+        Type::Array(element) => Field { ty: element.as_ref().clone(), ..field.clone() },
+        _ => field.clone(),
+    }).collect()
+}
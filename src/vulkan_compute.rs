@@ -0,0 +1,532 @@
+// Vulkan compute kernels: with --gpu-backend=vulkan, `@[launch(kernel = integrate)] fn
+// update_physics(q: query<Position, Velocity>, dt: f32)` becomes the GLSL compute shader
+// compute_shaders/integrate.comp instead of a CUDA kernel, so GPU systems run on any Vulkan
+// device. The shader has one storage buffer per array of the query (the arrays the CUDA backend
+// copies, cuda::device_arrays), the kernel's parameters as push constants, and `for entity in q`
+// as a loop in which each invocation takes every Nth entity, like the CUDA grid-stride loop.
+// update_physics_launch(q, dt) in the .cpp uploads the arrays with heidic_gpu_buffer_write,
+// dispatches the shader on the engine's graphics queue and reads the arrays back
+// (stdlib/vulkan_compute.h).
+//
+// The CUDA backend compiles a kernel's body as C++; here it's translated to GLSL, which only
+// takes numbers, bools, whole AoS components, arithmetic, comparisons, casts, lets, ifs, loops
+// and the math built-ins GLSL shares with C. conflicts() reports anything else at compile time.
+
+use std::collections::HashMap;
+
+use crate::ast::*;
+use crate::codegen;
+use crate::cuda;
+use crate::error::SourceLocation;
+use crate::shaders;
+
+/// Invocations per workgroup: local_size_x of every kernel
+pub const GROUP_SIZE: u32 = 256;
+
+/// Push constant bytes every Vulkan device supports (maxPushConstantsSize is at least 128)
+const PUSH_CONSTANT_LIMIT: usize = 128;
+
+/// Calls a kernel may make: GLSL built-ins with the C meaning, under their HEIDIC names
+const FUNCTIONS: &[(&str, &str)] = &[
+    ("abs", "abs"), ("sqrt", "sqrt"), ("sin", "sin"), ("cos", "cos"), ("tan", "tan"),
+    ("asin", "asin"), ("acos", "acos"), ("atan", "atan"), ("atan2", "atan"), ("exp", "exp"),
+    ("log", "log"), ("pow", "pow"), ("floor", "floor"), ("ceil", "ceil"), ("trunc", "trunc"),
+    ("min", "min"), ("max", "max"), ("clamp", "clamp"),
+    ("heidic_sin", "sin"), ("heidic_cos", "cos"), ("heidic_sqrt", "sqrt"),
+];
+
+/// GLSL keywords and built-in names a HEIDIC identifier might take; those get a heidic_ prefix
+const GLSL_RESERVED: &[&str] = &[
+    "attribute", "buffer", "centroid", "coherent", "common", "discard", "filter", "flat", "highp",
+    "image", "in", "inout", "input", "invariant", "layout", "lowp", "mediump", "noperspective",
+    "out", "output", "partition", "patch", "precise", "precision", "readonly", "resource",
+    "restrict", "sample", "sampler", "shared", "smooth", "subroutine", "texture", "uniform",
+    "varying", "volatile", "writeonly", "uint", "vec2", "vec3", "vec4", "mat2", "mat3", "mat4",
+    "dot", "cross", "length", "distance", "normalize", "mix", "step", "fract", "mod", "sign",
+    "half", "fixed", "long", "short", "double", "float", "int", "bool", "unsigned", "cast",
+    "class", "union", "enum", "typedef", "template", "this", "goto", "inline", "noinline",
+    "public", "static", "extern", "external", "interface", "namespace", "using", "asm",
+    "sizeof", "switch", "case", "default", "const", "struct", "active", "superp", "row_major",
+    "ivec2", "ivec3", "ivec4", "uvec2", "uvec3", "uvec4", "dvec2", "dvec3", "dvec4",
+    "bvec2", "bvec3", "bvec4", "hvec2", "hvec3", "hvec4", "fvec2", "fvec3", "fvec4",
+];
+
+/// The GLSL source of a kernel, next to the .hd file
+pub fn shader_path(launch: &CudaLaunch) -> String {
+    format!("compute_shaders/{}.comp", launch.kernel)
+}
+
+/// The SPIR-V the program loads: compute_shaders/integrate.comp.spv
+pub fn spirv_path(launch: &CudaLaunch) -> String {
+    shaders::spv_path(&shader_path(launch))
+}
+
+/// The HeidicComputeKernel holding a kernel's pipeline
+pub fn kernel_global(launch: &CudaLaunch) -> String {
+    format!("g_compute_{}", launch.kernel)
+}
+
+/// The struct a kernel's push constants are filled in
+pub fn params_type(launch: &CudaLaunch) -> String {
+    format!("HeidicComputeParams_{}", launch.kernel)
+}
+
+/// The HeidicGpuBuffer behind one array of a kernel's query
+pub fn buffer_global(launch: &CudaLaunch, array: &cuda::DeviceArray) -> String {
+    format!("g_compute_{}_{}", launch.kernel, array.host)
+}
+
+/// An async launch's host arrays, which its wait reads the results back into
+pub fn results_global(launch: &CudaLaunch) -> String {
+    format!("g_compute_{}_results", launch.kernel)
+}
+
+/// How many entities an async launch has left to read back
+pub fn pending_global(launch: &CudaLaunch) -> String {
+    format!("g_compute_{}_pending", launch.kernel)
+}
+
+/// A name as it appears in the GLSL
+fn glsl_name(name: &str) -> String {
+    if GLSL_RESERVED.contains(&name) || name.starts_with("gl_") || name.contains("__") {
+        format!("heidic_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Bytes of a push constant or array element type (its alignment too, for numbers)
+fn type_size(ty: &Type) -> usize {
+    match ty {
+        Type::I64 | Type::F64 => 8,
+        _ => 4,  // i32, f32, and bools, which are 32 bits in GLSL
+    }
+}
+
+/// (message, suggestion) at a location of the .hd file
+pub type Conflict = (SourceLocation, String, String);
+
+/// What --gpu-backend=vulkan can't generate: sync_to_gpu/sync_to_cpu, and kernels GLSL can't express
+pub fn conflicts(program: &Program, item_locations: &[SourceLocation]) -> Vec<Conflict> {
+    let components = cuda_components(program);
+    let mut conflicts = Vec::new();
+    for (index, item) in program.items.iter().enumerate() {
+        let location = item_locations.get(index).copied().unwrap_or_else(SourceLocation::unknown);
+        let syncs = [SyncDirection::ToGpu.name(), SyncDirection::ToCpu.name()];
+        if codegen::item_calls(item, &syncs) {
+            conflicts.push((location, format!("{}/{} keep components in CUDA device memory; they aren't available with --gpu-backend=vulkan", syncs[0], syncs[1]),
+                "Let each launch copy its query's arrays, or build with --gpu-backend=cuda".to_string()));
+        }
+        if let Item::Function(f) = item {
+            if let Err(mut kernel) = compute_shader(f, &components, location) {
+                conflicts.append(&mut kernel);
+            }
+        }
+    }
+    conflicts
+}
+
+/// The compute shader of every @[launch] function: (item index, path next to the .hd file, GLSL)
+pub fn compute_shaders(program: &Program) -> Vec<(usize, String, String)> {
+    let components = cuda_components(program);
+    program.items.iter().enumerate().filter_map(|(index, item)| match item {
+        Item::Function(f) => {
+            let launch = f.cuda_kernel.as_ref()?;
+            let glsl = compute_shader(f, &components, SourceLocation::unknown()).ok()?;
+            Some((index, shader_path(launch), glsl))
+        }
+        _ => None,
+    }).collect()
+}
+
+fn cuda_components(program: &Program) -> Vec<&ComponentDef> {
+    program.items.iter().filter_map(|item| match item {
+        Item::Component(c) if c.is_cuda => Some(c),
+        _ => None,
+    }).collect()
+}
+
+/// The GLSL of a @[launch] function's kernel, or why it can't be written (`location` is the
+/// function's, for what has no location of its own)
+pub fn compute_shader(f: &FunctionDef, components: &[&ComponentDef], location: SourceLocation) -> Result<String, Vec<Conflict>> {
+    let Some(launch) = &f.cuda_kernel else { return Ok(String::new()) };
+    let Some((query, component_types)) = cuda::kernel_query(f) else { return Ok(String::new()) };
+    let kernel_components: Vec<&ComponentDef> = component_types.iter().filter_map(|ty| match ty {
+        Type::Struct(name) | Type::Component(name) => components.iter().find(|c| &c.name == name).copied(),
+        _ => None,
+    }).collect();
+    let mut translator = Translator {
+        components: kernel_components.clone(),
+        query: query.name.clone(),
+        entity: None,
+        scopes: vec![HashMap::new()],
+        int64: false,
+        conflicts: Vec::new(),
+        location,
+    };
+    let mut output = String::new();
+
+    // AoS components are read and written whole, so the shader declares their structs; with
+    // numbers only, std430 lays them out as C++ does
+    for component in kernel_components.iter().filter(|c| !c.is_soa) {
+        output.push_str(&format!("struct {} {{\n", glsl_name(&component.name)));
+        for field in &component.fields {
+            output.push_str(&format!("    {} {};\n", translator.element_type(component, field), glsl_name(&field.name)));
+        }
+        output.push_str("};\n\n");
+    }
+    let arrays: Vec<cuda::DeviceArray> = kernel_components.iter()
+        .flat_map(|component| cuda::device_arrays(&query.name, component))
+        .collect();
+    for (binding, array) in arrays.iter().enumerate() {
+        let element = match &array.element {
+            Type::Struct(name) => glsl_name(name),
+            element => translator.glsl_type(element, location),
+        };
+        output.push_str(&format!("layout(std430, set = 0, binding = {}) buffer HeidicBuffer{} {{ {} {}[]; }};\n", binding, binding, element, array.host));
+    }
+
+    output.push_str("\nlayout(push_constant) uniform HeidicParams {\n");
+    output.push_str("    uint heidic_count;\n");
+    let mut push_size: usize = 4;
+    for param in f.params.iter().filter(|param| !matches!(param.ty, Type::Query(_))) {
+        let size = type_size(&param.ty);
+        push_size = push_size.div_ceil(size) * size + size;
+        output.push_str(&format!("    {} {};\n", translator.glsl_type(&param.ty, param.location), glsl_name(&param.name)));
+        translator.declare(&param.name, param.ty.clone());
+    }
+    output.push_str("};\n\n");
+    if push_size > PUSH_CONSTANT_LIMIT {
+        translator.conflict(location, format!("Kernel '{}' takes {} bytes of parameters; Vulkan only guarantees {} bytes of push constants", launch.kernel, push_size, PUSH_CONSTANT_LIMIT),
+            "Pass fewer parameters, or put per-entity data in a @[cuda] component");
+    }
+
+    output.push_str("void main() {\n");
+    for stmt in &f.body {
+        match stmt {
+            Statement::For { iterator, collection: Expression::Variable(name, _), chunk_size: None, body, .. } if *name == query.name => {
+                output.push_str(&format!("    for (uint {it}_index = gl_GlobalInvocationID.x; {it}_index < heidic_count; {it}_index += gl_NumWorkGroups.x * gl_WorkGroupSize.x) {{\n", it = iterator));
+                translator.entity = Some(iterator.clone());
+                translator.block(body, 2, &mut output);
+                translator.entity = None;
+                output.push_str("    }\n");
+            }
+            // The type checker has rejected anything else at the top of a kernel
+            _ => translator.statement(stmt, 1, &mut output),
+        }
+    }
+    output.push_str("}\n");
+
+    if !translator.conflicts.is_empty() {
+        return Err(translator.conflicts);
+    }
+    let mut header = format!("// {}: kernel of fn {}, generated by heidic_v2 --gpu-backend=vulkan\n", launch.kernel, f.name);
+    header.push_str("#version 450\n");
+    if translator.int64 {
+        header.push_str("#extension GL_EXT_shader_explicit_arithmetic_types_int64 : require\n");
+    }
+    header.push_str(&format!("\nlayout(local_size_x = {}) in;\n\n", GROUP_SIZE));
+    Ok(header + &output)
+}
+
+// HEIDIC statements and expressions of a kernel body as GLSL, with the types GLSL declarations need
+struct Translator<'a> {
+    components: Vec<&'a ComponentDef>,  // The kernel query's components
+    query: String,
+    entity: Option<String>,  // Iterator of the query loop being translated
+    scopes: Vec<HashMap<String, Type>>,
+    int64: bool,  // An i64 was used: the shader needs GL_EXT_shader_explicit_arithmetic_types_int64
+    conflicts: Vec<Conflict>,
+    location: SourceLocation,  // The kernel function's
+}
+
+impl Translator<'_> {
+    fn conflict(&mut self, location: SourceLocation, message: String, suggestion: &str) {
+        let location = if location.line == 0 { self.location } else { location };
+        self.conflicts.push((location, message, suggestion.to_string()));
+    }
+
+    fn unsupported(&mut self, location: SourceLocation, what: &str) {
+        self.conflict(location, format!("{} can't be translated to a Vulkan compute shader", what),
+            "Kernels compile to GLSL with --gpu-backend=vulkan: use numbers, bools and @[cuda] components, or build with --gpu-backend=cuda");
+    }
+
+    fn declare(&mut self, name: &str, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), ty);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Type> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).cloned())
+    }
+
+    fn component(&self, name: &str) -> Option<&ComponentDef> {
+        self.components.iter().find(|c| c.name == name).copied()
+    }
+
+    fn glsl_type(&mut self, ty: &Type, location: SourceLocation) -> String {
+        match ty {
+            Type::I32 => "int".to_string(),
+            Type::I64 => {
+                self.int64 = true;
+                "int64_t".to_string()
+            }
+            Type::F32 => "float".to_string(),
+            Type::F64 => "double".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Struct(name) | Type::Component(name) if self.component(name).is_some_and(|c| !c.is_soa) => glsl_name(name),
+            _ => {
+                self.unsupported(location, &format!("A value of type '{}'", crate::formatter::type_str(ty)));
+                "void".to_string()
+            }
+        }
+    }
+
+    // A field of a component as the buffers hold it: GLSL bools are 32 bits, C++ bools 8
+    fn element_type(&mut self, component: &ComponentDef, field: &Field) -> String {
+        let element = match &field.ty {
+            Type::Array(element) => element.as_ref(),
+            ty => ty,
+        };
+        if matches!(element, Type::Bool) {
+            self.conflict(self.location, format!("Field '{}.{}' is a bool, which GLSL stores in 4 bytes and C++ in 1", component.name, field.name),
+                "Store it as an i32 (0 or 1) for --gpu-backend=vulkan");
+        }
+        self.glsl_type(element, self.location)
+    }
+
+    fn block(&mut self, statements: &[Statement], indent: usize, output: &mut String) {
+        self.scopes.push(HashMap::new());
+        for stmt in statements {
+            self.statement(stmt, indent, output);
+        }
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, stmt: &Statement, indent: usize, output: &mut String) {
+        let pad = "    ".repeat(indent);
+        match stmt {
+            Statement::Let { name, ty, value, location, .. } => {
+                let (value, value_ty) = self.expression(value);
+                let ty = ty.clone().unwrap_or(value_ty);
+                let glsl_ty = self.glsl_type(&ty, *location);
+                output.push_str(&format!("{}{} {} = {};\n", pad, glsl_ty, glsl_name(name), value));
+                self.declare(name, ty);
+            }
+            Statement::Assign { target, value, .. } => {
+                let (target, _) = self.expression(target);
+                let (value, _) = self.expression(value);
+                output.push_str(&format!("{}{} = {};\n", pad, target, value));
+            }
+            Statement::If { condition, then_block, else_block, .. } => {
+                let (condition, _) = self.expression(condition);
+                output.push_str(&format!("{}if ({}) {{\n", pad, condition));
+                self.block(then_block, indent + 1, output);
+                if let Some(else_block) = else_block {
+                    output.push_str(&format!("{}}} else {{\n", pad));
+                    self.block(else_block, indent + 1, output);
+                }
+                output.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::While { condition, body, .. } => {
+                let (condition, _) = self.expression(condition);
+                output.push_str(&format!("{}while ({}) {{\n", pad, condition));
+                self.block(body, indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::Loop { body, .. } => {
+                output.push_str(&format!("{}while (true) {{\n", pad));
+                self.block(body, indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::Break(None, _) => output.push_str(&format!("{}break;\n", pad)),
+            Statement::Continue(_) => output.push_str(&format!("{}continue;\n", pad)),
+            Statement::Return(None, _) => output.push_str(&format!("{}return;\n", pad)),
+            Statement::Expression(expr, _) => {
+                let (expr, _) = self.expression(expr);
+                output.push_str(&format!("{}{};\n", pad, expr));
+            }
+            Statement::For { collection: Expression::Variable(name, _), location, .. } if *name == self.query => {
+                self.conflict(*location, format!("A loop over '{}' inside another one can't be spread over invocations", name),
+                    &format!("Keep `for ... in {}` at the top of the kernel", name));
+            }
+            Statement::For { location, .. } => self.unsupported(*location, "A for loop over a collection"),
+            Statement::Destructure { location, .. } => self.unsupported(*location, "Destructuring"),
+            Statement::Break(Some(_), location) => self.unsupported(*location, "Breaking out of a loop with a value"),
+            Statement::Return(Some(_), location) => self.unsupported(*location, "Returning a value"),
+            Statement::Defer(_, location) => self.unsupported(*location, "defer"),
+            Statement::Emit(_, location) => self.unsupported(*location, "Emitting an event"),
+            Statement::SpawnBatch { location, .. } => self.unsupported(*location, "spawn_batch"),
+            Statement::Block(statements, _) => {
+                output.push_str(&format!("{}{{\n", pad));
+                self.block(statements, indent + 1, output);
+                output.push_str(&format!("{}}}\n", pad));
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) -> (String, Type) {
+        match expr {
+            Expression::Literal(literal, location) => match literal {
+                Literal::Int(n, Some(Type::I64)) => {
+                    self.int64 = true;
+                    (format!("{}l", n), Type::I64)
+                }
+                Literal::Int(n, _) => (n.to_string(), Type::I32),
+                Literal::Float(n, Some(Type::F64)) => (format!("{:?}lf", n), Type::F64),
+                Literal::Float(n, _) => (format!("{:?}", n), Type::F32),
+                Literal::Bool(b) => (b.to_string(), Type::Bool),
+                Literal::String(_) => {
+                    self.unsupported(*location, "A string");
+                    (String::new(), Type::Void)
+                }
+            },
+            Expression::Variable(name, location) => match self.lookup(name) {
+                Some(ty) => (glsl_name(name), ty),
+                None => {
+                    self.conflict(*location, format!("'{}' isn't visible to the compute shader of a --gpu-backend=vulkan kernel", name),
+                        "Kernels see their parameters, their lets and their query; pass the value as a parameter");
+                    (String::new(), Type::Void)
+                }
+            },
+            Expression::BinaryOp { op, left, right, .. } => {
+                let (l, left_ty) = self.expression(left);
+                let (r, right_ty) = self.expression(right);
+                let floats = matches!(left_ty, Type::F32 | Type::F64) || matches!(right_ty, Type::F32 | Type::F64);
+                let ty = match op {
+                    BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+                    | BinaryOp::And | BinaryOp::Or => Type::Bool,
+                    _ if matches!(left_ty, Type::I32) && floats => right_ty,
+                    _ => left_ty,
+                };
+                let op = match op {
+                    // GLSL's % is integers only; this is C's fmod
+                    BinaryOp::Mod if floats => return (format!("({l} - {r} * trunc({l} / {r}))", l = l, r = r), ty),
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::Mod => "%",
+                    BinaryOp::Eq => "==",
+                    BinaryOp::Ne => "!=",
+                    BinaryOp::Lt => "<",
+                    BinaryOp::Le => "<=",
+                    BinaryOp::Gt => ">",
+                    BinaryOp::Ge => ">=",
+                    BinaryOp::And => "&&",
+                    BinaryOp::Or => "||",
+                };
+                (format!("({} {} {})", l, op, r), ty)
+            }
+            Expression::UnaryOp { op, expr, .. } => {
+                let (e, ty) = self.expression(expr);
+                match op {
+                    UnaryOp::Neg => (format!("(-{})", e), ty),
+                    UnaryOp::Not => (format!("(!{})", e), Type::Bool),
+                }
+            }
+            Expression::Call { name, args, location } => {
+                let Some((_, glsl)) = FUNCTIONS.iter().find(|(heidic, _)| heidic == name) else {
+                    self.conflict(*location, format!("fn '{}' can't be called from the compute shader of a --gpu-backend=vulkan kernel", name),
+                        "Kernels may call GLSL's math built-ins (sqrt, sin, min, clamp, ...); inline the rest");
+                    return (String::new(), Type::Void);
+                };
+                let args: Vec<(String, Type)> = args.iter().map(|arg| self.expression(arg)).collect();
+                let ty = args.first().map_or(Type::F32, |(_, ty)| ty.clone());
+                let args: Vec<String> = args.into_iter().map(|(arg, _)| arg).collect();
+                (format!("{}({})", glsl, args.join(", ")), ty)
+            }
+            Expression::MemberAccess { object, member, location } => self.member_access(object, member, *location),
+            Expression::Cast { expr, ty, location } => {
+                let (e, _) = self.expression(expr);
+                let glsl_ty = self.glsl_type(ty, *location);
+                (format!("{}({})", glsl_ty, e), ty.clone())
+            }
+            Expression::If { condition, then_branch, else_branch, location } => {
+                if !then_branch.statements.is_empty() || !else_branch.statements.is_empty() {
+                    self.unsupported(*location, "An if expression with statements in its branches");
+                }
+                let (condition, _) = self.expression(condition);
+                let (then_value, ty) = self.expression(&then_branch.value);
+                let (else_value, _) = self.expression(&else_branch.value);
+                (format!("({} ? {} : {})", condition, then_value, else_value), ty)
+            }
+            Expression::StructLiteral { name, fields, location } => {
+                let Some(component) = self.component(name).filter(|c| !c.is_soa).cloned() else {
+                    self.unsupported(*location, &format!("A '{}' literal", name));
+                    return (String::new(), Type::Void);
+                };
+                let mut values = Vec::new();
+                for field in &component.fields {
+                    match fields.iter().find(|(given, _)| *given == field.name) {
+                        Some((_, value)) => values.push(self.expression(value).0),
+                        None => self.conflict(*location, format!("'{}' literal without field '{}' can't be translated to GLSL", name, field.name),
+                            "Give every field of a component built in a kernel"),
+                    }
+                }
+                (format!("{}({})", glsl_name(name), values.join(", ")), Type::Struct(name.clone()))
+            }
+            other => {
+                self.unsupported(other.location(), "This expression");
+                (String::new(), Type::Void)
+            }
+        }
+    }
+
+    // entity.Position.x (a query array element), entity.Velocity (a whole AoS component), or a
+    // field of an AoS component held in a let
+    fn member_access(&mut self, object: &Expression, member: &str, location: SourceLocation) -> (String, Type) {
+        let entity = self.entity.clone().unwrap_or_default();
+        let is_entity = |expr: &Expression| matches!(expr, Expression::Variable(name, _) if *name == entity);
+        if let Expression::MemberAccess { object: inner, member: component_name, .. } = object {
+            if is_entity(inner) {
+                let Some(component) = self.component(component_name).cloned() else {
+                    self.unsupported(location, &format!("Component '{}'", component_name));
+                    return (String::new(), Type::Void);
+                };
+                let arrays = cuda::device_arrays(&self.query, &component);
+                let field = component.fields.iter().find(|field| field.name == member);
+                return match (field, component.is_soa) {
+                    (Some(field), true) => {
+                        let array = arrays.iter().find(|array| array.field.as_deref() == Some(member)).map_or(String::new(), |array| array.host.clone());
+                        let element = match &field.ty {
+                            Type::Array(element) => (**element).clone(),
+                            ty => ty.clone(),
+                        };
+                        (format!("{}[{}_index]", array, entity), element)
+                    }
+                    (Some(field), false) => (format!("{}[{}_index].{}", arrays[0].host, entity, glsl_name(member)), field.ty.clone()),
+                    (None, _) => {
+                        self.unsupported(location, &format!("'{}.{}'", component_name, member));
+                        (String::new(), Type::Void)
+                    }
+                };
+            }
+        }
+        if is_entity(object) {
+            return match self.component(member).filter(|c| !c.is_soa).cloned() {
+                Some(component) => {
+                    let arrays = cuda::device_arrays(&self.query, &component);
+                    (format!("{}[{}_index]", arrays[0].host, entity), Type::Struct(component.name.clone()))
+                }
+                None => {
+                    self.unsupported(location, &format!("'{}.{}'", entity, member));
+                    (String::new(), Type::Void)
+                }
+            };
+        }
+        let (o, ty) = self.expression(object);
+        let field = match &ty {
+            Type::Struct(name) | Type::Component(name) => self.component(name).and_then(|c| c.fields.iter().find(|field| field.name == member)).map(|field| field.ty.clone()),
+            _ => None,
+        };
+        match field {
+            Some(field) => (format!("{}.{}", o, glsl_name(member)), field),
+            None => {
+                self.unsupported(location, &format!("Member '{}'", member));
+                (String::new(), Type::Void)
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+// --target=wasm: a browser build. The generated C++ is compiled by Emscripten (em++) into
+// <file>.js and <file>.wasm, and <file>.html is the page that runs it. stdlib/canvas.h stands in
+// for GLFW (the window is the page's canvas) and stdlib/webgpu.h for the native renderer, so a
+// program written against GLFW and the renderer's extern fns runs unchanged; what needs Vulkan
+// itself (pipelines, shaders, GPU buffers and queries, @[launch] kernels) is reported by
+// conflicts().
+//
+// Browsers load no DLLs, so @hot items compile as ordinary ones (without_hot_reload) and the
+// page drives reloading instead: served with ?hot, it reloads itself when <file>.wasm is rebuilt,
+// and writes changed tweak files into the program's virtual file system before calling the
+// exported heidic_reload(), which makes the next frame's heidic_check_reloads() reread them.
+
+use std::collections::HashSet;
+
+use crate::ast::*;
+use crate::audit;
+use crate::codegen;
+use crate::error::SourceLocation;
+use crate::replication;
+
+pub type Conflict = (SourceLocation, String, String);
+
+// Calls whose generated code needs Vulkan or sockets
+const NATIVE_CALLS: &[(&str, &str)] = &[
+    ("screenshot", "frame capture reads back a Vulkan image"),
+    ("readback", "GPU buffers need Vulkan"),
+    ("upload", "GPU buffers need Vulkan"),
+    ("submit_transfer", "transfers run on a Vulkan queue"),
+    ("transfer_ready", "transfers run on a Vulkan queue"),
+    ("wait_transfer", "transfers run on a Vulkan queue"),
+    ("sync_to_gpu", "@[cuda] components live in CUDA device memory"),
+    ("sync_to_cpu", "@[cuda] components live in CUDA device memory"),
+];
+
+fn is_vulkan_type(ty: &Type) -> bool {
+    match ty {
+        Type::VkInstance | Type::VkDevice | Type::VkResult | Type::VkPhysicalDevice | Type::VkQueue
+        | Type::VkCommandPool | Type::VkCommandBuffer | Type::VkSwapchainKHR | Type::VkSurfaceKHR
+        | Type::VkRenderPass | Type::VkPipeline | Type::VkFramebuffer | Type::VkBuffer | Type::VkImage
+        | Type::VkImageView | Type::VkSemaphore | Type::VkFence => true,
+        Type::Array(inner) | Type::Optional(inner) => is_vulkan_type(inner),
+        Type::Function(params, ret) => params.iter().any(is_vulkan_type) || is_vulkan_type(ret),
+        _ => false,
+    }
+}
+
+/// What a browser build can't run, as (location, message, suggestion): Vulkan items and types,
+/// GPU kernels, replication and calls into the native renderer's GPU features
+pub fn conflicts(program: &Program, item_locations: &[SourceLocation]) -> Vec<Conflict> {
+    let vulkan = "The browser build draws through stdlib/webgpu.h, which has no Vulkan; keep this in the native build";
+    let defined: HashSet<&str> = program.items.iter().filter_map(|item| match item {
+        Item::Function(f) => Some(f.name.as_str()),
+        _ => None,
+    }).collect();
+    let mut conflicts = Vec::new();
+    let mut reported = HashSet::new();
+    for (index, item) in program.items.iter().enumerate() {
+        let location = item_locations.get(index).copied().unwrap_or_else(SourceLocation::unknown);
+        let mut conflict = |message: String, suggestion: &str| conflicts.push((location, message, suggestion.to_string()));
+        match item {
+            Item::Pipeline(p) => conflict(format!("pipeline '{}' isn't available with --target=wasm", p.name), vulkan),
+            Item::Shader(shader) => conflict(format!("shader '{}' isn't available with --target=wasm", shader.path), vulkan),
+            Item::Gpu(_) => conflict("gpu { ... } isn't available with --target=wasm".to_string(), vulkan),
+            Item::GpuQuery(q) => conflict(format!("gpu_query '{}' isn't available with --target=wasm", q.name), vulkan),
+            Item::Uniform(u) => conflict(format!("uniform '{}' isn't available with --target=wasm", u.name), vulkan),
+            Item::Resource(r) => conflict(format!("resource '{}' isn't available with --target=wasm", r.name),
+                "Resources are uploaded by the engine's Vulkan loaders; load assets through extern fns"),
+            Item::Function(f) if f.cuda_kernel.is_some() => conflict(format!("@[launch] fn '{}' isn't available with --target=wasm", f.name),
+                "Browsers run neither CUDA nor Vulkan compute; call it as an ordinary fn"),
+            Item::Component(c) if c.is_replicated => conflict(format!("@[replicated] component '{}' isn't available with --target=wasm", c.name),
+                "Replication runs over UDP sockets, which browsers don't have"),
+            _ => {}
+        }
+        let signatures: Vec<(&str, Vec<&Type>)> = match item {
+            Item::Function(f) => vec![(f.name.as_str(), f.params.iter().map(|p| &p.ty).chain([&f.return_type]).collect())],
+            Item::ExternFunction(f) => vec![(f.name.as_str(), f.params.iter().map(|p| &p.ty).chain([&f.return_type]).collect())],
+            Item::Struct(st) => vec![(st.name.as_str(), st.fields.iter().map(|field| &field.ty).collect())],
+            Item::Component(c) => vec![(c.name.as_str(), c.fields.iter().map(|field| &field.ty).collect())],
+            _ => Vec::new(),
+        };
+        for (name, types) in signatures {
+            if types.into_iter().any(is_vulkan_type) {
+                conflict(format!("'{}' uses Vulkan types, which --target=wasm leaves out", name),
+                    "Pass plain values or ids across extern fns; the window (GLFWwindow) is still available");
+            }
+        }
+        let calls: Vec<&str> = NATIVE_CALLS.iter().map(|(name, _)| *name)
+            .chain(replication::FUNCTIONS.iter().copied())
+            .filter(|name| !defined.contains(name) && codegen::item_calls(item, &[*name]))
+            .collect();
+        for call in calls {
+            if !reported.insert(call) {
+                continue;
+            }
+            let reason = NATIVE_CALLS.iter().find(|(name, _)| *name == call).map(|(_, reason)| *reason)
+                .unwrap_or("replication runs over UDP sockets");
+            conflict(format!("'{}' isn't available with --target=wasm: {}", call, reason),
+                "Call it from the native build only");
+        }
+    }
+    conflicts
+}
+
+/// The program with @hot cleared everywhere: a browser build compiles hot items into the program
+pub fn without_hot_reload(program: &Program) -> Program {
+    let mut program = program.clone();
+    for item in &mut program.items {
+        match item {
+            Item::Component(c) => c.is_hot = false,
+            Item::System(s) => s.is_hot = false,
+            Item::Function(f) => f.is_hot = false,
+            Item::Shader(shader) => shader.is_hot = false,
+            Item::Resource(r) => r.is_hot = false,
+            _ => {}
+        }
+    }
+    program
+}
+
+/// The files the page preloads into the virtual file system and watches: tweak_file() paths
+pub fn watched_files(program: &Program) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for (name, args, _) in audit::calls(program) {
+        if name != "tweak_file" {
+            continue;
+        }
+        if let Some(Expression::Literal(Literal::String(path), _)) = args.first() {
+            if !files.contains(path) {
+                files.push(path.clone());
+            }
+        }
+    }
+    files
+}
+
+/// <name>.html: requests a WebGPU device, runs <name>.js on the page's canvas, and with ?hot
+/// reloads when <name>.wasm changes and passes changed watched files to heidic_reload()
+pub fn page_harness(name: &str, watched: &[String]) -> String {
+    let title = name.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    PAGE_HARNESS
+        .replace("{title}", &title)
+        .replace("{script}", &serde_json::to_string(&format!("{}.js", name)).unwrap_or_default())
+        .replace("{wasm}", &serde_json::to_string(&format!("{}.wasm", name)).unwrap_or_default())
+        .replace("{watched}", &serde_json::to_string(watched).unwrap_or_default())
+}
+
+const PAGE_HARNESS: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+  html, body { margin: 0; background: #111; color: #ccc; font: 14px sans-serif; }
+  canvas { display: block; margin: 0 auto; outline: none; }
+  #status { text-align: center; padding: 8px; }
+</style>
+</head>
+<body>
+<canvas id="canvas" tabindex="-1"></canvas>
+<div id="status"></div>
+<script>
+// Generated by heidic (--target=wasm). Serve this directory over HTTP and open this page; add ?hot
+// to reload when the program is rebuilt and to pass changed tweak files to the running program.
+const SCRIPT = {script};
+const WASM = {wasm};
+const WATCHED = {watched};
+const HOT = new URLSearchParams(location.search).has('hot');
+const statusLine = document.getElementById('status');
+
+var Module = {
+  canvas: document.getElementById('canvas'),
+  print: (text) => console.log(text),
+  printErr: (text) => console.error(text),
+  onAbort: (what) => { statusLine.textContent = 'Stopped: ' + what; },
+  // Watched files are in the virtual file system before main() runs
+  preRun: [() => {
+    Module.addRunDependency('heidic-files');
+    Promise.all(WATCHED.map(loadFile)).finally(() => Module.removeRunDependency('heidic-files'));
+  }],
+};
+
+function version(response) {
+  return response.headers.get('ETag') || response.headers.get('Last-Modified');
+}
+
+async function probe(path) {
+  try {
+    const response = await fetch(path, { method: 'HEAD', cache: 'no-store' });
+    return response.ok ? version(response) : null;
+  } catch (e) {
+    return null;
+  }
+}
+
+// Copy a served file into the virtual file system; its version, or null when it isn't served
+async function loadFile(path) {
+  const response = await fetch(path, { cache: 'no-store' });
+  if (!response.ok) {
+    return null;
+  }
+  const data = new Uint8Array(await response.arrayBuffer());
+  const dir = path.substring(0, path.lastIndexOf('/'));
+  if (dir) {
+    Module.FS.mkdirTree(dir);
+  }
+  Module.FS.writeFile(path, data);
+  return version(response);
+}
+
+async function watch() {
+  const versions = new Map();
+  for (const path of [WASM, ...WATCHED]) {
+    versions.set(path, await probe(path));
+  }
+  setInterval(async () => {
+    if (await probe(WASM) !== versions.get(WASM)) {
+      location.reload();
+      return;
+    }
+    let changed = false;
+    for (const path of WATCHED) {
+      const current = await probe(path);
+      if (current !== null && current !== versions.get(path)) {
+        versions.set(path, await loadFile(path));
+        changed = true;
+      }
+    }
+    if (changed && Module._heidic_reload) {
+      Module._heidic_reload();
+    }
+  }, 500);
+}
+
+async function start() {
+  if (navigator.gpu) {
+    const adapter = await navigator.gpu.requestAdapter();
+    if (adapter) {
+      Module.heidicGpuDevice = await adapter.requestDevice();
+    }
+  }
+  if (!Module.heidicGpuDevice) {
+    statusLine.textContent = 'WebGPU is unavailable in this browser: nothing will be drawn';
+  }
+  const script = document.createElement('script');
+  script.src = SCRIPT;
+  document.body.appendChild(script);
+  if (HOT) {
+    watch();
+  }
+}
+
+start();
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,127 @@
+// Workspaces: several programs (game, dedicated server, asset tool) built together from one
+// heidic.toml, sharing component and struct definitions from common modules.
+// Shared items are prepended to every member's program, so each binary gets the same layout.
+// Members can still declare components of their own; when two members declare the same name
+// differently, the binaries disagree on its layout (saves, network packets), so that's reported.
+
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+
+use crate::ast::*;
+use crate::formatter::type_str;
+
+/// A shared module, already parsed and checked on its own
+pub struct SharedModule {
+    pub path: String,
+    pub items: Vec<Item>,
+}
+
+impl SharedModule {
+    pub fn new(path: &str, program: Program) -> Result<Self> {
+        for item in &program.items {
+            if type_name(item).is_none() {
+                bail!("{}: shared modules may only contain components and structs (found {})", path, item_kind(item));
+            }
+        }
+        Ok(Self { path: path.to_string(), items: program.items })
+    }
+}
+
+/// Member items that redefine a shared component or struct, as (message, suggestion)
+pub fn shared_conflicts(shared: &[SharedModule], program: &Program) -> Vec<(String, String)> {
+    let mut conflicts = Vec::new();
+    for item in &program.items {
+        let Some(name) = type_name(item) else { continue };
+        if let Some(module) = shared.iter().find(|m| m.items.iter().any(|i| type_name(i) == Some(name))) {
+            conflicts.push((
+                format!("{} '{}' is already defined by shared module {}", item_kind(item), name, module.path),
+                format!("Remove this definition, or rename it if it's meant to be a different type than the shared '{}'", name),
+            ));
+        }
+    }
+    conflicts
+}
+
+/// A type declared with different layouts by different members
+pub struct Divergence {
+    pub name: String,
+    pub layouts: Vec<(String, String)>,  // (member, layout signature)
+}
+
+/// Compare every component and struct across the members' programs. Returns the number of
+/// types used by more than one member with a consistent layout, and the ones that diverge.
+pub fn check_layouts(members: &[(String, Program)]) -> (usize, Vec<Divergence>) {
+    let mut layouts: BTreeMap<&str, Vec<(String, String)>> = BTreeMap::new();
+    for (member, program) in members {
+        for item in &program.items {
+            if let Some(name) = type_name(item) {
+                layouts.entry(name).or_default().push((member.clone(), layout_signature(item)));
+            }
+        }
+    }
+
+    let mut consistent = 0;
+    let mut divergences = Vec::new();
+    for (name, layouts) in layouts {
+        if layouts.len() < 2 {
+            continue;
+        }
+        if layouts.iter().all(|(_, signature)| *signature == layouts[0].1) {
+            consistent += 1;
+        } else {
+            divergences.push(Divergence { name: name.to_string(), layouts });
+        }
+    }
+    (consistent, divergences)
+}
+
+fn type_name(item: &Item) -> Option<&str> {
+    match item {
+        Item::Component(c) => Some(&c.name),
+        Item::Struct(s) => Some(&s.name),
+        Item::Event(e) => Some(&e.name),
+        Item::Uniform(u) => Some(&u.name),
+        _ => None,
+    }
+}
+
+fn item_kind(item: &Item) -> &'static str {
+    match item {
+        Item::Struct(_) => "struct",
+        Item::Component(c) if c.is_soa => "component_soa",
+        Item::Component(_) => "component",
+        Item::System(_) => "system",
+        Item::Shader(_) => "shader",
+        Item::Function(_) => "fn",
+        Item::ExternFunction(_) => "extern fn",
+        Item::Resource(_) => "resource",
+        Item::Pipeline(_) => "pipeline",
+        Item::Gpu(_) => "gpu",
+        Item::Interface(_) => "interface",
+        Item::Impl(_) => "impl",
+        Item::GpuQuery(_) => "gpu_query",
+        Item::Test(_) => "test",
+        Item::Const(_) => "const",
+        Item::StaticAssert(_) => "static_assert",
+        Item::Event(_) => "event",
+        Item::Uniform(_) => "uniform",
+        Item::Input(_) => "input",
+        Item::Window(_) => "window",
+        Item::SaveSchema(_) => "save_schema",
+        Item::Scene(_) => "scene",
+        Item::Migrate(_) => "migrate",
+    }
+}
+
+// Everything that affects the generated C++ layout: kind, field order, names and types
+fn layout_signature(item: &Item) -> String {
+    let fields = match item {
+        Item::Component(c) => &c.fields,
+        Item::Struct(s) => &s.fields,
+        Item::Event(e) => &e.fields,
+        Item::Uniform(u) => &u.fields,
+        _ => return String::new(),
+    };
+    let fields: Vec<String> = fields.iter().map(|f| format!("{}: {}", f.name, type_str(&f.ty))).collect();
+    format!("{} {{ {} }}", item_kind(item), fields.join(", "))
+}
@@ -0,0 +1,501 @@
+// EDEN ENGINE Standard Library - Archetype Entity Storage
+// The EntityStorage used with `heidic_v2 compile --ecs-storage=archetype` (generated code defines
+// HEIDIC_ARCHETYPE_STORAGE before including entity_storage.h, which includes this header).
+//
+// Entities with the same set of components share an archetype, whose components live in one
+// contiguous column per component type, row i of every column belonging to entities[i]. Adding
+// or removing a component moves the entity's row to the archetype of its new component set, so
+// structural changes cost more than with the sparse-set storage, while iteration is a linear scan
+// of each matching archetype: for_each_chunk<Position, Velocity>(...) hands out whole columns.
+//
+// The API matches the sparse-set EntityStorage, so generated code is the same for both backends.
+
+#pragma once
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <map>
+#include <memory>
+#include <optional>
+#include <type_traits>
+#include <typeinfo>
+#include <utility>
+#include <vector>
+
+#include "type_map.h"
+
+#ifndef HEIDIC_ARCHETYPE_STORAGE
+#error "archetype_storage.h is included by entity_storage.h when HEIDIC_ARCHETYPE_STORAGE is defined"
+#endif
+
+// -----------------------------------------------------------------------------
+// Type-erased column: one component type's values for every row of an archetype
+// -----------------------------------------------------------------------------
+struct IArchetypeColumn {
+    virtual ~IArchetypeColumn() = default;
+    virtual std::unique_ptr<IArchetypeColumn> empty_copy() const = 0;  // Same component type, no rows
+    virtual void move_row(size_t row, IArchetypeColumn& dst) = 0;      // Append this row's value to dst
+    virtual void swap_remove(size_t row) = 0;
+    virtual void reserve(size_t rows) = 0;
+    virtual size_t capacity() const = 0;  // Rows the allocated memory holds
+    virtual size_t bytes() const = 0;     // Allocated bytes, ticks included
+
+    std::vector<uint32_t> ticks;  // Change tick per row (for changed<T> query filters)
+};
+
+template <typename T, bool Soa = is_soa_component<T>::value>
+struct ArchetypeColumn final : IArchetypeColumn {
+    using Value = T;
+    std::vector<T> values;
+
+    std::unique_ptr<IArchetypeColumn> empty_copy() const override { return std::make_unique<ArchetypeColumn>(); }
+    void move_row(size_t row, IArchetypeColumn& dst) override {
+        auto& column = static_cast<ArchetypeColumn&>(dst);
+        column.values.emplace_back(std::move(values[row]));
+        column.ticks.emplace_back(ticks[row]);
+    }
+    void swap_remove(size_t row) override {
+        values[row] = std::move(values.back());
+        values.pop_back();
+        ticks[row] = ticks.back();
+        ticks.pop_back();
+    }
+    void reserve(size_t rows) override {
+        values.reserve(rows);
+        ticks.reserve(rows);
+    }
+    size_t capacity() const override { return values.capacity(); }
+    size_t bytes() const override { return values.capacity() * sizeof(T) + ticks.capacity() * sizeof(uint32_t); }
+
+    void push(const T& value, uint32_t tick) {
+        values.emplace_back(value);
+        ticks.emplace_back(tick);
+    }
+    void set(size_t row, const T& value) { values[row] = value; }
+    T* data() { return values.data(); }
+};
+
+// A component_soa column is a single T holding every row's fields (see is_soa_component)
+template <typename T>
+struct ArchetypeColumn<T, true> final : IArchetypeColumn {
+    using Value = typename T::Row;
+    T arrays;
+
+    std::unique_ptr<IArchetypeColumn> empty_copy() const override { return std::make_unique<ArchetypeColumn>(); }
+    void move_row(size_t row, IArchetypeColumn& dst) override {
+        auto& column = static_cast<ArchetypeColumn&>(dst);
+        column.arrays.heidic_push(arrays.heidic_row(row));
+        column.ticks.emplace_back(ticks[row]);
+    }
+    void swap_remove(size_t row) override {
+        arrays.heidic_move(ticks.size() - 1, row);
+        arrays.heidic_pop();
+        ticks[row] = ticks.back();
+        ticks.pop_back();
+    }
+    void reserve(size_t rows) override {
+        arrays.heidic_reserve(rows);
+        ticks.reserve(rows);
+    }
+    // The field arrays grow together with ticks
+    size_t capacity() const override { return ticks.capacity(); }
+    size_t bytes() const override { return arrays.heidic_bytes() + ticks.capacity() * sizeof(uint32_t); }
+
+    void push(const Value& row, uint32_t tick) {
+        arrays.heidic_push(row);
+        ticks.emplace_back(tick);
+    }
+    void set(size_t row, const Value& value) { arrays.heidic_set(row, value); }
+    T* data() { return &arrays; }
+};
+
+// -----------------------------------------------------------------------------
+// Archetype: the entities that have exactly one set of components
+// -----------------------------------------------------------------------------
+struct Archetype {
+    std::vector<size_t> types;  // Sorted component type keys
+    std::vector<std::unique_ptr<IArchetypeColumn>> columns;  // Parallel to types
+    std::vector<EntityId> entities;  // Row -> entity
+    size_t high_water = 0;  // Most rows at once
+
+    // Column index of a component type, or -1
+    int column_of(size_t type) const {
+        auto it = std::lower_bound(types.begin(), types.end(), type);
+        return it != types.end() && *it == type ? static_cast<int>(it - types.begin()) : -1;
+    }
+};
+
+// -----------------------------------------------------------------------------
+// EntityStorage: entities + archetypes
+// -----------------------------------------------------------------------------
+class EntityStorage {
+public:
+    // What storage<T>() returns: spawn_batch adds its components through it
+    template <typename T>
+    struct BatchInserter {
+        EntityStorage& owner;
+        void reserve(size_t additional, EntityId max_entity) { owner.reserve_locations(max_entity); (void)additional; }
+        // A component, or for a component_soa one entity's values (its Row)
+        template <typename Value>
+        void add(EntityId entity, const Value& value, uint32_t tick = 0) { owner.insert<T>(entity, value, tick); }
+    };
+
+    EntityId create_entity() {
+        if (!free_list.empty()) {
+            EntityId id = free_list.back();
+            free_list.pop_back();
+            return id;
+        }
+        return ++next_id;
+    }
+
+    // Allocate `count` entities with consecutive ids and return the first one
+    EntityId create_entities(uint32_t count) {
+        EntityId first = next_id + 1;
+        next_id += count;
+        return first;
+    }
+
+    // Make room for `count` more entities in every archetype, including ones created later
+    void reserve_entities(uint32_t count) {
+        reserved = count;
+        reserve_locations(next_id + count);
+        for (auto& archetype : archetypes) {
+            reserve_rows(*archetype, archetype->entities.size() + count);
+        }
+    }
+
+    EntityId entity_limit() const { return next_id; }
+    const std::vector<EntityId>& free_entities() const { return free_list; }
+
+    void restore_entities(EntityId limit, std::vector<EntityId> free, uint32_t start_tick) {
+        next_id = limit;
+        free_list = std::move(free);
+        tick = start_tick;
+        reserve_locations(limit);
+    }
+
+    void destroy_entity(EntityId entity) {
+        if (Location* location = locate(entity)) {
+            for (size_t type : location->archetype->types) {
+                --tallies[type].count;
+            }
+            remove_row(*location->archetype, location->row);
+            *location = Location{};
+        }
+        free_list.push_back(entity);
+    }
+
+    template <typename T>
+    void add_component(EntityId entity, const T& component) {
+        insert<T>(entity, component, ++tick);
+    }
+
+    // A component_soa is added from one entity's values (its Row)
+    template <typename T>
+    void add_component(EntityId entity, const typename T::Row& row) {
+        insert<T>(entity, row, ++tick);
+    }
+
+    template <typename T>
+    T* get_component(EntityId entity) {
+        static_assert(!is_soa_component<T>::value, "component_soa values are read with get_row<T>()");
+        auto* column = column_for<T>(entity);
+        return column ? &column->values[locations[entity].row] : nullptr;
+    }
+
+    // component_soa values are spread over the field arrays, so they are read as a copy
+    template <typename T>
+    std::optional<typename T::Row> get_row(EntityId entity) {
+        auto* column = column_for<T>(entity);
+        if (!column) return std::nullopt;
+        return column->arrays.heidic_row(locations[entity].row);
+    }
+
+    // Write a component_soa's values back without taking a tick (queries copy components out
+    // and back; writes that count as changes are recorded with mark_changed())
+    template <typename T>
+    void set_row(EntityId entity, const typename T::Row& row) {
+        auto* column = column_for<T>(entity);
+        if (column) column->set(locations[entity].row, row);
+    }
+
+    template <typename T>
+    bool has_component(EntityId entity) const {
+        const Location* location = locate(entity);
+        return location && location->archetype->column_of(type_key<T>()) >= 0;
+    }
+
+    uint32_t change_tick() const { return tick; }
+
+    uint32_t advance_tick() { return ++tick; }
+
+    template <typename T>
+    void mark_changed(EntityId entity) {
+        if (auto* column = column_for<T>(entity)) {
+            column->ticks[locations[entity].row] = ++tick;
+            versions[type_key<T>()] = tick;
+        }
+    }
+
+    template <typename T>
+    bool changed_since(EntityId entity, uint32_t since) const {
+        const Location* location = locate(entity);
+        if (!location) return false;
+        int index = location->archetype->column_of(type_key<T>());
+        return index >= 0 && location->archetype->columns[index]->ticks[location->row] > since;
+    }
+
+    template <typename T>
+    uint32_t component_version() const {
+        auto it = versions.find(type_key<T>());
+        return it == versions.end() ? 0 : it->second;
+    }
+
+    template <typename T>
+    void remove_component(EntityId entity) {
+        Location* location = locate(entity);
+        size_t type = type_key<T>();
+        if (!location || location->archetype->column_of(type) < 0) return;
+        --tallies[type].count;
+        Archetype& source = *location->archetype;
+        std::vector<size_t> types;
+        for (size_t other : source.types) {
+            if (other != type) types.push_back(other);
+        }
+        if (types.empty()) {
+            remove_row(source, location->row);
+            *location = Location{};
+            return;
+        }
+        Archetype& target = archetype_for(types, source, nullptr);
+        move_entity(entity, source, target);
+    }
+
+    // Every entity with a T, archetype by archetype
+    template <typename T, typename Func>
+    void for_each(Func&& func) {
+        size_t type = type_key<T>();
+        for (auto& archetype : archetypes) {
+            int index = archetype->column_of(type);
+            if (index < 0) continue;
+            auto& column = static_cast<ArchetypeColumn<T>&>(*archetype->columns[index]);
+            for (size_t row = 0; row < archetype->entities.size(); ++row) {
+                if constexpr (is_soa_component<T>::value) {
+                    func(archetype->entities[row], column.arrays.heidic_row(row));
+                } else {
+                    func(archetype->entities[row], column.values[row]);
+                }
+            }
+        }
+    }
+
+    // Every archetype with all of Ts, as contiguous columns:
+    // func(count, entities, Ts*...) where element i of each column belongs to entities[i]. A
+    // component_soa column is passed as its structure of arrays (velocities->x[i]).
+    template <typename... Ts, typename Func>
+    void for_each_chunk(Func&& func) {
+        const size_t types[] = {type_key<Ts>()...};
+        for (auto& archetype : archetypes) {
+            if (archetype->entities.empty()) continue;
+            bool matches = std::all_of(std::begin(types), std::end(types), [&](size_t type) { return archetype->column_of(type) >= 0; });
+            if (!matches) continue;
+            func(archetype->entities.size(), archetype->entities.data(),
+                 static_cast<ArchetypeColumn<Ts>&>(*archetype->columns[archetype->column_of(type_key<Ts>())]).data()...);
+        }
+    }
+
+    // Bulk insertion, for loops that add many components at once (spawn_batch)
+    template <typename T>
+    BatchInserter<T> storage() {
+        return BatchInserter<T>{*this};
+    }
+
+    size_t archetype_count() const { return archetypes.size(); }
+
+    // Memory of T's columns over every archetype that has them
+    template <typename T>
+    HeidicComponentMemory memory_usage() const {
+        HeidicComponentMemory usage;
+        size_t type = type_key<T>();
+        for (auto& archetype : archetypes) {
+            int index = archetype->column_of(type);
+            if (index < 0) continue;
+            usage.count += archetype->entities.size();
+            usage.capacity += archetype->columns[index]->capacity();
+            usage.bytes += archetype->columns[index]->bytes();
+        }
+        auto it = tallies.find(type);
+        usage.high_water = it == tallies.end() ? 0 : it->second.high_water;
+        return usage;
+    }
+
+    // Every archetype's component type keys and memory (rows, entity ids and all its columns)
+    std::vector<std::pair<std::vector<size_t>, HeidicComponentMemory>> archetype_memory() const {
+        std::vector<std::pair<std::vector<size_t>, HeidicComponentMemory>> result;
+        for (auto& archetype : archetypes) {
+            HeidicComponentMemory usage;
+            usage.count = archetype->entities.size();
+            usage.capacity = archetype->entities.capacity();
+            usage.high_water = archetype->high_water;
+            usage.bytes = archetype->entities.capacity() * sizeof(EntityId);
+            for (auto& column : archetype->columns) {
+                usage.bytes += column->bytes();
+            }
+            result.emplace_back(archetype->types, usage);
+        }
+        return result;
+    }
+
+    // Bytes of the entity allocator and the entity -> row index
+    size_t bookkeeping_bytes() const {
+        return free_list.capacity() * sizeof(EntityId) + locations.capacity() * sizeof(Location);
+    }
+
+private:
+    struct Location {
+        Archetype* archetype = nullptr;  // nullptr while the entity has no components
+        uint32_t row = 0;
+    };
+
+    struct Tally {
+        size_t count = 0;       // Entities with the component
+        size_t high_water = 0;  // Most at once
+        size_t pool = 0;        // @[pool] capacity (0: grows as needed)
+    };
+
+    EntityId next_id {0};
+    uint32_t reserved {0};  // Last reserve_entities() count, applied to archetypes created afterwards
+    uint32_t tick {0};      // Change-detection clock, advanced by every add and mark_changed()
+    std::vector<EntityId> free_list;
+    std::vector<Location> locations;  // Entity -> archetype row
+    std::vector<std::unique_ptr<Archetype>> archetypes;
+    std::map<std::vector<size_t>, Archetype*> by_types;
+    HeidicTypeMap<size_t, uint32_t> versions;  // Component type -> latest add/change tick
+    HeidicTypeMap<size_t, Tally> tallies;      // Component type -> count (memory_report())
+
+    template <typename T>
+    static size_t type_key() { return typeid(T).hash_code(); }
+
+    void reserve_locations(EntityId max_entity) {
+        if (max_entity >= locations.size()) {
+            locations.resize(static_cast<size_t>(max_entity) + 1);
+        }
+    }
+
+    static void reserve_rows(Archetype& archetype, size_t rows) {
+        archetype.entities.reserve(rows);
+        for (auto& column : archetype.columns) {
+            column->reserve(rows);
+        }
+    }
+
+    Location* locate(EntityId entity) {
+        return entity < locations.size() && locations[entity].archetype ? &locations[entity] : nullptr;
+    }
+
+    const Location* locate(EntityId entity) const {
+        return entity < locations.size() && locations[entity].archetype ? &locations[entity] : nullptr;
+    }
+
+    template <typename T>
+    ArchetypeColumn<T>* column_for(EntityId entity) {
+        Location* location = locate(entity);
+        if (!location) return nullptr;
+        int index = location->archetype->column_of(type_key<T>());
+        return index >= 0 ? static_cast<ArchetypeColumn<T>*>(location->archetype->columns[index].get()) : nullptr;
+    }
+
+    // The archetype with exactly `types`, created with the columns of `like` (plus `added`)
+    Archetype& archetype_for(const std::vector<size_t>& types, const Archetype& like, std::unique_ptr<IArchetypeColumn> added, size_t added_type = 0) {
+        auto it = by_types.find(types);
+        if (it != by_types.end()) {
+            return *it->second;
+        }
+        auto archetype = std::make_unique<Archetype>();
+        archetype->types = types;
+        for (size_t type : types) {
+            if (added && type == added_type) {
+                archetype->columns.push_back(std::move(added));
+            } else {
+                archetype->columns.push_back(like.columns[like.column_of(type)]->empty_copy());
+            }
+        }
+        // An archetype holding a pooled component can't have more rows than the pool, so it is
+        // allocated for all of them up front
+        size_t rows = reserved;
+        for (size_t type : types) {
+            auto tally = tallies.find(type);
+            if (tally != tallies.end() && tally->second.pool > 0) rows = std::max(rows, tally->second.pool);
+        }
+        if (rows > 0) {
+            reserve_rows(*archetype, rows);
+        }
+        Archetype* ptr = archetype.get();
+        archetypes.push_back(std::move(archetype));
+        by_types.emplace(types, ptr);
+        return *ptr;
+    }
+
+    // Swap-remove a row; the archetype's last entity takes its place
+    void remove_row(Archetype& archetype, uint32_t row) {
+        for (auto& column : archetype.columns) {
+            column->swap_remove(row);
+        }
+        EntityId moved = archetype.entities.back();
+        archetype.entities[row] = moved;
+        archetype.entities.pop_back();
+        if (row < archetype.entities.size()) {
+            locations[moved].row = row;
+        }
+    }
+
+    // Move an entity's row into `target`, whose columns are a superset or subset of the source's
+    void move_entity(EntityId entity, Archetype& source, Archetype& target) {
+        uint32_t row = locations[entity].row;
+        for (size_t i = 0; i < source.types.size(); ++i) {
+            int index = target.column_of(source.types[i]);
+            if (index >= 0) {
+                source.columns[i]->move_row(row, *target.columns[index]);
+            }
+        }
+        target.entities.push_back(entity);
+        target.high_water = std::max(target.high_water, target.entities.size());
+        remove_row(source, row);
+        locations[entity] = Location{&target, static_cast<uint32_t>(target.entities.size() - 1)};
+    }
+
+    template <typename T, typename Value>
+    void insert(EntityId entity, const Value& value, uint32_t at_tick) {
+        reserve_locations(entity);
+        size_t type = type_key<T>();
+        uint32_t& version = versions[type];
+        version = std::max(version, at_tick);
+        if (auto* column = column_for<T>(entity)) {
+            // Already has this component; overwrite
+            column->set(locations[entity].row, value);
+            column->ticks[locations[entity].row] = at_tick;
+            return;
+        }
+        Tally& tally = tallies[type];
+        tally.pool = heidic_pool_capacity<T>::value;
+        if (!heidic_pool_admits<T>(tally.count)) return;
+        static const Archetype no_components;
+        Location* location = locate(entity);
+        const Archetype& source = location ? *location->archetype : no_components;
+        std::vector<size_t> types = source.types;
+        types.insert(std::upper_bound(types.begin(), types.end(), type), type);
+        Archetype& target = archetype_for(types, source, std::make_unique<ArchetypeColumn<T>>(), type);
+        if (location) {
+            move_entity(entity, *location->archetype, target);
+        } else {
+            target.entities.push_back(entity);
+            target.high_water = std::max(target.high_water, target.entities.size());
+            locations[entity] = Location{&target, static_cast<uint32_t>(target.entities.size() - 1)};
+        }
+        tally.high_water = std::max(tally.high_water, ++tally.count);
+        auto& column = static_cast<ArchetypeColumn<T>&>(*target.columns[target.column_of(type)]);
+        column.push(value, at_tick);
+    }
+};
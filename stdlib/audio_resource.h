@@ -5,6 +5,10 @@
 // Supports two types:
 // - Sound: Short audio clips (effects, UI sounds) - loaded into memory
 // - Music: Long audio tracks (background music) - streamed from disk
+//
+// Each clip has its own volume, can loop, and can be paused and resumed. HeidicAudioMixer holds the
+// master and per-channel volumes the generated set_*_volume() functions apply on top. Positional
+// clips (@[spatial] sounds) are also attenuated and panned by heidic_spatial_mix() every frame.
 
 #ifndef EDEN_AUDIO_RESOURCE_H
 #define EDEN_AUDIO_RESOURCE_H
@@ -14,6 +18,8 @@
 #include <memory>
 #include <stdexcept>
 #include <algorithm>
+#include <atomic>
+#include <cmath>
 #include <cstdint>
 #include <iostream>
 #include <fstream>
@@ -86,6 +92,109 @@ private:
     // For Music type (streamed)
     // SDL3 handles streaming internally
     
+    // Playback controls: the stream plays at m_volume * m_mixGain (the mixer's master and channel
+    // volumes). A looping clip is queued again from SDL's audio thread whenever the stream runs low.
+    float m_volume = 1.0f;
+    float m_mixGain = 1.0f;
+    std::atomic<bool> m_looping{false};
+    bool m_paused = false;
+    
+    // Positional playback (@[spatial] sounds): the clip is mixed down to mono floats once and fed
+    // to a stereo stream in small chunks, each scaled by the current left/right gains, so the
+    // attenuation and pan setSpatial() sets are heard within a chunk
+    bool m_positional = false;
+    std::vector<float> m_monoSamples;
+    std::atomic<size_t> m_cursor{0};
+    std::atomic<float> m_gainLeft{1.0f};
+    std::atomic<float> m_gainRight{1.0f};
+    
+#ifdef SDL3_AUDIO_AVAILABLE
+    /**
+     * SDL get-callback for the playback stream: re-queues the clip while looping
+     */
+    static void SDLCALL loopCallback(void* userdata, SDL_AudioStream* stream, int additional_amount, int /*total_amount*/) {
+        auto* self = static_cast<AudioResource*>(userdata);
+        if (!self->m_looping.load() || self->m_audioData.empty()) {
+            return;
+        }
+        while (SDL_GetAudioStreamQueued(stream) < additional_amount) {
+            if (!SDL_PutAudioStreamData(stream, self->m_audioData.data(), static_cast<int>(self->m_audioData.size()))) {
+                return;
+            }
+        }
+    }
+    
+    /**
+     * SDL get-callback for positional playback: the next chunk of mono samples as panned stereo
+     */
+    static void SDLCALL spatialCallback(void* userdata, SDL_AudioStream* stream, int additional_amount, int /*total_amount*/) {
+        auto* self = static_cast<AudioResource*>(userdata);
+        const std::vector<float>& samples = self->m_monoSamples;
+        const float left = self->m_gainLeft.load();
+        const float right = self->m_gainRight.load();
+        constexpr size_t CHUNK_FRAMES = 512;
+        float chunk[CHUNK_FRAMES * 2];
+        size_t frames = static_cast<size_t>(additional_amount) / (2 * sizeof(float));
+        while (frames > 0 && !samples.empty()) {
+            size_t cursor = self->m_cursor.load();
+            if (cursor >= samples.size()) {
+                if (!self->m_looping.load()) {
+                    return;
+                }
+                cursor = 0;
+            }
+            const size_t count = std::min({frames, CHUNK_FRAMES, samples.size() - cursor});
+            for (size_t i = 0; i < count; ++i) {
+                chunk[2 * i] = samples[cursor + i] * left;
+                chunk[2 * i + 1] = samples[cursor + i] * right;
+            }
+            if (!SDL_PutAudioStreamData(stream, chunk, static_cast<int>(count * 2 * sizeof(float)))) {
+                return;
+            }
+            self->m_cursor = cursor + count;
+            frames -= count;
+        }
+    }
+    
+    SDL_AudioStreamCallback streamCallback() const {
+        return m_positional ? spatialCallback : loopCallback;
+    }
+    
+    /**
+     * Convert the clip to mono floats for positional playback (once per load)
+     * @return true if the samples are ready
+     */
+    bool prepareMonoSamples() {
+        if (!m_monoSamples.empty()) {
+            return true;
+        }
+        SDL_AudioSpec mono = m_spec;
+        mono.format = SDL_AUDIO_F32;
+        mono.channels = 1;
+        Uint8* converted = nullptr;
+        int length = 0;
+        if (!SDL_ConvertAudioSamples(&m_spec, m_audioData.data(), static_cast<int>(m_audioData.size()), &mono, &converted, &length)) {
+            std::cerr << "[Audio] SDL_ConvertAudioSamples failed: " << SDL_GetError() << std::endl;
+            return false;
+        }
+        const float* begin = reinterpret_cast<const float*>(converted);
+        m_monoSamples.assign(begin, begin + length / static_cast<int>(sizeof(float)));
+        SDL_free(converted);
+        return !m_monoSamples.empty();
+    }
+#endif
+    
+    /**
+     * Apply volume * mix gain to the playing stream
+     */
+    void applyGain() {
+#ifdef SDL3_AUDIO_AVAILABLE
+        if (m_audioStream) {
+            SDL_SetAudioStreamGain(m_audioStream, m_volume * m_mixGain);
+        }
+#endif
+    }
+    
     /**
      * Detect audio type from file extension
      * @param filepath Path to audio file
@@ -236,7 +345,22 @@ public:
           m_audioStream(other.m_audioStream),
           m_audioDevice(other.m_audioDevice),
           m_audioData(std::move(other.m_audioData)),
-          m_spec(other.m_spec) {
+          m_spec(other.m_spec),
+          m_volume(other.m_volume),
+          m_mixGain(other.m_mixGain),
+          m_looping(other.m_looping.load()),
+          m_paused(other.m_paused),
+          m_positional(other.m_positional),
+          m_monoSamples(std::move(other.m_monoSamples)),
+          m_cursor(other.m_cursor.load()),
+          m_gainLeft(other.m_gainLeft.load()),
+          m_gainRight(other.m_gainRight.load()) {
+#ifdef SDL3_AUDIO_AVAILABLE
+        // The stream's callback points at the object it was opened for
+        if (m_audioStream) {
+            SDL_SetAudioStreamGetCallback(m_audioStream, streamCallback(), this);
+        }
+#endif
         other.m_audioStream = nullptr;
         other.m_audioDevice = 0;
         other.m_loaded = false;
@@ -254,6 +378,20 @@ public:
             m_audioDevice = other.m_audioDevice;
             m_audioData = std::move(other.m_audioData);
             m_spec = other.m_spec;
+            m_volume = other.m_volume;
+            m_mixGain = other.m_mixGain;
+            m_looping = other.m_looping.load();
+            m_paused = other.m_paused;
+            m_positional = other.m_positional;
+            m_monoSamples = std::move(other.m_monoSamples);
+            m_cursor = other.m_cursor.load();
+            m_gainLeft = other.m_gainLeft.load();
+            m_gainRight = other.m_gainRight.load();
+#ifdef SDL3_AUDIO_AVAILABLE
+            if (m_audioStream) {
+                SDL_SetAudioStreamGetCallback(m_audioStream, streamCallback(), this);
+            }
+#endif
             
             other.m_audioStream = nullptr;
             other.m_audioDevice = 0;
@@ -284,12 +422,13 @@ public:
         }
 #endif
         m_audioData.clear();
+        m_monoSamples.clear();
         m_loaded = false;
     }
     
     /**
      * Play the audio
-     * @param loop Whether to loop the audio (default: false)
+     * @param loop Whether to loop the audio until stopped (default: false)
      * @return true if playback started successfully
      */
     bool play(bool loop = false) {
@@ -301,6 +440,7 @@ public:
         
         // Stop any existing playback
         stop();
+        m_looping = loop;
         
         // Ensure SDL3 is initialized (SDL3 requires SDL_Init() before subsystems)
         // SDL3 returns 0 on failure, non-zero on success (opposite of SDL2!)
@@ -313,18 +453,31 @@ public:
         
         // SDL3 audio API: Use SDL_OpenAudioDeviceStream which combines device and stream creation
         // This is the recommended way in SDL3
-        SDL_AudioStream* stream = SDL_OpenAudioDeviceStream(SDL_AUDIO_DEVICE_DEFAULT_PLAYBACK, &m_spec, nullptr, nullptr);
+        // Positional clips play as panned stereo floats, fed by spatialCallback
+        SDL_AudioSpec streamSpec = m_spec;
+        if (m_positional) {
+            if (!prepareMonoSamples()) {
+                m_looping = false;
+                return false;
+            }
+            streamSpec.format = SDL_AUDIO_F32;
+            streamSpec.channels = 2;
+            m_cursor = 0;
+        }
+        SDL_AudioStream* stream = SDL_OpenAudioDeviceStream(SDL_AUDIO_DEVICE_DEFAULT_PLAYBACK, &streamSpec, streamCallback(), this);
         if (!stream) {
+            m_looping = false;
             // Stream creation failed
             std::cerr << "[Audio] SDL_OpenAudioDeviceStream failed: " << SDL_GetError() << std::endl;
             return false;
         }
         
-        // Queue the audio data
-        int result = SDL_PutAudioStreamData(stream, m_audioData.data(), static_cast<int>(m_audioData.size()));
+        // Queue the audio data (positional clips are queued chunk by chunk from the callback)
+        int result = m_positional ? 0 : SDL_PutAudioStreamData(stream, m_audioData.data(), static_cast<int>(m_audioData.size()));
         if (result < 0) {
             std::cerr << "[Audio] SDL_PutAudioStreamData failed: " << SDL_GetError() << std::endl;
             SDL_DestroyAudioStream(stream);
+            m_looping = false;
             return false;
         }
         
@@ -334,6 +487,8 @@ public:
         // Store references for cleanup
         m_audioStream = stream;
         m_audioDevice = 0;  // Device is managed by the stream
+        m_paused = false;
+        applyGain();
         
         return true;
 #else
@@ -348,6 +503,8 @@ public:
      * Stop playback
      */
     void stop() {
+        m_looping = false;
+        m_paused = false;
 #ifdef SDL3_AUDIO_AVAILABLE
         if (m_audioStream) {
             // Pause the stream device
@@ -365,6 +522,100 @@ public:
 #endif
     }
     
+    /**
+     * Pause playback; resume() continues from the same position
+     */
+    void pause() {
+#ifdef SDL3_AUDIO_AVAILABLE
+        if (m_audioStream && !m_paused) {
+            SDL_PauseAudioStreamDevice(m_audioStream);
+            m_paused = true;
+        }
+#endif
+    }
+    
+    /**
+     * Resume paused playback
+     */
+    void resume() {
+#ifdef SDL3_AUDIO_AVAILABLE
+        if (m_audioStream && m_paused) {
+            SDL_ResumeAudioStreamDevice(m_audioStream);
+            m_paused = false;
+        }
+#endif
+    }
+    
+    /**
+     * Check if the clip is audible: started, not paused, and looping or still has data queued
+     * @return true if playing
+     */
+    bool isPlaying() const {
+#ifdef SDL3_AUDIO_AVAILABLE
+        if (!m_audioStream || m_paused) {
+            return false;
+        }
+        const bool pending = m_positional && m_cursor.load() < m_monoSamples.size();
+        return m_looping.load() || pending || SDL_GetAudioStreamQueued(m_audioStream) > 0;
+#else
+        return false;
+#endif
+    }
+    
+    /**
+     * Check if playback is paused
+     * @return true if paused
+     */
+    bool isPaused() const {
+        return m_paused;
+    }
+    
+    /**
+     * Set the clip's volume; a playing stream changes immediately
+     * @param volume Linear gain (1.0 = as recorded), clamped to 0 or more
+     */
+    void setVolume(float volume) {
+        m_volume = std::max(0.0f, volume);
+        applyGain();
+    }
+    
+    /**
+     * Get the clip's volume
+     * @return Linear gain set by setVolume()
+     */
+    float getVolume() const {
+        return m_volume;
+    }
+    
+    /**
+     * Set the gain the mixer applies on top of the clip's volume (master times channel)
+     * @param gain Linear gain, clamped to 0 or more
+     */
+    void setMixGain(float gain) {
+        m_mixGain = std::max(0.0f, gain);
+        applyGain();
+    }
+    
+    /**
+     * Play as a positional clip, attenuated and panned by setSpatial(); takes effect on the next play()
+     * @param positional true for @[spatial] sounds
+     */
+    void setPositional(bool positional) {
+        m_positional = positional;
+    }
+    
+    /**
+     * Set a positional clip's attenuation and pan
+     * @param gain Distance attenuation, 0 (silent) to 1
+     * @param pan -1 (left) to 1 (right); the far side fades out while the near side stays at gain
+     */
+    void setSpatial(float gain, float pan) {
+        gain = std::clamp(gain, 0.0f, 1.0f);
+        pan = std::clamp(pan, -1.0f, 1.0f);
+        m_gainLeft = gain * std::min(1.0f, 1.0f - pan);
+        m_gainRight = gain * std::min(1.0f, 1.0f + pan);
+    }
+    
     /**
      * Check if audio is loaded
      * @return true if loaded
@@ -422,5 +673,52 @@ public:
     }
 };
 
+/**
+ * HeidicAudioMixer - Master and channel volumes for a program's audio resources
+ *
+ * Sound resources play on the sound channel and Music resources on the music channel. A clip plays
+ * at its own volume times the master volume times its channel's volume.
+ */
+struct HeidicAudioMixer {
+    float master = 1.0f;
+    float sound = 1.0f;
+    float music = 1.0f;
+    
+    float soundGain() const { return master * sound; }
+    float musicGain() const { return master * music; }
+};
+
+/**
+ * HeidicSpatialMix - Attenuation and pan of a spatial sound, for AudioResource::setSpatial()
+ */
+struct HeidicSpatialMix {
+    float gain = 1.0f;
+    float pan = 0.0f;
+};
+
+/**
+ * Mix for a sound at offset (dx, dy, dz) from the listener
+ *
+ * Gain is 1 within one unit, falls off with the inverse of the distance beyond that, and fades to 0
+ * at `range` (50 units when range is 0). Pan is how far the sound lies to the listener's right: at
+ * yaw 0 the listener faces -Z with +X on its right, and a growing yaw turns it to the left.
+ */
+inline HeidicSpatialMix heidic_spatial_mix(float dx, float dy, float dz, float yaw, float range) {
+    if (range <= 0.0f) {
+        range = 50.0f;
+    }
+    const float distance = std::sqrt(dx * dx + dy * dy + dz * dz);
+    if (distance >= range) {
+        return HeidicSpatialMix{0.0f, 0.0f};
+    }
+    HeidicSpatialMix mix;
+    mix.gain = (1.0f / std::max(distance, 1.0f)) * (1.0f - distance / range);
+    if (distance > 1e-4f) {
+        // The listener's right is (cos yaw, 0, -sin yaw)
+        mix.pan = (dx * std::cos(yaw) - dz * std::sin(yaw)) / distance;
+    }
+    return mix;
+}
+
 #endif // EDEN_AUDIO_RESOURCE_H
 
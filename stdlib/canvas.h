@@ -0,0 +1,327 @@
+// EDEN ENGINE Standard Library - Canvas Window (--target=wasm)
+// The GLFW subset generated code and HEIDIC programs use, for a browser build. The one window is
+// the page's <canvas id="canvas">: keys come from DOM keyboard events (KeyboardEvent.code mapped to
+// GLFW key codes), mouse buttons and the cursor from the canvas, gamepads from the Gamepad API
+// (standard mapping only) and time from performance.now(). glfwPollEvents() yields to the browser
+// (the program is built with -sASYNCIFY), so a frame loop `while glfwWindowShouldClose(window) == 0`
+// runs as it does natively. Defines EDEN_GLFW_H: headers that include "glfw.h" get this instead.
+
+#ifndef EDEN_CANVAS_H
+#define EDEN_CANVAS_H
+#define EDEN_GLFW_H
+
+#include <emscripten.h>
+#include <emscripten/html5.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define GLFW_FALSE 0
+#define GLFW_TRUE 1
+#define GLFW_RELEASE 0
+#define GLFW_PRESS 1
+#define GLFW_REPEAT 2
+
+#define GLFW_CLIENT_API 0x00022001
+#define GLFW_NO_API 0
+#define GLFW_RESIZABLE 0x00020003
+#define GLFW_CURSOR 0x00033001
+#define GLFW_CURSOR_NORMAL 0x00034001
+#define GLFW_CURSOR_HIDDEN 0x00034002
+#define GLFW_CURSOR_DISABLED 0x00034003
+#define GLFW_CONNECTED 0x00040001
+#define GLFW_DISCONNECTED 0x00040002
+
+#define GLFW_KEY_SPACE 32
+#define GLFW_KEY_A 65
+#define GLFW_KEY_D 68
+#define GLFW_KEY_S 83
+#define GLFW_KEY_W 87
+#define GLFW_KEY_ESCAPE 256
+#define GLFW_KEY_ENTER 257
+#define GLFW_KEY_LAST 348
+
+#define GLFW_MOUSE_BUTTON_LEFT 0
+#define GLFW_MOUSE_BUTTON_RIGHT 1
+#define GLFW_MOUSE_BUTTON_MIDDLE 2
+#define GLFW_MOUSE_BUTTON_LAST 7
+
+#define GLFW_JOYSTICK_1 0
+#define GLFW_JOYSTICK_LAST 15
+#define GLFW_GAMEPAD_BUTTON_LAST 14
+#define GLFW_GAMEPAD_AXIS_LEFT_TRIGGER 4
+#define GLFW_GAMEPAD_AXIS_RIGHT_TRIGGER 5
+#define GLFW_GAMEPAD_AXIS_LAST 5
+
+typedef struct GLFWmonitor GLFWmonitor;
+typedef struct GLFWwindow GLFWwindow;
+
+typedef void (*GLFWkeyfun)(GLFWwindow* window, int key, int scancode, int action, int mods);
+typedef void (*GLFWmousebuttonfun)(GLFWwindow* window, int button, int action, int mods);
+typedef void (*GLFWjoystickfun)(int jid, int event);
+
+typedef struct GLFWgamepadstate {
+    unsigned char buttons[15];
+    float axes[6];
+} GLFWgamepadstate;
+
+struct GLFWwindow {
+    int should_close;
+    int cursor_locked;  // GLFW_CURSOR_DISABLED: the cursor moves by pointer-lock deltas
+    double cursor_x;
+    double cursor_y;
+    unsigned char keys[GLFW_KEY_LAST + 1];
+    unsigned char buttons[GLFW_MOUSE_BUTTON_LAST + 1];
+    GLFWkeyfun key_callback;
+    GLFWmousebuttonfun mouse_button_callback;
+};
+
+namespace heidic_canvas {
+
+inline GLFWwindow& window() {
+    static GLFWwindow canvas_window = {};
+    return canvas_window;
+}
+
+inline GLFWjoystickfun& joystick_callback() {
+    static GLFWjoystickfun callback = nullptr;
+    return callback;
+}
+
+// The GLFW key code of a KeyboardEvent.code, or -1
+inline int key_code(const char* code) {
+    static const struct { const char* code; int key; } named[] = {
+        {"Space", 32}, {"Quote", 39}, {"Comma", 44}, {"Minus", 45}, {"Period", 46}, {"Slash", 47},
+        {"Semicolon", 59}, {"Equal", 61}, {"BracketLeft", 91}, {"Backslash", 92}, {"BracketRight", 93},
+        {"Backquote", 96}, {"Escape", 256}, {"Enter", 257}, {"Tab", 258}, {"Backspace", 259},
+        {"Insert", 260}, {"Delete", 261}, {"ArrowRight", 262}, {"ArrowLeft", 263}, {"ArrowDown", 264},
+        {"ArrowUp", 265}, {"PageUp", 266}, {"PageDown", 267}, {"Home", 268}, {"End", 269},
+        {"ShiftLeft", 340}, {"ControlLeft", 341}, {"AltLeft", 342}, {"MetaLeft", 343},
+        {"ShiftRight", 344}, {"ControlRight", 345}, {"AltRight", 346}, {"MetaRight", 347},
+    };
+    if (strncmp(code, "Key", 3) == 0 && code[3] >= 'A' && code[3] <= 'Z' && code[4] == '\0') {
+        return code[3];
+    }
+    if (strncmp(code, "Digit", 5) == 0 && code[5] >= '0' && code[5] <= '9' && code[6] == '\0') {
+        return code[5];
+    }
+    if (code[0] == 'F' && code[1] != '\0' && strspn(code + 1, "0123456789") == strlen(code + 1)) {
+        int n = atoi(code + 1);
+        return n >= 1 && n <= 12 ? 289 + n : -1;  // GLFW_KEY_F1 is 290
+    }
+    for (const auto& entry : named) {
+        if (strcmp(code, entry.code) == 0) {
+            return entry.key;
+        }
+    }
+    return -1;
+}
+
+inline int modifiers(const EmscriptenKeyboardEvent* event) {
+    return (event->shiftKey ? 0x1 : 0) | (event->ctrlKey ? 0x2 : 0) | (event->altKey ? 0x4 : 0) | (event->metaKey ? 0x8 : 0);
+}
+
+inline EM_BOOL on_key(int type, const EmscriptenKeyboardEvent* event, void*) {
+    int key = key_code(event->code);
+    if (key < 0) {
+        return EM_FALSE;
+    }
+    GLFWwindow& w = window();
+    int action = type == EMSCRIPTEN_EVENT_KEYUP ? GLFW_RELEASE : (event->repeat ? GLFW_REPEAT : GLFW_PRESS);
+    w.keys[key] = action != GLFW_RELEASE;
+    if (w.key_callback) {
+        w.key_callback(&w, key, 0, action, modifiers(event));
+    }
+    return EM_TRUE;  // Keep arrows and space from scrolling the page
+}
+
+// DOM buttons are left, middle, right; GLFW's are left, right, middle
+inline int mouse_button(unsigned short button) {
+    return button == 1 ? GLFW_MOUSE_BUTTON_MIDDLE : button == 2 ? GLFW_MOUSE_BUTTON_RIGHT : button;
+}
+
+inline EM_BOOL on_mouse_button(int type, const EmscriptenMouseEvent* event, void*) {
+    int button = mouse_button(event->button);
+    if (button > GLFW_MOUSE_BUTTON_LAST) {
+        return EM_FALSE;
+    }
+    GLFWwindow& w = window();
+    int action = type == EMSCRIPTEN_EVENT_MOUSEUP ? GLFW_RELEASE : GLFW_PRESS;
+    w.buttons[button] = action == GLFW_PRESS;
+    if (w.mouse_button_callback) {
+        w.mouse_button_callback(&w, button, action, 0);
+    }
+    return EM_FALSE;
+}
+
+inline EM_BOOL on_mouse_move(int, const EmscriptenMouseEvent* event, void*) {
+    GLFWwindow& w = window();
+    if (w.cursor_locked) {
+        w.cursor_x += event->movementX;
+        w.cursor_y += event->movementY;
+    } else {
+        w.cursor_x = event->targetX;
+        w.cursor_y = event->targetY;
+    }
+    return EM_FALSE;
+}
+
+inline EM_BOOL on_gamepad(int type, const EmscriptenGamepadEvent* event, void*) {
+    if (joystick_callback() && event->index <= GLFW_JOYSTICK_LAST) {
+        int change = type == EMSCRIPTEN_EVENT_GAMEPADCONNECTED ? GLFW_CONNECTED : GLFW_DISCONNECTED;
+        joystick_callback()(static_cast<int>(event->index), change);
+    }
+    return EM_FALSE;
+}
+
+}  // namespace heidic_canvas
+
+extern "C" {
+
+inline int glfwInit(void) {
+    static bool registered = false;
+    if (!registered) {
+        using namespace heidic_canvas;
+        emscripten_set_keydown_callback(EMSCRIPTEN_EVENT_TARGET_WINDOW, nullptr, EM_TRUE, on_key);
+        emscripten_set_keyup_callback(EMSCRIPTEN_EVENT_TARGET_WINDOW, nullptr, EM_TRUE, on_key);
+        emscripten_set_mousedown_callback("#canvas", nullptr, EM_TRUE, on_mouse_button);
+        emscripten_set_mouseup_callback(EMSCRIPTEN_EVENT_TARGET_WINDOW, nullptr, EM_TRUE, on_mouse_button);
+        emscripten_set_mousemove_callback("#canvas", nullptr, EM_TRUE, on_mouse_move);
+        emscripten_set_gamepadconnected_callback(nullptr, EM_TRUE, on_gamepad);
+        emscripten_set_gamepaddisconnected_callback(nullptr, EM_TRUE, on_gamepad);
+        registered = true;
+    }
+    return GLFW_TRUE;
+}
+
+inline void glfwTerminate(void) {}
+
+// Hints are for native windows and graphics APIs; the canvas has neither
+inline void glfwWindowHint(int, int) {}
+
+inline void glfwSetWindowTitle(GLFWwindow*, const char* title) {
+    emscripten_set_window_title(title);
+}
+
+inline GLFWwindow* glfwCreateWindow(int width, int height, const char* title, GLFWmonitor*, GLFWwindow*) {
+    GLFWwindow& w = heidic_canvas::window();
+    w.should_close = GLFW_FALSE;
+    emscripten_set_canvas_element_size("#canvas", width, height);
+    glfwSetWindowTitle(&w, title);
+    return &w;
+}
+
+inline void glfwDestroyWindow(GLFWwindow* window) {
+    if (window) {
+        window->should_close = GLFW_TRUE;
+    }
+}
+
+inline int glfwWindowShouldClose(GLFWwindow* window) {
+    return window ? window->should_close : GLFW_TRUE;
+}
+
+inline void glfwSetWindowShouldClose(GLFWwindow* window, int value) {
+    if (window) {
+        window->should_close = value;
+    }
+}
+
+// Hands the frame back to the browser, which runs the queued input callbacks before resuming
+inline void glfwPollEvents(void) {
+    emscripten_sleep(0);
+}
+
+inline int glfwGetKey(GLFWwindow* window, int key) {
+    return window && key >= 0 && key <= GLFW_KEY_LAST && window->keys[key] ? GLFW_PRESS : GLFW_RELEASE;
+}
+
+inline int glfwGetMouseButton(GLFWwindow* window, int button) {
+    return window && button >= 0 && button <= GLFW_MOUSE_BUTTON_LAST && window->buttons[button] ? GLFW_PRESS : GLFW_RELEASE;
+}
+
+inline void glfwGetCursorPos(GLFWwindow* window, double* x, double* y) {
+    if (x) *x = window ? window->cursor_x : 0.0;
+    if (y) *y = window ? window->cursor_y : 0.0;
+}
+
+// Pages can't move the pointer: only the position reported to the program changes
+inline void glfwSetCursorPos(GLFWwindow* window, double x, double y) {
+    if (window) {
+        window->cursor_x = x;
+        window->cursor_y = y;
+    }
+}
+
+// GLFW_CURSOR_DISABLED requests pointer lock (granted on the next click if the page has no focus)
+inline void glfwSetInputMode(GLFWwindow* window, int mode, int value) {
+    if (!window || mode != GLFW_CURSOR) {
+        return;
+    }
+    window->cursor_locked = value == GLFW_CURSOR_DISABLED;
+    if (window->cursor_locked) {
+        emscripten_request_pointerlock("#canvas", EM_TRUE);
+    } else {
+        emscripten_exit_pointerlock();
+    }
+}
+
+inline double glfwGetTime(void) {
+    static const double start = emscripten_get_now();
+    return (emscripten_get_now() - start) / 1000.0;
+}
+
+inline void glfwGetFramebufferSize(GLFWwindow*, int* width, int* height) {
+    emscripten_get_canvas_element_size("#canvas", width, height);
+}
+
+inline GLFWkeyfun glfwSetKeyCallback(GLFWwindow* window, GLFWkeyfun callback) {
+    GLFWkeyfun previous = window->key_callback;
+    window->key_callback = callback;
+    return previous;
+}
+
+inline GLFWmousebuttonfun glfwSetMouseButtonCallback(GLFWwindow* window, GLFWmousebuttonfun callback) {
+    GLFWmousebuttonfun previous = window->mouse_button_callback;
+    window->mouse_button_callback = callback;
+    return previous;
+}
+
+inline GLFWjoystickfun glfwSetJoystickCallback(GLFWjoystickfun callback) {
+    GLFWjoystickfun previous = heidic_canvas::joystick_callback();
+    heidic_canvas::joystick_callback() = callback;
+    return previous;
+}
+
+inline int glfwJoystickIsGamepad(int jid) {
+    EmscriptenGamepadEvent gamepad;
+    if (emscripten_sample_gamepad_data() != EMSCRIPTEN_RESULT_SUCCESS
+        || emscripten_get_gamepad_status(jid, &gamepad) != EMSCRIPTEN_RESULT_SUCCESS) {
+        return GLFW_FALSE;
+    }
+    return gamepad.connected && strcmp(gamepad.mapping, "standard") == 0;
+}
+
+// The standard mapping's buttons and axes in GLFW's gamepad order; triggers go from -1 to 1
+inline int glfwGetGamepadState(int jid, GLFWgamepadstate* state) {
+    EmscriptenGamepadEvent gamepad;
+    if (!glfwJoystickIsGamepad(jid) || emscripten_get_gamepad_status(jid, &gamepad) != EMSCRIPTEN_RESULT_SUCCESS) {
+        return GLFW_FALSE;
+    }
+    static const int buttons[15] = {0, 1, 2, 3, 4, 5, 8, 9, 16, 10, 11, 12, 15, 13, 14};
+    for (int i = 0; i < 15; ++i) {
+        state->buttons[i] = buttons[i] < gamepad.numButtons && gamepad.digitalButton[buttons[i]] ? GLFW_PRESS : GLFW_RELEASE;
+    }
+    for (int i = 0; i < 4; ++i) {
+        state->axes[i] = i < gamepad.numAxes ? static_cast<float>(gamepad.axis[i]) : 0.0f;
+    }
+    for (int i = 0; i < 2; ++i) {
+        int button = 6 + i;
+        state->axes[4 + i] = button < gamepad.numButtons ? static_cast<float>(gamepad.analogButton[button] * 2.0 - 1.0) : -1.0f;
+    }
+    return GLFW_TRUE;
+}
+
+}  // extern "C"
+
+#endif // EDEN_CANVAS_H
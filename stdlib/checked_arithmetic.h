@@ -0,0 +1,142 @@
+// EDEN ENGINE Standard Library - Checked arithmetic
+// With --checked-arithmetic every +, -, *, /, % and unary - of the program goes through these.
+// Integer operations that overflow (or divide by zero) stop the program with the HEIDIC source
+// location instead of wrapping; anything else (floats, vectors, strings) is computed as usual.
+
+#ifndef EDEN_CHECKED_ARITHMETIC_H
+#define EDEN_CHECKED_ARITHMETIC_H
+
+#include <cstdio>
+#include <cstdlib>
+#include <limits>
+#include <type_traits>
+
+[[noreturn]] inline void heidic_arithmetic_trap(const char* what, const char* where) {
+    std::fprintf(stderr, "[HEIDIC] integer %s at %s\n", what, where);
+    std::fflush(stderr);
+    std::abort();
+}
+
+// Integer operands after the usual arithmetic conversions
+template <typename A, typename B>
+constexpr bool heidic_integer_operands = std::is_arithmetic_v<A> && std::is_arithmetic_v<B>
+    && std::is_integral_v<std::common_type_t<A, B>>;
+
+// x op y overflows R; __builtin_*_overflow where the compiler has them
+template <typename R>
+bool heidic_add_overflows(R x, R y, R* result) {
+#if defined(__GNUC__) || defined(__clang__)
+    return __builtin_add_overflow(x, y, result);
+#else
+    bool overflow = y > 0 ? x > std::numeric_limits<R>::max() - y : x < std::numeric_limits<R>::min() - y;
+    *result = overflow ? R(0) : R(x + y);
+    return overflow;
+#endif
+}
+
+template <typename R>
+bool heidic_sub_overflows(R x, R y, R* result) {
+#if defined(__GNUC__) || defined(__clang__)
+    return __builtin_sub_overflow(x, y, result);
+#else
+    bool overflow = y < 0 ? x > std::numeric_limits<R>::max() + y : x < std::numeric_limits<R>::min() + y;
+    *result = overflow ? R(0) : R(x - y);
+    return overflow;
+#endif
+}
+
+template <typename R>
+bool heidic_mul_overflows(R x, R y, R* result) {
+#if defined(__GNUC__) || defined(__clang__)
+    return __builtin_mul_overflow(x, y, result);
+#else
+    constexpr R max = std::numeric_limits<R>::max(), min = std::numeric_limits<R>::min();
+    bool overflow = false;
+    if (x != 0 && y != 0) {
+        if (x > 0) {
+            overflow = y > 0 ? x > max / y : y < min / x;
+        } else {
+            overflow = y > 0 ? x < min / y : x < max / y;
+        }
+    }
+    *result = overflow ? R(0) : R(x * y);
+    return overflow;
+#endif
+}
+
+template <typename A, typename B>
+auto heidic_checked_add(A a, B b, const char* where) {
+    if constexpr (heidic_integer_operands<A, B>) {
+        using R = decltype(a + b);
+        R result;
+        if (heidic_add_overflows<R>(a, b, &result)) heidic_arithmetic_trap("overflow in +", where);
+        return result;
+    } else {
+        return a + b;
+    }
+}
+
+template <typename A, typename B>
+auto heidic_checked_sub(A a, B b, const char* where) {
+    if constexpr (heidic_integer_operands<A, B>) {
+        using R = decltype(a - b);
+        R result;
+        if (heidic_sub_overflows<R>(a, b, &result)) heidic_arithmetic_trap("overflow in -", where);
+        return result;
+    } else {
+        return a - b;
+    }
+}
+
+template <typename A, typename B>
+auto heidic_checked_mul(A a, B b, const char* where) {
+    if constexpr (heidic_integer_operands<A, B>) {
+        using R = decltype(a * b);
+        R result;
+        if (heidic_mul_overflows<R>(a, b, &result)) heidic_arithmetic_trap("overflow in *", where);
+        return result;
+    } else {
+        return a * b;
+    }
+}
+
+template <typename A, typename B>
+auto heidic_checked_div(A a, B b, const char* where) {
+    if constexpr (heidic_integer_operands<A, B>) {
+        using R = decltype(a / b);
+        if (b == 0) heidic_arithmetic_trap("division by zero", where);
+        if (std::is_signed_v<R> && static_cast<R>(a) == std::numeric_limits<R>::min() && static_cast<R>(b) == static_cast<R>(-1)) {
+            heidic_arithmetic_trap("overflow in /", where);
+        }
+        return static_cast<R>(a / b);
+    } else {
+        return a / b;
+    }
+}
+
+template <typename A, typename B>
+auto heidic_checked_mod(A a, B b, const char* where) {
+    if constexpr (heidic_integer_operands<A, B>) {
+        using R = decltype(a % b);
+        if (b == 0) heidic_arithmetic_trap("division by zero in %", where);
+        if (std::is_signed_v<R> && static_cast<R>(a) == std::numeric_limits<R>::min() && static_cast<R>(b) == static_cast<R>(-1)) {
+            return static_cast<R>(0);  // The remainder is 0; computing it would trap on x86
+        }
+        return static_cast<R>(a % b);
+    } else {
+        return a % b;
+    }
+}
+
+template <typename A>
+auto heidic_checked_neg(A a, const char* where) {
+    if constexpr (std::is_integral_v<A> && std::is_signed_v<A>) {
+        using R = decltype(-a);
+        if (static_cast<R>(a) == std::numeric_limits<R>::min()) heidic_arithmetic_trap("overflow in unary -", where);
+        return -a;
+    } else {
+        return -a;
+    }
+}
+
+#endif // EDEN_CHECKED_ARITHMETIC_H
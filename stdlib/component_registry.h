@@ -6,7 +6,7 @@
 
 #include <cstdint>
 #include <cstddef>
-#include <unordered_map>
+#include "type_map.h"
 #include <string>
 #include <type_traits>
 #include <typeinfo>
@@ -42,6 +42,7 @@ struct ComponentMetadata {
     static constexpr size_t size() { return sizeof(T); }
     static constexpr size_t alignment() { return alignof(T); }
     static constexpr bool is_soa() { return false; }
+    static constexpr const char* doc() { return ""; }  // `///` comment above the component
 };
 
 // Component Fields Reflection Template
@@ -53,6 +54,11 @@ struct ComponentFields {
         const char* type_name;
         size_t offset;
         size_t size;
+        const char* doc;  // `///` comment above the field, or ""
+        const char* unit;  // @[unit("...")], or ""
+        bool has_range;  // @[range(min, max)]: editors clamp values to [range_min, range_max]
+        double range_min;
+        double range_max;
     };
     static constexpr FieldInfo fields[] = {};
 };
@@ -133,10 +139,10 @@ private:
         return instance;
     }
     
-    std::unordered_map<ComponentId, const char*> component_names;
-    std::unordered_map<ComponentId, size_t> component_sizes;
-    std::unordered_map<ComponentId, size_t> component_alignments;
-    std::unordered_map<ComponentId, bool> component_soa_flags;
+    HeidicTypeMap<ComponentId, const char*> component_names;
+    HeidicTypeMap<ComponentId, size_t> component_sizes;
+    HeidicTypeMap<ComponentId, size_t> component_alignments;
+    HeidicTypeMap<ComponentId, bool> component_soa_flags;
 };
 
 // Helper macros for easier registration
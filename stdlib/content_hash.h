@@ -0,0 +1,67 @@
+// EDEN ENGINE Standard Library - Content Hashes
+// Hot-reload watchers decide whether a file changed by hashing its contents instead of comparing
+// modification times, so copying an older file into place, touching a file or saving twice within
+// the same second all behave: an artifact reloads exactly when its bytes differ from the version
+// that was last loaded.
+
+#ifndef EDEN_CONTENT_HASH_H
+#define EDEN_CONTENT_HASH_H
+
+#include <chrono>
+#include <cstdint>
+#include <cstdio>
+#include <string>
+
+// Hashing reads the whole file, so each watch polls at most this often
+#ifndef HEIDIC_WATCH_INTERVAL_MS
+#define HEIDIC_WATCH_INTERVAL_MS 250
+#endif
+
+// FNV-1a (64-bit) of a file's contents; 0 if it can't be read (a readable file never hashes to 0)
+inline uint64_t heidic_hash_file(const char* path) {
+    FILE* file = fopen(path, "rb");
+    if (!file) {
+        return 0;
+    }
+    uint64_t hash = 14695981039346656037ull;
+    unsigned char buffer[16384];
+    size_t count;
+    while ((count = fread(buffer, 1, sizeof(buffer), file)) > 0) {
+        for (size_t i = 0; i < count; i++) {
+            hash ^= buffer[i];
+            hash *= 1099511628211ull;
+        }
+    }
+    fclose(file);
+    return hash == 0 ? 1 : hash;
+}
+
+// One watched artifact and the hash of the version last loaded from it
+struct HeidicFileWatch {
+    std::string path;
+    uint64_t hash = 0;  // 0 until the file has been seen
+    std::chrono::steady_clock::time_point next_poll{};
+};
+
+// Record the file's current contents as loaded
+inline void heidic_watch_reset(HeidicFileWatch& watch) {
+    watch.hash = heidic_hash_file(watch.path.c_str());
+}
+
+// True when the contents differ from the version last seen, which becomes the new baseline.
+// A missing file is not a change: editors and build tools often delete before writing.
+inline bool heidic_watch_changed(HeidicFileWatch& watch) {
+    auto now = std::chrono::steady_clock::now();
+    if (now < watch.next_poll) {
+        return false;
+    }
+    watch.next_poll = now + std::chrono::milliseconds(HEIDIC_WATCH_INTERVAL_MS);
+    uint64_t hash = heidic_hash_file(watch.path.c_str());
+    if (hash == 0 || hash == watch.hash) {
+        return false;
+    }
+    watch.hash = hash;
+    return true;
+}
+
+#endif // EDEN_CONTENT_HASH_H
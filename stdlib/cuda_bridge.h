@@ -0,0 +1,65 @@
+// EDEN ENGINE Standard Library - CUDA launches from host code
+// The generated .cpp never sees CUDA: the kernels of @[launch(kernel = name)] functions and the
+// code that copies their queries to the device are in <file>.cu, built by nvcc, and reached
+// through extern "C" functions taking each query array as a plain host pointer. This is what the
+// host side needs to hand those arrays over.
+//
+// Once a program calls sync_to_gpu<T>() or sync_to_cpu<T>(), its @[cuda] components live on the
+// GPU: sync_to_gpu<T>() copies every entity's T into a device mirror that kernels read and write
+// in place, and sync_to_cpu<T>() copies the mirror back into the entities. HeidicCudaResidency
+// remembers which entity is on which row of a mirror.
+
+#ifndef EDEN_CUDA_BRIDGE_H
+#define EDEN_CUDA_BRIDGE_H
+
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <vector>
+
+// The host memory behind a query array: a std::vector's elements, or a plain pointer
+template <typename T>
+T* heidic_cuda_host(std::vector<T>& items) { return items.data(); }
+
+template <typename T>
+T* heidic_cuda_host(T* items) { return items; }
+
+// The entities a GPU-resident component's device mirror holds, in row order
+struct HeidicCudaResidency {
+    static constexpr uint32_t none = UINT32_MAX;
+
+    std::vector<uint32_t> entities;  // Entity of each row
+    std::vector<uint32_t> rows;      // Row of each entity id, `none` when it isn't mirrored
+    bool reported = false;
+
+    // Start over for entity ids up to `last` (EntityStorage::entity_limit())
+    void begin(uint32_t last) {
+        entities.clear();
+        rows.assign(static_cast<size_t>(last) + 1, none);
+    }
+
+    void add(uint32_t entity) {
+        rows[entity] = static_cast<uint32_t>(entities.size());
+        entities.push_back(entity);
+    }
+
+    size_t size() const { return entities.size(); }
+
+    // Whether the mirror has a row for every entity of a query (reported once when it doesn't:
+    // the entity was spawned or given the component after the last `sync_call`)
+    bool covers(const uint32_t* query_entities, size_t count, const char* sync_call) {
+        for (size_t i = 0; i < count; ++i) {
+            uint32_t entity = query_entities[i];
+            if (entity < rows.size() && rows[entity] != none) continue;
+            if (!reported) {
+                std::fprintf(stderr, "[HEIDIC] Entity %u isn't on the GPU: call %s after changing which entities have it and before launching\n",
+                             entity, sync_call);
+                reported = true;
+            }
+            return false;
+        }
+        return true;
+    }
+};
+
+#endif // EDEN_CUDA_BRIDGE_H
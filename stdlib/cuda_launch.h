@@ -0,0 +1,130 @@
+// EDEN ENGINE Standard Library - CUDA kernel launches
+// What the generated <file>.cu is built from (the .cpp reaches it through stdlib/cuda_bridge.h):
+// HeidicCudaBuffer keeps one query array's device copy between launches (it only reallocates when
+// a query outgrows it), HeidicCudaStream is the stream an async kernel's work is queued on, and
+// every CUDA call is checked, reporting a failure on stderr with what was being done.
+//
+// Kernels over GPU-resident components reach each query entity's values through the component's
+// mirror: HeidicCudaResident maps the query's i-th entity to its row there.
+//
+// Copies from pageable memory only overlap with the host when the arrays are pinned
+// (cudaHostRegister); without that an async launch still queues, but its copies may block.
+
+#ifndef EDEN_CUDA_LAUNCH_H
+#define EDEN_CUDA_LAUNCH_H
+
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+
+#include <cuda_runtime.h>
+
+#include "cuda_bridge.h"
+
+// Threads per block of generated kernels
+#define HEIDIC_CUDA_BLOCK_SIZE 256
+
+// Kernels loop over their entities with a grid-wide stride, so the grid can stay this size for
+// any entity count
+#define HEIDIC_CUDA_MAX_BLOCKS 65535
+
+// False (after reporting it) when a CUDA call failed
+inline bool heidic_cuda_check(cudaError_t error, const char* what) {
+    if (error == cudaSuccess) return true;
+    std::fprintf(stderr, "[HEIDIC] CUDA error %s: %s\n", what, cudaGetErrorString(error));
+    return false;
+}
+
+// Blocks to launch for `count` entities
+inline unsigned int heidic_cuda_blocks(size_t count) {
+    size_t blocks = (count + HEIDIC_CUDA_BLOCK_SIZE - 1) / HEIDIC_CUDA_BLOCK_SIZE;
+    return static_cast<unsigned int>(blocks < HEIDIC_CUDA_MAX_BLOCKS ? blocks : HEIDIC_CUDA_MAX_BLOCKS);
+}
+
+// A device array that grows to the largest count it was asked to hold and keeps that memory
+template <typename T>
+class HeidicCudaBuffer {
+public:
+    HeidicCudaBuffer() = default;
+    HeidicCudaBuffer(const HeidicCudaBuffer&) = delete;
+    HeidicCudaBuffer& operator=(const HeidicCudaBuffer&) = delete;
+
+    // Freed unchecked: at exit the CUDA context may already be gone
+    ~HeidicCudaBuffer() {
+        if (m_data) cudaFree(m_data);
+    }
+
+    T* data() const { return m_data; }
+
+    // Queue a copy of `count` host elements to the device
+    bool upload(const T* host, size_t count, cudaStream_t stream) {
+        if (count == 0) return true;
+        if (!reserve(count)) return false;
+        return heidic_cuda_check(cudaMemcpyAsync(m_data, host, sizeof(T) * count, cudaMemcpyHostToDevice, stream),
+                                 "copying a query array to the device");
+    }
+
+    // Queue a copy of the first `count` device elements back to the host
+    bool download(T* host, size_t count, cudaStream_t stream) const {
+        if (count == 0) return true;
+        return heidic_cuda_check(cudaMemcpyAsync(host, m_data, sizeof(T) * count, cudaMemcpyDeviceToHost, stream),
+                                 "copying a query array back from the device");
+    }
+
+private:
+    bool reserve(size_t count) {
+        if (count <= m_capacity) return true;
+        size_t capacity = count > m_capacity * 2 ? count : m_capacity * 2;
+        // cudaFree waits for work still using the old memory
+        if (m_data && !heidic_cuda_check(cudaFree(m_data), "freeing a device array")) return false;
+        m_data = nullptr;
+        m_capacity = 0;
+        if (!heidic_cuda_check(cudaMalloc(reinterpret_cast<void**>(&m_data), sizeof(T) * capacity), "allocating a device array")) {
+            m_data = nullptr;
+            return false;
+        }
+        m_capacity = capacity;
+        return true;
+    }
+
+    T* m_data = nullptr;
+    size_t m_capacity = 0;
+};
+
+// One array of a GPU-resident component as a kernel's query sees it: element i is the value of
+// the query's i-th entity in the component's device mirror
+template <typename T>
+struct HeidicCudaResident {
+    T* data;                   // The mirror
+    const uint32_t* rows;      // Mirror row of each entity id
+    const uint32_t* entities;  // The query's entities
+
+    __device__ T& operator[](size_t i) const { return data[rows[entities[i]]]; }
+};
+
+// A non-blocking stream, created on first use
+class HeidicCudaStream {
+public:
+    HeidicCudaStream() = default;
+    HeidicCudaStream(const HeidicCudaStream&) = delete;
+    HeidicCudaStream& operator=(const HeidicCudaStream&) = delete;
+
+    ~HeidicCudaStream() {
+        if (m_created) cudaStreamDestroy(m_stream);
+    }
+
+    // The default stream if the stream can't be created, so work still runs in order
+    cudaStream_t get() {
+        if (!m_created) {
+            m_created = heidic_cuda_check(cudaStreamCreateWithFlags(&m_stream, cudaStreamNonBlocking), "creating a stream");
+            if (!m_created) return 0;
+        }
+        return m_stream;
+    }
+
+private:
+    cudaStream_t m_stream = 0;
+    bool m_created = false;
+};
+
+#endif // EDEN_CUDA_LAUNCH_H
@@ -1,34 +1,105 @@
 #pragma once
 
+#include <algorithm>
 #include <vector>
 #include <cstdint>
-#include <unordered_map>
+#include <cstdio>
 #include <memory>
+#include <optional>
+#include <type_traits>
 #include <typeinfo>
 #include <typeindex>
 
+#include "type_map.h"
+
 // Basic entity identifier
 using EntityId = uint32_t;
 static constexpr EntityId INVALID_ENTITY = 0;
 
+// component_soa types are generated with a nested Row (one entity's values) next to their
+// field arrays, and heidic_push/heidic_set/heidic_move/... applying an operation to every array
+template <typename T, typename = void>
+struct is_soa_component : std::false_type {};
+
+template <typename T>
+struct is_soa_component<T, std::void_t<typename T::Row>> : std::true_type {};
+
+// One component type's share of the storage, for memory_report() (stdlib/memory_report.h)
+struct HeidicComponentMemory {
+    size_t count = 0;       // Components stored now
+    size_t capacity = 0;    // Components the allocated memory holds
+    size_t high_water = 0;  // Most components stored at once
+    size_t bytes = 0;       // Allocated bytes, bookkeeping (entity index, ids, ticks) included
+};
+
+// @[pool(capacity = N)] components are generated with heidic_pool_capacity = N: their storage
+// is allocated once for N components and never grows, so adding one can't reallocate mid-frame
+template <typename T, typename = void>
+struct heidic_pool_capacity : std::integral_constant<size_t, 0> {};
+
+template <typename T>
+struct heidic_pool_capacity<T, std::void_t<decltype(T::heidic_pool_capacity)>>
+    : std::integral_constant<size_t, T::heidic_pool_capacity> {};
+
+// Whether a T can be added to a storage holding `count` of them. A full pool drops the add and
+// says so once, rather than growing behind the program's back.
+template <typename T>
+bool heidic_pool_admits(size_t count) {
+    if constexpr (heidic_pool_capacity<T>::value == 0) {
+        (void)count;
+        return true;
+    } else {
+        if (count < heidic_pool_capacity<T>::value) return true;
+        static bool reported = false;
+        if (!reported) {
+            reported = true;
+            std::fprintf(stderr, "[HEIDIC] Component pool '%s' is full (capacity %zu); further adds are dropped\n",
+                         T::heidic_pool_name, heidic_pool_capacity<T>::value);
+        }
+        return false;
+    }
+}
+
+// Rows worth reserving when `rows` are asked for: a pool is allocated whole, up front
+template <typename T>
+size_t heidic_pool_rows(size_t rows) {
+    return heidic_pool_capacity<T>::value > 0 ? heidic_pool_capacity<T>::value : rows;
+}
+
+#ifdef HEIDIC_ARCHETYPE_STORAGE
+// --ecs-storage=archetype: entities grouped by component set into contiguous columns
+#include "archetype_storage.h"
+#else
+
 // -----------------------------------------------------------------------------
 // Sparse-set storage for a single component type
 // -----------------------------------------------------------------------------
-template <typename T>
+template <typename T, bool Soa = is_soa_component<T>::value>
 class ComponentStorage {
 public:
-    void add(EntityId entity, const T& component) {
+    ComponentStorage() {
+        if (heidic_pool_capacity<T>::value > 0) reserve(0, 0);
+    }
+
+    // `tick` records when the component was added or last changed (for changed<T> query filters)
+    void add(EntityId entity, const T& component, uint32_t tick = 0) {
         if (entity >= sparse.size()) {
             sparse.resize(entity + 1, invalid_marker);
         }
         if (sparse[entity] != invalid_marker) {
             // Already has this component; overwrite
             dense[sparse[entity]] = component;
+            ticks[sparse[entity]] = tick;
+            version = std::max(version, tick);
             return;
         }
+        if (!heidic_pool_admits<T>(dense.size())) return;
         sparse[entity] = static_cast<uint32_t>(dense.size());
         dense.emplace_back(component);
         entities.emplace_back(entity);
+        ticks.emplace_back(tick);
+        version = std::max(version, tick);
+        high_water = std::max(high_water, dense.size());
     }
 
     void remove(EntityId entity) {
@@ -41,10 +112,12 @@ public:
         // Swap-remove to keep dense packed
         dense[idx] = std::move(dense[last]);
         entities[idx] = entities[last];
+        ticks[idx] = ticks[last];
         sparse[entities[idx]] = idx;
 
         dense.pop_back();
         entities.pop_back();
+        ticks.pop_back();
         sparse[entity] = invalid_marker;
     }
 
@@ -59,6 +132,21 @@ public:
         return entity < sparse.size() && sparse[entity] != invalid_marker;
     }
 
+    void mark_changed(EntityId entity, uint32_t tick) {
+        if (has(entity)) {
+            ticks[sparse[entity]] = tick;
+            version = std::max(version, tick);
+        }
+    }
+
+    // True when the entity has the component and it was added or changed after `tick`
+    bool changed_since(EntityId entity, uint32_t tick) const {
+        return has(entity) && ticks[sparse[entity]] > tick;
+    }
+
+    // Tick of the latest add or change of any entity's component (0 before the first)
+    uint32_t last_changed() const { return version; }
+
     template <typename Func>
     void for_each(Func&& func) {
         for (size_t i = 0; i < dense.size(); ++i) {
@@ -68,11 +156,157 @@ public:
 
     size_t size() const { return dense.size(); }
 
+    HeidicComponentMemory memory_usage() const {
+        size_t bytes = dense.capacity() * sizeof(T) + entities.capacity() * sizeof(EntityId)
+            + ticks.capacity() * sizeof(uint32_t) + sparse.capacity() * sizeof(uint32_t);
+        return HeidicComponentMemory{dense.size(), dense.capacity(), high_water, bytes};
+    }
+
+    // Pre-size for `additional` more components on entities up to `max_entity`, so bulk
+    // spawning doesn't grow the vectors one reallocation at a time
+    void reserve(size_t additional, EntityId max_entity) {
+        size_t rows = heidic_pool_rows<T>(dense.size() + additional);
+        dense.reserve(rows);
+        entities.reserve(rows);
+        ticks.reserve(rows);
+        if (max_entity >= sparse.size()) {
+            sparse.resize(static_cast<size_t>(max_entity) + 1, invalid_marker);
+        }
+    }
+
 private:
     static constexpr uint32_t invalid_marker = UINT32_MAX;
     std::vector<uint32_t> sparse;      // entity -> dense index
     std::vector<T> dense;              // packed components
     std::vector<EntityId> entities;    // packed entity ids
+    std::vector<uint32_t> ticks;       // packed change ticks
+    uint32_t version {0};              // latest tick in `ticks`, including removed entities'
+    size_t high_water {0};             // most components stored at once
+};
+
+// -----------------------------------------------------------------------------
+// Sparse-set storage for a component_soa: a single T holds every entity's fields, element i of
+// each field array belonging to entities()[i], so queries can iterate the arrays in place
+// (q.velocities.x[i]) instead of gathering components
+// -----------------------------------------------------------------------------
+template <typename T>
+class ComponentStorage<T, true> {
+public:
+    using Row = typename T::Row;
+
+    ComponentStorage() {
+        if (heidic_pool_capacity<T>::value > 0) reserve(0, 0);
+    }
+
+    void add(EntityId entity, const Row& row, uint32_t tick = 0) {
+        if (entity >= sparse.size()) {
+            sparse.resize(entity + 1, invalid_marker);
+        }
+        if (sparse[entity] != invalid_marker) {
+            // Already has this component; overwrite
+            columns.heidic_set(sparse[entity], row);
+            ticks[sparse[entity]] = tick;
+            version = std::max(version, tick);
+            return;
+        }
+        if (!heidic_pool_admits<T>(dense_entities.size())) return;
+        sparse[entity] = static_cast<uint32_t>(dense_entities.size());
+        columns.heidic_push(row);
+        dense_entities.emplace_back(entity);
+        ticks.emplace_back(tick);
+        version = std::max(version, tick);
+        high_water = std::max(high_water, dense_entities.size());
+    }
+
+    void remove(EntityId entity) {
+        if (entity >= sparse.size() || sparse[entity] == invalid_marker) {
+            return;
+        }
+        uint32_t idx = sparse[entity];
+        uint32_t last = static_cast<uint32_t>(dense_entities.size() - 1);
+
+        // Swap-remove in every field array to keep them packed and aligned
+        columns.heidic_move(last, idx);
+        dense_entities[idx] = dense_entities[last];
+        ticks[idx] = ticks[last];
+        sparse[dense_entities[idx]] = idx;
+
+        columns.heidic_pop();
+        dense_entities.pop_back();
+        ticks.pop_back();
+        sparse[entity] = invalid_marker;
+    }
+
+    // The entity's values, copied out of the field arrays
+    std::optional<Row> get_row(EntityId entity) const {
+        if (!has(entity)) {
+            return std::nullopt;
+        }
+        return columns.heidic_row(sparse[entity]);
+    }
+
+    // Overwrite the entity's values in place, keeping its change tick
+    void set_row(EntityId entity, const Row& row) {
+        if (has(entity)) {
+            columns.heidic_set(sparse[entity], row);
+        }
+    }
+
+    bool has(EntityId entity) const {
+        return entity < sparse.size() && sparse[entity] != invalid_marker;
+    }
+
+    void mark_changed(EntityId entity, uint32_t tick) {
+        if (has(entity)) {
+            ticks[sparse[entity]] = tick;
+            version = std::max(version, tick);
+        }
+    }
+
+    bool changed_since(EntityId entity, uint32_t tick) const {
+        return has(entity) && ticks[sparse[entity]] > tick;
+    }
+
+    uint32_t last_changed() const { return version; }
+
+    template <typename Func>
+    void for_each(Func&& func) {
+        for (size_t i = 0; i < dense_entities.size(); ++i) {
+            func(dense_entities[i], columns.heidic_row(i));
+        }
+    }
+
+    size_t size() const { return dense_entities.size(); }
+
+    // The field arrays grow together with dense_entities, so its capacity is theirs
+    HeidicComponentMemory memory_usage() const {
+        size_t bytes = columns.heidic_bytes() + dense_entities.capacity() * sizeof(EntityId)
+            + ticks.capacity() * sizeof(uint32_t) + sparse.capacity() * sizeof(uint32_t);
+        return HeidicComponentMemory{dense_entities.size(), dense_entities.capacity(), high_water, bytes};
+    }
+
+    void reserve(size_t additional, EntityId max_entity) {
+        size_t rows = heidic_pool_rows<T>(dense_entities.size() + additional);
+        columns.heidic_reserve(rows);
+        dense_entities.reserve(rows);
+        ticks.reserve(rows);
+        if (max_entity >= sparse.size()) {
+            sparse.resize(static_cast<size_t>(max_entity) + 1, invalid_marker);
+        }
+    }
+
+    // The field arrays and the entity each element belongs to
+    T& arrays() { return columns; }
+    const std::vector<EntityId>& entities() const { return dense_entities; }
+
+private:
+    static constexpr uint32_t invalid_marker = UINT32_MAX;
+    std::vector<uint32_t> sparse;          // entity -> array index
+    T columns;                             // one packed array per field
+    std::vector<EntityId> dense_entities;  // packed entity ids
+    std::vector<uint32_t> ticks;           // packed change ticks
+    uint32_t version {0};                  // latest tick in `ticks`, including removed entities'
+    size_t high_water {0};                 // most rows stored at once
 };
 
 // -----------------------------------------------------------------------------
@@ -82,12 +316,14 @@ private:
 struct IComponentStorage {
     virtual ~IComponentStorage() = default;
     virtual void remove(EntityId entity) = 0;
+    virtual void reserve(size_t additional, EntityId max_entity) = 0;
 };
 
 template <typename T>
 struct StorageWrapper final : IComponentStorage {
     ComponentStorage<T> storage;
     void remove(EntityId entity) override { storage.remove(entity); }
+    void reserve(size_t additional, EntityId max_entity) override { storage.reserve(additional, max_entity); }
 };
 
 // -----------------------------------------------------------------------------
@@ -104,6 +340,35 @@ public:
         return ++next_id;
     }
 
+    // Allocate `count` entities with consecutive ids and return the first one. Freed ids are
+    // not reused here, so a batch can be initialized with a plain index loop.
+    EntityId create_entities(uint32_t count) {
+        EntityId first = next_id + 1;
+        next_id += count;
+        return first;
+    }
+
+    // Make room for `count` more entities in every component storage, including ones created
+    // later, so spawning them doesn't reallocate along the way
+    void reserve_entities(uint32_t count) {
+        reserved = count;
+        for (auto& kv : storages) {
+            kv.second->reserve(count, next_id + count);
+        }
+    }
+
+    // The id allocator's state, written by save_world(): ids handed out so far and freed ones
+    EntityId entity_limit() const { return next_id; }
+    const std::vector<EntityId>& free_entities() const { return free_list; }
+
+    // Continue a saved allocator on an empty storage (load_world). Starting from the tick of the
+    // world being replaced keeps change detection moving forward across the load.
+    void restore_entities(EntityId limit, std::vector<EntityId> free, uint32_t start_tick) {
+        next_id = limit;
+        free_list = std::move(free);
+        tick = start_tick;
+    }
+
     void destroy_entity(EntityId entity) {
         // Remove entity from all component storages
         for (auto& kv : storages) {
@@ -115,7 +380,14 @@ public:
     template <typename T>
     void add_component(EntityId entity, const T& component) {
         auto& wrap = get_or_create<T>();
-        wrap.storage.add(entity, component);
+        wrap.storage.add(entity, component, ++tick);
+    }
+
+    // A component_soa is added from one entity's values (its Row)
+    template <typename T>
+    void add_component(EntityId entity, const typename T::Row& row) {
+        auto& wrap = get_or_create<T>();
+        wrap.storage.add(entity, row, ++tick);
     }
 
     template <typename T>
@@ -125,12 +397,55 @@ public:
         return wrap->storage.get(entity);
     }
 
+    // component_soa values are spread over the field arrays, so they are read as a copy
+    template <typename T>
+    std::optional<typename T::Row> get_row(EntityId entity) const {
+        auto* wrap = find<T>();
+        if (!wrap) return std::nullopt;
+        return wrap->storage.get_row(entity);
+    }
+
+    // Write a component_soa's values back without taking a tick (queries copy components out
+    // and back; writes that count as changes are recorded with mark_changed())
+    template <typename T>
+    void set_row(EntityId entity, const typename T::Row& row) {
+        auto* wrap = find<T>();
+        if (wrap) wrap->storage.set_row(entity, row);
+    }
+
     template <typename T>
     bool has_component(EntityId entity) const {
         auto* wrap = find<T>();
         return wrap && wrap->storage.has(entity);
     }
 
+    // Change detection: every add and mark_changed() takes a new tick, so a reader that
+    // remembers change_tick() sees exactly the components touched after that point
+    uint32_t change_tick() const { return tick; }
+
+    // A new tick for components added straight into a storage (spawn_batch)
+    uint32_t advance_tick() { return ++tick; }
+
+    template <typename T>
+    void mark_changed(EntityId entity) {
+        auto* wrap = find<T>();
+        if (wrap) wrap->storage.mark_changed(entity, ++tick);
+    }
+
+    template <typename T>
+    bool changed_since(EntityId entity, uint32_t since) const {
+        auto* wrap = find<T>();
+        return wrap && wrap->storage.changed_since(entity, since);
+    }
+
+    // Per-component version: the tick of the latest add or change of any T, so a reader can
+    // tell nothing of T changed since `since` without visiting its entities
+    template <typename T>
+    uint32_t component_version() const {
+        auto* wrap = find<T>();
+        return wrap ? wrap->storage.last_changed() : 0;
+    }
+
     template <typename T>
     void remove_component(EntityId entity) {
         auto* wrap = find<T>();
@@ -144,10 +459,28 @@ public:
         wrap->storage.for_each(std::forward<Func>(func));
     }
 
+    // Direct access to one component's storage, for loops that add many components at once
+    template <typename T>
+    ComponentStorage<T>& storage() {
+        return get_or_create<T>().storage;
+    }
+
+    // Memory of T's storage (all zero before the first T is added)
+    template <typename T>
+    HeidicComponentMemory memory_usage() const {
+        auto* wrap = find<T>();
+        return wrap ? wrap->storage.memory_usage() : HeidicComponentMemory{};
+    }
+
+    // Bytes of the entity allocator itself (freed ids)
+    size_t bookkeeping_bytes() const { return free_list.capacity() * sizeof(EntityId); }
+
 private:
     EntityId next_id {0};
+    uint32_t reserved {0};  // Last reserve_entities() count, applied to storages created afterwards
+    uint32_t tick {0};      // Change-detection clock, advanced by every add and mark_changed()
     std::vector<EntityId> free_list;
-    std::unordered_map<size_t, std::unique_ptr<IComponentStorage>> storages;
+    HeidicTypeMap<size_t, std::unique_ptr<IComponentStorage>> storages;
 
     template <typename T>
     StorageWrapper<T>& get_or_create() {
@@ -156,7 +489,10 @@ private:
         if (it == storages.end()) {
             auto wrapper = std::make_unique<StorageWrapper<T>>();
             auto* ptr = wrapper.get();
-            storages.emplace(key, std::move(wrapper));
+            if (reserved > 0) {
+                ptr->storage.reserve(reserved, next_id + reserved);
+            }
+            storages[key] = std::move(wrapper);
             return *ptr;
         }
         return *static_cast<StorageWrapper<T>*>(it->second.get());
@@ -171,4 +507,4 @@ private:
     }
 };
 
-
+#endif // HEIDIC_ARCHETYPE_STORAGE
@@ -0,0 +1,50 @@
+// EDEN ENGINE Standard Library - Events
+// One queue per `event` type. `emit` appends to the current frame's events; each `on` handler
+// keeps a cursor and, when run_systems() runs its system, receives every event past it. Events
+// live for two frames (run_systems() calls next_frame() first), so a handler whose system runs
+// before the emitting one still sees the event on the following frame, and sees it only once.
+
+#ifndef EDEN_EVENTS_H
+#define EDEN_EVENTS_H
+
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
+template <typename T>
+class EventQueue {
+public:
+    void emit(const T& event) { current_.push_back(event); }
+
+    // Start a new frame: events from two frames ago are dropped
+    void next_frame() {
+        first_id_ += previous_.size();
+        previous_.swap(current_);
+        current_.clear();
+    }
+
+    // Calls handler(event) for every event after `cursor`, oldest first, and moves the cursor
+    // past them. Events the handler emits are delivered in the same call.
+    template <typename F>
+    void drain(uint64_t& cursor, F&& handler) {
+        if (cursor < first_id_) {
+            cursor = first_id_;  // Missed (the handler didn't run for two frames)
+        }
+        while (cursor < first_id_ + previous_.size() + current_.size()) {
+            size_t index = static_cast<size_t>(cursor - first_id_);
+            // Copied: the handler may emit and reallocate the buffer
+            T event = index < previous_.size() ? previous_[index] : current_[index - previous_.size()];
+            cursor++;
+            handler(event);
+        }
+    }
+
+    size_t size() const { return previous_.size() + current_.size(); }
+
+private:
+    std::vector<T> previous_;
+    std::vector<T> current_;
+    uint64_t first_id_ = 0;  // Id of previous_[0]; ids count every event ever emitted
+};
+
+#endif // EDEN_EVENTS_H
@@ -0,0 +1,146 @@
+// EDEN ENGINE Standard Library - Frame arena
+// The bump allocator behind `frame.alloc_array<T>(n)`: an allocation moves a pointer forward in
+// the current block, and a HeidicFrameScope (which generated code opens at the top of the
+// program's outermost loops) rewinds the arena to where it was when the iteration started, so
+// everything one frame allocated is released at once. Allocations made before the loop keep
+// their memory. When the arena runs out it adds a block twice the size of the last one; the next
+// time it is empty those blocks are merged into one, so a steady frame settles on a single block.
+//
+// Only trivially destructible element types can be allocated: the arena never runs destructors.
+
+#ifndef EDEN_FRAME_ARENA_H
+#define EDEN_FRAME_ARENA_H
+
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <new>
+#include <type_traits>
+#include <vector>
+
+#include "slice.h"
+
+// Size of the first block; later ones double
+#define HEIDIC_FRAME_ARENA_BLOCK_SIZE (64 * 1024)
+
+class HeidicFrameArena {
+public:
+    // A position in the arena to rewind to
+    struct Mark {
+        size_t block;
+        size_t offset;
+    };
+
+    HeidicFrameArena() = default;
+    HeidicFrameArena(const HeidicFrameArena&) = delete;
+    HeidicFrameArena& operator=(const HeidicFrameArena&) = delete;
+
+    ~HeidicFrameArena() {
+        for (Block& block : m_blocks) std::free(block.data);
+    }
+
+    // `count` value-initialized (zeroed) elements that stay valid until the arena rewinds past them
+    template <typename T>
+    HeidicSlice<T> alloc_array(int64_t count) {
+        static_assert(std::is_trivially_destructible<T>::value, "frame.alloc_array never runs destructors");
+        if (count <= 0) return HeidicSlice<T>{nullptr, 0};
+        T* items = static_cast<T*>(allocate(sizeof(T) * static_cast<size_t>(count), alignof(T)));
+        for (int64_t i = 0; i < count; ++i) new (items + i) T();
+        return HeidicSlice<T>{items, static_cast<int32_t>(count)};
+    }
+
+    Mark mark() const { return Mark{m_current, m_offset}; }
+
+    void rewind(Mark mark) {
+        m_current = mark.block;
+        m_offset = mark.offset;
+        if (m_current == 0 && m_offset == 0 && m_blocks.size() > 1) consolidate();
+    }
+
+    // Release everything
+    void reset() { rewind(Mark{0, 0}); }
+
+    // The most bytes the arena has held at once (block padding included)
+    size_t high_water() const { return m_highWater; }
+    size_t capacity() const {
+        size_t total = 0;
+        for (const Block& block : m_blocks) total += block.size;
+        return total;
+    }
+
+private:
+    struct Block {
+        unsigned char* data;
+        size_t size;
+    };
+
+    void* allocate(size_t bytes, size_t align) {
+        while (m_current < m_blocks.size()) {
+            Block& block = m_blocks[m_current];
+            size_t offset = alignUp(reinterpret_cast<uintptr_t>(block.data) + m_offset, align) - reinterpret_cast<uintptr_t>(block.data);
+            if (offset + bytes <= block.size) {
+                m_offset = offset + bytes;
+                noteUsage();
+                return block.data + offset;
+            }
+            // Doesn't fit: the rest of this block goes unused until the next rewind
+            if (m_current + 1 == m_blocks.size()) break;
+            ++m_current;
+            m_offset = 0;
+        }
+        size_t size = m_blocks.empty() ? HEIDIC_FRAME_ARENA_BLOCK_SIZE : m_blocks.back().size * 2;
+        while (size < bytes + align) size *= 2;
+        addBlock(size);
+        m_current = m_blocks.size() - 1;
+        m_offset = 0;
+        return allocate(bytes, align);
+    }
+
+    void addBlock(size_t size) {
+        unsigned char* data = static_cast<unsigned char*>(std::malloc(size));
+        if (!data) std::abort();  // Out of memory: nothing sensible is left to do mid-frame
+        m_blocks.push_back(Block{data, size});
+    }
+
+    // One block as large as all of them, so the next frame doesn't have to hop between blocks
+    void consolidate() {
+        size_t total = capacity();
+        for (Block& block : m_blocks) std::free(block.data);
+        m_blocks.clear();
+        addBlock(total);
+    }
+
+    void noteUsage() {
+        size_t used = m_offset;
+        for (size_t i = 0; i < m_current; ++i) used += m_blocks[i].size;
+        if (used > m_highWater) m_highWater = used;
+    }
+
+    static uintptr_t alignUp(uintptr_t value, size_t align) {
+        return (value + align - 1) & ~static_cast<uintptr_t>(align - 1);
+    }
+
+    std::vector<Block> m_blocks;
+    size_t m_current = 0;  // Block allocations come from
+    size_t m_offset = 0;   // Bytes of it in use
+    size_t m_highWater = 0;
+};
+
+// Opened at the top of a loop body: whatever the iteration allocates is released when it ends,
+// however it ends (continue, break or return)
+class HeidicFrameScope {
+public:
+    explicit HeidicFrameScope(HeidicFrameArena& arena) : m_arena(arena), m_mark(arena.mark()) {}
+    ~HeidicFrameScope() { m_arena.rewind(m_mark); }
+    HeidicFrameScope(const HeidicFrameScope&) = delete;
+    HeidicFrameScope& operator=(const HeidicFrameScope&) = delete;
+
+private:
+    HeidicFrameArena& m_arena;
+    HeidicFrameArena::Mark m_mark;
+};
+
+// FrameArena parameters share the caller's arena
+using FrameArena = HeidicFrameArena&;
+
+#endif // EDEN_FRAME_ARENA_H
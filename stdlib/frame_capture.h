@@ -0,0 +1,42 @@
+// EDEN ENGINE Standard Library - Frame Capture
+// Reads presented frames back from the swapchain for screenshot() and for golden-image tests
+// (`heidic_v2 test --render`). Images are written as 8-bit RGBA PNGs.
+//
+// Offscreen mode creates the window hidden, so a render test doesn't flash a window on the
+// desktop. It still needs a display for the swapchain; on a headless CI machine run the test
+// binary under a virtual one (e.g. xvfb-run).
+
+#ifndef EDEN_FRAME_CAPTURE_H
+#define EDEN_FRAME_CAPTURE_H
+
+#include <stdint.h>
+
+// Render modes (heidic_set_render_mode), applied by heidic_glfw_vulkan_hints() to the next window
+#define HEIDIC_RENDER_WINDOWED   0
+#define HEIDIC_RENDER_OFFSCREEN  1  // Hidden window; frames are rendered and presented as usual
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+void heidic_set_render_mode(int32_t mode);
+
+// Save the next presented frame to `path` (PNG)
+void heidic_screenshot(const char* path);
+
+// Keep a copy of every presented frame for heidic_expect_frame (costs a GPU sync per frame)
+void heidic_set_frame_capture(int32_t enabled);
+
+// With update enabled, heidic_expect_frame writes its reference image instead of comparing
+void heidic_set_update_golden(int32_t update);
+
+// Compare the last presented frame against a reference PNG. The difference is the mean absolute
+// per-channel error in [0, 1]; the frame matches when it is <= tolerance. On a mismatch the frame
+// is saved next to the reference as <reference>.actual.png. Returns 1 on a match.
+int32_t heidic_expect_frame(const char* reference, float tolerance);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // EDEN_FRAME_CAPTURE_H
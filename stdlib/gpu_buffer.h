@@ -0,0 +1,49 @@
+// EDEN ENGINE Standard Library - GPU Storage Buffers (upload / readback)
+// Generated code declares one HeidicGpuBuffer per named `storage T[] name` pipeline binding.
+// upload() (re)allocates the device-local buffer to fit the data and copies it through the
+// transfer queue; readback() copies the buffer back on the graphics queue after all previously
+// submitted work (e.g. a compute dispatch) and waits on a fence, so CPU systems see final results.
+// Element structs must match the shader's std430 layout.
+
+#ifndef EDEN_GPU_BUFFER_H
+#define EDEN_GPU_BUFFER_H
+
+#include "vulkan.h"
+#include <stdint.h>
+#include <vector>
+
+typedef struct HeidicGpuBuffer {
+    const char* name;       // Binding name (for diagnostics)
+    VkBuffer buffer;        // Device-local storage buffer (VK_NULL_HANDLE until the first upload)
+    VkDeviceMemory memory;
+    VkDeviceSize size;      // Bytes of valid data
+} HeidicGpuBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Waits for the GPU to stop using the buffer, grows it if needed, then copies size bytes in
+void heidic_gpu_buffer_write(HeidicGpuBuffer* buffer, const void* data, VkDeviceSize size);
+// Copies up to maxSize bytes out once prior GPU work completes; returns the bytes copied
+VkDeviceSize heidic_gpu_buffer_read(HeidicGpuBuffer* buffer, void* dst, VkDeviceSize maxSize);
+
+#ifdef __cplusplus
+}
+
+// Typed wrappers used by the readback(buffer) / upload(buffer, data) intrinsics
+template<typename T>
+inline std::vector<T> heidic_readback(HeidicGpuBuffer* buffer) {
+    std::vector<T> data((size_t)(buffer->size / sizeof(T)));
+    VkDeviceSize copied = heidic_gpu_buffer_read(buffer, data.data(), data.size() * sizeof(T));
+    data.resize((size_t)(copied / sizeof(T)));
+    return data;
+}
+
+template<typename T>
+inline void heidic_upload(HeidicGpuBuffer* buffer, const std::vector<T>& data) {
+    heidic_gpu_buffer_write(buffer, data.data(), data.size() * sizeof(T));
+}
+#endif
+
+#endif // EDEN_GPU_BUFFER_H
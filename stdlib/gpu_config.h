@@ -0,0 +1,41 @@
+// EDEN ENGINE Standard Library - GPU Device Selection
+// Generated code fills a HeidicGpuConfig from a HEIDIC `gpu { ... }` block and registers it
+// before the renderer is initialized. heidic_init_renderer() uses it to score physical devices,
+// reject devices missing required features, and enable the requested features on the device.
+
+#ifndef EDEN_GPU_CONFIG_H
+#define EDEN_GPU_CONFIG_H
+
+#include <stdint.h>
+
+// Device type preference (gpu { prefer: discrete })
+#define HEIDIC_GPU_PREFER_ANY        0
+#define HEIDIC_GPU_PREFER_DISCRETE   1
+#define HEIDIC_GPU_PREFER_INTEGRATED 2
+
+// Staging upload queue (gpu { transfer_queue: shared })
+#define HEIDIC_GPU_TRANSFER_DEDICATED 0  // Transfer-only queue family when available, else graphics
+#define HEIDIC_GPU_TRANSFER_SHARED    1  // Always upload on the graphics queue
+
+typedef struct HeidicGpuConfig {
+    int32_t prefer;                         // HEIDIC_GPU_PREFER_*
+    int32_t device_index;                   // Explicit device index (gpu { device: 1 }), or -1 to score all devices
+    const char* const* required_features;   // Device is rejected if any of these is unsupported
+    uint32_t required_feature_count;
+    const char* const* optional_features;   // Enabled when supported, skipped otherwise
+    uint32_t optional_feature_count;
+    int32_t transfer_queue;                 // HEIDIC_GPU_TRANSFER_*
+} HeidicGpuConfig;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Register the GPU configuration (must be called before heidic_init_renderer)
+void heidic_set_gpu_config(const HeidicGpuConfig* config);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // EDEN_GPU_CONFIG_H
@@ -0,0 +1,37 @@
+// EDEN ENGINE Standard Library - GPU Queries
+// Generated code declares one HeidicQueryPool per HEIDIC `gpu_query` declaration; the Vulkan
+// query pool behind it is created on first use. A query slot opened with heidic_begin_query()
+// measures every scene render pass recorded until heidic_end_query(), and its latest completed
+// result is returned by heidic_query_result() (results lag the GPU by a frame or more).
+
+#ifndef EDEN_GPU_QUERY_H
+#define EDEN_GPU_QUERY_H
+
+#include <stdint.h>
+
+// Query kinds (gpu_query Occlusion; gpu_query Timestamp; gpu_query PipelineStatistics(...))
+#define HEIDIC_QUERY_OCCLUSION            0  // Result: samples that passed depth/stencil tests
+#define HEIDIC_QUERY_TIMESTAMP            1  // Result: elapsed GPU time in nanoseconds
+#define HEIDIC_QUERY_PIPELINE_STATISTICS  2  // Result: the selected pipeline statistic counter
+
+typedef struct HeidicQueryPool {
+    const char* name;       // Declaration name (for diagnostics)
+    int32_t kind;           // HEIDIC_QUERY_*
+    uint32_t statistic;     // VkQueryPipelineStatisticFlagBits (pipeline statistics pools only)
+    uint32_t count;         // Number of query slots
+    int32_t handle;         // Runtime pool index, -1 until first use
+} HeidicQueryPool;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+void heidic_begin_query(HeidicQueryPool* pool, int32_t index);
+void heidic_end_query(HeidicQueryPool* pool, int32_t index);
+int64_t heidic_query_result(HeidicQueryPool* pool, int32_t index);  // -1 until a result is available
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // EDEN_GPU_QUERY_H
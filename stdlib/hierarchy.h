@@ -0,0 +1,107 @@
+// EDEN ENGINE Standard Library - Entity Hierarchy
+// Parent/child relationships between entities, kept as two components in the EntityStorage:
+// every child has a Parent and every parent has a Children list. Both sides are updated
+// together, so queries can select roots with without<Parent> and children with with<Parent>.
+// Parent records the child's depth (1 under a root), and heidic_hierarchy_order() lists every
+// entity in a hierarchy with each parent before its children, the order transform propagation
+// needs to compute world transforms in one pass.
+
+#ifndef EDEN_HIERARCHY_H
+#define EDEN_HIERARCHY_H
+
+#include <algorithm>
+#include <cstdint>
+#include <cstdio>
+#include <vector>
+
+#include "entity_storage.h"
+
+struct Parent {
+    EntityId entity = INVALID_ENTITY;
+    uint32_t depth = 0;  // Number of ancestors
+};
+
+struct Children {
+    std::vector<EntityId> entities;  // In the order they were parented
+};
+
+inline EntityId heidic_parent_of(EntityStorage& storage, EntityId entity) {
+    Parent* parent = storage.get_component<Parent>(entity);
+    return parent ? parent->entity : INVALID_ENTITY;
+}
+
+// Depths below `entity` follow its own after it moves to another parent
+inline void heidic_update_depths(EntityStorage& storage, EntityId entity, uint32_t depth) {
+    Children* children = storage.get_component<Children>(entity);
+    if (!children) {
+        return;
+    }
+    for (EntityId child : children->entities) {
+        if (Parent* parent = storage.get_component<Parent>(child)) {
+            parent->depth = depth + 1;
+        }
+        heidic_update_depths(storage, child, depth + 1);
+    }
+}
+
+// child.clear_parent(): makes `child` a root; its own children stay with it
+inline void heidic_clear_parent(EntityStorage& storage, EntityId child) {
+    EntityId parent = heidic_parent_of(storage, child);
+    if (parent == INVALID_ENTITY) {
+        return;
+    }
+    if (Children* siblings = storage.get_component<Children>(parent)) {
+        auto& list = siblings->entities;
+        list.erase(std::remove(list.begin(), list.end(), child), list.end());
+        if (list.empty()) {
+            storage.remove_component<Children>(parent);
+        }
+    }
+    storage.remove_component<Parent>(child);
+    heidic_update_depths(storage, child, 0);
+}
+
+// child.set_parent(parent): moves `child` (with its subtree) under `parent`. Refused, with a
+// message, when `parent` is `child` or one of its descendants, which would make a cycle.
+inline void heidic_set_parent(EntityStorage& storage, EntityId child, EntityId parent) {
+    for (EntityId ancestor = parent; ancestor != INVALID_ENTITY; ancestor = heidic_parent_of(storage, ancestor)) {
+        if (ancestor == child) {
+            fprintf(stderr, "[hierarchy] Can't parent entity %u to %u: %u is %s\n", child, parent, parent,
+                parent == child ? "the entity itself" : "one of its descendants");
+            return;
+        }
+    }
+    if (heidic_parent_of(storage, child) == parent) {
+        return;
+    }
+    heidic_clear_parent(storage, child);
+    Parent* grandparent = storage.get_component<Parent>(parent);
+    uint32_t depth = grandparent ? grandparent->depth + 1 : 1;
+    storage.add_component<Parent>(child, Parent{parent, depth});
+    Children* children = storage.get_component<Children>(parent);
+    if (!children) {
+        storage.add_component<Children>(parent, Children{});
+        children = storage.get_component<Children>(parent);
+    }
+    children->entities.push_back(child);
+    heidic_update_depths(storage, child, depth);
+}
+
+// Every entity with a parent or children, parents first: roots, then depth 1, depth 2, ...
+inline std::vector<EntityId> heidic_hierarchy_order(EntityStorage& storage) {
+    std::vector<EntityId> order;
+    storage.for_each<Children>([&](EntityId entity, Children&) {
+        if (!storage.has_component<Parent>(entity)) {
+            order.push_back(entity);
+        }
+    });
+    // Breadth-first, so each level follows the one above it
+    for (size_t i = 0; i < order.size(); i++) {
+        if (Children* children = storage.get_component<Children>(order[i])) {
+            order.insert(order.end(), children->entities.begin(), children->entities.end());
+        }
+    }
+    return order;
+}
+
+#endif // EDEN_HIERARCHY_H
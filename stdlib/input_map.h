@@ -0,0 +1,165 @@
+// EDEN ENGINE Standard Library - Input mapping
+// An `input { ... }` block's actions read their keys, mouse buttons and gamepad controls from a
+// HeidicInputDevices. attach(window) installs GLFW key and mouse button callbacks (the window's
+// previous callbacks, e.g. an ImGui backend's, are still called), and update() takes a snapshot
+// once a frame: a key pressed and released between two updates reads as held for one of them,
+// so short taps aren't lost. The first connected joystick with a gamepad mapping is used, and a
+// new one is picked when it disconnects.
+//
+// Codes are GLFW's (GLFW_KEY_*, GLFW_MOUSE_BUTTON_*, GLFW_GAMEPAD_BUTTON_*, GLFW_GAMEPAD_AXIS_*).
+
+#ifndef EDEN_INPUT_MAP_H
+#define EDEN_INPUT_MAP_H
+
+#include <cmath>
+#include <initializer_list>
+
+#include "glfw.h"
+
+// Sticks read 0 inside this much of their travel, so a resting stick doesn't drift
+#define HEIDIC_GAMEPAD_DEAD_ZONE 0.15f
+
+class HeidicInputDevices {
+public:
+    void attach(GLFWwindow* window) {
+        current() = this;
+        GLFWkeyfun key = glfwSetKeyCallback(window, keyCallback);
+        GLFWmousebuttonfun mouse = glfwSetMouseButtonCallback(window, mouseButtonCallback);
+        if (key != keyCallback) previousKeyCallback() = key;
+        if (mouse != mouseButtonCallback) previousMouseButtonCallback() = mouse;
+        glfwSetJoystickCallback(joystickCallback);
+        findGamepad();
+    }
+
+    void update() {
+        for (int key = 0; key <= GLFW_KEY_LAST; ++key) {
+            m_keys[key] = m_keysDown[key] || m_keysTapped[key];
+            m_keysTapped[key] = false;
+        }
+        for (int button = 0; button <= GLFW_MOUSE_BUTTON_LAST; ++button) {
+            m_mouseButtons[button] = m_mouseButtonsDown[button] || m_mouseButtonsTapped[button];
+            m_mouseButtonsTapped[button] = false;
+        }
+        m_hasGamepad = m_gamepad >= 0 && glfwGetGamepadState(m_gamepad, &m_gamepadState) == GLFW_TRUE;
+    }
+
+    bool key(int key) const {
+        return key >= 0 && key <= GLFW_KEY_LAST && m_keys[key];
+    }
+
+    bool mouseButton(int button) const {
+        return button >= 0 && button <= GLFW_MOUSE_BUTTON_LAST && m_mouseButtons[button];
+    }
+
+    // False without a gamepad
+    bool gamepadButton(int button) const {
+        return m_hasGamepad && button >= 0 && button <= GLFW_GAMEPAD_BUTTON_LAST
+            && m_gamepadState.buttons[button] == GLFW_PRESS;
+    }
+
+    // Sticks in [-1, 1] (past the dead zone), triggers in [0, 1]; 0 without a gamepad
+    float gamepadAxis(int axis) const {
+        if (!m_hasGamepad || axis < 0 || axis > GLFW_GAMEPAD_AXIS_LAST) return 0.0f;
+        float value = m_gamepadState.axes[axis];
+        if (axis == GLFW_GAMEPAD_AXIS_LEFT_TRIGGER || axis == GLFW_GAMEPAD_AXIS_RIGHT_TRIGGER) {
+            return (value + 1.0f) * 0.5f;  // GLFW reports a released trigger as -1
+        }
+        float magnitude = std::fabs(value);
+        if (magnitude < HEIDIC_GAMEPAD_DEAD_ZONE) return 0.0f;
+        float scaled = std::fmin((magnitude - HEIDIC_GAMEPAD_DEAD_ZONE) / (1.0f - HEIDIC_GAMEPAD_DEAD_ZONE), 1.0f);
+        return value < 0.0f ? -scaled : scaled;
+    }
+
+private:
+    // GLFW callbacks are plain functions: they reach the attached devices through here
+    static HeidicInputDevices*& current() {
+        static HeidicInputDevices* devices = nullptr;
+        return devices;
+    }
+    static GLFWkeyfun& previousKeyCallback() {
+        static GLFWkeyfun callback = nullptr;
+        return callback;
+    }
+    static GLFWmousebuttonfun& previousMouseButtonCallback() {
+        static GLFWmousebuttonfun callback = nullptr;
+        return callback;
+    }
+
+    static void keyCallback(GLFWwindow* window, int key, int scancode, int action, int mods) {
+        HeidicInputDevices* devices = current();
+        if (devices && key >= 0 && key <= GLFW_KEY_LAST && action != GLFW_REPEAT) {
+            devices->m_keysDown[key] = action == GLFW_PRESS;
+            devices->m_keysTapped[key] = devices->m_keysTapped[key] || action == GLFW_PRESS;
+        }
+        if (previousKeyCallback()) previousKeyCallback()(window, key, scancode, action, mods);
+    }
+
+    static void mouseButtonCallback(GLFWwindow* window, int button, int action, int mods) {
+        HeidicInputDevices* devices = current();
+        if (devices && button >= 0 && button <= GLFW_MOUSE_BUTTON_LAST) {
+            devices->m_mouseButtonsDown[button] = action == GLFW_PRESS;
+            devices->m_mouseButtonsTapped[button] = devices->m_mouseButtonsTapped[button] || action == GLFW_PRESS;
+        }
+        if (previousMouseButtonCallback()) previousMouseButtonCallback()(window, button, action, mods);
+    }
+
+    static void joystickCallback(int joystick, int event) {
+        HeidicInputDevices* devices = current();
+        if (!devices) return;
+        if (event == GLFW_CONNECTED && devices->m_gamepad < 0 && glfwJoystickIsGamepad(joystick)) {
+            devices->m_gamepad = joystick;
+        } else if (event == GLFW_DISCONNECTED && joystick == devices->m_gamepad) {
+            devices->findGamepad();
+        }
+    }
+
+    void findGamepad() {
+        m_gamepad = -1;
+        m_hasGamepad = false;
+        for (int joystick = GLFW_JOYSTICK_1; joystick <= GLFW_JOYSTICK_LAST; ++joystick) {
+            if (glfwJoystickIsGamepad(joystick)) {
+                m_gamepad = joystick;
+                return;
+            }
+        }
+    }
+
+    bool m_keys[GLFW_KEY_LAST + 1] = {};
+    bool m_keysDown[GLFW_KEY_LAST + 1] = {};
+    bool m_keysTapped[GLFW_KEY_LAST + 1] = {};
+    bool m_mouseButtons[GLFW_MOUSE_BUTTON_LAST + 1] = {};
+    bool m_mouseButtonsDown[GLFW_MOUSE_BUTTON_LAST + 1] = {};
+    bool m_mouseButtonsTapped[GLFW_MOUSE_BUTTON_LAST + 1] = {};
+    int m_gamepad = -1;
+    bool m_hasGamepad = false;
+    GLFWgamepadstate m_gamepadState = {};
+};
+
+// A button action: held this frame, and whether it was the frame before
+struct HeidicInputButton {
+    bool down = false;
+    bool wasDown = false;
+
+    void set(bool held) {
+        wasDown = down;
+        down = held;
+    }
+    bool pressed() const { return down && !wasDown; }
+    bool released() const { return !down && wasDown; }
+};
+
+// axis(negative, positive): -1, 0 or 1
+inline float heidic_input_axis(bool negative, bool positive) {
+    return (positive ? 1.0f : 0.0f) - (negative ? 1.0f : 0.0f);
+}
+
+// An axis action bound to several controls follows the one pushed furthest
+inline float heidic_input_strongest(std::initializer_list<float> values) {
+    float strongest = 0.0f;
+    for (float value : values) {
+        if (std::fabs(value) > std::fabs(strongest)) strongest = value;
+    }
+    return strongest;
+}
+
+#endif // EDEN_INPUT_MAP_H
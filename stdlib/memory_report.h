@@ -0,0 +1,74 @@
+// EDEN ENGINE Standard Library - Memory report
+// memory_report() lists the bytes the entity storage holds per component type (and per archetype
+// with --ecs-storage=archetype): how many are stored, how many the allocations hold, the most
+// stored at once. It prints a table, largest first, returns the total, and memory_report(path)
+// also writes the report as JSON.
+
+#ifndef EDEN_MEMORY_REPORT_H
+#define EDEN_MEMORY_REPORT_H
+
+#include <algorithm>
+#include <cstdint>
+#include <cstdio>
+#include <string>
+#include <vector>
+
+#include "entity_storage.h"
+
+struct HeidicMemoryEntry {
+    std::string name;  // Component name, or "A+B" for an archetype
+    HeidicComponentMemory memory;
+};
+
+inline void heidic_memory_json_entries(FILE* file, const std::vector<HeidicMemoryEntry>& entries) {
+    for (size_t i = 0; i < entries.size(); ++i) {
+        const HeidicComponentMemory& m = entries[i].memory;
+        std::fprintf(file, "    {\"name\": \"%s\", \"count\": %zu, \"capacity\": %zu, \"high_water\": %zu, \"bytes\": %zu}%s\n",
+                     entries[i].name.c_str(), m.count, m.capacity, m.high_water, m.bytes, i + 1 < entries.size() ? "," : "");
+    }
+}
+
+inline void heidic_memory_table(const char* title, const std::vector<HeidicMemoryEntry>& entries) {
+    std::printf("  %-32s %10s %10s %10s %12s\n", title, "count", "capacity", "high", "bytes");
+    for (const HeidicMemoryEntry& entry : entries) {
+        const HeidicComponentMemory& m = entry.memory;
+        std::printf("  %-32s %10zu %10zu %10zu %12zu\n", entry.name.c_str(), m.count, m.capacity, m.high_water, m.bytes);
+    }
+}
+
+// Prints the report and returns the total bytes; writes it to `json_path` too unless that is empty
+inline int64_t heidic_memory_report(std::vector<HeidicMemoryEntry> components, std::vector<HeidicMemoryEntry> archetypes,
+                                    size_t bookkeeping_bytes, const std::string& json_path) {
+    auto largest_first = [](const HeidicMemoryEntry& a, const HeidicMemoryEntry& b) { return a.memory.bytes > b.memory.bytes; };
+    std::stable_sort(components.begin(), components.end(), largest_first);
+    std::stable_sort(archetypes.begin(), archetypes.end(), largest_first);
+    // Archetypes hold the component columns plus their rows' entity ids, so they're the total when there are any
+    size_t total = bookkeeping_bytes;
+    for (const HeidicMemoryEntry& entry : archetypes.empty() ? components : archetypes) {
+        total += entry.memory.bytes;
+    }
+
+    std::printf("[memory] entity storage: %zu bytes\n", total);
+    heidic_memory_table("component", components);
+    if (!archetypes.empty()) {
+        heidic_memory_table("archetype", archetypes);
+    }
+    std::printf("  %-32s %45zu\n", "entity bookkeeping", bookkeeping_bytes);
+
+    if (!json_path.empty()) {
+        FILE* file = std::fopen(json_path.c_str(), "w");
+        if (!file) {
+            std::fprintf(stderr, "[memory] can't write %s\n", json_path.c_str());
+        } else {
+            std::fprintf(file, "{\n  \"total_bytes\": %zu,\n  \"bookkeeping_bytes\": %zu,\n  \"components\": [\n", total, bookkeeping_bytes);
+            heidic_memory_json_entries(file, components);
+            std::fprintf(file, "  ],\n  \"archetypes\": [\n");
+            heidic_memory_json_entries(file, archetypes);
+            std::fprintf(file, "  ]\n}\n");
+            std::fclose(file);
+        }
+    }
+    return static_cast<int64_t>(total);
+}
+
+#endif // EDEN_MEMORY_REPORT_H
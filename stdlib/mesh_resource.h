@@ -7,6 +7,7 @@
 
 #include "vulkan.h"
 #include "obj_loader.h"
+#include "transfer_queue.h"
 #include <vector>
 #include <string>
 #include <cstring>
@@ -148,52 +149,8 @@ private:
                     VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT,
                     m_vertexBuffer, m_vertexBufferMemory);
         
-        // Create staging buffer for vertex data
-        VkBuffer stagingVertexBuffer;
-        VkDeviceMemory stagingVertexBufferMemory;
-        createBuffer(vertexBufferSize,
-                    VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
-                    VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
-                    stagingVertexBuffer, stagingVertexBufferMemory);
-        
-        // Copy vertex data to staging buffer
-        void* data;
-        vkMapMemory(g_device, stagingVertexBufferMemory, 0, vertexBufferSize, 0, &data);
-        memcpy(data, m_vertices.data(), (size_t)vertexBufferSize);
-        vkUnmapMemory(g_device, stagingVertexBufferMemory);
-        
-        // Copy staging buffer to vertex buffer (using command buffer)
-        VkCommandBufferAllocateInfo allocInfo = {};
-        allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
-        allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
-        allocInfo.commandPool = g_commandPool;
-        allocInfo.commandBufferCount = 1;
-        
-        VkCommandBuffer commandBuffer;
-        vkAllocateCommandBuffers(g_device, &allocInfo, &commandBuffer);
-        
-        VkCommandBufferBeginInfo beginInfo = {};
-        beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
-        beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
-        vkBeginCommandBuffer(commandBuffer, &beginInfo);
-        
-        VkBufferCopy copyRegion = {};
-        copyRegion.size = vertexBufferSize;
-        vkCmdCopyBuffer(commandBuffer, stagingVertexBuffer, m_vertexBuffer, 1, &copyRegion);
-        
-        vkEndCommandBuffer(commandBuffer);
-        
-        VkSubmitInfo submitInfo = {};
-        submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
-        submitInfo.commandBufferCount = 1;
-        submitInfo.pCommandBuffers = &commandBuffer;
-        
-        vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
-        vkQueueWaitIdle(g_graphicsQueue);
-        
-        vkFreeCommandBuffers(g_device, g_commandPool, 1, &commandBuffer);
-        vkDestroyBuffer(g_device, stagingVertexBuffer, nullptr);
-        vkFreeMemory(g_device, stagingVertexBufferMemory, nullptr);
+        // Upload vertex data (transfer queue when available)
+        heidic_upload_buffer(m_vertexBuffer, 0, m_vertices.data(), vertexBufferSize);
         
         // Create index buffer
         VkDeviceSize indexBufferSize = sizeof(uint32_t) * m_indices.size();
@@ -202,37 +159,8 @@ private:
                     VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT,
                     m_indexBuffer, m_indexBufferMemory);
         
-        // Create staging buffer for index data
-        VkBuffer stagingIndexBuffer;
-        VkDeviceMemory stagingIndexBufferMemory;
-        createBuffer(indexBufferSize,
-                    VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
-                    VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
-                    stagingIndexBuffer, stagingIndexBufferMemory);
-        
-        // Copy index data to staging buffer
-        vkMapMemory(g_device, stagingIndexBufferMemory, 0, indexBufferSize, 0, &data);
-        memcpy(data, m_indices.data(), (size_t)indexBufferSize);
-        vkUnmapMemory(g_device, stagingIndexBufferMemory);
-        
-        // Copy staging buffer to index buffer
-        vkAllocateCommandBuffers(g_device, &allocInfo, &commandBuffer);
-        vkBeginCommandBuffer(commandBuffer, &beginInfo);
-        
-        copyRegion.size = indexBufferSize;
-        vkCmdCopyBuffer(commandBuffer, stagingIndexBuffer, m_indexBuffer, 1, &copyRegion);
-        
-        vkEndCommandBuffer(commandBuffer);
-        
-        submitInfo.commandBufferCount = 1;
-        submitInfo.pCommandBuffers = &commandBuffer;
-        
-        vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
-        vkQueueWaitIdle(g_graphicsQueue);
-        
-        vkFreeCommandBuffers(g_device, g_commandPool, 1, &commandBuffer);
-        vkDestroyBuffer(g_device, stagingIndexBuffer, nullptr);
-        vkFreeMemory(g_device, stagingIndexBufferMemory, nullptr);
+        // Upload index data (transfer queue when available)
+        heidic_upload_buffer(m_indexBuffer, 0, m_indices.data(), indexBufferSize);
     }
 
 public:
@@ -290,52 +218,8 @@ public:
                         VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT,
                         m_vertexBuffer, m_vertexBufferMemory);
             
-            // Create staging buffer for vertex data
-            VkBuffer stagingVertexBuffer;
-            VkDeviceMemory stagingVertexBufferMemory;
-            createBuffer(vertexBufferSize,
-                        VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
-                        VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
-                        stagingVertexBuffer, stagingVertexBufferMemory);
-            
-            // Copy vertex data to staging buffer
-            void* data;
-            vkMapMemory(g_device, stagingVertexBufferMemory, 0, vertexBufferSize, 0, &data);
-            memcpy(data, m_vertices.data(), (size_t)vertexBufferSize);
-            vkUnmapMemory(g_device, stagingVertexBufferMemory);
-            
-            // Copy staging buffer to vertex buffer
-            VkCommandBufferAllocateInfo allocInfo = {};
-            allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
-            allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
-            allocInfo.commandPool = g_commandPool;
-            allocInfo.commandBufferCount = 1;
-            
-            VkCommandBuffer commandBuffer;
-            vkAllocateCommandBuffers(g_device, &allocInfo, &commandBuffer);
-            
-            VkCommandBufferBeginInfo beginInfo = {};
-            beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
-            beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
-            vkBeginCommandBuffer(commandBuffer, &beginInfo);
-            
-            VkBufferCopy copyRegion = {};
-            copyRegion.size = vertexBufferSize;
-            vkCmdCopyBuffer(commandBuffer, stagingVertexBuffer, m_vertexBuffer, 1, &copyRegion);
-            
-            vkEndCommandBuffer(commandBuffer);
-            
-            VkSubmitInfo submitInfo = {};
-            submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
-            submitInfo.commandBufferCount = 1;
-            submitInfo.pCommandBuffers = &commandBuffer;
-            
-            vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
-            vkQueueWaitIdle(g_graphicsQueue);
-            
-            vkFreeCommandBuffers(g_device, g_commandPool, 1, &commandBuffer);
-            vkDestroyBuffer(g_device, stagingVertexBuffer, nullptr);
-            vkFreeMemory(g_device, stagingVertexBufferMemory, nullptr);
+            // Upload vertex data (transfer queue when available)
+            heidic_upload_buffer(m_vertexBuffer, 0, m_vertices.data(), vertexBufferSize);
             
             // Create index buffer
             VkDeviceSize indexBufferSize = sizeof(uint32_t) * m_indices.size();
@@ -344,37 +228,8 @@ public:
                         VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT,
                         m_indexBuffer, m_indexBufferMemory);
             
-            // Create staging buffer for index data
-            VkBuffer stagingIndexBuffer;
-            VkDeviceMemory stagingIndexBufferMemory;
-            createBuffer(indexBufferSize,
-                        VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
-                        VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
-                        stagingIndexBuffer, stagingIndexBufferMemory);
-            
-            // Copy index data to staging buffer
-            vkMapMemory(g_device, stagingIndexBufferMemory, 0, indexBufferSize, 0, &data);
-            memcpy(data, m_indices.data(), (size_t)indexBufferSize);
-            vkUnmapMemory(g_device, stagingIndexBufferMemory);
-            
-            // Copy staging buffer to index buffer
-            vkAllocateCommandBuffers(g_device, &allocInfo, &commandBuffer);
-            vkBeginCommandBuffer(commandBuffer, &beginInfo);
-            
-            copyRegion.size = indexBufferSize;
-            vkCmdCopyBuffer(commandBuffer, stagingIndexBuffer, m_indexBuffer, 1, &copyRegion);
-            
-            vkEndCommandBuffer(commandBuffer);
-            
-            submitInfo.commandBufferCount = 1;
-            submitInfo.pCommandBuffers = &commandBuffer;
-            
-            vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
-            vkQueueWaitIdle(g_graphicsQueue);
-            
-            vkFreeCommandBuffers(g_device, g_commandPool, 1, &commandBuffer);
-            vkDestroyBuffer(g_device, stagingIndexBuffer, nullptr);
-            vkFreeMemory(g_device, stagingIndexBufferMemory, nullptr);
+            // Upload index data (transfer queue when available)
+            heidic_upload_buffer(m_indexBuffer, 0, m_indices.data(), indexBufferSize);
             
             m_loaded = true;
             return true;
@@ -477,51 +332,8 @@ public:
                         VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT,
                         m_vertexBuffer, m_vertexBufferMemory);
             
-            // Staging buffer for vertices
-            VkBuffer stagingBuffer;
-            VkDeviceMemory stagingBufferMemory;
-            createBuffer(vertexBufferSize,
-                        VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
-                        VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
-                        stagingBuffer, stagingBufferMemory);
-            
-            void* data;
-            vkMapMemory(g_device, stagingBufferMemory, 0, vertexBufferSize, 0, &data);
-            memcpy(data, m_vertices.data(), (size_t)vertexBufferSize);
-            vkUnmapMemory(g_device, stagingBufferMemory);
-            
-            // Copy to GPU
-            VkCommandBufferAllocateInfo allocInfo = {};
-            allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
-            allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
-            allocInfo.commandPool = g_commandPool;
-            allocInfo.commandBufferCount = 1;
-            
-            VkCommandBuffer commandBuffer;
-            vkAllocateCommandBuffers(g_device, &allocInfo, &commandBuffer);
-            
-            VkCommandBufferBeginInfo beginInfo = {};
-            beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
-            beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
-            vkBeginCommandBuffer(commandBuffer, &beginInfo);
-            
-            VkBufferCopy copyRegion = {};
-            copyRegion.size = vertexBufferSize;
-            vkCmdCopyBuffer(commandBuffer, stagingBuffer, m_vertexBuffer, 1, &copyRegion);
-            
-            vkEndCommandBuffer(commandBuffer);
-            
-            VkSubmitInfo submitInfo = {};
-            submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
-            submitInfo.commandBufferCount = 1;
-            submitInfo.pCommandBuffers = &commandBuffer;
-            
-            vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
-            vkQueueWaitIdle(g_graphicsQueue);
-            
-            vkFreeCommandBuffers(g_device, g_commandPool, 1, &commandBuffer);
-            vkDestroyBuffer(g_device, stagingBuffer, nullptr);
-            vkFreeMemory(g_device, stagingBufferMemory, nullptr);
+            // Upload vertex data (transfer queue when available)
+            heidic_upload_buffer(m_vertexBuffer, 0, m_vertices.data(), vertexBufferSize);
             
             // Create new index buffer
             if (!m_indices.empty()) {
@@ -533,32 +345,8 @@ public:
                             VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT,
                             m_indexBuffer, m_indexBufferMemory);
                 
-                VkBuffer stagingIndexBuffer;
-                VkDeviceMemory stagingIndexBufferMemory;
-                createBuffer(indexBufferSize,
-                            VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
-                            VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
-                            stagingIndexBuffer, stagingIndexBufferMemory);
-                
-                vkMapMemory(g_device, stagingIndexBufferMemory, 0, indexBufferSize, 0, &data);
-                memcpy(data, m_indices.data(), (size_t)indexBufferSize);
-                vkUnmapMemory(g_device, stagingIndexBufferMemory);
-                
-                vkAllocateCommandBuffers(g_device, &allocInfo, &commandBuffer);
-                vkBeginCommandBuffer(commandBuffer, &beginInfo);
-                
-                copyRegion.size = indexBufferSize;
-                vkCmdCopyBuffer(commandBuffer, stagingIndexBuffer, m_indexBuffer, 1, &copyRegion);
-                
-                vkEndCommandBuffer(commandBuffer);
-                
-                submitInfo.pCommandBuffers = &commandBuffer;
-                vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
-                vkQueueWaitIdle(g_graphicsQueue);
-                
-                vkFreeCommandBuffers(g_device, g_commandPool, 1, &commandBuffer);
-                vkDestroyBuffer(g_device, stagingIndexBuffer, nullptr);
-                vkFreeMemory(g_device, stagingIndexBufferMemory, nullptr);
+                // Upload index data (transfer queue when available)
+                heidic_upload_buffer(m_indexBuffer, 0, m_indices.data(), indexBufferSize);
             }
             
         } catch (const std::exception& e) {
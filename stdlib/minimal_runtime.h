@@ -0,0 +1,151 @@
+// EDEN ENGINE Standard Library - Minimal runtime (--runtime minimal)
+// What generated code uses on platforms without iostream, std::unordered_map or exceptions
+// (consoles, WASM, embedded): print() lines and runtime errors go to a logging callback the
+// embedding program installs, maps keyed by component type are fixed-capacity arrays sized by the
+// compiler (HEIDIC_MAX_COMPONENT_TYPES), and failures are HeidicStatus codes. The one failure that
+// can't be returned, a full map behind a reference, is logged and aborts.
+
+#ifndef EDEN_MINIMAL_RUNTIME_H
+#define EDEN_MINIMAL_RUNTIME_H
+
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <cstdlib>
+#include <string>
+#include <type_traits>
+#include <utility>
+
+#ifndef HEIDIC_MAX_COMPONENT_TYPES
+#define HEIDIC_MAX_COMPONENT_TYPES 64
+#endif
+
+enum HeidicStatus : int32_t {
+    HEIDIC_OK = 0,
+    HEIDIC_ERROR_CAPACITY = 1,  // A fixed-capacity container is full
+};
+
+enum HeidicLogLevel : int32_t {
+    HEIDIC_LOG_INFO = 0,   // print()
+    HEIDIC_LOG_ERROR = 1,  // Runtime errors
+};
+
+// Receives one line per print() call or error
+typedef void (*HeidicLogCallback)(HeidicLogLevel level, const char* message);
+
+inline void heidic_default_log(HeidicLogLevel level, const char* message) {
+    std::FILE* stream = level == HEIDIC_LOG_ERROR ? stderr : stdout;
+    std::fputs(message, stream);
+    std::fputc('\n', stream);
+}
+
+inline HeidicLogCallback& heidic_log_callback() {
+    static HeidicLogCallback callback = heidic_default_log;
+    return callback;
+}
+
+// Install the platform's logger; nullptr restores the stdio default
+inline void heidic_set_log_callback(HeidicLogCallback callback) {
+    heidic_log_callback() = callback ? callback : heidic_default_log;
+}
+
+inline void heidic_log(HeidicLogLevel level, const char* message) {
+    heidic_log_callback()(level, message);
+}
+
+[[noreturn]] inline void heidic_fatal(HeidicStatus status, const char* message) {
+    char line[256];
+    std::snprintf(line, sizeof(line), "fatal error %d: %s", static_cast<int>(status), message);
+    heidic_log(HEIDIC_LOG_ERROR, line);
+    std::abort();
+}
+
+// One print() line; longer output is truncated
+struct HeidicLogLine {
+    char text[1024] = {};
+    size_t length = 0;
+
+    void append(const char* value) {
+        while (*value && length + 1 < sizeof(text)) {
+            text[length++] = *value++;
+        }
+    }
+};
+
+inline void heidic_append(HeidicLogLine& line, const char* value) { line.append(value); }
+inline void heidic_append(HeidicLogLine& line, const std::string& value) { line.append(value.c_str()); }
+inline void heidic_append(HeidicLogLine& line, bool value) { line.append(value ? "1" : "0"); }
+
+// Numbers print as std::cout would by default: integers in full, floats with 6 significant digits
+template <typename T>
+void heidic_append(HeidicLogLine& line, T value) {
+    static_assert(std::is_arithmetic<T>::value, "print() in the minimal runtime takes strings, numbers and bools");
+    char buffer[32];
+    if constexpr (std::is_floating_point<T>::value) {
+        std::snprintf(buffer, sizeof(buffer), "%g", static_cast<double>(value));
+    } else if constexpr (std::is_signed<T>::value) {
+        std::snprintf(buffer, sizeof(buffer), "%lld", static_cast<long long>(value));
+    } else {
+        std::snprintf(buffer, sizeof(buffer), "%llu", static_cast<unsigned long long>(value));
+    }
+    line.append(buffer);
+}
+
+template <typename... Args>
+void heidic_print(const Args&... args) {
+    HeidicLogLine line;
+    (heidic_append(line, args), ...);
+    heidic_log(HEIDIC_LOG_INFO, line.text);
+}
+
+// A map of at most Capacity entries in one array, searched linearly: the runtime keys a few
+// dozen component types at most, so this beats hashing and never allocates
+template <typename K, typename V, size_t Capacity>
+class HeidicFixedMap {
+public:
+    using value_type = std::pair<K, V>;
+    using iterator = value_type*;
+    using const_iterator = const value_type*;
+
+    iterator begin() { return entries; }
+    iterator end() { return entries + count; }
+    const_iterator begin() const { return entries; }
+    const_iterator end() const { return entries + count; }
+    size_t size() const { return count; }
+
+    iterator find(const K& key) {
+        for (size_t i = 0; i < count; ++i) {
+            if (entries[i].first == key) return entries + i;
+        }
+        return end();
+    }
+
+    const_iterator find(const K& key) const {
+        return const_cast<HeidicFixedMap*>(this)->find(key);
+    }
+
+    // Keeps the existing value for a key already present, like std::unordered_map::emplace
+    HeidicStatus emplace(const K& key, V value) {
+        if (find(key) != end()) return HEIDIC_OK;
+        if (count == Capacity) return HEIDIC_ERROR_CAPACITY;
+        entries[count].first = key;
+        entries[count].second = std::move(value);
+        ++count;
+        return HEIDIC_OK;
+    }
+
+    V& operator[](const K& key) {
+        iterator it = find(key);
+        if (it != end()) return it->second;
+        if (emplace(key, V()) != HEIDIC_OK) {
+            heidic_fatal(HEIDIC_ERROR_CAPACITY, "more component types than HEIDIC_MAX_COMPONENT_TYPES");
+        }
+        return entries[count - 1].second;
+    }
+
+private:
+    value_type entries[Capacity];
+    size_t count = 0;
+};
+
+#endif // EDEN_MINIMAL_RUNTIME_H
@@ -0,0 +1,396 @@
+// EDEN ENGINE Standard Library - Networking
+// A minimal UDP transport for @[replicated] components, in the spirit of ENet: one non-blocking
+// socket, peers identified by their address, a connect handshake, keepalives and timeouts.
+// There is no reliable channel. The server sends every peer a snapshot on each net_update():
+// the replicated components changed since the newest snapshot the peer acknowledged, plus the
+// ones removed since. A lost packet is covered by the next snapshot instead of being resent.
+//
+// Packets are save_data.h buffers of the "Net" schema (version = protocol). A snapshot is split
+// into packets of about HEIDIC_NET_PACKET_SIZE bytes, each holding whole entries:
+//   Connect:  the client's replicated components and fields, refused if they differ from the server's
+//   Ack:      the sequence and change tick of the newest snapshot the client received whole
+//   Snapshot: sequence, change tick, packet index, packet count, entries (component, entity,
+//             present, fields if present)
+//
+// Windows needs ws2_32: MSVC links it from the pragma below, MinGW needs -lws2_32.
+
+#ifndef EDEN_NET_H
+#define EDEN_NET_H
+
+#include <chrono>
+#include <cstdint>
+#include <cstdio>
+#include <string>
+#include <unordered_map>
+#include <vector>
+
+#ifdef _WIN32
+#ifndef NOMINMAX
+#define NOMINMAX
+#endif
+#ifndef WIN32_LEAN_AND_MEAN
+#define WIN32_LEAN_AND_MEAN
+#endif
+#include <winsock2.h>
+#include <ws2tcpip.h>
+#ifdef _MSC_VER
+#pragma comment(lib, "ws2_32.lib")
+#endif
+using HeidicSocket = SOCKET;
+static const HeidicSocket HEIDIC_NO_SOCKET = INVALID_SOCKET;
+#else
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netdb.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <unistd.h>
+using HeidicSocket = int;
+static const HeidicSocket HEIDIC_NO_SOCKET = -1;
+#endif
+
+#include "entity_storage.h"
+#include "save_data.h"
+
+static constexpr uint32_t HEIDIC_NET_PROTOCOL = 1;
+static constexpr size_t HEIDIC_NET_PACKET_SIZE = 1200;  // Fits the MTU of about any path
+static constexpr double HEIDIC_NET_TIMEOUT = 5.0;        // Seconds of silence before a peer is dropped
+static constexpr double HEIDIC_NET_RETRY = 0.5;          // Seconds between a client's connect attempts
+
+enum class HeidicNetPacket : uint32_t { Connect = 0, Ack = 1, Snapshot = 2 };
+
+struct HeidicNetAddress {
+    sockaddr_in addr {};
+
+    bool operator==(const HeidicNetAddress& other) const {
+        return addr.sin_addr.s_addr == other.addr.sin_addr.s_addr && addr.sin_port == other.addr.sin_port;
+    }
+
+    std::string to_string() const {
+        char host[INET_ADDRSTRLEN] = "?";
+        inet_ntop(AF_INET, &addr.sin_addr, host, sizeof(host));
+        return std::string(host) + ":" + std::to_string(ntohs(addr.sin_port));
+    }
+};
+
+// A client, as the server sees it
+struct HeidicNetPeer {
+    HeidicNetAddress address;
+    std::chrono::steady_clock::time_point last_heard;
+    uint32_t sequence = 0;        // Snapshots sent
+    uint32_t acked_sequence = 0;  // Newest snapshot the peer received whole, and its change tick
+    uint32_t acked_tick = 0;
+    // Per replicated component: the entities the peer was sent it for, mapped to 0 while it's
+    // present and to the sequence of the first snapshot reporting its removal until that's acked
+    std::vector<std::unordered_map<EntityId, uint32_t>> sent;
+};
+
+// A snapshot split into packets that each start with the snapshot's header, so the client can
+// apply every packet that arrives on its own
+class HeidicSnapshotWriter {
+public:
+    HeidicSnapshotWriter(uint32_t sequence, uint32_t tick) : sequence_(sequence), tick_(tick) { start(); }
+
+    uint32_t sequence() const { return sequence_; }
+
+    // One entity's component; the generated code writes its fields after a present one
+    HeidicSaveWriter& entry(uint32_t component, EntityId entity, bool present) {
+        if (packets_.back().size() >= HEIDIC_NET_PACKET_SIZE) {
+            start();
+        }
+        HeidicSaveWriter& packet = packets_.back();
+        ++entries_.back();
+        packet.write(component);
+        packet.write(entity);
+        packet.write(present);
+        return packet;
+    }
+
+    const std::vector<HeidicSaveWriter>& finish() {
+        for (size_t i = 0; i < packets_.size(); i++) {
+            packets_[i].fill(count_at_, static_cast<uint32_t>(packets_.size()));
+            packets_[i].fill(entries_at_, entries_[i]);
+        }
+        return packets_;
+    }
+
+private:
+    void start() {
+        packets_.emplace_back("Net", HEIDIC_NET_PROTOCOL);
+        HeidicSaveWriter& packet = packets_.back();
+        packet.write(static_cast<uint32_t>(HeidicNetPacket::Snapshot));
+        packet.write(sequence_);
+        packet.write(tick_);
+        packet.write(static_cast<uint32_t>(packets_.size() - 1));
+        count_at_ = packet.reserve_size();  // Every packet's header has the same length
+        entries_at_ = packet.reserve_size();
+        entries_.push_back(0);
+    }
+
+    uint32_t sequence_;
+    uint32_t tick_;
+    std::vector<HeidicSaveWriter> packets_;
+    std::vector<uint32_t> entries_;
+    size_t count_at_ = 0;
+    size_t entries_at_ = 0;
+};
+
+class HeidicNetHost {
+public:
+    HeidicNetHost() = default;
+    HeidicNetHost(const HeidicNetHost&) = delete;
+    HeidicNetHost& operator=(const HeidicNetHost&) = delete;
+    ~HeidicNetHost() { close(); }
+
+    // Server: take clients on `port`
+    bool host(uint16_t port) {
+        server_ = true;
+        return open(port);
+    }
+
+    // Client: update() sends connect packets to the server until its first snapshot arrives
+    bool connect(const std::string& host, uint16_t port) {
+        server_ = false;
+        if (!open(0)) {
+            return false;
+        }
+        addrinfo hints {};
+        hints.ai_family = AF_INET;
+        hints.ai_socktype = SOCK_DGRAM;
+        addrinfo* found = nullptr;
+        if (getaddrinfo(host.c_str(), nullptr, &hints, &found) != 0 || !found) {
+            fprintf(stderr, "[net] Can't resolve %s\n", host.c_str());
+            close();
+            return false;
+        }
+        server_address_.addr = *reinterpret_cast<const sockaddr_in*>(found->ai_addr);
+        server_address_.addr.sin_port = htons(port);
+        freeaddrinfo(found);
+        return true;
+    }
+
+    bool is_open() const { return socket_ != HEIDIC_NO_SOCKET; }
+    bool is_server() const { return server_; }
+    bool connected() const { return connected_; }
+    std::vector<HeidicNetPeer>& peers() { return peers_; }
+
+    void send(const HeidicNetAddress& to, const HeidicSaveWriter& packet) {
+        sendto(socket_, reinterpret_cast<const char*>(packet.bytes().data()), static_cast<int>(packet.size()), 0,
+               reinterpret_cast<const sockaddr*>(&to.addr), sizeof(to.addr));
+    }
+
+    // The next packet of this protocol, with its kind already read; false once none are waiting
+    bool receive(HeidicSaveReader& packet, HeidicNetAddress& from, HeidicNetPacket& kind) {
+        std::vector<unsigned char> buffer(65536);
+        while (true) {
+            socklen_t from_size = sizeof(from.addr);
+            auto size = recvfrom(socket_, reinterpret_cast<char*>(buffer.data()), static_cast<int>(buffer.size()), 0,
+                                 reinterpret_cast<sockaddr*>(&from.addr), &from_size);
+            if (size < 0) {
+                return false;
+            }
+            buffer.resize(static_cast<size_t>(size));
+            uint32_t value = 0;
+            if (packet.open_bytes(buffer, "Net") && packet.version() == HEIDIC_NET_PROTOCOL) {
+                packet.read(value);
+                kind = static_cast<HeidicNetPacket>(value);
+                return packet.ok();
+            }
+            buffer.resize(65536);
+        }
+    }
+
+    // Server: a connect packet (re)starts the peer at `address` from an empty world
+    void add_peer(const HeidicNetAddress& address, size_t components) {
+        HeidicNetPeer* peer = find_peer(address);
+        if (!peer) {
+            fprintf(stderr, "[net] %s connected\n", address.to_string().c_str());
+            peers_.emplace_back();
+            peer = &peers_.back();
+        }
+        *peer = HeidicNetPeer {};
+        peer->address = address;
+        peer->last_heard = std::chrono::steady_clock::now();
+        peer->sent.resize(components);
+    }
+
+    // Server: an ack packet
+    void acknowledge(const HeidicNetAddress& address, uint32_t sequence, uint32_t tick) {
+        HeidicNetPeer* peer = find_peer(address);
+        if (!peer) {
+            return;
+        }
+        peer->last_heard = std::chrono::steady_clock::now();
+        if (sequence > peer->acked_sequence && sequence <= peer->sequence) {
+            peer->acked_sequence = sequence;
+            peer->acked_tick = tick;
+        }
+    }
+
+    // Server: forget peers that stopped sending acks
+    void drop_silent_peers() {
+        auto now = std::chrono::steady_clock::now();
+        for (size_t i = peers_.size(); i-- > 0;) {
+            if (std::chrono::duration<double>(now - peers_[i].last_heard).count() > HEIDIC_NET_TIMEOUT) {
+                fprintf(stderr, "[net] %s timed out\n", peers_[i].address.to_string().c_str());
+                peers_.erase(peers_.begin() + static_cast<std::ptrdiff_t>(i));
+            }
+        }
+    }
+
+    // Client: whether a snapshot packet should be applied. Packets of a snapshot older than one
+    // already seen are dropped, so values never go back in time.
+    bool begin_snapshot_packet(const HeidicNetAddress& from, uint32_t sequence, uint32_t tick, uint32_t index, uint32_t count) {
+        if (!(from == server_address_) || count == 0 || count > 65536 || index >= count) {
+            return false;
+        }
+        if (connected_ && sequence < sequence_) {
+            return false;
+        }
+        if (!connected_ || sequence > sequence_) {
+            sequence_ = sequence;
+            tick_ = tick;
+            received_.assign(count, false);
+        }
+        if (received_.size() != count || received_[index]) {
+            return false;
+        }
+        if (!connected_) {
+            fprintf(stderr, "[net] Connected to %s\n", server_address_.to_string().c_str());
+        }
+        connected_ = true;
+        last_heard_ = std::chrono::steady_clock::now();
+        received_[index] = true;
+        return true;
+    }
+
+    // Client: ask to connect, or acknowledge the newest snapshot received whole (which also
+    // keeps the connection alive)
+    void client_update(const std::vector<std::string>& schema) {
+        auto now = std::chrono::steady_clock::now();
+        if (connected_ && std::chrono::duration<double>(now - last_heard_).count() > HEIDIC_NET_TIMEOUT) {
+            fprintf(stderr, "[net] Lost the connection to %s\n", server_address_.to_string().c_str());
+            connected_ = false;
+            acked_sequence_ = 0;
+        }
+        if (!connected_) {
+            if (std::chrono::duration<double>(now - last_connect_).count() >= HEIDIC_NET_RETRY) {
+                HeidicSaveWriter packet("Net", HEIDIC_NET_PROTOCOL);
+                packet.write(static_cast<uint32_t>(HeidicNetPacket::Connect));
+                packet.write(schema);
+                send(server_address_, packet);
+                last_connect_ = now;
+            }
+            return;
+        }
+        bool whole = true;
+        for (bool received : received_) {
+            whole = whole && received;
+        }
+        if (whole) {
+            acked_sequence_ = sequence_;
+            acked_tick_ = tick_;
+        }
+        if (acked_sequence_ != 0) {
+            HeidicSaveWriter packet("Net", HEIDIC_NET_PROTOCOL);
+            packet.write(static_cast<uint32_t>(HeidicNetPacket::Ack));
+            packet.write(acked_sequence_);
+            packet.write(acked_tick_);
+            send(server_address_, packet);
+        }
+    }
+
+    // Client: this world's entity for one of the server's, created the first time it's seen
+    EntityId local_entity(EntityId remote, EntityStorage& storage) {
+        auto found = entities_.find(remote);
+        if (found != entities_.end()) {
+            return found->second;
+        }
+        EntityId entity = storage.create_entity();
+        entities_.emplace(remote, entity);
+        return entity;
+    }
+
+private:
+    bool open(uint16_t port) {
+        close();
+#ifdef _WIN32
+        WSADATA data;
+        if (WSAStartup(MAKEWORD(2, 2), &data) != 0) {
+            fprintf(stderr, "[net] Can't start Winsock\n");
+            return false;
+        }
+        winsock_ = true;
+#endif
+        socket_ = ::socket(AF_INET, SOCK_DGRAM, IPPROTO_UDP);
+        if (socket_ == HEIDIC_NO_SOCKET) {
+            fprintf(stderr, "[net] Can't create a UDP socket\n");
+            return false;
+        }
+        sockaddr_in address {};
+        address.sin_family = AF_INET;
+        address.sin_addr.s_addr = htonl(INADDR_ANY);
+        address.sin_port = htons(port);
+        if (bind(socket_, reinterpret_cast<const sockaddr*>(&address), sizeof(address)) != 0) {
+            fprintf(stderr, "[net] Can't listen on port %u (in use?)\n", static_cast<unsigned>(port));
+            close();
+            return false;
+        }
+#ifdef _WIN32
+        u_long non_blocking = 1;
+        ioctlsocket(socket_, FIONBIO, &non_blocking);
+#else
+        fcntl(socket_, F_SETFL, fcntl(socket_, F_GETFL, 0) | O_NONBLOCK);
+#endif
+        return true;
+    }
+
+    void close() {
+        if (socket_ != HEIDIC_NO_SOCKET) {
+#ifdef _WIN32
+            closesocket(socket_);
+#else
+            ::close(socket_);
+#endif
+            socket_ = HEIDIC_NO_SOCKET;
+        }
+#ifdef _WIN32
+        if (winsock_) {
+            WSACleanup();
+            winsock_ = false;
+        }
+#endif
+        peers_.clear();
+        connected_ = false;
+    }
+
+    HeidicNetPeer* find_peer(const HeidicNetAddress& address) {
+        for (HeidicNetPeer& peer : peers_) {
+            if (peer.address == address) {
+                return &peer;
+            }
+        }
+        return nullptr;
+    }
+
+    HeidicSocket socket_ = HEIDIC_NO_SOCKET;
+    bool server_ = false;
+#ifdef _WIN32
+    bool winsock_ = false;
+#endif
+    std::vector<HeidicNetPeer> peers_;
+
+    // Client state: the server, the newest snapshot seen and which of its packets arrived
+    HeidicNetAddress server_address_;
+    bool connected_ = false;
+    uint32_t sequence_ = 0;
+    uint32_t tick_ = 0;
+    std::vector<bool> received_;
+    uint32_t acked_sequence_ = 0;
+    uint32_t acked_tick_ = 0;
+    std::chrono::steady_clock::time_point last_heard_;
+    std::chrono::steady_clock::time_point last_connect_;
+    std::unordered_map<EntityId, EntityId> entities_;  // Server entity -> this world's
+};
+
+#endif // EDEN_NET_H
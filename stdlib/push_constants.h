@@ -0,0 +1,24 @@
+// EDEN ENGINE Standard Library - Push constants
+// A pipeline's `push_constants { ... }` block becomes a struct laid out as the shaders read it
+// (std430: Vec3, Vec4 and Mat4 members are alignas(16), bool is 4 bytes) and is pushed with
+// push_constants_<pipeline>(cmd, data). Mat4 keeps extra data on the CPU side, so its fields hold
+// a HeidicGpuMat4: the 16 column-major floats a GLSL mat4 reads, converted from a Mat4. Uniform
+// blocks (uniform_buffer.h) hold their Mat4 fields the same way.
+
+#ifndef EDEN_PUSH_CONSTANTS_H
+#define EDEN_PUSH_CONSTANTS_H
+
+#include <cstring>
+
+#include "math.h"
+
+struct HeidicGpuMat4 {
+    float m[16];
+
+    HeidicGpuMat4() : HeidicGpuMat4(Mat4()) {}
+    HeidicGpuMat4(const Mat4& matrix) { std::memcpy(m, matrix.m, sizeof(m)); }
+};
+
+static_assert(sizeof(HeidicGpuMat4) == 64, "a GLSL mat4 is 64 bytes");
+
+#endif // EDEN_PUSH_CONSTANTS_H
@@ -9,6 +9,9 @@
 #include <string>
 #include <ctime>
 #include <stdexcept>
+#include <type_traits>
+
+#include "content_hash.h"
 
 #ifdef _WIN32
 #include <sys/stat.h>
@@ -18,13 +21,22 @@
 #include <unistd.h>
 #endif
 
+// Options a declaration passes to T's constructor (`resource Grass: Texture = "grass.png" { ... }`);
+// a type that takes some specializes this, the rest are constructed from the path alone
+struct HeidicNoResourceOptions {};
+
+template<typename T>
+struct HeidicResourceOptions {
+    using type = HeidicNoResourceOptions;
+};
+
 /**
  * Resource<T> - Generic resource wrapper with hot-reload support
  * 
  * Provides:
  * - RAII lifecycle management
- * - File modification time tracking
- * - Hot-reload capability (check and reload on file change)
+ * - Content-hash change tracking (see content_hash.h)
+ * - Hot-reload capability (check and reload when the file's contents change)
  * - Convenient accessors (get(), operator*, operator->)
  * 
  * Usage:
@@ -34,10 +46,15 @@
  */
 template<typename T>
 class Resource {
+public:
+    using Options = typename HeidicResourceOptions<T>::type;
+    
 private:
     std::unique_ptr<T> m_data;
     std::string m_path;
+    Options m_options;
     std::time_t m_lastModified;
+    HeidicFileWatch m_watch;  // Hash of the contents last loaded; decides reload()
     bool m_loaded;
     
     /**
@@ -65,8 +82,13 @@ private:
      */
     void loadResource() {
         try {
-            m_data = std::make_unique<T>(m_path);
+            if constexpr (std::is_same<Options, HeidicNoResourceOptions>::value) {
+                m_data = std::make_unique<T>(m_path);
+            } else {
+                m_data = std::make_unique<T>(m_path, m_options);
+            }
             m_lastModified = getFileModificationTime(m_path);
+            heidic_watch_reset(m_watch);
             m_loaded = true;
         } catch (const std::exception& e) {
             m_loaded = false;
@@ -80,9 +102,11 @@ public:
      * Resource will be loaded on first access (get(), operator*, operator->)
      * This allows resources to be declared as globals before Vulkan is initialized
      * @param filepath Path to resource file
+     * @param options Passed to T's constructor on every (re)load
      */
-    explicit Resource(const std::string& filepath) 
-        : m_path(filepath), m_lastModified(0), m_loaded(false) {
+    explicit Resource(const std::string& filepath, const Options& options = Options()) 
+        : m_path(filepath), m_options(options), m_lastModified(0), m_loaded(false) {
+        m_watch.path = filepath;
         // Don't load yet - lazy load on first access
         // This allows resources to be declared before Vulkan is initialized
     }
@@ -95,7 +119,9 @@ public:
     Resource(Resource&& other) noexcept
         : m_data(std::move(other.m_data)),
           m_path(std::move(other.m_path)),
+          m_options(other.m_options),
           m_lastModified(other.m_lastModified),
+          m_watch(std::move(other.m_watch)),
           m_loaded(other.m_loaded) {
         other.m_loaded = false;
         other.m_lastModified = 0;
@@ -106,7 +132,9 @@ public:
         if (this != &other) {
             m_data = std::move(other.m_data);
             m_path = std::move(other.m_path);
+            m_options = other.m_options;
             m_lastModified = other.m_lastModified;
+            m_watch = std::move(other.m_watch);
             m_loaded = other.m_loaded;
             other.m_loaded = false;
             other.m_lastModified = 0;
@@ -216,7 +244,7 @@ public:
     }
     
     /**
-     * Check if file has been modified and reload if needed
+     * Check if the file's contents have changed and reload if needed
      * This is the hot-reload method for CONTINUUM integration
      * @return true if resource was reloaded, false otherwise
      */
@@ -225,10 +253,8 @@ public:
             return false;
         }
         
-        std::time_t currentModified = getFileModificationTime(m_path);
-        
-        // Check if file has been modified
-        if (currentModified > m_lastModified && currentModified > 0) {
+        // Timestamps misfire on copies and touches and miss same-second edits; compare contents
+        if (heidic_watch_changed(m_watch)) {
             try {
                 // Destroy old resource
                 m_data.reset();
@@ -280,6 +306,7 @@ public:
         m_data.reset();
         m_loaded = false;
         m_lastModified = 0;
+        m_watch.hash = 0;
     }
 };
 
@@ -0,0 +1,214 @@
+// EDEN ENGINE Standard Library - Save Data
+// Binary files for save_schema types and saved worlds (save_world), and the packets of
+// @[replicated] components (net.h). A file starts with the magic "HSAV", the schema name and the
+// version that wrote it, followed by the fields in declaration order. Numbers are little-endian,
+// strings and arrays are a u32 count followed by their elements, and vectors are their float
+// components. Generated load_<schema>() functions read any declared version and migrate it to
+// the newest; the reader only has to get the bytes out safely.
+
+#ifndef EDEN_SAVE_DATA_H
+#define EDEN_SAVE_DATA_H
+
+#include <cstdint>
+#include <cstdio>
+#include <cstring>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "math.h"
+
+class HeidicSaveWriter {
+public:
+    HeidicSaveWriter(const char* schema, uint32_t version) {
+        bytes_.insert(bytes_.end(), {'H', 'S', 'A', 'V'});
+        write(std::string(schema));
+        write(version);
+    }
+
+    void write(uint32_t value) { put(value, 4); }
+    void write(int32_t value) { put(static_cast<uint32_t>(value), 4); }
+    void write(int64_t value) { put(static_cast<uint64_t>(value), 8); }
+    void write(bool value) { bytes_.push_back(value ? 1 : 0); }
+    void write(float value) {
+        uint32_t bits;
+        memcpy(&bits, &value, sizeof(bits));
+        put(bits, 4);
+    }
+    void write(double value) {
+        uint64_t bits;
+        memcpy(&bits, &value, sizeof(bits));
+        put(bits, 8);
+    }
+    void write(const std::string& value) {
+        write(static_cast<uint32_t>(value.size()));
+        bytes_.insert(bytes_.end(), value.begin(), value.end());
+    }
+    void write(const Vec2& value) { write(value.x); write(value.y); }
+    void write(const Vec3& value) { write(value.x); write(value.y); write(value.z); }
+    void write(const Vec4& value) { write(value.x); write(value.y); write(value.z); write(value.w); }
+    template <typename T>
+    void write(const std::vector<T>& values) {
+        write(static_cast<uint32_t>(values.size()));
+        for (const T& value : values) {
+            write(value);
+        }
+    }
+
+    // A length written before the bytes it counts are known: reserve its place, then fill it in
+    size_t reserve_size() {
+        size_t at = bytes_.size();
+        write(static_cast<uint32_t>(0));
+        return at;
+    }
+    void fill_size(size_t at) { fill(at, static_cast<uint32_t>(bytes_.size() - at - 4)); }
+    void fill(size_t at, uint32_t value) {
+        for (int i = 0; i < 4; i++) {
+            bytes_[at + i] = static_cast<unsigned char>(value >> (8 * i));
+        }
+    }
+
+    const std::vector<unsigned char>& bytes() const { return bytes_; }
+    size_t size() const { return bytes_.size(); }
+
+    // Written to a temporary file first, so a failed save doesn't destroy the previous one
+    bool save_to(const std::string& path) const {
+        std::string temporary = path + ".tmp";
+        {
+            std::ofstream file(temporary, std::ios::binary | std::ios::trunc);
+            if (!file.write(reinterpret_cast<const char*>(bytes_.data()), static_cast<std::streamsize>(bytes_.size()))) {
+                fprintf(stderr, "[save] Can't write %s\n", temporary.c_str());
+                return false;
+            }
+        }
+        std::remove(path.c_str());  // rename() won't replace an existing file on Windows
+        if (std::rename(temporary.c_str(), path.c_str()) != 0) {
+            fprintf(stderr, "[save] Can't replace %s\n", path.c_str());
+            return false;
+        }
+        return true;
+    }
+
+private:
+    void put(uint64_t value, int size) {
+        for (int i = 0; i < size; i++) {
+            bytes_.push_back(static_cast<unsigned char>(value >> (8 * i)));
+        }
+    }
+
+    std::vector<unsigned char> bytes_;
+};
+
+class HeidicSaveReader {
+public:
+    // Reads the file and its header; false if it's missing or isn't a save of `schema`
+    bool open(const std::string& path, const char* schema) {
+        std::ifstream file(path, std::ios::binary);
+        if (!file) {
+            return false;
+        }
+        std::string name;
+        if (open_header(std::vector<unsigned char>(std::istreambuf_iterator<char>(file), std::istreambuf_iterator<char>()), name)
+            && name != schema) {
+            fprintf(stderr, "[save] %s holds %s data, not %s\n", path.c_str(), name.c_str(), schema);
+            ok_ = false;
+        }
+        return ok_;
+    }
+
+    // The same for bytes already in memory (a received packet), without the messages
+    bool open_bytes(std::vector<unsigned char> bytes, const char* schema) {
+        std::string name;
+        ok_ = open_header(std::move(bytes), name) && name == schema;
+        return ok_;
+    }
+
+    uint32_t version() const { return version_; }
+
+    // False once any read ran past the end of the file; every read after that yields zeroes
+    bool ok() const { return ok_; }
+
+    void read(uint32_t& value) { value = static_cast<uint32_t>(get(4)); }
+    void read(int32_t& value) { value = static_cast<int32_t>(get(4)); }
+    void read(int64_t& value) { value = static_cast<int64_t>(get(8)); }
+    void read(bool& value) { value = get(1) != 0; }
+    void read(float& value) {
+        uint32_t bits = static_cast<uint32_t>(get(4));
+        memcpy(&value, &bits, sizeof(value));
+    }
+    void read(double& value) {
+        uint64_t bits = get(8);
+        memcpy(&value, &bits, sizeof(value));
+    }
+    void read(std::string& value) {
+        uint32_t size = 0;
+        read(size);
+        if (!has(size)) {
+            value.clear();
+            return;
+        }
+        value.assign(reinterpret_cast<const char*>(bytes_.data()) + position_, size);
+        position_ += size;
+    }
+    void read(Vec2& value) { read(value.x); read(value.y); }
+    void read(Vec3& value) { read(value.x); read(value.y); read(value.z); }
+    void read(Vec4& value) { read(value.x); read(value.y); read(value.z); read(value.w); }
+    template <typename T>
+    void read(std::vector<T>& values) {
+        uint32_t count = 0;
+        read(count);
+        values.clear();
+        // Every element takes at least a byte, so a corrupt count can't allocate more than the file
+        if (!has(count)) {
+            return;
+        }
+        values.resize(count);
+        for (T& value : values) {
+            read(value);
+        }
+    }
+
+    void skip(uint32_t size) {
+        if (has(size)) {
+            position_ += size;
+        }
+    }
+
+private:
+    bool open_header(std::vector<unsigned char> bytes, std::string& name) {
+        bytes_ = std::move(bytes);
+        ok_ = bytes_.size() >= 4 && memcmp(bytes_.data(), "HSAV", 4) == 0;
+        position_ = 4;
+        read(name);
+        read(version_);
+        return ok_;
+    }
+
+    bool has(size_t size) {
+        if (ok_ && bytes_.size() - position_ >= size) {
+            return true;
+        }
+        ok_ = false;
+        return false;
+    }
+
+    uint64_t get(int size) {
+        if (!has(static_cast<size_t>(size))) {
+            return 0;
+        }
+        uint64_t value = 0;
+        for (int i = 0; i < size; i++) {
+            value |= static_cast<uint64_t>(bytes_[position_ + i]) << (8 * i);
+        }
+        position_ += size;
+        return value;
+    }
+
+    std::vector<unsigned char> bytes_;
+    size_t position_ = 0;
+    uint32_t version_ = 0;
+    bool ok_ = false;
+};
+
+#endif // EDEN_SAVE_DATA_H
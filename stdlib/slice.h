@@ -0,0 +1,46 @@
+// EDEN ENGINE Standard Library - Slices
+// A pointer and a length over contiguous elements. Chunked query loops (for chunk in
+// q.chunks(1024)) hand these out, so a system can pass a whole run of components or SoA field
+// values to an extern routine (e.g. a SIMD kernel) with one call instead of one per entity.
+
+#ifndef EDEN_SLICE_H
+#define EDEN_SLICE_H
+
+#include <cstddef>
+#include <cstdint>
+#include <type_traits>
+#include <vector>
+
+template <typename T>
+struct HeidicSlice {
+    T* data;
+    int32_t count;
+
+    T& operator[](int32_t i) const { return data[i]; }
+    T* begin() const { return data; }
+    T* end() const { return data + count; }
+    size_t size() const { return static_cast<size_t>(count); }
+};
+
+// `count` elements of a contiguous container starting at `start`
+template <typename Container>
+auto heidic_slice(Container& items, size_t start, int32_t count) {
+    using T = std::remove_pointer_t<decltype(items.data())>;
+    return HeidicSlice<T>{items.data() + start, count};
+}
+
+// Arrays cross the C boundary as a pointer to their first element
+template <typename T>
+T* heidic_data(std::vector<T>& items) { return items.data(); }
+
+template <typename T>
+T* heidic_data(const HeidicSlice<T>& slice) { return slice.data; }
+
+// Chunk loops: a chunk size below 1 still makes progress, and the last chunk may be short
+inline size_t heidic_chunk_step(int64_t size) { return size > 0 ? static_cast<size_t>(size) : 1; }
+
+inline int32_t heidic_chunk_count(size_t total, size_t start, size_t step) {
+    return static_cast<int32_t>(total - start < step ? total - start : step);
+}
+
+#endif // EDEN_SLICE_H
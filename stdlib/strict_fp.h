@@ -0,0 +1,31 @@
+// EDEN ENGINE Standard Library - Strict floating point
+// @strict_fp functions (and everything with --strict-fp) are wrapped in HEIDIC_STRICT_FP_BEGIN /
+// HEIDIC_STRICT_FP_END so the same float code gives the same bits on every machine: no fast-math
+// reassociation, no a*b+c contracted into an FMA, and every float evaluated at its own width.
+// Lockstep networking and replays depend on it. The compile commands add -ffp-contract=off too.
+
+#ifndef EDEN_STRICT_FP_H
+#define EDEN_STRICT_FP_H
+
+#include <cfloat>
+
+// x87 keeps intermediates in 80-bit registers, so results would depend on register allocation
+#if defined(FLT_EVAL_METHOD) && FLT_EVAL_METHOD != 0 && FLT_EVAL_METHOD != -1
+#error "@strict_fp needs float math at float width (FLT_EVAL_METHOD 0): build with -msse2 -mfpmath=sse"
+#endif
+
+#if defined(__clang__)
+#define HEIDIC_STRICT_FP_BEGIN _Pragma("float_control(precise, on, push)") _Pragma("clang fp contract(off)")
+#define HEIDIC_STRICT_FP_END _Pragma("float_control(pop)")
+#elif defined(__GNUC__)
+#define HEIDIC_STRICT_FP_BEGIN _Pragma("GCC push_options") _Pragma("GCC optimize(\"no-fast-math\", \"fp-contract=off\")")
+#define HEIDIC_STRICT_FP_END _Pragma("GCC pop_options")
+#elif defined(_MSC_VER)
+#define HEIDIC_STRICT_FP_BEGIN __pragma(float_control(precise, on, push)) __pragma(fp_contract(off))
+#define HEIDIC_STRICT_FP_END __pragma(float_control(pop))
+#else
+#define HEIDIC_STRICT_FP_BEGIN
+#define HEIDIC_STRICT_FP_END
+#endif
+
+#endif // EDEN_STRICT_FP_H
@@ -0,0 +1,25 @@
+// EDEN ENGINE Standard Library - Frame Capture Stub (heidic_v2 compile --gen-stdlib-stubs)
+// No frames are ever presented: screenshots aren't written, and heidic_expect_frame() skips
+// the comparison with a message instead of failing the test.
+
+#ifndef EDEN_FRAME_CAPTURE_H
+#define EDEN_FRAME_CAPTURE_H
+
+#include <stdint.h>
+#include <cstdio>
+
+#define HEIDIC_RENDER_WINDOWED   0
+#define HEIDIC_RENDER_OFFSCREEN  1
+
+extern "C" {
+    inline void heidic_set_render_mode(int32_t) {}
+    inline void heidic_screenshot(const char*) {}
+    inline void heidic_set_frame_capture(int32_t) {}
+    inline void heidic_set_update_golden(int32_t) {}
+    inline int32_t heidic_expect_frame(const char* reference, float) {
+        fprintf(stderr, "[stub] expect_frame(\"%s\") skipped: built with stub stdlib headers\n", reference);
+        return 1;
+    }
+}
+
+#endif // EDEN_FRAME_CAPTURE_H
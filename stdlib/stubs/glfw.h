@@ -0,0 +1,72 @@
+// EDEN ENGINE Standard Library - GLFW Stub (heidic_v2 compile --gen-stdlib-stubs)
+// No-op window and input functions, without GLFW. glfwCreateWindow() returns no window and
+// glfwWindowShouldClose() is always true, so a frame loop ends right away; keys and mouse
+// buttons are never pressed. glfwGetTime() is real time since the first call.
+
+#ifndef EDEN_GLFW_H
+#define EDEN_GLFW_H
+
+#include <chrono>
+#include <stdint.h>
+
+typedef struct GLFWwindow GLFWwindow;
+typedef struct GLFWmonitor GLFWmonitor;
+
+// GLFW constants
+#define GLFW_TRUE 1
+#define GLFW_FALSE 0
+#define GLFW_RELEASE 0
+#define GLFW_PRESS 1
+#define GLFW_REPEAT 2
+
+// GLFW window hints
+#define GLFW_CLIENT_API 0x00022001
+#define GLFW_NO_API 0
+#define GLFW_VISIBLE 0x00020004
+#define GLFW_RESIZABLE 0x00020003
+
+// GLFW key codes
+#define GLFW_KEY_SPACE 32
+#define GLFW_KEY_ESCAPE 256
+#define GLFW_KEY_ENTER 257
+#define GLFW_KEY_W 87
+#define GLFW_KEY_A 65
+#define GLFW_KEY_S 83
+#define GLFW_KEY_D 68
+
+// GLFW mouse buttons
+#define GLFW_MOUSE_BUTTON_LEFT 0
+#define GLFW_MOUSE_BUTTON_RIGHT 1
+#define GLFW_MOUSE_BUTTON_MIDDLE 2
+
+// GLFW cursor modes
+#define GLFW_CURSOR 0x00033001
+#define GLFW_CURSOR_NORMAL 0x00034001
+#define GLFW_CURSOR_HIDDEN 0x00034002
+#define GLFW_CURSOR_DISABLED 0x00034003
+
+extern "C" {
+    inline int glfwInit() { return GLFW_TRUE; }
+    inline void glfwTerminate() {}
+    inline void glfwWindowHint(int, int) {}
+    inline GLFWwindow* glfwCreateWindow(int, int, const char*, GLFWmonitor*, GLFWwindow*) { return nullptr; }
+    inline void glfwDestroyWindow(GLFWwindow*) {}
+    inline int glfwWindowShouldClose(GLFWwindow*) { return GLFW_TRUE; }
+    inline void glfwSetWindowShouldClose(GLFWwindow*, int) {}
+    inline void glfwSetWindowTitle(GLFWwindow*, const char*) {}
+    inline void glfwPollEvents() {}
+    inline int glfwGetKey(GLFWwindow*, int) { return GLFW_RELEASE; }
+    inline int glfwGetMouseButton(GLFWwindow*, int) { return GLFW_RELEASE; }
+    inline void glfwGetCursorPos(GLFWwindow*, double* x, double* y) {
+        if (x) *x = 0.0;
+        if (y) *y = 0.0;
+    }
+    inline void glfwSetCursorPos(GLFWwindow*, double, double) {}
+    inline void glfwSetInputMode(GLFWwindow*, int, int) {}
+    inline double glfwGetTime() {
+        static const auto start = std::chrono::steady_clock::now();
+        return std::chrono::duration<double>(std::chrono::steady_clock::now() - start).count();
+    }
+}
+
+#endif // EDEN_GLFW_H
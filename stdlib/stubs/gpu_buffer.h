@@ -0,0 +1,31 @@
+// EDEN ENGINE Standard Library - GPU Storage Buffers Stub (heidic_v2 compile --gen-stdlib-stubs)
+// Uploads are dropped and readback() returns no elements.
+
+#ifndef EDEN_GPU_BUFFER_H
+#define EDEN_GPU_BUFFER_H
+
+#include "vulkan.h"
+#include <stdint.h>
+#include <vector>
+
+typedef struct HeidicGpuBuffer {
+    const char* name;
+    VkBuffer buffer;
+    VkDeviceMemory memory;
+    VkDeviceSize size;
+} HeidicGpuBuffer;
+
+extern "C" {
+    inline void heidic_gpu_buffer_write(HeidicGpuBuffer*, const void*, VkDeviceSize) {}
+    inline VkDeviceSize heidic_gpu_buffer_read(HeidicGpuBuffer*, void*, VkDeviceSize) { return 0; }
+}
+
+template<typename T>
+inline std::vector<T> heidic_readback(HeidicGpuBuffer*) {
+    return std::vector<T>();
+}
+
+template<typename T>
+inline void heidic_upload(HeidicGpuBuffer*, const std::vector<T>&) {}
+
+#endif // EDEN_GPU_BUFFER_H
@@ -0,0 +1,30 @@
+// EDEN ENGINE Standard Library - GPU Device Selection Stub (heidic_v2 compile --gen-stdlib-stubs)
+// The configuration is accepted and ignored.
+
+#ifndef EDEN_GPU_CONFIG_H
+#define EDEN_GPU_CONFIG_H
+
+#include <stdint.h>
+
+#define HEIDIC_GPU_PREFER_ANY        0
+#define HEIDIC_GPU_PREFER_DISCRETE   1
+#define HEIDIC_GPU_PREFER_INTEGRATED 2
+
+#define HEIDIC_GPU_TRANSFER_DEDICATED 0
+#define HEIDIC_GPU_TRANSFER_SHARED    1
+
+typedef struct HeidicGpuConfig {
+    int32_t prefer;
+    int32_t device_index;
+    const char* const* required_features;
+    uint32_t required_feature_count;
+    const char* const* optional_features;
+    uint32_t optional_feature_count;
+    int32_t transfer_queue;
+} HeidicGpuConfig;
+
+extern "C" {
+    inline void heidic_set_gpu_config(const HeidicGpuConfig*) {}
+}
+
+#endif // EDEN_GPU_CONFIG_H
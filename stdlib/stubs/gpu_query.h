@@ -0,0 +1,27 @@
+// EDEN ENGINE Standard Library - GPU Queries Stub (heidic_v2 compile --gen-stdlib-stubs)
+// Nothing is measured: heidic_query_result() never has a result.
+
+#ifndef EDEN_GPU_QUERY_H
+#define EDEN_GPU_QUERY_H
+
+#include <stdint.h>
+
+#define HEIDIC_QUERY_OCCLUSION            0
+#define HEIDIC_QUERY_TIMESTAMP            1
+#define HEIDIC_QUERY_PIPELINE_STATISTICS  2
+
+typedef struct HeidicQueryPool {
+    const char* name;
+    int32_t kind;
+    uint32_t statistic;
+    uint32_t count;
+    int32_t handle;
+} HeidicQueryPool;
+
+extern "C" {
+    inline void heidic_begin_query(HeidicQueryPool*, int32_t) {}
+    inline void heidic_end_query(HeidicQueryPool*, int32_t) {}
+    inline int64_t heidic_query_result(HeidicQueryPool*, int32_t) { return -1; }
+}
+
+#endif // EDEN_GPU_QUERY_H
@@ -0,0 +1,47 @@
+// EDEN ENGINE Standard Library - Input Mapping Stub (heidic_v2 compile --gen-stdlib-stubs)
+// No callbacks are installed and no device is ever pressed, so every action reads as released
+// and every axis as 0.
+
+#ifndef EDEN_INPUT_MAP_H
+#define EDEN_INPUT_MAP_H
+
+#include <cmath>
+#include <initializer_list>
+
+#include "glfw.h"
+
+class HeidicInputDevices {
+public:
+    void attach(GLFWwindow*) {}
+    void update() {}
+    bool key(int) const { return false; }
+    bool mouseButton(int) const { return false; }
+    bool gamepadButton(int) const { return false; }
+    float gamepadAxis(int) const { return 0.0f; }
+};
+
+struct HeidicInputButton {
+    bool down = false;
+    bool wasDown = false;
+
+    void set(bool held) {
+        wasDown = down;
+        down = held;
+    }
+    bool pressed() const { return down && !wasDown; }
+    bool released() const { return !down && wasDown; }
+};
+
+inline float heidic_input_axis(bool negative, bool positive) {
+    return (positive ? 1.0f : 0.0f) - (negative ? 1.0f : 0.0f);
+}
+
+inline float heidic_input_strongest(std::initializer_list<float> values) {
+    float strongest = 0.0f;
+    for (float value : values) {
+        if (std::fabs(value) > std::fabs(strongest)) strongest = value;
+    }
+    return strongest;
+}
+
+#endif // EDEN_INPUT_MAP_H
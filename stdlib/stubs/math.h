@@ -0,0 +1,145 @@
+#pragma once
+
+// EDEN ENGINE Math Library - Stub (heidic_v2 compile --gen-stdlib-stubs)
+// The EDEN math API without GLM: the same types and functions, computed directly. Matrices
+// follow the real header's conventions (column-major m[], right-handed, clip Z in [0, 1]).
+// The eden_* FFI wrappers aren't available.
+
+#include <cstring>
+#include <cmath>  // For sinf, cosf, sqrtf
+
+struct Vec2 {
+    float x, y;
+    Vec2() : x(0.0f), y(0.0f) {}
+    Vec2(float x, float y) : x(x), y(y) {}
+};
+
+struct Vec3 {
+    float x, y, z;
+    Vec3() : x(0.0f), y(0.0f), z(0.0f) {}
+    Vec3(float x, float y, float z) : x(x), y(y), z(z) {}
+};
+
+struct Vec4 {
+    float x, y, z, w;
+    Vec4() : x(0.0f), y(0.0f), z(0.0f), w(0.0f) {}
+    Vec4(float x, float y, float z, float w) : x(x), y(y), z(z), w(w) {}
+};
+
+struct Mat4 {
+    float m[16]; // Column-major: m[col * 4 + row]
+
+    Mat4() {
+        std::memset(m, 0, sizeof(m));
+        m[0] = m[5] = m[10] = m[15] = 1.0f;
+    }
+
+    static Mat4 identity() { return Mat4(); }
+
+    float& at(int col, int row) { return m[col * 4 + row]; }
+    float at(int col, int row) const { return m[col * 4 + row]; }
+};
+
+// Vector operations
+inline Vec3 vec3_add(Vec3 a, Vec3 b) { return Vec3(a.x + b.x, a.y + b.y, a.z + b.z); }
+inline Vec3 vec3_sub(Vec3 a, Vec3 b) { return Vec3(a.x - b.x, a.y - b.y, a.z - b.z); }
+inline Vec3 vec3_mul(Vec3 a, float s) { return Vec3(a.x * s, a.y * s, a.z * s); }
+inline float vec3_dot(Vec3 a, Vec3 b) { return a.x * b.x + a.y * b.y + a.z * b.z; }
+inline Vec3 vec3_cross(Vec3 a, Vec3 b) {
+    return Vec3(a.y * b.z - a.z * b.y, a.z * b.x - a.x * b.z, a.x * b.y - a.y * b.x);
+}
+inline Vec3 vec3_normalize(Vec3 a) {
+    float len = sqrtf(vec3_dot(a, a));
+    if (len > 0.0f) return vec3_mul(a, 1.0f / len);
+    return Vec3(0.0f, 0.0f, 0.0f);
+}
+
+inline Vec3 vec3_lerp(Vec3 a, Vec3 b, float t) {
+    // Linear interpolation: a * (1 - t) + b * t
+    return vec3_add(vec3_mul(a, 1.0f - t), vec3_mul(b, t));
+}
+
+// Matrix operations
+inline Mat4 mat4_mul(Mat4 a, Mat4 b) {
+    Mat4 res;
+    for (int col = 0; col < 4; col++) {
+        for (int row = 0; row < 4; row++) {
+            float sum = 0.0f;
+            for (int k = 0; k < 4; k++) {
+                sum += a.at(k, row) * b.at(col, k);
+            }
+            res.at(col, row) = sum;
+        }
+    }
+    return res;
+}
+
+inline Mat4 mat4_perspective(float fov_rad, float aspect, float near_plane, float far_plane) {
+    // EDEN uses Vulkan coordinate system: Clip Z is [0, 1]
+    float tan_half = tanf(fov_rad / 2.0f);
+    Mat4 res;
+    std::memset(res.m, 0, sizeof(res.m));
+    res.at(0, 0) = 1.0f / (aspect * tan_half);
+    res.at(1, 1) = 1.0f / tan_half;
+    res.at(2, 2) = far_plane / (near_plane - far_plane);
+    res.at(2, 3) = -1.0f;
+    res.at(3, 2) = -(far_plane * near_plane) / (far_plane - near_plane);
+    return res;
+}
+
+inline Mat4 mat4_lookat(Vec3 eye, Vec3 center, Vec3 up) {
+    Vec3 f = vec3_normalize(vec3_sub(center, eye));
+    Vec3 s = vec3_normalize(vec3_cross(f, up));
+    Vec3 u = vec3_cross(s, f);
+    Mat4 res;
+    res.at(0, 0) = s.x; res.at(1, 0) = s.y; res.at(2, 0) = s.z;
+    res.at(0, 1) = u.x; res.at(1, 1) = u.y; res.at(2, 1) = u.z;
+    res.at(0, 2) = -f.x; res.at(1, 2) = -f.y; res.at(2, 2) = -f.z;
+    res.at(3, 0) = -vec3_dot(s, eye);
+    res.at(3, 1) = -vec3_dot(u, eye);
+    res.at(3, 2) = vec3_dot(f, eye);
+    return res;
+}
+
+inline Mat4 mat4_rotate(Vec3 axis, float angle_rad) {
+    Vec3 a = vec3_normalize(axis);
+    float c = cosf(angle_rad);
+    float s = sinf(angle_rad);
+    Vec3 t = vec3_mul(a, 1.0f - c);
+    Mat4 res;
+    res.at(0, 0) = c + t.x * a.x;       res.at(0, 1) = t.x * a.y + s * a.z; res.at(0, 2) = t.x * a.z - s * a.y;
+    res.at(1, 0) = t.y * a.x - s * a.z; res.at(1, 1) = c + t.y * a.y;       res.at(1, 2) = t.y * a.z + s * a.x;
+    res.at(2, 0) = t.z * a.x + s * a.y; res.at(2, 1) = t.z * a.y - s * a.x; res.at(2, 2) = c + t.z * a.z;
+    return res;
+}
+
+inline Mat4 mat4_rotate_z(float angle_rad) {
+    return mat4_rotate(Vec3(0.0f, 0.0f, 1.0f), angle_rad);
+}
+
+inline Mat4 mat4_translate(Vec3 translation) {
+    Mat4 res;
+    res.at(3, 0) = translation.x;
+    res.at(3, 1) = translation.y;
+    res.at(3, 2) = translation.z;
+    return res;
+}
+
+extern "C" {
+    // Math helper functions for HEIDIC
+    inline float heidic_sin(float radians) {
+        return sinf(radians);
+    }
+
+    inline float heidic_cos(float radians) {
+        return cosf(radians);
+    }
+
+    inline float heidic_sqrt(float value) {
+        return sqrtf(value);
+    }
+
+    inline float heidic_convert_degrees_to_radians(float degrees) {
+        return degrees * 3.14159265358979323846f / 180.0f;
+    }
+}
@@ -0,0 +1,35 @@
+// EDEN ENGINE Standard Library - Transfer Queue Stub (heidic_v2 compile --gen-stdlib-stubs)
+// There is no device: uploads are dropped and every transfer is already complete.
+
+#ifndef EDEN_TRANSFER_QUEUE_H
+#define EDEN_TRANSFER_QUEUE_H
+
+#include "vulkan.h"
+#include <stdint.h>
+
+// Typed handle for a submitted transfer batch (id 0 = nothing submitted, always complete)
+typedef struct HeidicTransferTicket {
+    uint64_t id;
+} HeidicTransferTicket;
+typedef HeidicTransferTicket TransferTicket;  // Name used from HEIDIC code
+
+extern "C" {
+    inline uint32_t heidic_graphics_queue_family() { return 0; }
+    inline uint32_t heidic_transfer_queue_family() { return 0; }
+    inline bool heidic_has_dedicated_transfer_queue() { return false; }
+
+    inline VkCommandBuffer heidic_begin_transfer() { return VK_NULL_HANDLE; }
+    inline void heidic_transfer_release_buffer(VkCommandBuffer, VkBuffer, VkDeviceSize, VkDeviceSize) {}
+    inline void heidic_transfer_release_image(VkCommandBuffer, VkImage, VkImageAspectFlags, uint32_t, uint32_t, VkImageLayout) {}
+    inline HeidicTransferTicket heidic_submit_transfer_commands(VkCommandBuffer) { return HeidicTransferTicket{0}; }
+
+    inline void heidic_queue_buffer_upload(VkBuffer, VkDeviceSize, const void*, VkDeviceSize) {}
+    inline HeidicTransferTicket submit_transfer() { return HeidicTransferTicket{0}; }
+
+    inline bool transfer_ready(HeidicTransferTicket) { return true; }
+    inline void wait_transfer(HeidicTransferTicket) {}
+
+    inline void heidic_upload_buffer(VkBuffer, VkDeviceSize, const void*, VkDeviceSize) {}
+}
+
+#endif // EDEN_TRANSFER_QUEUE_H
@@ -0,0 +1,23 @@
+// EDEN ENGINE Standard Library - Uniform Buffers Stub (heidic_v2 compile --gen-stdlib-stubs)
+// No buffer is created and updates are dropped.
+
+#ifndef EDEN_UNIFORM_BUFFER_H
+#define EDEN_UNIFORM_BUFFER_H
+
+#include "vulkan.h"
+#include <stdint.h>
+
+typedef struct HeidicUniformBuffer {
+    const char* name;
+    VkDeviceSize size;
+    VkBuffer buffer;
+    VkDeviceMemory memory;
+    void* mapped;
+} HeidicUniformBuffer;
+
+extern "C" {
+    inline VkBuffer heidic_uniform_buffer_handle(HeidicUniformBuffer*) { return VK_NULL_HANDLE; }
+    inline void heidic_uniform_buffer_write(HeidicUniformBuffer*, const void*) {}
+}
+
+#endif // EDEN_UNIFORM_BUFFER_H
@@ -0,0 +1,60 @@
+// EDEN ENGINE Standard Library - Vulkan Stub (heidic_v2 compile --gen-stdlib-stubs)
+// The handle types and result codes generated code names, without the Vulkan SDK. Handles are
+// opaque pointers that are always VK_NULL_HANDLE; nothing here talks to a GPU.
+
+#ifndef EDEN_VULKAN_H
+#define EDEN_VULKAN_H
+
+#include <stdint.h>
+
+#define VK_NULL_HANDLE nullptr
+#define HEIDIC_STUB_HANDLE(name) typedef struct name##_T* name;
+
+HEIDIC_STUB_HANDLE(VkInstance)
+HEIDIC_STUB_HANDLE(VkPhysicalDevice)
+HEIDIC_STUB_HANDLE(VkDevice)
+HEIDIC_STUB_HANDLE(VkQueue)
+HEIDIC_STUB_HANDLE(VkCommandPool)
+HEIDIC_STUB_HANDLE(VkCommandBuffer)
+HEIDIC_STUB_HANDLE(VkSwapchainKHR)
+HEIDIC_STUB_HANDLE(VkSurfaceKHR)
+HEIDIC_STUB_HANDLE(VkRenderPass)
+HEIDIC_STUB_HANDLE(VkPipeline)
+HEIDIC_STUB_HANDLE(VkFramebuffer)
+HEIDIC_STUB_HANDLE(VkBuffer)
+HEIDIC_STUB_HANDLE(VkImage)
+HEIDIC_STUB_HANDLE(VkImageView)
+HEIDIC_STUB_HANDLE(VkSemaphore)
+HEIDIC_STUB_HANDLE(VkFence)
+HEIDIC_STUB_HANDLE(VkDeviceMemory)
+
+#undef HEIDIC_STUB_HANDLE
+
+typedef int32_t VkResult;
+typedef uint32_t VkBool32;
+typedef uint64_t VkDeviceSize;
+typedef uint32_t VkFlags;
+typedef VkFlags VkImageAspectFlags;
+typedef int32_t VkImageLayout;
+
+#define VK_SUCCESS 0
+#define VK_NOT_READY 1
+#define VK_TIMEOUT 2
+#define VK_EVENT_SET 3
+#define VK_EVENT_RESET 4
+#define VK_INCOMPLETE 5
+#define VK_ERROR_OUT_OF_HOST_MEMORY -1
+#define VK_ERROR_OUT_OF_DEVICE_MEMORY -2
+#define VK_ERROR_INITIALIZATION_FAILED -3
+#define VK_ERROR_DEVICE_LOST -4
+#define VK_ERROR_MEMORY_MAP_FAILED -5
+#define VK_ERROR_LAYER_NOT_PRESENT -6
+#define VK_ERROR_EXTENSION_NOT_PRESENT -7
+#define VK_ERROR_FEATURE_NOT_PRESENT -8
+#define VK_ERROR_INCOMPATIBLE_DRIVER -9
+#define VK_ERROR_TOO_MANY_OBJECTS -10
+#define VK_ERROR_FORMAT_NOT_SUPPORTED -11
+#define VK_ERROR_FRAGMENTED_POOL -12
+#define VK_ERROR_UNKNOWN -13
+
+#endif // EDEN_VULKAN_H
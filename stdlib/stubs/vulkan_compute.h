@@ -0,0 +1,32 @@
+// EDEN ENGINE Standard Library - Vulkan Compute Stub (heidic_v2 compile --gen-stdlib-stubs)
+// Nothing is dispatched: launches leave their queries' arrays as they were.
+
+#ifndef EDEN_VULKAN_COMPUTE_H
+#define EDEN_VULKAN_COMPUTE_H
+
+#include "gpu_buffer.h"
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
+#define HEIDIC_COMPUTE_GROUP_SIZE 256
+
+typedef struct HeidicComputeKernel {
+    const char* name;
+    const char* spirv_path;
+    uint32_t buffer_count;
+    uint32_t push_size;
+    void* state;
+} HeidicComputeKernel;
+
+extern "C" {
+    inline bool heidic_compute_dispatch(HeidicComputeKernel*, HeidicGpuBuffer* const*, const void*, size_t) { return false; }
+}
+
+template <typename T>
+T* heidic_compute_host(std::vector<T>& items) { return items.data(); }
+
+template <typename T>
+T* heidic_compute_host(T* items) { return items; }
+
+#endif // EDEN_VULKAN_COMPUTE_H
@@ -0,0 +1,26 @@
+// EDEN ENGINE Standard Library - Window Configuration Stub (heidic_v2 compile --gen-stdlib-stubs)
+// The configuration is accepted and no window is opened: heidic_open_window() returns null.
+
+#ifndef EDEN_WINDOW_CONFIG_H
+#define EDEN_WINDOW_CONFIG_H
+#define EDEN_WINDOW_CONFIG_STUB
+
+#include <stdint.h>
+
+#include "glfw.h"
+
+typedef struct HeidicWindowConfig {
+    const char* title;
+    int32_t width;
+    int32_t height;
+    int32_t vsync;
+    int32_t resizable;
+} HeidicWindowConfig;
+
+extern "C" {
+    inline void heidic_set_window_config(const HeidicWindowConfig*) {}
+    inline GLFWwindow* heidic_open_window(void) { return nullptr; }
+    inline void heidic_close_window(GLFWwindow*) {}
+}
+
+#endif // EDEN_WINDOW_CONFIG_H
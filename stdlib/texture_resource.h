@@ -8,6 +8,8 @@
 #include "vulkan.h"
 #include "dds_loader.h"
 #include "png_loader.h"
+#include "transfer_queue.h"
+#include "resource.h"
 #include <vector>
 #include <string>
 #include <algorithm>
@@ -21,6 +23,14 @@ extern VkPhysicalDevice g_physicalDevice;
 extern VkCommandPool g_commandPool;
 extern VkQueue g_graphicsQueue;
 
+// Sampler and mip options from a declaration's `{ filter: nearest, wrap: clamp, mips: true }`.
+// mips builds a full chain for PNGs; a DDS keeps the mip levels stored in the file.
+struct HeidicTextureOptions {
+    VkFilter filter = VK_FILTER_LINEAR;
+    VkSamplerAddressMode wrap = VK_SAMPLER_ADDRESS_MODE_REPEAT;
+    bool mips = false;
+};
+
 /**
  * TextureResource - Unified texture loading and Vulkan resource management
  * 
@@ -40,6 +50,7 @@ private:
     uint32_t m_mipmapCount = 1;
     
     bool m_loaded = false;
+    HeidicTextureOptions m_options;
     
     // Helper function to find memory type (same as in vulkan helpers)
     uint32_t findMemoryType(uint32_t typeFilter, VkMemoryPropertyFlags properties) {
@@ -56,38 +67,14 @@ private:
         return UINT32_MAX;
     }
     
-    // Helper to begin single-time command buffer
+    // Helper to begin single-time command buffer (records on the transfer queue)
     VkCommandBuffer beginSingleTimeCommands() {
-        VkCommandBufferAllocateInfo allocInfo = {};
-        allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
-        allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
-        allocInfo.commandPool = g_commandPool;
-        allocInfo.commandBufferCount = 1;
-        
-        VkCommandBuffer commandBuffer;
-        vkAllocateCommandBuffers(g_device, &allocInfo, &commandBuffer);
-        
-        VkCommandBufferBeginInfo beginInfo = {};
-        beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
-        beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
-        
-        vkBeginCommandBuffer(commandBuffer, &beginInfo);
-        return commandBuffer;
+        return heidic_begin_transfer();
     }
     
-    // Helper to end single-time command buffer
+    // Helper to end single-time command buffer (submits and waits for the upload)
     void endSingleTimeCommands(VkCommandBuffer commandBuffer) {
-        vkEndCommandBuffer(commandBuffer);
-        
-        VkSubmitInfo submitInfo = {};
-        submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
-        submitInfo.commandBufferCount = 1;
-        submitInfo.pCommandBuffers = &commandBuffer;
-        
-        vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
-        vkQueueWaitIdle(g_graphicsQueue);
-        
-        vkFreeCommandBuffers(g_device, g_commandPool, 1, &commandBuffer);
+        wait_transfer(heidic_submit_transfer_commands(commandBuffer));
     }
     
     // Create buffer helper (for staging)
@@ -255,14 +242,9 @@ private:
         vkCmdCopyBufferToImage(commandBuffer, stagingBuffer, m_image, 
                               VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL, 1, &region);
         
-        // Transition to shader-readable
-        barrier.oldLayout = VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL;
-        barrier.newLayout = VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL;
-        barrier.srcAccessMask = VK_ACCESS_TRANSFER_WRITE_BIT;
-        barrier.dstAccessMask = VK_ACCESS_SHADER_READ_BIT;
-        
-        vkCmdPipelineBarrier(commandBuffer, VK_PIPELINE_STAGE_TRANSFER_BIT, 
-                            VK_PIPELINE_STAGE_FRAGMENT_SHADER_BIT, 0, 0, nullptr, 0, nullptr, 1, &barrier);
+        // Transition to shader-readable (released to the graphics queue if uploaded on a transfer queue)
+        heidic_transfer_release_image(commandBuffer, m_image, VK_IMAGE_ASPECT_COLOR_BIT, m_mipmapCount, 1,
+                                      VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL);
         
         endSingleTimeCommands(commandBuffer);
         
@@ -271,6 +253,49 @@ private:
         vkFreeMemory(g_device, stagingBufferMemory, nullptr);
     }
     
+    // Box-filters RGBA8 level 0 down to 1x1: returns every level back to back, with the copy
+    // region of each. Built on the CPU so it works on a transfer-only queue (no vkCmdBlitImage).
+    std::vector<unsigned char> buildMipChain(const std::vector<unsigned char>& base, std::vector<VkBufferImageCopy>& regions) {
+        std::vector<unsigned char> chain(base);
+        uint32_t width = m_width;
+        uint32_t height = m_height;
+        size_t offset = 0;
+        while (true) {
+            VkBufferImageCopy region = {};
+            region.bufferOffset = offset;
+            region.imageSubresource.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
+            region.imageSubresource.mipLevel = static_cast<uint32_t>(regions.size());
+            region.imageSubresource.layerCount = 1;
+            region.imageExtent = {width, height, 1};
+            regions.push_back(region);
+            if (width == 1 && height == 1) {
+                break;
+            }
+            uint32_t nextWidth = std::max(width / 2, 1u);
+            uint32_t nextHeight = std::max(height / 2, 1u);
+            size_t nextOffset = chain.size();
+            chain.resize(nextOffset + static_cast<size_t>(nextWidth) * nextHeight * 4);
+            for (uint32_t y = 0; y < nextHeight; ++y) {
+                for (uint32_t x = 0; x < nextWidth; ++x) {
+                    // The 2x2 block above this texel, clamped on odd edges
+                    uint32_t x0 = std::min(x * 2, width - 1), x1 = std::min(x * 2 + 1, width - 1);
+                    uint32_t y0 = std::min(y * 2, height - 1), y1 = std::min(y * 2 + 1, height - 1);
+                    for (uint32_t c = 0; c < 4; ++c) {
+                        auto texel = [&](uint32_t tx, uint32_t ty) {
+                            return static_cast<uint32_t>(chain[offset + (static_cast<size_t>(ty) * width + tx) * 4 + c]);
+                        };
+                        uint32_t sum = texel(x0, y0) + texel(x1, y0) + texel(x0, y1) + texel(x1, y1);
+                        chain[nextOffset + (static_cast<size_t>(y) * nextWidth + x) * 4 + c] = static_cast<unsigned char>((sum + 2) / 4);
+                    }
+                }
+            }
+            width = nextWidth;
+            height = nextHeight;
+            offset = nextOffset;
+        }
+        return chain;
+    }
+    
     // Load PNG texture and create Vulkan resources
     void loadPNG(const std::string& filepath) {
         PNGData pngData = load_png(filepath);
@@ -278,7 +303,18 @@ private:
         m_format = pngData.format;
         m_width = pngData.width;
         m_height = pngData.height;
-        m_mipmapCount = 1;  // PNG has no mipmaps
+        // PNG has no mipmaps of its own; with mips on, the chain is built here
+        std::vector<VkBufferImageCopy> regions;
+        std::vector<unsigned char> pixels = m_options.mips ? buildMipChain(pngData.pixelData, regions)
+                                                           : std::move(pngData.pixelData);
+        if (regions.empty()) {
+            VkBufferImageCopy region = {};
+            region.imageSubresource.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
+            region.imageSubresource.layerCount = 1;
+            region.imageExtent = {m_width, m_height, 1};
+            regions.push_back(region);
+        }
+        m_mipmapCount = static_cast<uint32_t>(regions.size());
         
         // Create Vulkan image
         VkImageCreateInfo imageInfo = {};
@@ -287,7 +323,7 @@ private:
         imageInfo.extent.width = m_width;
         imageInfo.extent.height = m_height;
         imageInfo.extent.depth = 1;
-        imageInfo.mipLevels = 1;
+        imageInfo.mipLevels = m_mipmapCount;
         imageInfo.arrayLayers = 1;
         imageInfo.format = m_format;
         imageInfo.tiling = VK_IMAGE_TILING_OPTIMAL;
@@ -325,15 +361,15 @@ private:
         // Create staging buffer for uncompressed RGBA8 data
         VkBuffer stagingBuffer;
         VkDeviceMemory stagingBufferMemory;
-        createBuffer(pngData.pixelData.size(), 
+        createBuffer(pixels.size(), 
                     VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
                     VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
                     stagingBuffer, stagingBufferMemory);
         
         // Copy pixel data to staging buffer
         void* data;
-        vkMapMemory(g_device, stagingBufferMemory, 0, pngData.pixelData.size(), 0, &data);
-        memcpy(data, pngData.pixelData.data(), pngData.pixelData.size());
+        vkMapMemory(g_device, stagingBufferMemory, 0, pixels.size(), 0, &data);
+        memcpy(data, pixels.data(), pixels.size());
         vkUnmapMemory(g_device, stagingBufferMemory);
         
         // Upload to GPU
@@ -349,7 +385,7 @@ private:
         barrier.image = m_image;
         barrier.subresourceRange.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
         barrier.subresourceRange.baseMipLevel = 0;
-        barrier.subresourceRange.levelCount = 1;
+        barrier.subresourceRange.levelCount = m_mipmapCount;
         barrier.subresourceRange.baseArrayLayer = 0;
         barrier.subresourceRange.layerCount = 1;
         barrier.srcAccessMask = 0;
@@ -358,29 +394,13 @@ private:
         vkCmdPipelineBarrier(commandBuffer, VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT, 
                             VK_PIPELINE_STAGE_TRANSFER_BIT, 0, 0, nullptr, 0, nullptr, 1, &barrier);
         
-        // Copy buffer to image (uncompressed RGBA8)
-        VkBufferImageCopy region = {};
-        region.bufferOffset = 0;
-        region.bufferRowLength = 0;
-        region.bufferImageHeight = 0;
-        region.imageSubresource.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
-        region.imageSubresource.mipLevel = 0;
-        region.imageSubresource.baseArrayLayer = 0;
-        region.imageSubresource.layerCount = 1;
-        region.imageOffset = {0, 0, 0};
-        region.imageExtent = {m_width, m_height, 1};
-        
+        // Copy buffer to image (uncompressed RGBA8, one region per mip level)
         vkCmdCopyBufferToImage(commandBuffer, stagingBuffer, m_image, 
-                              VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL, 1, &region);
-        
-        // Transition to shader-readable
-        barrier.oldLayout = VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL;
-        barrier.newLayout = VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL;
-        barrier.srcAccessMask = VK_ACCESS_TRANSFER_WRITE_BIT;
-        barrier.dstAccessMask = VK_ACCESS_SHADER_READ_BIT;
+                              VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL, static_cast<uint32_t>(regions.size()), regions.data());
         
-        vkCmdPipelineBarrier(commandBuffer, VK_PIPELINE_STAGE_TRANSFER_BIT, 
-                            VK_PIPELINE_STAGE_FRAGMENT_SHADER_BIT, 0, 0, nullptr, 0, nullptr, 1, &barrier);
+        // Transition to shader-readable (released to the graphics queue if uploaded on a transfer queue)
+        heidic_transfer_release_image(commandBuffer, m_image, VK_IMAGE_ASPECT_COLOR_BIT, m_mipmapCount, 1,
+                                      VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL);
         
         endSingleTimeCommands(commandBuffer);
         
@@ -410,18 +430,18 @@ private:
         // Create sampler
         VkSamplerCreateInfo samplerInfo = {};
         samplerInfo.sType = VK_STRUCTURE_TYPE_SAMPLER_CREATE_INFO;
-        samplerInfo.magFilter = VK_FILTER_LINEAR;
-        samplerInfo.minFilter = VK_FILTER_LINEAR;
-        samplerInfo.addressModeU = VK_SAMPLER_ADDRESS_MODE_REPEAT;
-        samplerInfo.addressModeV = VK_SAMPLER_ADDRESS_MODE_REPEAT;
-        samplerInfo.addressModeW = VK_SAMPLER_ADDRESS_MODE_REPEAT;
+        samplerInfo.magFilter = m_options.filter;
+        samplerInfo.minFilter = m_options.filter;
+        samplerInfo.addressModeU = m_options.wrap;
+        samplerInfo.addressModeV = m_options.wrap;
+        samplerInfo.addressModeW = m_options.wrap;
         samplerInfo.anisotropyEnable = VK_FALSE;
         samplerInfo.maxAnisotropy = 1.0f;
         samplerInfo.borderColor = VK_BORDER_COLOR_INT_OPAQUE_BLACK;
         samplerInfo.unnormalizedCoordinates = VK_FALSE;
         samplerInfo.compareEnable = VK_FALSE;
         samplerInfo.compareOp = VK_COMPARE_OP_ALWAYS;
-        samplerInfo.mipmapMode = VK_SAMPLER_MIPMAP_MODE_LINEAR;
+        samplerInfo.mipmapMode = m_options.filter == VK_FILTER_NEAREST ? VK_SAMPLER_MIPMAP_MODE_NEAREST : VK_SAMPLER_MIPMAP_MODE_LINEAR;
         samplerInfo.mipLodBias = 0.0f;
         samplerInfo.minLod = 0.0f;
         samplerInfo.maxLod = static_cast<float>(m_mipmapCount);
@@ -436,9 +456,11 @@ public:
     /**
      * Constructor - Loads texture from file and creates Vulkan resources
      * @param filepath Path to texture file (DDS or PNG)
+     * @param options Sampler filter/wrap and whether to build mips for a PNG
      * @throws std::runtime_error if loading or resource creation fails
      */
-    TextureResource(const std::string& filepath) {
+    TextureResource(const std::string& filepath, const HeidicTextureOptions& options = HeidicTextureOptions())
+        : m_options(options) {
         try {
             // Auto-detect format and load
             if (isDDS(filepath)) {
@@ -464,7 +486,7 @@ public:
           m_sampler(other.m_sampler), m_imageMemory(other.m_imageMemory),
           m_format(other.m_format), m_width(other.m_width), 
           m_height(other.m_height), m_mipmapCount(other.m_mipmapCount),
-          m_loaded(other.m_loaded) {
+          m_loaded(other.m_loaded), m_options(other.m_options) {
         other.m_image = VK_NULL_HANDLE;
         other.m_imageView = VK_NULL_HANDLE;
         other.m_sampler = VK_NULL_HANDLE;
@@ -522,5 +544,11 @@ public:
     }
 };
 
+// Resource<TextureResource> passes a declaration's options to the constructor
+template<>
+struct HeidicResourceOptions<TextureResource> {
+    using type = HeidicTextureOptions;
+};
+
 #endif // EDEN_TEXTURE_RESOURCE_H
 
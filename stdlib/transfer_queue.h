@@ -0,0 +1,50 @@
+// EDEN ENGINE Standard Library - Queue Families and Transfer-Queue Uploads
+// Staging uploads and async streaming run on a dedicated transfer queue when the device has one
+// (a DMA-only queue family), and fall back to the graphics queue otherwise.
+// Uploaded buffers/images are released by the transfer queue; the matching graphics-queue
+// acquire is issued automatically once the transfer is observed complete.
+
+#ifndef EDEN_TRANSFER_QUEUE_H
+#define EDEN_TRANSFER_QUEUE_H
+
+#include "vulkan.h"
+#include <stdint.h>
+
+// Typed handle for a submitted transfer batch (id 0 = nothing submitted, always complete)
+typedef struct HeidicTransferTicket {
+    uint64_t id;
+} HeidicTransferTicket;
+typedef HeidicTransferTicket TransferTicket;  // Name used from HEIDIC code
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Queue family layout chosen at device creation
+uint32_t heidic_graphics_queue_family();
+uint32_t heidic_transfer_queue_family();
+bool heidic_has_dedicated_transfer_queue();
+
+// Manual recording: begin, record copies, release written resources, submit
+VkCommandBuffer heidic_begin_transfer();
+void heidic_transfer_release_buffer(VkCommandBuffer cmd, VkBuffer buffer, VkDeviceSize offset, VkDeviceSize size);
+void heidic_transfer_release_image(VkCommandBuffer cmd, VkImage image, VkImageAspectFlags aspect,
+                                   uint32_t mipLevels, uint32_t layerCount, VkImageLayout finalLayout);
+HeidicTransferTicket heidic_submit_transfer_commands(VkCommandBuffer cmd);
+
+// Async streaming: queue staged uploads, then submit everything queued as one batch
+void heidic_queue_buffer_upload(VkBuffer dst, VkDeviceSize offset, const void* data, VkDeviceSize size);
+HeidicTransferTicket submit_transfer();
+
+// Synchronization (callable from HEIDIC)
+bool transfer_ready(HeidicTransferTicket ticket);  // Non-blocking; finishes the transfer if complete
+void wait_transfer(HeidicTransferTicket ticket);   // Blocks until the transfer completes
+
+// Blocking upload (stage + copy + wait)
+void heidic_upload_buffer(VkBuffer dst, VkDeviceSize offset, const void* data, VkDeviceSize size);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // EDEN_TRANSFER_QUEUE_H
@@ -0,0 +1,204 @@
+// EDEN ENGINE Standard Library - Tweakable Constants
+// `@tweak const GRAVITY: f32 = -9.8;` compiles to an ordinary variable plus an entry in a table
+// of tweaks. tweak_panel() edits the table in an ImGui window; tweak_file(path) loads values
+// from a text file, lets the panel save them back there, and reloads the file when it changes
+// on disk (checked with the other hot-reload checks), so values can be tuned in a running game
+// and kept without recompiling.
+//
+// File format: one `NAME = value` per line; blank lines and lines starting with # are ignored,
+// as are names the program no longer has.
+
+#ifndef EDEN_TWEAK_H
+#define EDEN_TWEAK_H
+
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <cstdlib>
+#include <filesystem>
+#include <fstream>
+#include <sstream>
+#include <string>
+
+#ifdef USE_IMGUI
+#include "imgui.h"
+#endif
+
+enum class HeidicTweakType { I32, I64, F32, F64, Bool };
+
+struct HeidicTweak {
+    const char* name;
+    HeidicTweakType type;
+    void* value;     // The generated variable
+    double initial;  // Value in the source, for Reset
+};
+
+// The table entry's type, from the generated variable's C++ type
+constexpr HeidicTweakType heidic_tweak_type_of(const int32_t&) { return HeidicTweakType::I32; }
+constexpr HeidicTweakType heidic_tweak_type_of(const int64_t&) { return HeidicTweakType::I64; }
+constexpr HeidicTweakType heidic_tweak_type_of(const float&) { return HeidicTweakType::F32; }
+constexpr HeidicTweakType heidic_tweak_type_of(const double&) { return HeidicTweakType::F64; }
+constexpr HeidicTweakType heidic_tweak_type_of(const bool&) { return HeidicTweakType::Bool; }
+
+struct HeidicTweakTable {
+    HeidicTweak* tweaks;
+    size_t count;
+    std::string path;  // Set by tweak_file(); empty when values aren't persisted
+    std::filesystem::file_time_type loaded_time{};
+};
+
+inline void heidic_tweak_set(HeidicTweak& tweak, double value) {
+    switch (tweak.type) {
+        case HeidicTweakType::I32: *static_cast<int32_t*>(tweak.value) = static_cast<int32_t>(value); break;
+        case HeidicTweakType::I64: *static_cast<int64_t*>(tweak.value) = static_cast<int64_t>(value); break;
+        case HeidicTweakType::F32: *static_cast<float*>(tweak.value) = static_cast<float>(value); break;
+        case HeidicTweakType::F64: *static_cast<double*>(tweak.value) = value; break;
+        case HeidicTweakType::Bool: *static_cast<bool*>(tweak.value) = value != 0.0; break;
+    }
+}
+
+inline std::string heidic_tweak_format(const HeidicTweak& tweak) {
+    char buffer[64];
+    switch (tweak.type) {
+        case HeidicTweakType::I32: snprintf(buffer, sizeof(buffer), "%d", *static_cast<int32_t*>(tweak.value)); break;
+        case HeidicTweakType::I64: snprintf(buffer, sizeof(buffer), "%lld", static_cast<long long>(*static_cast<int64_t*>(tweak.value))); break;
+        // The shortest of these that reads back as the same value
+        case HeidicTweakType::F32: {
+            float value = *static_cast<float*>(tweak.value);
+            snprintf(buffer, sizeof(buffer), "%.7g", value);
+            if (strtof(buffer, nullptr) != value) {
+                snprintf(buffer, sizeof(buffer), "%.9g", value);
+            }
+            break;
+        }
+        case HeidicTweakType::F64: {
+            double value = *static_cast<double*>(tweak.value);
+            snprintf(buffer, sizeof(buffer), "%.15g", value);
+            if (strtod(buffer, nullptr) != value) {
+                snprintf(buffer, sizeof(buffer), "%.17g", value);
+            }
+            break;
+        }
+        case HeidicTweakType::Bool: snprintf(buffer, sizeof(buffer), "%s", *static_cast<bool*>(tweak.value) ? "true" : "false"); break;
+    }
+    return buffer;
+}
+
+// Reads `table.path`; returns false if it can't be opened
+inline bool heidic_tweak_load(HeidicTweakTable& table) {
+    std::ifstream file(table.path);
+    if (!file) {
+        return false;
+    }
+    std::error_code error;
+    table.loaded_time = std::filesystem::last_write_time(table.path, error);
+    std::string line;
+    int line_number = 0;
+    while (std::getline(file, line)) {
+        line_number++;
+        size_t start = line.find_first_not_of(" \t\r");
+        if (start == std::string::npos || line[start] == '#') {
+            continue;
+        }
+        size_t equals = line.find('=');
+        if (equals == std::string::npos) {
+            fprintf(stderr, "[tweak] %s:%d: expected NAME = value\n", table.path.c_str(), line_number);
+            continue;
+        }
+        std::string name, text;
+        std::istringstream(line.substr(0, equals)) >> name;
+        std::istringstream(line.substr(equals + 1)) >> text;
+        for (size_t i = 0; i < table.count; i++) {
+            HeidicTweak& tweak = table.tweaks[i];
+            if (name != tweak.name) {
+                continue;
+            }
+            if (text == "true" || text == "false") {
+                heidic_tweak_set(tweak, text == "true" ? 1.0 : 0.0);
+            } else {
+                char* end = nullptr;
+                double value = strtod(text.c_str(), &end);
+                if (text.empty() || *end != '\0') {
+                    fprintf(stderr, "[tweak] %s:%d: '%s' isn't a number\n", table.path.c_str(), line_number, text.c_str());
+                } else {
+                    heidic_tweak_set(tweak, value);
+                }
+            }
+        }
+    }
+    return true;
+}
+
+inline bool heidic_tweak_save(HeidicTweakTable& table) {
+    std::ofstream file(table.path);
+    if (!file) {
+        fprintf(stderr, "[tweak] Can't write %s\n", table.path.c_str());
+        return false;
+    }
+    file << "# Tweakable constants (@tweak); edit while the program runs\n";
+    for (size_t i = 0; i < table.count; i++) {
+        file << table.tweaks[i].name << " = " << heidic_tweak_format(table.tweaks[i]) << "\n";
+    }
+    file.close();
+    // Our own write isn't a change to reload
+    std::error_code error;
+    table.loaded_time = std::filesystem::last_write_time(table.path, error);
+    return true;
+}
+
+// tweak_file(path): persist values in `path`, loading it now if it exists
+inline bool heidic_tweak_use_file(HeidicTweakTable& table, const std::string& path) {
+    table.path = path;
+    return heidic_tweak_load(table);
+}
+
+// Hot reload: load the file again when its modification time changes
+inline void heidic_tweak_reload(HeidicTweakTable& table) {
+    if (table.path.empty()) {
+        return;
+    }
+    std::error_code error;
+    auto time = std::filesystem::last_write_time(table.path, error);
+    if (!error && time != table.loaded_time && heidic_tweak_load(table)) {
+        printf("[tweak] Reloaded %s\n", table.path.c_str());
+    }
+}
+
+// One ImGui window with a widget per tweak (a no-op without USE_IMGUI)
+inline void heidic_tweak_panel(HeidicTweakTable& table) {
+#ifdef USE_IMGUI
+    if (!ImGui::Begin("Tweaks")) {
+        ImGui::End();
+        return;
+    }
+    for (size_t i = 0; i < table.count; i++) {
+        HeidicTweak& tweak = table.tweaks[i];
+        ImGui::PushID(static_cast<int>(i));
+        switch (tweak.type) {
+            case HeidicTweakType::I32: ImGui::DragScalar(tweak.name, ImGuiDataType_S32, tweak.value, 1.0f); break;
+            case HeidicTweakType::I64: ImGui::DragScalar(tweak.name, ImGuiDataType_S64, tweak.value, 1.0f); break;
+            case HeidicTweakType::F32: ImGui::DragScalar(tweak.name, ImGuiDataType_Float, tweak.value, 0.01f); break;
+            case HeidicTweakType::F64: ImGui::DragScalar(tweak.name, ImGuiDataType_Double, tweak.value, 0.01f); break;
+            case HeidicTweakType::Bool: ImGui::Checkbox(tweak.name, static_cast<bool*>(tweak.value)); break;
+        }
+        ImGui::SameLine();
+        if (ImGui::SmallButton("Reset")) {
+            heidic_tweak_set(tweak, tweak.initial);
+        }
+        ImGui::PopID();
+    }
+    if (!table.path.empty()) {
+        ImGui::Separator();
+        if (ImGui::Button("Save")) {
+            heidic_tweak_save(table);
+        }
+        ImGui::SameLine();
+        ImGui::TextUnformatted(table.path.c_str());
+    }
+    ImGui::End();
+#else
+    (void)table;
+#endif
+}
+
+#endif // EDEN_TWEAK_H
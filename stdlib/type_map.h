@@ -0,0 +1,20 @@
+// EDEN ENGINE Standard Library - Maps keyed by component type
+// HeidicTypeMap is std::unordered_map, or under --runtime minimal (HEIDIC_MINIMAL_RUNTIME) a
+// fixed-capacity map of HEIDIC_MAX_COMPONENT_TYPES entries (minimal_runtime.h)
+
+#ifndef EDEN_TYPE_MAP_H
+#define EDEN_TYPE_MAP_H
+
+#ifdef HEIDIC_MINIMAL_RUNTIME
+#include "minimal_runtime.h"
+
+template <typename K, typename V>
+using HeidicTypeMap = HeidicFixedMap<K, V, HEIDIC_MAX_COMPONENT_TYPES>;
+#else
+#include <unordered_map>
+
+template <typename K, typename V>
+using HeidicTypeMap = std::unordered_map<K, V>;
+#endif
+
+#endif // EDEN_TYPE_MAP_H
@@ -0,0 +1,34 @@
+// EDEN ENGINE Standard Library - Uniform buffers
+// Generated code declares one HeidicUniformBuffer per `uniform Name { ... }` block, sized for the
+// block's std140 struct. The buffer is host-visible and stays mapped, so update_<name>(value) is a
+// plain copy the next submitted draw sees. Frames already in flight see it too: programs that keep
+// several frames in flight should update between frames.
+
+#ifndef EDEN_UNIFORM_BUFFER_H
+#define EDEN_UNIFORM_BUFFER_H
+
+#include "vulkan.h"
+#include <stdint.h>
+
+typedef struct HeidicUniformBuffer {
+    const char* name;       // Uniform block name (for diagnostics)
+    VkDeviceSize size;      // sizeof the block's struct
+    VkBuffer buffer;        // Host-visible uniform buffer (VK_NULL_HANDLE until first used)
+    VkDeviceMemory memory;
+    void* mapped;           // Persistently mapped memory
+} HeidicUniformBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// The buffer, created and mapped on first use; VK_NULL_HANDLE before the Vulkan device exists
+VkBuffer heidic_uniform_buffer_handle(HeidicUniformBuffer* buffer);
+// Copies the block's size bytes from data into the buffer
+void heidic_uniform_buffer_write(HeidicUniformBuffer* buffer, const void* data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // EDEN_UNIFORM_BUFFER_H
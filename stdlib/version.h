@@ -0,0 +1,16 @@
+// EDEN ENGINE Standard Library - Version
+// Generated code checks these against the version its compiler was built for (static_assert
+// right after this header is included), so a stdlib/ directory from another engine release
+// fails the C++ build with a readable message instead of undefined symbols or silent ABI drift.
+//
+// Bump MAJOR when a declaration generated code relies on changes or disappears; bump MINOR
+// when adding headers or functions. Generated code accepts the same MAJOR with a MINOR at
+// least as new as the one it was generated for.
+
+#ifndef EDEN_VERSION_H
+#define EDEN_VERSION_H
+
+#define HEIDIC_STDLIB_VERSION_MAJOR 1
+#define HEIDIC_STDLIB_VERSION_MINOR 34
+
+#endif // EDEN_VERSION_H
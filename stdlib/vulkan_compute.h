@@ -0,0 +1,50 @@
+// EDEN ENGINE Standard Library - Vulkan compute kernels (--gpu-backend=vulkan)
+// With --gpu-backend=vulkan each @[launch(kernel = name)] function is a GLSL compute shader,
+// compute_shaders/<name>.comp (built to .comp.spv), instead of a CUDA kernel. Its launch function
+// uploads the query's arrays into HeidicGpuBuffers (stdlib/gpu_buffer.h), one storage buffer
+// binding each in query order, fills the kernel's push constants (the entity count, then its
+// parameters) and calls heidic_compute_dispatch, then reads the arrays back.
+//
+// The first dispatch of a kernel loads its SPIR-V and creates the pipeline and descriptor set on
+// the engine's device; dispatches run on the graphics queue, so a readback waits for them. A
+// kernel whose pipeline can't be created reports it once and is skipped from then on.
+
+#ifndef EDEN_VULKAN_COMPUTE_H
+#define EDEN_VULKAN_COMPUTE_H
+
+#include "gpu_buffer.h"
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
+// Invocations per workgroup: the local_size_x of every generated compute shader
+#define HEIDIC_COMPUTE_GROUP_SIZE 256
+
+typedef struct HeidicComputeKernel {
+    const char* name;          // Kernel name (for diagnostics)
+    const char* spirv_path;    // compute_shaders/<name>.comp.spv
+    uint32_t buffer_count;     // Storage buffers, at bindings 0 .. buffer_count - 1
+    uint32_t push_size;        // Bytes of push constants
+    void* state;               // Pipeline, descriptor set and fence; created by the first dispatch
+} HeidicComputeKernel;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Binds `buffers` (kernel->buffer_count of them), pushes `push` and dispatches enough workgroups
+// for `count` invocations (each loops over the rest); returns false if nothing was dispatched
+bool heidic_compute_dispatch(HeidicComputeKernel* kernel, HeidicGpuBuffer* const* buffers, const void* push, size_t count);
+
+#ifdef __cplusplus
+}
+
+// The host memory behind a query array: a std::vector's elements, or a plain pointer
+template <typename T>
+T* heidic_compute_host(std::vector<T>& items) { return items.data(); }
+
+template <typename T>
+T* heidic_compute_host(T* items) { return items; }
+#endif
+
+#endif // EDEN_VULKAN_COMPUTE_H
@@ -0,0 +1,177 @@
+// EDEN ENGINE Standard Library - WebGPU Renderer (--target=wasm)
+// The renderer functions HEIDIC programs declare as extern fns (heidic_init_renderer,
+// heidic_render_frame, heidic_cleanup_renderer, ...) and stdlib/window_config.h's window functions,
+// for a browser build. They draw the native renderer's spinning triangle with WebGPU on the page's
+// canvas, through the device the page harness (<file>.html) requests before the program starts
+// (Module.heidicGpuDevice); without one, heidic_init_renderer() returns 0. Parameter types follow
+// the extern fn declarations generated code repeats (i32 is int32_t).
+
+#ifndef EDEN_WEBGPU_H
+#define EDEN_WEBGPU_H
+
+#include "canvas.h"
+#include "window_config.h"
+
+EM_JS(int, heidic_webgpu_init, (void), {
+    const device = Module.heidicGpuDevice;
+    const canvas = Module.canvas;
+    if (!device || !canvas || !navigator.gpu) {
+        return 0;
+    }
+    const context = canvas.getContext('webgpu');
+    const format = navigator.gpu.getPreferredCanvasFormat();
+    context.configure({ device: device, format: format, alphaMode: 'opaque' });
+    const module = device.createShaderModule({ code: `
+        @group(0) @binding(0) var<uniform> frame: vec4f;
+        struct VertexOut {
+            @builtin(position) position: vec4f,
+            @location(0) color: vec3f,
+        };
+        @vertex fn vs(@builtin(vertex_index) i: u32) -> VertexOut {
+            var corners = array<vec2f, 3>(vec2f(0.0, 0.5), vec2f(-0.5, -0.5), vec2f(0.5, -0.5));
+            var colors = array<vec3f, 3>(vec3f(1.0, 0.0, 0.0), vec3f(0.0, 1.0, 0.0), vec3f(0.0, 0.0, 1.0));
+            let c = cos(frame.x);
+            let s = sin(frame.x);
+            let p = corners[i];
+            var out: VertexOut;
+            out.position = vec4f(p.x * c - p.y * s, p.x * s + p.y * c, 0.0, 1.0);
+            out.color = colors[i];
+            return out;
+        }
+        @fragment fn fs(v: VertexOut) -> @location(0) vec4f {
+            return vec4f(v.color, 1.0);
+        }
+    ` });
+    const pipeline = device.createRenderPipeline({
+        layout: 'auto',
+        vertex: { module: module, entryPoint: 'vs' },
+        fragment: { module: module, entryPoint: 'fs', targets: [{ format: format }] },
+        primitive: { topology: 'triangle-list' },
+    });
+    const uniforms = device.createBuffer({ size: 16, usage: GPUBufferUsage.UNIFORM | GPUBufferUsage.COPY_DST });
+    const bindGroup = device.createBindGroup({
+        layout: pipeline.getBindGroupLayout(0),
+        entries: [{ binding: 0, resource: { buffer: uniforms } }],
+    });
+    Module.heidicRenderer = { device: device, context: context, pipeline: pipeline, uniforms: uniforms, bindGroup: bindGroup };
+    return 1;
+});
+
+EM_JS(void, heidic_webgpu_draw, (float angle), {
+    const r = Module.heidicRenderer;
+    if (!r) {
+        return;
+    }
+    r.device.queue.writeBuffer(r.uniforms, 0, new Float32Array([angle, 0, 0, 0]));
+    const encoder = r.device.createCommandEncoder();
+    const pass = encoder.beginRenderPass({
+        colorAttachments: [{
+            view: r.context.getCurrentTexture().createView(),
+            clearValue: { r: 0.1, g: 0.1, b: 0.15, a: 1.0 },
+            loadOp: 'clear',
+            storeOp: 'store',
+        }],
+    });
+    pass.setPipeline(r.pipeline);
+    pass.setBindGroup(0, r.bindGroup);
+    pass.draw(3);
+    pass.end();
+    r.device.queue.submit([encoder.finish()]);
+});
+
+EM_JS(void, heidic_webgpu_shutdown, (void), {
+    if (Module.heidicRenderer) {
+        Module.heidicRenderer.context.unconfigure();
+        Module.heidicRenderer = null;
+    }
+});
+
+namespace heidic_webgpu {
+
+struct Renderer {
+    float rotation_speed = 1.0f;  // Radians per second
+    float angle = 0.0f;
+    double last_frame = -1.0;
+};
+
+inline Renderer& renderer() {
+    static Renderer state;
+    return state;
+}
+
+inline HeidicWindowConfig& window_config() {
+    static HeidicWindowConfig config = {"HEIDIC", 800, 600, 1, 0};
+    return config;
+}
+
+}  // namespace heidic_webgpu
+
+extern "C" {
+
+// Native code picks GLFW_NO_API for Vulkan here; the canvas needs nothing
+inline void heidic_glfw_vulkan_hints() {}
+
+inline int32_t heidic_init_renderer(GLFWwindow* window) {
+    heidic_webgpu::renderer().last_frame = -1.0;
+    return window ? heidic_webgpu_init() : 0;
+}
+
+inline void heidic_render_frame(GLFWwindow*) {
+    heidic_webgpu::Renderer& r = heidic_webgpu::renderer();
+    double now = glfwGetTime();
+    if (r.last_frame >= 0.0) {
+        r.angle += r.rotation_speed * static_cast<float>(now - r.last_frame);
+    }
+    r.last_frame = now;
+    heidic_webgpu_draw(r.angle);
+}
+
+inline void heidic_cleanup_renderer() {
+    heidic_webgpu_shutdown();
+}
+
+// The canvas context sizes its textures from the canvas every frame: there's no swapchain to rebuild
+inline int32_t heidic_recreate_swapchain(GLFWwindow* window) {
+    return window ? 1 : 0;
+}
+
+inline void heidic_set_rotation_speed(float speed) {
+    heidic_webgpu::renderer().rotation_speed = speed;
+}
+
+// Sleeping yields to the browser, so the page keeps running input callbacks meanwhile
+inline void heidic_sleep_ms(int32_t milliseconds) {
+    emscripten_sleep(milliseconds > 0 ? static_cast<unsigned int>(milliseconds) : 0);
+}
+
+#ifndef EDEN_WINDOW_CONFIG_STUB  // --gen-stdlib-stubs wrote window_config.h with no-op functions
+
+// The window's title and size apply to the page and canvas; vsync and resizing are the browser's
+inline void heidic_set_window_config(const HeidicWindowConfig* config) {
+    heidic_webgpu::window_config() = *config;
+}
+
+inline GLFWwindow* heidic_open_window(void) {
+    const HeidicWindowConfig& config = heidic_webgpu::window_config();
+    if (!glfwInit()) {
+        return nullptr;
+    }
+    GLFWwindow* window = glfwCreateWindow(config.width, config.height, config.title, nullptr, nullptr);
+    if (!heidic_init_renderer(window)) {
+        glfwTerminate();
+        return nullptr;
+    }
+    return window;
+}
+
+inline void heidic_close_window(GLFWwindow* window) {
+    heidic_cleanup_renderer();
+    glfwDestroyWindow(window);
+    glfwTerminate();
+}
+
+#endif // EDEN_WINDOW_CONFIG_STUB
+
+}  // extern "C"
+
+#endif // EDEN_WEBGPU_H
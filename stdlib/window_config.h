@@ -0,0 +1,39 @@
+// EDEN ENGINE Standard Library - Window and Swapchain Configuration
+// Generated code fills a HeidicWindowConfig from a HEIDIC `window { ... }` block and registers it
+// at startup. heidic_open_window() then initializes GLFW, creates the window and sets up the
+// renderer (device, swapchain, depth buffer, framebuffers) for it; a resizable window's swapchain
+// is rebuilt by heidic_render_frame() whenever its size changes.
+
+#ifndef EDEN_WINDOW_CONFIG_H
+#define EDEN_WINDOW_CONFIG_H
+
+#include <stdint.h>
+
+#include "glfw.h"
+
+typedef struct HeidicWindowConfig {
+    const char* title;
+    int32_t width;       // Initial size in screen coordinates
+    int32_t height;
+    int32_t vsync;       // 1: FIFO presentation; 0: mailbox, or immediate when mailbox is unsupported
+    int32_t resizable;   // 1: the window can be resized and the swapchain follows it
+} HeidicWindowConfig;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Register the window configuration (must be called before heidic_open_window)
+void heidic_set_window_config(const HeidicWindowConfig* config);
+
+// Create the configured window and its renderer; null (with GLFW terminated) on failure
+GLFWwindow* heidic_open_window(void);
+
+// Destroy the renderer and the window, and terminate GLFW
+void heidic_close_window(GLFWwindow* window);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // EDEN_WINDOW_CONFIG_H
@@ -0,0 +1,89 @@
+// heidic_v2 check --error-format=json: one plain-text JSON diagnostic per line
+
+mod common;
+
+use std::fs;
+
+#[test]
+fn json_diagnostics_hold_no_terminal_escapes() {
+    let dir = common::scratch_dir("check_json");
+    fs::write(dir.join("scope.hd"), "fn main(): void {\n    if true {\n        let a: i32 = 1;\n    }\n    let b: i32 = a;\n}\n")
+        .expect("write fixture");
+    let output = common::heidic(&["check", "--error-format=json", "scope.hd"], &dir);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains('\u{1b}'), "escape sequence in JSON output:\n{}", stdout);
+    let undefined = stdout.lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("each line is a JSON object"))
+        .find(|diagnostic| diagnostic["code"] == "E0001")
+        .expect("E0001 for the out-of-scope variable");
+    assert!(undefined["suggestion"].as_str().unwrap().starts_with("Variable 'a' was declared at line 3"));
+}
+
+#[test]
+fn unknown_and_repeated_fields_have_codes() {
+    let dir = common::scratch_dir("check_fields");
+    fs::write(dir.join("fields.hd"), concat!(
+        "struct Point {\n    x: f32,\n    y: f32\n}\n\n",
+        "fn main(): void {\n",
+        "    let p = Point { x: 1.0, z: 2.0 };\n",
+        "    let q = Point { x: 1.0, y: 2.0, x: 3.0 };\n",
+        "}\n",
+    )).expect("write fixture");
+    let output = common::heidic(&["check", "--error-format=json", "fields.hd"], &dir);
+    let codes: Vec<(u64, String)> = String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("each line is a JSON object"))
+        .filter(|diagnostic| diagnostic["severity"] == "error")
+        .map(|diagnostic| (diagnostic["line"].as_u64().unwrap(), diagnostic["code"].as_str().unwrap_or("").to_string()))
+        .collect();
+    assert_eq!(codes, [(7, "E0029".to_string()), (8, "E0030".to_string())]);
+}
+
+#[test]
+fn match_value_without_a_catch_all_has_a_code() {
+    let dir = common::scratch_dir("check_match");
+    fs::write(dir.join("label.hd"), concat!(
+        "fn label(count: i32): string {\n",
+        "    return match count {\n",
+        "        0 => { \"none\" }\n",
+        "        1 => { \"one\" }\n",
+        "    };\n",
+        "}\n\n",
+        "fn main(): void {\n    print(label(1));\n}\n",
+    )).expect("write fixture");
+    let output = common::heidic(&["check", "--error-format=json", "label.hd"], &dir);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let diagnostic: serde_json::Value = serde_json::from_str(stdout.lines().next().expect("a diagnostic")).expect("JSON diagnostic");
+    assert_eq!(diagnostic["code"], "E0031");
+    assert_eq!(diagnostic["line"], 2);
+}
+
+#[test]
+fn lowercase_type_names_are_rejected_at_their_declaration() {
+    let dir = common::scratch_dir("check_type_name");
+    fs::write(dir.join("point.hd"), "struct point {\n    x: f32\n}\n\nfn main(): void {\n    print(1);\n}\n")
+        .expect("write fixture");
+    let output = common::heidic(&["check", "--error-format=json", "point.hd"], &dir);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let diagnostic: serde_json::Value = serde_json::from_str(stdout.lines().next().expect("a diagnostic")).expect("JSON diagnostic");
+    assert_eq!(diagnostic["line"], 1);
+    assert!(diagnostic["message"].as_str().unwrap().contains("'point' must start with an uppercase letter"));
+    assert!(diagnostic["suggestion"].as_str().unwrap().starts_with("Rename it to 'Point'"));
+}
+
+#[test]
+fn one_bad_expression_reports_one_error() {
+    let dir = common::scratch_dir("check_cascade");
+    // The negation and the later use of `speed` would each report again without error recovery
+    fs::write(dir.join("cascade.hd"), "fn main(): void {\n    let speed = -!undefined_rate;\n    print(speed * 2.0);\n}\n")
+        .expect("write fixture");
+    let output = common::heidic(&["check", "--error-format=json", "cascade.hd"], &dir);
+    assert!(!output.status.success());
+    let errors: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("each line is a JSON object"))
+        .filter(|diagnostic| diagnostic["severity"] == "error")
+        .collect();
+    assert_eq!(errors.len(), 1, "expected only the undefined variable:\n{:#?}", errors);
+    assert_eq!(errors[0]["code"], "E0001");
+}
@@ -0,0 +1,104 @@
+#include <iostream>
+#include <vector>
+#include <string>
+#include <unordered_map>
+#include <memory>
+#include <cmath>
+#include <cstdint>
+#include <optional>
+#include <functional>
+
+// EDEN ENGINE Standard Library
+#if __has_include("stdlib/version.h")
+#include "stdlib/version.h"
+static_assert(HEIDIC_STDLIB_VERSION_MAJOR == 1, "stdlib/ major version mismatch: generated code (heidic 0.1.0) requires stdlib 1.34; use the stdlib/ shipped with this compiler");
+static_assert(HEIDIC_STDLIB_VERSION_MINOR >= 34, "stdlib/ is older than generated code (heidic 0.1.0) requires (stdlib 1.34); update stdlib/");
+#else
+#error "stdlib/version.h not found: this stdlib/ predates version checks; generated code (heidic 0.1.0) requires stdlib 1.34"
+#endif
+#include "stdlib/vulkan.h"
+#include "stdlib/glfw.h"
+#include "stdlib/math.h"
+#include "stdlib/imgui.h"
+#include "stdlib/transfer_queue.h"
+
+// Defer statement support
+// Note: Defer expressions should not throw exceptions.
+// If a defer expression throws during stack unwinding, std::terminate is called.
+template<typename F>
+class DeferHelper {
+    F f;
+public:
+    DeferHelper(F&& func) : f(std::forward<F>(func)) {}
+    ~DeferHelper() noexcept { f(); }
+    DeferHelper(const DeferHelper&) = delete;
+    DeferHelper& operator=(const DeferHelper&) = delete;
+};
+template<typename F>
+DeferHelper<F> make_defer(F&& f) {
+    return DeferHelper<F>(std::forward<F>(f));
+}
+
+// Constants
+constexpr int32_t SCALE = 3;
+
+struct Point {
+        int32_t x;
+        int32_t y;
+};
+
+
+Point scaled(Point p);
+std::string classify(int32_t n);
+int hd_basics_main();
+
+Point scaled(Point p) {
+        return Point {.x = (p.x * SCALE), .y = (p.y * SCALE)};
+}
+
+std::string classify(int32_t n) {
+        return [&]() {
+    auto heidic_match = n;
+    switch (heidic_match) {
+        case 0: {
+            return std::string("zero");
+        }
+        case 1: {
+            return std::string("one");
+        }
+        default: {
+            return std::string("many");
+        }
+    }
+}();
+}
+
+int hd_basics_main() {
+        int32_t total = 0;
+        int32_t i = 0;
+        while ((i < 4)) {
+            const auto p = scaled(Point {.x = i, .y = (i + 1)});
+            total = ((total + p.x) + p.y);
+            i = (i + 1);
+        }
+        {
+            const auto doubled = (total * 2);
+            std::cout << doubled << std::endl;
+        }
+        const auto offset = [&]() {
+    const auto base = (total + 1);
+    return (base * SCALE);
+}();
+        total = (total + offset);
+        if ((total > 10)) {
+            std::cout << classify(total) << std::endl;
+        } else {
+            std::cout << total << std::endl;
+        }
+        return 0;
+}
+
+int main(int argc, char* argv[]) {
+    hd_basics_main();
+    return 0;
+}
@@ -0,0 +1,33 @@
+// C99 (heidic_v2 --backend=c)
+#include <inttypes.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+// Structs
+typedef struct Counter Counter;
+
+struct Counter {
+    int32_t value;
+};
+
+// Functions
+Counter bump(Counter c, int32_t by);
+int hd_c_backend_main(void);
+
+Counter bump(Counter c, int32_t by) {
+    return (Counter){ .value = (c.value + by) };
+}
+
+int hd_c_backend_main(void) {
+    Counter c = bump((Counter){ .value = 1 }, 41);
+    printf("%" PRId32 "\n", c.value);
+    return 0;
+}
+
+int main(void) {
+    hd_c_backend_main();
+    return 0;
+}
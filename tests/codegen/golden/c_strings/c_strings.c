@@ -0,0 +1,40 @@
+// C99 (heidic_v2 --backend=c)
+#include <inttypes.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+// Functions
+int32_t label(const char* name);
+int hd_c_strings_main(void);
+
+int32_t label(const char* name) {
+    int32_t heidic_result_1;
+    {
+        const char* heidic_match_2 = name;
+        if (strcmp(heidic_match_2, "tab\there") == 0) {
+            heidic_result_1 = 1;
+        } else if (strcmp(heidic_match_2, "say \"hi\"") == 0) {
+            heidic_result_1 = 2;
+        } else {
+            heidic_result_1 = 0;
+        }
+    }
+    return heidic_result_1;
+}
+
+int hd_c_strings_main(void) {
+    const char* greeting = "line one\nline two";
+    printf("a\tb\n\n");
+    printf("quote: \"100%%\"\n\n");
+    printf("%s\n", greeting);
+    printf("%" PRId32 "\n", label("tab\there"));
+    return 0;
+}
+
+int main(void) {
+    hd_c_strings_main();
+    return 0;
+}
@@ -0,0 +1,59 @@
+#include <iostream>
+#include <vector>
+#include <string>
+#include <unordered_map>
+#include <memory>
+#include <cmath>
+#include <cstdint>
+#include <optional>
+#include <functional>
+
+// EDEN ENGINE Standard Library
+#if __has_include("stdlib/version.h")
+#include "stdlib/version.h"
+static_assert(HEIDIC_STDLIB_VERSION_MAJOR == 1, "stdlib/ major version mismatch: generated code (heidic 0.1.0) requires stdlib 1.34; use the stdlib/ shipped with this compiler");
+static_assert(HEIDIC_STDLIB_VERSION_MINOR >= 34, "stdlib/ is older than generated code (heidic 0.1.0) requires (stdlib 1.34); update stdlib/");
+#else
+#error "stdlib/version.h not found: this stdlib/ predates version checks; generated code (heidic 0.1.0) requires stdlib 1.34"
+#endif
+#include "stdlib/vulkan.h"
+#include "stdlib/glfw.h"
+#include "stdlib/math.h"
+#include "stdlib/imgui.h"
+#include "stdlib/transfer_queue.h"
+#include "stdlib/checked_arithmetic.h"
+
+// Defer statement support
+// Note: Defer expressions should not throw exceptions.
+// If a defer expression throws during stack unwinding, std::terminate is called.
+template<typename F>
+class DeferHelper {
+    F f;
+public:
+    DeferHelper(F&& func) : f(std::forward<F>(func)) {}
+    ~DeferHelper() noexcept { f(); }
+    DeferHelper(const DeferHelper&) = delete;
+    DeferHelper& operator=(const DeferHelper&) = delete;
+};
+template<typename F>
+DeferHelper<F> make_defer(F&& f) {
+    return DeferHelper<F>(std::forward<F>(f));
+}
+
+
+int32_t area(int32_t w, int32_t h);
+int hd_checked_main();
+
+int32_t area(int32_t w, int32_t h) {
+        return heidic_checked_sub(heidic_checked_mul(w, h, "checked.hd:3:14"), heidic_checked_neg(w, "checked.hd:3:20"), "checked.hd:3:18");
+}
+
+int hd_checked_main() {
+        std::cout << area(6, 7) << std::endl;
+        return 0;
+}
+
+int main(int argc, char* argv[]) {
+    hd_checked_main();
+    return 0;
+}
@@ -0,0 +1,189 @@
+#include <iostream>
+#include <vector>
+#include <string>
+#include <unordered_map>
+#include <memory>
+#include <cmath>
+#include <cstdint>
+#include <optional>
+#include <functional>
+
+// EDEN ENGINE Standard Library
+#if __has_include("stdlib/version.h")
+#include "stdlib/version.h"
+static_assert(HEIDIC_STDLIB_VERSION_MAJOR == 1, "stdlib/ major version mismatch: generated code (heidic 0.1.0) requires stdlib 1.34; use the stdlib/ shipped with this compiler");
+static_assert(HEIDIC_STDLIB_VERSION_MINOR >= 34, "stdlib/ is older than generated code (heidic 0.1.0) requires (stdlib 1.34); update stdlib/");
+#else
+#error "stdlib/version.h not found: this stdlib/ predates version checks; generated code (heidic 0.1.0) requires stdlib 1.34"
+#endif
+#include "stdlib/vulkan.h"
+#include "stdlib/glfw.h"
+#include "stdlib/math.h"
+#include "stdlib/imgui.h"
+#include "stdlib/transfer_queue.h"
+#include "stdlib/entity_storage.h"
+
+// Defer statement support
+// Note: Defer expressions should not throw exceptions.
+// If a defer expression throws during stack unwinding, std::terminate is called.
+template<typename F>
+class DeferHelper {
+    F f;
+public:
+    DeferHelper(F&& func) : f(std::forward<F>(func)) {}
+    ~DeferHelper() noexcept { f(); }
+    DeferHelper(const DeferHelper&) = delete;
+    DeferHelper& operator=(const DeferHelper&) = delete;
+};
+template<typename F>
+DeferHelper<F> make_defer(F&& f) {
+    return DeferHelper<F>(std::forward<F>(f));
+}
+
+struct Position {
+        float x;
+        float y;
+};
+
+struct Velocity {
+        float x;
+        float y;
+};
+
+struct Query_Position_Velocity {
+    std::vector<EntityId> entities;
+    std::vector<Position> positions;
+    std::vector<Velocity> velocities;
+    size_t size() const { return entities.size(); }
+};
+
+// Component Registry and Reflection
+#include "stdlib/component_registry.h"
+
+// Component Metadata: Position
+template<>
+struct ComponentMetadata<Position> {
+    static constexpr const char* name() { return "Position"; }
+    static uint32_t id() { return component_id<Position>(); }
+    static constexpr size_t size() { return 8; }
+    static constexpr size_t alignment() { return 4; }
+    static constexpr bool is_soa() { return false; }
+    static constexpr const char* doc() { return ""; }
+};
+
+// Field Reflection Data: Position
+template<>
+struct ComponentFields<Position> {
+    static constexpr size_t field_count = 2;
+    struct FieldInfo {
+        const char* name;
+        const char* type_name;
+        size_t offset;
+        size_t size;
+        const char* doc;  // `///` comment above the field, or ""
+        const char* unit;  // @[unit("...")], or ""
+        bool has_range;  // @[range(min, max)]
+        double range_min;
+        double range_max;
+    };
+    static const FieldInfo* get_fields() {
+        static FieldInfo fields[] = {
+            { "x", "float", 0, 4, "", "", false, 0.0, 0.0 },
+            { "y", "float", 4, 4, "", "", false, 0.0, 0.0 },
+        };
+        return fields;
+    }
+};
+static_assert(sizeof(Position) == 8, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+static_assert(alignof(Position) == 4, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+static_assert(offsetof(Position, x) == 0, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+static_assert(offsetof(Position, y) == 4, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+
+// Component Metadata: Velocity
+template<>
+struct ComponentMetadata<Velocity> {
+    static constexpr const char* name() { return "Velocity"; }
+    static uint32_t id() { return component_id<Velocity>(); }
+    static constexpr size_t size() { return 8; }
+    static constexpr size_t alignment() { return 4; }
+    static constexpr bool is_soa() { return false; }
+    static constexpr const char* doc() { return ""; }
+};
+
+// Field Reflection Data: Velocity
+template<>
+struct ComponentFields<Velocity> {
+    static constexpr size_t field_count = 2;
+    struct FieldInfo {
+        const char* name;
+        const char* type_name;
+        size_t offset;
+        size_t size;
+        const char* doc;  // `///` comment above the field, or ""
+        const char* unit;  // @[unit("...")], or ""
+        bool has_range;  // @[range(min, max)]
+        double range_min;
+        double range_max;
+    };
+    static const FieldInfo* get_fields() {
+        static FieldInfo fields[] = {
+            { "x", "float", 0, 4, "", "", false, 0.0, 0.0 },
+            { "y", "float", 4, 4, "", "", false, 0.0, 0.0 },
+        };
+        return fields;
+    }
+};
+static_assert(sizeof(Velocity) == 8, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+static_assert(alignof(Velocity) == 4, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+static_assert(offsetof(Velocity, x) == 0, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+static_assert(offsetof(Velocity, y) == 4, "HEIDIC layout model disagrees with the C++ compiler (check --target-abi)");
+
+// Component Registry Initialization
+void hd_ecs_register_all_components() {
+    ComponentRegistry::register_component<Position>();
+    ComponentRegistry::register_component<Velocity>();
+}
+
+
+void integrate(Query_Position_Velocity& q);
+int hd_ecs_main();
+
+// ECS storage
+static EntityStorage hd_ecs_storage;
+
+static inline Query_Position_Velocity heidic_gather_Query_Position_Velocity() {
+    Query_Position_Velocity query;
+    hd_ecs_storage.for_each<Position>([&](EntityId entity, const auto& value) {
+        if (!hd_ecs_storage.has_component<Velocity>(entity)) return;
+        query.entities.push_back(entity);
+        query.positions.push_back(value);
+        query.velocities.push_back(*hd_ecs_storage.get_component<Velocity>(entity));
+    });
+    return query;
+}
+
+static inline void heidic_commit(const Query_Position_Velocity& query) {
+    for (size_t i = 0; i < query.size(); ++i) {
+        if (Position* value = hd_ecs_storage.get_component<Position>(query.entities[i])) *value = query.positions[i];
+        if (Velocity* value = hd_ecs_storage.get_component<Velocity>(query.entities[i])) *value = query.velocities[i];
+    }
+}
+
+void integrate(Query_Position_Velocity& q) {
+        // Query iteration: for entity in q
+        for (size_t entity_index = 0; entity_index < q.size(); ++entity_index) {
+            q.positions[entity_index].x = (q.positions[entity_index].x + (q.velocities[entity_index].x * 0.016f));
+            q.positions[entity_index].y = (q.positions[entity_index].y + (q.velocities[entity_index].y * 0.016f));
+        }
+}
+
+int hd_ecs_main() {
+        std::cout << "ecs\n" << std::endl;
+        return 0;
+}
+
+int main(int argc, char* argv[]) {
+    hd_ecs_register_all_components();
+    hd_ecs_main();
+    return 0;
+}
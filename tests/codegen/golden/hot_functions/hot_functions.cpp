@@ -0,0 +1,275 @@
+#include <iostream>
+#include <vector>
+#include <string>
+#include <unordered_map>
+#include <memory>
+#include <cmath>
+#include <cstdint>
+#include <optional>
+#include <functional>
+#include <chrono>
+
+// EDEN ENGINE Standard Library
+#if __has_include("stdlib/version.h")
+#include "stdlib/version.h"
+static_assert(HEIDIC_STDLIB_VERSION_MAJOR == 1, "stdlib/ major version mismatch: generated code (heidic 0.1.0) requires stdlib 1.34; use the stdlib/ shipped with this compiler");
+static_assert(HEIDIC_STDLIB_VERSION_MINOR >= 34, "stdlib/ is older than generated code (heidic 0.1.0) requires (stdlib 1.34); update stdlib/");
+#else
+#error "stdlib/version.h not found: this stdlib/ predates version checks; generated code (heidic 0.1.0) requires stdlib 1.34"
+#endif
+#include "stdlib/vulkan.h"
+#include "stdlib/glfw.h"
+#include "stdlib/math.h"
+#include "stdlib/imgui.h"
+#include "stdlib/transfer_queue.h"
+
+// Defer statement support
+// Note: Defer expressions should not throw exceptions.
+// If a defer expression throws during stack unwinding, std::terminate is called.
+template<typename F>
+class DeferHelper {
+    F f;
+public:
+    DeferHelper(F&& func) : f(std::forward<F>(func)) {}
+    ~DeferHelper() noexcept { f(); }
+    DeferHelper(const DeferHelper&) = delete;
+    DeferHelper& operator=(const DeferHelper&) = delete;
+};
+template<typename F>
+DeferHelper<F> make_defer(F&& f) {
+    return DeferHelper<F>(std::forward<F>(f));
+}
+
+// Constants
+constexpr float BASE = 2.0f;
+
+
+// Hot-reloadable function: gravity
+typedef float (*gravity_ptr)();
+extern gravity_ptr hd_hot_functions_gravity;
+
+// Hot-reloadable function: jump_height
+typedef float (*jump_height_ptr)(float);
+extern jump_height_ptr hd_hot_functions_jump_height;
+
+int hd_hot_functions_main();
+
+// Hot-reload function forward declarations
+void hd_hot_functions_check_and_reload_hot_system();
+void hd_hot_functions_load_hot_system(const char* dll_path);
+void hd_hot_functions_unload_hot_system();
+bool hd_hot_functions_rollback_last_reload();
+
+// Hot-reload checks run at most this often (compile with -DHEIDIC_RELOAD_CHECK_INTERVAL_MS=0 to check every iteration)
+#ifndef HEIDIC_RELOAD_CHECK_INTERVAL_MS
+#define HEIDIC_RELOAD_CHECK_INTERVAL_MS 100
+#endif
+static inline void heidic_check_reloads() {
+    static std::chrono::steady_clock::time_point next_check{};
+    auto now = std::chrono::steady_clock::now();
+    if (now < next_check) {
+        return;
+    }
+    next_check = now + std::chrono::milliseconds(HEIDIC_RELOAD_CHECK_INTERVAL_MS);
+    hd_hot_functions_check_and_reload_hot_system();
+}
+
+int hd_hot_functions_main() {
+        std::cout << hd_hot_functions_jump_height(2.0f) << std::endl;
+        return 0;
+}
+
+
+// Hot-Reload Session Log
+#include <ctime>
+#include <cstdio>
+
+static void log_reload_event(const char* kind, const char* target, bool ok, const std::string& detail) {
+    static FILE* log_file = nullptr;
+    char stamp[32];
+    time_t now = time(nullptr);
+    strftime(stamp, sizeof(stamp), "%Y-%m-%d %H:%M:%S", localtime(&now));
+    if (!log_file) {
+        log_file = fopen("heidic_reload.log", "a");
+        if (log_file) {
+            fprintf(log_file, "=== session started %s ===\n", stamp);
+        }
+    }
+    const char* status = ok ? "ok" : "FAILED";
+    std::cout << "[Hot-Reload] " << kind << " " << target << ": " << status << " (" << detail << ")" << std::endl;
+    if (log_file) {
+        fprintf(log_file, "%s  %-9s %-32s %-6s %s\n", stamp, kind, target, status, detail.c_str());
+        fflush(log_file);
+    }
+}
+
+
+// Hot-Reload Runtime Integration
+#include <windows.h>
+#include <string>
+#include <thread>
+#include <chrono>
+
+gravity_ptr hd_hot_functions_gravity = nullptr;
+jump_height_ptr hd_hot_functions_jump_height = nullptr;
+
+// Hot-reload helper functions
+HMODULE hd_hot_functions_hot_dll = nullptr;
+
+// Hot-reload ABI verification
+#include <cstring>
+typedef uint32_t (*heidic_hot_abi_version_ptr)();
+typedef const char* (*heidic_hot_abi_str_ptr)();
+typedef uint64_t (*heidic_hot_abi_hash_ptr)();
+static const uint32_t HEIDIC_HOT_ABI_MAJOR = 1;
+static const uint32_t HEIDIC_HOT_ABI_MINOR = 0;
+struct HotAbiExpectation { const char* system; uint64_t signature_hash; uint64_t layout_hash; const char* descriptor; };
+static const HotAbiExpectation hd_hot_functions_hot_abi_expected[] = {
+    { "hot_functions", 0x6498ff0b92e16c9dULL, 0xcbf29ce484222325ULL, "heidic-hot-abi 1.0 system hot_functions\nfn gravity() -> float\nfn jump_height(float) -> float\n" },
+};
+
+static bool verify_hot_abi(HMODULE dll, const char* dll_path) {
+    auto get_version = (heidic_hot_abi_version_ptr)GetProcAddress(dll, "heidic_hot_abi_version");
+    auto get_system = (heidic_hot_abi_str_ptr)GetProcAddress(dll, "heidic_hot_abi_system");
+    auto get_signature_hash = (heidic_hot_abi_hash_ptr)GetProcAddress(dll, "heidic_hot_abi_signature_hash");
+    auto get_layout_hash = (heidic_hot_abi_hash_ptr)GetProcAddress(dll, "heidic_hot_abi_layout_hash");
+    auto get_descriptor = (heidic_hot_abi_str_ptr)GetProcAddress(dll, "heidic_hot_abi_descriptor");
+    if (!get_version || !get_system || !get_signature_hash || !get_layout_hash) {
+        std::cerr << "[Hot-Reload] Refusing to load " << dll_path << ": no ABI descriptor (rebuild it with the current compiler)" << std::endl;
+        return false;
+    }
+    uint32_t version = get_version();
+    if ((version >> 16) != HEIDIC_HOT_ABI_MAJOR || (version & 0xFFFF) > HEIDIC_HOT_ABI_MINOR) {
+        std::cerr << "[Hot-Reload] Refusing to load " << dll_path << ": ABI version " << (version >> 16) << "." << (version & 0xFFFF)
+                  << " is incompatible with host ABI " << HEIDIC_HOT_ABI_MAJOR << "." << HEIDIC_HOT_ABI_MINOR << std::endl;
+        return false;
+    }
+    const char* system = get_system();
+    for (const auto& expected : hd_hot_functions_hot_abi_expected) {
+        if (std::strcmp(expected.system, system) != 0) continue;
+        const char* mismatch = nullptr;
+        if (get_signature_hash() != expected.signature_hash) mismatch = "function signatures";
+        else if (get_layout_hash() != expected.layout_hash) mismatch = "component layouts";
+        if (mismatch) {
+            std::cerr << "[Hot-Reload] Refusing to load " << dll_path << ": " << mismatch << " differ from the host" << std::endl;
+            std::cerr << "  host expects:\n" << expected.descriptor << std::endl;
+            if (get_descriptor) std::cerr << "  DLL provides:\n" << get_descriptor() << std::endl;
+            return false;
+        }
+        return true;
+    }
+    std::cerr << "[Hot-Reload] Refusing to load " << dll_path << ": unknown hot system '" << system << "'" << std::endl;
+    return false;
+}
+
+// Resolve a loaded DLL's entry points; false (with nothing loaded) if it can't be used
+static bool load_hot_library(const char* path) {
+    hd_hot_functions_hot_dll = LoadLibraryA(path);
+    if (!hd_hot_functions_hot_dll) {
+        std::cerr << "Failed to load hot-reload DLL: " << path << std::endl;
+        return false;
+    }
+    
+    // Refuse DLLs built against a different ABI (signatures or component layouts)
+    bool ok = verify_hot_abi(hd_hot_functions_hot_dll, path);
+    
+    // Load function pointers
+    hd_hot_functions_gravity = ok ? (gravity_ptr)GetProcAddress(hd_hot_functions_hot_dll, "gravity") : nullptr;
+    if (ok && !hd_hot_functions_gravity) {
+        std::cerr << "Failed to load function: gravity" << std::endl;
+        ok = false;
+    }
+    hd_hot_functions_jump_height = ok ? (jump_height_ptr)GetProcAddress(hd_hot_functions_hot_dll, "jump_height") : nullptr;
+    if (ok && !hd_hot_functions_jump_height) {
+        std::cerr << "Failed to load function: jump_height" << std::endl;
+        ok = false;
+    }
+    if (!ok) {
+        hd_hot_functions_unload_hot_system();
+    }
+    return ok;
+}
+
+// DLLs are loaded from numbered shadow copies, so the build can overwrite the original while
+// it's in use and the previous copy that loaded cleanly stays around for rollback_last_reload()
+static std::string hd_hot_functions_hot_dll_current;     // Shadow copy currently loaded
+static std::string hd_hot_functions_hot_dll_known_good;  // The one before it
+static int hd_hot_functions_hot_dll_generation = 0;
+
+void hd_hot_functions_load_hot_system(const char* dll_path) {
+    std::string shadow = std::string(dll_path) + ".shadow" + std::to_string(++hd_hot_functions_hot_dll_generation);
+    if (!CopyFileA(dll_path, shadow.c_str(), FALSE)) {
+        log_reload_event("system", dll_path, false, "could not copy the DLL (missing, or still being written?)");
+        return;
+    }
+    std::string previous = hd_hot_functions_hot_dll_current;
+    hd_hot_functions_unload_hot_system();
+    if (load_hot_library(shadow.c_str())) {
+        if (!previous.empty()) {
+            if (!hd_hot_functions_hot_dll_known_good.empty()) {
+                DeleteFileA(hd_hot_functions_hot_dll_known_good.c_str());
+            }
+            hd_hot_functions_hot_dll_known_good = previous;
+        }
+        hd_hot_functions_hot_dll_current = shadow;
+        log_reload_event("system", dll_path, true, "loaded " + shadow);
+        return;
+    }
+    DeleteFileA(shadow.c_str());
+    // Keep the game running on the code that worked
+    if (!previous.empty() && load_hot_library(previous.c_str())) {
+        log_reload_event("system", dll_path, false, "rejected; still running " + previous);
+    } else {
+        hd_hot_functions_hot_dll_current.clear();
+        log_reload_event("system", dll_path, false, "rejected; no hot code loaded");
+    }
+}
+
+// Console command: go back to the DLL that was loaded before the last successful reload
+bool hd_hot_functions_rollback_last_reload() {
+    if (hd_hot_functions_hot_dll_known_good.empty()) {
+        log_reload_event("rollback", hd_hot_functions_hot_dll_current.c_str(), false, "no earlier DLL to roll back to");
+        return false;
+    }
+    std::string bad = hd_hot_functions_hot_dll_current;
+    hd_hot_functions_unload_hot_system();
+    if (!load_hot_library(hd_hot_functions_hot_dll_known_good.c_str())) {
+        log_reload_event("rollback", hd_hot_functions_hot_dll_known_good.c_str(), false, "known-good DLL failed to load");
+        load_hot_library(bad.c_str());
+        return false;
+    }
+    DeleteFileA(bad.c_str());
+    hd_hot_functions_hot_dll_current = hd_hot_functions_hot_dll_known_good;
+    hd_hot_functions_hot_dll_known_good.clear();
+    log_reload_event("rollback", hd_hot_functions_hot_dll_current.c_str(), true, "replaced " + bad);
+    return true;
+}
+
+void hd_hot_functions_unload_hot_system() {
+    if (hd_hot_functions_hot_dll) {
+        FreeLibrary(hd_hot_functions_hot_dll);
+        hd_hot_functions_hot_dll = nullptr;
+        hd_hot_functions_gravity = nullptr;
+        hd_hot_functions_jump_height = nullptr;
+    }
+}
+
+// File watching and auto-reload: a DLL reloads when its contents change
+#include "stdlib/content_hash.h"
+
+static HeidicFileWatch hd_hot_functions_dll_watch_hot_functions {"hot_functions.dll"};
+
+void hd_hot_functions_check_and_reload_hot_system() {
+    if (heidic_watch_changed(hd_hot_functions_dll_watch_hot_functions)) {
+        std::cout << "[Hot-Reload] Detected change in hot_functions.dll, reloading..." << std::endl;
+        hd_hot_functions_load_hot_system("hot_functions.dll");
+    }
+}
+
+int main(int argc, char* argv[]) {
+    hd_hot_functions_load_hot_system("hot_functions.dll");
+    heidic_watch_reset(hd_hot_functions_dll_watch_hot_functions);
+    hd_hot_functions_main();
+    hd_hot_functions_unload_hot_system();
+    return 0;
+}
@@ -0,0 +1,29 @@
+// Hot-reloadable system DLL
+// Auto-generated from @hot system
+#include <cmath>
+#include <cstdint>
+
+// Constants
+constexpr float BASE = 2.0f;
+
+// Hot-reload ABI descriptor
+extern "C" {
+    uint32_t heidic_hot_abi_version() { return (1u << 16) | 0u; }
+    const char* heidic_hot_abi_system() { return "hot_functions"; }
+    uint64_t heidic_hot_abi_signature_hash() { return 0x6498ff0b92e16c9dULL; }
+    uint64_t heidic_hot_abi_layout_hash() { return 0xcbf29ce484222325ULL; }
+    const char* heidic_hot_abi_descriptor() { return "heidic-hot-abi 1.0 system hot_functions\nfn gravity() -> float\nfn jump_height(float) -> float\n"; }
+}
+
+extern "C" {
+    float gravity() {
+            return (BASE * 4.9f);
+    }
+}
+
+extern "C" {
+    float jump_height(float scale) {
+            return (gravity() * scale);
+    }
+}
+
@@ -0,0 +1,95 @@
+// Shared by the integration tests: compile HEIDIC programs with the built compiler and check the
+// generated code with the system's g++ or gcc (the stdlib stubs stand in for Vulkan, GLFW and ImGui).
+
+#![allow(dead_code)]  // Each test crate uses its own subset
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// An empty scratch directory for one test
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("heidic-tests-{}", std::process::id())).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("create scratch directory");
+    dir
+}
+
+/// Run the compiler with `args`
+pub fn heidic(args: &[&str], dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_heidic_v2"))
+        .args(args)
+        .current_dir(dir)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .expect("run heidic_v2")
+}
+
+fn describe(output: &Output) -> String {
+    format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+}
+
+/// Compile `source` as <dir>/<name>.hd with `flags` and stdlib stubs, and return the generated
+/// code (<name>.cpp, or <name>.c with --backend=c)
+pub fn compile(dir: &Path, name: &str, source: &str, flags: &[&str]) -> String {
+    let file = format!("{}.hd", name);
+    fs::write(dir.join(&file), source).expect("write fixture");
+    let args: Vec<&str> = ["compile", "--gen-stdlib-stubs"].into_iter().chain(flags.iter().copied()).chain([file.as_str()]).collect();
+    let output = heidic(&args, dir);
+    assert!(output.status.success(), "heidic_v2 compile {} failed:\n{}", file, describe(&output));
+    let generated = ["cpp", "c"].iter().map(|ext| dir.join(format!("{}.{}", name, ext))).find(|path| path.exists());
+    fs::read_to_string(generated.expect("generated code")).expect("read generated code")
+}
+
+/// Compile `source`, which must fail, and return the compiler's output
+pub fn compile_errors(dir: &Path, name: &str, source: &str) -> String {
+    let file = format!("{}.hd", name);
+    fs::write(dir.join(&file), source).expect("write fixture");
+    let output = heidic(&["compile", &file], dir);
+    assert!(!output.status.success(), "heidic_v2 compile {} succeeded", file);
+    describe(&output)
+}
+
+/// Check that <dir>/<file> compiles as C++17 (skipped where g++ isn't installed)
+pub fn check_cpp(dir: &Path, file: &str) {
+    check_source(dir, &dir.join(file));
+}
+
+/// Check that generated code compiles, C++17 with g++ or a .c as C99 with gcc, finding stdlib/
+/// stubs in `include_dir` first (skipped where the compiler isn't installed)
+pub fn check_source(include_dir: &Path, path: &Path) {
+    let (compiler, standard) = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("c") => ("gcc", "-std=c99"),
+        _ => ("g++", "-std=c++17"),
+    };
+    let result = Command::new(compiler)
+        .args([standard, "-fsyntax-only", "-Wall"])
+        .arg("-I").arg(include_dir)
+        .arg("-I").arg(env!("CARGO_MANIFEST_DIR"))
+        .arg(path)
+        .output();
+    match result {
+        Ok(output) => assert!(output.status.success(), "{} rejected {}:\n{}", compiler, path.display(), describe(&output)),
+        Err(_) => eprintln!("{} not found; not compiling {}", compiler, path.display()),
+    }
+}
+
+/// Build <dir>/<file> with g++ and return what the program prints (None where g++ isn't installed)
+pub fn run_cpp(dir: &Path, file: &str) -> Option<String> {
+    let exe = dir.join("heidic-test-program");
+    let result = Command::new("g++")
+        .args(["-std=c++17", "-o"])
+        .arg(&exe)
+        .arg("-I").arg(dir)
+        .arg("-I").arg(env!("CARGO_MANIFEST_DIR"))
+        .arg(dir.join(file))
+        .output();
+    let Ok(output) = result else {
+        eprintln!("g++ not found; not running {}", file);
+        return None;
+    };
+    assert!(output.status.success(), "g++ failed to build {}:\n{}", file, describe(&output));
+    let output = Command::new(&exe).current_dir(dir).output().expect("run the built program");
+    assert!(output.status.success(), "{} failed:\n{}", file, describe(&output));
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,52 @@
+// The codegen golden files (tests/codegen): the generated code still matches them, and they
+// are code a C or C++ compiler accepts
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+fn fixture_dir() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/codegen"))
+}
+
+#[test]
+fn generated_code_matches_the_golden_files() {
+    let dir = fixture_dir().to_string_lossy().into_owned();
+    let output = common::heidic(&["test-codegen", &dir], fixture_dir());
+    assert!(
+        output.status.success(),
+        "heidic_v2 test-codegen failed (run it with --update-golden when the change is intended):\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn golden_files_compile() {
+    let mut fixtures: Vec<_> = fs::read_dir(fixture_dir()).expect("read tests/codegen")
+        .map(|entry| entry.expect("read tests/codegen").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "hd"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty(), "no fixtures in tests/codegen");
+    for fixture in fixtures {
+        let name = fixture.file_stem().unwrap().to_string_lossy().into_owned();
+        let source = fs::read_to_string(&fixture).expect("read fixture");
+        let flags: Vec<&str> = source.lines().next()
+            .and_then(|line| line.trim().strip_prefix("// flags:"))
+            .map(|flags| flags.split_whitespace().collect())
+            .unwrap_or_default();
+        // Compiling the fixture again writes the stdlib stubs its golden files are checked against
+        let dir = common::scratch_dir(&format!("golden_{}", name));
+        common::compile(&dir, &name, &source, &flags);
+        for entry in fs::read_dir(fixture_dir().join("golden").join(&name)).expect("read golden directory") {
+            let golden = entry.expect("read golden directory").path();
+            // The hot-reload host side loads DLLs through the Windows API
+            if !cfg!(windows) && fs::read_to_string(&golden).expect("read golden file").contains("#include <windows.h>") {
+                continue;
+            }
+            common::check_source(&dir, &golden);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// Immutable locals: emitted `const`, and rejected where a call could modify them
+
+mod common;
+
+const PROGRAM: &str = r#"
+component Health {
+    value: f32
+}
+
+interface Damageable {
+    fn apply_damage(self, amount: f32);
+}
+
+impl Damageable for Health {
+    fn apply_damage(self, amount: f32) {
+        self.value = self.value - amount;
+    }
+}
+
+struct Point {
+    x: f32,
+    y: f32
+}
+
+fn shade(light: f32, albedo: f32): f32 {
+    return light * albedo;
+}
+
+fn main(): void {
+    let a = shade(2.0, 1.0);
+    let p = Point { x: a, y: 1.0 };
+    let mut h = Health { value: 10.0 };
+    apply_damage(h, 5.0);
+    let mut total = p.x;
+    total = total + h.value;
+    print(total);
+}
+"#;
+
+#[test]
+fn immutable_locals_are_const() {
+    let dir = common::scratch_dir("const_locals");
+    let cpp = common::compile(&dir, "locals", PROGRAM, &[]);
+    assert!(cpp.contains("const auto a = shade("), "call result not const:\n{}", cpp);
+    assert!(cpp.contains("const auto p = Point"), "struct literal not const:\n{}", cpp);
+    assert!(cpp.contains("    auto h = Health") && !cpp.contains("const auto h"), "mutable local is const:\n{}", cpp);
+    assert!(!cpp.contains("const auto total"));
+    common::check_cpp(&dir, "locals.cpp");
+}
+
+#[test]
+fn immutable_locals_cannot_be_passed_to_self() {
+    let dir = common::scratch_dir("borrowed_immutable");
+    // `self` is taken by reference and apply_damage writes through it
+    let source = PROGRAM.replace("let mut h", "let h");
+    let output = common::compile_errors(&dir, "locals", &source);
+    assert!(output.contains("E0015"), "borrowing an immutable local was accepted:\n{}", output);
+    assert!(output.contains("let mut h"), "no suggestion to declare h mutable:\n{}", output);
+}
@@ -0,0 +1,66 @@
+// heidic_v2 lsp: positions in UTF-16 code units, and hover signatures
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+const SOURCE: &str = "fn spawn_at(x: f32, y: f32 = 0.5): i32 {\n    return 1;\n}\n\nfn main(): void {\n    let label = \"🚀\"; let n: i32 = missing_value;\n    print(spawn_at(1.0));\n}\n";
+
+// Send `messages` to a language server session and return everything it wrote back
+fn session(messages: &[Value]) -> Vec<Value> {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_heidic_v2"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("run heidic_v2 lsp");
+    let mut stdin = server.stdin.take().unwrap();
+    for message in messages {
+        let body = message.to_string();
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).expect("write message");
+    }
+    drop(stdin);
+    let output = server.wait_with_output().expect("wait for heidic_v2 lsp");
+    let stdout = String::from_utf8(output.stdout).expect("UTF-8 output");
+    stdout.split("Content-Length: ").skip(1)
+        .map(|chunk| serde_json::from_str(chunk.split_once("\r\n\r\n").expect("header").1).expect("JSON body"))
+        .collect()
+}
+
+fn responses() -> Vec<Value> {
+    let uri = "file:///game.hd";
+    let call_line = SOURCE.lines().nth(6).unwrap();
+    session(&[
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen",
+                "params": { "textDocument": { "uri": uri, "languageId": "heidic", "version": 1, "text": SOURCE } } }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover",
+                "params": { "textDocument": { "uri": uri }, "position": { "line": 6, "character": call_line.find("spawn_at").unwrap() + 2 } } }),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+    ])
+}
+
+#[test]
+fn diagnostic_ranges_count_utf16_code_units() {
+    let responses = responses();
+    let diagnostics = responses.iter()
+        .find(|message| message["method"] == "textDocument/publishDiagnostics")
+        .expect("diagnostics for the opened document");
+    let undefined = diagnostics["params"]["diagnostics"].as_array().unwrap().iter()
+        .find(|diagnostic| diagnostic["code"] == "E0001")
+        .expect("E0001 for missing_value");
+    // The rocket is one char but two UTF-16 code units
+    let line = SOURCE.lines().nth(5).unwrap();
+    let start = line[..line.find("missing_value").unwrap()].encode_utf16().count();
+    assert_eq!(undefined["range"]["start"], json!({ "line": 5, "character": start }));
+    assert_eq!(undefined["range"]["end"], json!({ "line": 5, "character": start + "missing_value".len() }));
+}
+
+#[test]
+fn hover_shows_default_parameter_values() {
+    let responses = responses();
+    let hover = responses.iter().find(|message| message["id"] == 2).expect("hover response");
+    let contents = hover["result"]["contents"]["value"].as_str().expect("hover contents");
+    assert!(contents.contains("fn spawn_at(x: f32, y: f32 = 0.5): i32"), "hover without the default:\n{}", contents);
+}
@@ -0,0 +1,145 @@
+// Query parameters: the Query_* structs codegen emits for them, and their gathering from g_storage
+
+mod common;
+
+const PROGRAM: &str = r#"
+component Position {
+    x: f32,
+    y: f32
+}
+
+component_soa Velocity {
+    x: [f32],
+    y: [f32]
+}
+
+component Frozen {
+    t: f32
+}
+
+fn integrate(q: query<Position, Velocity, without<Frozen>>): void {
+    for entity in q {
+        entity.Position.x = entity.Position.x + entity.Velocity.x * 0.016;
+    }
+}
+
+fn watch(q: query<Position, changed<Position>>): void {
+    for entity in q {
+        entity.Position.y = entity.Position.x;
+    }
+}
+
+fn thaw(q: query<Frozen, with<Position>>): void {
+    for entity in q {
+        entity.Frozen.t = 0.0;
+    }
+}
+
+fn chunked(q: query<Position, Velocity>): void {
+    for chunk in q.chunks(64) {
+        let n: i32 = chunk.count;
+    }
+}
+
+fn main(): void {
+    print("queries\n");
+}
+"#;
+
+#[test]
+fn query_structs_hold_entities_and_component_arrays() {
+    let dir = common::scratch_dir("query_structs");
+    let cpp = common::compile(&dir, "queries", PROGRAM, &[]);
+    assert!(cpp.contains("struct Query_Position_Velocity_WithoutFrozen {\n    std::vector<EntityId> entities;\n    std::vector<Position> positions;\n    Velocity velocities;\n"));
+    assert!(cpp.contains("void integrate(Query_Position_Velocity_WithoutFrozen& q);"));
+    assert!(cpp.contains("heidic_gather_Query_Frozen_WithPosition()"));
+}
+
+#[test]
+fn query_programs_compile_with_sparse_set_storage() {
+    let dir = common::scratch_dir("query_sparse");
+    common::compile(&dir, "queries", PROGRAM, &[]);
+    common::check_cpp(&dir, "queries.cpp");
+}
+
+#[test]
+fn query_programs_compile_with_archetype_storage() {
+    let dir = common::scratch_dir("query_archetype");
+    common::compile(&dir, "queries", PROGRAM, &["--ecs-storage=archetype"]);
+    common::check_cpp(&dir, "queries.cpp");
+}
+
+#[test]
+fn component_fields_keep_their_declared_types_in_query_loops() {
+    let dir = common::scratch_dir("query_field_types");
+    let source = r#"
+component Mass {
+    m: f64,
+    frozen: bool
+}
+
+component_soa Spin {
+    rate: [f64]
+}
+
+fn scale(q: query<Mass, Spin>, s: f64): void {
+    for entity in q {
+        entity.Mass.m = entity.Mass.m * s;
+        entity.Mass.frozen = true;
+        entity.Spin.rate = entity.Spin.rate * 2.0;
+    }
+}
+
+fn main(): void {
+    print("fields\n");
+}
+"#;
+    common::compile(&dir, "fields", source, &[]);
+    common::check_cpp(&dir, "fields.cpp");
+}
+
+const SOA_BATCH: &str = r#"
+component Position {
+    x: f32
+}
+
+component_soa Velocity {
+    x: [f32],
+    y: [f32]
+}
+
+@[stage(update)]
+system Report {
+    fn report(q: query<Position, Velocity>): void {
+        let mut total = 0.0;
+        for entity in q {
+            total = total + entity.Velocity.y;
+        }
+        if total == 6.0 {
+            print("rows\n");
+        }
+    }
+}
+
+fn main(): void {
+    spawn_batch(4) {
+        init(i) {
+            Position { x: 0.0 };
+            Velocity { y: i as f32, x: 1.0 };
+        }
+    }
+    run_systems();
+}
+"#;
+
+#[test]
+fn spawn_batch_attaches_component_soa_rows() {
+    for storage in ["sparse", "archetype"] {
+        let dir = common::scratch_dir(&format!("soa_batch_{}", storage));
+        let cpp = common::compile(&dir, "batch", SOA_BATCH, &[&format!("--ecs-storage={}", storage)]);
+        assert!(cpp.contains("heidic_storage_Velocity.add(heidic_entity, Velocity::Row {.x = 1.0f, .y = static_cast<float>(i)});"), "{}", cpp);
+        if let Some(stdout) = common::run_cpp(&dir, "batch.cpp") {
+            assert_eq!(stdout.trim(), "rows");
+        }
+    }
+}
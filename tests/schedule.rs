@@ -0,0 +1,123 @@
+// run_systems(): which functions of scheduled systems it calls, and with what
+
+mod common;
+
+use std::fs;
+
+const COMPONENTS: &str = r#"
+component Position {
+    x: f32,
+    y: f32
+}
+
+component Velocity {
+    x: f32,
+    y: f32
+}
+"#;
+
+#[test]
+fn query_and_resource_system_with_a_handler_is_called() {
+    let dir = common::scratch_dir("schedule_query_handler");
+    let source = format!("{}{}", COMPONENTS, r#"
+event Hit {
+    amount: f32
+}
+
+@[stage(update)]
+system Movement {
+    fn step(q: query<Position, Velocity>, time: res<Time>): void {
+        for entity in q {
+            entity.Position.x = entity.Position.x + entity.Velocity.x * time.delta;
+        }
+    }
+
+    on Hit(ev) {
+        let amount: f32 = ev.amount;
+    }
+}
+
+fn main(): void {
+    run_systems();
+}
+"#);
+    let cpp = common::compile(&dir, "movement", &source, &[]);
+    assert!(cpp.contains("heidic_events_Hit.drain(cursor, heidic_Movement_on_Hit);"));
+    assert!(cpp.contains(concat!(
+        "        Query_Position_Velocity heidic_query_q = heidic_gather_Query_Position_Velocity();\n",
+        "        step(heidic_query_q, heidic_res_Time);\n",
+        "        heidic_commit(heidic_query_q);\n",
+    )));
+    common::check_cpp(&dir, "movement.cpp");
+}
+
+#[test]
+fn query_systems_write_back_before_the_next_system_runs() {
+    let dir = common::scratch_dir("schedule_write_back");
+    fs::write(dir.join("level.hscene"), r#"{ "entities": [ { "components": { "Position": { "x": 0.0 }, "Velocity": { "x": 2.0 } } } ] }"#)
+        .expect("write scene");
+    let source = format!("{}{}", COMPONENTS, r#"
+scene "level.hscene";
+
+@[stage(update)]
+system Movement {
+    fn step(q: query<Position, Velocity>): void {
+        for entity in q {
+            entity.Position.x = entity.Position.x + entity.Velocity.x;
+        }
+    }
+}
+
+@[stage(post_update)]
+system Report {
+    fn report(q: query<Position>): void {
+        for entity in q {
+            if entity.Position.x > 1.0 {
+                moved();
+            }
+        }
+    }
+}
+
+fn moved(): void {
+    print("moved\n");
+}
+
+fn main(): void {
+    run_systems();
+}
+"#);
+    common::compile(&dir, "write_back", &source, &[]);
+    if let Some(stdout) = common::run_cpp(&dir, "write_back.cpp") {
+        assert_eq!(stdout.trim(), "moved");
+    }
+}
+
+#[test]
+fn entry_arguments_follow_the_parameter_types() {
+    let dir = common::scratch_dir("schedule_arguments");
+    let source = format!("{}{}", COMPONENTS, r#"
+@[stage(update)]
+system Physics {
+    fn step(time: res<Time>, dt: f32, q: query<Position, Velocity>): void {
+        for entity in q {
+            nudge(entity.Position.x, dt);
+        }
+    }
+
+    fn nudge(x: f32, dt: f32): void {
+    }
+}
+
+fn main(): void {
+    run_systems();
+}
+"#);
+    let cpp = common::compile(&dir, "physics", &source, &[]);
+    let run_systems = &cpp[cpp.find("static void heidic_run_systems()").expect("run_systems() is generated")..];
+    let run_systems = &run_systems[..run_systems.find("\n}\n").expect("end of run_systems()")];
+    assert!(run_systems.contains("step(heidic_res_Time, dt, heidic_query_q);"));
+    // Two f32 parameters: a helper, not something run_systems() can call
+    assert!(!run_systems.contains("nudge("));
+    common::check_cpp(&dir, "physics.cpp");
+}
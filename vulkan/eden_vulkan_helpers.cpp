@@ -63,6 +63,14 @@
 #include "../stdlib/texture_resource.h"
 #include "../stdlib/mesh_resource.h"
 #include "../stdlib/resource.h"
+#include "../stdlib/gpu_config.h"
+#include "../stdlib/window_config.h"
+#include "../stdlib/transfer_queue.h"
+#include "../stdlib/gpu_query.h"
+#include "../stdlib/frame_capture.h"
+#include "../stdlib/gpu_buffer.h"
+#include "../stdlib/vulkan_compute.h"
+#include "../stdlib/uniform_buffer.h"
 
 // ImGui includes (if available)
 #ifdef USE_IMGUI
@@ -210,6 +218,310 @@ static uint32_t findGraphicsQueueFamily(VkPhysicalDevice device, VkSurfaceKHR su
     return UINT32_MAX;
 }
 
+// =============================================================================
+// GPU device selection (configured by a HEIDIC `gpu { ... }` block)
+// =============================================================================
+
+static const HeidicGpuConfig* g_gpuConfig = nullptr;
+
+extern "C" void heidic_set_gpu_config(const HeidicGpuConfig* config) {
+    g_gpuConfig = config;
+}
+
+// Feature name -> VkBool32 member of VkPhysicalDeviceFeatures, or of
+// VkPhysicalDeviceDescriptorIndexingFeatures (Vulkan 1.2, needed by bindless)
+struct GpuFeatureEntry {
+    const char* name;
+    size_t offset;
+    bool descriptorIndexing;
+};
+
+#define CORE_FEATURE(f) { #f, offsetof(VkPhysicalDeviceFeatures, f), false }
+#define INDEXING_FEATURE(f) { #f, offsetof(VkPhysicalDeviceDescriptorIndexingFeatures, f), true }
+static const GpuFeatureEntry g_gpuFeatureTable[] = {
+    CORE_FEATURE(robustBufferAccess),
+    CORE_FEATURE(fullDrawIndexUint32),
+    CORE_FEATURE(imageCubeArray),
+    CORE_FEATURE(independentBlend),
+    CORE_FEATURE(geometryShader),
+    CORE_FEATURE(tessellationShader),
+    CORE_FEATURE(sampleRateShading),
+    CORE_FEATURE(dualSrcBlend),
+    CORE_FEATURE(logicOp),
+    CORE_FEATURE(multiDrawIndirect),
+    CORE_FEATURE(drawIndirectFirstInstance),
+    CORE_FEATURE(depthClamp),
+    CORE_FEATURE(depthBiasClamp),
+    CORE_FEATURE(fillModeNonSolid),
+    CORE_FEATURE(depthBounds),
+    CORE_FEATURE(wideLines),
+    CORE_FEATURE(largePoints),
+    CORE_FEATURE(alphaToOne),
+    CORE_FEATURE(multiViewport),
+    CORE_FEATURE(samplerAnisotropy),
+    CORE_FEATURE(textureCompressionETC2),
+    CORE_FEATURE(textureCompressionASTC_LDR),
+    CORE_FEATURE(textureCompressionBC),
+    CORE_FEATURE(occlusionQueryPrecise),
+    CORE_FEATURE(pipelineStatisticsQuery),
+    CORE_FEATURE(vertexPipelineStoresAndAtomics),
+    CORE_FEATURE(fragmentStoresAndAtomics),
+    CORE_FEATURE(shaderTessellationAndGeometryPointSize),
+    CORE_FEATURE(shaderImageGatherExtended),
+    CORE_FEATURE(shaderStorageImageExtendedFormats),
+    CORE_FEATURE(shaderStorageImageMultisample),
+    CORE_FEATURE(shaderStorageImageReadWithoutFormat),
+    CORE_FEATURE(shaderStorageImageWriteWithoutFormat),
+    CORE_FEATURE(shaderUniformBufferArrayDynamicIndexing),
+    CORE_FEATURE(shaderSampledImageArrayDynamicIndexing),
+    CORE_FEATURE(shaderStorageBufferArrayDynamicIndexing),
+    CORE_FEATURE(shaderStorageImageArrayDynamicIndexing),
+    CORE_FEATURE(shaderClipDistance),
+    CORE_FEATURE(shaderCullDistance),
+    CORE_FEATURE(shaderFloat64),
+    CORE_FEATURE(shaderInt64),
+    CORE_FEATURE(shaderInt16),
+    CORE_FEATURE(shaderResourceResidency),
+    CORE_FEATURE(shaderResourceMinLod),
+    CORE_FEATURE(sparseBinding),
+    CORE_FEATURE(sparseResidencyBuffer),
+    CORE_FEATURE(sparseResidencyImage2D),
+    CORE_FEATURE(sparseResidencyImage3D),
+    CORE_FEATURE(sparseResidency2Samples),
+    CORE_FEATURE(sparseResidency4Samples),
+    CORE_FEATURE(sparseResidency8Samples),
+    CORE_FEATURE(sparseResidency16Samples),
+    CORE_FEATURE(sparseResidencyAliased),
+    CORE_FEATURE(variableMultisampleRate),
+    CORE_FEATURE(inheritedQueries),
+    INDEXING_FEATURE(shaderInputAttachmentArrayDynamicIndexing),
+    INDEXING_FEATURE(shaderUniformTexelBufferArrayDynamicIndexing),
+    INDEXING_FEATURE(shaderStorageTexelBufferArrayDynamicIndexing),
+    INDEXING_FEATURE(shaderUniformBufferArrayNonUniformIndexing),
+    INDEXING_FEATURE(shaderSampledImageArrayNonUniformIndexing),
+    INDEXING_FEATURE(shaderStorageBufferArrayNonUniformIndexing),
+    INDEXING_FEATURE(shaderStorageImageArrayNonUniformIndexing),
+    INDEXING_FEATURE(shaderInputAttachmentArrayNonUniformIndexing),
+    INDEXING_FEATURE(shaderUniformTexelBufferArrayNonUniformIndexing),
+    INDEXING_FEATURE(shaderStorageTexelBufferArrayNonUniformIndexing),
+    INDEXING_FEATURE(descriptorBindingUniformBufferUpdateAfterBind),
+    INDEXING_FEATURE(descriptorBindingSampledImageUpdateAfterBind),
+    INDEXING_FEATURE(descriptorBindingStorageImageUpdateAfterBind),
+    INDEXING_FEATURE(descriptorBindingStorageBufferUpdateAfterBind),
+    INDEXING_FEATURE(descriptorBindingUniformTexelBufferUpdateAfterBind),
+    INDEXING_FEATURE(descriptorBindingStorageTexelBufferUpdateAfterBind),
+    INDEXING_FEATURE(descriptorBindingUpdateUnusedWhilePending),
+    INDEXING_FEATURE(descriptorBindingPartiallyBound),
+    INDEXING_FEATURE(descriptorBindingVariableDescriptorCount),
+    INDEXING_FEATURE(runtimeDescriptorArray),
+};
+#undef CORE_FEATURE
+#undef INDEXING_FEATURE
+
+struct GpuDeviceFeatures {
+    VkPhysicalDeviceFeatures core = {};
+    VkPhysicalDeviceDescriptorIndexingFeatures indexing = {};
+    bool hasIndexing = false;  // Indexing struct is meaningful (queried from a 1.2+ device / has features to enable)
+};
+
+// Features of the selected device (used to decide which optional features to enable)
+static GpuDeviceFeatures g_selectedGpuFeatures;
+// Core features actually enabled on the logical device
+static VkPhysicalDeviceFeatures g_enabledCoreFeatures = {};
+
+static const GpuFeatureEntry* findGpuFeature(const char* name) {
+    for (const auto& entry : g_gpuFeatureTable) {
+        if (strcmp(entry.name, name) == 0) {
+            return &entry;
+        }
+    }
+    return nullptr;
+}
+
+static VkBool32* gpuFeatureField(GpuDeviceFeatures& features, const GpuFeatureEntry& entry) {
+    char* base = entry.descriptorIndexing
+        ? reinterpret_cast<char*>(&features.indexing)
+        : reinterpret_cast<char*>(&features.core);
+    return reinterpret_cast<VkBool32*>(base + entry.offset);
+}
+
+// True if the config asks for any descriptor-indexing feature (requires a Vulkan 1.2 instance)
+static bool gpuConfigNeedsIndexing() {
+    if (!g_gpuConfig) {
+        return false;
+    }
+    for (uint32_t i = 0; i < g_gpuConfig->required_feature_count; i++) {
+        const GpuFeatureEntry* entry = findGpuFeature(g_gpuConfig->required_features[i]);
+        if (entry && entry->descriptorIndexing) return true;
+    }
+    for (uint32_t i = 0; i < g_gpuConfig->optional_feature_count; i++) {
+        const GpuFeatureEntry* entry = findGpuFeature(g_gpuConfig->optional_features[i]);
+        if (entry && entry->descriptorIndexing) return true;
+    }
+    return false;
+}
+
+static GpuDeviceFeatures queryGpuFeatures(VkPhysicalDevice device) {
+    GpuDeviceFeatures features;
+    features.indexing.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_DESCRIPTOR_INDEXING_FEATURES;
+    
+    VkPhysicalDeviceProperties props;
+    vkGetPhysicalDeviceProperties(device, &props);
+    if (gpuConfigNeedsIndexing() && props.apiVersion >= VK_API_VERSION_1_2) {
+        VkPhysicalDeviceFeatures2 features2 = {};
+        features2.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_FEATURES_2;
+        features2.pNext = &features.indexing;
+        vkGetPhysicalDeviceFeatures2(device, &features2);
+        features.core = features2.features;
+        features.indexing.pNext = nullptr;
+        features.hasIndexing = true;
+    } else {
+        vkGetPhysicalDeviceFeatures(device, &features.core);
+    }
+    return features;
+}
+
+// Required features the device lacks (empty if the device satisfies the config)
+static std::vector<std::string> missingGpuFeatures(GpuDeviceFeatures& supported) {
+    std::vector<std::string> missing;
+    if (!g_gpuConfig) {
+        return missing;
+    }
+    for (uint32_t i = 0; i < g_gpuConfig->required_feature_count; i++) {
+        const char* name = g_gpuConfig->required_features[i];
+        const GpuFeatureEntry* entry = findGpuFeature(name);
+        if (!entry) {
+            missing.push_back(std::string(name) + " (unknown feature)");
+        } else if (entry->descriptorIndexing && !supported.hasIndexing) {
+            missing.push_back(std::string(name) + " (requires Vulkan 1.2)");
+        } else if (!*gpuFeatureField(supported, *entry)) {
+            missing.push_back(name);
+        }
+    }
+    return missing;
+}
+
+static const char* gpuDeviceTypeName(VkPhysicalDeviceType type) {
+    switch (type) {
+        case VK_PHYSICAL_DEVICE_TYPE_DISCRETE_GPU: return "discrete";
+        case VK_PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU: return "integrated";
+        case VK_PHYSICAL_DEVICE_TYPE_VIRTUAL_GPU: return "virtual";
+        case VK_PHYSICAL_DEVICE_TYPE_CPU: return "cpu";
+        default: return "other";
+    }
+}
+
+// Higher is better. Without a config every device scores the same, so the first suitable one wins.
+static int scoreGpuDevice(const VkPhysicalDeviceProperties& props) {
+    if (!g_gpuConfig) {
+        return 0;
+    }
+    int prefer = g_gpuConfig->prefer;
+    switch (props.deviceType) {
+        case VK_PHYSICAL_DEVICE_TYPE_DISCRETE_GPU:
+            return prefer == HEIDIC_GPU_PREFER_DISCRETE ? 1000 : (prefer == HEIDIC_GPU_PREFER_INTEGRATED ? 100 : 500);
+        case VK_PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU:
+            return prefer == HEIDIC_GPU_PREFER_INTEGRATED ? 1000 : (prefer == HEIDIC_GPU_PREFER_DISCRETE ? 100 : 250);
+        case VK_PHYSICAL_DEVICE_TYPE_VIRTUAL_GPU:
+            return 50;
+        case VK_PHYSICAL_DEVICE_TYPE_CPU:
+            return 10;
+        default:
+            return 1;
+    }
+}
+
+// Pick the best physical device for the registered gpu config.
+// Prints why each rejected device was rejected; returns VK_NULL_HANDLE if none qualifies.
+static VkPhysicalDevice selectPhysicalDevice(const std::vector<VkPhysicalDevice>& devices, VkSurfaceKHR surface) {
+    int32_t forcedIndex = g_gpuConfig ? g_gpuConfig->device_index : -1;
+    if (forcedIndex >= static_cast<int32_t>(devices.size())) {
+        std::cerr << "[EDEN] ERROR: gpu { device: " << forcedIndex << " } requested, but only "
+                  << devices.size() << " Vulkan device(s) are available" << std::endl;
+        return VK_NULL_HANDLE;
+    }
+    
+    VkPhysicalDevice best = VK_NULL_HANDLE;
+    int bestScore = -1;
+    std::vector<std::string> rejections;
+    for (size_t i = 0; i < devices.size(); i++) {
+        if (forcedIndex >= 0 && static_cast<int32_t>(i) != forcedIndex) {
+            continue;
+        }
+        VkPhysicalDeviceProperties props;
+        vkGetPhysicalDeviceProperties(devices[i], &props);
+        std::string label = "GPU " + std::to_string(i) + ": " + props.deviceName + " (" + gpuDeviceTypeName(props.deviceType) + ")";
+        
+        if (findGraphicsQueueFamily(devices[i], surface) == UINT32_MAX) {
+            rejections.push_back(label + " - no graphics queue that can present to the window");
+            continue;
+        }
+        GpuDeviceFeatures supported = queryGpuFeatures(devices[i]);
+        std::vector<std::string> missing = missingGpuFeatures(supported);
+        if (!missing.empty()) {
+            std::string list;
+            for (size_t m = 0; m < missing.size(); m++) {
+                list += (m > 0 ? ", " : "") + missing[m];
+            }
+            rejections.push_back(label + " - missing required features: " + list);
+            continue;
+        }
+        
+        int score = scoreGpuDevice(props);
+        if (score > bestScore) {
+            best = devices[i];
+            bestScore = score;
+            g_selectedGpuFeatures = supported;
+        }
+    }
+    
+    if (best == VK_NULL_HANDLE) {
+        std::cerr << "[EDEN] ERROR: No GPU satisfies the gpu { } requirements:" << std::endl;
+        for (const auto& rejection : rejections) {
+            std::cerr << "[EDEN]   " << rejection << std::endl;
+        }
+        return VK_NULL_HANDLE;
+    }
+    
+    VkPhysicalDeviceProperties props;
+    vkGetPhysicalDeviceProperties(best, &props);
+    std::cout << "[EDEN] Selected GPU: " << props.deviceName << " (" << gpuDeviceTypeName(props.deviceType) << ")" << std::endl;
+    return best;
+}
+
+// Features to enable on the logical device: the renderer's own needs plus the config's
+// required features and whichever optional features the selected device supports
+static GpuDeviceFeatures enabledGpuFeatures() {
+    GpuDeviceFeatures enabled;
+    enabled.indexing.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_DESCRIPTOR_INDEXING_FEATURES;
+    enabled.core.fillModeNonSolid = g_selectedGpuFeatures.core.fillModeNonSolid;  // Wireframe mode when available
+    if (!g_gpuConfig) {
+        return enabled;
+    }
+    for (uint32_t i = 0; i < g_gpuConfig->required_feature_count; i++) {
+        const GpuFeatureEntry* entry = findGpuFeature(g_gpuConfig->required_features[i]);
+        if (entry) {
+            *gpuFeatureField(enabled, *entry) = VK_TRUE;
+            enabled.hasIndexing |= entry->descriptorIndexing;
+        }
+    }
+    for (uint32_t i = 0; i < g_gpuConfig->optional_feature_count; i++) {
+        const char* name = g_gpuConfig->optional_features[i];
+        const GpuFeatureEntry* entry = findGpuFeature(name);
+        if (!entry || (entry->descriptorIndexing && !g_selectedGpuFeatures.hasIndexing)) {
+            std::cout << "[EDEN] Optional GPU feature unavailable: " << name << std::endl;
+            continue;
+        }
+        if (*gpuFeatureField(g_selectedGpuFeatures, *entry)) {
+            *gpuFeatureField(enabled, *entry) = VK_TRUE;
+            enabled.hasIndexing |= entry->descriptorIndexing;
+        } else {
+            std::cout << "[EDEN] Optional GPU feature unavailable: " << name << std::endl;
+        }
+    }
+    return enabled;
+}
+
 // Helper to find memory type
 static uint32_t findMemoryType(uint32_t typeFilter, VkMemoryPropertyFlags properties) {
     VkPhysicalDeviceMemoryProperties memProperties;
@@ -248,93 +560,1438 @@ static void createBuffer(VkDeviceSize size, VkBufferUsageFlags usage, VkMemoryPr
         std::cerr << "[EDEN] Failed to allocate buffer memory!" << std::endl;
         return;
     }
-
-    vkBindBufferMemory(g_device, buffer, bufferMemory, 0);
+
+    vkBindBufferMemory(g_device, buffer, bufferMemory, 0);
+}
+
+// =============================================================================
+// Queue families and transfer-queue uploads
+// =============================================================================
+
+static uint32_t g_transferQueueFamilyIndex = 0;
+static VkQueue g_transferQueue = VK_NULL_HANDLE;
+static VkCommandPool g_transferCommandPool = VK_NULL_HANDLE;
+static VkCommandPool g_acquireCommandPool = VK_NULL_HANDLE;  // Graphics-family pool for ownership acquires
+
+struct StagingAllocation {
+    VkBuffer buffer;
+    VkDeviceMemory memory;
+};
+
+// A transfer batch from heidic_begin_transfer() until it is retired
+struct PendingTransfer {
+    VkCommandBuffer cmd = VK_NULL_HANDLE;
+    VkFence fence = VK_NULL_HANDLE;
+    std::vector<StagingAllocation> staging;
+    std::vector<VkBufferMemoryBarrier> bufferAcquires;  // Issued on the graphics queue when complete
+    std::vector<VkImageMemoryBarrier> imageAcquires;
+};
+
+static std::map<VkCommandBuffer, PendingTransfer> g_recordingTransfers;
+static std::map<uint64_t, PendingTransfer> g_inFlightTransfers;
+static uint64_t g_nextTransferTicket = 1;
+static VkCommandBuffer g_streamingBatch = VK_NULL_HANDLE;  // Uploads queued since the last submit_transfer()
+
+// Prefer a transfer-only family (dedicated DMA engine), then any non-graphics family that
+// supports transfers, then the graphics family itself
+static uint32_t findTransferQueueFamily(VkPhysicalDevice device, uint32_t graphicsFamily) {
+    if (g_gpuConfig && g_gpuConfig->transfer_queue == HEIDIC_GPU_TRANSFER_SHARED) {
+        return graphicsFamily;
+    }
+    uint32_t queueFamilyCount = 0;
+    vkGetPhysicalDeviceQueueFamilyProperties(device, &queueFamilyCount, nullptr);
+    std::vector<VkQueueFamilyProperties> queueFamilies(queueFamilyCount);
+    vkGetPhysicalDeviceQueueFamilyProperties(device, &queueFamilyCount, queueFamilies.data());
+    
+    uint32_t fallback = graphicsFamily;
+    for (uint32_t i = 0; i < queueFamilyCount; i++) {
+        VkQueueFlags flags = queueFamilies[i].queueFlags;
+        if (!(flags & VK_QUEUE_TRANSFER_BIT) || (flags & VK_QUEUE_GRAPHICS_BIT)) {
+            continue;
+        }
+        if (!(flags & VK_QUEUE_COMPUTE_BIT)) {
+            return i;
+        }
+        if (fallback == graphicsFamily) {
+            fallback = i;
+        }
+    }
+    return fallback;
+}
+
+static bool createTransferResources() {
+    vkGetDeviceQueue(g_device, g_transferQueueFamilyIndex, 0, &g_transferQueue);
+    
+    VkCommandPoolCreateInfo poolInfo = {};
+    poolInfo.sType = VK_STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO;
+    poolInfo.flags = VK_COMMAND_POOL_CREATE_TRANSIENT_BIT;
+    poolInfo.queueFamilyIndex = g_transferQueueFamilyIndex;
+    if (vkCreateCommandPool(g_device, &poolInfo, nullptr, &g_transferCommandPool) != VK_SUCCESS) {
+        std::cerr << "[EDEN] ERROR: Failed to create transfer command pool!" << std::endl;
+        return false;
+    }
+    poolInfo.queueFamilyIndex = g_graphicsQueueFamilyIndex;
+    if (vkCreateCommandPool(g_device, &poolInfo, nullptr, &g_acquireCommandPool) != VK_SUCCESS) {
+        std::cerr << "[EDEN] ERROR: Failed to create ownership-acquire command pool!" << std::endl;
+        return false;
+    }
+    
+    if (heidic_has_dedicated_transfer_queue()) {
+        std::cout << "[EDEN] Using dedicated transfer queue (family " << g_transferQueueFamilyIndex << ")" << std::endl;
+    }
+    return true;
+}
+
+static StagingAllocation createStagingBuffer(const void* data, VkDeviceSize size) {
+    StagingAllocation staging = {};
+    createBuffer(size, VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
+                 VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
+                 staging.buffer, staging.memory);
+    void* mapped;
+    vkMapMemory(g_device, staging.memory, 0, size, 0, &mapped);
+    memcpy(mapped, data, (size_t)size);
+    vkUnmapMemory(g_device, staging.memory);
+    return staging;
+}
+
+// Acquire ownership on the graphics queue (if needed), then free the batch's resources
+static void retireTransfer(uint64_t id) {
+    auto it = g_inFlightTransfers.find(id);
+    if (it == g_inFlightTransfers.end()) {
+        return;
+    }
+    PendingTransfer& transfer = it->second;
+    
+    if (!transfer.bufferAcquires.empty() || !transfer.imageAcquires.empty()) {
+        VkCommandBufferAllocateInfo allocInfo = {};
+        allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
+        allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
+        allocInfo.commandPool = g_acquireCommandPool;
+        allocInfo.commandBufferCount = 1;
+        VkCommandBuffer acquireCmd;
+        vkAllocateCommandBuffers(g_device, &allocInfo, &acquireCmd);
+        
+        VkCommandBufferBeginInfo beginInfo = {};
+        beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
+        beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
+        vkBeginCommandBuffer(acquireCmd, &beginInfo);
+        vkCmdPipelineBarrier(acquireCmd, VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT, VK_PIPELINE_STAGE_ALL_COMMANDS_BIT, 0,
+                             0, nullptr,
+                             static_cast<uint32_t>(transfer.bufferAcquires.size()), transfer.bufferAcquires.data(),
+                             static_cast<uint32_t>(transfer.imageAcquires.size()), transfer.imageAcquires.data());
+        vkEndCommandBuffer(acquireCmd);
+        
+        VkSubmitInfo submitInfo = {};
+        submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
+        submitInfo.commandBufferCount = 1;
+        submitInfo.pCommandBuffers = &acquireCmd;
+        vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
+        vkQueueWaitIdle(g_graphicsQueue);
+        vkFreeCommandBuffers(g_device, g_acquireCommandPool, 1, &acquireCmd);
+    }
+    
+    for (const auto& staging : transfer.staging) {
+        vkDestroyBuffer(g_device, staging.buffer, nullptr);
+        vkFreeMemory(g_device, staging.memory, nullptr);
+    }
+    vkDestroyFence(g_device, transfer.fence, nullptr);
+    vkFreeCommandBuffers(g_device, g_transferCommandPool, 1, &transfer.cmd);
+    g_inFlightTransfers.erase(it);
+}
+
+static void destroyTransferResources() {
+    if (g_streamingBatch != VK_NULL_HANDLE) {
+        wait_transfer(submit_transfer());
+    }
+    while (!g_inFlightTransfers.empty()) {
+        wait_transfer(HeidicTransferTicket{ g_inFlightTransfers.begin()->first });
+    }
+    if (g_transferCommandPool != VK_NULL_HANDLE) {
+        vkDestroyCommandPool(g_device, g_transferCommandPool, nullptr);
+        g_transferCommandPool = VK_NULL_HANDLE;
+    }
+    if (g_acquireCommandPool != VK_NULL_HANDLE) {
+        vkDestroyCommandPool(g_device, g_acquireCommandPool, nullptr);
+        g_acquireCommandPool = VK_NULL_HANDLE;
+    }
+}
+
+extern "C" uint32_t heidic_graphics_queue_family() {
+    return g_graphicsQueueFamilyIndex;
+}
+
+extern "C" uint32_t heidic_transfer_queue_family() {
+    return g_transferQueueFamilyIndex;
+}
+
+extern "C" bool heidic_has_dedicated_transfer_queue() {
+    return g_transferQueueFamilyIndex != g_graphicsQueueFamilyIndex;
+}
+
+extern "C" VkCommandBuffer heidic_begin_transfer() {
+    VkCommandBufferAllocateInfo allocInfo = {};
+    allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
+    allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
+    allocInfo.commandPool = g_transferCommandPool;
+    allocInfo.commandBufferCount = 1;
+    
+    VkCommandBuffer cmd;
+    vkAllocateCommandBuffers(g_device, &allocInfo, &cmd);
+    
+    VkCommandBufferBeginInfo beginInfo = {};
+    beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
+    beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
+    vkBeginCommandBuffer(cmd, &beginInfo);
+    
+    g_recordingTransfers[cmd].cmd = cmd;
+    return cmd;
+}
+
+extern "C" void heidic_transfer_release_buffer(VkCommandBuffer cmd, VkBuffer buffer, VkDeviceSize offset, VkDeviceSize size) {
+    VkBufferMemoryBarrier barrier = {};
+    barrier.sType = VK_STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER;
+    barrier.srcAccessMask = VK_ACCESS_TRANSFER_WRITE_BIT;
+    barrier.buffer = buffer;
+    barrier.offset = offset;
+    barrier.size = size;
+    
+    if (!heidic_has_dedicated_transfer_queue()) {
+        // Same queue family: just make the copy visible to later reads
+        barrier.dstAccessMask = VK_ACCESS_MEMORY_READ_BIT;
+        barrier.srcQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+        barrier.dstQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+        vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_ALL_COMMANDS_BIT, 0,
+                             0, nullptr, 1, &barrier, 0, nullptr);
+        return;
+    }
+    
+    // Queue family ownership release; the acquire half runs on the graphics queue
+    barrier.dstAccessMask = 0;
+    barrier.srcQueueFamilyIndex = g_transferQueueFamilyIndex;
+    barrier.dstQueueFamilyIndex = g_graphicsQueueFamilyIndex;
+    vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT, 0,
+                         0, nullptr, 1, &barrier, 0, nullptr);
+    
+    VkBufferMemoryBarrier acquire = barrier;
+    acquire.srcAccessMask = 0;
+    acquire.dstAccessMask = VK_ACCESS_MEMORY_READ_BIT;
+    g_recordingTransfers[cmd].bufferAcquires.push_back(acquire);
+}
+
+extern "C" void heidic_transfer_release_image(VkCommandBuffer cmd, VkImage image, VkImageAspectFlags aspect,
+                                              uint32_t mipLevels, uint32_t layerCount, VkImageLayout finalLayout) {
+    VkImageMemoryBarrier barrier = {};
+    barrier.sType = VK_STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER;
+    barrier.oldLayout = VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL;
+    barrier.newLayout = finalLayout;
+    barrier.image = image;
+    barrier.subresourceRange.aspectMask = aspect;
+    barrier.subresourceRange.baseMipLevel = 0;
+    barrier.subresourceRange.levelCount = mipLevels;
+    barrier.subresourceRange.baseArrayLayer = 0;
+    barrier.subresourceRange.layerCount = layerCount;
+    barrier.srcAccessMask = VK_ACCESS_TRANSFER_WRITE_BIT;
+    
+    if (!heidic_has_dedicated_transfer_queue()) {
+        barrier.dstAccessMask = VK_ACCESS_SHADER_READ_BIT;
+        barrier.srcQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+        barrier.dstQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+        vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_ALL_COMMANDS_BIT, 0,
+                             0, nullptr, 0, nullptr, 1, &barrier);
+        return;
+    }
+    
+    // Release with the layout transition; the identical acquire barrier runs on the graphics queue
+    barrier.dstAccessMask = 0;
+    barrier.srcQueueFamilyIndex = g_transferQueueFamilyIndex;
+    barrier.dstQueueFamilyIndex = g_graphicsQueueFamilyIndex;
+    vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT, 0,
+                         0, nullptr, 0, nullptr, 1, &barrier);
+    
+    VkImageMemoryBarrier acquire = barrier;
+    acquire.srcAccessMask = 0;
+    acquire.dstAccessMask = VK_ACCESS_SHADER_READ_BIT;
+    g_recordingTransfers[cmd].imageAcquires.push_back(acquire);
+}
+
+extern "C" HeidicTransferTicket heidic_submit_transfer_commands(VkCommandBuffer cmd) {
+    auto it = g_recordingTransfers.find(cmd);
+    if (it == g_recordingTransfers.end()) {
+        std::cerr << "[EDEN] ERROR: heidic_submit_transfer_commands() called with a command buffer not from heidic_begin_transfer()" << std::endl;
+        return HeidicTransferTicket{ 0 };
+    }
+    PendingTransfer transfer = std::move(it->second);
+    g_recordingTransfers.erase(it);
+    
+    vkEndCommandBuffer(cmd);
+    
+    VkFenceCreateInfo fenceInfo = {};
+    fenceInfo.sType = VK_STRUCTURE_TYPE_FENCE_CREATE_INFO;
+    vkCreateFence(g_device, &fenceInfo, nullptr, &transfer.fence);
+    
+    VkSubmitInfo submitInfo = {};
+    submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
+    submitInfo.commandBufferCount = 1;
+    submitInfo.pCommandBuffers = &cmd;
+    vkQueueSubmit(g_transferQueue, 1, &submitInfo, transfer.fence);
+    
+    uint64_t id = g_nextTransferTicket++;
+    g_inFlightTransfers[id] = std::move(transfer);
+    return HeidicTransferTicket{ id };
+}
+
+extern "C" void heidic_queue_buffer_upload(VkBuffer dst, VkDeviceSize offset, const void* data, VkDeviceSize size) {
+    if (g_streamingBatch == VK_NULL_HANDLE) {
+        g_streamingBatch = heidic_begin_transfer();
+    }
+    StagingAllocation staging = createStagingBuffer(data, size);
+    g_recordingTransfers[g_streamingBatch].staging.push_back(staging);
+    
+    VkBufferCopy region = {};
+    region.dstOffset = offset;
+    region.size = size;
+    vkCmdCopyBuffer(g_streamingBatch, staging.buffer, dst, 1, &region);
+    heidic_transfer_release_buffer(g_streamingBatch, dst, offset, size);
+}
+
+extern "C" HeidicTransferTicket submit_transfer() {
+    if (g_streamingBatch == VK_NULL_HANDLE) {
+        return HeidicTransferTicket{ 0 };
+    }
+    VkCommandBuffer cmd = g_streamingBatch;
+    g_streamingBatch = VK_NULL_HANDLE;
+    return heidic_submit_transfer_commands(cmd);
+}
+
+extern "C" bool transfer_ready(HeidicTransferTicket ticket) {
+    auto it = g_inFlightTransfers.find(ticket.id);
+    if (it == g_inFlightTransfers.end()) {
+        return true;  // Never submitted or already retired
+    }
+    if (vkGetFenceStatus(g_device, it->second.fence) != VK_SUCCESS) {
+        return false;
+    }
+    retireTransfer(ticket.id);
+    return true;
+}
+
+extern "C" void wait_transfer(HeidicTransferTicket ticket) {
+    auto it = g_inFlightTransfers.find(ticket.id);
+    if (it == g_inFlightTransfers.end()) {
+        return;
+    }
+    vkWaitForFences(g_device, 1, &it->second.fence, VK_TRUE, UINT64_MAX);
+    retireTransfer(ticket.id);
+}
+
+extern "C" void heidic_upload_buffer(VkBuffer dst, VkDeviceSize offset, const void* data, VkDeviceSize size) {
+    VkCommandBuffer cmd = heidic_begin_transfer();
+    StagingAllocation staging = createStagingBuffer(data, size);
+    g_recordingTransfers[cmd].staging.push_back(staging);
+    
+    VkBufferCopy region = {};
+    region.dstOffset = offset;
+    region.size = size;
+    vkCmdCopyBuffer(cmd, staging.buffer, dst, 1, &region);
+    heidic_transfer_release_buffer(cmd, dst, offset, size);
+    
+    wait_transfer(heidic_submit_transfer_commands(cmd));
+}
+
+// =============================================================================
+// GPU storage buffers (upload / readback)
+// =============================================================================
+
+static std::vector<HeidicGpuBuffer*> g_gpuBuffers;  // Every buffer allocated so far, freed at cleanup
+
+static void releaseGpuBuffer(HeidicGpuBuffer* buffer) {
+    if (buffer->buffer != VK_NULL_HANDLE) {
+        vkDestroyBuffer(g_device, buffer->buffer, nullptr);
+        vkFreeMemory(g_device, buffer->memory, nullptr);
+    }
+    buffer->buffer = VK_NULL_HANDLE;
+    buffer->memory = VK_NULL_HANDLE;
+    buffer->size = 0;
+}
+
+static void destroyGpuBuffers() {
+    for (HeidicGpuBuffer* buffer : g_gpuBuffers) {
+        releaseGpuBuffer(buffer);
+    }
+    g_gpuBuffers.clear();
+}
+
+extern "C" void heidic_gpu_buffer_write(HeidicGpuBuffer* buffer, const void* data, VkDeviceSize size) {
+    if (g_device == VK_NULL_HANDLE || size == 0) {
+        return;
+    }
+    // Frames and dispatches already submitted may still read the old contents
+    vkQueueWaitIdle(g_graphicsQueue);
+    
+    VkDeviceSize capacity = 0;
+    if (buffer->buffer != VK_NULL_HANDLE) {
+        VkMemoryRequirements memRequirements;
+        vkGetBufferMemoryRequirements(g_device, buffer->buffer, &memRequirements);
+        capacity = memRequirements.size;
+    }
+    if (capacity < size) {
+        // Note: growing replaces the VkBuffer, so descriptor sets must be rewritten afterwards
+        releaseGpuBuffer(buffer);
+        createBuffer(size, VK_BUFFER_USAGE_STORAGE_BUFFER_BIT | VK_BUFFER_USAGE_VERTEX_BUFFER_BIT |
+                     VK_BUFFER_USAGE_TRANSFER_DST_BIT | VK_BUFFER_USAGE_TRANSFER_SRC_BIT,
+                     VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT, buffer->buffer, buffer->memory);
+        if (std::find(g_gpuBuffers.begin(), g_gpuBuffers.end(), buffer) == g_gpuBuffers.end()) {
+            g_gpuBuffers.push_back(buffer);
+        }
+    }
+    
+    heidic_upload_buffer(buffer->buffer, 0, data, size);
+    buffer->size = size;
+}
+
+extern "C" VkDeviceSize heidic_gpu_buffer_read(HeidicGpuBuffer* buffer, void* dst, VkDeviceSize maxSize) {
+    VkDeviceSize size = buffer->size < maxSize ? buffer->size : maxSize;
+    if (buffer->buffer == VK_NULL_HANDLE || size == 0) {
+        return 0;
+    }
+    
+    VkBuffer readbackBuffer;
+    VkDeviceMemory readbackMemory;
+    createBuffer(size, VK_BUFFER_USAGE_TRANSFER_DST_BIT,
+                 VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
+                 readbackBuffer, readbackMemory);
+    
+    VkCommandBufferAllocateInfo allocInfo = {};
+    allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
+    allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
+    allocInfo.commandPool = g_commandPool;
+    allocInfo.commandBufferCount = 1;
+    VkCommandBuffer cmd;
+    vkAllocateCommandBuffers(g_device, &allocInfo, &cmd);
+    
+    VkCommandBufferBeginInfo beginInfo = {};
+    beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
+    beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
+    vkBeginCommandBuffer(cmd, &beginInfo);
+    
+    // Shader writes from earlier submissions on this queue must land before the copy reads them
+    VkBufferMemoryBarrier barrier = {};
+    barrier.sType = VK_STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER;
+    barrier.srcAccessMask = VK_ACCESS_SHADER_WRITE_BIT | VK_ACCESS_TRANSFER_WRITE_BIT;
+    barrier.dstAccessMask = VK_ACCESS_TRANSFER_READ_BIT;
+    barrier.srcQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+    barrier.dstQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+    barrier.buffer = buffer->buffer;
+    barrier.offset = 0;
+    barrier.size = size;
+    vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_ALL_COMMANDS_BIT, VK_PIPELINE_STAGE_TRANSFER_BIT, 0,
+                         0, nullptr, 1, &barrier, 0, nullptr);
+    
+    VkBufferCopy region = {};
+    region.size = size;
+    vkCmdCopyBuffer(cmd, buffer->buffer, readbackBuffer, 1, &region);
+    
+    VkBufferMemoryBarrier hostBarrier = barrier;
+    hostBarrier.srcAccessMask = VK_ACCESS_TRANSFER_WRITE_BIT;
+    hostBarrier.dstAccessMask = VK_ACCESS_HOST_READ_BIT;
+    hostBarrier.buffer = readbackBuffer;
+    vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_HOST_BIT, 0,
+                         0, nullptr, 1, &hostBarrier, 0, nullptr);
+    vkEndCommandBuffer(cmd);
+    
+    VkFenceCreateInfo fenceInfo = {};
+    fenceInfo.sType = VK_STRUCTURE_TYPE_FENCE_CREATE_INFO;
+    VkFence fence;
+    vkCreateFence(g_device, &fenceInfo, nullptr, &fence);
+    
+    VkSubmitInfo submitInfo = {};
+    submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
+    submitInfo.commandBufferCount = 1;
+    submitInfo.pCommandBuffers = &cmd;
+    vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, fence);
+    vkWaitForFences(g_device, 1, &fence, VK_TRUE, UINT64_MAX);
+    
+    void* mapped;
+    vkMapMemory(g_device, readbackMemory, 0, size, 0, &mapped);
+    memcpy(dst, mapped, (size_t)size);
+    vkUnmapMemory(g_device, readbackMemory);
+    
+    vkDestroyFence(g_device, fence, nullptr);
+    vkFreeCommandBuffers(g_device, g_commandPool, 1, &cmd);
+    vkDestroyBuffer(g_device, readbackBuffer, nullptr);
+    vkFreeMemory(g_device, readbackMemory, nullptr);
+    return size;
+}
+
+// =============================================================================
+// Compute kernels (--gpu-backend=vulkan)
+// =============================================================================
+
+struct ComputeKernelState {
+    VkDescriptorSetLayout setLayout = VK_NULL_HANDLE;
+    VkPipelineLayout layout = VK_NULL_HANDLE;
+    VkPipeline pipeline = VK_NULL_HANDLE;
+    VkDescriptorPool pool = VK_NULL_HANDLE;
+    VkDescriptorSet set = VK_NULL_HANDLE;
+    VkCommandBuffer cmd = VK_NULL_HANDLE;
+    VkFence fence = VK_NULL_HANDLE;  // Signalled once the last dispatch finished
+    bool failed = false;             // Reported once; later dispatches are skipped
+};
+
+static std::vector<HeidicComputeKernel*> g_computeKernels;  // Every kernel created so far, destroyed at cleanup
+
+static void destroyComputeKernel(HeidicComputeKernel* kernel) {
+    ComputeKernelState* state = static_cast<ComputeKernelState*>(kernel->state);
+    if (!state) {
+        return;
+    }
+    if (state->fence != VK_NULL_HANDLE) {
+        vkWaitForFences(g_device, 1, &state->fence, VK_TRUE, UINT64_MAX);
+        vkDestroyFence(g_device, state->fence, nullptr);
+    }
+    if (state->cmd != VK_NULL_HANDLE) {
+        vkFreeCommandBuffers(g_device, g_commandPool, 1, &state->cmd);
+    }
+    if (state->pool != VK_NULL_HANDLE) {
+        vkDestroyDescriptorPool(g_device, state->pool, nullptr);
+    }
+    if (state->pipeline != VK_NULL_HANDLE) {
+        vkDestroyPipeline(g_device, state->pipeline, nullptr);
+    }
+    if (state->layout != VK_NULL_HANDLE) {
+        vkDestroyPipelineLayout(g_device, state->layout, nullptr);
+    }
+    if (state->setLayout != VK_NULL_HANDLE) {
+        vkDestroyDescriptorSetLayout(g_device, state->setLayout, nullptr);
+    }
+    delete state;
+    kernel->state = nullptr;
+}
+
+static void destroyComputeKernels() {
+    for (HeidicComputeKernel* kernel : g_computeKernels) {
+        destroyComputeKernel(kernel);
+    }
+    g_computeKernels.clear();
+}
+
+static bool createComputeKernel(HeidicComputeKernel* kernel, ComputeKernelState& state) {
+    std::vector<char> code;
+    try {
+        code = readFile(kernel->spirv_path);
+    } catch (const std::exception& e) {
+        std::cerr << "[compute] " << kernel->name << ": " << e.what() << " (build it with glslc, or recompile the .hd with glslc installed)" << std::endl;
+        return false;
+    }
+    VkShaderModuleCreateInfo moduleInfo = {};
+    moduleInfo.sType = VK_STRUCTURE_TYPE_SHADER_MODULE_CREATE_INFO;
+    moduleInfo.codeSize = code.size();
+    moduleInfo.pCode = reinterpret_cast<const uint32_t*>(code.data());
+    VkShaderModule module;
+    if (vkCreateShaderModule(g_device, &moduleInfo, nullptr, &module) != VK_SUCCESS) {
+        std::cerr << "[compute] " << kernel->name << ": failed to create the shader module from " << kernel->spirv_path << std::endl;
+        return false;
+    }
+    
+    std::vector<VkDescriptorSetLayoutBinding> bindings(kernel->buffer_count);
+    for (uint32_t i = 0; i < kernel->buffer_count; ++i) {
+        bindings[i] = {};
+        bindings[i].binding = i;
+        bindings[i].descriptorType = VK_DESCRIPTOR_TYPE_STORAGE_BUFFER;
+        bindings[i].descriptorCount = 1;
+        bindings[i].stageFlags = VK_SHADER_STAGE_COMPUTE_BIT;
+    }
+    VkDescriptorSetLayoutCreateInfo setLayoutInfo = {};
+    setLayoutInfo.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_CREATE_INFO;
+    setLayoutInfo.bindingCount = kernel->buffer_count;
+    setLayoutInfo.pBindings = bindings.data();
+    
+    VkPushConstantRange pushRange = {};
+    pushRange.stageFlags = VK_SHADER_STAGE_COMPUTE_BIT;
+    pushRange.size = kernel->push_size;
+    VkPipelineLayoutCreateInfo layoutInfo = {};
+    layoutInfo.sType = VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO;
+    layoutInfo.setLayoutCount = 1;
+    layoutInfo.pSetLayouts = &state.setLayout;
+    layoutInfo.pushConstantRangeCount = 1;
+    layoutInfo.pPushConstantRanges = &pushRange;
+    
+    VkComputePipelineCreateInfo pipelineInfo = {};
+    pipelineInfo.sType = VK_STRUCTURE_TYPE_COMPUTE_PIPELINE_CREATE_INFO;
+    pipelineInfo.stage.sType = VK_STRUCTURE_TYPE_PIPELINE_SHADER_STAGE_CREATE_INFO;
+    pipelineInfo.stage.stage = VK_SHADER_STAGE_COMPUTE_BIT;
+    pipelineInfo.stage.module = module;
+    pipelineInfo.stage.pName = "main";
+    
+    VkDescriptorPoolSize poolSize = {};
+    poolSize.type = VK_DESCRIPTOR_TYPE_STORAGE_BUFFER;
+    poolSize.descriptorCount = kernel->buffer_count;
+    VkDescriptorPoolCreateInfo poolInfo = {};
+    poolInfo.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO;
+    poolInfo.maxSets = 1;
+    poolInfo.poolSizeCount = 1;
+    poolInfo.pPoolSizes = &poolSize;
+    
+    bool created = vkCreateDescriptorSetLayout(g_device, &setLayoutInfo, nullptr, &state.setLayout) == VK_SUCCESS
+        && vkCreatePipelineLayout(g_device, &layoutInfo, nullptr, &state.layout) == VK_SUCCESS;
+    if (created) {
+        pipelineInfo.layout = state.layout;
+        created = vkCreateComputePipelines(g_device, VK_NULL_HANDLE, 1, &pipelineInfo, nullptr, &state.pipeline) == VK_SUCCESS;
+    }
+    vkDestroyShaderModule(g_device, module, nullptr);
+    if (created && vkCreateDescriptorPool(g_device, &poolInfo, nullptr, &state.pool) == VK_SUCCESS) {
+        VkDescriptorSetAllocateInfo setInfo = {};
+        setInfo.sType = VK_STRUCTURE_TYPE_DESCRIPTOR_SET_ALLOCATE_INFO;
+        setInfo.descriptorPool = state.pool;
+        setInfo.descriptorSetCount = 1;
+        setInfo.pSetLayouts = &state.setLayout;
+        VkCommandBufferAllocateInfo cmdInfo = {};
+        cmdInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
+        cmdInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
+        cmdInfo.commandPool = g_commandPool;
+        cmdInfo.commandBufferCount = 1;
+        VkFenceCreateInfo fenceInfo = {};
+        fenceInfo.sType = VK_STRUCTURE_TYPE_FENCE_CREATE_INFO;
+        fenceInfo.flags = VK_FENCE_CREATE_SIGNALED_BIT;
+        created = vkAllocateDescriptorSets(g_device, &setInfo, &state.set) == VK_SUCCESS
+            && vkAllocateCommandBuffers(g_device, &cmdInfo, &state.cmd) == VK_SUCCESS
+            && vkCreateFence(g_device, &fenceInfo, nullptr, &state.fence) == VK_SUCCESS;
+    } else {
+        created = false;
+    }
+    if (!created) {
+        std::cerr << "[compute] " << kernel->name << ": failed to create its compute pipeline (does the device support what the shader uses, e.g. f64?)" << std::endl;
+    }
+    return created;
+}
+
+extern "C" bool heidic_compute_dispatch(HeidicComputeKernel* kernel, HeidicGpuBuffer* const* buffers, const void* push, size_t count) {
+    if (g_device == VK_NULL_HANDLE || count == 0) {
+        return false;
+    }
+    ComputeKernelState* state = static_cast<ComputeKernelState*>(kernel->state);
+    if (!state) {
+        state = new ComputeKernelState();
+        kernel->state = state;
+        g_computeKernels.push_back(kernel);
+        state->failed = !createComputeKernel(kernel, *state);
+    }
+    if (state->failed) {
+        return false;
+    }
+    
+    // The previous dispatch must be done with the descriptor set and command buffer; uploads may
+    // also have replaced the buffers since, so the bindings are written again every time
+    vkWaitForFences(g_device, 1, &state->fence, VK_TRUE, UINT64_MAX);
+    vkResetFences(g_device, 1, &state->fence);
+    std::vector<VkDescriptorBufferInfo> infos(kernel->buffer_count);
+    std::vector<VkWriteDescriptorSet> writes(kernel->buffer_count);
+    for (uint32_t i = 0; i < kernel->buffer_count; ++i) {
+        infos[i] = {};
+        infos[i].buffer = buffers[i]->buffer;
+        infos[i].range = VK_WHOLE_SIZE;
+        writes[i] = {};
+        writes[i].sType = VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET;
+        writes[i].dstSet = state->set;
+        writes[i].dstBinding = i;
+        writes[i].descriptorCount = 1;
+        writes[i].descriptorType = VK_DESCRIPTOR_TYPE_STORAGE_BUFFER;
+        writes[i].pBufferInfo = &infos[i];
+    }
+    vkUpdateDescriptorSets(g_device, kernel->buffer_count, writes.data(), 0, nullptr);
+    
+    VkCommandBufferBeginInfo beginInfo = {};
+    beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
+    beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
+    vkResetCommandBuffer(state->cmd, 0);
+    vkBeginCommandBuffer(state->cmd, &beginInfo);
+    // The uploads were copied in before this submission; make them visible to the shader
+    VkMemoryBarrier barrier = {};
+    barrier.sType = VK_STRUCTURE_TYPE_MEMORY_BARRIER;
+    barrier.srcAccessMask = VK_ACCESS_TRANSFER_WRITE_BIT;
+    barrier.dstAccessMask = VK_ACCESS_SHADER_READ_BIT | VK_ACCESS_SHADER_WRITE_BIT;
+    vkCmdPipelineBarrier(state->cmd, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_COMPUTE_SHADER_BIT, 0,
+                         1, &barrier, 0, nullptr, 0, nullptr);
+    vkCmdBindPipeline(state->cmd, VK_PIPELINE_BIND_POINT_COMPUTE, state->pipeline);
+    vkCmdBindDescriptorSets(state->cmd, VK_PIPELINE_BIND_POINT_COMPUTE, state->layout, 0, 1, &state->set, 0, nullptr);
+    vkCmdPushConstants(state->cmd, state->layout, VK_SHADER_STAGE_COMPUTE_BIT, 0, kernel->push_size, push);
+    // Every device dispatches at least 65535 groups; the shader's loop covers any entities beyond
+    size_t groups = (count + HEIDIC_COMPUTE_GROUP_SIZE - 1) / HEIDIC_COMPUTE_GROUP_SIZE;
+    vkCmdDispatch(state->cmd, (uint32_t)(groups < 65535 ? groups : 65535), 1, 1);
+    vkEndCommandBuffer(state->cmd);
+    
+    VkSubmitInfo submitInfo = {};
+    submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
+    submitInfo.commandBufferCount = 1;
+    submitInfo.pCommandBuffers = &state->cmd;
+    if (vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, state->fence) != VK_SUCCESS) {
+        std::cerr << "[compute] " << kernel->name << ": dispatch submission failed" << std::endl;
+        return false;
+    }
+    return true;
+}
+
+// =============================================================================
+// Uniform buffers (uniform blocks)
+// =============================================================================
+
+static std::vector<HeidicUniformBuffer*> g_uniformBuffers;  // Every buffer created so far, freed at cleanup
+
+static void destroyUniformBuffers() {
+    for (HeidicUniformBuffer* buffer : g_uniformBuffers) {
+        vkUnmapMemory(g_device, buffer->memory);
+        vkDestroyBuffer(g_device, buffer->buffer, nullptr);
+        vkFreeMemory(g_device, buffer->memory, nullptr);
+        buffer->buffer = VK_NULL_HANDLE;
+        buffer->memory = VK_NULL_HANDLE;
+        buffer->mapped = nullptr;
+    }
+    g_uniformBuffers.clear();
+}
+
+extern "C" VkBuffer heidic_uniform_buffer_handle(HeidicUniformBuffer* buffer) {
+    if (buffer->buffer == VK_NULL_HANDLE && g_device != VK_NULL_HANDLE) {
+        createBuffer(buffer->size, VK_BUFFER_USAGE_UNIFORM_BUFFER_BIT,
+                     VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
+                     buffer->buffer, buffer->memory);
+        vkMapMemory(g_device, buffer->memory, 0, buffer->size, 0, &buffer->mapped);
+        g_uniformBuffers.push_back(buffer);
+    }
+    return buffer->buffer;
+}
+
+extern "C" void heidic_uniform_buffer_write(HeidicUniformBuffer* buffer, const void* data) {
+    if (heidic_uniform_buffer_handle(buffer) == VK_NULL_HANDLE) {
+        std::cerr << "[Uniform " << buffer->name << "] ERROR: updated before the Vulkan device exists!" << std::endl;
+        return;
+    }
+    memcpy(buffer->mapped, data, (size_t)buffer->size);
+}
+
+// =============================================================================
+// GPU queries (occlusion, timestamps, pipeline statistics)
+// =============================================================================
+
+struct GpuQuerySlot {
+    bool open = false;     // Between heidic_begin_query() and heidic_end_query()
+    bool armed = false;    // Query begun in the render pass being recorded
+    bool pending = false;  // Submitted, result not read back yet
+    int64_t result = -1;   // Latest completed result
+};
+
+struct GpuQueryPoolState {
+    VkQueryPool pool = VK_NULL_HANDLE;
+    int32_t kind = HEIDIC_QUERY_OCCLUSION;
+    uint32_t queriesPerSlot = 1;  // Timestamp slots hold a begin and an end timestamp
+    std::vector<GpuQuerySlot> slots;
+};
+
+static std::vector<GpuQueryPoolState> g_queryPools;
+
+// Create the Vulkan pool behind a generated HeidicQueryPool on first use
+static GpuQuerySlot* resolveQuerySlot(HeidicQueryPool* desc, int32_t index) {
+    if (g_device == VK_NULL_HANDLE) {
+        return nullptr;
+    }
+    if (index < 0 || (uint32_t)index >= desc->count) {
+        std::cerr << "[EDEN] ERROR: Query index " << index << " out of range for gpu_query " << desc->name
+                  << " (" << desc->count << " slots)" << std::endl;
+        return nullptr;
+    }
+    if (desc->handle < 0 || (size_t)desc->handle >= g_queryPools.size()) {
+        GpuQueryPoolState state;
+        state.kind = desc->kind;
+        state.queriesPerSlot = desc->kind == HEIDIC_QUERY_TIMESTAMP ? 2 : 1;
+        state.slots.resize(desc->count);
+        
+        VkQueryPoolCreateInfo poolInfo = {};
+        poolInfo.sType = VK_STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO;
+        poolInfo.queryCount = desc->count * state.queriesPerSlot;
+        switch (desc->kind) {
+            case HEIDIC_QUERY_TIMESTAMP: {
+                VkPhysicalDeviceProperties props;
+                vkGetPhysicalDeviceProperties(g_physicalDevice, &props);
+                if (!props.limits.timestampComputeAndGraphics) {
+                    std::cerr << "[EDEN] WARNING: GPU does not support timestamps on graphics queues (gpu_query " << desc->name << ")" << std::endl;
+                }
+                poolInfo.queryType = VK_QUERY_TYPE_TIMESTAMP;
+                break;
+            }
+            case HEIDIC_QUERY_PIPELINE_STATISTICS:
+                if (!g_enabledCoreFeatures.pipelineStatisticsQuery) {
+                    std::cerr << "[EDEN] ERROR: gpu_query " << desc->name << " needs the pipelineStatisticsQuery feature" << std::endl;
+                    return nullptr;
+                }
+                poolInfo.queryType = VK_QUERY_TYPE_PIPELINE_STATISTICS;
+                poolInfo.pipelineStatistics = desc->statistic;
+                break;
+            default:
+                poolInfo.queryType = VK_QUERY_TYPE_OCCLUSION;
+                break;
+        }
+        if (vkCreateQueryPool(g_device, &poolInfo, nullptr, &state.pool) != VK_SUCCESS) {
+            std::cerr << "[EDEN] ERROR: Failed to create query pool for gpu_query " << desc->name << std::endl;
+            return nullptr;
+        }
+        desc->handle = (int32_t)g_queryPools.size();
+        g_queryPools.push_back(std::move(state));
+    }
+    return &g_queryPools[desc->handle].slots[index];
+}
+
+// Read back a submitted slot without waiting; returns false if the GPU isn't done with it
+static bool readQuerySlot(GpuQueryPoolState& state, uint32_t slot) {
+    uint64_t data[4] = {};  // (value, availability) per query
+    uint32_t first = slot * state.queriesPerSlot;
+    VkResult result = vkGetQueryPoolResults(g_device, state.pool, first, state.queriesPerSlot, sizeof(data), data,
+                                            2 * sizeof(uint64_t), VK_QUERY_RESULT_64_BIT | VK_QUERY_RESULT_WITH_AVAILABILITY_BIT);
+    if (result != VK_SUCCESS && result != VK_NOT_READY) {
+        return false;
+    }
+    if (state.queriesPerSlot == 2) {
+        if (!data[1] || !data[3]) {
+            return false;
+        }
+        VkPhysicalDeviceProperties props;
+        vkGetPhysicalDeviceProperties(g_physicalDevice, &props);
+        double ticks = (double)(data[2] - data[0]);
+        state.slots[slot].result = (int64_t)(ticks * props.limits.timestampPeriod);
+    } else {
+        if (!data[1]) {
+            return false;
+        }
+        state.slots[slot].result = (int64_t)data[0];
+    }
+    return true;
+}
+
+// Begin the scene render pass: collect finished results, reset and arm the open query slots
+static void beginScenePass(VkCommandBuffer cmd, const VkRenderPassBeginInfo* renderPassInfo) {
+    for (auto& state : g_queryPools) {
+        for (uint32_t i = 0; i < state.slots.size(); i++) {
+            GpuQuerySlot& slot = state.slots[i];
+            if (slot.pending && readQuerySlot(state, i)) {
+                slot.pending = false;
+            }
+            // A slot still in flight is skipped this frame rather than reset under the GPU
+            if (slot.open && !slot.pending) {
+                vkCmdResetQueryPool(cmd, state.pool, i * state.queriesPerSlot, state.queriesPerSlot);
+                slot.armed = true;
+            }
+        }
+    }
+    
+    vkCmdBeginRenderPass(cmd, renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    
+    for (auto& state : g_queryPools) {
+        for (uint32_t i = 0; i < state.slots.size(); i++) {
+            if (!state.slots[i].armed) {
+                continue;
+            }
+            if (state.kind == HEIDIC_QUERY_TIMESTAMP) {
+                vkCmdWriteTimestamp(cmd, VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT, state.pool, i * 2);
+            } else {
+                VkQueryControlFlags flags = 0;
+                if (state.kind == HEIDIC_QUERY_OCCLUSION && g_enabledCoreFeatures.occlusionQueryPrecise) {
+                    flags = VK_QUERY_CONTROL_PRECISE_BIT;
+                }
+                vkCmdBeginQuery(cmd, state.pool, i, flags);
+            }
+        }
+    }
+}
+
+static void endScenePass(VkCommandBuffer cmd) {
+    for (auto& state : g_queryPools) {
+        for (uint32_t i = 0; i < state.slots.size(); i++) {
+            GpuQuerySlot& slot = state.slots[i];
+            if (!slot.armed) {
+                continue;
+            }
+            if (state.kind == HEIDIC_QUERY_TIMESTAMP) {
+                vkCmdWriteTimestamp(cmd, VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT, state.pool, i * 2 + 1);
+            } else {
+                vkCmdEndQuery(cmd, state.pool, i);
+            }
+            slot.armed = false;
+            slot.pending = true;
+        }
+    }
+    
+    vkCmdEndRenderPass(cmd);
+}
+
+static void destroyQueryPools() {
+    for (auto& state : g_queryPools) {
+        vkDestroyQueryPool(g_device, state.pool, nullptr);
+    }
+    g_queryPools.clear();
+}
+
+extern "C" void heidic_begin_query(HeidicQueryPool* pool, int32_t index) {
+    if (GpuQuerySlot* slot = resolveQuerySlot(pool, index)) {
+        slot->open = true;
+    }
+}
+
+extern "C" void heidic_end_query(HeidicQueryPool* pool, int32_t index) {
+    if (GpuQuerySlot* slot = resolveQuerySlot(pool, index)) {
+        slot->open = false;
+    }
+}
+
+extern "C" int64_t heidic_query_result(HeidicQueryPool* pool, int32_t index) {
+    GpuQuerySlot* slot = resolveQuerySlot(pool, index);
+    return slot ? slot->result : -1;
+}
+
+// =============================================================================
+// Frame capture (screenshot(), golden-image render tests)
+// =============================================================================
+
+static std::string g_pendingScreenshot;       // Written when the next frame is presented
+static bool g_captureEveryFrame = false;      // heidic_set_frame_capture
+static bool g_updateGolden = false;           // heidic_set_update_golden
+static std::vector<uint8_t> g_capturedFrame;  // Last captured frame, RGBA8
+static uint32_t g_capturedWidth = 0;
+static uint32_t g_capturedHeight = 0;
+static bool g_swapchainReadable = false;      // Swapchain created with TRANSFER_SRC usage
+static int32_t g_renderMode = HEIDIC_RENDER_WINDOWED;
+
+// Window hints only take effect after glfwInit, so the mode is applied by heidic_glfw_vulkan_hints()
+extern "C" void heidic_set_render_mode(int32_t mode) {
+    g_renderMode = mode;
+}
+
+extern "C" void heidic_screenshot(const char* path) {
+    g_pendingScreenshot = path;
+}
+
+extern "C" void heidic_set_frame_capture(int32_t enabled) {
+    g_captureEveryFrame = enabled != 0;
+}
+
+extern "C" void heidic_set_update_golden(int32_t update) {
+    g_updateGolden = update != 0;
+}
+
+// PNG with uncompressed (stored) deflate blocks: larger than a real encoder's output, but
+// lossless and readable by any image tool, which is all screenshots and references need
+static uint32_t pngCrc(uint32_t crc, const uint8_t* data, size_t size) {
+    static uint32_t table[256];
+    if (table[1] == 0) {
+        for (uint32_t n = 0; n < 256; n++) {
+            uint32_t c = n;
+            for (int k = 0; k < 8; k++) {
+                c = (c & 1) ? 0xEDB88320u ^ (c >> 1) : c >> 1;
+            }
+            table[n] = c;
+        }
+    }
+    crc = ~crc;
+    for (size_t i = 0; i < size; i++) {
+        crc = table[(crc ^ data[i]) & 0xFF] ^ (crc >> 8);
+    }
+    return ~crc;
+}
+
+static void pngChunk(std::vector<uint8_t>& out, const char* type, const std::vector<uint8_t>& data) {
+    uint32_t length = (uint32_t)data.size();
+    uint8_t header[8] = { (uint8_t)(length >> 24), (uint8_t)(length >> 16), (uint8_t)(length >> 8), (uint8_t)length,
+                          (uint8_t)type[0], (uint8_t)type[1], (uint8_t)type[2], (uint8_t)type[3] };
+    out.insert(out.end(), header, header + 8);
+    out.insert(out.end(), data.begin(), data.end());
+    uint32_t crc = pngCrc(pngCrc(0, header + 4, 4), data.data(), data.size());
+    uint8_t trailer[4] = { (uint8_t)(crc >> 24), (uint8_t)(crc >> 16), (uint8_t)(crc >> 8), (uint8_t)crc };
+    out.insert(out.end(), trailer, trailer + 4);
+}
+
+static bool writePng(const std::string& path, const uint8_t* rgba, uint32_t width, uint32_t height) {
+    // Scanlines, each prefixed with filter type 0 (none)
+    std::vector<uint8_t> raw;
+    raw.reserve((size_t)(width * 4 + 1) * height);
+    for (uint32_t y = 0; y < height; y++) {
+        raw.push_back(0);
+        raw.insert(raw.end(), rgba + (size_t)y * width * 4, rgba + (size_t)(y + 1) * width * 4);
+    }
+    
+    std::vector<uint8_t> zlib = { 0x78, 0x01 };
+    size_t offset = 0;
+    do {
+        size_t blockSize = std::min<size_t>(raw.size() - offset, 65535);
+        bool last = offset + blockSize == raw.size();
+        zlib.push_back(last ? 1 : 0);
+        zlib.push_back((uint8_t)blockSize);
+        zlib.push_back((uint8_t)(blockSize >> 8));
+        zlib.push_back((uint8_t)~blockSize);
+        zlib.push_back((uint8_t)(~blockSize >> 8));
+        zlib.insert(zlib.end(), raw.begin() + offset, raw.begin() + offset + blockSize);
+        offset += blockSize;
+    } while (offset < raw.size());
+    uint32_t a = 1, b = 0;
+    for (uint8_t byte : raw) {
+        a = (a + byte) % 65521;
+        b = (b + a) % 65521;
+    }
+    uint32_t adler = (b << 16) | a;
+    zlib.insert(zlib.end(), { (uint8_t)(adler >> 24), (uint8_t)(adler >> 16), (uint8_t)(adler >> 8), (uint8_t)adler });
+    
+    std::vector<uint8_t> ihdr = {
+        (uint8_t)(width >> 24), (uint8_t)(width >> 16), (uint8_t)(width >> 8), (uint8_t)width,
+        (uint8_t)(height >> 24), (uint8_t)(height >> 16), (uint8_t)(height >> 8), (uint8_t)height,
+        8, 6, 0, 0, 0,  // 8-bit RGBA, deflate, no filtering extensions, no interlace
+    };
+    std::vector<uint8_t> png = { 0x89, 'P', 'N', 'G', '\r', '\n', 0x1A, '\n' };
+    pngChunk(png, "IHDR", ihdr);
+    pngChunk(png, "IDAT", zlib);
+    pngChunk(png, "IEND", {});
+    
+    std::ofstream file(path, std::ios::binary);
+    if (!file) {
+        std::cerr << "[EDEN] ERROR: Could not write " << path << std::endl;
+        return false;
+    }
+    file.write((const char*)png.data(), (std::streamsize)png.size());
+    return true;
+}
+
+// Copy the swapchain image about to be presented into g_capturedFrame. Called after the
+// frame's submit and before vkQueuePresentKHR; the image is in PRESENT_SRC layout.
+static void captureBeforePresent(uint32_t imageIndex) {
+    if (g_pendingScreenshot.empty() && !g_captureEveryFrame) {
+        return;
+    }
+    if (!g_swapchainReadable) {
+        std::cerr << "[EDEN] ERROR: Frame capture unsupported: the surface doesn't allow reading swapchain images" << std::endl;
+        g_pendingScreenshot.clear();
+        g_captureEveryFrame = false;
+        return;
+    }
+    vkQueueWaitIdle(g_graphicsQueue);
+    
+    uint32_t width = g_swapchainExtent.width;
+    uint32_t height = g_swapchainExtent.height;
+    VkDeviceSize size = (VkDeviceSize)width * height * 4;
+    VkBuffer readbackBuffer;
+    VkDeviceMemory readbackMemory;
+    createBuffer(size, VK_BUFFER_USAGE_TRANSFER_DST_BIT,
+                 VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT,
+                 readbackBuffer, readbackMemory);
+    
+    VkCommandBufferAllocateInfo allocInfo = {};
+    allocInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO;
+    allocInfo.level = VK_COMMAND_BUFFER_LEVEL_PRIMARY;
+    allocInfo.commandPool = g_commandPool;
+    allocInfo.commandBufferCount = 1;
+    VkCommandBuffer cmd;
+    vkAllocateCommandBuffers(g_device, &allocInfo, &cmd);
+    
+    VkCommandBufferBeginInfo beginInfo = {};
+    beginInfo.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
+    beginInfo.flags = VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
+    vkBeginCommandBuffer(cmd, &beginInfo);
+    
+    VkImageMemoryBarrier barrier = {};
+    barrier.sType = VK_STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER;
+    barrier.srcAccessMask = VK_ACCESS_COLOR_ATTACHMENT_WRITE_BIT;
+    barrier.dstAccessMask = VK_ACCESS_TRANSFER_READ_BIT;
+    barrier.oldLayout = VK_IMAGE_LAYOUT_PRESENT_SRC_KHR;
+    barrier.newLayout = VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL;
+    barrier.srcQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+    barrier.dstQueueFamilyIndex = VK_QUEUE_FAMILY_IGNORED;
+    barrier.image = g_swapchainImages[imageIndex];
+    barrier.subresourceRange.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
+    barrier.subresourceRange.levelCount = 1;
+    barrier.subresourceRange.layerCount = 1;
+    vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT, VK_PIPELINE_STAGE_TRANSFER_BIT, 0,
+                         0, nullptr, 0, nullptr, 1, &barrier);
+    
+    VkBufferImageCopy region = {};
+    region.imageSubresource.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
+    region.imageSubresource.layerCount = 1;
+    region.imageExtent = { width, height, 1 };
+    vkCmdCopyImageToBuffer(cmd, g_swapchainImages[imageIndex], VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+                           readbackBuffer, 1, &region);
+    
+    // Back to the layout the present expects
+    barrier.srcAccessMask = VK_ACCESS_TRANSFER_READ_BIT;
+    barrier.dstAccessMask = 0;
+    barrier.oldLayout = VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL;
+    barrier.newLayout = VK_IMAGE_LAYOUT_PRESENT_SRC_KHR;
+    vkCmdPipelineBarrier(cmd, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT, 0,
+                         0, nullptr, 0, nullptr, 1, &barrier);
+    vkEndCommandBuffer(cmd);
+    
+    VkSubmitInfo submitInfo = {};
+    submitInfo.sType = VK_STRUCTURE_TYPE_SUBMIT_INFO;
+    submitInfo.commandBufferCount = 1;
+    submitInfo.pCommandBuffers = &cmd;
+    vkQueueSubmit(g_graphicsQueue, 1, &submitInfo, VK_NULL_HANDLE);
+    vkQueueWaitIdle(g_graphicsQueue);
+    
+    g_capturedFrame.resize((size_t)size);
+    void* mapped;
+    vkMapMemory(g_device, readbackMemory, 0, size, 0, &mapped);
+    memcpy(g_capturedFrame.data(), mapped, (size_t)size);
+    vkUnmapMemory(g_device, readbackMemory);
+    vkFreeCommandBuffers(g_device, g_commandPool, 1, &cmd);
+    vkDestroyBuffer(g_device, readbackBuffer, nullptr);
+    vkFreeMemory(g_device, readbackMemory, nullptr);
+    g_capturedWidth = width;
+    g_capturedHeight = height;
+    
+    // Swapchains are usually BGRA; images are stored RGBA and opaque
+    bool bgra = g_swapchainImageFormat == VK_FORMAT_B8G8R8A8_SRGB || g_swapchainImageFormat == VK_FORMAT_B8G8R8A8_UNORM;
+    for (size_t i = 0; i < g_capturedFrame.size(); i += 4) {
+        if (bgra) {
+            std::swap(g_capturedFrame[i], g_capturedFrame[i + 2]);
+        }
+        g_capturedFrame[i + 3] = 255;
+    }
+    
+    if (!g_pendingScreenshot.empty()) {
+        if (writePng(g_pendingScreenshot, g_capturedFrame.data(), width, height)) {
+            std::cout << "[EDEN] Screenshot saved: " << g_pendingScreenshot << std::endl;
+        }
+        g_pendingScreenshot.clear();
+    }
+}
+
+extern "C" int32_t heidic_expect_frame(const char* reference, float tolerance) {
+    if (g_capturedFrame.empty()) {
+        std::cerr << "[EDEN] expect_frame(\"" << reference << "\"): no frame has been presented yet" << std::endl;
+        return 0;
+    }
+    if (g_updateGolden) {
+        if (!writePng(reference, g_capturedFrame.data(), g_capturedWidth, g_capturedHeight)) {
+            return 0;
+        }
+        std::cout << "[EDEN] Updated reference image " << reference << std::endl;
+        return 1;
+    }
+    
+    std::string actualPath = std::string(reference) + ".actual.png";
+    int refWidth, refHeight, channels;
+    stbi_uc* expected = stbi_load(reference, &refWidth, &refHeight, &channels, 4);
+    if (!expected) {
+        std::cerr << "[EDEN] expect_frame: reference image " << reference << " is missing or unreadable"
+                  << " (create it with `heidic_v2 test --render --update-golden`)" << std::endl;
+        writePng(actualPath, g_capturedFrame.data(), g_capturedWidth, g_capturedHeight);
+        return 0;
+    }
+    
+    bool matches = false;
+    if ((uint32_t)refWidth != g_capturedWidth || (uint32_t)refHeight != g_capturedHeight) {
+        std::cerr << "[EDEN] expect_frame: frame is " << g_capturedWidth << "x" << g_capturedHeight
+                  << " but " << reference << " is " << refWidth << "x" << refHeight << std::endl;
+    } else {
+        uint64_t totalError = 0;
+        for (size_t i = 0; i < g_capturedFrame.size(); i++) {
+            totalError += (uint64_t)std::abs((int)g_capturedFrame[i] - (int)expected[i]);
+        }
+        double difference = (double)totalError / (255.0 * (double)g_capturedFrame.size());
+        matches = difference <= tolerance;
+        if (!matches) {
+            std::cerr << "[EDEN] expect_frame: frame differs from " << reference << " by " << difference
+                      << " (tolerance " << tolerance << ")" << std::endl;
+        }
+    }
+    stbi_image_free(expected);
+    
+    if (!matches && writePng(actualPath, g_capturedFrame.data(), g_capturedWidth, g_capturedHeight)) {
+        std::cerr << "[EDEN] expect_frame: actual frame saved to " << actualPath << std::endl;
+    }
+    return matches ? 1 : 0;
+}
+
+// Helper to find supported format
+static VkFormat findSupportedFormat(const std::vector<VkFormat>& candidates, VkImageTiling tiling, VkFormatFeatureFlags features) {
+    for (VkFormat format : candidates) {
+        VkFormatProperties props;
+        vkGetPhysicalDeviceFormatProperties(g_physicalDevice, format, &props);
+        if (tiling == VK_IMAGE_TILING_LINEAR && (props.linearTilingFeatures & features) == features) {
+            return format;
+        } else if (tiling == VK_IMAGE_TILING_OPTIMAL && (props.optimalTilingFeatures & features) == features) {
+            return format;
+        }
+    }
+    return VK_FORMAT_UNDEFINED;
+}
+
+// Helper to create image
+static void createImage(uint32_t width, uint32_t height, VkFormat format, VkImageTiling tiling, VkImageUsageFlags usage, VkMemoryPropertyFlags properties, VkImage& image, VkDeviceMemory& imageMemory) {
+    VkImageCreateInfo imageInfo = {};
+    imageInfo.sType = VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO;
+    imageInfo.imageType = VK_IMAGE_TYPE_2D;
+    imageInfo.extent.width = width;
+    imageInfo.extent.height = height;
+    imageInfo.extent.depth = 1;
+    imageInfo.mipLevels = 1;
+    imageInfo.arrayLayers = 1;
+    imageInfo.format = format;
+    imageInfo.tiling = tiling;
+    imageInfo.initialLayout = VK_IMAGE_LAYOUT_UNDEFINED;
+    imageInfo.usage = usage;
+    imageInfo.samples = VK_SAMPLE_COUNT_1_BIT;
+    imageInfo.sharingMode = VK_SHARING_MODE_EXCLUSIVE;
+
+    if (vkCreateImage(g_device, &imageInfo, nullptr, &image) != VK_SUCCESS) {
+        std::cerr << "[EDEN] Failed to create image!" << std::endl;
+        return;
+    }
+
+    VkMemoryRequirements memRequirements;
+    vkGetImageMemoryRequirements(g_device, image, &memRequirements);
+
+    VkMemoryAllocateInfo allocInfo = {};
+    allocInfo.sType = VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO;
+    allocInfo.allocationSize = memRequirements.size;
+    allocInfo.memoryTypeIndex = findMemoryType(memRequirements.memoryTypeBits, properties);
+
+    if (vkAllocateMemory(g_device, &allocInfo, nullptr, &imageMemory) != VK_SUCCESS) {
+        std::cerr << "[EDEN] Failed to allocate image memory!" << std::endl;
+        return;
+    }
+
+    vkBindImageMemory(g_device, image, imageMemory, 0);
+}
+
+// Create depth buffer
+static void createDepthResources() {
+    g_depthFormat = findSupportedFormat(
+        {VK_FORMAT_D32_SFLOAT, VK_FORMAT_D32_SFLOAT_S8_UINT, VK_FORMAT_D24_UNORM_S8_UINT},
+        VK_IMAGE_TILING_OPTIMAL,
+        VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT
+    );
+
+    createImage(g_swapchainExtent.width, g_swapchainExtent.height, g_depthFormat,
+                VK_IMAGE_TILING_OPTIMAL, VK_IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT,
+                VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT, g_depthImage, g_depthImageMemory);
+
+    VkImageViewCreateInfo viewInfo = {};
+    viewInfo.sType = VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO;
+    viewInfo.image = g_depthImage;
+    viewInfo.viewType = VK_IMAGE_VIEW_TYPE_2D;
+    viewInfo.format = g_depthFormat;
+    viewInfo.subresourceRange.aspectMask = VK_IMAGE_ASPECT_DEPTH_BIT;
+    viewInfo.subresourceRange.baseMipLevel = 0;
+    viewInfo.subresourceRange.levelCount = 1;
+    viewInfo.subresourceRange.baseArrayLayer = 0;
+    viewInfo.subresourceRange.layerCount = 1;
+
+    if (vkCreateImageView(g_device, &viewInfo, nullptr, &g_depthImageView) != VK_SUCCESS) {
+        std::cerr << "[EDEN] Failed to create depth image view!" << std::endl;
+    }
+}
+
+// =============================================================================
+// Swapchain (sized to the window; rebuilt when a resizable window changes size)
+// =============================================================================
+
+static const HeidicWindowConfig* g_windowConfig = nullptr;  // Set by a HEIDIC `window { ... }` block
+static bool g_framebufferResized = false;
+
+static void framebufferResizeCallback(GLFWwindow*, int, int) {
+    g_framebufferResized = true;
+}
+
+// FIFO waits for the display; without vsync prefer mailbox (no tearing), then immediate
+static VkPresentModeKHR choosePresentMode() {
+    if (!g_windowConfig || g_windowConfig->vsync) {
+        return VK_PRESENT_MODE_FIFO_KHR;
+    }
+    uint32_t modeCount = 0;
+    vkGetPhysicalDeviceSurfacePresentModesKHR(g_physicalDevice, g_surface, &modeCount, nullptr);
+    std::vector<VkPresentModeKHR> modes(modeCount);
+    vkGetPhysicalDeviceSurfacePresentModesKHR(g_physicalDevice, g_surface, &modeCount, modes.data());
+    for (VkPresentModeKHR wanted : {VK_PRESENT_MODE_MAILBOX_KHR, VK_PRESENT_MODE_IMMEDIATE_KHR}) {
+        if (std::find(modes.begin(), modes.end(), wanted) != modes.end()) {
+            return wanted;
+        }
+    }
+    return VK_PRESENT_MODE_FIFO_KHR;  // Always supported
+}
+
+// Create g_swapchain and its image views at the window's framebuffer size
+static bool createSwapchain(GLFWwindow* window) {
+    VkSurfaceCapabilitiesKHR capabilities;
+    vkGetPhysicalDeviceSurfaceCapabilitiesKHR(g_physicalDevice, g_surface, &capabilities);
+    
+    uint32_t formatCount;
+    vkGetPhysicalDeviceSurfaceFormatsKHR(g_physicalDevice, g_surface, &formatCount, nullptr);
+    std::vector<VkSurfaceFormatKHR> formats(formatCount);
+    vkGetPhysicalDeviceSurfaceFormatsKHR(g_physicalDevice, g_surface, &formatCount, formats.data());
+    
+    VkSurfaceFormatKHR surfaceFormat = formats[0];
+    for (const auto& format : formats) {
+        if (format.format == VK_FORMAT_B8G8R8A8_SRGB && format.colorSpace == VK_COLOR_SPACE_SRGB_NONLINEAR_KHR) {
+            surfaceFormat = format;
+            break;
+        }
+    }
+    g_swapchainImageFormat = surfaceFormat.format;
+    
+    int width, height;
+    glfwGetFramebufferSize(window, &width, &height);
+    
+    if (capabilities.currentExtent.width != UINT32_MAX) {
+        g_swapchainExtent = capabilities.currentExtent;
+    } else {
+        g_swapchainExtent.width = std::clamp(static_cast<uint32_t>(width), capabilities.minImageExtent.width, capabilities.maxImageExtent.width);
+        g_swapchainExtent.height = std::clamp(static_cast<uint32_t>(height), capabilities.minImageExtent.height, capabilities.maxImageExtent.height);
+    }
+    
+    uint32_t imageCount = capabilities.minImageCount + 1;
+    if (capabilities.maxImageCount > 0 && imageCount > capabilities.maxImageCount) {
+        imageCount = capabilities.maxImageCount;
+    }
+    
+    VkSwapchainCreateInfoKHR swapchainCreateInfo = {};
+    swapchainCreateInfo.sType = VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR;
+    swapchainCreateInfo.surface = g_surface;
+    swapchainCreateInfo.minImageCount = imageCount;
+    swapchainCreateInfo.imageFormat = surfaceFormat.format;
+    swapchainCreateInfo.imageColorSpace = surfaceFormat.colorSpace;
+    swapchainCreateInfo.imageExtent = g_swapchainExtent;
+    swapchainCreateInfo.imageArrayLayers = 1;
+    swapchainCreateInfo.imageUsage = VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT;
+    if (capabilities.supportedUsageFlags & VK_IMAGE_USAGE_TRANSFER_SRC_BIT) {
+        swapchainCreateInfo.imageUsage |= VK_IMAGE_USAGE_TRANSFER_SRC_BIT;  // Frame capture reads it back
+    }
+    g_swapchainReadable = (swapchainCreateInfo.imageUsage & VK_IMAGE_USAGE_TRANSFER_SRC_BIT) != 0;
+    swapchainCreateInfo.imageSharingMode = VK_SHARING_MODE_EXCLUSIVE;
+    swapchainCreateInfo.preTransform = capabilities.currentTransform;
+    swapchainCreateInfo.compositeAlpha = VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR;
+    swapchainCreateInfo.presentMode = choosePresentMode();
+    swapchainCreateInfo.clipped = VK_TRUE;
+    swapchainCreateInfo.oldSwapchain = VK_NULL_HANDLE;
+    
+    if (vkCreateSwapchainKHR(g_device, &swapchainCreateInfo, nullptr, &g_swapchain) != VK_SUCCESS) {
+        std::cerr << "[EDEN] ERROR: Failed to create swapchain!" << std::endl;
+        return false;
+    }
+    
+    vkGetSwapchainImagesKHR(g_device, g_swapchain, &g_swapchainImageCount, nullptr);
+    g_swapchainImages.resize(g_swapchainImageCount);
+    vkGetSwapchainImagesKHR(g_device, g_swapchain, &g_swapchainImageCount, g_swapchainImages.data());
+    
+    g_swapchainImageViews.resize(g_swapchainImageCount);
+    for (uint32_t i = 0; i < g_swapchainImageCount; i++) {
+        VkImageViewCreateInfo viewInfo = {};
+        viewInfo.sType = VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO;
+        viewInfo.image = g_swapchainImages[i];
+        viewInfo.viewType = VK_IMAGE_VIEW_TYPE_2D;
+        viewInfo.format = g_swapchainImageFormat;
+        viewInfo.components.r = VK_COMPONENT_SWIZZLE_IDENTITY;
+        viewInfo.components.g = VK_COMPONENT_SWIZZLE_IDENTITY;
+        viewInfo.components.b = VK_COMPONENT_SWIZZLE_IDENTITY;
+        viewInfo.components.a = VK_COMPONENT_SWIZZLE_IDENTITY;
+        viewInfo.subresourceRange.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
+        viewInfo.subresourceRange.baseMipLevel = 0;
+        viewInfo.subresourceRange.levelCount = 1;
+        viewInfo.subresourceRange.baseArrayLayer = 0;
+        viewInfo.subresourceRange.layerCount = 1;
+        
+        if (vkCreateImageView(g_device, &viewInfo, nullptr, &g_swapchainImageViews[i]) != VK_SUCCESS) {
+            std::cerr << "[EDEN] ERROR: Failed to create image view!" << std::endl;
+            return false;
+        }
+    }
+    return true;
+}
+
+// One framebuffer per swapchain image, sharing the depth buffer
+static bool createFramebuffers() {
+    g_framebuffers.resize(g_swapchainImageCount);
+    for (uint32_t i = 0; i < g_swapchainImageCount; i++) {
+        std::array<VkImageView, 2> attachments = {
+            g_swapchainImageViews[i],
+            g_depthImageView
+        };
+        
+        VkFramebufferCreateInfo framebufferInfo = {};
+        framebufferInfo.sType = VK_STRUCTURE_TYPE_FRAMEBUFFER_CREATE_INFO;
+        framebufferInfo.renderPass = g_renderPass;
+        framebufferInfo.attachmentCount = static_cast<uint32_t>(attachments.size());
+        framebufferInfo.pAttachments = attachments.data();
+        framebufferInfo.width = g_swapchainExtent.width;
+        framebufferInfo.height = g_swapchainExtent.height;
+        framebufferInfo.layers = 1;
+        
+        if (vkCreateFramebuffer(g_device, &framebufferInfo, nullptr, &g_framebuffers[i]) != VK_SUCCESS) {
+            std::cerr << "[EDEN] ERROR: Failed to create framebuffer!" << std::endl;
+            return false;
+        }
+    }
+    return true;
+}
+
+// Framebuffers, depth buffer, image views and the swapchain itself (the render pass is kept)
+static void destroySwapchain() {
+    for (auto framebuffer : g_framebuffers) {
+        vkDestroyFramebuffer(g_device, framebuffer, nullptr);
+    }
+    g_framebuffers.clear();
+    if (g_depthImageView != VK_NULL_HANDLE) {
+        vkDestroyImageView(g_device, g_depthImageView, nullptr);
+        g_depthImageView = VK_NULL_HANDLE;
+    }
+    if (g_depthImage != VK_NULL_HANDLE) {
+        vkDestroyImage(g_device, g_depthImage, nullptr);
+        g_depthImage = VK_NULL_HANDLE;
+    }
+    if (g_depthImageMemory != VK_NULL_HANDLE) {
+        vkFreeMemory(g_device, g_depthImageMemory, nullptr);
+        g_depthImageMemory = VK_NULL_HANDLE;
+    }
+    for (auto imageView : g_swapchainImageViews) {
+        vkDestroyImageView(g_device, imageView, nullptr);
+    }
+    g_swapchainImageViews.clear();
+    if (g_swapchain != VK_NULL_HANDLE) {
+        vkDestroySwapchainKHR(g_device, g_swapchain, nullptr);
+        g_swapchain = VK_NULL_HANDLE;
+    }
 }
 
-// Helper to find supported format
-static VkFormat findSupportedFormat(const std::vector<VkFormat>& candidates, VkImageTiling tiling, VkFormatFeatureFlags features) {
-    for (VkFormat format : candidates) {
-        VkFormatProperties props;
-        vkGetPhysicalDeviceFormatProperties(g_physicalDevice, format, &props);
-        if (tiling == VK_IMAGE_TILING_LINEAR && (props.linearTilingFeatures & features) == features) {
-            return format;
-        } else if (tiling == VK_IMAGE_TILING_OPTIMAL && (props.optimalTilingFeatures & features) == features) {
-            return format;
-        }
+// Rebuild the swapchain at the window's new size. A minimized window has no size: this waits
+// (processing events) until it's restored. The image count doesn't change, so the per-image
+// command buffers and uniform buffers are kept.
+static bool recreateSwapchain(GLFWwindow* window) {
+    int width = 0, height = 0;
+    glfwGetFramebufferSize(window, &width, &height);
+    while ((width == 0 || height == 0) && !glfwWindowShouldClose(window)) {
+        glfwWaitEvents();
+        glfwGetFramebufferSize(window, &width, &height);
     }
-    return VK_FORMAT_UNDEFINED;
-}
-
-// Helper to create image
-static void createImage(uint32_t width, uint32_t height, VkFormat format, VkImageTiling tiling, VkImageUsageFlags usage, VkMemoryPropertyFlags properties, VkImage& image, VkDeviceMemory& imageMemory) {
-    VkImageCreateInfo imageInfo = {};
-    imageInfo.sType = VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO;
-    imageInfo.imageType = VK_IMAGE_TYPE_2D;
-    imageInfo.extent.width = width;
-    imageInfo.extent.height = height;
-    imageInfo.extent.depth = 1;
-    imageInfo.mipLevels = 1;
-    imageInfo.arrayLayers = 1;
-    imageInfo.format = format;
-    imageInfo.tiling = tiling;
-    imageInfo.initialLayout = VK_IMAGE_LAYOUT_UNDEFINED;
-    imageInfo.usage = usage;
-    imageInfo.samples = VK_SAMPLE_COUNT_1_BIT;
-    imageInfo.sharingMode = VK_SHARING_MODE_EXCLUSIVE;
-
-    if (vkCreateImage(g_device, &imageInfo, nullptr, &image) != VK_SUCCESS) {
-        std::cerr << "[EDEN] Failed to create image!" << std::endl;
-        return;
+    if (width == 0 || height == 0) {
+        return false;  // Closed while minimized
     }
-
-    VkMemoryRequirements memRequirements;
-    vkGetImageMemoryRequirements(g_device, image, &memRequirements);
-
-    VkMemoryAllocateInfo allocInfo = {};
-    allocInfo.sType = VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO;
-    allocInfo.allocationSize = memRequirements.size;
-    allocInfo.memoryTypeIndex = findMemoryType(memRequirements.memoryTypeBits, properties);
-
-    if (vkAllocateMemory(g_device, &allocInfo, nullptr, &imageMemory) != VK_SUCCESS) {
-        std::cerr << "[EDEN] Failed to allocate image memory!" << std::endl;
-        return;
+    
+    vkDeviceWaitIdle(g_device);
+    uint32_t imageCount = g_swapchainImageCount;
+    destroySwapchain();
+    if (!createSwapchain(window)) {
+        return false;
     }
-
-    vkBindImageMemory(g_device, image, imageMemory, 0);
-}
-
-// Create depth buffer
-static void createDepthResources() {
-    g_depthFormat = findSupportedFormat(
-        {VK_FORMAT_D32_SFLOAT, VK_FORMAT_D32_SFLOAT_S8_UINT, VK_FORMAT_D24_UNORM_S8_UINT},
-        VK_IMAGE_TILING_OPTIMAL,
-        VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT
-    );
-
-    createImage(g_swapchainExtent.width, g_swapchainExtent.height, g_depthFormat,
-                VK_IMAGE_TILING_OPTIMAL, VK_IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT,
-                VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT, g_depthImage, g_depthImageMemory);
-
-    VkImageViewCreateInfo viewInfo = {};
-    viewInfo.sType = VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO;
-    viewInfo.image = g_depthImage;
-    viewInfo.viewType = VK_IMAGE_VIEW_TYPE_2D;
-    viewInfo.format = g_depthFormat;
-    viewInfo.subresourceRange.aspectMask = VK_IMAGE_ASPECT_DEPTH_BIT;
-    viewInfo.subresourceRange.baseMipLevel = 0;
-    viewInfo.subresourceRange.levelCount = 1;
-    viewInfo.subresourceRange.baseArrayLayer = 0;
-    viewInfo.subresourceRange.layerCount = 1;
-
-    if (vkCreateImageView(g_device, &viewInfo, nullptr, &g_depthImageView) != VK_SUCCESS) {
-        std::cerr << "[EDEN] Failed to create depth image view!" << std::endl;
+    createDepthResources();
+    if (!createFramebuffers()) {
+        return false;
+    }
+    if (g_swapchainImageCount != imageCount) {
+        // The per-image command buffers, uniform buffers and descriptor sets were made for the old count
+        std::cerr << "[EDEN] ERROR: Swapchain image count changed from " << imageCount << " to " << g_swapchainImageCount << " on resize" << std::endl;
+        return false;
     }
+    g_framebufferResized = false;
+    std::cout << "[EDEN] Swapchain resized to " << g_swapchainExtent.width << "x" << g_swapchainExtent.height << std::endl;
+    return true;
 }
 
 // Configure GLFW for Vulkan
 extern "C" void heidic_glfw_vulkan_hints() {
     glfwWindowHint(GLFW_CLIENT_API, GLFW_NO_API);
+    glfwWindowHint(GLFW_VISIBLE, g_renderMode == HEIDIC_RENDER_OFFSCREEN ? GLFW_FALSE : GLFW_TRUE);
 }
 
 // Create a fullscreen window on the primary monitor
@@ -423,7 +2080,8 @@ extern "C" int heidic_init_renderer(GLFWwindow* window) {
     appInfo.applicationVersion = VK_MAKE_VERSION(1, 0, 0);
     appInfo.pEngineName = "EDEN Engine";
     appInfo.engineVersion = VK_MAKE_VERSION(1, 0, 0);
-    appInfo.apiVersion = VK_API_VERSION_1_0;
+    // Descriptor-indexing features (bindless) are core in Vulkan 1.2
+    appInfo.apiVersion = gpuConfigNeedsIndexing() ? VK_API_VERSION_1_2 : VK_API_VERSION_1_0;
     
     uint32_t glfwExtensionCount = 0;
     const char** glfwExtensions = glfwGetRequiredInstanceExtensions(&glfwExtensionCount);
@@ -538,9 +2196,14 @@ extern "C" int heidic_init_renderer(GLFWwindow* window) {
     
     std::vector<VkPhysicalDevice> devices(deviceCount);
     vkEnumeratePhysicalDevices(g_instance, &deviceCount, devices.data());
-    g_physicalDevice = devices[0];
+    g_physicalDevice = selectPhysicalDevice(devices, g_surface);
+    if (g_physicalDevice == VK_NULL_HANDLE) {
+        vkDestroySurfaceKHR(g_instance, g_surface, nullptr);
+        vkDestroyInstance(g_instance, nullptr);
+        return 0;
+    }
     
-    // 4. Find graphics queue family
+    // 4. Find graphics queue family (and a transfer family for staging uploads)
     g_graphicsQueueFamilyIndex = findGraphicsQueueFamily(g_physicalDevice, g_surface);
     if (g_graphicsQueueFamilyIndex == UINT32_MAX) {
         std::cerr << "[EDEN] ERROR: No suitable queue family found!" << std::endl;
@@ -548,23 +2211,40 @@ extern "C" int heidic_init_renderer(GLFWwindow* window) {
         vkDestroyInstance(g_instance, nullptr);
         return 0;
     }
+    g_transferQueueFamilyIndex = findTransferQueueFamily(g_physicalDevice, g_graphicsQueueFamilyIndex);
     
     // 5. Create logical device
     float queuePriority = 1.0f;
-    VkDeviceQueueCreateInfo queueCreateInfo = {};
-    queueCreateInfo.sType = VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO;
-    queueCreateInfo.queueFamilyIndex = g_graphicsQueueFamilyIndex;
-    queueCreateInfo.queueCount = 1;
-    queueCreateInfo.pQueuePriorities = &queuePriority;
+    VkDeviceQueueCreateInfo queueCreateInfos[2] = {};
+    uint32_t queueCreateInfoCount = 1;
+    queueCreateInfos[0].sType = VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO;
+    queueCreateInfos[0].queueFamilyIndex = g_graphicsQueueFamilyIndex;
+    queueCreateInfos[0].queueCount = 1;
+    queueCreateInfos[0].pQueuePriorities = &queuePriority;
+    if (g_transferQueueFamilyIndex != g_graphicsQueueFamilyIndex) {
+        queueCreateInfos[1] = queueCreateInfos[0];
+        queueCreateInfos[1].queueFamilyIndex = g_transferQueueFamilyIndex;
+        queueCreateInfoCount = 2;
+    }
     
-    VkPhysicalDeviceFeatures deviceFeatures = {};
-    deviceFeatures.fillModeNonSolid = VK_TRUE;  // Enable wireframe rendering mode
+    GpuDeviceFeatures deviceFeatures = enabledGpuFeatures();
+    g_enabledCoreFeatures = deviceFeatures.core;
     
     VkDeviceCreateInfo deviceCreateInfo = {};
     deviceCreateInfo.sType = VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO;
-    deviceCreateInfo.pQueueCreateInfos = &queueCreateInfo;
-    deviceCreateInfo.queueCreateInfoCount = 1;
-    deviceCreateInfo.pEnabledFeatures = &deviceFeatures;
+    deviceCreateInfo.pQueueCreateInfos = queueCreateInfos;
+    deviceCreateInfo.queueCreateInfoCount = queueCreateInfoCount;
+    
+    // Descriptor-indexing features must be chained through VkPhysicalDeviceFeatures2
+    VkPhysicalDeviceFeatures2 deviceFeatures2 = {};
+    if (deviceFeatures.hasIndexing) {
+        deviceFeatures2.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_FEATURES_2;
+        deviceFeatures2.pNext = &deviceFeatures.indexing;
+        deviceFeatures2.features = deviceFeatures.core;
+        deviceCreateInfo.pNext = &deviceFeatures2;
+    } else {
+        deviceCreateInfo.pEnabledFeatures = &deviceFeatures.core;
+    }
     
     const char* deviceExtensions[] = { VK_KHR_SWAPCHAIN_EXTENSION_NAME };
     deviceCreateInfo.enabledExtensionCount = 1;
@@ -578,91 +2258,19 @@ extern "C" int heidic_init_renderer(GLFWwindow* window) {
     }
     
     vkGetDeviceQueue(g_device, g_graphicsQueueFamilyIndex, 0, &g_graphicsQueue);
-    
-    // 6. Create swapchain
-    VkSurfaceCapabilitiesKHR capabilities;
-    vkGetPhysicalDeviceSurfaceCapabilitiesKHR(g_physicalDevice, g_surface, &capabilities);
-    
-    uint32_t formatCount;
-    vkGetPhysicalDeviceSurfaceFormatsKHR(g_physicalDevice, g_surface, &formatCount, nullptr);
-    std::vector<VkSurfaceFormatKHR> formats(formatCount);
-    vkGetPhysicalDeviceSurfaceFormatsKHR(g_physicalDevice, g_surface, &formatCount, formats.data());
-    
-    VkSurfaceFormatKHR surfaceFormat = formats[0];
-    for (const auto& format : formats) {
-        if (format.format == VK_FORMAT_B8G8R8A8_SRGB && format.colorSpace == VK_COLOR_SPACE_SRGB_NONLINEAR_KHR) {
-            surfaceFormat = format;
-            break;
-        }
-    }
-    g_swapchainImageFormat = surfaceFormat.format;
-    
-    int width, height;
-    glfwGetFramebufferSize(window, &width, &height);
-    
-    VkExtent2D swapchainExtent;
-    if (capabilities.currentExtent.width != UINT32_MAX) {
-        swapchainExtent = capabilities.currentExtent;
-    } else {
-        swapchainExtent.width = std::clamp(static_cast<uint32_t>(width), capabilities.minImageExtent.width, capabilities.maxImageExtent.width);
-        swapchainExtent.height = std::clamp(static_cast<uint32_t>(height), capabilities.minImageExtent.height, capabilities.maxImageExtent.height);
-    }
-    g_swapchainExtent = swapchainExtent;
-    
-    uint32_t imageCount = capabilities.minImageCount + 1;
-    if (capabilities.maxImageCount > 0 && imageCount > capabilities.maxImageCount) {
-        imageCount = capabilities.maxImageCount;
-    }
-    
-    VkSwapchainCreateInfoKHR swapchainCreateInfo = {};
-    swapchainCreateInfo.sType = VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR;
-    swapchainCreateInfo.surface = g_surface;
-    swapchainCreateInfo.minImageCount = imageCount;
-    swapchainCreateInfo.imageFormat = surfaceFormat.format;
-    swapchainCreateInfo.imageColorSpace = surfaceFormat.colorSpace;
-    swapchainCreateInfo.imageExtent = swapchainExtent;
-    swapchainCreateInfo.imageArrayLayers = 1;
-    swapchainCreateInfo.imageUsage = VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT;
-    swapchainCreateInfo.imageSharingMode = VK_SHARING_MODE_EXCLUSIVE;
-    swapchainCreateInfo.preTransform = capabilities.currentTransform;
-    swapchainCreateInfo.compositeAlpha = VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR;
-    swapchainCreateInfo.presentMode = VK_PRESENT_MODE_FIFO_KHR;
-    swapchainCreateInfo.clipped = VK_TRUE;
-    swapchainCreateInfo.oldSwapchain = VK_NULL_HANDLE;
-    
-    if (vkCreateSwapchainKHR(g_device, &swapchainCreateInfo, nullptr, &g_swapchain) != VK_SUCCESS) {
-        std::cerr << "[EDEN] ERROR: Failed to create swapchain!" << std::endl;
+    if (!createTransferResources()) {
         vkDestroyDevice(g_device, nullptr);
         vkDestroySurfaceKHR(g_instance, g_surface, nullptr);
         vkDestroyInstance(g_instance, nullptr);
         return 0;
     }
     
-    vkGetSwapchainImagesKHR(g_device, g_swapchain, &g_swapchainImageCount, nullptr);
-    g_swapchainImages.resize(g_swapchainImageCount);
-    vkGetSwapchainImagesKHR(g_device, g_swapchain, &g_swapchainImageCount, g_swapchainImages.data());
-    
-    g_swapchainImageViews.resize(g_swapchainImageCount);
-    for (uint32_t i = 0; i < g_swapchainImageCount; i++) {
-        VkImageViewCreateInfo viewInfo = {};
-        viewInfo.sType = VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO;
-        viewInfo.image = g_swapchainImages[i];
-        viewInfo.viewType = VK_IMAGE_VIEW_TYPE_2D;
-        viewInfo.format = g_swapchainImageFormat;
-        viewInfo.components.r = VK_COMPONENT_SWIZZLE_IDENTITY;
-        viewInfo.components.g = VK_COMPONENT_SWIZZLE_IDENTITY;
-        viewInfo.components.b = VK_COMPONENT_SWIZZLE_IDENTITY;
-        viewInfo.components.a = VK_COMPONENT_SWIZZLE_IDENTITY;
-        viewInfo.subresourceRange.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
-        viewInfo.subresourceRange.baseMipLevel = 0;
-        viewInfo.subresourceRange.levelCount = 1;
-        viewInfo.subresourceRange.baseArrayLayer = 0;
-        viewInfo.subresourceRange.layerCount = 1;
-        
-        if (vkCreateImageView(g_device, &viewInfo, nullptr, &g_swapchainImageViews[i]) != VK_SUCCESS) {
-            std::cerr << "[EDEN] ERROR: Failed to create image view!" << std::endl;
-            return 0;
-        }
+    // 6. Create swapchain
+    if (!createSwapchain(window)) {
+        vkDestroyDevice(g_device, nullptr);
+        vkDestroySurfaceKHR(g_instance, g_surface, nullptr);
+        vkDestroyInstance(g_instance, nullptr);
+        return 0;
     }
     
     // 7. Create render pass
@@ -891,14 +2499,14 @@ extern "C" int heidic_init_renderer(GLFWwindow* window) {
     VkViewport viewport = {};
     viewport.x = 0.0f;
     viewport.y = 0.0f;
-    viewport.width = (float)swapchainExtent.width;
-    viewport.height = (float)swapchainExtent.height;
+    viewport.width = (float)g_swapchainExtent.width;
+    viewport.height = (float)g_swapchainExtent.height;
     viewport.minDepth = 0.0f;
     viewport.maxDepth = 1.0f;
     
     VkRect2D scissor = {};
     scissor.offset = {0, 0};
-    scissor.extent = swapchainExtent;
+    scissor.extent = g_swapchainExtent;
     
     VkPipelineViewportStateCreateInfo viewportState = {};
     viewportState.sType = VK_STRUCTURE_TYPE_PIPELINE_VIEWPORT_STATE_CREATE_INFO;
@@ -971,6 +2579,13 @@ extern "C" int heidic_init_renderer(GLFWwindow* window) {
     pipelineInfo.pMultisampleState = &multisampling;
     pipelineInfo.pDepthStencilState = &depthStencil;
     pipelineInfo.pColorBlendState = &colorBlending;
+    // Viewport and scissor are set per frame, so the pipeline outlives swapchain resizes
+    VkDynamicState dynamicStates[] = {VK_DYNAMIC_STATE_VIEWPORT, VK_DYNAMIC_STATE_SCISSOR};
+    VkPipelineDynamicStateCreateInfo dynamicState = {};
+    dynamicState.sType = VK_STRUCTURE_TYPE_PIPELINE_DYNAMIC_STATE_CREATE_INFO;
+    dynamicState.dynamicStateCount = 2;
+    dynamicState.pDynamicStates = dynamicStates;
+    pipelineInfo.pDynamicState = &dynamicState;
     pipelineInfo.layout = g_pipelineLayout;
     pipelineInfo.renderPass = g_renderPass;
     pipelineInfo.subpass = 0;
@@ -991,26 +2606,8 @@ extern "C" int heidic_init_renderer(GLFWwindow* window) {
     createDepthResources();
     
     // 11. Create framebuffers
-    g_framebuffers.resize(g_swapchainImageCount);
-    for (uint32_t i = 0; i < g_swapchainImageCount; i++) {
-        std::array<VkImageView, 2> attachments = {
-            g_swapchainImageViews[i],
-            g_depthImageView
-        };
-        
-        VkFramebufferCreateInfo framebufferInfo = {};
-        framebufferInfo.sType = VK_STRUCTURE_TYPE_FRAMEBUFFER_CREATE_INFO;
-        framebufferInfo.renderPass = g_renderPass;
-        framebufferInfo.attachmentCount = static_cast<uint32_t>(attachments.size());
-        framebufferInfo.pAttachments = attachments.data();
-        framebufferInfo.width = swapchainExtent.width;
-        framebufferInfo.height = swapchainExtent.height;
-        framebufferInfo.layers = 1;
-        
-        if (vkCreateFramebuffer(g_device, &framebufferInfo, nullptr, &g_framebuffers[i]) != VK_SUCCESS) {
-            std::cerr << "[EDEN] ERROR: Failed to create framebuffer!" << std::endl;
-            return 0;
-        }
+    if (!createFramebuffers()) {
+        return 0;
     }
     
     // 12. Create command pool
@@ -1138,11 +2735,16 @@ extern "C" void heidic_render_frame(GLFWwindow* window) {
     
     // Wait for previous frame to finish
     vkWaitForFences(g_device, 1, &g_inFlightFence, VK_TRUE, UINT64_MAX);
-    vkResetFences(g_device, 1, &g_inFlightFence);
     
-    // Acquire next image
+    // Acquire next image; a swapchain that no longer matches the window is rebuilt and the frame skipped
     uint32_t imageIndex;
-    vkAcquireNextImageKHR(g_device, g_swapchain, UINT64_MAX, g_imageAvailableSemaphore, VK_NULL_HANDLE, &imageIndex);
+    VkResult acquireResult = vkAcquireNextImageKHR(g_device, g_swapchain, UINT64_MAX, g_imageAvailableSemaphore, VK_NULL_HANDLE, &imageIndex);
+    if (acquireResult == VK_ERROR_OUT_OF_DATE_KHR) {
+        recreateSwapchain(window);
+        return;
+    }
+    // Only reset once work will be submitted, or the next frame waits on it forever
+    vkResetFences(g_device, 1, &g_inFlightFence);
     
     // Update uniform buffer with rotation using GLM
     UniformBufferObject ubo = {};
@@ -1157,10 +2759,8 @@ extern "C" void heidic_render_frame(GLFWwindow* window) {
     Vec3 up = {0.0f, 1.0f, 0.0f};
     ubo.view = mat4_lookat(eye, center, up);
     
-    // Projection matrix
-    int width, height;
-    glfwGetFramebufferSize(window, &width, &height);
-    float aspect = (float)width / (float)height;
+    // Projection matrix (the swapchain's size: the window's reads 0x0 while minimized)
+    float aspect = (float)g_swapchainExtent.width / (float)g_swapchainExtent.height;
     ubo.proj = mat4_perspective(1.0472f, aspect, 0.1f, 100.0f); // 60 degree FOV
     
     // Vulkan clip space has inverted Y and half Z.
@@ -1196,10 +2796,15 @@ extern "C" void heidic_render_frame(GLFWwindow* window) {
     renderPassInfo.clearValueCount = static_cast<uint32_t>(clearValues.size());
     renderPassInfo.pClearValues = clearValues.data();
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_pipeline);
     
+    VkViewport viewport = {0.0f, 0.0f, (float)g_swapchainExtent.width, (float)g_swapchainExtent.height, 0.0f, 1.0f};
+    VkRect2D scissor = {{0, 0}, g_swapchainExtent};
+    vkCmdSetViewport(g_commandBuffers[imageIndex], 0, 1, &viewport);
+    vkCmdSetScissor(g_commandBuffers[imageIndex], 0, 1, &scissor);
+    
     // Bind descriptor set (contains model/view/proj matrices)
     vkCmdBindDescriptorSets(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_pipelineLayout, 0, 1, &g_descriptorSets[imageIndex], 0, nullptr);
     
@@ -1224,7 +2829,7 @@ extern "C" void heidic_render_frame(GLFWwindow* window) {
     }
     #endif
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     
     if (vkEndCommandBuffer(g_commandBuffers[imageIndex]) != VK_SUCCESS) {
         return;
@@ -1262,7 +2867,11 @@ extern "C" void heidic_render_frame(GLFWwindow* window) {
     presentInfo.pImageIndices = &imageIndex;
     presentInfo.pResults = nullptr;
     
-    vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
+    captureBeforePresent(imageIndex);
+    VkResult presentResult = vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
+    if (presentResult == VK_ERROR_OUT_OF_DATE_KHR || presentResult == VK_SUBOPTIMAL_KHR || g_framebufferResized) {
+        recreateSwapchain(window);
+    }
     
     g_currentFrame = (g_currentFrame + 1) % g_swapchainImageCount;
 }
@@ -1316,6 +2925,11 @@ extern "C" void heidic_cleanup_renderer() {
     if (g_commandPool != VK_NULL_HANDLE) {
         vkDestroyCommandPool(g_device, g_commandPool, nullptr);
     }
+    destroyTransferResources();
+    destroyQueryPools();
+    destroyComputeKernels();
+    destroyGpuBuffers();
+    destroyUniformBuffers();
     
     // Cleanup framebuffers
     for (auto framebuffer : g_framebuffers) {
@@ -1390,6 +3004,58 @@ extern "C" void heidic_cleanup_renderer() {
     std::cout << "[EDEN] Renderer cleaned up" << std::endl;
 }
 
+extern "C" int heidic_recreate_swapchain(GLFWwindow* window) {
+    if (g_device == VK_NULL_HANDLE || g_swapchain == VK_NULL_HANDLE) {
+        return 0;
+    }
+    return recreateSwapchain(window) ? 1 : 0;
+}
+
+// =============================================================================
+// Window (configured by a HEIDIC `window { ... }` block)
+// =============================================================================
+
+extern "C" void heidic_set_window_config(const HeidicWindowConfig* config) {
+    g_windowConfig = config;
+}
+
+extern "C" GLFWwindow* heidic_open_window(void) {
+    HeidicWindowConfig defaults = {"HEIDIC", 1280, 720, 1, 1};
+    const HeidicWindowConfig& config = g_windowConfig ? *g_windowConfig : defaults;
+    
+    if (!glfwInit()) {
+        std::cerr << "[GLFW] ERROR: Failed to initialize GLFW!" << std::endl;
+        return nullptr;
+    }
+    heidic_glfw_vulkan_hints();
+    glfwWindowHint(GLFW_RESIZABLE, config.resizable ? GLFW_TRUE : GLFW_FALSE);
+    
+    GLFWwindow* window = glfwCreateWindow(config.width, config.height, config.title, nullptr, nullptr);
+    if (!window) {
+        std::cerr << "[GLFW] ERROR: Failed to create window!" << std::endl;
+        glfwTerminate();
+        return nullptr;
+    }
+    if (config.resizable) {
+        glfwSetFramebufferSizeCallback(window, framebufferResizeCallback);
+    }
+    
+    if (!heidic_init_renderer(window)) {
+        glfwDestroyWindow(window);
+        glfwTerminate();
+        return nullptr;
+    }
+    return window;
+}
+
+extern "C" void heidic_close_window(GLFWwindow* window) {
+    heidic_cleanup_renderer();
+    if (window) {
+        glfwDestroyWindow(window);
+    }
+    glfwTerminate();
+}
+
 // Sleep for milliseconds
 extern "C" void heidic_sleep_ms(uint32_t milliseconds) {
     std::this_thread::sleep_for(std::chrono::milliseconds(milliseconds));
@@ -1400,11 +3066,11 @@ extern "C" int32_t f32_to_i32(float value) {
     return static_cast<int32_t>(value);
 }
 
-// Hot-reload shader function
-extern "C" void heidic_reload_shader(const char* shader_path) {
+// Hot-reload shader function (returns 1 when the shader and its pipeline were rebuilt)
+extern "C" int32_t heidic_reload_shader(const char* shader_path) {
     if (g_device == VK_NULL_HANDLE) {
         std::cerr << "[Shader Hot-Reload] ERROR: Device not initialized!" << std::endl;
-        return;
+        return 0;
     }
     
     // Wait for device to be idle before reloading
@@ -1458,7 +3124,7 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
             isTriangleShader = true;
         } else {
             std::cerr << "[Shader Hot-Reload] WARNING: Unknown shader path: " << shader_path << std::endl;
-            return;
+            return 0;
         }
     }
     
@@ -1493,7 +3159,7 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
     
     if (targetModule == nullptr || targetPipeline == nullptr) {
         std::cerr << "[Shader Hot-Reload] ERROR: Could not determine shader module!" << std::endl;
-        return;
+        return 0;
     }
     
     // Read new shader code from the .spv file
@@ -1502,7 +3168,7 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
         shaderCode = readFile(spv_path);
     } catch (const std::exception& e) {
         std::cerr << "[Shader Hot-Reload] ERROR: Failed to read shader file " << spv_path << ": " << e.what() << std::endl;
-        return;
+        return 0;
     }
     
     // Destroy old shader module
@@ -1519,7 +3185,7 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
     
     if (vkCreateShaderModule(g_device, &createInfo, nullptr, targetModule) != VK_SUCCESS) {
         std::cerr << "[Shader Hot-Reload] ERROR: Failed to create new shader module!" << std::endl;
-        return;
+        return 0;
     }
     
     // Destroy old pipeline
@@ -1546,7 +3212,7 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
     
     if (otherModule == VK_NULL_HANDLE) {
         std::cerr << "[Shader Hot-Reload] ERROR: Other shader module not found!" << std::endl;
-        return;
+        return 0;
     }
     
     // Create shader stage info for both shaders
@@ -1690,6 +3356,13 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
         pipelineInfo.pMultisampleState = &multisampling;
         pipelineInfo.pDepthStencilState = &depthStencil;
         pipelineInfo.pColorBlendState = &colorBlending;
+        // Dynamic like the pipeline it replaces (heidic_render_frame sets viewport and scissor)
+        VkDynamicState dynamicStates[] = {VK_DYNAMIC_STATE_VIEWPORT, VK_DYNAMIC_STATE_SCISSOR};
+        VkPipelineDynamicStateCreateInfo dynamicState = {};
+        dynamicState.sType = VK_STRUCTURE_TYPE_PIPELINE_DYNAMIC_STATE_CREATE_INFO;
+        dynamicState.dynamicStateCount = 2;
+        dynamicState.pDynamicStates = dynamicStates;
+        pipelineInfo.pDynamicState = &dynamicState;
         pipelineInfo.layout = g_pipelineLayout;
         pipelineInfo.renderPass = g_renderPass;
         pipelineInfo.subpass = 0;
@@ -1697,7 +3370,7 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
         
         if (vkCreateGraphicsPipelines(g_device, VK_NULL_HANDLE, 1, &pipelineInfo, nullptr, &g_pipeline) != VK_SUCCESS) {
             std::cerr << "[Shader Hot-Reload] ERROR: Failed to recreate triangle pipeline!" << std::endl;
-            return;
+            return 0;
         }
     } else if (isCubeShader) {
         // Cube pipeline setup (with vertex input)
@@ -1799,7 +3472,7 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
         
         if (vkCreateGraphicsPipelines(g_device, VK_NULL_HANDLE, 1, &pipelineInfo, nullptr, &g_cubePipeline) != VK_SUCCESS) {
             std::cerr << "[Shader Hot-Reload] ERROR: Failed to recreate cube pipeline!" << std::endl;
-            return;
+            return 0;
         }
     }
     
@@ -1819,10 +3492,11 @@ extern "C" void heidic_reload_shader(const char* shader_path) {
     
     if (vkAllocateCommandBuffers(g_device, &allocInfo, g_commandBuffers.data()) != VK_SUCCESS) {
         std::cerr << "[Shader Hot-Reload] ERROR: Failed to reallocate command buffers!" << std::endl;
-        return;
+        return 0;
     }
     
     std::cout << "[Shader Hot-Reload] Successfully reloaded shader: " << shader_path << std::endl;
+    return 1;
 }
 
 // ============================================================================
@@ -2118,7 +3792,7 @@ extern "C" void heidic_render_frame_cube(GLFWwindow* window) {
     renderPassInfo.clearValueCount = static_cast<uint32_t>(clearValues.size());
     renderPassInfo.pClearValues = clearValues.data();
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     // Bind pipeline
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_cubePipeline);
@@ -2182,7 +3856,7 @@ extern "C" void heidic_render_frame_cube(GLFWwindow* window) {
     }
     #endif
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     
     if (vkEndCommandBuffer(g_commandBuffers[imageIndex]) != VK_SUCCESS) {
         std::cerr << "[EDEN] ERROR: Failed to record command buffer!" << std::endl;
@@ -2222,6 +3896,7 @@ extern "C" void heidic_render_frame_cube(GLFWwindow* window) {
     presentInfo.pImageIndices = &imageIndex;
     presentInfo.pResults = nullptr;
     
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
 }
 
@@ -2781,7 +4456,7 @@ extern "C" void heidic_render_fps(GLFWwindow* window, float camera_pos_x, float
     renderPassInfo.clearValueCount = static_cast<uint32_t>(clearValues.size());
     renderPassInfo.pClearValues = clearValues.data();
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     // Bind pipeline FIRST (use the selected pipeline)
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, pipelineToUse);
@@ -2932,7 +4607,7 @@ extern "C" void heidic_render_fps(GLFWwindow* window, float camera_pos_x, float
     }
     #endif
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     
     if (vkEndCommandBuffer(g_commandBuffers[imageIndex]) != VK_SUCCESS) {
         std::cerr << "[FPS] ERROR: Failed to record command buffer!" << std::endl;
@@ -2972,6 +4647,7 @@ extern "C" void heidic_render_fps(GLFWwindow* window, float camera_pos_x, float
     presentInfo.pImageIndices = &imageIndex;
     presentInfo.pResults = nullptr;
     
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
 }
 
@@ -4118,7 +5794,7 @@ extern "C" void heidic_render_balls(GLFWwindow* window, int32_t ball_count, floa
     renderPassInfo.clearValueCount = 2;
     renderPassInfo.pClearValues = clearValues;
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     // Bind pipeline
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_ballsPipeline);
@@ -4188,7 +5864,7 @@ extern "C" void heidic_render_balls(GLFWwindow* window, int32_t ball_count, floa
     }
     #endif
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     vkEndCommandBuffer(g_commandBuffers[imageIndex]);
     
     // Submit
@@ -4220,6 +5896,7 @@ extern "C" void heidic_render_balls(GLFWwindow* window, int32_t ball_count, floa
     presentInfo.pSwapchains = swapChains;
     presentInfo.pImageIndices = &imageIndex;
     
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
 }
 
@@ -4854,7 +6531,7 @@ extern "C" void heidic_render_dds_quad(GLFWwindow* window) {
     renderPassInfo.clearValueCount = 2;
     renderPassInfo.pClearValues = clearValues;
 
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
 
     // Bind pipeline
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_ddsQuadPipeline);
@@ -4881,7 +6558,7 @@ extern "C" void heidic_render_dds_quad(GLFWwindow* window) {
     }
     #endif
 
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     vkEndCommandBuffer(g_commandBuffers[imageIndex]);
 
     // Submit
@@ -4913,6 +6590,7 @@ extern "C" void heidic_render_dds_quad(GLFWwindow* window) {
     presentInfo.pSwapchains = swapChains;
     presentInfo.pImageIndices = &imageIndex;
 
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
 }
 
@@ -5540,7 +7218,7 @@ extern "C" void heidic_render_png_quad(GLFWwindow* window) {
     renderPassInfo.clearValueCount = 2;
     renderPassInfo.pClearValues = clearValues;
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     // Bind pipeline
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_pngQuadPipeline);
@@ -5558,7 +7236,7 @@ extern "C" void heidic_render_png_quad(GLFWwindow* window) {
     // Draw quad (2 triangles = 6 indices)
     vkCmdDrawIndexed(g_commandBuffers[imageIndex], 6, 1, 0, 0, 0);
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     vkEndCommandBuffer(g_commandBuffers[imageIndex]);
     
     // Submit
@@ -5590,6 +7268,7 @@ extern "C" void heidic_render_png_quad(GLFWwindow* window) {
     presentInfo.pSwapchains = swapChains;
     presentInfo.pImageIndices = &imageIndex;
     
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
 }
 
@@ -6060,7 +7739,7 @@ extern "C" void heidic_render_texture_quad(GLFWwindow* window) {
     renderPassInfo.clearValueCount = 2;
     renderPassInfo.pClearValues = clearValues;
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_textureResourceQuadPipeline);
     
@@ -6074,7 +7753,7 @@ extern "C" void heidic_render_texture_quad(GLFWwindow* window) {
     
     vkCmdDrawIndexed(g_commandBuffers[imageIndex], 6, 1, 0, 0, 0);
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     vkEndCommandBuffer(g_commandBuffers[imageIndex]);
     
     VkSubmitInfo submitInfo = {};
@@ -6104,6 +7783,7 @@ extern "C" void heidic_render_texture_quad(GLFWwindow* window) {
     presentInfo.pSwapchains = swapChains;
     presentInfo.pImageIndices = &imageIndex;
     
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
 }
 
@@ -7164,7 +8844,7 @@ extern "C" void heidic_render_obj_mesh(GLFWwindow* window) {
     renderPassInfo.clearValueCount = static_cast<uint32_t>(clearValues.size());
     renderPassInfo.pClearValues = clearValues.data();
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     // Bind pipeline
     vkCmdBindPipeline(g_commandBuffers[imageIndex], VK_PIPELINE_BIND_POINT_GRAPHICS, g_objMeshPipeline);
@@ -7225,7 +8905,7 @@ extern "C" void heidic_render_obj_mesh(GLFWwindow* window) {
     }
     #endif
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     vkEndCommandBuffer(g_commandBuffers[imageIndex]);
     
     VkSubmitInfo submitInfo = {};
@@ -7255,6 +8935,7 @@ extern "C" void heidic_render_obj_mesh(GLFWwindow* window) {
     presentInfo.pSwapchains = swapChains;
     presentInfo.pImageIndices = &imageIndex;
     
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
 }
 
@@ -12445,7 +14126,7 @@ extern "C" void heidic_render_ese(GLFWwindow* window) {
     renderPassInfo.clearValueCount = static_cast<uint32_t>(clearValues.size());
     renderPassInfo.pClearValues = clearValues.data();
     
-    vkCmdBeginRenderPass(g_commandBuffers[imageIndex], &renderPassInfo, VK_SUBPASS_CONTENTS_INLINE);
+    beginScenePass(g_commandBuffers[imageIndex], &renderPassInfo);
     
     // Render 3D model if loaded
     // Only render if we have proper mesh shaders (not the fallback 3D shaders)
@@ -12461,7 +14142,7 @@ extern "C" void heidic_render_ese(GLFWwindow* window) {
     ImGui_ImplVulkan_RenderDrawData(ImGui::GetDrawData(), g_commandBuffers[imageIndex]);
 #endif
     
-    vkCmdEndRenderPass(g_commandBuffers[imageIndex]);
+    endScenePass(g_commandBuffers[imageIndex]);
     vkEndCommandBuffer(g_commandBuffers[imageIndex]);
     
     // Submit command buffer
@@ -12493,6 +14174,7 @@ extern "C" void heidic_render_ese(GLFWwindow* window) {
     presentInfo.pSwapchains = swapChains;
     presentInfo.pImageIndices = &imageIndex;
     
+    captureBeforePresent(imageIndex);
     vkQueuePresentKHR(g_graphicsQueue, &presentInfo);
     
 #ifdef USE_IMGUI
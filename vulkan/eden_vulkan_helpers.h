@@ -57,6 +57,10 @@ void* heidic_get_current_command_buffer();
 // Cleanup renderer resources
 void heidic_cleanup_renderer();
 
+// Rebuild the swapchain, depth buffer and framebuffers at the window's current size
+// (returns 1 on success). heidic_render_frame() does this itself when the window is resized.
+int heidic_recreate_swapchain(GLFWwindow* window);
+
 // Sleep for milliseconds (to prevent CPU spinning)
 void heidic_sleep_ms(uint32_t milliseconds);
 